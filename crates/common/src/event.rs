@@ -2,12 +2,13 @@ use std::str::FromStr;
 
 use fake::Dummy;
 use num_bigint::BigUint;
+use pathfinder_crypto::hash::PoseidonHasher;
 use pathfinder_crypto::Felt;
 use serde_with::serde_conv;
 use tagged::Tagged;
 use tagged_debug_derive::TaggedDebug;
 
-use crate::{ContractAddress, EventData, EventKey};
+use crate::{ContractAddress, EventData, EventKey, TransactionHash};
 
 #[serde_with::serde_as]
 #[derive(Clone, serde::Deserialize, serde::Serialize, PartialEq, Eq, Dummy, TaggedDebug)]
@@ -20,6 +21,27 @@ pub struct Event {
     pub keys: Vec<EventKey>,
 }
 
+impl Event {
+    /// The hash of the event as used for the block's event commitment since
+    /// Starknet 0.13.2.
+    ///
+    /// [Reference code from StarkWare](https://github.com/starkware-libs/starknet-api/blob/5565e5282f5fead364a41e49c173940fd83dee00/src/block_hash/event_commitment.rs#L33).
+    pub fn hash(&self, transaction_hash: TransactionHash) -> Felt {
+        let mut hasher = PoseidonHasher::new();
+        hasher.write(self.from_address.0.into());
+        hasher.write(transaction_hash.0.into());
+        hasher.write((self.keys.len() as u64).into());
+        for key in &self.keys {
+            hasher.write(key.0.into());
+        }
+        hasher.write((self.data.len() as u64).into());
+        for data in &self.data {
+            hasher.write(data.0.into());
+        }
+        hasher.finish().into()
+    }
+}
+
 serde_conv!(
     EventDataAsDecimalStr,
     EventData,
@@ -29,6 +29,7 @@ pub mod state_update;
 pub mod test_utils;
 pub mod transaction;
 pub mod trie;
+pub mod version_matrix;
 
 pub use header::{BlockHeader, BlockHeaderBuilder, L1DataAvailabilityMode, SignedBlockHeader};
 pub use l1::{L1BlockNumber, L1TransactionHash};
@@ -217,8 +218,7 @@ impl TransactionVersion {
     /// added to the real version to make sure transactions constructed for
     /// call or estimateFee cannot be submitted for inclusion on the chain.
     pub fn without_query_version(&self) -> u128 {
-        let lower = &self.0.as_be_bytes()[16..];
-        u128::from_be_bytes(lower.try_into().expect("slice should be the right length"))
+        self.0.low_u128()
     }
 
     pub const fn with_query_version(self) -> Self {
@@ -267,6 +267,9 @@ pub enum BlockId {
     Latest,
     #[serde(rename = "pending")]
     Pending,
+    /// The latest block whose state update was accepted on Ethereum.
+    #[serde(rename = "l1_accepted")]
+    L1Accepted,
 }
 
 impl BlockId {
@@ -384,14 +387,9 @@ impl TryFrom<Felt> for GasPrice {
     type Error = anyhow::Error;
 
     fn try_from(src: Felt) -> Result<Self, Self::Error> {
-        anyhow::ensure!(
-            src.as_be_bytes()[0..16] == [0; 16],
-            "Gas price fits into u128"
-        );
+        anyhow::ensure!(src.high_u128() == 0, "Gas price fits into u128");
 
-        let mut bytes = [0u8; 16];
-        bytes.copy_from_slice(&src.as_be_bytes()[16..]);
-        Ok(Self(u128::from_be_bytes(bytes)))
+        Ok(Self(src.low_u128()))
     }
 }
 
@@ -1,5 +1,7 @@
 use fake::{Dummy, Fake, Faker};
-use pathfinder_crypto::Felt;
+use pathfinder_crypto::hash::{poseidon_hash_many, PoseidonHasher};
+use pathfinder_crypto::{Felt, MontFelt};
+use sha3::Digest;
 
 use crate::prelude::*;
 
@@ -24,6 +26,50 @@ impl Receipt {
             ExecutionStatus::Reverted { reason } => Some(reason.as_str()),
         }
     }
+
+    /// The hash of the receipt as used for the block's receipt commitment.
+    pub fn hash(&self) -> Felt {
+        poseidon_hash_many(&[
+            self.transaction_hash.0.into(),
+            self.actual_fee.0.into(),
+            // Calculate hash of messages sent.
+            {
+                let mut hasher = PoseidonHasher::new();
+                hasher.write((self.l2_to_l1_messages.len() as u64).into());
+                for msg in &self.l2_to_l1_messages {
+                    hasher.write(msg.from_address.0.into());
+                    hasher.write(msg.to_address.0.into());
+                    hasher.write((msg.payload.len() as u64).into());
+                    for payload in &msg.payload {
+                        hasher.write(payload.0.into());
+                    }
+                }
+                hasher.finish()
+            },
+            // Revert reason.
+            match &self.execution_status {
+                ExecutionStatus::Succeeded => MontFelt::ZERO,
+                ExecutionStatus::Reverted { reason } => {
+                    let mut keccak = sha3::Keccak256::default();
+                    keccak.update(reason.as_bytes());
+                    let mut hashed_bytes: [u8; 32] = keccak.finalize().into();
+                    hashed_bytes[0] &= 0b00000011_u8; // Discard the six MSBs.
+                    MontFelt::from_be_bytes(hashed_bytes)
+                }
+            },
+            // Execution resources:
+            // L2 gas
+            MontFelt::ZERO,
+            // L1 gas consumed
+            self.execution_resources.total_gas_consumed.l1_gas.into(),
+            // L1 data gas consumed
+            self.execution_resources
+                .total_gas_consumed
+                .l1_data_gas
+                .into(),
+        ])
+        .into()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
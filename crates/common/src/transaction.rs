@@ -131,6 +131,28 @@ impl TransactionVariant {
         }
     }
 
+    /// The address of the account which sent the transaction, i.e. the
+    /// deployed account for deploy account transactions.
+    ///
+    /// [None] for deploy and L1 handler transactions, which are not sent by
+    /// an account.
+    pub fn sender_address(&self) -> Option<ContractAddress> {
+        match self {
+            TransactionVariant::DeclareV0(tx) => Some(tx.sender_address),
+            TransactionVariant::DeclareV1(tx) => Some(tx.sender_address),
+            TransactionVariant::DeclareV2(tx) => Some(tx.sender_address),
+            TransactionVariant::DeclareV3(tx) => Some(tx.sender_address),
+            TransactionVariant::DeployV0(_) => None,
+            TransactionVariant::DeployV1(_) => None,
+            TransactionVariant::DeployAccountV1(tx) => Some(tx.contract_address),
+            TransactionVariant::DeployAccountV3(tx) => Some(tx.contract_address),
+            TransactionVariant::InvokeV0(tx) => Some(tx.sender_address),
+            TransactionVariant::InvokeV1(tx) => Some(tx.sender_address),
+            TransactionVariant::InvokeV3(tx) => Some(tx.sender_address),
+            TransactionVariant::L1Handler(_) => None,
+        }
+    }
+
     /// Some variants had a different hash calculations for blocks around
     /// Starknet v0.8 and earlier. The hash excluded the transaction version
     /// and nonce.
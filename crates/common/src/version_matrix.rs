@@ -0,0 +1,209 @@
+//! What pathfinder supports for blocks of each Starknet version.
+//!
+//! Behaviour that depends on the Starknet version of a block (whether it can be
+//! re-executed locally, how its hashes are computed and how its fees are
+//! charged) is looked up in a single table instead of being gated on version
+//! constants throughout the code base. The built-in table is
+//! `resources/starknet_versions.json`. Custom networks whose versions behave
+//! differently can [install] their own table at startup.
+
+use std::str::FromStr;
+use std::sync::{LazyLock, OnceLock};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+use crate::StarknetVersion;
+
+const BUILTIN_JSON: &[u8] = include_bytes!("../resources/starknet_versions.json");
+
+static BUILTIN: LazyLock<VersionMatrix> = LazyLock::new(|| {
+    VersionMatrix::from_json(BUILTIN_JSON).expect("Built-in version matrix is valid")
+});
+
+static INSTALLED: OnceLock<VersionMatrix> = OnceLock::new();
+
+/// The capabilities of blocks of a Starknet version.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Blocks can be re-executed locally. Traces of other blocks are fetched
+    /// from the feeder gateway.
+    pub traceable_locally: bool,
+    pub hash_scheme: HashScheme,
+    pub gas_model: GasModel,
+}
+
+/// How block hashes and the commitments in block headers are computed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashScheme {
+    /// Pedersen, only the signatures of invoke transactions are part of the
+    /// transaction commitment.
+    PedersenInvokeSignatures,
+    /// Pedersen, the signatures of all transactions are part of the
+    /// transaction commitment.
+    Pedersen,
+    /// Poseidon, with receipt and state diff commitments. Empty transaction
+    /// signatures are hashed as a single zero.
+    PoseidonV0,
+    /// Poseidon, with gas prices hashed separately from the rest of the
+    /// header.
+    PoseidonV1,
+}
+
+impl HashScheme {
+    /// Whether block hashes and commitments are Poseidon based. Headers of
+    /// these blocks also commit to receipts, which makes receipt and event
+    /// proofs possible.
+    pub fn is_poseidon(self) -> bool {
+        self >= Self::PoseidonV0
+    }
+}
+
+/// The resources transactions pay for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GasModel {
+    /// All resources are charged as L1 gas.
+    L1Gas,
+    /// Data availability is charged separately as L1 data gas.
+    L1DataGas,
+    /// Computation is charged as L2 gas and transactions can pay a tip.
+    L2Gas,
+}
+
+/// The capabilities of each Starknet version.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionMatrix {
+    /// Ordered by version. Each entry covers the versions up to the next one.
+    entries: Vec<(StarknetVersion, Capabilities)>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    since: String,
+    traceable_locally: bool,
+    hash_scheme: HashScheme,
+    gas_model: GasModel,
+}
+
+impl VersionMatrix {
+    /// Parses a matrix from a JSON array of entries, each holding the first
+    /// version it applies to as `since` and the capabilities of the versions
+    /// up to the next entry.
+    pub fn from_json(json: &[u8]) -> anyhow::Result<Self> {
+        let entries: Vec<Entry> = serde_json::from_slice(json).context("Parsing version matrix")?;
+
+        let entries = entries
+            .into_iter()
+            .map(|entry| {
+                let since = StarknetVersion::from_str(&entry.since)
+                    .with_context(|| format!("Parsing version {:?}", entry.since))?;
+                let capabilities = Capabilities {
+                    traceable_locally: entry.traceable_locally,
+                    hash_scheme: entry.hash_scheme,
+                    gas_model: entry.gas_model,
+                };
+                Ok((since, capabilities))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        anyhow::ensure!(
+            entries.first().map(|(since, _)| *since) == Some(StarknetVersion::default()),
+            "The first entry of the version matrix must start at version 0.0.0"
+        );
+        if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 >= pair[1].0) {
+            anyhow::bail!(
+                "Version matrix entries must be ordered by version, {} is followed by {}",
+                pair[0].0,
+                pair[1].0
+            );
+        }
+
+        Ok(Self { entries })
+    }
+
+    pub fn capabilities(&self, version: StarknetVersion) -> Capabilities {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(since, _)| version >= *since)
+            .map(|(_, capabilities)| *capabilities)
+            .expect("The first entry covers all versions")
+    }
+}
+
+impl Default for VersionMatrix {
+    fn default() -> Self {
+        BUILTIN.clone()
+    }
+}
+
+/// Replaces the built-in matrix for the rest of the process. Fails if a matrix
+/// was installed already.
+pub fn install(matrix: VersionMatrix) -> anyhow::Result<()> {
+    INSTALLED
+        .set(matrix)
+        .map_err(|_| anyhow::anyhow!("A version matrix was installed already"))
+}
+
+/// The capabilities of `version` according to the installed matrix, or the
+/// built-in one if none was installed.
+pub fn capabilities(version: StarknetVersion) -> Capabilities {
+    INSTALLED
+        .get()
+        .unwrap_or_else(|| &*BUILTIN)
+        .capabilities(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_matrix() {
+        let matrix = VersionMatrix::default();
+
+        let at = |a, b, c, d| matrix.capabilities(StarknetVersion::new(a, b, c, d));
+
+        assert_eq!(
+            at(0, 0, 0, 0).hash_scheme,
+            HashScheme::PedersenInvokeSignatures
+        );
+        assert_eq!(at(0, 11, 1, 0).hash_scheme, HashScheme::Pedersen);
+        assert!(!at(0, 13, 1, 0).traceable_locally);
+        assert_eq!(at(0, 13, 1, 0).gas_model, GasModel::L1DataGas);
+        assert!(at(0, 13, 1, 1).traceable_locally);
+        assert_eq!(at(0, 13, 3, 0).hash_scheme, HashScheme::PoseidonV0);
+        assert_eq!(
+            at(0, 14, 0, 0),
+            Capabilities {
+                traceable_locally: true,
+                hash_scheme: HashScheme::PoseidonV1,
+                gas_model: GasModel::L2Gas,
+            }
+        );
+    }
+
+    #[test]
+    fn entries_must_cover_all_versions_in_order() {
+        let entry = |since: &str| {
+            serde_json::json!({
+                "since": since,
+                "traceable_locally": true,
+                "hash_scheme": "poseidon_v1",
+                "gas_model": "l2_gas",
+            })
+        };
+        let parse = |entries: Vec<serde_json::Value>| {
+            VersionMatrix::from_json(serde_json::to_string(&entries).unwrap().as_bytes())
+        };
+
+        parse(vec![entry("0.0.0"), entry("0.13.4")]).unwrap();
+        parse(vec![]).unwrap_err();
+        parse(vec![entry("0.13.4")]).unwrap_err();
+        parse(vec![entry("0.0.0"), entry("0.13.4"), entry("0.13.2")]).unwrap_err();
+        parse(vec![entry("0.0.0"), entry("0.13.4"), entry("0.13.4")]).unwrap_err();
+    }
+}
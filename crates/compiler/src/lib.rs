@@ -4,13 +4,22 @@ use anyhow::Context;
 use pathfinder_common::{felt, CasmHash};
 use pathfinder_crypto::Felt;
 
+pub mod limits;
+
 /// Compile a Sierra class definition into CASM.
 ///
 /// The class representation expected by the compiler doesn't match the
 /// representation used by the feeder gateway for Sierra classes, so we have to
 /// convert the JSON to something that can be parsed into the expected input
 /// format for the compiler.
+///
+/// Fails with [limits::ResourcesExceeded] if the compilation exceeds the
+/// configured [limits](limits::CompilationLimits).
 pub fn compile_to_casm(sierra_definition: &[u8]) -> anyhow::Result<Vec<u8>> {
+    limits::run(sierra_definition, compile)
+}
+
+fn compile(sierra_definition: &[u8]) -> anyhow::Result<Vec<u8>> {
     let definition = serde_json::from_slice::<FeederGatewayContractClass<'_>>(sierra_definition)
         .context("Parsing Sierra class")?;
 
@@ -75,6 +84,90 @@ pub fn casm_class_hash(casm_definition: &[u8]) -> anyhow::Result<CasmHash> {
     v2::casm_class_hash(casm_definition)
 }
 
+/// Maps offsets into the CASM bytecode of a compiled Sierra class back to the
+/// Sierra statements they were generated from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SierraStatementMap {
+    /// The bytecode offset at which the code of each Sierra statement starts,
+    /// indexed by statement.
+    statement_offsets: Vec<usize>,
+    /// Sierra functions sorted by the index of their first statement.
+    functions: Vec<SierraFunction>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct SierraFunction {
+    entry_point: usize,
+    id: u64,
+    name: Option<String>,
+}
+
+/// The Sierra statement a CASM bytecode offset belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SierraLocation<'a> {
+    pub statement_idx: usize,
+    pub function_id: u64,
+    /// Only available if the Sierra program contains debug information.
+    pub function_name: Option<&'a str>,
+}
+
+impl std::fmt::Display for SierraLocation<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sierra statement #{}", self.statement_idx)?;
+        match self.function_name {
+            Some(name) => write!(f, " in function `{name}`"),
+            None => write!(f, " in function #{}", self.function_id),
+        }
+    }
+}
+
+impl SierraStatementMap {
+    /// Returns the Sierra location of the CASM instruction at `offset`.
+    pub fn locate(&self, offset: usize) -> Option<SierraLocation<'_>> {
+        let statement_idx = self
+            .statement_offsets
+            .partition_point(|start| *start <= offset)
+            .checked_sub(1)?;
+        let function_idx = self
+            .functions
+            .partition_point(|function| function.entry_point <= statement_idx)
+            .checked_sub(1)?;
+        let function = &self.functions[function_idx];
+
+        Some(SierraLocation {
+            statement_idx,
+            function_id: function.id,
+            function_name: function.name.as_deref(),
+        })
+    }
+}
+
+/// Recompiles a Sierra class definition to build the mapping from its CASM
+/// bytecode back to Sierra statements.
+///
+/// Only supported for classes compiled with Sierra 1.2.0 and later. Subject
+/// to the same [limits](limits::CompilationLimits) as [compile_to_casm].
+pub fn sierra_statement_map(sierra_definition: &[u8]) -> anyhow::Result<SierraStatementMap> {
+    limits::run(sierra_definition, statement_map)
+}
+
+fn statement_map(sierra_definition: &[u8]) -> anyhow::Result<SierraStatementMap> {
+    let definition = serde_json::from_slice::<FeederGatewayContractClass<'_>>(sierra_definition)
+        .context("Parsing Sierra class")?;
+
+    let sierra_version =
+        parse_sierra_version(definition.sierra_program).context("Parsing Sierra version")?;
+
+    let result = std::panic::catch_unwind(|| match sierra_version {
+        SierraVersion(0, 1, 0) | SierraVersion(1, 0, 0) | SierraVersion(1, 1, 0) => Err(
+            anyhow::anyhow!("Sierra statement mapping is not supported for {sierra_version:?}"),
+        ),
+        _ => v2::sierra_statement_map(definition),
+    });
+
+    result.unwrap_or_else(|e| Err(panic_error(e)))
+}
+
 mod v1_0_0_alpha6 {
     use anyhow::Context;
     use casm_compiler_v1_0_0_alpha6::allowed_libfuncs::{
@@ -222,7 +315,7 @@ mod v2 {
     use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
     use cairo_lang_starknet_classes::contract_class::ContractClass;
 
-    use super::{CasmHash, FeederGatewayContractClass};
+    use super::{CasmHash, FeederGatewayContractClass, SierraFunction, SierraStatementMap};
 
     impl<'a> TryFrom<FeederGatewayContractClass<'a>> for ContractClass {
         type Error = serde_json::Error;
@@ -260,6 +353,42 @@ mod v2 {
         Ok(casm_definition)
     }
 
+    pub(super) fn sierra_statement_map(
+        definition: FeederGatewayContractClass<'_>,
+    ) -> anyhow::Result<SierraStatementMap> {
+        let sierra_class: ContractClass = definition
+            .try_into()
+            .context("Converting to Sierra class")?;
+
+        let program = sierra_class
+            .extract_sierra_program()
+            .context("Extracting Sierra program")?;
+        let mut functions = program
+            .funcs
+            .iter()
+            .map(|function| SierraFunction {
+                entry_point: function.entry_point.0,
+                id: function.id.id,
+                name: function.id.debug_name.as_ref().map(ToString::to_string),
+            })
+            .collect::<Vec<_>>();
+        functions.sort_by_key(|function| function.entry_point);
+
+        let (_, debug_info) =
+            CasmContractClass::from_contract_class_with_debug_info(sierra_class, false, usize::MAX)
+                .context("Compiling to CASM")?;
+        let statement_offsets = debug_info
+            .sierra_statement_info
+            .iter()
+            .map(|(code_offset, _instruction_idx)| *code_offset)
+            .collect();
+
+        Ok(SierraStatementMap {
+            statement_offsets,
+            functions,
+        })
+    }
+
     pub(super) fn casm_class_hash(casm_definition: &[u8]) -> anyhow::Result<CasmHash> {
         let ccc: CasmContractClass =
             serde_json::from_slice(casm_definition).context("Deserializing CASM class")?;
@@ -293,6 +422,47 @@ struct FeederGatewayContractClass<'a> {
 mod tests {
     use super::{compile_to_casm, FeederGatewayContractClass};
 
+    mod sierra_statement_map {
+        use super::super::{SierraFunction, SierraStatementMap};
+
+        fn map() -> SierraStatementMap {
+            SierraStatementMap {
+                statement_offsets: vec![0, 3, 3, 10, 12],
+                functions: vec![
+                    SierraFunction {
+                        entry_point: 0,
+                        id: 7,
+                        name: None,
+                    },
+                    SierraFunction {
+                        entry_point: 3,
+                        id: 8,
+                        name: Some("contract::transfer".to_owned()),
+                    },
+                ],
+            }
+        }
+
+        #[test]
+        fn locate() {
+            let map = map();
+
+            let location = map.locate(2).unwrap();
+            assert_eq!(location.statement_idx, 0);
+            assert_eq!(location.to_string(), "Sierra statement #0 in function #7");
+
+            // Statements without any code share their offset with the next one.
+            assert_eq!(map.locate(3).unwrap().statement_idx, 2);
+
+            let location = map.locate(100).unwrap();
+            assert_eq!(location.statement_idx, 4);
+            assert_eq!(
+                location.to_string(),
+                "Sierra statement #4 in function `contract::transfer`"
+            );
+        }
+    }
+
     mod parse_version {
         use rstest::rstest;
         use starknet_gateway_test_fixtures::class_definitions::{
@@ -382,6 +552,26 @@ mod tests {
             compile_to_casm(CAIRO_1_1_0_RC0_SIERRA).unwrap();
         }
 
+        #[test]
+        fn sierra_statement_map() {
+            let map = super::super::sierra_statement_map(CAIRO_2_0_0_STACK_OVERFLOW).unwrap();
+
+            let first = map.locate(0).unwrap();
+            assert_eq!(first.statement_idx, 0);
+
+            let casm: serde_json::Value =
+                serde_json::from_slice(&compile_to_casm(CAIRO_2_0_0_STACK_OVERFLOW).unwrap())
+                    .unwrap();
+            let bytecode_len = casm["bytecode"].as_array().unwrap().len();
+            let last = map.locate(bytecode_len - 1).unwrap();
+            assert!(last.statement_idx > first.statement_idx);
+        }
+
+        #[test]
+        fn sierra_statement_map_unsupported_version() {
+            super::super::sierra_statement_map(CAIRO_1_1_0_RC0_SIERRA).unwrap_err();
+        }
+
         #[test]
         fn regression_stack_overflow() {
             // This class caused a stack-overflow in v2 compilers <= v2.0.1
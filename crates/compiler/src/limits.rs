@@ -0,0 +1,140 @@
+//! Limits on the resources compiling a Sierra class may use.
+//!
+//! Classes are compiled on a dedicated pool of worker threads. A compilation
+//! which does not finish in time is abandoned: the caller gets an error while
+//! the worker finishes in the background, so the number of workers also bounds
+//! how many runaway compilations can run at once. The compiler's memory use
+//! grows with the size of the class, which is bounded by rejecting large
+//! definitions up front.
+
+use std::num::NonZeroUsize;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::Context;
+
+static LIMITS: OnceLock<CompilationLimits> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompilationLimits {
+    /// Sierra class definitions larger than this many bytes are not compiled.
+    pub max_definition_size: usize,
+    /// Compilations taking longer than this, including the time spent waiting
+    /// for a worker, are abandoned.
+    pub timeout: Duration,
+    /// The number of threads compiling classes.
+    pub workers: NonZeroUsize,
+}
+
+impl Default for CompilationLimits {
+    fn default() -> Self {
+        Self {
+            max_definition_size: 16 * 1024 * 1024,
+            timeout: Duration::from_secs(60),
+            workers: std::thread::available_parallelism()
+                .unwrap_or(NonZeroUsize::new(4).expect("Non-zero")),
+        }
+    }
+}
+
+/// A compilation was refused or abandoned for exceeding the
+/// [CompilationLimits].
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum ResourcesExceeded {
+    #[error("Class definition of {size} bytes exceeds the limit of {limit} bytes")]
+    DefinitionSize { size: usize, limit: usize },
+    #[error("Compilation did not finish within {0:?}")]
+    Timeout(Duration),
+}
+
+/// Configures the compilation limits. Must be called before compiling
+/// anything, and at most once.
+pub fn configure(limits: CompilationLimits) -> anyhow::Result<()> {
+    LIMITS
+        .set(limits)
+        .map_err(|_| anyhow::anyhow!("Compilation limits already configured"))
+}
+
+/// The configured compilation limits, or the defaults if none were
+/// configured.
+pub fn configured() -> CompilationLimits {
+    LIMITS.get().copied().unwrap_or_default()
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+static WORKERS: LazyLock<mpsc::Sender<Job>> = LazyLock::new(|| {
+    let (sender, receiver) = mpsc::channel::<Job>();
+    let receiver = std::sync::Arc::new(Mutex::new(receiver));
+
+    for i in 0..configured().workers.get() {
+        let receiver = receiver.clone();
+        std::thread::Builder::new()
+            .name(format!("compiler-{i}"))
+            .spawn(move || loop {
+                // The lock is released before the job is run.
+                let Ok(job) = receiver.lock().expect("Lock should not be poisoned").recv() else {
+                    return;
+                };
+                // Compilers catch their panics, this keeps the thread alive regardless.
+                let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(job));
+            })
+            .expect("Spawning compiler thread");
+    }
+
+    sender
+});
+
+/// Runs `compile` on the definition within the configured limits.
+pub(crate) fn run<T: Send + 'static>(
+    definition: &[u8],
+    compile: fn(&[u8]) -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let limits = configured();
+    if definition.len() > limits.max_definition_size {
+        return Err(ResourcesExceeded::DefinitionSize {
+            size: definition.len(),
+            limit: limits.max_definition_size,
+        }
+        .into());
+    }
+
+    let definition = definition.to_vec();
+    let (sender, receiver) = mpsc::sync_channel(1);
+    WORKERS
+        .send(Box::new(move || {
+            // The receiver is gone if the compilation was abandoned.
+            let _ = sender.send(compile(&definition));
+        }))
+        .ok()
+        .context("Compiler threads have exited")?;
+
+    match receiver.recv_timeout(limits.timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => {
+            tracing::warn!(timeout=?limits.timeout, "Abandoned Sierra class compilation");
+            Err(ResourcesExceeded::Timeout(limits.timeout).into())
+        }
+        Err(RecvTimeoutError::Disconnected) => Err(anyhow::anyhow!("Compiler thread exited")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn large_definitions_are_rejected() {
+        let definition = vec![b' '; configured().max_definition_size + 1];
+        let error = run(&definition, |_| Ok(())).unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<ResourcesExceeded>(),
+            Some(&ResourcesExceeded::DefinitionSize {
+                size: definition.len(),
+                limit: configured().max_definition_size,
+            })
+        );
+    }
+}
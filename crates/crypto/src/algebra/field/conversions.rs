@@ -0,0 +1,281 @@
+//! Conversions of [Felt] to and from Cairo short strings, decimal strings and
+//! the `low`/`high` words of Cairo's `u256`.
+
+use std::error::Error;
+
+use crate::algebra::field::felt::{Felt, OverflowError, OVERFLOW_MSG};
+
+/// The maximum length of a Cairo short string, the largest number of bytes
+/// which always fits into a [Felt].
+const SHORT_STRING_MAX_LEN: usize = 31;
+
+/// Largest power of ten fitting into a `u64`, used to convert to and from
+/// decimal in as few steps as possible.
+const DEC_CHUNK: u64 = 10_000_000_000_000_000_000;
+const DEC_CHUNK_DIGITS: usize = 19;
+
+impl Felt {
+    /// Encodes an ASCII string of at most 31 characters as a Cairo short
+    /// string, e.g. `SN_MAIN` for chain IDs.
+    pub fn from_short_string(s: &str) -> Result<Self, ShortStringError> {
+        if !s.is_ascii() {
+            return Err(ShortStringError::NonAscii);
+        }
+        if s.len() > SHORT_STRING_MAX_LEN {
+            return Err(ShortStringError::TooLong(s.len()));
+        }
+
+        Ok(Self::from_be_slice(s.as_bytes()).expect("31 bytes always fit"))
+    }
+
+    /// Decodes a Cairo short string. Returns [None] if the bytes are not
+    /// printable ASCII.
+    pub fn to_short_string(&self) -> Option<String> {
+        let bytes = self.as_be_bytes();
+        let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+        let bytes = &bytes[start..];
+
+        if !bytes.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+            return None;
+        }
+
+        Some(String::from_utf8(bytes.to_vec()).expect("ASCII is valid UTF-8"))
+    }
+
+    /// Parses an unsigned decimal number, e.g. `1000`.
+    pub fn from_dec_str(s: &str) -> Result<Self, DecParseError> {
+        if s.is_empty() {
+            return Err(DecParseError::Empty);
+        }
+
+        // Little-endian 64 bit limbs.
+        let mut limbs = [0u64; 4];
+        for chunk in s.as_bytes().chunks(DEC_CHUNK_DIGITS) {
+            let mut value = 0u64;
+            for &digit in chunk {
+                if !digit.is_ascii_digit() {
+                    return Err(DecParseError::InvalidDigit(digit));
+                }
+                value = value * 10 + u64::from(digit - b'0');
+            }
+
+            let mut carry = u128::from(value);
+            let multiplier = 10u128.pow(chunk.len() as u32);
+            for limb in &mut limbs {
+                let product = u128::from(*limb) * multiplier + carry;
+                *limb = product as u64;
+                carry = product >> 64;
+            }
+            if carry != 0 {
+                return Err(DecParseError::Overflow);
+            }
+        }
+
+        let mut bytes = [0u8; 32];
+        for (i, limb) in limbs.iter().rev().enumerate() {
+            bytes[i * 8..(i + 1) * 8].copy_from_slice(&limb.to_be_bytes());
+        }
+
+        Ok(Self::from_be_bytes(bytes)?)
+    }
+
+    /// The unsigned decimal representation, e.g. `1000`.
+    pub fn to_dec_string(&self) -> String {
+        // Big-endian 64 bit limbs.
+        let mut limbs = [0u64; 4];
+        for (limb, bytes) in limbs.iter_mut().zip(self.as_be_bytes().chunks(8)) {
+            *limb = u64::from_be_bytes(bytes.try_into().expect("8 byte chunks"));
+        }
+
+        // Divide by 10^19 until nothing is left, collecting the remainders.
+        let mut chunks = Vec::new();
+        while limbs.iter().any(|limb| *limb != 0) {
+            let mut remainder = 0u128;
+            for limb in &mut limbs {
+                let dividend = (remainder << 64) | u128::from(*limb);
+                *limb = (dividend / u128::from(DEC_CHUNK)) as u64;
+                remainder = dividend % u128::from(DEC_CHUNK);
+            }
+            chunks.push(remainder as u64);
+        }
+
+        let Some((most_significant, rest)) = chunks.split_last() else {
+            return "0".to_owned();
+        };
+
+        let mut s = most_significant.to_string();
+        for chunk in rest.iter().rev() {
+            s.push_str(&format!("{chunk:0width$}", width = DEC_CHUNK_DIGITS));
+        }
+        s
+    }
+
+    /// Creates a [Felt] from the `low` and `high` 128 bit words of a Cairo
+    /// `u256`. Fails if the value does not fit into the field.
+    pub fn from_u256_words(low: u128, high: u128) -> Result<Self, OverflowError> {
+        let mut bytes = [0u8; 32];
+        bytes[..16].copy_from_slice(&high.to_be_bytes());
+        bytes[16..].copy_from_slice(&low.to_be_bytes());
+        Self::from_be_bytes(bytes)
+    }
+
+    /// The `low` and `high` 128 bit words of the value as a Cairo `u256`.
+    pub fn to_u256_words(&self) -> (u128, u128) {
+        (self.low_u128(), self.high_u128())
+    }
+
+    /// The 128 least significant bits, discarding the rest.
+    ///
+    /// Used for values which are stored as a [Felt] but are known to fit into
+    /// a `u128`, such as fees.
+    pub fn low_u128(&self) -> u128 {
+        let bytes = &self.as_be_bytes()[16..];
+        u128::from_be_bytes(bytes.try_into().expect("16 bytes"))
+    }
+
+    /// The 128 most significant bits.
+    pub fn high_u128(&self) -> u128 {
+        let bytes = &self.as_be_bytes()[..16];
+        u128::from_be_bytes(bytes.try_into().expect("16 bytes"))
+    }
+}
+
+/// Error returned by [Felt::from_short_string].
+#[derive(Debug, PartialEq, Eq)]
+pub enum ShortStringError {
+    NonAscii,
+    TooLong(usize),
+}
+
+impl Error for ShortStringError {}
+
+impl std::fmt::Display for ShortStringError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NonAscii => f.write_str("Short strings may only contain ASCII characters"),
+            Self::TooLong(len) => f.write_fmt(format_args!(
+                "Short strings are at most {SHORT_STRING_MAX_LEN} characters long, found {len}"
+            )),
+        }
+    }
+}
+
+/// Error returned by [Felt::from_dec_str].
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecParseError {
+    Empty,
+    InvalidDigit(u8),
+    Overflow,
+}
+
+impl Error for DecParseError {}
+
+impl From<OverflowError> for DecParseError {
+    fn from(_: OverflowError) -> Self {
+        Self::Overflow
+    }
+}
+
+impl std::fmt::Display for DecParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => f.write_str("Empty decimal string"),
+            Self::InvalidDigit(d) => {
+                f.write_fmt(format_args!("Invalid decimal digit found: 0x{:x}", *d))
+            }
+            Self::Overflow => f.write_str(OVERFLOW_MSG),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod short_string {
+        use super::*;
+
+        #[test]
+        fn round_trip() {
+            let felt = Felt::from_short_string("SN_MAIN").unwrap();
+            assert_eq!(felt, Felt::from_be_slice(b"SN_MAIN").unwrap());
+            assert_eq!(felt.to_short_string().as_deref(), Some("SN_MAIN"));
+
+            assert_eq!(Felt::ZERO.to_short_string().as_deref(), Some(""));
+        }
+
+        #[test]
+        fn invalid() {
+            assert_eq!(
+                Felt::from_short_string(&"a".repeat(32)),
+                Err(ShortStringError::TooLong(32))
+            );
+            assert_eq!(
+                Felt::from_short_string("ü"),
+                Err(ShortStringError::NonAscii)
+            );
+            assert_eq!(Felt::from_u64(0x0102).to_short_string(), None);
+        }
+    }
+
+    mod decimal {
+        use super::*;
+
+        /// p - 1, the largest field element.
+        const MAX: &str =
+            "3618502788666131213697322783095070105623107215331596699973092056135872020480";
+
+        #[test]
+        fn round_trip() {
+            for value in [0u128, 1, 10, 10_000_000_000_000_000_000, u128::MAX] {
+                let felt = Felt::from_dec_str(&value.to_string()).unwrap();
+                assert_eq!(felt, Felt::from_u128(value));
+                assert_eq!(felt.to_dec_string(), value.to_string());
+            }
+
+            let max = Felt::from_dec_str(MAX).unwrap();
+            assert_eq!(max, Felt::ZERO - Felt::ONE);
+            assert_eq!(max.to_dec_string(), MAX);
+        }
+
+        #[test]
+        fn leading_zeros() {
+            assert_eq!(Felt::from_dec_str("0007"), Ok(Felt::from_u64(7)));
+        }
+
+        #[test]
+        fn invalid() {
+            assert_eq!(Felt::from_dec_str(""), Err(DecParseError::Empty));
+            assert_eq!(
+                Felt::from_dec_str("12a"),
+                Err(DecParseError::InvalidDigit(b'a'))
+            );
+            assert_eq!(
+                Felt::from_dec_str("0x12"),
+                Err(DecParseError::InvalidDigit(b'x'))
+            );
+            // p
+            assert_eq!(
+                Felt::from_dec_str(
+                    "3618502788666131213697322783095070105623107215331596699973092056135872020481"
+                ),
+                Err(DecParseError::Overflow)
+            );
+            // Overflows the 256 bit intermediate value.
+            assert_eq!(
+                Felt::from_dec_str(&"9".repeat(80)),
+                Err(DecParseError::Overflow)
+            );
+        }
+    }
+
+    #[test]
+    fn u256_words() {
+        let felt = Felt::from_u256_words(1, 2).unwrap();
+        assert_eq!(felt.to_u256_words(), (1, 2));
+        assert_eq!(felt.low_u128(), 1);
+        assert_eq!(felt.high_u128(), 2);
+
+        assert_eq!(Felt::from_u256_words(0, u128::MAX), Err(OverflowError));
+    }
+}
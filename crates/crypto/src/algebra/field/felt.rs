@@ -65,7 +65,7 @@ pub struct OverflowError;
 
 impl Error for OverflowError {}
 
-const OVERFLOW_MSG: &str = "The maximum field value was exceeded.";
+pub(super) const OVERFLOW_MSG: &str = "The maximum field value was exceeded.";
 
 impl std::fmt::Display for OverflowError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
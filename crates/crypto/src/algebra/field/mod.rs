@@ -1,4 +1,5 @@
 mod bits;
+mod conversions;
 pub mod core;
 mod curveorder;
 mod felt;
@@ -6,6 +7,7 @@ mod montfelt;
 mod serde;
 
 pub use bits::{BitIteratorBE, BitIteratorLE};
+pub use conversions::{DecParseError, ShortStringError};
 pub use curveorder::CurveOrderMontFelt;
 pub use felt::{Felt, HexParseError, OverflowError};
 pub use montfelt::MontFelt;
@@ -13,9 +13,11 @@ pub mod signature;
 pub use algebra::{
     AffinePoint,
     CurveOrderMontFelt,
+    DecParseError,
     Felt,
     HexParseError,
     MontFelt,
     OverflowError,
     ProjectivePoint,
+    ShortStringError,
 };
@@ -124,6 +124,32 @@ pub fn ecdsa_verify_partial(pk: Felt, z: Felt, r: Felt, s: Felt) -> Result<(), S
     ecdsa_verify_inner(pk_proj, z, r, s)
 }
 
+/// Verify a batch of ECDSA signatures `(z,r,s)` made with the same partial
+/// public key.
+///
+/// The public key is only decompressed once and the signatures are verified in
+/// parallel on the global rayon thread pool. Returns the result for each
+/// signature in the order given.
+pub fn ecdsa_verify_partial_batch(
+    pk: Felt,
+    signatures: &[(Felt, Felt, Felt)],
+) -> Vec<Result<(), SignatureError>> {
+    use rayon::prelude::*;
+
+    let Some(pk_point) = get_pk_point(MontFelt::from(pk)) else {
+        return signatures
+            .iter()
+            .map(|_| Err(SignatureError::PublicKey))
+            .collect();
+    };
+    let pk_proj = ProjectivePoint::from(&pk_point);
+
+    signatures
+        .par_iter()
+        .map(|&(z, r, s)| ecdsa_verify_inner(pk_proj.clone(), z, r, s))
+        .collect()
+}
+
 /// Verify an ECDSA signature `(r,s)` on message `z` given a full public key
 /// `pk=(x,y)`.
 pub fn ecdsa_verify(pk: AffinePoint, z: Felt, r: Felt, s: Felt) -> Result<(), SignatureError> {
@@ -254,6 +280,28 @@ mod tests {
         assert!(ecdsa_verify_partial(pk, msg, sig.0, sig.1).is_ok());
     }
 
+    #[test]
+    fn verify_partial_batch() {
+        let sk = felt_hex("03c1e9550e66958296d11b60f8e8e7a7ad990d07fa65d5f7652c4a6c87d4e3cc");
+        let pk = get_pk(sk).expect("can get pk");
+
+        let mut signatures = (1..=20u64)
+            .map(|i| {
+                let msg = Felt::from_u64(i);
+                let (r, s) = ecdsa_sign(sk, msg).expect("can sign");
+                (msg, r, s)
+            })
+            .collect::<Vec<_>>();
+        // Sign a different message.
+        signatures[7].0 = Felt::from_u64(100);
+
+        let results = ecdsa_verify_partial_batch(pk, &signatures);
+        assert_eq!(results.len(), signatures.len());
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(result.is_ok(), i != 7, "signature {i}");
+        }
+    }
+
     #[test]
     fn verify_inner() {
         // Test vector from https://github.com/starkware-libs/crypto-cpp/blob/master/src/starkware/crypto/ecdsa_test.cc
@@ -5,6 +5,7 @@ pub use ecdsa::{
     ecdsa_sign_k,
     ecdsa_verify,
     ecdsa_verify_partial,
+    ecdsa_verify_partial_batch,
     get_pk,
     SignatureError,
 };
@@ -1,5 +1,6 @@
 use std::collections::BTreeMap;
 use std::future::Future;
+use std::sync::Arc;
 use std::time::Duration;
 
 use alloy::eips::{BlockId, BlockNumberOrTag};
@@ -29,9 +30,12 @@ use tokio::select;
 
 use crate::utils::*;
 
+mod light_client;
 mod starknet;
 mod utils;
 
+pub use light_client::{BeaconNetwork, LightClient, LightClientConfig};
+
 /// Starknet core contract addresses
 pub mod core_addr {
     use const_decoder::Decoder;
@@ -83,6 +87,9 @@ pub trait EthereumApi {
 pub struct EthereumClient {
     url: Url,
     pending_state_updates: BTreeMap<L1BlockNumber, EthereumStateUpdate>,
+    /// When set, Starknet state read from L1 is verified against the
+    /// light client instead of trusting the JSON-RPC endpoint.
+    light_client: Option<Arc<LightClient>>,
 }
 
 impl EthereumClient {
@@ -91,9 +98,19 @@ impl EthereumClient {
         Ok(Self {
             url: url.into_url()?,
             pending_state_updates: BTreeMap::new(),
+            light_client: None,
         })
     }
 
+    /// Verifies all Starknet state read from L1 using an embedded light
+    /// client.
+    pub fn with_light_client(self, config: LightClientConfig) -> Self {
+        Self {
+            light_client: Some(Arc::new(LightClient::new(config, self.url.clone()))),
+            ..self
+        }
+    }
+
     /// Creates a new password-protected [EthereumClient]
     pub fn with_password<U: IntoUrl>(url: U, password: &str) -> anyhow::Result<Self> {
         let mut url = url.into_url()?;
@@ -114,6 +131,36 @@ impl EthereumClient {
             .map(|block| L1BlockNumber::new_or_panic(block.header.number))
             .context("Failed to fetch finalized block hash")
     }
+
+    /// Light client equivalent of [EthereumApi::sync_and_listen]. Log
+    /// subscriptions cannot be verified, so instead the verified Starknet
+    /// state is polled and emitted whenever it changes.
+    async fn poll_verified_state<F, Fut>(
+        &self,
+        address: &H160,
+        poll_interval: Duration,
+        callback: F,
+    ) -> anyhow::Result<()>
+    where
+        F: Fn(EthereumStateUpdate) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut interval = tokio::time::interval(poll_interval);
+        let mut last = None;
+        loop {
+            interval.tick().await;
+            match self.get_starknet_state(address).await {
+                Ok(state_update) if last != Some(state_update) => {
+                    last = Some(state_update);
+                    callback(state_update).await;
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    tracing::warn!(%error, "Failed to fetch verified L1 state");
+                }
+            }
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -131,6 +178,12 @@ impl EthereumApi for EthereumClient {
         F: Fn(EthereumStateUpdate) -> Fut + Send + 'static,
         Fut: Future<Output = ()> + Send + 'static,
     {
+        if self.light_client.is_some() {
+            return self
+                .poll_verified_state(address, poll_interval, callback)
+                .await;
+        }
+
         // Create a WebSocket connection
         let ws = WsConnect::new(self.url.clone());
         let provider = ProviderBuilder::new().on_ws(ws).await?;
@@ -285,6 +338,13 @@ impl EthereumApi for EthereumClient {
 
     /// Get the Starknet state
     async fn get_starknet_state(&self, address: &H160) -> anyhow::Result<EthereumStateUpdate> {
+        if let Some(light_client) = &self.light_client {
+            return light_client
+                .verified_starknet_state(address)
+                .await
+                .context("Fetching light client verified Starknet state");
+        }
+
         // Create a WebSocket connection
         let ws = WsConnect::new(self.url.clone());
         let provider = ProviderBuilder::new().on_ws(ws).await?;
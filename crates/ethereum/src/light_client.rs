@@ -0,0 +1,1263 @@
+//! An embedded Ethereum consensus light client.
+//!
+//! Instead of trusting the Ethereum JSON-RPC endpoint to report the Starknet
+//! core contract's state, the light client follows the beacon chain's sync
+//! committees starting from a trusted checkpoint. The execution state root of
+//! the latest finalized block is then used to verify `eth_getProof` responses
+//! for the core contract's state storage slots.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use alloy::eips::{BlockId, BlockNumberOrTag};
+use alloy::primitives::{Address, Bytes, B256};
+use alloy::providers::{Provider, ProviderBuilder, RootProvider, WsConnect};
+use alloy::pubsub::PubSubFrontend;
+use anyhow::Context;
+use pathfinder_common::{BlockHash, BlockNumber, EthereumChain, StateCommitment};
+use pathfinder_crypto::Felt;
+use primitive_types::{H160, H256};
+use reqwest::Url;
+
+use self::ssz::{
+    hash_pair,
+    is_valid_merkle_branch,
+    merkleize,
+    mix_in_length,
+    pack_bytes,
+    u64_chunk,
+    Root,
+};
+use crate::EthereumStateUpdate;
+
+mod mpt;
+mod ssz;
+
+const SLOTS_PER_EPOCH: u64 = 32;
+const EPOCHS_PER_SYNC_COMMITTEE_PERIOD: u64 = 256;
+const SYNC_COMMITTEE_SIZE: usize = 512;
+/// Beacon nodes serve at most this many updates per request.
+const MAX_REQUEST_LIGHT_CLIENT_UPDATES: u64 = 128;
+
+const DOMAIN_SYNC_COMMITTEE: [u8; 4] = [7, 0, 0, 0];
+const BLS_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// Subtree indices of the light client proofs. Their depth differs between
+/// forks (Electra added enough `BeaconState` fields to grow the tree), so the
+/// depth is taken from the branch length and checked against the known ones.
+const FINALIZED_ROOT_INDEX: u64 = 41;
+const FINALIZED_ROOT_DEPTHS: [usize; 2] = [6, 7];
+const CURRENT_SYNC_COMMITTEE_INDEX: u64 = 22;
+const NEXT_SYNC_COMMITTEE_INDEX: u64 = 23;
+const SYNC_COMMITTEE_DEPTHS: [usize; 2] = [5, 6];
+const EXECUTION_PAYLOAD_INDEX: u64 = 9;
+const EXECUTION_PAYLOAD_DEPTH: usize = 4;
+
+/// Location of the `StarknetState.State` struct in the core contract's storage:
+/// `keccak256("STARKNET_1.0_INIT_STARKNET_STATE_STRUCT")`. The struct's
+/// `globalRoot`, `blockNumber` and `blockHash` fields occupy this slot and the
+/// two following it.
+fn starknet_state_slot() -> [u8; 32] {
+    keccak_hash::keccak(b"STARKNET_1.0_INIT_STARKNET_STATE_STRUCT").0
+}
+
+/// Beacon chain parameters required to verify sync committee signatures.
+#[derive(Debug, Clone)]
+pub struct BeaconNetwork {
+    genesis_validators_root: Root,
+    /// Fork versions and their activation epochs, in activation order.
+    forks: Vec<(u64, [u8; 4])>,
+}
+
+impl BeaconNetwork {
+    pub fn for_chain(chain: EthereumChain) -> Option<Self> {
+        let network = match chain {
+            EthereumChain::Mainnet => Self {
+                genesis_validators_root: const_decoder::Decoder::Hex
+                    .decode(b"4b363db94e286120d76eb905340fdd4e54bfe9f06bf33ff6cf5ad27f511bfe95"),
+                forks: vec![
+                    (0, [0x00, 0, 0, 0]),
+                    (74240, [0x01, 0, 0, 0]),
+                    (144896, [0x02, 0, 0, 0]),
+                    (194048, [0x03, 0, 0, 0]),
+                    (269568, [0x04, 0, 0, 0]),
+                    (364032, [0x05, 0, 0, 0]),
+                    (411392, [0x06, 0, 0, 0]),
+                ],
+            },
+            EthereumChain::Sepolia => Self {
+                genesis_validators_root: const_decoder::Decoder::Hex
+                    .decode(b"d8ea171f3c94aea21ebc42a1ed61052acf3f9209c00e4efbaaddac09ed9b8078"),
+                forks: vec![
+                    (0, [0x90, 0x00, 0x00, 0x69]),
+                    (50, [0x90, 0x00, 0x00, 0x70]),
+                    (100, [0x90, 0x00, 0x00, 0x71]),
+                    (56832, [0x90, 0x00, 0x00, 0x72]),
+                    (132608, [0x90, 0x00, 0x00, 0x73]),
+                    (222464, [0x90, 0x00, 0x00, 0x74]),
+                    (272640, [0x90, 0x00, 0x00, 0x75]),
+                ],
+            },
+            EthereumChain::Other(_) => return None,
+        };
+
+        Some(network)
+    }
+
+    fn fork_version(&self, slot: u64) -> [u8; 4] {
+        let epoch = slot / SLOTS_PER_EPOCH;
+        self.forks
+            .iter()
+            .rev()
+            .find(|(activation, _)| *activation <= epoch)
+            .map(|(_, version)| *version)
+            .unwrap_or(self.forks[0].1)
+    }
+
+    fn sync_committee_domain(&self, signature_slot: u64) -> Root {
+        let version = self.fork_version(signature_slot.max(1) - 1);
+
+        let mut version_chunk = [0u8; 32];
+        version_chunk[..4].copy_from_slice(&version);
+        let fork_data_root = hash_pair(&version_chunk, &self.genesis_validators_root);
+
+        let mut domain = [0u8; 32];
+        domain[..4].copy_from_slice(&DOMAIN_SYNC_COMMITTEE);
+        domain[4..].copy_from_slice(&fork_data_root[..28]);
+        domain
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LightClientConfig {
+    /// URL of the beacon node REST API.
+    pub beacon_url: Url,
+    /// A trusted, finalized beacon block root to bootstrap from.
+    pub checkpoint: H256,
+    pub network: BeaconNetwork,
+}
+
+#[derive(Debug, Clone)]
+struct SyncCommittee {
+    pubkeys: Vec<[u8; 48]>,
+    aggregate_pubkey: [u8; 48],
+}
+
+impl SyncCommittee {
+    fn hash_tree_root(&self) -> Root {
+        let pubkey_root = |key: &[u8; 48]| merkleize(&pack_bytes(key), 2);
+        let pubkeys: Vec<Root> = self.pubkeys.iter().map(pubkey_root).collect();
+        hash_pair(
+            &merkleize(&pubkeys, SYNC_COMMITTEE_SIZE),
+            &pubkey_root(&self.aggregate_pubkey),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BeaconBlockHeader {
+    slot: u64,
+    proposer_index: u64,
+    parent_root: Root,
+    state_root: Root,
+    body_root: Root,
+}
+
+impl BeaconBlockHeader {
+    fn hash_tree_root(&self) -> Root {
+        merkleize(
+            &[
+                u64_chunk(self.slot),
+                u64_chunk(self.proposer_index),
+                self.parent_root,
+                self.state_root,
+                self.body_root,
+            ],
+            8,
+        )
+    }
+}
+
+/// The execution payload header fields the light client relies on, together
+/// with the SSZ root of the whole header.
+#[derive(Debug, Clone, Copy)]
+struct ExecutionHeader {
+    block_number: u64,
+    state_root: Root,
+    root: Root,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LightClientHeader {
+    beacon: BeaconBlockHeader,
+    execution: ExecutionHeader,
+}
+
+/// The verified state of the light client.
+#[derive(Debug, Clone)]
+struct Store {
+    finalized_header: LightClientHeader,
+    current_sync_committee: SyncCommittee,
+    next_sync_committee: Option<SyncCommittee>,
+}
+
+fn sync_committee_period(slot: u64) -> u64 {
+    slot / SLOTS_PER_EPOCH / EPOCHS_PER_SYNC_COMMITTEE_PERIOD
+}
+
+pub struct LightClient {
+    config: LightClientConfig,
+    http: reqwest::Client,
+    /// Ethereum JSON-RPC endpoint serving `eth_getProof`.
+    url: Url,
+    /// Connected on first use and dropped on failure, so that the next call
+    /// reconnects.
+    provider: Mutex<Option<RootProvider<PubSubFrontend>>>,
+    /// Only locked to take a snapshot or to store an advanced one, never
+    /// across network requests.
+    store: Mutex<Option<Store>>,
+}
+
+impl std::fmt::Debug for LightClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LightClient")
+            .field("config", &self.config)
+            .field("url", &self.url)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LightClient {
+    pub fn new(config: LightClientConfig, url: Url) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .expect("Default reqwest client should build"),
+            url,
+            provider: Mutex::new(None),
+            store: Mutex::new(None),
+        }
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> anyhow::Result<T> {
+        let url = self
+            .config
+            .beacon_url
+            .join(path)
+            .context("Building beacon API URL")?;
+        self.http
+            .get(url)
+            .send()
+            .await
+            .context("Sending beacon API request")?
+            .error_for_status()
+            .context("Beacon API request failed")?
+            .json()
+            .await
+            .context("Parsing beacon API response")
+    }
+
+    async fn bootstrap(&self) -> anyhow::Result<Store> {
+        let checkpoint = hex::encode(self.config.checkpoint.as_bytes());
+        let response: dto::Versioned<dto::Bootstrap> = self
+            .get(&format!(
+                "eth/v1/beacon/light_client/bootstrap/0x{checkpoint}"
+            ))
+            .await
+            .context("Fetching light client bootstrap")?;
+
+        let store = verify_bootstrap(&response.data, &self.config.checkpoint.0)?;
+        tracing::info!(slot=%store.finalized_header.beacon.slot, "Light client bootstrapped");
+
+        Ok(store)
+    }
+
+    /// Advances the light client to the latest finalized header and returns
+    /// its execution block number and state root.
+    async fn finalized_execution(&self) -> anyhow::Result<(u64, Root)> {
+        // Advance a copy so that concurrent callers are not blocked on each
+        // other's beacon API requests.
+        let snapshot = self.store.lock().unwrap().clone();
+        let mut store = match snapshot {
+            Some(store) => store,
+            None => self.bootstrap().await?,
+        };
+
+        let result = self.advance(&mut store).await;
+        let execution = store.finalized_header.execution;
+        self.save(store);
+        result?;
+
+        Ok((execution.block_number, execution.state_root))
+    }
+
+    /// Replaces the stored state unless a concurrent call has already
+    /// finalized a later header.
+    fn save(&self, store: Store) {
+        let mut current = self.store.lock().unwrap();
+        let is_stale = current.as_ref().is_some_and(|current| {
+            current.finalized_header.beacon.slot > store.finalized_header.beacon.slot
+        });
+        if !is_stale {
+            *current = Some(store);
+        }
+    }
+
+    /// Returns the shared WebSocket provider, connecting if there is none.
+    async fn provider(&self) -> anyhow::Result<RootProvider<PubSubFrontend>> {
+        let cached = self.provider.lock().unwrap().clone();
+        if let Some(provider) = cached {
+            return Ok(provider);
+        }
+
+        let provider = ProviderBuilder::new()
+            .on_ws(WsConnect::new(self.url.clone()))
+            .await
+            .context("Connecting to Ethereum")?;
+        *self.provider.lock().unwrap() = Some(provider.clone());
+
+        Ok(provider)
+    }
+
+    async fn advance(&self, store: &mut Store) -> anyhow::Result<()> {
+        let finality: dto::Versioned<dto::Update> = self
+            .get("eth/v1/beacon/light_client/finality_update")
+            .await
+            .context("Fetching light client finality update")?;
+        let head_period = sync_committee_period(finality.data.signature_slot);
+
+        // Catch up on sync committee rotations before applying the finality
+        // update, since it may be signed by a committee we don't know yet.
+        loop {
+            let store_period = sync_committee_period(store.finalized_header.beacon.slot);
+            if store_period >= head_period && store.next_sync_committee.is_some() {
+                break;
+            }
+
+            let count = (head_period - store_period + 1).min(MAX_REQUEST_LIGHT_CLIENT_UPDATES);
+            let updates: Vec<dto::Versioned<dto::Update>> = self
+                .get(&format!(
+                    "eth/v1/beacon/light_client/updates?start_period={store_period}&count={count}"
+                ))
+                .await
+                .context("Fetching light client updates")?;
+
+            let before = store.finalized_header.beacon.slot;
+            for update in updates {
+                self.apply_update(store, update.data)?;
+            }
+
+            if store.finalized_header.beacon.slot == before {
+                break;
+            }
+        }
+
+        self.apply_update(store, finality.data)
+    }
+
+    /// Verifies and applies a light client update. This is a simplified
+    /// version of `process_light_client_update` from the consensus spec which
+    /// only ever moves to finalized headers.
+    fn apply_update(&self, store: &mut Store, update: dto::Update) -> anyhow::Result<()> {
+        let attested = update.attested_header.verify()?;
+        let finalized = update
+            .finalized_header
+            .context("Update has no finalized header")?
+            .verify()?;
+
+        if finalized.beacon.slot <= store.finalized_header.beacon.slot
+            && update.next_sync_committee.is_none()
+        {
+            return Ok(());
+        }
+
+        let store_period = sync_committee_period(store.finalized_header.beacon.slot);
+        let signature_period = sync_committee_period(update.signature_slot);
+        let committee = if signature_period == store_period {
+            &store.current_sync_committee
+        } else if signature_period == store_period + 1 {
+            store
+                .next_sync_committee
+                .as_ref()
+                .context("Update is signed by an unknown sync committee")?
+        } else {
+            anyhow::bail!(
+                "Update signature period {signature_period} is not adjacent to {store_period}"
+            );
+        };
+
+        let bits = hex_bytes(&update.sync_aggregate.sync_committee_bits)?;
+        let signature = hex_bytes(&update.sync_aggregate.sync_committee_signature)?;
+        let signing_root = hash_pair(
+            &attested.beacon.hash_tree_root(),
+            &self
+                .config
+                .network
+                .sync_committee_domain(update.signature_slot),
+        );
+        verify_sync_aggregate(committee, &bits, &signature, &signing_root)?;
+
+        let finality_branch = parse_branch(&update.finality_branch)?;
+        anyhow::ensure!(
+            FINALIZED_ROOT_DEPTHS.contains(&finality_branch.len())
+                && is_valid_merkle_branch(
+                    &finalized.beacon.hash_tree_root(),
+                    &finality_branch,
+                    finality_branch.len(),
+                    FINALIZED_ROOT_INDEX,
+                    &attested.beacon.state_root,
+                ),
+            "Invalid finality branch"
+        );
+
+        let next_committee = match (
+            update.next_sync_committee,
+            update.next_sync_committee_branch,
+        ) {
+            (Some(committee), Some(branch)) => {
+                let committee = committee.parse()?;
+                verify_sync_committee_branch(
+                    &committee,
+                    &branch,
+                    NEXT_SYNC_COMMITTEE_INDEX,
+                    &attested.beacon.state_root,
+                )
+                .context("Verifying next sync committee")?;
+                Some(committee)
+            }
+            _ => None,
+        };
+
+        // Rotate committees once finality crosses into the next period.
+        let finalized_period = sync_committee_period(finalized.beacon.slot);
+        if finalized_period == store_period + 1 {
+            let next = store
+                .next_sync_committee
+                .take()
+                .context("Cannot rotate into a period without its sync committee")?;
+            store.current_sync_committee = next;
+            store.next_sync_committee = next_committee;
+        } else if finalized_period == store_period
+            && store.next_sync_committee.is_none()
+            && sync_committee_period(attested.beacon.slot) == store_period
+        {
+            store.next_sync_committee = next_committee;
+        }
+
+        if finalized.beacon.slot > store.finalized_header.beacon.slot {
+            tracing::debug!(slot=%finalized.beacon.slot, execution_block=%finalized.execution.block_number, "Light client finalized header updated");
+            store.finalized_header = finalized;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the Starknet state stored in the core contract at the latest
+    /// finalized Ethereum block, verified against the light client's execution
+    /// state root.
+    pub async fn verified_starknet_state(
+        &self,
+        address: &H160,
+    ) -> anyhow::Result<EthereumStateUpdate> {
+        let (block_number, state_root) = self.finalized_execution().await?;
+
+        let provider = self.provider().await?;
+        let proof = provider
+            .get_proof(
+                Address::new((*address).into()),
+                starknet_state_slots().to_vec(),
+            )
+            .block_id(BlockId::Number(BlockNumberOrTag::Number(block_number)))
+            .await;
+        let proof = match proof {
+            Ok(proof) => proof,
+            Err(error) => {
+                *self.provider.lock().unwrap() = None;
+                return Err(error).context("Fetching core contract storage proof");
+            }
+        };
+
+        let storage_proofs: Vec<Vec<Bytes>> = proof
+            .storage_proof
+            .into_iter()
+            .map(|storage_proof| storage_proof.proof)
+            .collect();
+        verify_starknet_state(&state_root, address, &proof.account_proof, &storage_proofs)
+    }
+}
+
+/// The storage keys of the `StarknetState.State` fields.
+fn starknet_state_slots() -> [B256; 3] {
+    let base = alloy::primitives::U256::from_be_bytes(starknet_state_slot());
+    [0u8, 1, 2].map(|i| B256::from(base + alloy::primitives::U256::from(i)))
+}
+
+/// Verifies a light client bootstrap against the trusted checkpoint.
+fn verify_bootstrap(bootstrap: &dto::Bootstrap, checkpoint: &Root) -> anyhow::Result<Store> {
+    let header = bootstrap.header.verify()?;
+    anyhow::ensure!(
+        &header.beacon.hash_tree_root() == checkpoint,
+        "Bootstrap header does not match the trusted checkpoint"
+    );
+
+    let committee = bootstrap.current_sync_committee.parse()?;
+    verify_sync_committee_branch(
+        &committee,
+        &bootstrap.current_sync_committee_branch,
+        CURRENT_SYNC_COMMITTEE_INDEX,
+        &header.beacon.state_root,
+    )
+    .context("Verifying current sync committee")?;
+
+    Ok(Store {
+        finalized_header: header,
+        current_sync_committee: committee,
+        next_sync_committee: None,
+    })
+}
+
+/// Verifies an `eth_getProof` response for [starknet_state_slots] of the core
+/// contract at `address` against an execution state root.
+fn verify_starknet_state(
+    state_root: &Root,
+    address: &H160,
+    account_proof: &[Bytes],
+    storage_proofs: &[Vec<Bytes>],
+) -> anyhow::Result<EthereumStateUpdate> {
+    let account_key = keccak_hash::keccak(address.as_bytes()).0;
+    let account = mpt::verify_proof(state_root, &account_key, account_proof)
+        .context("Verifying core contract account proof")?
+        .context("Core contract account does not exist")?;
+    let storage_root = mpt::account_storage_root(&account)?;
+
+    let slots = starknet_state_slots();
+    anyhow::ensure!(
+        storage_proofs.len() == slots.len(),
+        "Expected {} storage proofs, got {}",
+        slots.len(),
+        storage_proofs.len()
+    );
+
+    // Storage proofs are returned in the order the keys were requested in.
+    let mut values = [[0u8; 32]; 3];
+    for ((value, slot), storage_proof) in values.iter_mut().zip(&slots).zip(storage_proofs) {
+        let key = keccak_hash::keccak(slot.as_slice()).0;
+        *value = match mpt::verify_proof(&storage_root, &key, storage_proof)
+            .context("Verifying core contract storage proof")?
+        {
+            Some(encoded) => mpt::storage_value(&encoded)?,
+            None => [0u8; 32],
+        };
+    }
+
+    let [global_root, number, hash] = values;
+    let number = u64::from_be_bytes(number[24..].try_into().expect("8 bytes"));
+
+    Ok(EthereumStateUpdate {
+        state_root: StateCommitment(Felt::from(global_root)),
+        block_number: BlockNumber::new(number)
+            .context("Core contract block number out of range")?,
+        block_hash: BlockHash(Felt::from(hash)),
+    })
+}
+
+fn hex_bytes(value: &str) -> anyhow::Result<Vec<u8>> {
+    hex::decode(value.trim_start_matches("0x")).context("Decoding hex value")
+}
+
+fn hex_root(value: &str) -> anyhow::Result<Root> {
+    hex_bytes(value)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Expected 32 bytes"))
+}
+
+fn parse_branch(branch: &[String]) -> anyhow::Result<Vec<Root>> {
+    branch.iter().map(|node| hex_root(node)).collect()
+}
+
+fn verify_sync_committee_branch(
+    committee: &SyncCommittee,
+    branch: &[String],
+    index: u64,
+    state_root: &Root,
+) -> anyhow::Result<()> {
+    let branch = parse_branch(branch)?;
+    anyhow::ensure!(
+        SYNC_COMMITTEE_DEPTHS.contains(&branch.len())
+            && is_valid_merkle_branch(
+                &committee.hash_tree_root(),
+                &branch,
+                branch.len(),
+                index,
+                state_root,
+            ),
+        "Invalid sync committee branch"
+    );
+    Ok(())
+}
+
+fn verify_sync_aggregate(
+    committee: &SyncCommittee,
+    bits: &[u8],
+    signature: &[u8],
+    signing_root: &Root,
+) -> anyhow::Result<()> {
+    use blst::min_pk::{PublicKey, Signature};
+
+    anyhow::ensure!(
+        bits.len() * 8 == SYNC_COMMITTEE_SIZE,
+        "Invalid sync committee bits length"
+    );
+
+    let participants = committee
+        .pubkeys
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| (bits[i / 8] >> (i % 8)) & 1 == 1)
+        .map(|(_, key)| PublicKey::from_bytes(key))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow::anyhow!("Invalid sync committee public key: {e:?}"))?;
+
+    // Require a supermajority of the committee, like the spec does for
+    // finalizing updates.
+    anyhow::ensure!(
+        participants.len() * 3 >= SYNC_COMMITTEE_SIZE * 2,
+        "Insufficient sync committee participation: {}",
+        participants.len()
+    );
+
+    let signature = Signature::from_bytes(signature)
+        .map_err(|e| anyhow::anyhow!("Invalid sync committee signature: {e:?}"))?;
+    let participants: Vec<&PublicKey> = participants.iter().collect();
+
+    match signature.fast_aggregate_verify(true, signing_root, BLS_DST, &participants) {
+        blst::BLST_ERROR::BLST_SUCCESS => Ok(()),
+        error => anyhow::bail!("Sync committee signature verification failed: {error:?}"),
+    }
+}
+
+/// Beacon API light client JSON types.
+mod dto {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize)]
+    pub struct Versioned<T> {
+        pub data: T,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Bootstrap {
+        pub header: LightClientHeader,
+        pub current_sync_committee: SyncCommittee,
+        pub current_sync_committee_branch: Vec<String>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Update {
+        pub attested_header: LightClientHeader,
+        #[serde(default)]
+        pub next_sync_committee: Option<SyncCommittee>,
+        #[serde(default)]
+        pub next_sync_committee_branch: Option<Vec<String>>,
+        #[serde(default)]
+        pub finalized_header: Option<LightClientHeader>,
+        #[serde(default)]
+        pub finality_branch: Vec<String>,
+        pub sync_aggregate: SyncAggregate,
+        #[serde(deserialize_with = "quoted_u64")]
+        pub signature_slot: u64,
+    }
+
+    #[derive(Deserialize)]
+    pub struct SyncAggregate {
+        pub sync_committee_bits: String,
+        pub sync_committee_signature: String,
+    }
+
+    #[derive(Deserialize)]
+    pub struct SyncCommittee {
+        pub pubkeys: Vec<String>,
+        pub aggregate_pubkey: String,
+    }
+
+    impl SyncCommittee {
+        pub fn parse(&self) -> anyhow::Result<super::SyncCommittee> {
+            let pubkey = |key: &String| -> anyhow::Result<[u8; 48]> {
+                hex_bytes(key)?
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("Expected 48 byte public key"))
+            };
+
+            anyhow::ensure!(
+                self.pubkeys.len() == SYNC_COMMITTEE_SIZE,
+                "Expected {SYNC_COMMITTEE_SIZE} sync committee members"
+            );
+
+            Ok(super::SyncCommittee {
+                pubkeys: self.pubkeys.iter().map(pubkey).collect::<Result<_, _>>()?,
+                aggregate_pubkey: pubkey(&self.aggregate_pubkey)?,
+            })
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct LightClientHeader {
+        pub beacon: BeaconBlockHeader,
+        pub execution: ExecutionPayloadHeader,
+        pub execution_branch: Vec<String>,
+    }
+
+    impl LightClientHeader {
+        /// Parses the header and verifies that the execution payload header
+        /// is part of the beacon block body.
+        pub fn verify(&self) -> anyhow::Result<super::LightClientHeader> {
+            let beacon = super::BeaconBlockHeader {
+                slot: self.beacon.slot,
+                proposer_index: self.beacon.proposer_index,
+                parent_root: hex_root(&self.beacon.parent_root)?,
+                state_root: hex_root(&self.beacon.state_root)?,
+                body_root: hex_root(&self.beacon.body_root)?,
+            };
+            let execution = self.execution.parse()?;
+
+            let branch = parse_branch(&self.execution_branch)?;
+            anyhow::ensure!(
+                is_valid_merkle_branch(
+                    &execution.root,
+                    &branch,
+                    EXECUTION_PAYLOAD_DEPTH,
+                    EXECUTION_PAYLOAD_INDEX,
+                    &beacon.body_root,
+                ),
+                "Invalid execution payload branch"
+            );
+
+            Ok(super::LightClientHeader { beacon, execution })
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct BeaconBlockHeader {
+        #[serde(deserialize_with = "quoted_u64")]
+        pub slot: u64,
+        #[serde(deserialize_with = "quoted_u64")]
+        pub proposer_index: u64,
+        pub parent_root: String,
+        pub state_root: String,
+        pub body_root: String,
+    }
+
+    /// Deneb (and later) execution payload header.
+    #[derive(Deserialize)]
+    pub struct ExecutionPayloadHeader {
+        pub parent_hash: String,
+        pub fee_recipient: String,
+        pub state_root: String,
+        pub receipts_root: String,
+        pub logs_bloom: String,
+        pub prev_randao: String,
+        #[serde(deserialize_with = "quoted_u64")]
+        pub block_number: u64,
+        #[serde(deserialize_with = "quoted_u64")]
+        pub gas_limit: u64,
+        #[serde(deserialize_with = "quoted_u64")]
+        pub gas_used: u64,
+        #[serde(deserialize_with = "quoted_u64")]
+        pub timestamp: u64,
+        pub extra_data: String,
+        pub base_fee_per_gas: String,
+        pub block_hash: String,
+        pub transactions_root: String,
+        pub withdrawals_root: String,
+        #[serde(deserialize_with = "quoted_u64")]
+        pub blob_gas_used: u64,
+        #[serde(deserialize_with = "quoted_u64")]
+        pub excess_blob_gas: u64,
+    }
+
+    impl ExecutionPayloadHeader {
+        pub fn parse(&self) -> anyhow::Result<super::ExecutionHeader> {
+            let state_root = hex_root(&self.state_root)?;
+
+            let mut fee_recipient = [0u8; 32];
+            let address = hex_bytes(&self.fee_recipient)?;
+            anyhow::ensure!(address.len() == 20, "Expected 20 byte fee recipient");
+            fee_recipient[..20].copy_from_slice(&address);
+
+            let logs_bloom = hex_bytes(&self.logs_bloom)?;
+            anyhow::ensure!(logs_bloom.len() == 256, "Expected 256 byte logs bloom");
+
+            let extra_data = hex_bytes(&self.extra_data)?;
+            anyhow::ensure!(extra_data.len() <= 32, "Extra data longer than 32 bytes");
+
+            let base_fee = primitive_types::U256::from_dec_str(&self.base_fee_per_gas)
+                .context("Parsing base fee per gas")?;
+            let mut base_fee_chunk = [0u8; 32];
+            base_fee.to_little_endian(&mut base_fee_chunk);
+
+            let fields = [
+                hex_root(&self.parent_hash)?,
+                fee_recipient,
+                state_root,
+                hex_root(&self.receipts_root)?,
+                merkleize(&pack_bytes(&logs_bloom), 8),
+                hex_root(&self.prev_randao)?,
+                u64_chunk(self.block_number),
+                u64_chunk(self.gas_limit),
+                u64_chunk(self.gas_used),
+                u64_chunk(self.timestamp),
+                mix_in_length(&merkleize(&pack_bytes(&extra_data), 1), extra_data.len()),
+                base_fee_chunk,
+                hex_root(&self.block_hash)?,
+                hex_root(&self.transactions_root)?,
+                hex_root(&self.withdrawals_root)?,
+                u64_chunk(self.blob_gas_used),
+                u64_chunk(self.excess_blob_gas),
+            ];
+
+            Ok(super::ExecutionHeader {
+                block_number: self.block_number,
+                state_root,
+                root: merkleize(&fields, fields.len()),
+            })
+        }
+    }
+
+    fn quoted_u64<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blst::min_pk::{AggregatePublicKey, AggregateSignature, SecretKey, Signature};
+    use serde_json::{json, Value};
+
+    use super::mpt::tests::{bytes, fixture, nodes, word};
+    use super::*;
+
+    const PERIOD: u64 = SLOTS_PER_EPOCH * EPOCHS_PER_SYNC_COMMITTEE_PERIOD;
+
+    #[test]
+    fn starknet_state_proof() {
+        let fixture = fixture();
+        let proof = &fixture["proof"];
+        let address = H160::from_slice(&bytes(&proof["address"]));
+        let account_proof: Vec<Bytes> = nodes(&proof["accountProof"])
+            .into_iter()
+            .map(Bytes::from)
+            .collect();
+        let storage_proofs: Vec<Vec<Bytes>> = proof["storageProof"].as_array().unwrap()[..3]
+            .iter()
+            .map(|p| nodes(&p["proof"]).into_iter().map(Bytes::from).collect())
+            .collect();
+
+        let state_root = word(&fixture["stateRoot"]);
+        let state =
+            verify_starknet_state(&state_root, &address, &account_proof, &storage_proofs).unwrap();
+
+        let expected = &fixture["starknetState"];
+        assert_eq!(
+            state,
+            EthereumStateUpdate {
+                state_root: StateCommitment(Felt::from(word(&expected["globalRoot"]))),
+                block_number: BlockNumber::new_or_panic(expected["blockNumber"].as_u64().unwrap()),
+                block_hash: BlockHash(Felt::from(word(&expected["blockHash"]))),
+            }
+        );
+
+        let mut wrong_root = state_root;
+        wrong_root[31] ^= 1;
+        verify_starknet_state(&wrong_root, &address, &account_proof, &storage_proofs).unwrap_err();
+
+        let other_address = H160::repeat_byte(1);
+        verify_starknet_state(&state_root, &other_address, &account_proof, &storage_proofs)
+            .unwrap_err();
+
+        verify_starknet_state(&state_root, &address, &account_proof, &storage_proofs[..2])
+            .unwrap_err();
+    }
+
+    struct Committee {
+        keys: Vec<SecretKey>,
+        json: Value,
+        root: Root,
+    }
+
+    fn committee(seed: u8) -> Committee {
+        let keys: Vec<SecretKey> = (0..SYNC_COMMITTEE_SIZE)
+            .map(|i| {
+                let mut ikm = [seed; 32];
+                ikm[..8].copy_from_slice(&(i as u64).to_le_bytes());
+                SecretKey::key_gen(&ikm, &[]).unwrap()
+            })
+            .collect();
+        let pubkeys: Vec<_> = keys.iter().map(SecretKey::sk_to_pk).collect();
+        let aggregate = AggregatePublicKey::aggregate(&pubkeys.iter().collect::<Vec<_>>(), false)
+            .unwrap()
+            .to_public_key();
+
+        let json = json!({
+            "pubkeys": pubkeys
+                .iter()
+                .map(|key| format!("0x{}", hex::encode(key.compress())))
+                .collect::<Vec<_>>(),
+            "aggregate_pubkey": format!("0x{}", hex::encode(aggregate.compress())),
+        });
+        let root = serde_json::from_value::<dto::SyncCommittee>(json.clone())
+            .unwrap()
+            .parse()
+            .unwrap()
+            .hash_tree_root();
+
+        Committee { keys, json, root }
+    }
+
+    fn hex_value(bytes: &[u8]) -> Value {
+        Value::String(format!("0x{}", hex::encode(bytes)))
+    }
+
+    fn fold_branch(leaf: &Root, branch: &[Root], index: u64) -> Root {
+        branch.iter().enumerate().fold(*leaf, |node, (i, sibling)| {
+            if (index >> i) & 1 == 1 {
+                hash_pair(sibling, &node)
+            } else {
+                hash_pair(&node, sibling)
+            }
+        })
+    }
+
+    /// A light client header at `slot` whose beacon state root is
+    /// `state_root`. Returns its JSON and the beacon block root.
+    fn header(slot: u64, state_root: Root) -> (Value, Root) {
+        let execution = json!({
+            "parent_hash": hex_value(&[1; 32]),
+            "fee_recipient": hex_value(&[2; 20]),
+            "state_root": hex_value(&u64_chunk(slot)),
+            "receipts_root": hex_value(&[3; 32]),
+            "logs_bloom": hex_value(&[0; 256]),
+            "prev_randao": hex_value(&[4; 32]),
+            "block_number": slot.to_string(),
+            "gas_limit": "30000000",
+            "gas_used": "12345",
+            "timestamp": (1_700_000_000 + 12 * slot).to_string(),
+            "extra_data": "0x",
+            "base_fee_per_gas": "7",
+            "block_hash": hex_value(&[5; 32]),
+            "transactions_root": hex_value(&[6; 32]),
+            "withdrawals_root": hex_value(&[7; 32]),
+            "blob_gas_used": "0",
+            "excess_blob_gas": "0",
+        });
+        let execution_root =
+            serde_json::from_value::<dto::ExecutionPayloadHeader>(execution.clone())
+                .unwrap()
+                .parse()
+                .unwrap()
+                .root;
+        let execution_branch: Vec<Root> = (0..EXECUTION_PAYLOAD_DEPTH as u8)
+            .map(|i| [0x10 + i; 32])
+            .collect();
+
+        let beacon = BeaconBlockHeader {
+            slot,
+            proposer_index: 42,
+            parent_root: [8; 32],
+            state_root,
+            body_root: fold_branch(&execution_root, &execution_branch, EXECUTION_PAYLOAD_INDEX),
+        };
+
+        let json = json!({
+            "beacon": {
+                "slot": slot.to_string(),
+                "proposer_index": beacon.proposer_index.to_string(),
+                "parent_root": hex_value(&beacon.parent_root),
+                "state_root": hex_value(&beacon.state_root),
+                "body_root": hex_value(&beacon.body_root),
+            },
+            "execution": execution,
+            "execution_branch": execution_branch.iter().map(|n| hex_value(n)).collect::<Vec<_>>(),
+        });
+
+        (json, beacon.hash_tree_root())
+    }
+
+    /// The top 32 fields of a beacon state, with the finalized checkpoint and
+    /// sync committees at their real positions.
+    struct BeaconState {
+        fields: Vec<Root>,
+    }
+
+    impl BeaconState {
+        const FINALIZED_CHECKPOINT: usize = (FINALIZED_ROOT_INDEX / 2) as usize;
+
+        fn new(finalized_root: Root, current: &Committee, next: &Committee) -> Self {
+            let mut fields: Vec<Root> = (0..32u8).map(|i| [0x40 + i; 32]).collect();
+            fields[Self::FINALIZED_CHECKPOINT] = hash_pair(&u64_chunk(1), &finalized_root);
+            fields[CURRENT_SYNC_COMMITTEE_INDEX as usize] = current.root;
+            fields[NEXT_SYNC_COMMITTEE_INDEX as usize] = next.root;
+            Self { fields }
+        }
+
+        fn root(&self) -> Root {
+            merkleize(&self.fields, 32)
+        }
+
+        fn branch(&self, index: usize) -> Vec<Value> {
+            let mut layer = self.fields.clone();
+            let mut index = index;
+            let mut branch = Vec::new();
+            while layer.len() > 1 {
+                branch.push(hex_value(&layer[index ^ 1]));
+                layer = layer.chunks(2).map(|p| hash_pair(&p[0], &p[1])).collect();
+                index /= 2;
+            }
+            branch
+        }
+
+        fn finality_branch(&self) -> Vec<Value> {
+            let mut branch = vec![hex_value(&u64_chunk(1))];
+            branch.extend(self.branch(Self::FINALIZED_CHECKPOINT));
+            branch
+        }
+    }
+
+    fn network() -> BeaconNetwork {
+        BeaconNetwork {
+            genesis_validators_root: [9; 32],
+            forks: vec![(0, [0, 0, 0, 1]), (2600, [0, 0, 0, 2])],
+        }
+    }
+
+    fn client(checkpoint: Root) -> LightClient {
+        LightClient::new(
+            LightClientConfig {
+                beacon_url: Url::parse("http://localhost:5052").unwrap(),
+                checkpoint: H256(checkpoint),
+                network: network(),
+            },
+            Url::parse("ws://localhost:8546").unwrap(),
+        )
+    }
+
+    /// Returns a bootstrap at `slot` and the checkpoint it is valid for.
+    fn bootstrap(slot: u64, current: &Committee, next: &Committee) -> (dto::Bootstrap, Root) {
+        let state = BeaconState::new([0; 32], current, next);
+        let (header, checkpoint) = header(slot, state.root());
+        let bootstrap = serde_json::from_value(json!({
+            "header": header,
+            "current_sync_committee": current.json,
+            "current_sync_committee_branch": state.branch(CURRENT_SYNC_COMMITTEE_INDEX as usize),
+        }))
+        .unwrap();
+
+        (bootstrap, checkpoint)
+    }
+
+    /// An update finalizing `finalized_slot`, attested one epoch later and
+    /// signed by the first `participants` members of `signer`.
+    fn update(
+        finalized_slot: u64,
+        current: &Committee,
+        next: &Committee,
+        signer: &Committee,
+        participants: usize,
+    ) -> Value {
+        let (finalized, finalized_root) = header(finalized_slot, [0x20; 32]);
+        let state = BeaconState::new(finalized_root, current, next);
+        let attested_slot = finalized_slot + SLOTS_PER_EPOCH;
+        let (attested, attested_root) = header(attested_slot, state.root());
+
+        let signature_slot = attested_slot + 1;
+        let signing_root = hash_pair(
+            &attested_root,
+            &network().sync_committee_domain(signature_slot),
+        );
+        let signatures: Vec<Signature> = signer.keys[..participants]
+            .iter()
+            .map(|key| key.sign(&signing_root, BLS_DST, &[]))
+            .collect();
+        let signature = AggregateSignature::aggregate(&signatures.iter().collect::<Vec<_>>(), true)
+            .unwrap()
+            .to_signature();
+
+        let mut bits = [0u8; SYNC_COMMITTEE_SIZE / 8];
+        for i in 0..participants {
+            bits[i / 8] |= 1 << (i % 8);
+        }
+
+        json!({
+            "attested_header": attested,
+            "next_sync_committee": next.json,
+            "next_sync_committee_branch": state.branch(NEXT_SYNC_COMMITTEE_INDEX as usize),
+            "finalized_header": finalized,
+            "finality_branch": state.finality_branch(),
+            "sync_aggregate": {
+                "sync_committee_bits": hex_value(&bits),
+                "sync_committee_signature": hex_value(&signature.compress()),
+            },
+            "signature_slot": signature_slot.to_string(),
+        })
+    }
+
+    fn parse_update(update: Value) -> dto::Update {
+        serde_json::from_value(update).unwrap()
+    }
+
+    #[test]
+    fn bootstrap_is_verified_against_checkpoint() {
+        let (first, second) = (committee(1), committee(2));
+        let (bootstrap, checkpoint) = bootstrap(10 * PERIOD + 64, &first, &second);
+
+        let store = verify_bootstrap(&bootstrap, &checkpoint).unwrap();
+        assert_eq!(store.finalized_header.beacon.slot, 10 * PERIOD + 64);
+        assert_eq!(
+            store.finalized_header.execution.block_number,
+            10 * PERIOD + 64
+        );
+        assert_eq!(store.current_sync_committee.hash_tree_root(), first.root);
+        assert!(store.next_sync_committee.is_none());
+
+        let mut wrong_checkpoint = checkpoint;
+        wrong_checkpoint[0] ^= 1;
+        verify_bootstrap(&bootstrap, &wrong_checkpoint).unwrap_err();
+
+        // A committee that is not part of the bootstrapped state.
+        let mut bootstrap = bootstrap;
+        bootstrap.current_sync_committee = serde_json::from_value(second.json).unwrap();
+        verify_bootstrap(&bootstrap, &checkpoint).unwrap_err();
+    }
+
+    #[test]
+    fn updates_advance_and_rotate_committees() {
+        let committees: Vec<Committee> = (1..=3).map(committee).collect();
+        let (bootstrap, checkpoint) = bootstrap(10 * PERIOD + 64, &committees[0], &committees[1]);
+        let client = client(checkpoint);
+        let mut store = verify_bootstrap(&bootstrap, &checkpoint).unwrap();
+
+        // Signed by the next committee, which is not known yet.
+        let rotation = update(
+            11 * PERIOD + 32,
+            &committees[1],
+            &committees[2],
+            &committees[1],
+            SYNC_COMMITTEE_SIZE,
+        );
+        client
+            .apply_update(&mut store, parse_update(rotation.clone()))
+            .unwrap_err();
+
+        let finality = update(
+            10 * PERIOD + 128,
+            &committees[0],
+            &committees[1],
+            &committees[0],
+            SYNC_COMMITTEE_SIZE,
+        );
+        client
+            .apply_update(&mut store, parse_update(finality))
+            .unwrap();
+        assert_eq!(store.finalized_header.beacon.slot, 10 * PERIOD + 128);
+        assert_eq!(
+            store.next_sync_committee.as_ref().unwrap().hash_tree_root(),
+            committees[1].root
+        );
+
+        client
+            .apply_update(&mut store, parse_update(rotation))
+            .unwrap();
+        assert_eq!(store.finalized_header.beacon.slot, 11 * PERIOD + 32);
+        assert_eq!(
+            store.finalized_header.execution.block_number,
+            11 * PERIOD + 32
+        );
+        assert_eq!(
+            store.current_sync_committee.hash_tree_root(),
+            committees[1].root
+        );
+        assert_eq!(
+            store.next_sync_committee.as_ref().unwrap().hash_tree_root(),
+            committees[2].root
+        );
+    }
+
+    #[test]
+    fn invalid_updates_are_rejected() {
+        let (first, second) = (committee(1), committee(2));
+        let (bootstrap, checkpoint) = bootstrap(10 * PERIOD + 64, &first, &second);
+        let client = client(checkpoint);
+        let store = verify_bootstrap(&bootstrap, &checkpoint).unwrap();
+        let slot = 10 * PERIOD + 128;
+
+        // The smallest two-thirds supermajority is enough.
+        let quorum = SYNC_COMMITTEE_SIZE * 2 / 3 + 1;
+        client
+            .apply_update(
+                &mut store.clone(),
+                parse_update(update(slot, &first, &second, &first, quorum)),
+            )
+            .unwrap();
+
+        let low_participation = update(slot, &first, &second, &first, quorum - 1);
+        let wrong_signer = update(slot, &first, &second, &second, SYNC_COMMITTEE_SIZE);
+
+        let mut bad_finality = update(slot, &first, &second, &first, SYNC_COMMITTEE_SIZE);
+        bad_finality["finality_branch"][0] = hex_value(&u64_chunk(2));
+
+        let mut bad_execution = update(slot, &first, &second, &first, SYNC_COMMITTEE_SIZE);
+        bad_execution["finalized_header"]["execution"]["block_number"] = json!("1");
+
+        let mut bad_committee = update(slot, &first, &second, &first, SYNC_COMMITTEE_SIZE);
+        bad_committee["next_sync_committee"] = first.json.clone();
+
+        for update in [
+            low_participation,
+            wrong_signer,
+            bad_finality,
+            bad_execution,
+            bad_committee,
+        ] {
+            let mut store = store.clone();
+            client
+                .apply_update(&mut store, parse_update(update))
+                .unwrap_err();
+            assert_eq!(store.finalized_header.beacon.slot, 10 * PERIOD + 64);
+        }
+    }
+
+    #[test]
+    fn older_state_is_not_saved() {
+        let (first, second) = (committee(1), committee(2));
+        let (bootstrap, checkpoint) = bootstrap(10 * PERIOD + 64, &first, &second);
+        let client = client(checkpoint);
+        let old = verify_bootstrap(&bootstrap, &checkpoint).unwrap();
+
+        let mut new = old.clone();
+        client
+            .apply_update(
+                &mut new,
+                parse_update(update(
+                    10 * PERIOD + 128,
+                    &first,
+                    &second,
+                    &first,
+                    SYNC_COMMITTEE_SIZE,
+                )),
+            )
+            .unwrap();
+
+        client.save(new);
+        client.save(old);
+
+        let store = client.store.lock().unwrap();
+        assert_eq!(
+            store.as_ref().unwrap().finalized_header.beacon.slot,
+            10 * PERIOD + 128
+        );
+    }
+}
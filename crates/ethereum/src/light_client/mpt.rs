@@ -0,0 +1,275 @@
+//! Verification of Ethereum Merkle-Patricia trie proofs as returned by
+//! `eth_getProof`.
+
+use alloy_rlp::Header;
+use anyhow::Context;
+use keccak_hash::keccak;
+
+/// Verifies `proof` for `path` (an already hashed trie key) against `root`.
+///
+/// Returns the RLP encoded value stored at `path`, or `None` if the proof
+/// shows that the key is absent from the trie.
+pub(crate) fn verify_proof(
+    root: &[u8; 32],
+    path: &[u8; 32],
+    proof: &[impl AsRef<[u8]>],
+) -> anyhow::Result<Option<Vec<u8>>> {
+    let nibbles: Vec<u8> = path.iter().flat_map(|b| [b >> 4, b & 0x0f]).collect();
+    let mut nibbles = nibbles.as_slice();
+
+    let mut proof = proof.iter().map(AsRef::as_ref);
+    let mut expected_hash = *root;
+    let mut node = proof.next().context("Empty proof")?;
+    anyhow::ensure!(keccak(node).0 == expected_hash, "Root node hash mismatch");
+
+    loop {
+        let items = rlp_list(node).context("Decoding trie node")?;
+
+        let next = match items.len() {
+            17 => {
+                let Some((&nibble, rest)) = nibbles.split_first() else {
+                    let value = rlp_string(items[16])?;
+                    return Ok((!value.is_empty()).then(|| value.to_vec()));
+                };
+                nibbles = rest;
+                items[nibble as usize]
+            }
+            2 => {
+                let (is_leaf, node_path) = decode_hex_prefix(rlp_string(items[0])?)?;
+                if !nibbles.starts_with(&node_path) {
+                    return Ok(None);
+                }
+                nibbles = &nibbles[node_path.len()..];
+
+                if is_leaf {
+                    anyhow::ensure!(nibbles.is_empty(), "Leaf node path is too short");
+                    return Ok(Some(rlp_string(items[1])?.to_vec()));
+                }
+                items[1]
+            }
+            other => anyhow::bail!("Unexpected trie node with {other} items"),
+        };
+
+        // A child reference is either the hash of the next node in the proof, an
+        // empty string for a missing child, or the child node itself if its
+        // encoding is shorter than 32 bytes.
+        if is_rlp_list(next) {
+            node = next;
+            continue;
+        }
+
+        let child = rlp_string(next)?;
+        match child.len() {
+            0 => return Ok(None),
+            32 => {
+                expected_hash.copy_from_slice(child);
+                node = proof
+                    .next()
+                    .context("Proof ended before reaching the key")?;
+                anyhow::ensure!(keccak(node).0 == expected_hash, "Trie node hash mismatch");
+            }
+            other => anyhow::bail!("Invalid child reference length {other}"),
+        }
+    }
+}
+
+fn is_rlp_list(item: &[u8]) -> bool {
+    item.first().is_some_and(|b| *b >= 0xc0)
+}
+
+fn rlp_string(mut item: &[u8]) -> anyhow::Result<&[u8]> {
+    Header::decode_bytes(&mut item, false).map_err(|e| anyhow::anyhow!("{e}"))
+}
+
+/// Splits an RLP list into its raw (still encoded) items.
+fn rlp_list(mut node: &[u8]) -> anyhow::Result<Vec<&[u8]>> {
+    let mut payload = Header::decode_bytes(&mut node, true).map_err(|e| anyhow::anyhow!("{e}"))?;
+
+    let mut items = Vec::new();
+    while !payload.is_empty() {
+        let mut rest = payload;
+        let header = Header::decode(&mut rest).map_err(|e| anyhow::anyhow!("{e}"))?;
+        let header_len = payload.len() - rest.len();
+        let item_len = header_len + header.payload_length;
+        anyhow::ensure!(item_len <= payload.len(), "RLP item overflows list");
+
+        let (item, remainder) = payload.split_at(item_len);
+        items.push(item);
+        payload = remainder;
+    }
+
+    Ok(items)
+}
+
+/// Decodes a hex-prefix encoded path, returning whether it belongs to a leaf
+/// node and its nibbles.
+fn decode_hex_prefix(encoded: &[u8]) -> anyhow::Result<(bool, Vec<u8>)> {
+    let (&first, rest) = encoded.split_first().context("Empty hex-prefix path")?;
+    let flag = first >> 4;
+    anyhow::ensure!(flag <= 3, "Invalid hex-prefix flag {flag}");
+
+    let is_leaf = flag >= 2;
+    let is_odd = flag & 1 == 1;
+
+    let mut nibbles = Vec::with_capacity(rest.len() * 2 + 1);
+    if is_odd {
+        nibbles.push(first & 0x0f);
+    }
+    nibbles.extend(rest.iter().flat_map(|b| [b >> 4, b & 0x0f]));
+
+    Ok((is_leaf, nibbles))
+}
+
+/// Decodes the storage root from an RLP encoded account
+/// `[nonce, balance, storage_root, code_hash]`.
+pub(crate) fn account_storage_root(account: &[u8]) -> anyhow::Result<[u8; 32]> {
+    let items = rlp_list(account).context("Decoding account")?;
+    anyhow::ensure!(items.len() == 4, "Expected 4 account fields");
+
+    rlp_string(items[2])?
+        .try_into()
+        .context("Storage root is not 32 bytes")
+}
+
+/// Decodes an RLP encoded storage value into a big-endian 32 byte word.
+pub(crate) fn storage_value(value: &[u8]) -> anyhow::Result<[u8; 32]> {
+    let value = rlp_string(value)?;
+    anyhow::ensure!(value.len() <= 32, "Storage value longer than 32 bytes");
+
+    let mut word = [0u8; 32];
+    word[32 - value.len()..].copy_from_slice(value);
+    Ok(word)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    /// An `eth_getProof` response for the Starknet core contract's state slots
+    /// (and one unset slot), together with the state root it was taken at.
+    pub(crate) fn fixture() -> Value {
+        serde_json::from_str(include_str!(
+            "../../fixtures/light_client/eth_getProof.json"
+        ))
+        .unwrap()
+    }
+
+    pub(crate) fn bytes(value: &Value) -> Vec<u8> {
+        hex::decode(value.as_str().unwrap().trim_start_matches("0x")).unwrap()
+    }
+
+    pub(crate) fn word(value: &Value) -> [u8; 32] {
+        bytes(value).try_into().unwrap()
+    }
+
+    pub(crate) fn nodes(proof: &Value) -> Vec<Vec<u8>> {
+        proof.as_array().unwrap().iter().map(bytes).collect()
+    }
+
+    fn account(fixture: &Value) -> Vec<u8> {
+        let proof = &fixture["proof"];
+        verify_proof(
+            &word(&fixture["stateRoot"]),
+            &keccak(bytes(&proof["address"])).0,
+            &nodes(&proof["accountProof"]),
+        )
+        .unwrap()
+        .unwrap()
+    }
+
+    #[test]
+    fn account_proof() {
+        let fixture = fixture();
+        let account = account(&fixture);
+
+        assert_eq!(
+            account_storage_root(&account).unwrap(),
+            word(&fixture["proof"]["storageHash"])
+        );
+    }
+
+    #[test]
+    fn storage_proofs() {
+        let fixture = fixture();
+        let storage_root = account_storage_root(&account(&fixture)).unwrap();
+
+        for storage_proof in fixture["proof"]["storageProof"].as_array().unwrap() {
+            let value = verify_proof(
+                &storage_root,
+                &keccak(bytes(&storage_proof["key"])).0,
+                &nodes(&storage_proof["proof"]),
+            )
+            .unwrap()
+            .map(|value| storage_value(&value).unwrap())
+            .unwrap_or_default();
+
+            let expected = primitive_types::U256::from_str_radix(
+                storage_proof["value"]
+                    .as_str()
+                    .unwrap()
+                    .trim_start_matches("0x"),
+                16,
+            )
+            .unwrap();
+            assert_eq!(primitive_types::U256::from_big_endian(&value), expected);
+        }
+    }
+
+    #[test]
+    fn absent_key() {
+        let fixture = fixture();
+        let storage_root = account_storage_root(&account(&fixture)).unwrap();
+        let absent = &fixture["proof"]["storageProof"][3];
+
+        let value = verify_proof(
+            &storage_root,
+            &keccak(bytes(&absent["key"])).0,
+            &nodes(&absent["proof"]),
+        )
+        .unwrap();
+        assert_eq!(value, None);
+    }
+
+    #[test]
+    fn tampered_proof_is_rejected() {
+        let fixture = fixture();
+        let proof = &fixture["proof"];
+        let state_root = word(&fixture["stateRoot"]);
+        let key = keccak(bytes(&proof["address"])).0;
+
+        for i in 0..nodes(&proof["accountProof"]).len() {
+            let mut nodes = nodes(&proof["accountProof"]);
+            let last = nodes[i].len() - 1;
+            nodes[i][last] ^= 1;
+            verify_proof(&state_root, &key, &nodes).unwrap_err();
+        }
+
+        let mut truncated = nodes(&proof["accountProof"]);
+        truncated.pop();
+        verify_proof(&state_root, &key, &truncated).unwrap_err();
+
+        let mut wrong_root = state_root;
+        wrong_root[0] ^= 1;
+        verify_proof(&wrong_root, &key, &nodes(&proof["accountProof"])).unwrap_err();
+    }
+
+    #[test]
+    fn hex_prefix() {
+        assert_eq!(
+            decode_hex_prefix(&[0x00, 0x12]).unwrap(),
+            (false, vec![1, 2])
+        );
+        assert_eq!(
+            decode_hex_prefix(&[0x13, 0x45]).unwrap(),
+            (false, vec![3, 4, 5])
+        );
+        assert_eq!(decode_hex_prefix(&[0x20]).unwrap(), (true, vec![]));
+        assert_eq!(
+            decode_hex_prefix(&[0x3f, 0x1c]).unwrap(),
+            (true, vec![15, 1, 12])
+        );
+        decode_hex_prefix(&[0x40]).unwrap_err();
+    }
+}
@@ -0,0 +1,141 @@
+//! The minimal subset of SSZ merkleization needed to verify light client
+//! data served by a beacon node.
+
+use sha2::{Digest, Sha256};
+
+pub(crate) type Root = [u8; 32];
+
+pub(crate) fn hash_pair(left: &Root, right: &Root) -> Root {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+pub(crate) fn u64_chunk(value: u64) -> Root {
+    let mut chunk = [0u8; 32];
+    chunk[..8].copy_from_slice(&value.to_le_bytes());
+    chunk
+}
+
+/// Packs `bytes` into 32 byte chunks, right padding the last chunk with
+/// zeros.
+pub(crate) fn pack_bytes(bytes: &[u8]) -> Vec<Root> {
+    bytes
+        .chunks(32)
+        .map(|c| {
+            let mut chunk = [0u8; 32];
+            chunk[..c.len()].copy_from_slice(c);
+            chunk
+        })
+        .collect()
+}
+
+/// Merkleizes `chunks` into a tree with `limit` leaves (rounded up to the next
+/// power of two), padding with zero chunks.
+pub(crate) fn merkleize(chunks: &[Root], limit: usize) -> Root {
+    let width = limit.max(chunks.len()).max(1).next_power_of_two();
+
+    let mut layer = chunks.to_vec();
+    layer.resize(width, [0u8; 32]);
+
+    while layer.len() > 1 {
+        layer = layer
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], &pair[1]))
+            .collect();
+    }
+
+    layer[0]
+}
+
+pub(crate) fn mix_in_length(root: &Root, length: usize) -> Root {
+    hash_pair(root, &u64_chunk(length as u64))
+}
+
+/// Verifies a Merkle branch of `leaf` at the given `depth` and `index` against
+/// `root`, as defined by `is_valid_merkle_branch` in the consensus spec.
+pub(crate) fn is_valid_merkle_branch(
+    leaf: &Root,
+    branch: &[Root],
+    depth: usize,
+    index: u64,
+    root: &Root,
+) -> bool {
+    if branch.len() != depth {
+        return false;
+    }
+
+    let computed = branch.iter().enumerate().fold(*leaf, |node, (i, sibling)| {
+        if (index >> i) & 1 == 1 {
+            hash_pair(sibling, &node)
+        } else {
+            hash_pair(&node, sibling)
+        }
+    });
+
+    &computed == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root(hex: &str) -> Root {
+        hex::decode(hex).unwrap().try_into().unwrap()
+    }
+
+    /// `hash_tree_root` of the `uint64` values 1 to 5, as a vector of length 8.
+    const FIVE_U64S: &str = "b26528272e5e43113dbd86763ea69f188495bec3a75e185b327ad84ba0a9c881";
+
+    fn five_u64s() -> Vec<Root> {
+        (1..=5).map(u64_chunk).collect()
+    }
+
+    #[test]
+    fn zero_hashes() {
+        assert_eq!(
+            merkleize(&[], 2),
+            root("f5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a92759fb4b")
+        );
+        assert_eq!(
+            merkleize(&[], 4),
+            root("db56114e00fdd4c1f85c892bf35ac9a89289aaecb1ebd0a96cde606a748b5d71")
+        );
+    }
+
+    #[test]
+    fn merkleize_pads_to_limit() {
+        assert_eq!(merkleize(&five_u64s(), 8), root(FIVE_U64S));
+        assert_eq!(merkleize(&five_u64s(), 5), root(FIVE_U64S));
+        assert_eq!(
+            mix_in_length(&root(FIVE_U64S), 5),
+            root("3107bc83bdc44ffcf91ea6d1b322f3e84656ef7641de848617d61696c077fe8d")
+        );
+    }
+
+    #[test]
+    fn packed_bytes() {
+        let bytes: Vec<u8> = (0..40).collect();
+        assert_eq!(
+            merkleize(&pack_bytes(&bytes), 2),
+            root("6032bb14a2dc38d055bb806a766a1082c6c56d2b2662c42eabce7c25a3cf157d")
+        );
+    }
+
+    #[test]
+    fn merkle_branch() {
+        let leaf = u64_chunk(5);
+        let branch = [
+            [0u8; 32],
+            root("f5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a92759fb4b"),
+            root("bfe3c665d2e561f13b30606c580cb703b2041287e212ade110f0bfd8563e21bb"),
+        ];
+        let root = root(FIVE_U64S);
+
+        assert!(is_valid_merkle_branch(&leaf, &branch, 3, 4, &root));
+        assert!(!is_valid_merkle_branch(&leaf, &branch, 3, 5, &root));
+        assert!(!is_valid_merkle_branch(&leaf, &branch, 4, 4, &root));
+        assert!(!is_valid_merkle_branch(&u64_chunk(6), &branch, 3, 4, &root));
+    }
+}
@@ -0,0 +1,90 @@
+//! Dispatch of block replay to the blockifier release able to execute it.
+//!
+//! A blockifier release only reproduces the execution of blocks of the
+//! Starknet versions it was written for. Each [Backend] covers a range of
+//! Starknet versions, and blocks not covered by any backend cannot be traced
+//! locally. Neither can blocks of versions the
+//! [version matrix](pathfinder_common::version_matrix) marks as not traceable.
+//! Callers fall back to fetching their traces from the feeder gateway
+//! instead.
+//!
+//! Adding a backend means vendoring the blockifier release as a renamed
+//! dependency (e.g. `blockifier_0_13_1 = { package = "blockifier", .. }`),
+//! adding a variant for it and listing it in [BACKENDS]. [trace](crate::trace)
+//! and [trace_range](crate::trace_range) then have to execute the blocks it
+//! covers through it.
+
+use pathfinder_common::{version_matrix, StarknetVersion};
+
+use crate::TransactionExecutionError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// The blockifier release of the workspace, also used for simulation,
+    /// estimation and calls.
+    Blockifier0_13_4,
+}
+
+/// The backends along with the first Starknet version they execute, ordered
+/// by version. Each backend covers the versions up to the next one.
+const BACKENDS: &[(StarknetVersion, Backend)] =
+    &[(StarknetVersion::new(0, 13, 1, 1), Backend::Blockifier0_13_4)];
+
+impl Backend {
+    /// The backend replaying blocks of `version`, [None] if blocks of this
+    /// version cannot be executed locally.
+    pub fn for_version(version: StarknetVersion) -> Option<Self> {
+        if !version_matrix::capabilities(version).traceable_locally {
+            return None;
+        }
+
+        BACKENDS
+            .iter()
+            .rev()
+            .find(|(since, _)| version >= *since)
+            .map(|(_, backend)| *backend)
+    }
+
+    /// The version of the blockifier release.
+    pub fn blockifier_version(&self) -> &'static str {
+        match self {
+            Self::Blockifier0_13_4 => "0.13.4",
+        }
+    }
+
+    /// Fails if blocks of `version` cannot be replayed by any backend.
+    ///
+    /// Only the blockifier release of the workspace is vendored so far, so
+    /// replaying a block needs no dispatch beyond this check.
+    pub(crate) fn ensure_replayable(
+        version: StarknetVersion,
+    ) -> Result<(), TransactionExecutionError> {
+        match Self::for_version(version) {
+            Some(Self::Blockifier0_13_4) => Ok(()),
+            None => Err(TransactionExecutionError::Custom(anyhow::anyhow!(
+                "Blocks of Starknet version {version} cannot be executed locally"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dispatch_by_version() {
+        assert_eq!(
+            Backend::for_version(StarknetVersion::new(0, 13, 1, 0)),
+            None
+        );
+        assert_eq!(
+            Backend::for_version(StarknetVersion::new(0, 13, 1, 1)),
+            Some(Backend::Blockifier0_13_4)
+        );
+        assert_eq!(
+            Backend::for_version(StarknetVersion::new(0, 13, 4, 0)),
+            Some(Backend::Blockifier0_13_4)
+        );
+    }
+}
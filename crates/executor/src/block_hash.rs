@@ -0,0 +1,114 @@
+//! The contract the `get_block_hash` syscall reads block hashes from.
+//!
+//! On Starknet the hash of block `n - 10` is written to the storage of contract
+//! `0x1`, keyed by block number, at the start of block `n`. Appchains may use a
+//! different contract, or only retain the latest hashes. For those the reads
+//! can also be served from the block headers instead.
+
+use std::num::NonZeroU64;
+use std::sync::OnceLock;
+
+use pathfinder_common::{contract_address, BlockNumber, ContractAddress};
+use pathfinder_crypto::Felt;
+
+/// The hash of a block becomes available this many blocks later.
+pub(crate) const STORED_BLOCK_HASH_BUFFER: u64 = 10;
+
+static CONTRACT: OnceLock<BlockHashContract> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockHashContract {
+    /// The contract mapping block numbers to block hashes in its storage.
+    pub address: ContractAddress,
+    /// The number of latest block hashes the contract retains, [None] if it
+    /// retains all of them. Only used when reading from headers.
+    pub retained: Option<NonZeroU64>,
+    /// Serve reads of the contract's storage from the block headers instead of
+    /// from contract storage.
+    pub from_headers: bool,
+}
+
+impl Default for BlockHashContract {
+    fn default() -> Self {
+        Self {
+            address: contract_address!("0x1"),
+            retained: None,
+            from_headers: false,
+        }
+    }
+}
+
+impl BlockHashContract {
+    /// Returns the block whose hash is stored at `key` of the contract while
+    /// executing `block`, [None] if no hash is available for it.
+    pub(crate) fn block_for_key(&self, block: BlockNumber, key: Felt) -> Option<BlockNumber> {
+        let bytes = key.as_be_bytes();
+        if bytes[..24].iter().any(|b| *b != 0) {
+            return None;
+        }
+        let requested = u64::from_be_bytes(bytes[24..].try_into().expect("Slice has length 8"));
+
+        let newest = block.get().checked_sub(STORED_BLOCK_HASH_BUFFER)?;
+        if requested > newest {
+            return None;
+        }
+        if self
+            .retained
+            .is_some_and(|retained| newest - requested >= retained.get())
+        {
+            return None;
+        }
+
+        BlockNumber::new(requested)
+    }
+}
+
+/// Configures the block hash contract of the chain. Must be called before
+/// executing anything, and at most once.
+pub fn configure(contract: BlockHashContract) -> anyhow::Result<()> {
+    CONTRACT
+        .set(contract)
+        .map_err(|_| anyhow::anyhow!("Block hash contract already configured"))
+}
+
+/// The configured block hash contract, or Starknet's.
+pub(crate) fn configured() -> BlockHashContract {
+    CONTRACT.get().copied().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_crypto::Felt;
+
+    use super::*;
+
+    #[test]
+    fn block_for_key() {
+        let contract = BlockHashContract::default();
+        let block = BlockNumber::new_or_panic(100);
+
+        assert_eq!(
+            contract.block_for_key(block, Felt::from_u64(90)),
+            Some(BlockNumber::new_or_panic(90))
+        );
+        assert_eq!(
+            contract.block_for_key(block, Felt::ZERO),
+            Some(BlockNumber::GENESIS)
+        );
+        assert_eq!(contract.block_for_key(block, Felt::from_u64(91)), None);
+        assert_eq!(
+            contract.block_for_key(BlockNumber::new_or_panic(5), Felt::ZERO),
+            None
+        );
+
+        let contract = BlockHashContract {
+            retained: NonZeroU64::new(5),
+            ..contract
+        };
+        assert_eq!(
+            contract.block_for_key(block, Felt::from_u64(86)),
+            Some(BlockNumber::new_or_panic(86))
+        );
+        assert_eq!(contract.block_for_key(block, Felt::from_u64(85)), None);
+    }
+}
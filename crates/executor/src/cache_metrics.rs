@@ -0,0 +1,58 @@
+//! Metrics of the caches used during execution.
+//!
+//! All caches report `executor_cache_hits_total` and
+//! `executor_cache_misses_total`. The in-memory caches also report
+//! `executor_cache_evictions_total` and their number of entries as
+//! `executor_cache_entries`, and caches weighing their entries report their
+//! estimated memory use as `executor_cache_size_bytes`. Each metric is
+//! labelled with the `cache` it belongs to, see [Cache].
+
+const METRIC_HITS: &str = "executor_cache_hits_total";
+const METRIC_MISSES: &str = "executor_cache_misses_total";
+const METRIC_EVICTIONS: &str = "executor_cache_evictions_total";
+const METRIC_ENTRIES: &str = "executor_cache_entries";
+const METRIC_SIZE: &str = "executor_cache_size_bytes";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Cache {
+    /// Block traces, see [crate::TraceCache].
+    Trace,
+    /// Compiled classes kept in memory by the state reader.
+    Class,
+    /// Compiled classes persisted in the database, see
+    /// [crate::persistent_class_cache].
+    PersistentClass,
+}
+
+impl Cache {
+    fn label(self) -> &'static str {
+        match self {
+            Cache::Trace => "trace",
+            Cache::Class => "class",
+            Cache::PersistentClass => "persistent_class",
+        }
+    }
+}
+
+pub(crate) fn hit(cache: Cache) {
+    metrics::increment_counter!(METRIC_HITS, "cache" => cache.label());
+}
+
+pub(crate) fn miss(cache: Cache) {
+    metrics::increment_counter!(METRIC_MISSES, "cache" => cache.label());
+}
+
+pub(crate) fn evicted(cache: Cache, count: usize) {
+    if count > 0 {
+        metrics::counter!(METRIC_EVICTIONS, count as u64, "cache" => cache.label());
+    }
+}
+
+/// Records the number of entries of a cache and, if its entries are weighed,
+/// their estimated size in bytes.
+pub(crate) fn size(cache: Cache, entries: usize, bytes: Option<usize>) {
+    metrics::gauge!(METRIC_ENTRIES, entries as f64, "cache" => cache.label());
+    if let Some(bytes) = bytes {
+        metrics::gauge!(METRIC_SIZE, bytes as f64, "cache" => cache.label());
+    }
+}
@@ -1,11 +1,13 @@
 use std::sync::Arc;
 
-use blockifier::context::TransactionContext;
+use blockifier::context::{BlockContext, TransactionContext};
+use blockifier::execution::call_info::CallInfo;
 use blockifier::execution::entry_point::{
     CallEntryPoint,
     EntryPointExecutionContext,
     SierraGasRevertTracker,
 };
+use blockifier::state::cached_state::CachedState;
 use blockifier::state::state_api::StateReader;
 use blockifier::transaction::objects::{DeprecatedTransactionInfo, TransactionInfo};
 use blockifier::versioned_constants::VersionedConstants;
@@ -16,6 +18,8 @@ use starknet_api::core::PatriciaKey;
 use super::error::CallError;
 use super::execution_state::ExecutionState;
 use super::felt::{IntoFelt, IntoStarkFelt};
+use super::os_input::{RecordingStateReader, StateReads};
+use super::policy::PolicyStateReader;
 
 pub fn call(
     execution_state: ExecutionState<'_>,
@@ -25,6 +29,80 @@ pub fn call(
 ) -> Result<Vec<CallResultValue>, CallError> {
     let (mut state, block_context) = execution_state.starknet_state()?;
 
+    execute(
+        &mut state,
+        block_context,
+        contract_address,
+        entry_point_selector,
+        calldata,
+    )
+}
+
+/// Like [call], but also returns the state the call read from the block.
+///
+/// Together with proofs of the reads this allows verifying the result against
+/// the block's state commitment.
+pub fn call_with_state_reads(
+    execution_state: ExecutionState<'_>,
+    contract_address: ContractAddress,
+    entry_point_selector: EntryPoint,
+    calldata: Vec<CallParam>,
+) -> Result<(Vec<CallResultValue>, StateReads), CallError> {
+    let (mut state, block_context) =
+        execution_state.starknet_state_with(RecordingStateReader::new)?;
+
+    let result = execute(
+        &mut state,
+        block_context,
+        contract_address,
+        entry_point_selector,
+        calldata,
+    )?;
+
+    Ok((result, state.state.into_inner().into_reads()))
+}
+
+fn execute<S: StateReader>(
+    state: &mut CachedState<PolicyStateReader<S>>,
+    block_context: BlockContext,
+    contract_address: ContractAddress,
+    entry_point_selector: EntryPoint,
+    calldata: Vec<CallParam>,
+) -> Result<Vec<CallResultValue>, CallError> {
+    let policy = state.state.policy();
+    policy.check_entry_point(contract_address, entry_point_selector)?;
+
+    let call_info = execute_entry_point(
+        state,
+        block_context,
+        contract_address,
+        entry_point_selector,
+        calldata,
+    );
+    if let Some(refused) = state.state.take_refused() {
+        return Err(refused.into());
+    }
+    let call_info = call_info?;
+    policy.check_call_info(&call_info)?;
+
+    let result = call_info
+        .execution
+        .retdata
+        .0
+        .iter()
+        .map(|f| CallResultValue(f.into_felt()))
+        .collect();
+
+    Ok(result)
+}
+
+fn execute_entry_point<S: StateReader>(
+    state: &mut CachedState<S>,
+    block_context: BlockContext,
+    contract_address: ContractAddress,
+    entry_point_selector: EntryPoint,
+    calldata: Vec<CallParam>,
+) -> Result<CallInfo, CallError> {
     let contract_address = starknet_api::core::ContractAddress(PatriciaKey::try_from(
         contract_address.0.into_starkfelt(),
     )?);
@@ -63,7 +141,7 @@ pub fn call(
 
     let mut remaining_gas = call_entry_point.initial_gas;
     let call_info = call_entry_point
-        .execute(&mut state, &mut context, &mut remaining_gas)
+        .execute(state, &mut context, &mut remaining_gas)
         .map_err(|e| {
             CallError::from_entry_point_execution_error(
                 e,
@@ -73,13 +151,5 @@ pub fn call(
             )
         })?;
 
-    let result = call_info
-        .execution
-        .retdata
-        .0
-        .iter()
-        .map(|f| CallResultValue(f.into_felt()))
-        .collect();
-
-    Ok(result)
+    Ok(call_info)
 }
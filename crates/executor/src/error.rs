@@ -8,12 +8,14 @@ use blockifier::state::errors::StateError;
 use blockifier::transaction::errors::TransactionExecutionError as BlockifierTransactionExecutionError;
 
 use crate::error_stack::ErrorStack;
+use crate::policy::ExecutionRefused;
 
 #[derive(Debug)]
 pub enum CallError {
     ContractNotFound,
     InvalidMessageSelector,
     ContractError(anyhow::Error, ErrorStack),
+    ExecutionRefused(ExecutionRefused),
     Internal(anyhow::Error),
     Custom(anyhow::Error),
 }
@@ -85,6 +87,12 @@ impl From<StateError> for CallError {
     }
 }
 
+impl From<ExecutionRefused> for CallError {
+    fn from(value: ExecutionRefused) -> Self {
+        Self::ExecutionRefused(value)
+    }
+}
+
 impl From<starknet_api::StarknetApiError> for CallError {
     fn from(value: starknet_api::StarknetApiError) -> Self {
         Self::Custom(value.into())
@@ -104,6 +112,7 @@ pub enum TransactionExecutionError {
         error: String,
         error_stack: ErrorStack,
     },
+    ExecutionRefused(ExecutionRefused),
     Internal(anyhow::Error),
     Custom(anyhow::Error),
 }
@@ -117,6 +126,12 @@ impl From<StateError> for TransactionExecutionError {
     }
 }
 
+impl From<ExecutionRefused> for TransactionExecutionError {
+    fn from(value: ExecutionRefused) -> Self {
+        Self::ExecutionRefused(value)
+    }
+}
+
 impl From<starknet_api::StarknetApiError> for TransactionExecutionError {
     fn from(value: starknet_api::StarknetApiError) -> Self {
         Self::Custom(value.into())
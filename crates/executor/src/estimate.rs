@@ -3,7 +3,7 @@ use blockifier::transaction::transactions::ExecutableTransaction;
 use starknet_api::transaction::fields::GasVectorComputationMode;
 
 use super::error::TransactionExecutionError;
-use super::execution_state::ExecutionState;
+use super::execution_state::{ExecutionState, FeeToken};
 use super::types::FeeEstimate;
 
 pub fn estimate(
@@ -13,6 +13,7 @@ pub fn estimate(
     let block_number = execution_state.header.number;
 
     let (mut state, block_context) = execution_state.starknet_state()?;
+    let policy = state.state.policy();
 
     let mut fees = Vec::with_capacity(transactions.len());
     for (transaction_idx, transaction) in transactions.into_iter().enumerate() {
@@ -33,9 +34,14 @@ pub fn estimate(
             blockifier::transaction::objects::TransactionExecutionInfo,
             blockifier::transaction::errors::TransactionExecutionError,
         > = transaction.execute(&mut state, &block_context);
+        if let Some(refused) = state.state.take_refused() {
+            return Err(refused.into());
+        }
 
         match tx_info {
             Ok(tx_info) => {
+                policy.check_transaction(&tx_info)?;
+
                 if let Some(revert_error) = tx_info.revert_error {
                     let revert_string = revert_error.to_string();
                     tracing::debug!(revert_error=%revert_string, "Transaction reverted");
@@ -63,3 +69,28 @@ pub fn estimate(
     }
     Ok(fees)
 }
+
+/// Estimates the fees of the transactions in each of the execution state's
+/// [fee tokens](ExecutionState::fee_tokens). A transaction is only estimated in
+/// the tokens matching the unit it pays fees in, i.e. STRK and other `fri`
+/// tokens for V3 transactions.
+pub fn estimate_per_token(
+    execution_state: ExecutionState<'_>,
+    transactions: Vec<Transaction>,
+) -> Result<Vec<Vec<(FeeToken, FeeEstimate)>>, TransactionExecutionError> {
+    let tokens: Vec<FeeToken> = execution_state.fee_tokens().iter().collect();
+
+    let mut output = vec![Vec::new(); transactions.len()];
+    for token in tokens {
+        let state = execution_state.clone().charging_fees_in(token)?;
+        let estimates = estimate(state, transactions.clone())?;
+
+        for (per_token, fee_estimate) in output.iter_mut().zip(estimates) {
+            if fee_estimate.unit == token.unit {
+                per_token.push((token, fee_estimate));
+            }
+        }
+    }
+
+    Ok(output)
+}
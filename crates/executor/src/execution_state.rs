@@ -5,6 +5,7 @@ use blockifier::blockifier::block::pre_process_block;
 use blockifier::bouncer::BouncerConfig;
 use blockifier::context::{BlockContext, ChainInfo};
 use blockifier::state::cached_state::CachedState;
+use blockifier::state::state_api::{State, StateReader};
 use blockifier::versioned_constants::VersionedConstants;
 use pathfinder_common::{
     BlockHeader,
@@ -16,8 +17,11 @@ use pathfinder_common::{
 use starknet_api::block::{BlockHashAndNumber, BlockInfo, GasPrice, NonzeroGasPrice};
 use starknet_api::core::PatriciaKey;
 
+use super::block_hash::{self, BlockHashContract, STORED_BLOCK_HASH_BUFFER};
 use super::pending::PendingStateReader;
+use super::policy::{ExecutionPolicy, PolicyStateReader};
 use super::state_reader::PathfinderStateReader;
+use crate::types::PriceUnit;
 use crate::IntoStarkFelt;
 
 mod versioned_constants {
@@ -46,6 +50,9 @@ mod versioned_constants {
     const BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_3: &[u8] =
         include_bytes!("../resources/versioned_constants_0_13_3.json");
 
+    const BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_4: &[u8] =
+        include_bytes!("../resources/versioned_constants_0_13_4.json");
+
     const STARKNET_VERSION_0_13_1: StarknetVersion = StarknetVersion::new(0, 13, 1, 0);
 
     const STARKNET_VERSION_0_13_1_1: StarknetVersion = StarknetVersion::new(0, 13, 1, 1);
@@ -111,8 +118,164 @@ mod versioned_constants {
                 .unwrap_or_else(|| Cow::Borrowed(VersionedConstants::latest_constants()))
         }
     }
+
+    /// The official constants of `version` as JSON, ignoring custom
+    /// constants.
+    pub(super) fn json_for_version(version: &StarknetVersion) -> &'static [u8] {
+        if version < &STARKNET_VERSION_0_13_1 {
+            BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_0
+        } else if version < &STARKNET_VERSION_0_13_1_1 {
+            BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_1
+        } else if version < &STARKNET_VERSION_0_13_2 {
+            BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_1_1
+        } else if version < &STARKNET_VERSION_0_13_2_1 {
+            BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_2
+        } else if version < &STARKNET_VERSION_0_13_3 {
+            BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_2_1
+        } else if version < &STARKNET_VERSION_0_13_4 {
+            BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_3
+        } else {
+            BLOCKIFIER_VERSIONED_CONSTANTS_JSON_0_13_4
+        }
+    }
+
+    /// Names the constants [for_version] picks for `version`.
+    pub(super) fn name_for_version(version: &StarknetVersion, custom: bool) -> &'static str {
+        if version < &STARKNET_VERSION_0_13_1 {
+            "0.13.0"
+        } else if version < &STARKNET_VERSION_0_13_1_1 {
+            "0.13.1"
+        } else if version < &STARKNET_VERSION_0_13_2 {
+            "0.13.1.1"
+        } else if version < &STARKNET_VERSION_0_13_2_1 {
+            "0.13.2"
+        } else if version < &STARKNET_VERSION_0_13_3 {
+            "0.13.2.1"
+        } else if version < &STARKNET_VERSION_0_13_4 {
+            "0.13.3"
+        } else if custom {
+            "custom"
+        } else {
+            "latest"
+        }
+    }
+}
+
+/// Names the versioned constants used to execute blocks of the given Starknet
+/// version, `custom` being whether custom constants are configured.
+pub fn versioned_constants_name(
+    version: &pathfinder_common::StarknetVersion,
+    custom: bool,
+) -> &'static str {
+    versioned_constants::name_for_version(version, custom)
+}
+
+/// The L1 gas charged per unit of a VM resource, as a fraction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VmResourceFeeCost {
+    pub numerator: u64,
+    pub denominator: u64,
+}
+
+/// The L1 gas charged per VM resource by the official versioned constants of
+/// `version`, keyed by resource name as in the versioned constants, i.e.
+/// `n_steps` or the builtin names such as `pedersen_builtin`.
+///
+/// Custom versioned constants are ignored since the fees of blocks produced
+/// by the sequencer are always based on the official ones.
+pub fn vm_resource_fee_costs(
+    version: &pathfinder_common::StarknetVersion,
+) -> anyhow::Result<std::collections::BTreeMap<String, VmResourceFeeCost>> {
+    let constants: serde_json::Value =
+        serde_json::from_slice(versioned_constants::json_for_version(version))
+            .context("Parsing versioned constants")?;
+    let costs = &constants["vm_resource_fee_cost"];
+
+    let parse = |cost: &serde_json::Value| -> anyhow::Result<VmResourceFeeCost> {
+        let [numerator, denominator] = cost
+            .as_array()
+            .map(Vec::as_slice)
+            .context("Resource cost is not an array")?
+        else {
+            anyhow::bail!("Resource cost is not a fraction");
+        };
+        let cost = VmResourceFeeCost {
+            numerator: numerator.as_u64().context("Parsing numerator")?,
+            denominator: denominator.as_u64().context("Parsing denominator")?,
+        };
+        anyhow::ensure!(cost.denominator != 0, "Zero denominator");
+        Ok(cost)
+    };
+
+    let mut result = std::collections::BTreeMap::new();
+    result.insert(
+        "n_steps".to_owned(),
+        parse(&costs["n_steps"]).context("Parsing n_steps cost")?,
+    );
+    for (name, cost) in costs["builtins"]
+        .as_object()
+        .context("Builtin costs missing")?
+    {
+        let cost = parse(cost).with_context(|| format!("Parsing {name} cost"))?;
+        result.insert(name.clone(), cost);
+    }
+
+    Ok(result)
+}
+
+/// A token transaction fees can be paid in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeToken {
+    pub address: ContractAddress,
+    /// Fees paid in this token are priced using the block's gas prices in this
+    /// unit.
+    pub unit: PriceUnit,
+}
+
+/// The tokens transaction fees can be paid in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeTokens {
+    /// The chain's ETH token, fees of transactions paying in wei are charged in
+    /// it.
+    pub eth: ContractAddress,
+    /// The chain's STRK token, fees of transactions paying in fri are charged
+    /// in it.
+    pub strk: ContractAddress,
+    /// Tokens accepted besides the chain's own, e.g. on appchains.
+    pub additional: Vec<FeeToken>,
+}
+
+impl FeeTokens {
+    pub fn new(eth: ContractAddress, strk: ContractAddress) -> Self {
+        Self {
+            eth,
+            strk,
+            additional: Vec::new(),
+        }
+    }
+
+    pub fn with_additional(self, additional: Vec<FeeToken>) -> Self {
+        Self { additional, ..self }
+    }
+
+    /// All tokens, starting with the chain's ETH and STRK tokens.
+    pub fn iter(&self) -> impl Iterator<Item = FeeToken> + '_ {
+        [
+            FeeToken {
+                address: self.eth,
+                unit: PriceUnit::Wei,
+            },
+            FeeToken {
+                address: self.strk,
+                unit: PriceUnit::Fri,
+            },
+        ]
+        .into_iter()
+        .chain(self.additional.iter().copied())
+    }
 }
 
+#[derive(Clone)]
 pub struct ExecutionState<'tx> {
     transaction: &'tx pathfinder_storage::Transaction<'tx>,
     pub chain_id: ChainId,
@@ -121,17 +284,30 @@ pub struct ExecutionState<'tx> {
     pending_state: Option<Arc<StateUpdate>>,
     allow_use_kzg_data: bool,
     custom_versioned_constants: Option<VersionedConstants>,
-    eth_fee_address: ContractAddress,
-    strk_fee_address: ContractAddress,
+    fee_tokens: FeeTokens,
+    /// Charges fees of transactions paying in its unit in this token instead
+    /// of the chain's.
+    charged_token: Option<FeeToken>,
+    policy: Arc<ExecutionPolicy>,
+    block_hash_contract: BlockHashContract,
 }
 
 impl<'tx> ExecutionState<'tx> {
     pub(super) fn starknet_state(
         self,
     ) -> anyhow::Result<(
-        CachedState<PendingStateReader<PathfinderStateReader<'tx>>>,
+        CachedState<PolicyStateReader<PendingStateReader<PathfinderStateReader<'tx>>>>,
         BlockContext,
     )> {
+        self.starknet_state_with(|reader| reader)
+    }
+
+    /// Like [Self::starknet_state], but with the state reader wrapped by
+    /// `wrap`.
+    pub(super) fn starknet_state_with<R: StateReader>(
+        self,
+        wrap: impl FnOnce(PendingStateReader<PathfinderStateReader<'tx>>) -> R,
+    ) -> anyhow::Result<(CachedState<PolicyStateReader<R>>, BlockContext)> {
         let block_number = if self.execute_on_parent_state {
             self.header.number.parent()
         } else {
@@ -142,18 +318,24 @@ impl<'tx> ExecutionState<'tx> {
             self.transaction,
             block_number,
             self.pending_state.is_some(),
-        );
+        )
+        .with_block_hash_contract(self.block_hash_contract, self.header.number);
         let pending_state_reader = PendingStateReader::new(raw_reader, self.pending_state.clone());
-        let mut cached_state = CachedState::new(pending_state_reader);
+        let mut cached_state = CachedState::new(PolicyStateReader::new(
+            wrap(pending_state_reader),
+            self.policy.clone(),
+        ));
 
         let chain_info = self.chain_info()?;
         let block_info = self.block_info()?;
 
         // Perform system contract updates if we are executing ontop of a parent block.
         // Currently this is only the block hash from 10 blocks ago.
-        let old_block_number_and_hash = if self.header.number.get() >= 10 {
+        let old_block_number_and_hash = if self.header.number.get() >= STORED_BLOCK_HASH_BUFFER {
             let block_number_whose_hash_becomes_available =
-                pathfinder_common::BlockNumber::new_or_panic(self.header.number.get() - 10);
+                pathfinder_common::BlockNumber::new_or_panic(
+                    self.header.number.get() - STORED_BLOCK_HASH_BUFFER,
+                );
             let block_hash = self
                 .transaction
                 .block_hash(block_number_whose_hash_becomes_available.into())?
@@ -183,6 +365,21 @@ impl<'tx> ExecutionState<'tx> {
             &versioned_constants.os_constants,
         )?;
 
+        // Blockifier only writes the hash to Starknet's block hash contract.
+        if let Some(BlockHashAndNumber { number, hash }) = old_block_number_and_hash {
+            if self.block_hash_contract.address != BlockHashContract::default().address {
+                let address = starknet_api::core::ContractAddress(
+                    PatriciaKey::try_from(self.block_hash_contract.address.0.into_starkfelt())
+                        .context("Block hash contract address overflow")?,
+                );
+                let key = starknet_api::state::StorageKey(
+                    PatriciaKey::try_from(starknet_types_core::felt::Felt::from(number.0))
+                        .context("Block number overflow")?,
+                );
+                cached_state.set_storage_at(address, key, hash.0)?;
+            }
+        }
+
         let block_context = BlockContext::new(
             block_info,
             chain_info,
@@ -194,12 +391,26 @@ impl<'tx> ExecutionState<'tx> {
     }
 
     fn chain_info(&self) -> anyhow::Result<ChainInfo> {
+        let (mut eth_fee_address, mut strk_fee_address) =
+            (self.fee_tokens.eth, self.fee_tokens.strk);
+        match self.charged_token {
+            Some(FeeToken {
+                address,
+                unit: PriceUnit::Wei,
+            }) => eth_fee_address = address,
+            Some(FeeToken {
+                address,
+                unit: PriceUnit::Fri,
+            }) => strk_fee_address = address,
+            None => {}
+        }
+
         let eth_fee_token_address = starknet_api::core::ContractAddress(
-            PatriciaKey::try_from(self.eth_fee_address.0.into_starkfelt())
+            PatriciaKey::try_from(eth_fee_address.0.into_starkfelt())
                 .expect("ETH fee token address overflow"),
         );
         let strk_fee_token_address = starknet_api::core::ContractAddress(
-            PatriciaKey::try_from(self.strk_fee_address.0.into_starkfelt())
+            PatriciaKey::try_from(strk_fee_address.0.into_starkfelt())
                 .expect("STRK fee token address overflow"),
         );
 
@@ -307,8 +518,7 @@ impl<'tx> ExecutionState<'tx> {
         header: BlockHeader,
         pending_state: Option<Arc<StateUpdate>>,
         custom_versioned_constants: Option<VersionedConstants>,
-        eth_fee_address: ContractAddress,
-        strk_fee_address: ContractAddress,
+        fee_tokens: FeeTokens,
     ) -> Self {
         Self {
             transaction,
@@ -318,8 +528,10 @@ impl<'tx> ExecutionState<'tx> {
             execute_on_parent_state: true,
             allow_use_kzg_data: true,
             custom_versioned_constants,
-            eth_fee_address,
-            strk_fee_address,
+            fee_tokens,
+            charged_token: None,
+            policy: Default::default(),
+            block_hash_contract: block_hash::configured(),
         }
     }
 
@@ -331,8 +543,7 @@ impl<'tx> ExecutionState<'tx> {
         pending_state: Option<Arc<StateUpdate>>,
         l1_blob_data_availability: L1BlobDataAvailability,
         custom_versioned_constants: Option<VersionedConstants>,
-        eth_fee_address: ContractAddress,
-        strk_fee_address: ContractAddress,
+        fee_tokens: FeeTokens,
     ) -> Self {
         Self {
             transaction,
@@ -342,10 +553,44 @@ impl<'tx> ExecutionState<'tx> {
             execute_on_parent_state: false,
             allow_use_kzg_data: l1_blob_data_availability == L1BlobDataAvailability::Enabled,
             custom_versioned_constants,
-            eth_fee_address,
-            strk_fee_address,
+            fee_tokens,
+            charged_token: None,
+            policy: Default::default(),
+            block_hash_contract: block_hash::configured(),
         }
     }
+
+    pub fn fee_tokens(&self) -> &FeeTokens {
+        &self.fee_tokens
+    }
+
+    /// Charges fees of transactions paying in `token.unit` in `token`, which
+    /// must be one of the [fee tokens](Self::fee_tokens), instead of the
+    /// chain's ETH or STRK token.
+    ///
+    /// This affects the fee balance checks and fee transfers.
+    pub(crate) fn charging_fees_in(mut self, token: FeeToken) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            self.fee_tokens.iter().any(|known| known == token),
+            "Unknown fee token {}",
+            token.address
+        );
+        self.charged_token = Some(token);
+        Ok(self)
+    }
+
+    /// Refuses to execute the contracts and entry points blocked by `policy`.
+    pub fn with_execution_policy(mut self, policy: Arc<ExecutionPolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Uses `contract` for the `get_block_hash` syscall instead of the
+    /// configured block hash contract.
+    pub fn with_block_hash_contract(mut self, contract: BlockHashContract) -> Self {
+        self.block_hash_contract = contract;
+        self
+    }
 }
 
 #[derive(Copy, Clone, PartialEq)]
@@ -353,3 +598,38 @@ pub enum L1BlobDataAvailability {
     Disabled,
     Enabled,
 }
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::StarknetVersion;
+
+    use super::*;
+
+    #[test]
+    fn vm_resource_fee_costs_by_version() {
+        let costs = vm_resource_fee_costs(&StarknetVersion::new(0, 12, 3, 0)).unwrap();
+        assert_eq!(
+            costs["n_steps"],
+            VmResourceFeeCost {
+                numerator: 5,
+                denominator: 1000
+            }
+        );
+
+        let costs = vm_resource_fee_costs(&StarknetVersion::new(0, 13, 4, 0)).unwrap();
+        assert_eq!(
+            costs["n_steps"],
+            VmResourceFeeCost {
+                numerator: 25,
+                denominator: 10000
+            }
+        );
+        assert_eq!(
+            costs["pedersen_builtin"],
+            VmResourceFeeCost {
+                numerator: 8,
+                denominator: 100
+            }
+        );
+    }
+}
@@ -1,3 +1,6 @@
+pub(crate) mod backend;
+pub(crate) mod block_hash;
+pub(crate) mod cache_metrics;
 pub(crate) mod call;
 pub(crate) mod class;
 pub(crate) mod error;
@@ -6,26 +9,46 @@ pub(crate) mod estimate;
 pub(crate) mod execution_state;
 pub(crate) mod felt;
 pub(crate) mod lru_cache;
+pub(crate) mod os_input;
 pub(crate) mod pending;
+pub(crate) mod persistent_class_cache;
+pub(crate) mod policy;
+#[cfg(test)]
+mod property_tests;
 pub(crate) mod simulate;
 pub(crate) mod state_reader;
+pub(crate) mod trace_cache;
 pub(crate) mod transaction;
 pub mod types;
 
 // re-export blockifier transaction type since it's exposed on our API
+pub use backend::Backend;
+pub use block_hash::{configure as configure_block_hash_contract, BlockHashContract};
 pub use blockifier::transaction::account_transaction::{
     AccountTransaction,
     ExecutionFlags as AccountTransactionExecutionFlags,
 };
 pub use blockifier::transaction::transaction_execution::Transaction;
 pub use blockifier::versioned_constants::VersionedConstants;
-pub use call::call;
+pub use call::{call, call_with_state_reads};
 pub use class::{parse_casm_definition, parse_deprecated_class_definition};
 pub use error::{CallError, TransactionExecutionError};
 pub use error_stack::{CallFrame, ErrorStack, Frame};
-pub use estimate::estimate;
-pub use execution_state::{ExecutionState, L1BlobDataAvailability};
+pub use estimate::{estimate, estimate_per_token};
+pub use execution_state::{
+    versioned_constants_name,
+    vm_resource_fee_costs,
+    ExecutionState,
+    FeeToken,
+    FeeTokens,
+    L1BlobDataAvailability,
+    VmResourceFeeCost,
+};
 pub use felt::{IntoFelt, IntoStarkFelt};
-pub use simulate::{simulate, trace, TraceCache};
+pub use os_input::{os_input, StateReads};
+pub use persistent_class_cache::enable as enable_persistent_class_cache;
+pub use policy::{ExecutionPolicy, ExecutionRefused};
+pub use simulate::{simulate, trace, trace_range, TraceCache};
 pub use starknet_api::contract_class::ClassInfo;
+pub use trace_cache::{EvictionPolicy, TraceCacheConfig};
 pub use transaction::transaction_hash;
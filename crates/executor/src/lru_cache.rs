@@ -5,6 +5,8 @@ use cached::{Cached, SizedCache};
 use pathfinder_common::BlockNumber;
 use starknet_api::core::ClassHash as StarknetClassHash;
 
+use crate::cache_metrics::{self, Cache};
+
 pub static GLOBAL_CACHE: LazyLock<LruContractCache> = LazyLock::new(LruContractCache::new);
 
 #[derive(Clone)]
@@ -36,12 +38,19 @@ impl LruContractCache {
         contract_class: RunnableCompiledClass,
         block_number: BlockNumber,
     ) {
-        self.locked_cache().cache_set(
+        let mut cache = self.locked_cache();
+        let entries = cache.cache_size();
+        let replaced = cache.cache_set(
             class_hash,
             Entry {
                 definition: contract_class.clone(),
                 height: block_number,
             },
         );
+        // A new entry which did not grow the cache displaced another one.
+        if replaced.is_none() && cache.cache_size() == entries {
+            cache_metrics::evicted(Cache::Class, 1);
+        }
+        cache_metrics::size(Cache::Class, cache.cache_size(), None);
     }
 }
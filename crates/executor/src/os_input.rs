@@ -0,0 +1,145 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+
+use blockifier::execution::contract_class::RunnableCompiledClass;
+use blockifier::state::cached_state::CachedState;
+use blockifier::state::state_api::{StateReader, StateResult};
+use blockifier::transaction::transaction_execution::Transaction;
+use blockifier::transaction::transactions::ExecutableTransaction;
+use pathfinder_common::{
+    CasmHash,
+    ClassHash,
+    ContractAddress,
+    ContractNonce,
+    StorageAddress,
+    StorageValue,
+};
+
+use super::error::TransactionExecutionError;
+use super::execution_state::ExecutionState;
+use crate::IntoFelt;
+
+/// The state read while executing a block, as it was before the block.
+///
+/// Together with the block's transactions this is what the Starknet OS needs to
+/// re-run the block, e.g. to prove it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateReads {
+    pub storage: BTreeMap<(ContractAddress, StorageAddress), StorageValue>,
+    pub nonces: BTreeMap<ContractAddress, ContractNonce>,
+    pub class_hashes: BTreeMap<ContractAddress, ClassHash>,
+    pub compiled_class_hashes: BTreeMap<ClassHash, CasmHash>,
+    /// Classes whose code was executed.
+    pub executed_classes: BTreeSet<ClassHash>,
+}
+
+/// Re-executes `transactions` on top of `execution_state` and returns the state
+/// they read.
+///
+/// Only reads which reach the committed state are recorded. Values written by
+/// an earlier transaction of the block are not part of the OS input.
+pub fn os_input(
+    execution_state: ExecutionState<'_>,
+    transactions: Vec<Transaction>,
+) -> Result<StateReads, TransactionExecutionError> {
+    let block_number = execution_state.header.number;
+    let (mut state, block_context) =
+        execution_state.starknet_state_with(RecordingStateReader::new)?;
+
+    for (transaction_idx, transaction) in transactions.into_iter().enumerate() {
+        let _span = tracing::debug_span!("os_input", transaction_hash=%super::transaction::transaction_hash(&transaction), %block_number, %transaction_idx).entered();
+
+        let mut tx_state = CachedState::<_>::create_transactional(&mut state);
+        transaction
+            .execute(&mut tx_state, &block_context)
+            .map_err(|error| TransactionExecutionError::new(transaction_idx, error))?;
+        tx_state.commit();
+    }
+
+    Ok(state.state.into_inner().into_reads())
+}
+
+/// Records every successful read passed through to the wrapped reader.
+pub(super) struct RecordingStateReader<S: StateReader> {
+    state: S,
+    reads: RefCell<StateReads>,
+}
+
+impl<S: StateReader> RecordingStateReader<S> {
+    pub(super) fn new(state: S) -> Self {
+        Self {
+            state,
+            reads: Default::default(),
+        }
+    }
+
+    pub(super) fn into_reads(self) -> StateReads {
+        self.reads.into_inner()
+    }
+}
+
+impl<S: StateReader> StateReader for RecordingStateReader<S> {
+    fn get_storage_at(
+        &self,
+        contract_address: starknet_api::core::ContractAddress,
+        key: starknet_api::state::StorageKey,
+    ) -> StateResult<starknet_types_core::felt::Felt> {
+        let value = self.state.get_storage_at(contract_address, key)?;
+        self.reads.borrow_mut().storage.insert(
+            (
+                ContractAddress::new_or_panic(contract_address.0.key().into_felt()),
+                StorageAddress::new_or_panic(key.0.key().into_felt()),
+            ),
+            StorageValue(value.into_felt()),
+        );
+        Ok(value)
+    }
+
+    fn get_nonce_at(
+        &self,
+        contract_address: starknet_api::core::ContractAddress,
+    ) -> StateResult<starknet_api::core::Nonce> {
+        let nonce = self.state.get_nonce_at(contract_address)?;
+        self.reads.borrow_mut().nonces.insert(
+            ContractAddress::new_or_panic(contract_address.0.key().into_felt()),
+            ContractNonce(nonce.0.into_felt()),
+        );
+        Ok(nonce)
+    }
+
+    fn get_class_hash_at(
+        &self,
+        contract_address: starknet_api::core::ContractAddress,
+    ) -> StateResult<starknet_api::core::ClassHash> {
+        let class_hash = self.state.get_class_hash_at(contract_address)?;
+        self.reads.borrow_mut().class_hashes.insert(
+            ContractAddress::new_or_panic(contract_address.0.key().into_felt()),
+            ClassHash(class_hash.0.into_felt()),
+        );
+        Ok(class_hash)
+    }
+
+    fn get_compiled_class(
+        &self,
+        class_hash: starknet_api::core::ClassHash,
+    ) -> StateResult<RunnableCompiledClass> {
+        let class = self.state.get_compiled_class(class_hash)?;
+        self.reads
+            .borrow_mut()
+            .executed_classes
+            .insert(ClassHash(class_hash.0.into_felt()));
+        Ok(class)
+    }
+
+    fn get_compiled_class_hash(
+        &self,
+        class_hash: starknet_api::core::ClassHash,
+    ) -> StateResult<starknet_api::core::CompiledClassHash> {
+        let compiled_class_hash = self.state.get_compiled_class_hash(class_hash)?;
+        self.reads.borrow_mut().compiled_class_hashes.insert(
+            ClassHash(class_hash.0.into_felt()),
+            CasmHash(compiled_class_hash.0.into_felt()),
+        );
+        Ok(compiled_class_hash)
+    }
+}
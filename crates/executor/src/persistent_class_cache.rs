@@ -0,0 +1,222 @@
+//! Class definitions prepared for execution, persisted in the database.
+//!
+//! Parsing class definitions is a large part of the latency of the first calls
+//! and traces involving a class after a restart. Once a class has been parsed,
+//! a copy of its definition without the parts blockifier does not need (the
+//! ABI, debug information and Python hints) is written to the database in the
+//! background. Later processes load the copy instead, which skips the
+//! decompression and most of the parsing.
+
+use std::sync::mpsc::{Receiver, SyncSender};
+use std::sync::OnceLock;
+
+use anyhow::Context;
+use blockifier::execution::contract_class::{
+    CompiledClassV0,
+    CompiledClassV1,
+    RunnableCompiledClass,
+};
+use pathfinder_common::{BlockNumber, ClassHash};
+use pathfinder_storage::Storage;
+use starknet_api::contract_class::SierraVersion;
+
+use crate::cache_metrics::{self, Cache};
+
+/// Identifies the format of the cached definitions. Blockifier is pinned for
+/// each release, so the crate version determines the blockifier version the
+/// definitions were prepared for. The suffix is bumped when the preparation
+/// changes.
+const VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "/1");
+
+/// Classes waiting to be written. Further classes are dropped while the queue
+/// is full.
+const QUEUE_SIZE: usize = 64;
+
+static WRITER: OnceLock<SyncSender<Entry>> = OnceLock::new();
+
+struct Entry {
+    class_hash: ClassHash,
+    /// Set for Sierra classes, whose definition is the CASM.
+    sierra_version: Option<SierraVersion>,
+    definition: String,
+}
+
+/// Enables loading classes from and writing them to the database.
+///
+/// Classes cached by other versions are removed. `storage` must not be read
+/// only, it is used by a background thread writing the classes.
+pub fn enable(storage: Storage) -> anyhow::Result<()> {
+    let mut db = storage
+        .connection()
+        .context("Creating database connection")?;
+    let tx = db.transaction().context("Creating database transaction")?;
+    let purged = tx.purge_compiled_class_cache(VERSION)?;
+    tx.commit().context("Committing database transaction")?;
+    if purged > 0 {
+        tracing::info!(%purged, "Removed classes cached by a different version");
+    }
+
+    let (sender, receiver) = std::sync::mpsc::sync_channel(QUEUE_SIZE);
+    WRITER
+        .set(sender)
+        .map_err(|_| anyhow::anyhow!("Persistent class cache is already enabled"))?;
+    std::thread::Builder::new()
+        .name("class-cache".to_owned())
+        .spawn(move || write_entries(storage, receiver))
+        .context("Spawning class cache writer")?;
+
+    Ok(())
+}
+
+fn write_entries(storage: Storage, receiver: Receiver<Entry>) {
+    for entry in receiver {
+        let class_hash = entry.class_hash;
+        if let Err(error) = write(&storage, entry) {
+            tracing::debug!(%class_hash, %error, "Failed to persist class");
+        }
+    }
+}
+
+fn write(storage: &Storage, entry: Entry) -> anyhow::Result<()> {
+    let definition = match entry.sierra_version {
+        Some(_) => strip_casm_definition(&entry.definition),
+        None => strip_cairo_definition(&entry.definition),
+    }
+    .context("Preparing definition")?;
+    let sierra_version = entry
+        .sierra_version
+        .map(|version| serde_json::to_string(&version))
+        .transpose()
+        .context("Serializing Sierra version")?;
+
+    let mut db = storage.connection()?;
+    let tx = db.transaction()?;
+    tx.insert_cached_compiled_class(
+        entry.class_hash,
+        VERSION,
+        sierra_version.as_deref(),
+        &definition,
+    )?;
+    tx.commit()
+}
+
+/// Queues a class which had to be parsed from its original definition to be
+/// written to the database. Does nothing unless the cache is enabled.
+pub(crate) fn store(
+    class_hash: ClassHash,
+    sierra_version: Option<SierraVersion>,
+    definition: String,
+) {
+    let Some(writer) = WRITER.get() else {
+        return;
+    };
+
+    // Dropping the class only means that it is loaded from the original definition
+    // again.
+    let _ = writer.try_send(Entry {
+        class_hash,
+        sierra_version,
+        definition,
+    });
+}
+
+/// Loads a class from the database, along with the block it was declared in.
+///
+/// Returns `None` if the cache is not enabled, the class is not cached or the
+/// cached copy cannot be used, in which case the original definition has to be
+/// parsed.
+pub(crate) fn load(
+    transaction: &pathfinder_storage::Transaction<'_>,
+    class_hash: ClassHash,
+) -> Option<(Option<BlockNumber>, RunnableCompiledClass)> {
+    WRITER.get()?;
+
+    let class = match try_load(transaction, class_hash) {
+        Ok(class) => class,
+        Err(error) => {
+            tracing::debug!(%class_hash, %error, "Failed to load persisted class");
+            None
+        }
+    };
+    match class {
+        Some(_) => cache_metrics::hit(Cache::PersistentClass),
+        None => cache_metrics::miss(Cache::PersistentClass),
+    }
+
+    class
+}
+
+fn try_load(
+    transaction: &pathfinder_storage::Transaction<'_>,
+    class_hash: ClassHash,
+) -> anyhow::Result<Option<(Option<BlockNumber>, RunnableCompiledClass)>> {
+    let Some(cached) = transaction.cached_compiled_class(class_hash, VERSION)? else {
+        return Ok(None);
+    };
+
+    let definition = String::from_utf8(cached.definition).context("Definition is not UTF-8")?;
+    let class = match cached.sierra_version {
+        Some(sierra_version) => {
+            let sierra_version: SierraVersion =
+                serde_json::from_str(&sierra_version).context("Parsing Sierra version")?;
+            RunnableCompiledClass::V1(
+                CompiledClassV1::try_from_json_string(&definition, sierra_version)
+                    .context("Parsing CASM definition")?,
+            )
+        }
+        None => RunnableCompiledClass::V0(
+            CompiledClassV0::try_from_json_string(&definition)
+                .context("Parsing Cairo definition")?,
+        ),
+    };
+
+    Ok(Some((cached.block_number, class)))
+}
+
+/// Removes the ABI and debug information, which are not needed for execution,
+/// from a Cairo class definition.
+fn strip_cairo_definition(definition: &str) -> anyhow::Result<Vec<u8>> {
+    let mut class: serde_json::Value = serde_json::from_str(definition)?;
+    let class_object = class
+        .as_object_mut()
+        .context("Class definition is not an object")?;
+    class_object.remove("abi");
+    if let Some(program) = class_object
+        .get_mut("program")
+        .and_then(serde_json::Value::as_object_mut)
+    {
+        program.insert("debug_info".to_owned(), serde_json::Value::Null);
+    }
+
+    Ok(serde_json::to_vec(&class)?)
+}
+
+/// Removes the Python hints, which are not needed for execution, from a CASM
+/// definition.
+fn strip_casm_definition(definition: &str) -> anyhow::Result<Vec<u8>> {
+    let mut class: serde_json::Value = serde_json::from_str(definition)?;
+    class
+        .as_object_mut()
+        .context("CASM definition is not an object")?
+        .remove("pythonic_hints");
+
+    Ok(serde_json::to_vec(&class)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stripped_cairo_definition_is_executable() {
+        let definition = String::from_utf8(
+            starknet_gateway_test_fixtures::class_definitions::CONTRACT_DEFINITION.to_vec(),
+        )
+        .unwrap();
+
+        let stripped = strip_cairo_definition(&definition).unwrap();
+        assert!(stripped.len() < definition.len());
+
+        CompiledClassV0::try_from_json_string(std::str::from_utf8(&stripped).unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,246 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use blockifier::execution::call_info::CallInfo;
+use blockifier::execution::contract_class::RunnableCompiledClass;
+use blockifier::state::errors::StateError;
+use blockifier::state::state_api::{StateReader, StateResult};
+use blockifier::transaction::objects::TransactionExecutionInfo;
+use pathfinder_common::{ContractAddress, EntryPoint};
+
+use crate::IntoFelt;
+
+/// Contracts and entry points the node refuses to execute when calling,
+/// estimating fees or simulating, e.g. contracts abused to exhaust the
+/// resources of public endpoints.
+///
+/// Blocked contracts are refused before any of their code runs, no matter how
+/// deep in the call stack they are reached. Blocked entry points can only be
+/// detected once the call has been executed, except for the entry point of
+/// `starknet_call` itself.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExecutionPolicy {
+    contracts: HashSet<ContractAddress>,
+    entry_points: HashSet<(ContractAddress, EntryPoint)>,
+}
+
+impl ExecutionPolicy {
+    pub fn new(
+        contracts: impl IntoIterator<Item = ContractAddress>,
+        entry_points: impl IntoIterator<Item = (ContractAddress, EntryPoint)>,
+    ) -> Self {
+        Self {
+            contracts: contracts.into_iter().collect(),
+            entry_points: entry_points.into_iter().collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contracts.is_empty() && self.entry_points.is_empty()
+    }
+
+    fn check_contract(&self, contract_address: ContractAddress) -> Result<(), ExecutionRefused> {
+        if self.contracts.contains(&contract_address) {
+            return Err(ExecutionRefused {
+                contract_address,
+                selector: None,
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_entry_point(
+        &self,
+        contract_address: ContractAddress,
+        selector: EntryPoint,
+    ) -> Result<(), ExecutionRefused> {
+        self.check_contract(contract_address)?;
+        if self.entry_points.contains(&(contract_address, selector)) {
+            return Err(ExecutionRefused {
+                contract_address,
+                selector: Some(selector),
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks every call made by `call_info`, including itself.
+    pub(crate) fn check_call_info(&self, call_info: &CallInfo) -> Result<(), ExecutionRefused> {
+        if self.entry_points.is_empty() {
+            return Ok(());
+        }
+
+        self.check_entry_point(
+            ContractAddress::new_or_panic(call_info.call.storage_address.0.key().into_felt()),
+            EntryPoint(call_info.call.entry_point_selector.0.into_felt()),
+        )?;
+        call_info
+            .inner_calls
+            .iter()
+            .try_for_each(|inner| self.check_call_info(inner))
+    }
+
+    pub(crate) fn check_transaction(
+        &self,
+        execution_info: &TransactionExecutionInfo,
+    ) -> Result<(), ExecutionRefused> {
+        [
+            &execution_info.validate_call_info,
+            &execution_info.execute_call_info,
+            &execution_info.fee_transfer_call_info,
+        ]
+        .into_iter()
+        .flatten()
+        .try_for_each(|call_info| self.check_call_info(call_info))
+    }
+}
+
+/// Execution touched a contract or entry point blocked by the
+/// [ExecutionPolicy].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionRefused {
+    pub contract_address: ContractAddress,
+    /// Set if only this entry point of the contract is blocked.
+    pub selector: Option<EntryPoint>,
+}
+
+impl std::fmt::Display for ExecutionRefused {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.selector {
+            Some(selector) => write!(
+                f,
+                "Execution of entry point {selector} of contract {} is refused by the node's \
+                 execution policy",
+                self.contract_address
+            ),
+            None => write!(
+                f,
+                "Execution of contract {} is refused by the node's execution policy",
+                self.contract_address
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExecutionRefused {}
+
+/// Refuses to look up the class of blocked contracts, which stops execution
+/// before their code runs.
+///
+/// The refusal surfaces from the blockifier as an arbitrary execution error,
+/// so it is also kept here for the caller to report instead.
+pub(super) struct PolicyStateReader<S: StateReader> {
+    state: S,
+    policy: Arc<ExecutionPolicy>,
+    refused: RefCell<Option<ExecutionRefused>>,
+}
+
+impl<S: StateReader> PolicyStateReader<S> {
+    pub(super) fn new(state: S, policy: Arc<ExecutionPolicy>) -> Self {
+        Self {
+            state,
+            policy,
+            refused: Default::default(),
+        }
+    }
+
+    pub(super) fn policy(&self) -> Arc<ExecutionPolicy> {
+        self.policy.clone()
+    }
+
+    /// Returns the first refusal since the last call.
+    pub(super) fn take_refused(&self) -> Option<ExecutionRefused> {
+        self.refused.borrow_mut().take()
+    }
+
+    pub(super) fn into_inner(self) -> S {
+        self.state
+    }
+}
+
+impl<S: StateReader> StateReader for PolicyStateReader<S> {
+    fn get_storage_at(
+        &self,
+        contract_address: starknet_api::core::ContractAddress,
+        key: starknet_api::state::StorageKey,
+    ) -> StateResult<starknet_types_core::felt::Felt> {
+        self.state.get_storage_at(contract_address, key)
+    }
+
+    fn get_nonce_at(
+        &self,
+        contract_address: starknet_api::core::ContractAddress,
+    ) -> StateResult<starknet_api::core::Nonce> {
+        self.state.get_nonce_at(contract_address)
+    }
+
+    fn get_class_hash_at(
+        &self,
+        contract_address: starknet_api::core::ContractAddress,
+    ) -> StateResult<starknet_api::core::ClassHash> {
+        let address = ContractAddress::new_or_panic(contract_address.0.key().into_felt());
+        if let Err(refused) = self.policy.check_contract(address) {
+            let error = StateError::StateReadError(refused.to_string());
+            self.refused.borrow_mut().get_or_insert(refused);
+            return Err(error);
+        }
+
+        self.state.get_class_hash_at(contract_address)
+    }
+
+    fn get_compiled_class(
+        &self,
+        class_hash: starknet_api::core::ClassHash,
+    ) -> StateResult<RunnableCompiledClass> {
+        self.state.get_compiled_class(class_hash)
+    }
+
+    fn get_compiled_class_hash(
+        &self,
+        class_hash: starknet_api::core::ClassHash,
+    ) -> StateResult<starknet_api::core::CompiledClassHash> {
+        self.state.get_compiled_class_hash(class_hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn blocked_contract_refuses_all_entry_points() {
+        let policy = ExecutionPolicy::new([contract_address!("0x1")], []);
+
+        assert_eq!(
+            policy.check_entry_point(contract_address!("0x1"), entry_point!("0x2")),
+            Err(ExecutionRefused {
+                contract_address: contract_address!("0x1"),
+                selector: None,
+            })
+        );
+        assert_eq!(
+            policy.check_entry_point(contract_address!("0x2"), entry_point!("0x2")),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn blocked_entry_point_allows_other_entry_points() {
+        let policy = ExecutionPolicy::new([], [(contract_address!("0x1"), entry_point!("0x2"))]);
+
+        assert_eq!(
+            policy.check_entry_point(contract_address!("0x1"), entry_point!("0x2")),
+            Err(ExecutionRefused {
+                contract_address: contract_address!("0x1"),
+                selector: Some(entry_point!("0x2")),
+            })
+        );
+        assert_eq!(
+            policy.check_entry_point(contract_address!("0x1"), entry_point!("0x3")),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,402 @@
+//! Property tests executing random transactions on top of random state.
+//!
+//! The state consists of blocks from [pathfinder_storage::fake] followed by a
+//! block deploying an account and a fee token. The transactions are fee token
+//! transfers of random amounts to random recipients, some of which exceed the
+//! account's balance and revert. Instead of comparing against fixed
+//! expectations, the results are checked against a model of the token
+//! balances and nonces, which catches regressions in how blockifier's results
+//! are mapped.
+//!
+//! State diffs are also checked to be independent of the order their entries
+//! are reported in once normalized.
+
+use pathfinder_common::macro_prelude::*;
+use pathfinder_common::{
+    BlockHash,
+    BlockHeader,
+    BlockNumber,
+    BlockTimestamp,
+    CasmHash,
+    ChainId,
+    ClassHash,
+    ContractAddress,
+    ContractNonce,
+    EntryPoint,
+    GasPrice,
+    L1DataAvailabilityMode,
+    SierraHash,
+    StarknetVersion,
+    StateUpdate,
+    StorageAddress,
+    StorageValue,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_storage::fake::{fill, generate, Config};
+use pathfinder_storage::StorageBuilder;
+use proptest::prelude::*;
+use starknet_api::core::PatriciaKey;
+use starknet_api::transaction::fields::{Calldata, Fee, TransactionSignature};
+use starknet_gateway_test_fixtures::class_definitions::{
+    DUMMY_ACCOUNT,
+    DUMMY_ACCOUNT_CLASS_HASH,
+    ERC20_CONTRACT_DEFINITION,
+    ERC20_CONTRACT_DEFINITION_CLASS_HASH,
+};
+
+use crate::types::{
+    DeclaredSierraClass,
+    DeployedContract,
+    ExecuteInvocation,
+    ReplacedClass,
+    StateDiff,
+    StorageDiff,
+    TransactionTrace,
+};
+use crate::{
+    AccountTransactionExecutionFlags,
+    ExecutionState,
+    FeeTokens,
+    IntoStarkFelt,
+    L1BlobDataAvailability,
+    Transaction,
+};
+
+const ACCOUNT: ContractAddress = contract_address!("0xc01");
+const FEE_TOKEN: ContractAddress = contract_address!("0xfee");
+const SEQUENCER: Felt = felt!("0x5e9");
+const RECIPIENTS: [ContractAddress; 3] = [
+    contract_address!("0xa1"),
+    contract_address!("0xa2"),
+    contract_address!("0xa3"),
+];
+
+const INITIAL_BALANCE: u128 = 1_000_000_000_000_000_000;
+const MAX_FEE: u128 = 1_000_000_000_000;
+
+#[derive(Debug, Clone)]
+struct Transfer {
+    recipient: usize,
+    amount: u128,
+}
+
+fn transfer() -> impl Strategy<Value = Transfer> {
+    let amount = prop_oneof![
+        // Zero would leave the recipient's balance unchanged and out of the state
+        // diff.
+        4 => 1..1_000_000_000_000_000u128,
+        // More than the account could ever hold, so the transfer reverts.
+        1 => 2 * INITIAL_BALANCE..3 * INITIAL_BALANCE,
+    ];
+    (0..RECIPIENTS.len(), amount).prop_map(|(recipient, amount)| Transfer { recipient, amount })
+}
+
+fn balance_key(owner: ContractAddress) -> StorageAddress {
+    StorageAddress::from_map_name_and_key(b"ERC20_balances", owner.0)
+}
+
+/// Fills the database with `num_blocks` fake blocks followed by the block to
+/// execute on, which deploys the account and the fee token.
+fn setup(
+    storage: &pathfinder_storage::Storage,
+    seed: u64,
+    num_blocks: usize,
+) -> anyhow::Result<BlockHeader> {
+    use rand::SeedableRng;
+
+    let mut rng = rand_chacha::ChaCha12Rng::seed_from_u64(seed);
+    let blocks = generate::with_rng_and_config(num_blocks, &mut rng, Config::default());
+    fill(storage, &blocks, None);
+
+    let mut db = storage.connection()?;
+    let tx = db.transaction()?;
+
+    let header = BlockHeader::builder()
+        .number(BlockNumber::new_or_panic(num_blocks as u64))
+        .timestamp(BlockTimestamp::new_or_panic(num_blocks as u64 + 1))
+        .eth_l1_gas_price(GasPrice(1))
+        .strk_l1_gas_price(GasPrice(2))
+        .eth_l1_data_gas_price(GasPrice(2))
+        .strk_l1_data_gas_price(GasPrice(2))
+        .l1_da_mode(L1DataAvailabilityMode::Blob)
+        .sequencer_address(pathfinder_common::SequencerAddress(SEQUENCER))
+        .starknet_version(StarknetVersion::new(0, 13, 1, 1))
+        .finalize_with_hash(BlockHash(felt!("0xb10c")));
+    tx.insert_block_header(&header)?;
+
+    tx.insert_cairo_class(DUMMY_ACCOUNT_CLASS_HASH, DUMMY_ACCOUNT)?;
+    tx.insert_cairo_class(
+        ERC20_CONTRACT_DEFINITION_CLASS_HASH,
+        ERC20_CONTRACT_DEFINITION,
+    )?;
+    let state_update = StateUpdate::default()
+        .with_block_hash(header.hash)
+        .with_declared_cairo_class(DUMMY_ACCOUNT_CLASS_HASH)
+        .with_declared_cairo_class(ERC20_CONTRACT_DEFINITION_CLASS_HASH)
+        .with_deployed_contract(ACCOUNT, DUMMY_ACCOUNT_CLASS_HASH)
+        .with_deployed_contract(FEE_TOKEN, ERC20_CONTRACT_DEFINITION_CLASS_HASH)
+        .with_storage_update(
+            FEE_TOKEN,
+            balance_key(ACCOUNT),
+            StorageValue(Felt::from(INITIAL_BALANCE)),
+        );
+    tx.insert_state_update(header.number, &state_update)?;
+    tx.commit()?;
+
+    Ok(header)
+}
+
+/// An invoke transaction calling `transfer` on the fee token through the
+/// account.
+fn invoke(nonce: u64, transfer: &Transfer) -> Transaction {
+    let recipient = RECIPIENTS[transfer.recipient];
+    let calldata = [
+        FEE_TOKEN.0,
+        EntryPoint::hashed(b"transfer").0,
+        // calldata_len
+        felt!("0x3"),
+        recipient.0,
+        // amount as Uint256
+        Felt::from(transfer.amount),
+        Felt::ZERO,
+    ];
+
+    let tx = starknet_api::transaction::InvokeTransactionV1 {
+        max_fee: Fee(MAX_FEE),
+        signature: TransactionSignature(Default::default()),
+        nonce: starknet_api::core::Nonce(Felt::from_u64(nonce).into_starkfelt()),
+        sender_address: starknet_api::core::ContractAddress(
+            PatriciaKey::try_from(ACCOUNT.0.into_starkfelt()).unwrap(),
+        ),
+        calldata: Calldata(std::sync::Arc::new(
+            calldata
+                .into_iter()
+                .map(IntoStarkFelt::into_starkfelt)
+                .collect(),
+        )),
+    };
+
+    Transaction::from_api(
+        starknet_api::transaction::Transaction::Invoke(
+            starknet_api::transaction::InvokeTransaction::V1(tx),
+        ),
+        starknet_api::transaction::TransactionHash(Felt::from_u64(nonce + 1).into_starkfelt()),
+        None,
+        None,
+        None,
+        AccountTransactionExecutionFlags::default(),
+    )
+    .unwrap()
+}
+
+fn storage_value(
+    state_diff: &StateDiff,
+    contract: ContractAddress,
+    key: StorageAddress,
+) -> Option<Felt> {
+    state_diff
+        .storage_diffs
+        .get(&contract)?
+        .iter()
+        .find(|diff| diff.key == key)
+        .map(|diff| diff.value.0)
+}
+
+fn to_u128(felt: Felt) -> u128 {
+    felt.low_u128()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(10))]
+    #[test]
+    fn simulated_transfers_match_model(
+        seed in any::<u64>(),
+        num_blocks in 0..12usize,
+        transfers in prop::collection::vec(transfer(), 1..8),
+    ) {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let header = setup(&storage, seed, num_blocks).unwrap();
+
+        let mut db = storage.connection().unwrap();
+        let db_tx = db.transaction().unwrap();
+        let execution_state = ExecutionState::simulation(
+            &db_tx,
+            ChainId::SEPOLIA_TESTNET,
+            header,
+            None,
+            L1BlobDataAvailability::Enabled,
+            None,
+            FeeTokens::new(FEE_TOKEN, FEE_TOKEN),
+        );
+        let transactions = transfers
+            .iter()
+            .enumerate()
+            .map(|(nonce, transfer)| invoke(nonce as u64, transfer))
+            .collect();
+
+        let simulations = crate::simulate(execution_state, transactions).unwrap();
+        prop_assert_eq!(simulations.len(), transfers.len());
+
+        let mut account_balance = INITIAL_BALANCE;
+        let mut recipient_balances = [0u128; RECIPIENTS.len()];
+        let mut sequencer_balance = 0u128;
+
+        for (nonce, (simulation, transfer)) in simulations.iter().zip(&transfers).enumerate() {
+            let estimate = &simulation.fee_estimation;
+            let TransactionTrace::Invoke(trace) = &simulation.trace else {
+                panic!("Expected an invoke trace");
+            };
+
+            // The fee covers the consumed resources at the block's prices.
+            let resources_price = estimate.l1_gas_consumed * estimate.l1_gas_price
+                + estimate.l1_data_gas_consumed * estimate.l1_data_gas_price
+                + estimate.l2_gas_consumed * estimate.l2_gas_price;
+            prop_assert!(estimate.overall_fee >= resources_price, "{estimate:?}");
+            prop_assert!(!estimate.overall_fee.is_zero());
+
+            let fee_transfer = trace
+                .fee_transfer_invocation
+                .as_ref()
+                .expect("Fee is charged");
+            prop_assert_eq!(fee_transfer.calldata[0], SEQUENCER);
+            let fee = to_u128(fee_transfer.calldata[1]);
+            prop_assert!(fee <= MAX_FEE);
+            prop_assert!(primitive_types::U256::from(fee) <= estimate.overall_fee);
+
+            let reverted = matches!(trace.execute_invocation, ExecuteInvocation::RevertedReason(_));
+            if transfer.amount > account_balance {
+                prop_assert!(reverted, "Transfer exceeding the balance succeeded");
+            }
+
+            // The state diff matches the transfer and the fee.
+            account_balance -= fee;
+            sequencer_balance += fee;
+            if !reverted {
+                account_balance -= transfer.amount;
+                recipient_balances[transfer.recipient] += transfer.amount;
+            }
+
+            let state_diff = &trace.state_diff;
+            prop_assert_eq!(
+                state_diff.nonces.get(&ACCOUNT),
+                Some(&ContractNonce(Felt::from_u64(nonce as u64 + 1)))
+            );
+            prop_assert_eq!(
+                storage_value(state_diff, FEE_TOKEN, balance_key(ACCOUNT)),
+                Some(Felt::from(account_balance))
+            );
+            prop_assert_eq!(
+                storage_value(
+                    state_diff,
+                    FEE_TOKEN,
+                    balance_key(ContractAddress(SEQUENCER))
+                ),
+                Some(Felt::from(sequencer_balance))
+            );
+            let recipient = RECIPIENTS[transfer.recipient];
+            let recipient_balance = storage_value(state_diff, FEE_TOKEN, balance_key(recipient));
+            if reverted {
+                prop_assert_eq!(recipient_balance, None);
+            } else {
+                prop_assert_eq!(
+                    recipient_balance,
+                    Some(Felt::from(recipient_balances[transfer.recipient]))
+                );
+            }
+            prop_assert!(state_diff.deployed_contracts.is_empty());
+            prop_assert!(state_diff.declared_classes.is_empty());
+            prop_assert!(state_diff.deprecated_declared_classes.is_empty());
+            prop_assert!(state_diff.replaced_classes.is_empty());
+        }
+    }
+}
+
+fn small_felt() -> impl Strategy<Value = Felt> {
+    // Few distinct values so that entries share keys.
+    (1..16u64).prop_map(Felt::from_u64)
+}
+
+/// A state diff along with a copy whose entries are reported in a different
+/// order.
+fn shuffled_state_diffs() -> impl Strategy<Value = (StateDiff, StateDiff)> {
+    let pairs = || prop::collection::vec((small_felt(), small_felt()), 0..8);
+    (pairs(), pairs(), pairs(), pairs())
+        .prop_flat_map(|(storage, deployed, declared, replaced)| {
+            (
+                Just((
+                    storage.clone(),
+                    deployed.clone(),
+                    declared.clone(),
+                    replaced.clone(),
+                )),
+                (
+                    Just(storage).prop_shuffle(),
+                    Just(deployed).prop_shuffle(),
+                    Just(declared).prop_shuffle(),
+                    Just(replaced).prop_shuffle(),
+                ),
+            )
+        })
+        .prop_map(|(original, shuffled)| (state_diff(original), state_diff(shuffled)))
+}
+
+type Entries = (
+    Vec<(Felt, Felt)>,
+    Vec<(Felt, Felt)>,
+    Vec<(Felt, Felt)>,
+    Vec<(Felt, Felt)>,
+);
+
+fn state_diff((storage, deployed, declared, replaced): Entries) -> StateDiff {
+    StateDiff {
+        storage_diffs: [(
+            FEE_TOKEN,
+            storage
+                .into_iter()
+                .map(|(key, value)| StorageDiff {
+                    key: StorageAddress(key),
+                    value: StorageValue(value),
+                })
+                .collect(),
+        )]
+        .into(),
+        deployed_contracts: deployed
+            .into_iter()
+            .map(|(address, class_hash)| DeployedContract {
+                address: ContractAddress(address),
+                class_hash: ClassHash(class_hash),
+            })
+            .collect(),
+        declared_classes: declared
+            .into_iter()
+            .map(|(class_hash, compiled_class_hash)| DeclaredSierraClass {
+                class_hash: SierraHash(class_hash),
+                compiled_class_hash: CasmHash(compiled_class_hash),
+            })
+            .collect(),
+        replaced_classes: replaced
+            .into_iter()
+            .map(|(contract_address, class_hash)| ReplacedClass {
+                contract_address: ContractAddress(contract_address),
+                class_hash: ClassHash(class_hash),
+            })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+proptest! {
+    #[test]
+    fn normalized_state_diffs_do_not_depend_on_order(
+        (mut original, mut shuffled) in shuffled_state_diffs()
+    ) {
+        original.normalize();
+        shuffled.normalize();
+        prop_assert_eq!(&original, &shuffled);
+
+        let deployed = &original.deployed_contracts;
+        prop_assert!(deployed.windows(2).all(|w| w[0].address <= w[1].address));
+        let storage = &original.storage_diffs[&FEE_TOKEN];
+        prop_assert!(storage.windows(2).all(|w| w[0].key <= w[1].key));
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::BTreeMap;
-use std::sync::{Arc, Mutex};
+use std::ops::Range;
+use std::sync::{Arc, Mutex, MutexGuard};
 
 use anyhow::Context;
 use blockifier::state::cached_state::CachedState;
@@ -7,7 +8,6 @@ use blockifier::state::errors::StateError;
 use blockifier::transaction::transaction_execution::Transaction;
 use blockifier::transaction::transactions::ExecutableTransaction;
 use blockifier::versioned_constants::VersionedConstants;
-use cached::{Cached, SizedCache};
 use pathfinder_common::{
     BlockHash,
     CasmHash,
@@ -20,11 +20,15 @@ use pathfinder_common::{
     TransactionHash,
 };
 use starknet_api::transaction::fields::GasVectorComputationMode;
+use util::cache::WeightedCache;
 
 use super::error::TransactionExecutionError;
 use super::execution_state::ExecutionState;
 use super::types::{FeeEstimate, TransactionSimulation, TransactionTrace};
+use crate::backend::Backend;
+use crate::cache_metrics::{self, Cache};
 use crate::error_stack::ErrorStack;
+use crate::trace_cache::TraceCacheConfig;
 use crate::transaction::transaction_hash;
 use crate::types::{
     DataAvailabilityResources,
@@ -68,16 +72,64 @@ impl From<ExecutionError> for TransactionExecutionError {
 }
 
 #[derive(Debug, Clone)]
-pub struct TraceCache(Arc<Mutex<SizedCache<BlockHash, CacheItem>>>);
+pub struct TraceCache(Arc<Mutex<WeightedCache<BlockHash, CacheItem>>>);
 
 type Traces = Vec<(TransactionHash, TransactionTrace)>;
 
 impl Default for TraceCache {
     fn default() -> Self {
-        Self(Arc::new(Mutex::new(SizedCache::with_size(128))))
+        Self::new(Default::default())
     }
 }
 
+impl TraceCache {
+    pub fn new(config: TraceCacheConfig) -> Self {
+        Self(Arc::new(Mutex::new(WeightedCache::new(
+            Some(config.max_entries),
+            config.max_size,
+            config.eviction_policy,
+        ))))
+    }
+
+    /// Whether the traces of a block are cached or already being computed.
+    pub fn contains(&self, block_hash: &BlockHash) -> bool {
+        self.lock().get(block_hash).is_some()
+    }
+
+    /// Returns the blocks whose traces are cached, the ones most likely to be
+    /// kept come first. Failed and in-flight traces are left out.
+    pub fn cached_blocks(&self) -> Vec<BlockHash> {
+        self.lock()
+            .entries_by_rank()
+            .into_iter()
+            .filter(|(_, item)| matches!(item, CacheItem::CachedOk(_)))
+            .map(|(block_hash, _)| *block_hash)
+            .collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, WeightedCache<BlockHash, CacheItem>> {
+        self.0.lock().unwrap()
+    }
+}
+
+/// Caches a trace result, weighed by its estimated size.
+fn insert_weighed(
+    cache: &mut WeightedCache<BlockHash, CacheItem>,
+    block_hash: BlockHash,
+    item: CacheItem,
+) {
+    let weight = match &item {
+        CacheItem::Inflight(_) => 0,
+        CacheItem::CachedOk(traces) => {
+            crate::trace_cache::estimated_size(traces.iter().map(|(_, trace)| trace))
+        }
+        CacheItem::CachedErr(error) => error.error.len(),
+    };
+    let evicted = cache.set(block_hash, item, weight);
+    cache_metrics::evicted(Cache::Trace, evicted);
+    cache_metrics::size(Cache::Trace, cache.len(), Some(cache.total_weight()));
+}
+
 pub fn simulate(
     execution_state: ExecutionState<'_>,
     transactions: Vec<Transaction>,
@@ -85,6 +137,7 @@ pub fn simulate(
     let block_number = execution_state.header.number;
 
     let (mut state, block_context) = execution_state.starknet_state()?;
+    let policy = state.state.policy();
 
     let mut simulations = Vec::with_capacity(transactions.len());
     for (transaction_idx, transaction) in transactions.into_iter().enumerate() {
@@ -109,9 +162,14 @@ pub fn simulate(
         let tx_info = transaction.execute(&mut tx_state, &block_context);
         let state_diff = to_state_diff(&mut tx_state, transaction_declared_deprecated_class_hash)?;
         tx_state.commit();
+        if let Some(refused) = state.state.take_refused() {
+            return Err(refused.into());
+        }
 
         match tx_info {
             Ok(tx_info) => {
+                policy.check_transaction(&tx_info)?;
+
                 if let Some(revert_error) = &tx_info.revert_error {
                     let revert_string = revert_error.to_string();
                     tracing::trace!(revert_error=%revert_string, "Transaction reverted");
@@ -149,21 +207,26 @@ pub fn trace(
     block_hash: BlockHash,
     transactions: Vec<Transaction>,
 ) -> Result<Vec<(TransactionHash, TransactionTrace)>, TransactionExecutionError> {
+    Backend::ensure_replayable(execution_state.header.starknet_version)?;
+
     let (mut state, block_context) = execution_state.starknet_state()?;
 
     let sender = {
-        let mut cache = cache.0.lock().unwrap();
-        match cache.cache_get(&block_hash) {
+        let mut cache = cache.lock();
+        match cache.get(&block_hash) {
             Some(CacheItem::CachedOk(cached)) => {
                 tracing::trace!(block=%block_hash, "trace cache hit: ok");
+                cache_metrics::hit(Cache::Trace);
                 return Ok(cached.clone());
             }
             Some(CacheItem::CachedErr(e)) => {
                 tracing::trace!(block=%block_hash, "trace cache hit: err");
+                cache_metrics::hit(Cache::Trace);
                 return Err(e.to_owned().into());
             }
             Some(CacheItem::Inflight(receiver)) => {
                 tracing::trace!(block=%block_hash, "trace already inflight");
+                cache_metrics::hit(Cache::Trace);
                 let mut receiver = receiver.resubscribe();
                 drop(cache);
 
@@ -172,8 +235,9 @@ pub fn trace(
             }
             None => {
                 tracing::trace!(block=%block_hash, "trace cache miss");
+                cache_metrics::miss(Cache::Trace);
                 let (sender, receiver) = tokio::sync::broadcast::channel(1);
-                cache.cache_set(block_hash, CacheItem::Inflight(receiver));
+                insert_weighed(&mut cache, block_hash, CacheItem::Inflight(receiver));
                 sender
             }
         }
@@ -196,16 +260,15 @@ pub fn trace(
                 error: e.to_string(),
                 error_stack: e.into(),
             };
-            let mut cache = cache.0.lock().unwrap();
+            let mut cache = cache.lock();
             let _ = sender.send(Err(err.clone()));
-            cache.cache_set(block_hash, CacheItem::CachedErr(err.clone()));
+            insert_weighed(&mut cache, block_hash, CacheItem::CachedErr(err.clone()));
             err
         })?;
         let state_diff = to_state_diff(&mut tx_state, tx_declared_deprecated_class_hash)
             .inspect_err(|_| {
                 // Remove the cache entry so it's no longer inflight.
-                let mut cache = cache.0.lock().unwrap();
-                cache.cache_remove(&block_hash);
+                cache.lock().remove(&block_hash);
             })?;
         tx_state.commit();
 
@@ -220,9 +283,84 @@ pub fn trace(
 
     // Lock the cache before sending to avoid race conditions between senders and
     // receivers.
-    let mut cache = cache.0.lock().unwrap();
+    let mut cache = cache.lock();
     let _ = sender.send(Ok(traces.clone()));
-    cache.cache_set(block_hash, CacheItem::CachedOk(traces.clone()));
+    insert_weighed(&mut cache, block_hash, CacheItem::CachedOk(traces.clone()));
+    Ok(traces)
+}
+
+/// Traces the transactions of a block in `range`, executing only the
+/// transactions up to the end of the range.
+///
+/// The traces are taken from the cache if the whole block has been traced
+/// before. Partial traces are not cached.
+pub fn trace_range(
+    execution_state: ExecutionState<'_>,
+    cache: TraceCache,
+    block_hash: BlockHash,
+    transactions: Vec<Transaction>,
+    range: Range<usize>,
+) -> Result<Vec<(TransactionHash, TransactionTrace)>, TransactionExecutionError> {
+    if range.start > range.end || range.end > transactions.len() {
+        return Err(TransactionExecutionError::Custom(anyhow::anyhow!(
+            "Invalid transaction range {range:?} for a block of {} transactions",
+            transactions.len()
+        )));
+    }
+
+    match cache.lock().get(&block_hash) {
+        Some(CacheItem::CachedOk(cached)) => {
+            tracing::trace!(block=%block_hash, "trace cache hit: ok");
+            cache_metrics::hit(Cache::Trace);
+            return Ok(cached[range].to_vec());
+        }
+        Some(CacheItem::CachedErr(e)) if e.transaction_index < range.end => {
+            tracing::trace!(block=%block_hash, "trace cache hit: err");
+            cache_metrics::hit(Cache::Trace);
+            return Err(e.to_owned().into());
+        }
+        // Executing the prefix of the block is likely faster than waiting for a trace of the
+        // whole block which is already in flight.
+        _ => cache_metrics::miss(Cache::Trace),
+    }
+
+    Backend::ensure_replayable(execution_state.header.starknet_version)?;
+
+    let (mut state, block_context) = execution_state.starknet_state()?;
+
+    let mut traces = Vec::with_capacity(range.len());
+    for (transaction_idx, tx) in transactions.into_iter().enumerate().take(range.end) {
+        let hash = transaction_hash(&tx);
+        let _span =
+            tracing::debug_span!("simulate", transaction_hash=%hash, %transaction_idx).entered();
+
+        let tx_type = transaction_type(&tx);
+        let tx_declared_deprecated_class_hash = transaction_declared_deprecated_class(&tx);
+
+        let mut tx_state = CachedState::<_>::create_transactional(&mut state);
+        let tx_info = tx
+            .execute(&mut tx_state, &block_context)
+            .map_err(|e| TransactionExecutionError::new(transaction_idx, e))?;
+
+        // Transactions before the range are only executed for their effect on the
+        // state.
+        if transaction_idx < range.start {
+            tx_state.commit();
+            continue;
+        }
+
+        let state_diff = to_state_diff(&mut tx_state, tx_declared_deprecated_class_hash)?;
+        tx_state.commit();
+
+        let trace = to_trace(
+            tx_type,
+            tx_info,
+            state_diff,
+            block_context.versioned_constants(),
+        );
+        traces.push((hash, trace));
+    }
+
     Ok(traces)
 }
 
@@ -328,7 +466,7 @@ fn to_state_diff<S: blockifier::state::state_api::StateReader>(
         })
         .collect();
 
-    Ok(StateDiff {
+    let mut diff = StateDiff {
         storage_diffs,
         deployed_contracts,
         // This info is not present in the state diff, so we need to pass it separately.
@@ -354,7 +492,10 @@ fn to_state_diff<S: blockifier::state::state_api::StateReader>(
             })
             .collect(),
         replaced_classes,
-    })
+    };
+    diff.normalize();
+
+    Ok(diff)
 }
 
 fn to_trace(
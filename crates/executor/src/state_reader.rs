@@ -7,8 +7,11 @@ use pathfinder_crypto::Felt;
 use starknet_api::StarknetApiError;
 use starknet_types_core::felt::Felt as CoreFelt;
 
+use super::block_hash::BlockHashContract;
 use super::felt::{IntoFelt, IntoStarkFelt};
+use crate::cache_metrics::{self, Cache};
 use crate::lru_cache::GLOBAL_CACHE;
+use crate::persistent_class_cache;
 
 pub(super) struct PathfinderStateReader<'tx> {
     transaction: &'tx pathfinder_storage::Transaction<'tx>,
@@ -17,6 +20,9 @@ pub(super) struct PathfinderStateReader<'tx> {
     // This flag makes it possible to find these classes -- essentially makes the state
     // reader look up classes which are not declared at a canonical block yet.
     ignore_block_number_for_classes: bool,
+    /// Set if reads of the block hash contract are served from the block
+    /// headers, together with the block being executed.
+    block_hashes_from_headers: Option<(BlockHashContract, BlockNumber)>,
 }
 
 impl<'tx> PathfinderStateReader<'tx> {
@@ -29,9 +35,45 @@ impl<'tx> PathfinderStateReader<'tx> {
             transaction,
             block_number,
             ignore_block_number_for_classes,
+            block_hashes_from_headers: None,
         }
     }
 
+    /// Serves reads of `contract` from the block headers if it is configured
+    /// to, `block` being the block executed.
+    pub fn with_block_hash_contract(
+        mut self,
+        contract: BlockHashContract,
+        block: BlockNumber,
+    ) -> Self {
+        if contract.from_headers {
+            self.block_hashes_from_headers = Some((contract, block));
+        }
+        self
+    }
+
+    fn block_hash_from_headers(
+        &self,
+        contract: BlockHashContract,
+        block: BlockNumber,
+        key: StorageAddress,
+    ) -> blockifier::state::state_api::StateResult<CoreFelt> {
+        let Some(requested) = contract.block_for_key(block, key.0) else {
+            return Ok(Felt::ZERO.into_starkfelt());
+        };
+
+        let hash = self
+            .transaction
+            .block_hash(requested.into())
+            .map_err(map_anyhow_to_state_err)?
+            .map(|hash| hash.0)
+            .unwrap_or(Felt::ZERO);
+
+        tracing::trace!(%requested, block_hash=%hash, "Got block hash from headers");
+
+        Ok(hash.into_starkfelt())
+    }
+
     fn state_block_id(&self) -> Option<pathfinder_storage::BlockId> {
         self.block_number.map(Into::into)
     }
@@ -49,6 +91,20 @@ impl<'tx> PathfinderStateReader<'tx> {
             ))
         })?;
 
+        if let Some((definition_block_number, class)) =
+            persistent_class_cache::load(self.transaction, pathfinder_class_hash)
+        {
+            let is_declared = self.ignore_block_number_for_classes
+                || matches!(
+                    (definition_block_number, self.block_number),
+                    (Some(declared), Some(current)) if declared <= current
+                );
+            if is_declared {
+                tracing::trace!("Persistent class cache hit");
+                return Ok((definition_block_number, class));
+            }
+        }
+
         let (definition_block_number, class_definition, casm_definition) =
             if self.ignore_block_number_for_classes {
                 let casm_definition = self
@@ -105,9 +161,14 @@ impl<'tx> PathfinderStateReader<'tx> {
                 let casm_class =
                     blockifier::execution::contract_class::CompiledClassV1::try_from_json_string(
                         &casm_definition,
-                        sierra_version,
+                        sierra_version.clone(),
                     )
                     .map_err(StateError::ProgramError)?;
+                persistent_class_cache::store(
+                    pathfinder_class_hash,
+                    Some(sierra_version),
+                    casm_definition,
+                );
 
                 Ok((
                     definition_block_number,
@@ -128,6 +189,7 @@ impl<'tx> PathfinderStateReader<'tx> {
                         &class_definition,
                     )
                     .map_err(StateError::ProgramError)?;
+                persistent_class_cache::store(pathfinder_class_hash, None, class_definition);
 
                 Ok((definition_block_number, RunnableCompiledClass::V0(class)))
             }
@@ -157,6 +219,12 @@ impl StateReader for PathfinderStateReader<'_> {
 
         tracing::trace!("Getting storage value");
 
+        if let Some((contract, block)) = self.block_hashes_from_headers {
+            if contract.address == pathfinder_contract_address {
+                return self.block_hash_from_headers(contract, block, storage_key);
+            }
+        }
+
         let Some(block_id) = self.state_block_id() else {
             return Ok(Felt::ZERO.into_starkfelt());
         };
@@ -245,10 +313,12 @@ impl StateReader for PathfinderStateReader<'_> {
             if let Some(reader_block_number) = self.block_number {
                 if entry.height <= reader_block_number {
                     tracing::trace!("Global class cache hit");
+                    cache_metrics::hit(Cache::Class);
                     return Ok(entry.definition);
                 }
             }
         }
+        cache_metrics::miss(Cache::Class);
 
         let (definition_block_number, contract_class) =
             self.non_cached_compiled_contract_class(pathfinder_class_hash, &class_hash)?;
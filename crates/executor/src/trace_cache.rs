@@ -0,0 +1,123 @@
+use std::num::NonZeroUsize;
+
+pub use util::cache::EvictionPolicy;
+
+use crate::types::{ExecuteInvocation, FunctionInvocation, StateDiff, TransactionTrace};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceCacheConfig {
+    /// Maximum number of blocks whose traces are cached.
+    pub max_entries: NonZeroUsize,
+    /// Maximum estimated size of all cached traces in bytes. If set, entries
+    /// are weighed by the size of their traces.
+    pub max_size: Option<NonZeroUsize>,
+    pub eviction_policy: EvictionPolicy,
+}
+
+impl Default for TraceCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: NonZeroUsize::new(128).unwrap(),
+            max_size: None,
+            eviction_policy: Default::default(),
+        }
+    }
+}
+
+const FELT_SIZE: usize = 32;
+
+/// A rough estimate of the memory used by the traces, dominated by the felts
+/// they contain.
+pub(crate) fn estimated_size<'a>(traces: impl IntoIterator<Item = &'a TransactionTrace>) -> usize {
+    traces.into_iter().map(trace_size).sum()
+}
+
+fn trace_size(trace: &TransactionTrace) -> usize {
+    let (invocations, state_diff, revert_reason_size) = match trace {
+        TransactionTrace::Declare(trace) => (
+            [
+                trace.validate_invocation.as_ref(),
+                trace.fee_transfer_invocation.as_ref(),
+                None,
+            ],
+            &trace.state_diff,
+            0,
+        ),
+        TransactionTrace::DeployAccount(trace) => (
+            [
+                trace.validate_invocation.as_ref(),
+                trace.constructor_invocation.as_ref(),
+                trace.fee_transfer_invocation.as_ref(),
+            ],
+            &trace.state_diff,
+            0,
+        ),
+        TransactionTrace::Invoke(trace) => {
+            let (execute_invocation, revert_reason_size) = match &trace.execute_invocation {
+                ExecuteInvocation::FunctionInvocation(invocation) => (invocation.as_ref(), 0),
+                ExecuteInvocation::RevertedReason(reason) => (None, reason.len()),
+            };
+            (
+                [
+                    trace.validate_invocation.as_ref(),
+                    execute_invocation,
+                    trace.fee_transfer_invocation.as_ref(),
+                ],
+                &trace.state_diff,
+                revert_reason_size,
+            )
+        }
+        TransactionTrace::L1Handler(trace) => (
+            [trace.function_invocation.as_ref(), None, None],
+            &trace.state_diff,
+            0,
+        ),
+    };
+
+    invocations
+        .into_iter()
+        .flatten()
+        .map(invocation_size)
+        .sum::<usize>()
+        + state_diff_size(state_diff)
+        + revert_reason_size
+}
+
+fn invocation_size(invocation: &FunctionInvocation) -> usize {
+    let felts = invocation.calldata.len()
+        + invocation.result.len()
+        + invocation
+            .events
+            .iter()
+            .map(|event| event.keys.len() + event.data.len())
+            .sum::<usize>()
+        + invocation
+            .messages
+            .iter()
+            .map(|message| message.payload.len())
+            .sum::<usize>();
+
+    std::mem::size_of::<FunctionInvocation>()
+        + felts * FELT_SIZE
+        + invocation
+            .internal_calls
+            .iter()
+            .map(invocation_size)
+            .sum::<usize>()
+}
+
+fn state_diff_size(state_diff: &StateDiff) -> usize {
+    let felts = 2 * state_diff
+        .storage_diffs
+        .values()
+        .map(Vec::len)
+        .sum::<usize>()
+        + state_diff.storage_diffs.len()
+        + 2 * state_diff.deployed_contracts.len()
+        + state_diff.deprecated_declared_classes.len()
+        + 2 * state_diff.declared_classes.len()
+        + 2 * state_diff.nonces.len()
+        + 2 * state_diff.replaced_classes.len();
+
+    felts * FELT_SIZE
+}
@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet};
 
 use blockifier::execution::call_info::OrderedL2ToL1Message;
 use blockifier::fee::fee_utils::get_vm_resources_cost;
@@ -137,6 +137,24 @@ impl TransactionTrace {
             _ => None,
         }
     }
+
+    pub fn state_diff(&self) -> &StateDiff {
+        match self {
+            TransactionTrace::Declare(trace) => &trace.state_diff,
+            TransactionTrace::DeployAccount(trace) => &trace.state_diff,
+            TransactionTrace::Invoke(trace) => &trace.state_diff,
+            TransactionTrace::L1Handler(trace) => &trace.state_diff,
+        }
+    }
+
+    pub fn execution_resources(&self) -> &ExecutionResources {
+        match self {
+            TransactionTrace::Declare(trace) => &trace.execution_resources,
+            TransactionTrace::DeployAccount(trace) => &trace.execution_resources,
+            TransactionTrace::Invoke(trace) => &trace.execution_resources,
+            TransactionTrace::L1Handler(trace) => &trace.execution_resources,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -224,16 +242,92 @@ pub struct InnerCallExecutionResources {
     pub l2_gas: u128,
 }
 
+/// Contracts, classes and storage keys are ordered by address, class hash and
+/// key once [normalized](StateDiff::normalize), so that equal state diffs
+/// serialize identically.
 #[derive(Debug, Default, Clone, Eq, PartialEq)]
 pub struct StateDiff {
     pub storage_diffs: BTreeMap<ContractAddress, Vec<StorageDiff>>,
     pub deployed_contracts: Vec<DeployedContract>,
-    pub deprecated_declared_classes: HashSet<ClassHash>,
+    pub deprecated_declared_classes: BTreeSet<ClassHash>,
     pub declared_classes: Vec<DeclaredSierraClass>,
     pub nonces: BTreeMap<ContractAddress, ContractNonce>,
     pub replaced_classes: Vec<ReplacedClass>,
 }
 
+impl StateDiff {
+    /// Sorts the storage entries of each contract by key, deployed contracts
+    /// and replaced classes by contract address and declared classes by class
+    /// hash, breaking ties by the remaining fields. Blockifier reports these
+    /// from hash maps, so their order would otherwise differ between runs
+    /// of the same transaction.
+    pub fn normalize(&mut self) {
+        for diffs in self.storage_diffs.values_mut() {
+            diffs.sort_by_key(|diff| (diff.key, diff.value));
+        }
+        self.deployed_contracts
+            .sort_by_key(|contract| (contract.address, contract.class_hash));
+        self.declared_classes
+            .sort_by_key(|class| (class.class_hash, class.compiled_class_hash));
+        self.replaced_classes
+            .sort_by_key(|class| (class.contract_address, class.class_hash));
+    }
+
+    /// Applies the state diff of a later transaction on top of this one, so
+    /// that the result is the combined effect of both.
+    ///
+    /// Storage values, nonces and class hashes of `later` take precedence. A
+    /// class replaced in a contract deployed by this diff is recorded as the
+    /// deployed contract's class instead of as a replacement.
+    pub fn merge(&mut self, later: &StateDiff) {
+        for (address, diffs) in &later.storage_diffs {
+            let existing = self.storage_diffs.entry(*address).or_default();
+            for diff in diffs {
+                match existing.iter_mut().find(|x| x.key == diff.key) {
+                    Some(x) => x.value = diff.value,
+                    None => existing.push(diff.clone()),
+                }
+            }
+        }
+
+        self.deployed_contracts
+            .extend(later.deployed_contracts.iter().cloned());
+        self.deprecated_declared_classes
+            .extend(later.deprecated_declared_classes.iter().copied());
+        for class in &later.declared_classes {
+            if !self.declared_classes.contains(class) {
+                self.declared_classes.push(class.clone());
+            }
+        }
+        self.nonces.extend(
+            later
+                .nonces
+                .iter()
+                .map(|(address, nonce)| (*address, *nonce)),
+        );
+
+        for replaced in &later.replaced_classes {
+            if let Some(deployed) = self
+                .deployed_contracts
+                .iter_mut()
+                .find(|x| x.address == replaced.contract_address)
+            {
+                deployed.class_hash = replaced.class_hash;
+            } else if let Some(x) = self
+                .replaced_classes
+                .iter_mut()
+                .find(|x| x.contract_address == replaced.contract_address)
+            {
+                x.class_hash = replaced.class_hash;
+            } else {
+                self.replaced_classes.push(replaced.clone());
+            }
+        }
+
+        self.normalize();
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct StorageDiff {
     pub key: StorageAddress,
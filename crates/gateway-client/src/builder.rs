@@ -61,6 +61,7 @@ pub mod stage {
     /// Specify the REST operation send the request:
     /// - [get](super::Request::get)
     /// - [get_as_bytes](super::Request::get_as_bytes)
+    /// - [get_as_bytes_resumable](super::Request::get_as_bytes_resumable)
     /// - [post_with_json](super::Request::post_with_json)
     pub struct Final {
         pub meta: RequestMetadata,
@@ -170,7 +171,8 @@ impl<'a> Request<'a, stage::Method> {
 }
 
 impl<'a> Request<'a, stage::Params> {
-    pub fn block<B: Into<BlockId>>(self, block: B) -> Self {
+    /// Fails for block tags the gateway does not support.
+    pub fn block<B: Into<BlockId>>(self, block: B) -> Result<Self, SequencerError> {
         use std::borrow::Cow;
 
         let block: BlockId = block.into();
@@ -184,9 +186,11 @@ impl<'a> Request<'a, stage::Params> {
             // These have to use "blockNumber", "blockHash" does not accept tags.
             BlockId::Latest => ("blockNumber", Cow::from("latest"), BlockTag::Latest),
             BlockId::Pending => ("blockNumber", Cow::from("pending"), BlockTag::Pending),
+            // The gateway has no such tag, callers have to resolve it to a block first.
+            BlockId::L1Accepted => return Err(SequencerError::UnsupportedBlockTag),
         };
 
-        self.block_tag(tag).param(name, &value)
+        Ok(self.block_tag(tag).param(name, &value))
     }
 
     pub fn class_hash(self, class_hash: ClassHash) -> Self {
@@ -310,6 +314,32 @@ impl Request<'_, stage::Final> {
         }
     }
 
+    /// Like [get_as_bytes](Self::get_as_bytes), but retries continue the
+    /// download where the failed attempt stopped instead of starting over.
+    ///
+    /// Meant for large replies such as class definitions. Resuming requires
+    /// the server to support range requests and to identify the reply with an
+    /// `ETag`, which is sent back as `If-Range` so that a reply which changed
+    /// in the meantime is downloaded in full again. Otherwise each retry
+    /// starts from scratch.
+    pub async fn get_as_bytes_resumable(self) -> Result<bytes::Bytes, SequencerError> {
+        let download = tokio::sync::Mutex::new(PartialDownload::default());
+
+        let fetch = || async {
+            let mut download = download.lock().await;
+            with_metrics(
+                self.state.meta,
+                download.fetch(self.url.clone(), self.api_key.clone(), self.client),
+            )
+            .await
+        };
+
+        match self.state.retry {
+            false => fetch().await,
+            true => retry0(fetch, retry_condition).await,
+        }
+    }
+
     /// Sends the Sequencer request as a REST `POST` operation, in addition to
     /// the specified JSON body. The response is parsed as type `T`.
     ///
@@ -422,6 +452,110 @@ async fn parse_raw(response: reqwest::Response) -> Result<reqwest::Response, Seq
 
 pub trait RequestState {}
 
+/// The part of a reply received so far by
+/// [get_as_bytes_resumable](Request::get_as_bytes_resumable).
+#[derive(Default)]
+struct PartialDownload {
+    buffer: Vec<u8>,
+    /// Identifies the reply the buffer belongs to. Only set if the server
+    /// accepts range requests.
+    etag: Option<reqwest::header::HeaderValue>,
+}
+
+impl PartialDownload {
+    async fn fetch(
+        &mut self,
+        url: reqwest::Url,
+        api_key: Option<String>,
+        client: &reqwest::Client,
+    ) -> Result<bytes::Bytes, SequencerError> {
+        use reqwest::header::{ACCEPT_RANGES, ETAG, IF_RANGE, RANGE};
+        use reqwest::StatusCode;
+
+        loop {
+            let mut request = client.get(url.clone());
+            if let Some(api_key) = &api_key {
+                request = request.header(X_THROTTLING_BYPASS, api_key);
+            }
+
+            let resume_from = match &self.etag {
+                Some(etag) if !self.buffer.is_empty() => {
+                    tracing::debug!(%url, offset=%self.buffer.len(), "Resuming download");
+                    request = request
+                        .header(RANGE, format!("bytes={}-", self.buffer.len()))
+                        .header(IF_RANGE, etag);
+                    self.buffer.len()
+                }
+                _ => {
+                    self.buffer.clear();
+                    0
+                }
+            };
+
+            let mut response = parse_raw(request.send().await?).await?;
+
+            let mut expected_len = response.content_length().map(|len| len as usize);
+            if response.status() == StatusCode::PARTIAL_CONTENT && resume_from > 0 {
+                match content_range(&response) {
+                    Some((start, total)) if start == resume_from => expected_len = total,
+                    _ => {
+                        tracing::debug!(%url, "Unexpected content range, restarting download");
+                        self.etag = None;
+                        continue;
+                    }
+                }
+            } else {
+                // The server replied with the whole content.
+                self.buffer.clear();
+                let accepts_ranges = response
+                    .headers()
+                    .get(ACCEPT_RANGES)
+                    .is_some_and(|value| value.as_bytes() == b"bytes");
+                self.etag = response
+                    .headers()
+                    .get(ETAG)
+                    .filter(|_| accepts_ranges)
+                    .cloned();
+            }
+
+            // Keep what has been received so far if the connection fails.
+            while let Some(chunk) = response.chunk().await? {
+                self.buffer.extend_from_slice(&chunk);
+            }
+
+            if resume_from > 0 && expected_len.is_some_and(|len| len != self.buffer.len()) {
+                tracing::debug!(
+                    %url, received=%self.buffer.len(), expected=?expected_len,
+                    "Download size mismatch, restarting download"
+                );
+                self.etag = None;
+                continue;
+            }
+
+            self.etag = None;
+            return Ok(std::mem::take(&mut self.buffer).into());
+        }
+    }
+}
+
+/// Parses the start offset and the total length, if known, from the
+/// `Content-Range` header of a partial reply.
+fn content_range(response: &reqwest::Response) -> Option<(usize, Option<usize>)> {
+    let value = response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?;
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _end) = range.split_once('-')?;
+    let total = match total {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+
+    Some((start.parse().ok()?, total))
+}
+
 /// Wrapper function to allow retrying sequencer queries in an exponential
 /// manner.
 async fn retry0<T, Fut, FutureFactory, Ret>(
@@ -480,7 +614,7 @@ fn retry_condition(e: &SequencerError) -> bool {
 
             true
         }
-        SequencerError::StarknetError(_) => false,
+        SequencerError::StarknetError(_) | SequencerError::UnsupportedBlockTag => false,
         SequencerError::InvalidStarknetErrorVariant => {
             error!(reason=%e, "Request failed, retrying");
             true
@@ -654,6 +788,131 @@ mod tests {
         }
     }
 
+    mod resumable_download {
+        use std::net::SocketAddr;
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+
+        use futures::StreamExt;
+        use pretty_assertions_sorted::assert_eq;
+        use tokio::task::JoinHandle;
+        use warp::http::response::Builder;
+        use warp::hyper::Body;
+        use warp::Filter;
+
+        use crate::builder::Request;
+
+        const CONTENT: &[u8] = b"a class definition too large to fetch in one go";
+        const HALF: usize = CONTENT.len() / 2;
+        const ETAG: &str = "\"class\"";
+
+        /// Range headers of the received requests.
+        type Requests = Arc<Mutex<Vec<Option<String>>>>;
+
+        /// Serves [CONTENT], failing the first reply half way through. Range
+        /// requests are answered if `supports_ranges` is set.
+        fn server(supports_ranges: bool) -> (JoinHandle<()>, SocketAddr, Requests) {
+            let requests = Requests::default();
+            let received = requests.clone();
+
+            let filter = warp::header::optional::<String>("range")
+                .and(warp::header::optional::<String>("if-range"))
+                .map(move |range: Option<String>, if_range: Option<String>| {
+                    let first = {
+                        let mut received = received.lock().unwrap();
+                        received.push(range.clone());
+                        received.len() == 1
+                    };
+
+                    let builder = match supports_ranges {
+                        true => Builder::new()
+                            .header("accept-ranges", "bytes")
+                            .header("etag", ETAG),
+                        false => Builder::new(),
+                    };
+
+                    if first {
+                        // Give the client time to receive the first chunk before
+                        // the connection breaks.
+                        let chunks = futures::stream::iter([
+                            Ok(bytes::Bytes::from_static(&CONTENT[..HALF])),
+                            Err(std::io::Error::new(
+                                std::io::ErrorKind::ConnectionReset,
+                                "connection lost",
+                            )),
+                        ])
+                        .then(|chunk| async {
+                            tokio::time::sleep(Duration::from_millis(10)).await;
+                            chunk
+                        });
+                        return builder.status(200).body(Body::wrap_stream(chunks));
+                    }
+
+                    match range {
+                        Some(range) => {
+                            assert_eq!(if_range.as_deref(), Some(ETAG));
+                            let start: usize = range
+                                .strip_prefix("bytes=")
+                                .and_then(|range| range.strip_suffix('-'))
+                                .unwrap()
+                                .parse()
+                                .unwrap();
+                            builder
+                                .status(206)
+                                .header(
+                                    "content-range",
+                                    format!(
+                                        "bytes {start}-{}/{}",
+                                        CONTENT.len() - 1,
+                                        CONTENT.len()
+                                    ),
+                                )
+                                .body(Body::from(&CONTENT[start..]))
+                        }
+                        None => builder.status(200).body(Body::from(CONTENT)),
+                    }
+                });
+
+            let (addr, run_srv) = warp::serve(filter).bind_ephemeral(([127, 0, 0, 1], 0));
+            let server_handle = tokio::spawn(run_srv);
+            (server_handle, addr, requests)
+        }
+
+        async fn download(addr: SocketAddr) -> bytes::Bytes {
+            let client = reqwest::Client::new();
+            let url = reqwest::Url::parse(&format!("http://{addr}")).unwrap();
+            Request::builder(&client, url, None)
+                .method("get_class_by_hash")
+                .retry(true)
+                .get_as_bytes_resumable()
+                .await
+                .unwrap()
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn continues_where_the_failed_attempt_stopped() {
+            tokio::time::pause();
+
+            let (_jh, addr, requests) = server(true);
+
+            assert_eq!(download(addr).await, CONTENT);
+            assert_eq!(
+                *requests.lock().unwrap(),
+                vec![None, Some(format!("bytes={HALF}-"))]
+            );
+        }
+
+        #[test_log::test(tokio::test)]
+        async fn starts_over_without_range_support() {
+            tokio::time::pause();
+
+            let (_jh, addr, requests) = server(false);
+
+            assert_eq!(download(addr).await, CONTENT);
+            assert_eq!(*requests.lock().unwrap(), vec![None, None]);
+        }
+    }
+
     mod invalid_starknet_error_variant {
         use gateway_test_utils::GATEWAY_TIMEOUT;
         use warp::http::response::Builder;
@@ -687,6 +946,33 @@ mod tests {
         }
     }
 
+    mod unsupported_block_tag {
+        use assert_matches::assert_matches;
+        use gateway_test_utils::GATEWAY_TIMEOUT;
+        use httpmock::prelude::*;
+        use pathfinder_common::BlockId;
+        use starknet_gateway_types::error::SequencerError;
+
+        use crate::{Client, GatewayApi};
+
+        #[tokio::test]
+        async fn is_refused_without_a_request() {
+            let server = MockServer::start_async().await;
+            let mock = server.mock(|when, then| {
+                when.any_request();
+                then.status(200);
+            });
+            let client = Client::with_base_url(server.base_url().parse().unwrap(), GATEWAY_TIMEOUT)
+                .unwrap()
+                .disable_retry_for_tests();
+
+            let error = client.block_traces(BlockId::L1Accepted).await.unwrap_err();
+
+            assert_matches!(error, SequencerError::UnsupportedBlockTag);
+            mock.assert_hits(0);
+        }
+    }
+
     mod api_key_is_set_when_configured {
         use fake::{Fake, Faker};
         use gateway_test_utils::GATEWAY_TIMEOUT;
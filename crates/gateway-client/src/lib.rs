@@ -299,6 +299,24 @@ impl Client {
         }
     }
 
+    /// Gets a _block_ and the corresponding _state update_ as raw, unparsed
+    /// JSON.
+    ///
+    /// Useful to check the reply against our own types without failing the
+    /// request when they no longer match.
+    pub async fn state_update_with_block_raw(
+        &self,
+        block: BlockId,
+    ) -> Result<bytes::Bytes, SequencerError> {
+        self.feeder_gateway_request()
+            .get_state_update()
+            .block(block)?
+            .param("includeBlock", "true")
+            .retry(self.retry)
+            .get_as_bytes()
+            .await
+    }
+
     fn gateway_request(&self) -> builder::Request<'_, builder::stage::Method> {
         builder::Request::builder(&self.inner, self.gateway.clone(), self.api_key.clone())
     }
@@ -325,7 +343,7 @@ impl GatewayApi for Client {
         let result: Dto = self
             .feeder_gateway_request()
             .get_state_update()
-            .block(BlockId::Pending)
+            .block(BlockId::Pending)?
             .param("includeBlock", "true")
             .retry(self.retry)
             .get()
@@ -348,7 +366,7 @@ impl GatewayApi for Client {
         let header: BlockHeader = self
             .feeder_gateway_request()
             .get_block()
-            .block(block)
+            .block(block)?
             .param("headerOnly", "true")
             .retry(self.retry)
             .get()
@@ -366,9 +384,9 @@ impl GatewayApi for Client {
         self.feeder_gateway_request()
             .get_class_by_hash()
             .class_hash(class_hash)
-            .block(BlockId::Pending)
+            .block(BlockId::Pending)?
             .retry(self.retry)
-            .get_as_bytes()
+            .get_as_bytes_resumable()
             .await
     }
 
@@ -381,9 +399,9 @@ impl GatewayApi for Client {
         self.feeder_gateway_request()
             .get_compiled_class_by_class_hash()
             .class_hash(class_hash)
-            .block(BlockId::Pending)
+            .block(BlockId::Pending)?
             .retry(self.retry)
-            .get_as_bytes()
+            .get_as_bytes_resumable()
             .await
     }
 
@@ -422,7 +440,7 @@ impl GatewayApi for Client {
         let result: Dto = self
             .feeder_gateway_request()
             .get_state_update()
-            .block(block)
+            .block(block)?
             .param("includeBlock", "true")
             .retry(self.retry)
             .get()
@@ -506,7 +524,7 @@ impl GatewayApi for Client {
     async fn block_traces(&self, block: BlockId) -> Result<BlockTrace, SequencerError> {
         self.feeder_gateway_request()
             .get_block_traces()
-            .block(block)
+            .block(block)?
             .retry(self.retry)
             .get()
             .await
@@ -529,7 +547,7 @@ impl GatewayApi for Client {
     async fn signature(&self, block: BlockId) -> Result<reply::BlockSignature, SequencerError> {
         self.feeder_gateway_request()
             .get_signature()
-            .block(block)
+            .block(block)?
             .retry(self.retry)
             .get()
             .await
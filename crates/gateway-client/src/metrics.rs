@@ -76,7 +76,7 @@ pub enum BlockTag {
 impl From<BlockId> for BlockTag {
     fn from(x: BlockId) -> Self {
         match x {
-            BlockId::Number(_) | BlockId::Hash(_) => Self::None,
+            BlockId::Number(_) | BlockId::Hash(_) | BlockId::L1Accepted => Self::None,
             BlockId::Latest => Self::Latest,
             BlockId::Pending => Self::Pending,
         }
@@ -189,7 +189,7 @@ pub async fn with_metrics<T>(
             SequencerError::ReqwestError(e) if e.is_timeout() => {
                 increment_failed(meta, REASON_TIMEOUT);
             }
-            SequencerError::ReqwestError(_) => {}
+            SequencerError::ReqwestError(_) | SequencerError::UnsupportedBlockTag => {}
         }
     })
 }
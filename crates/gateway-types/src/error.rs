@@ -14,6 +14,10 @@ pub enum SequencerError {
     /// not informative enough or bloated
     #[error("error decoding response body: invalid error variant")]
     InvalidStarknetErrorVariant,
+    /// The request addresses a block by a tag the gateway does not support.
+    /// Such requests are refused before being sent.
+    #[error("block tag not supported by the gateway")]
+    UnsupportedBlockTag,
 }
 
 /// Used for deserializing specific Starknet sequencer error data.
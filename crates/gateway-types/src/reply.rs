@@ -146,6 +146,9 @@ pub enum Status {
     Received,
     #[serde(rename = "PENDING")]
     Pending,
+    /// Replaces [Status::Pending] from Starknet 0.14.
+    #[serde(rename = "PRE_CONFIRMED")]
+    PreConfirmed,
     #[serde(rename = "REJECTED")]
     Rejected,
     #[serde(rename = "ACCEPTED_ON_L1")]
@@ -165,6 +168,7 @@ impl std::fmt::Display for Status {
             Status::NotReceived => write!(f, "NOT_RECEIVED"),
             Status::Received => write!(f, "RECEIVED"),
             Status::Pending => write!(f, "PENDING"),
+            Status::PreConfirmed => write!(f, "PRE_CONFIRMED"),
             Status::Rejected => write!(f, "REJECTED"),
             Status::AcceptedOnL1 => write!(f, "ACCEPTED_ON_L1"),
             Status::AcceptedOnL2 => write!(f, "ACCEPTED_ON_L2"),
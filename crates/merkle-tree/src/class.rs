@@ -1,5 +1,8 @@
 use anyhow::Context;
+use bitvec::prelude::Msb0;
+use bitvec::slice::BitSlice;
 use pathfinder_common::hash::PoseidonHash;
+use pathfinder_common::trie::TrieNode;
 use pathfinder_common::{
     BlockNumber,
     ClassCommitment,
@@ -110,6 +113,22 @@ impl<'tx> ClassCommitmentTree<'tx> {
 
         MerkleTree::<PoseidonHash, 251>::get_proofs(root, &storage, &keys)
     }
+
+    /// Visits every node of the class commitment trie at `block`. See
+    /// [`MerkleTree::visit_nodes`].
+    pub fn visit_nodes(
+        tx: &'tx Transaction<'tx>,
+        block: BlockNumber,
+        root: u64,
+        f: impl FnMut(&BitSlice<u8, Msb0>, TrieNode, Felt) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let storage = ClassStorage {
+            tx,
+            block: Some(block),
+        };
+
+        MerkleTree::<PoseidonHash, 251>::visit_nodes(root, &storage, f)
+    }
 }
 
 struct ClassStorage<'tx> {
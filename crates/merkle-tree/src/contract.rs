@@ -10,6 +10,7 @@ use anyhow::Context;
 use bitvec::prelude::Msb0;
 use bitvec::slice::BitSlice;
 use pathfinder_common::hash::PedersenHash;
+use pathfinder_common::trie::TrieNode;
 use pathfinder_common::{
     BlockNumber,
     ContractAddress,
@@ -20,10 +21,10 @@ use pathfinder_common::{
     StorageValue,
 };
 use pathfinder_crypto::Felt;
-use pathfinder_storage::{Transaction, TrieUpdate};
+use pathfinder_storage::{Storage, Transaction, TrieUpdate};
 
 use crate::merkle_node::InternalNode;
-use crate::tree::{GetProofError, MerkleTree, TrieNodeWithHash, Visit};
+use crate::tree::{GetProofError, MerkleTree, ProofNodeCache, TrieNodeWithHash, Visit};
 
 /// A [Patricia Merkle tree](MerkleTree) used to calculate commitments to a
 /// Starknet contract's storage.
@@ -118,6 +119,72 @@ impl<'tx> ContractsStorageTree<'tx> {
         MerkleTree::<PedersenHash, 251>::get_proofs(root, &storage, &keys)
     }
 
+    /// Visits every node of the contract's storage trie at `block`. See
+    /// [`MerkleTree::visit_nodes`].
+    pub fn visit_nodes(
+        tx: &'tx Transaction<'tx>,
+        contract: ContractAddress,
+        block: BlockNumber,
+        root: u64,
+        f: impl FnMut(&BitSlice<u8, Msb0>, TrieNode, Felt) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let storage = ContractStorage {
+            tx,
+            block: Some(block),
+            contract,
+        };
+
+        MerkleTree::<PedersenHash, 251>::visit_nodes(root, &storage, f)
+    }
+
+    /// Same as [`ContractsStorageTree::get_proofs`] but generates the proofs on
+    /// the rayon thread pool.
+    ///
+    /// The keys are split into one chunk per thread, each of which reads the
+    /// trie through its own database connection. Nodes read by one thread are
+    /// shared with the others, as proofs of keys in the same trie mostly walk
+    /// the same nodes near the root.
+    pub fn get_proofs_parallel(
+        storage: &Storage,
+        contract: ContractAddress,
+        block: BlockNumber,
+        keys: &[StorageAddress],
+        root: u64,
+    ) -> Result<Vec<Vec<TrieNodeWithHash>>, GetProofError> {
+        use rayon::prelude::*;
+
+        let cache = ProofNodeCache::default();
+        let chunk_size = keys.len().div_ceil(rayon::current_num_threads()).max(1);
+
+        let proofs = keys
+            .par_chunks(chunk_size)
+            .map(|keys| {
+                let mut connection = storage
+                    .connection()
+                    .context("Creating database connection")?;
+                let tx = connection
+                    .transaction()
+                    .context("Creating database transaction")?;
+                let storage = ContractStorage {
+                    tx: &tx,
+                    block: Some(block),
+                    contract,
+                };
+
+                let keys = keys
+                    .iter()
+                    .map(|addr| addr.0.view_bits())
+                    .collect::<Vec<_>>();
+
+                MerkleTree::<PedersenHash, 251>::get_proofs_with_cache(
+                    root, &storage, &keys, &cache,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(proofs.into_iter().flatten().collect())
+    }
+
     pub fn set(&mut self, address: StorageAddress, value: StorageValue) -> anyhow::Result<()> {
         let key = address.view_bits().to_owned();
         self.tree.set(&self.storage, key, value.0)
@@ -245,6 +312,22 @@ impl<'tx> StorageCommitmentTree<'tx> {
         MerkleTree::<PedersenHash, 251>::get_proofs(root, &storage, &keys)
     }
 
+    /// Visits every node of the storage commitment trie at `block`. See
+    /// [`MerkleTree::visit_nodes`].
+    pub fn visit_nodes(
+        tx: &'tx Transaction<'tx>,
+        block: BlockNumber,
+        root: u64,
+        f: impl FnMut(&BitSlice<u8, Msb0>, TrieNode, Felt) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let storage = StorageTrieStorage {
+            tx,
+            block: Some(block),
+        };
+
+        MerkleTree::<PedersenHash, 251>::visit_nodes(root, &storage, f)
+    }
+
     /// See [`MerkleTree::dfs`]
     pub fn dfs<B, F: FnMut(&InternalNode, &BitSlice<u8, Msb0>) -> ControlFlow<B, Visit>>(
         &mut self,
@@ -1,9 +1,14 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use bitvec::prelude::{BitSlice, BitVec, Msb0};
 use bitvec::view::BitView;
 use pathfinder_common::hash::FeltHash;
+use pathfinder_common::trie::TrieNode;
 use pathfinder_crypto::Felt;
-use pathfinder_storage::StoredNode;
+use pathfinder_storage::{Node, NodeRef, StoredNode, TrieUpdate};
 
-use crate::tree::MerkleTree;
+use crate::tree::{GetProofError, MerkleTree, TrieNodeWithHash};
 
 /// A [Patricia Merkle tree](MerkleTree) which can be used to calculate
 /// transaction or event commitments.
@@ -16,12 +21,16 @@ use crate::tree::MerkleTree;
 /// More information about these commitments can be found in the Starknet [documentation](https://docs.starknet.io/documentation/architecture_and_concepts/Blocks/header/).
 pub struct TransactionOrEventTree<H: FeltHash> {
     tree: MerkleTree<H, 64>,
+    /// Leaves are not part of the committed nodes, but are required to
+    /// generate proofs.
+    leaves: HashMap<BitVec<u8, Msb0>, Felt>,
 }
 
 impl<H: FeltHash> Default for TransactionOrEventTree<H> {
     fn default() -> Self {
         Self {
             tree: MerkleTree::empty(),
+            leaves: Default::default(),
         }
     }
 }
@@ -48,7 +57,8 @@ impl crate::storage::Storage for NullStorage {
 
 impl<H: FeltHash> TransactionOrEventTree<H> {
     pub fn set(&mut self, index: u64, value: Felt) -> anyhow::Result<()> {
-        let key = index.to_be_bytes().view_bits().to_owned();
+        let key: BitVec<u8, Msb0> = index.to_be_bytes().view_bits().to_owned();
+        self.leaves.insert(key.clone(), value);
         self.tree.set(&NullStorage {}, key, value)
     }
 
@@ -57,6 +67,124 @@ impl<H: FeltHash> TransactionOrEventTree<H> {
             .commit(&NullStorage {})
             .map(|update| update.root_commitment)
     }
+
+    /// Commits the tree and generates a proof for the leaf at `index`. See
+    /// [`MerkleTree::get_proof`].
+    ///
+    /// Returns the root along with the proof nodes, root first.
+    pub fn commit_and_prove(self, index: u64) -> anyhow::Result<(Felt, Vec<TrieNodeWithHash>)> {
+        let update = self.tree.commit(&NullStorage {})?;
+        let root = update
+            .nodes_added
+            .len()
+            .checked_sub(1)
+            .context("Cannot prove a leaf of an empty tree")? as u64;
+        let storage = CommittedStorage::new(&update, self.leaves)?;
+
+        let key = index.to_be_bytes();
+        let proof =
+            MerkleTree::<H, 64>::get_proof(root, &storage, key.view_bits()).map_err(|error| {
+                match error {
+                    GetProofError::Internal(error) => error,
+                    GetProofError::StorageNodeMissing(index) => {
+                        anyhow::anyhow!("Committed node {index} is missing")
+                    }
+                }
+            })?;
+
+        Ok((update.root_commitment, proof))
+    }
+
+    /// Checks that `proof`, with the nodes ordered root first, proves `value`
+    /// to be the leaf at `index` of the tree committed to by `root`.
+    pub fn verify_proof<'a>(
+        root: Felt,
+        index: u64,
+        proof: impl IntoIterator<Item = &'a TrieNode>,
+        value: Felt,
+    ) -> bool {
+        let key = index.to_be_bytes();
+        let mut remaining = key.view_bits::<Msb0>();
+        let mut expected_hash = root;
+        for node in proof {
+            if node.hash::<H>() != expected_hash {
+                return false;
+            }
+            match node {
+                TrieNode::Binary { left, right } => {
+                    let Some((bit, rest)) = remaining.split_first() else {
+                        return false;
+                    };
+                    expected_hash = if *bit { *right } else { *left };
+                    remaining = rest;
+                }
+                TrieNode::Edge { child, path } => {
+                    if !remaining.starts_with(path.as_bitslice()) {
+                        return false;
+                    }
+                    expected_hash = *child;
+                    remaining = &remaining[path.len()..];
+                }
+            }
+        }
+        remaining.is_empty() && expected_hash == value
+    }
+}
+
+/// [Storage](crate::storage::Storage) over the nodes of a freshly committed
+/// tree, indexed by their position in [TrieUpdate::nodes_added].
+struct CommittedStorage {
+    nodes: Vec<(Felt, StoredNode)>,
+    leaves: HashMap<BitVec<u8, Msb0>, Felt>,
+}
+
+impl CommittedStorage {
+    fn new(update: &TrieUpdate, leaves: HashMap<BitVec<u8, Msb0>, Felt>) -> anyhow::Result<Self> {
+        fn index(node: &NodeRef) -> anyhow::Result<u64> {
+            match node {
+                NodeRef::Index(index) => Ok(*index as u64),
+                NodeRef::StorageIndex(_) => {
+                    anyhow::bail!("Ephemeral tree references a persisted node")
+                }
+            }
+        }
+
+        let nodes = update
+            .nodes_added
+            .iter()
+            .map(|(hash, node)| {
+                let node = match node {
+                    Node::Binary { left, right } => StoredNode::Binary {
+                        left: index(left)?,
+                        right: index(right)?,
+                    },
+                    Node::Edge { child, path } => StoredNode::Edge {
+                        child: index(child)?,
+                        path: path.clone(),
+                    },
+                    Node::LeafBinary => StoredNode::LeafBinary,
+                    Node::LeafEdge { path } => StoredNode::LeafEdge { path: path.clone() },
+                };
+                Ok((*hash, node))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self { nodes, leaves })
+    }
+}
+
+impl crate::storage::Storage for CommittedStorage {
+    fn get(&self, index: u64) -> anyhow::Result<Option<StoredNode>> {
+        Ok(self.nodes.get(index as usize).map(|(_, node)| node.clone()))
+    }
+
+    fn hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
+        Ok(self.nodes.get(index as usize).map(|(hash, _)| *hash))
+    }
+
+    fn leaf(&self, path: &BitSlice<u8, Msb0>) -> anyhow::Result<Option<Felt>> {
+        Ok(self.leaves.get(path).copied())
+    }
 }
 
 #[cfg(test)]
@@ -85,4 +213,41 @@ mod tests {
 
         assert_eq!(expected_root_hash, computed_root_hash);
     }
+
+    #[test]
+    fn proof() {
+        use pathfinder_common::hash::PoseidonHash;
+
+        let values = [11u64, 22, 33, 44, 55].map(Felt::from);
+        let tree = || {
+            let mut tree: TransactionOrEventTree<PoseidonHash> = Default::default();
+            for (idx, value) in values.iter().enumerate() {
+                tree.set(idx as u64, *value).unwrap();
+            }
+            tree
+        };
+        let expected_root = tree().commit().unwrap();
+
+        for (idx, value) in values.iter().enumerate() {
+            let (root, proof) = tree().commit_and_prove(idx as u64).unwrap();
+            assert_eq!(root, expected_root);
+
+            for (node, hash) in &proof {
+                assert_eq!(node.hash::<PoseidonHash>(), *hash);
+            }
+            let nodes = proof.iter().map(|(node, _)| node);
+            assert!(TransactionOrEventTree::<PoseidonHash>::verify_proof(
+                root,
+                idx as u64,
+                nodes.clone(),
+                *value
+            ));
+            assert!(!TransactionOrEventTree::<PoseidonHash>::verify_proof(
+                root,
+                idx as u64,
+                nodes,
+                *value + Felt::ONE
+            ));
+        }
+    }
 }
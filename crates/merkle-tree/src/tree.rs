@@ -55,6 +55,7 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::ops::ControlFlow;
 use std::rc::Rc;
+use std::sync::RwLock;
 
 use anyhow::Context;
 use bitvec::prelude::{BitSlice, BitVec, Msb0};
@@ -551,9 +552,18 @@ impl<H: FeltHash, const HEIGHT: usize> MerkleTree<H, HEIGHT> {
         storage: &impl Storage,
         keys: &[&BitSlice<u8, Msb0>],
     ) -> Result<Vec<Vec<TrieNodeWithHash>>, GetProofError> {
-        let mut node_cache: HashMap<u64, StoredNode> = HashMap::new();
-        let mut node_hash_cache: HashMap<u64, Felt> = HashMap::new();
+        Self::get_proofs_with_cache(root, storage, keys, &ProofNodeCache::default())
+    }
 
+    /// Same as [`MerkleTree::get_proofs`] but looks up nodes in `cache` first,
+    /// which allows sharing it between threads generating proofs for the same
+    /// trie.
+    pub fn get_proofs_with_cache(
+        root: u64,
+        storage: &impl Storage,
+        keys: &[&BitSlice<u8, Msb0>],
+        cache: &ProofNodeCache,
+    ) -> Result<Vec<Vec<TrieNodeWithHash>>, GetProofError> {
         let mut proofs = vec![];
 
         for key in keys {
@@ -563,13 +573,14 @@ impl<H: FeltHash, const HEIGHT: usize> MerkleTree<H, HEIGHT> {
             let mut next = Some(root);
             let mut height = 0;
             while let Some(index) = next.take() {
-                let node = match node_cache.get(&index) {
-                    Some(node) => node.clone(),
+                let cached = cache.nodes.read().unwrap().get(&index).cloned();
+                let node = match cached {
+                    Some(node) => node,
                     None => {
                         let Some(node) = storage.get(index).context("Resolving node")? else {
                             return Err(GetProofError::StorageNodeMissing(index));
                         };
-                        node_cache.insert(index, node.clone());
+                        cache.nodes.write().unwrap().insert(index, node.clone());
                         node
                     }
                 };
@@ -648,14 +659,15 @@ impl<H: FeltHash, const HEIGHT: usize> MerkleTree<H, HEIGHT> {
                     }
                 };
 
-                let node_hash = match node_hash_cache.get(&index) {
-                    Some(&hash) => hash,
+                let cached = cache.hashes.read().unwrap().get(&index).copied();
+                let node_hash = match cached {
+                    Some(hash) => hash,
                     None => {
                         let hash = storage
                             .hash(index)
                             .context("Querying node hash")?
                             .context("Node hash is missing")?;
-                        node_hash_cache.insert(index, hash);
+                        cache.hashes.write().unwrap().insert(index, hash);
                         hash
                     }
                 };
@@ -668,6 +680,94 @@ impl<H: FeltHash, const HEIGHT: usize> MerkleTree<H, HEIGHT> {
         Ok(proofs)
     }
 
+    /// Visits every node of the trie rooted at `root`, parents before their
+    /// children. `f` receives the path, contents and hash of each node.
+    ///
+    /// Leaves are not visited on their own, their values are the children of
+    /// the nodes at the bottom of the trie.
+    pub fn visit_nodes(
+        root: u64,
+        storage: &impl Storage,
+        mut f: impl FnMut(&BitSlice<u8, Msb0>, TrieNode, Felt) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let child_hash = |index: u64| -> anyhow::Result<Felt> {
+            storage
+                .hash(index)
+                .context("Querying child's hash")?
+                .with_context(|| format!("Hash of node {index} is missing"))
+        };
+        let leaf = |path: &BitSlice<u8, Msb0>| -> anyhow::Result<Felt> {
+            storage
+                .leaf(path)
+                .context("Querying leaf")?
+                .context("Leaf is missing")
+        };
+
+        let mut visiting = vec![(root, BitVec::<u8, Msb0>::new())];
+        while let Some((index, path)) = visiting.pop() {
+            let node = storage
+                .get(index)
+                .context("Resolving node")?
+                .with_context(|| format!("Node {index} is missing"))?;
+
+            let node = match node {
+                StoredNode::Binary { left, right } => {
+                    let mut right_path = path.clone();
+                    right_path.push(Direction::Right.into());
+                    visiting.push((right, right_path));
+                    let mut left_path = path.clone();
+                    left_path.push(Direction::Left.into());
+                    visiting.push((left, left_path));
+
+                    TrieNode::Binary {
+                        left: child_hash(left)?,
+                        right: child_hash(right)?,
+                    }
+                }
+                StoredNode::Edge {
+                    child,
+                    path: edge_path,
+                } => {
+                    let mut child_path = path.clone();
+                    child_path.extend_from_bitslice(&edge_path);
+                    visiting.push((child, child_path));
+
+                    TrieNode::Edge {
+                        child: child_hash(child)?,
+                        path: edge_path,
+                    }
+                }
+                StoredNode::LeafBinary => {
+                    let mut leaf_path = path.clone();
+                    leaf_path.push(Direction::Left.into());
+                    let left = leaf(&leaf_path)?;
+                    leaf_path.pop();
+                    leaf_path.push(Direction::Right.into());
+                    let right = leaf(&leaf_path)?;
+
+                    TrieNode::Binary { left, right }
+                }
+                StoredNode::LeafEdge { path: edge_path } => {
+                    let mut leaf_path = path.clone();
+                    leaf_path.extend_from_bitslice(&edge_path);
+
+                    TrieNode::Edge {
+                        child: leaf(&leaf_path)?,
+                        path: edge_path,
+                    }
+                }
+            };
+
+            let hash = storage
+                .hash(index)
+                .context("Querying node hash")?
+                .context("Node hash is missing")?;
+            f(&path, node, hash)?;
+        }
+
+        Ok(())
+    }
+
     /// Traverses from the current root towards destination node.
     /// Returns the list of nodes along the path.
     ///
@@ -911,6 +1011,14 @@ impl<H: FeltHash, const HEIGHT: usize> MerkleTree<H, HEIGHT> {
 
 pub type TrieNodeWithHash = (TrieNode, Felt);
 
+/// Nodes and node hashes read while generating proofs for a single trie, see
+/// [`MerkleTree::get_proofs_with_cache`].
+#[derive(Debug, Default)]
+pub struct ProofNodeCache {
+    nodes: RwLock<HashMap<u64, StoredNode>>,
+    hashes: RwLock<HashMap<u64, Felt>>,
+}
+
 #[derive(Debug)]
 pub enum GetProofError {
     Internal(anyhow::Error),
@@ -2568,4 +2676,56 @@ mod tests {
             }
         }
     }
+
+    mod visit_nodes {
+        use super::*;
+
+        #[test]
+        fn visits_every_node_and_leaf() {
+            let mut uut = TestTree::empty();
+            let mut storage = TestStorage::default();
+
+            let leaves = [
+                (felt!("0x99cadc82"), felt!("0x1")),
+                (felt!("0x901823"), felt!("0x2")),
+                (felt!("0x8975"), felt!("0x3")),
+                (felt!("0x8974"), felt!("0x4")),
+            ];
+            for (key, value) in leaves {
+                uut.set(&storage, key.view_bits().to_bitvec(), value)
+                    .unwrap();
+            }
+            let (root, root_idx) = commit_and_persist_with_pruning(uut, &mut storage);
+
+            let mut hashes = Vec::new();
+            let mut visited_leaves = HashMap::new();
+            TestTree::visit_nodes(root_idx, &storage, |path, node, hash| {
+                assert_eq!(node.hash::<PedersenHash>(), hash);
+                hashes.push(hash);
+
+                match node {
+                    TrieNode::Binary { left, right } if path.len() == 250 => {
+                        let mut leaf_path = path.to_bitvec();
+                        leaf_path.push(false);
+                        visited_leaves.insert(Felt::from_bits(&leaf_path).unwrap(), left);
+                        leaf_path.set(250, true);
+                        visited_leaves.insert(Felt::from_bits(&leaf_path).unwrap(), right);
+                    }
+                    TrieNode::Edge { child, path: edge } if path.len() + edge.len() == 251 => {
+                        let mut leaf_path = path.to_bitvec();
+                        leaf_path.extend_from_bitslice(&edge);
+                        visited_leaves.insert(Felt::from_bits(&leaf_path).unwrap(), child);
+                    }
+                    _ => {}
+                }
+                Ok(())
+            })
+            .unwrap();
+
+            // Parents are visited before their children.
+            assert_eq!(hashes.first(), Some(&root));
+            assert_eq!(hashes.len(), storage.nodes.len());
+            assert_eq!(visited_leaves, HashMap::from(leaves));
+        }
+    }
 }
@@ -24,7 +24,12 @@ use libp2p::swarm::{
     ToSwarm,
 };
 use libp2p::{autonat, dcutr, identify, identity, ping, relay, Multiaddr, PeerId, StreamProtocol};
-use p2p_proto::class::{ClassesRequest, ClassesResponse};
+use p2p_proto::class::{
+    ClassesByHashRequest,
+    ClassesByHashResponse,
+    ClassesRequest,
+    ClassesResponse,
+};
 use p2p_proto::event::{EventsRequest, EventsResponse};
 use p2p_proto::header::{BlockHeadersRequest, BlockHeadersResponse};
 use p2p_proto::state::{StateDiffsRequest, StateDiffsResponse};
@@ -38,7 +43,7 @@ pub use builder::Builder;
 use crate::peers::{Connectivity, Direction, KeyedNetworkGroup, Peer, PeerSet};
 use crate::secret::Secret;
 use crate::sync::codec;
-use crate::Config;
+use crate::{Config, SyncProtocolVersion};
 
 /// The default kademlia protocol name for a given Starknet chain.
 pub fn kademlia_protocol_name(chain_id: ChainId) -> StreamProtocol {
@@ -68,6 +73,7 @@ pub struct Inner {
     gossipsub: gossipsub::Behaviour,
     header_sync: p2p_stream::Behaviour<codec::Headers>,
     class_sync: p2p_stream::Behaviour<codec::Classes>,
+    class_by_hash_sync: p2p_stream::Behaviour<codec::ClassesByHash>,
     state_diff_sync: p2p_stream::Behaviour<codec::StateDiffs>,
     transaction_sync: p2p_stream::Behaviour<codec::Transactions>,
     event_sync: p2p_stream::Behaviour<codec::Events>,
@@ -173,6 +179,7 @@ impl NetworkBehaviour for Behaviour {
                             min_ping: None,
                             evicted: false,
                             useful: true,
+                            sync_version: None,
                         },
                     );
                     self.pending_events.push_back(ToSwarm::CloseConnection {
@@ -202,6 +209,7 @@ impl NetworkBehaviour for Behaviour {
                         min_ping: None,
                         evicted: false,
                         useful: true,
+                        sync_version: None,
                     },
                 );
             }
@@ -232,6 +240,7 @@ impl NetworkBehaviour for Behaviour {
                             keyed_network_group: None,
                             evicted: false,
                             useful: true,
+                            sync_version: None,
                         },
                     );
                 }
@@ -433,6 +442,7 @@ impl NetworkBehaviour for Behaviour {
                         min_ping: None,
                         evicted: false,
                         useful: true,
+                        sync_version: None,
                     },
                 );
             }
@@ -488,6 +498,14 @@ impl Behaviour {
         }
     }
 
+    /// Notify the behaviour of the sync protocol version negotiated for a
+    /// request to or from the peer.
+    pub fn sync_version_negotiated(&mut self, peer_id: PeerId, version: SyncProtocolVersion) {
+        self.peers.update(peer_id, |peer| {
+            peer.sync_version = Some(version);
+        });
+    }
+
     /// Only allow one connection per peer. If the peer is already connected,
     /// close the new connection.
     fn check_duplicate_connection(&mut self, peer_id: PeerId) -> Result<(), ConnectionDenied> {
@@ -754,6 +772,10 @@ impl Behaviour {
         &mut self.inner.class_sync
     }
 
+    pub fn classes_by_hash_sync_mut(&mut self) -> &mut p2p_stream::Behaviour<codec::ClassesByHash> {
+        &mut self.inner.class_by_hash_sync
+    }
+
     pub fn state_diffs_sync_mut(&mut self) -> &mut p2p_stream::Behaviour<codec::StateDiffs> {
         &mut self.inner.state_diff_sync
     }
@@ -804,6 +826,7 @@ pub enum Event {
     Gossipsub(gossipsub::Event),
     HeadersSync(p2p_stream::Event<BlockHeadersRequest, BlockHeadersResponse>),
     ClassesSync(p2p_stream::Event<ClassesRequest, ClassesResponse>),
+    ClassesByHashSync(p2p_stream::Event<ClassesByHashRequest, ClassesByHashResponse>),
     StateDiffsSync(p2p_stream::Event<StateDiffsRequest, StateDiffsResponse>),
     TransactionsSync(p2p_stream::Event<TransactionsRequest, TransactionsResponse>),
     EventsSync(p2p_stream::Event<EventsRequest, EventsResponse>),
@@ -863,6 +886,12 @@ impl From<p2p_stream::Event<ClassesRequest, ClassesResponse>> for Event {
     }
 }
 
+impl From<p2p_stream::Event<ClassesByHashRequest, ClassesByHashResponse>> for Event {
+    fn from(event: p2p_stream::Event<ClassesByHashRequest, ClassesByHashResponse>) -> Self {
+        Event::ClassesByHashSync(event)
+    }
+}
+
 impl From<p2p_stream::Event<StateDiffsRequest, StateDiffsResponse>> for Event {
     fn from(event: p2p_stream::Event<StateDiffsRequest, StateDiffsResponse>) -> Self {
         Event::StateDiffsSync(event)
@@ -10,7 +10,7 @@ use super::{Behaviour, BehaviourWithRelayTransport};
 use crate::behaviour::Inner;
 use crate::peers::PeerSet;
 use crate::secret::Secret;
-use crate::sync::codec;
+use crate::sync::{codec, protocol};
 use crate::{kademlia_protocol_name, Config};
 
 pub struct Builder {
@@ -19,6 +19,7 @@ pub struct Builder {
     cfg: Config,
     header_sync: Option<p2p_stream::Behaviour<codec::Headers>>,
     class_sync: Option<p2p_stream::Behaviour<codec::Classes>>,
+    class_by_hash_sync: Option<p2p_stream::Behaviour<codec::ClassesByHash>>,
     state_diff_sync: Option<p2p_stream::Behaviour<codec::StateDiffs>>,
     transaction_sync: Option<p2p_stream::Behaviour<codec::Transactions>>,
     event_sync: Option<p2p_stream::Behaviour<codec::Events>>,
@@ -32,6 +33,7 @@ impl Builder {
             cfg,
             header_sync: None,
             class_sync: None,
+            class_by_hash_sync: None,
             state_diff_sync: None,
             transaction_sync: None,
             event_sync: None,
@@ -56,6 +58,15 @@ impl Builder {
         self
     }
 
+    #[allow(unused)]
+    pub fn class_by_hash_sync_behaviour(
+        mut self,
+        behaviour: p2p_stream::Behaviour<codec::ClassesByHash>,
+    ) -> Self {
+        self.class_by_hash_sync = Some(behaviour);
+        self
+    }
+
     #[allow(unused)]
     pub fn state_diff_sync_behaviour(
         mut self,
@@ -87,6 +98,7 @@ impl Builder {
             cfg,
             header_sync,
             class_sync,
+            class_by_hash_sync,
             state_diff_sync,
             transaction_sync,
             event_sync,
@@ -135,16 +147,48 @@ impl Builder {
             .request_timeout(cfg.stream_timeout)
             .max_concurrent_streams(cfg.max_concurrent_streams);
 
-        let header_sync = header_sync
-            .unwrap_or_else(|| p2p_stream::Behaviour::<codec::Headers>::new(p2p_stream_cfg));
-        let class_sync = class_sync
-            .unwrap_or_else(|| p2p_stream::Behaviour::<codec::Classes>::new(p2p_stream_cfg));
-        let state_diff_sync = state_diff_sync
-            .unwrap_or_else(|| p2p_stream::Behaviour::<codec::StateDiffs>::new(p2p_stream_cfg));
-        let transaction_sync = transaction_sync
-            .unwrap_or_else(|| p2p_stream::Behaviour::<codec::Transactions>::new(p2p_stream_cfg));
-        let event_sync = event_sync
-            .unwrap_or_else(|| p2p_stream::Behaviour::<codec::Events>::new(p2p_stream_cfg));
+        let header_sync = header_sync.unwrap_or_else(|| {
+            p2p_stream::Behaviour::<codec::Headers>::with_codec_and_protocols(
+                Default::default(),
+                protocol::Headers::all(),
+                p2p_stream_cfg,
+            )
+        });
+        let class_sync = class_sync.unwrap_or_else(|| {
+            p2p_stream::Behaviour::<codec::Classes>::with_codec_and_protocols(
+                Default::default(),
+                protocol::Classes::all(),
+                p2p_stream_cfg,
+            )
+        });
+        let class_by_hash_sync = class_by_hash_sync.unwrap_or_else(|| {
+            p2p_stream::Behaviour::<codec::ClassesByHash>::with_codec_and_protocols(
+                Default::default(),
+                protocol::ClassesByHash::all(),
+                p2p_stream_cfg,
+            )
+        });
+        let state_diff_sync = state_diff_sync.unwrap_or_else(|| {
+            p2p_stream::Behaviour::<codec::StateDiffs>::with_codec_and_protocols(
+                Default::default(),
+                protocol::StateDiffs::all(),
+                p2p_stream_cfg,
+            )
+        });
+        let transaction_sync = transaction_sync.unwrap_or_else(|| {
+            p2p_stream::Behaviour::<codec::Transactions>::with_codec_and_protocols(
+                Default::default(),
+                protocol::Transactions::all(),
+                p2p_stream_cfg,
+            )
+        });
+        let event_sync = event_sync.unwrap_or_else(|| {
+            p2p_stream::Behaviour::<codec::Events>::with_codec_and_protocols(
+                Default::default(),
+                protocol::Events::all(),
+                p2p_stream_cfg,
+            )
+        });
 
         (
             Behaviour {
@@ -167,6 +211,7 @@ impl Builder {
                     gossipsub,
                     header_sync,
                     class_sync,
+                    class_by_hash_sync,
                     state_diff_sync,
                     transaction_sync,
                     event_sync,
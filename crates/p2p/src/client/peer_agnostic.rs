@@ -49,6 +49,7 @@ use traits::{
     ClassStream,
     EventStream,
     HeaderStream,
+    PeerPenalty,
     StateDiffStream,
     StreamItem,
     TransactionStream,
@@ -291,6 +292,12 @@ impl EventStream for Client {
     }
 }
 
+impl PeerPenalty for Client {
+    async fn penalize(self, peer: PeerId) {
+        self.inner.not_useful(peer).await
+    }
+}
+
 impl BlockClient for Client {
     async fn transactions_for_block(
         self,
@@ -109,3 +109,9 @@ pub trait BlockClient {
         )>,
     > + Send;
 }
+
+pub trait PeerPenalty {
+    /// Reports a peer which served data that failed verification. The peer
+    /// becomes a candidate for eviction.
+    fn penalize(self, peer: PeerId) -> impl Future<Output = ()> + Send;
+}
@@ -7,7 +7,12 @@ use anyhow::Context;
 use futures::channel::mpsc::Receiver as ResponseReceiver;
 use libp2p::gossipsub::IdentTopic;
 use libp2p::{Multiaddr, PeerId};
-use p2p_proto::class::{ClassesRequest, ClassesResponse};
+use p2p_proto::class::{
+    ClassesByHashRequest,
+    ClassesByHashResponse,
+    ClassesRequest,
+    ClassesResponse,
+};
 use p2p_proto::event::{EventsRequest, EventsResponse};
 use p2p_proto::header::{BlockHeadersRequest, BlockHeadersResponse, NewBlock};
 use p2p_proto::state::{StateDiffsRequest, StateDiffsResponse};
@@ -178,6 +183,13 @@ impl Client {
         ClassesResponse
     );
 
+    impl_send!(
+        send_classes_by_hash_sync_request,
+        SendClassesByHashSyncRequest,
+        ClassesByHashRequest,
+        ClassesByHashResponse
+    );
+
     impl_send!(
         send_state_diffs_sync_request,
         SendStateDiffsSyncRequest,
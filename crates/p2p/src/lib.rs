@@ -9,7 +9,12 @@ use libp2p::identity::Keypair;
 use libp2p::kad::RecordKey;
 use libp2p::{Multiaddr, PeerId};
 use main_loop::MainLoop;
-use p2p_proto::class::{ClassesRequest, ClassesResponse};
+use p2p_proto::class::{
+    ClassesByHashRequest,
+    ClassesByHashResponse,
+    ClassesRequest,
+    ClassesResponse,
+};
 use p2p_proto::event::{EventsRequest, EventsResponse};
 use p2p_proto::header::{BlockHeadersRequest, BlockHeadersResponse, NewBlock};
 use p2p_proto::state::{StateDiffsRequest, StateDiffsResponse};
@@ -38,7 +43,7 @@ use builder::Builder;
 use client::peer_aware::Client;
 pub use libp2p;
 pub use peer_data::PeerData;
-pub use sync::protocol::PROTOCOLS;
+pub use sync::protocol::{protocols, Version as SyncProtocolVersion};
 
 pub fn new(keypair: Keypair, cfg: Config, chain_id: ChainId) -> (Client, EventReceiver, MainLoop) {
     Builder::new(keypair, cfg, chain_id).build()
@@ -127,6 +132,13 @@ enum Command {
         request: ClassesRequest,
         sender: oneshot::Sender<anyhow::Result<ResponseReceiver<std::io::Result<ClassesResponse>>>>,
     },
+    SendClassesByHashSyncRequest {
+        peer_id: PeerId,
+        request: ClassesByHashRequest,
+        sender: oneshot::Sender<
+            anyhow::Result<ResponseReceiver<std::io::Result<ClassesByHashResponse>>>,
+        >,
+    },
     SendStateDiffsSyncRequest {
         peer_id: PeerId,
         request: StateDiffsRequest,
@@ -171,26 +183,49 @@ pub enum Event {
     },
     InboundHeadersSyncRequest {
         from: PeerId,
+        /// The protocol version the request was made in, and the responses
+        /// have to be sent in.
+        version: SyncProtocolVersion,
         request: BlockHeadersRequest,
         channel: ResponseSender<BlockHeadersResponse>,
     },
     InboundClassesSyncRequest {
         from: PeerId,
+        /// The protocol version the request was made in, and the responses
+        /// have to be sent in.
+        version: SyncProtocolVersion,
         request: ClassesRequest,
         channel: ResponseSender<ClassesResponse>,
     },
+    InboundClassesByHashSyncRequest {
+        from: PeerId,
+        /// The protocol version the request was made in, and the responses
+        /// have to be sent in.
+        version: SyncProtocolVersion,
+        request: ClassesByHashRequest,
+        channel: ResponseSender<ClassesByHashResponse>,
+    },
     InboundStateDiffsSyncRequest {
         from: PeerId,
+        /// The protocol version the request was made in, and the responses
+        /// have to be sent in.
+        version: SyncProtocolVersion,
         request: StateDiffsRequest,
         channel: ResponseSender<StateDiffsResponse>,
     },
     InboundTransactionsSyncRequest {
         from: PeerId,
+        /// The protocol version the request was made in, and the responses
+        /// have to be sent in.
+        version: SyncProtocolVersion,
         request: TransactionsRequest,
         channel: ResponseSender<TransactionsResponse>,
     },
     InboundEventsSyncRequest {
         from: PeerId,
+        /// The protocol version the request was made in, and the responses
+        /// have to be sent in.
+        version: SyncProtocolVersion,
         request: EventsRequest,
         channel: ResponseSender<EventsResponse>,
     },
@@ -10,7 +10,7 @@ use libp2p::multiaddr::Protocol;
 use libp2p::swarm::dial_opts::DialOpts;
 use libp2p::swarm::SwarmEvent;
 use libp2p::{identify, PeerId};
-use p2p_proto::class::ClassesResponse;
+use p2p_proto::class::{ClassesByHashResponse, ClassesResponse};
 use p2p_proto::event::EventsResponse;
 use p2p_proto::header::BlockHeadersResponse;
 use p2p_proto::state::StateDiffsResponse;
@@ -22,7 +22,15 @@ use tokio::time::Duration;
 
 #[cfg(test)]
 use crate::test_utils;
-use crate::{behaviour, Command, EmptyResultSender, Event, TestCommand, TestEvent};
+use crate::{
+    behaviour,
+    Command,
+    EmptyResultSender,
+    Event,
+    SyncProtocolVersion,
+    TestCommand,
+    TestEvent,
+};
 
 pub struct MainLoop {
     swarm: libp2p::swarm::Swarm<behaviour::Behaviour>,
@@ -51,6 +59,10 @@ struct PendingRequests {
         OutboundRequestId,
         oneshot::Sender<anyhow::Result<ResponseReceiver<std::io::Result<ClassesResponse>>>>,
     >,
+    pub classes_by_hash: HashMap<
+        OutboundRequestId,
+        oneshot::Sender<anyhow::Result<ResponseReceiver<std::io::Result<ClassesByHashResponse>>>>,
+    >,
     pub state_diffs: HashMap<
         OutboundRequestId,
         oneshot::Sender<anyhow::Result<ResponseReceiver<std::io::Result<StateDiffsResponse>>>>,
@@ -470,16 +482,19 @@ impl MainLoop {
             SwarmEvent::Behaviour(behaviour::Event::HeadersSync(
                 p2p_stream::Event::InboundRequest {
                     request_id,
+                    protocol,
                     request,
                     peer,
                     channel,
                 },
             )) => {
                 tracing::debug!(?request, %peer, %request_id, "Received sync request");
+                let version = self.negotiated_sync_version(peer, &protocol);
 
                 self.event_sender
                     .send(Event::InboundHeadersSyncRequest {
                         from: peer,
+                        version,
                         request,
                         channel,
                     })
@@ -490,10 +505,12 @@ impl MainLoop {
                 p2p_stream::Event::OutboundRequestSentAwaitingResponses {
                     request_id,
                     peer,
+                    protocol,
                     channel,
                 },
             )) => {
                 tracing::debug!(%peer, %request_id, "Header sync request sent");
+                self.negotiated_sync_version(peer, &protocol);
 
                 let _ = self
                     .pending_sync_requests
@@ -505,16 +522,19 @@ impl MainLoop {
             SwarmEvent::Behaviour(behaviour::Event::ClassesSync(
                 p2p_stream::Event::InboundRequest {
                     request_id,
+                    protocol,
                     request,
                     peer,
                     channel,
                 },
             )) => {
                 tracing::debug!(?request, %peer, %request_id, "Received sync request");
+                let version = self.negotiated_sync_version(peer, &protocol);
 
                 self.event_sender
                     .send(Event::InboundClassesSyncRequest {
                         from: peer,
+                        version,
                         request,
                         channel,
                     })
@@ -525,10 +545,12 @@ impl MainLoop {
                 p2p_stream::Event::OutboundRequestSentAwaitingResponses {
                     request_id,
                     peer,
+                    protocol,
                     channel,
                 },
             )) => {
                 tracing::debug!(%peer, %request_id, "Classes sync request sent");
+                self.negotiated_sync_version(peer, &protocol);
 
                 let _ = self
                     .pending_sync_requests
@@ -537,19 +559,62 @@ impl MainLoop {
                     .expect("Classes sync request still to be pending")
                     .send(Ok(channel));
             }
+            SwarmEvent::Behaviour(behaviour::Event::ClassesByHashSync(
+                p2p_stream::Event::InboundRequest {
+                    request_id,
+                    protocol,
+                    request,
+                    peer,
+                    channel,
+                },
+            )) => {
+                tracing::debug!(?request, %peer, %request_id, "Received sync request");
+                let version = self.negotiated_sync_version(peer, &protocol);
+
+                self.event_sender
+                    .send(Event::InboundClassesByHashSyncRequest {
+                        from: peer,
+                        version,
+                        request,
+                        channel,
+                    })
+                    .await
+                    .expect("Event receiver not to be dropped");
+            }
+            SwarmEvent::Behaviour(behaviour::Event::ClassesByHashSync(
+                p2p_stream::Event::OutboundRequestSentAwaitingResponses {
+                    request_id,
+                    peer,
+                    protocol,
+                    channel,
+                },
+            )) => {
+                tracing::debug!(%peer, %request_id, "Classes by hash sync request sent");
+                self.negotiated_sync_version(peer, &protocol);
+
+                let _ = self
+                    .pending_sync_requests
+                    .classes_by_hash
+                    .remove(&request_id)
+                    .expect("Classes by hash sync request still to be pending")
+                    .send(Ok(channel));
+            }
             SwarmEvent::Behaviour(behaviour::Event::StateDiffsSync(
                 p2p_stream::Event::InboundRequest {
                     request_id,
+                    protocol,
                     request,
                     peer,
                     channel,
                 },
             )) => {
                 tracing::debug!(?request, %peer, %request_id, "Received sync request");
+                let version = self.negotiated_sync_version(peer, &protocol);
 
                 self.event_sender
                     .send(Event::InboundStateDiffsSyncRequest {
                         from: peer,
+                        version,
                         request,
                         channel,
                     })
@@ -560,10 +625,12 @@ impl MainLoop {
                 p2p_stream::Event::OutboundRequestSentAwaitingResponses {
                     request_id,
                     peer,
+                    protocol,
                     channel,
                 },
             )) => {
                 tracing::debug!(%peer, %request_id, "State diff sync request sent");
+                self.negotiated_sync_version(peer, &protocol);
 
                 let _ = self
                     .pending_sync_requests
@@ -575,16 +642,19 @@ impl MainLoop {
             SwarmEvent::Behaviour(behaviour::Event::TransactionsSync(
                 p2p_stream::Event::InboundRequest {
                     request_id,
+                    protocol,
                     request,
                     peer,
                     channel,
                 },
             )) => {
                 tracing::debug!(?request, %peer, %request_id, "Received sync request");
+                let version = self.negotiated_sync_version(peer, &protocol);
 
                 self.event_sender
                     .send(Event::InboundTransactionsSyncRequest {
                         from: peer,
+                        version,
                         request,
                         channel,
                     })
@@ -595,10 +665,12 @@ impl MainLoop {
                 p2p_stream::Event::OutboundRequestSentAwaitingResponses {
                     request_id,
                     peer,
+                    protocol,
                     channel,
                 },
             )) => {
                 tracing::debug!(%peer, %request_id, "Transaction sync request sent");
+                self.negotiated_sync_version(peer, &protocol);
 
                 let _ = self
                     .pending_sync_requests
@@ -610,16 +682,19 @@ impl MainLoop {
             SwarmEvent::Behaviour(behaviour::Event::EventsSync(
                 p2p_stream::Event::InboundRequest {
                     request_id,
+                    protocol,
                     request,
                     peer,
                     channel,
                 },
             )) => {
                 tracing::debug!(?request, %peer, %request_id, "Received sync request");
+                let version = self.negotiated_sync_version(peer, &protocol);
 
                 self.event_sender
                     .send(Event::InboundEventsSyncRequest {
                         from: peer,
+                        version,
                         request,
                         channel,
                     })
@@ -630,10 +705,12 @@ impl MainLoop {
                 p2p_stream::Event::OutboundRequestSentAwaitingResponses {
                     request_id,
                     peer,
+                    protocol,
                     channel,
                 },
             )) => {
                 tracing::debug!(%peer, %request_id, "Event sync request sent");
+                self.negotiated_sync_version(peer, &protocol);
 
                 let _ = self
                     .pending_sync_requests
@@ -670,6 +747,24 @@ impl MainLoop {
                     let _ = sender.send(Err(error.into()));
                 }
             }
+            SwarmEvent::Behaviour(behaviour::Event::ClassesByHashSync(
+                p2p_stream::Event::OutboundFailure {
+                    request_id, error, ..
+                },
+            )) => {
+                tracing::warn!(
+                    ?request_id,
+                    ?error,
+                    "Outbound classes by hash sync request failed"
+                );
+                if let Some(sender) = self
+                    .pending_sync_requests
+                    .classes_by_hash
+                    .remove(&request_id)
+                {
+                    let _ = sender.send(Err(error.into()));
+                }
+            }
             SwarmEvent::Behaviour(behaviour::Event::StateDiffsSync(
                 p2p_stream::Event::OutboundFailure {
                     request_id, error, ..
@@ -837,6 +932,22 @@ impl MainLoop {
                     .classes
                     .insert(request_id, sender);
             }
+            Command::SendClassesByHashSyncRequest {
+                peer_id,
+                request,
+                sender,
+            } => {
+                tracing::debug!(?request, "Sending sync request");
+
+                let request_id = self
+                    .swarm
+                    .behaviour_mut()
+                    .classes_by_hash_sync_mut()
+                    .send_request(&peer_id, request);
+                self.pending_sync_requests
+                    .classes_by_hash
+                    .insert(request_id, sender);
+            }
             Command::SendStateDiffsSyncRequest {
                 peer_id,
                 request,
@@ -912,6 +1023,16 @@ impl MainLoop {
         Ok(())
     }
 
+    /// Records the version of the sync protocols negotiated with the peer.
+    fn negotiated_sync_version(&mut self, peer: PeerId, protocol: &str) -> SyncProtocolVersion {
+        let version = SyncProtocolVersion::from_protocol_name(protocol)
+            .expect("Only supported protocols are negotiated");
+        self.swarm
+            .behaviour_mut()
+            .sync_version_negotiated(peer, version);
+        version
+    }
+
     async fn disconnect(&mut self, peer_id: PeerId) -> anyhow::Result<()> {
         self.pending_dials.remove(&peer_id);
         match self.swarm.disconnect_peer_id(peer_id) {
@@ -7,6 +7,7 @@ use libp2p::{Multiaddr, PeerId};
 use sha3::{Digest, Sha3_256};
 
 use crate::secret::Secret;
+use crate::SyncProtocolVersion;
 
 #[derive(Debug, Clone)]
 pub struct Peer {
@@ -19,6 +20,9 @@ pub struct Peer {
     pub min_ping: Option<Duration>,
     pub evicted: bool,
     pub useful: bool,
+    /// The version of the sync protocols most recently negotiated with the
+    /// peer.
+    pub sync_version: Option<SyncProtocolVersion>,
     // TODO are we still able to maintain info about peers' sync heads?
     // sync_status: Option<p2p_proto_v0::sync::Status>,
 }
@@ -1,36 +1,107 @@
 //! request/streaming-response protocol and codec definitions for sync
 
 pub mod protocol {
+    /// A version of the sync protocols.
+    ///
+    /// All supported versions are served at the same time. When requesting,
+    /// the newest version the peer supports is negotiated.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+    pub enum Version {
+        #[default]
+        V0_1_0Rc0 = 0,
+    }
+
+    impl Version {
+        /// The supported versions, in order of preference.
+        pub const ALL: &'static [Version] = &[Version::V0_1_0Rc0];
+
+        pub const fn as_str(self) -> &'static str {
+            match self {
+                Version::V0_1_0Rc0 => "0.1.0-rc.0",
+            }
+        }
+
+        /// The version of a sync protocol name, e.g.
+        /// `/starknet/headers/0.1.0-rc.0`.
+        pub fn from_protocol_name(name: &str) -> Option<Self> {
+            let (_, version) = name.rsplit_once('/')?;
+            Self::ALL
+                .iter()
+                .copied()
+                .find(|supported| supported.as_str() == version)
+        }
+    }
+
+    impl std::fmt::Display for Version {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(self.as_str())
+        }
+    }
+
     macro_rules! define_protocol {
-        ($type_name:ident, $name:literal) => {
-            #[derive(Debug, Clone, Default)]
-            pub struct $type_name;
+        ($type_name:ident, $prefix:literal) => {
+            #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+            pub struct $type_name(pub Version);
 
             impl $type_name {
-                pub const NAME: &'static str = $name;
+                /// The protocol names, indexed by [Version]. Needs an entry
+                /// for every version.
+                const NAMES: [&'static str; Version::ALL.len()] = [concat!($prefix, "/0.1.0-rc.0")];
+
+                /// The protocol in all supported versions, in order of
+                /// preference.
+                pub fn all() -> impl Iterator<Item = Self> {
+                    Version::ALL.iter().copied().map(Self)
+                }
+
+                pub fn names() -> impl Iterator<Item = &'static str> {
+                    Self::all().map(|protocol| Self::NAMES[protocol.0 as usize])
+                }
             }
 
             impl AsRef<str> for $type_name {
                 fn as_ref(&self) -> &str {
-                    Self::NAME
+                    Self::NAMES[self.0 as usize]
                 }
             }
         };
     }
 
-    define_protocol!(Headers, "/starknet/headers/0.1.0-rc.0");
-    define_protocol!(StateDiffs, "/starknet/state_diffs/0.1.0-rc.0");
-    define_protocol!(Classes, "/starknet/classes/0.1.0-rc.0");
-    define_protocol!(Transactions, "/starknet/transactions/0.1.0-rc.0");
-    define_protocol!(Events, "/starknet/events/0.1.0-rc.0");
-
-    pub const PROTOCOLS: &[&str] = &[
-        Headers::NAME,
-        StateDiffs::NAME,
-        Classes::NAME,
-        Transactions::NAME,
-        Events::NAME,
-    ];
+    define_protocol!(Headers, "/starknet/headers");
+    define_protocol!(StateDiffs, "/starknet/state_diffs");
+    define_protocol!(Classes, "/starknet/classes");
+    define_protocol!(ClassesByHash, "/starknet/classes_by_hash");
+    define_protocol!(Transactions, "/starknet/transactions");
+    define_protocol!(Events, "/starknet/events");
+
+    /// The names of all sync protocols in all supported versions.
+    pub fn protocols() -> impl Iterator<Item = &'static str> {
+        Headers::names()
+            .chain(StateDiffs::names())
+            .chain(Classes::names())
+            .chain(ClassesByHash::names())
+            .chain(Transactions::names())
+            .chain(Events::names())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn protocol_names() {
+            assert_eq!(
+                Headers(Version::V0_1_0Rc0).as_ref(),
+                "/starknet/headers/0.1.0-rc.0"
+            );
+            assert_eq!(
+                Version::from_protocol_name("/starknet/classes_by_hash/0.1.0-rc.0"),
+                Some(Version::V0_1_0Rc0)
+            );
+            assert_eq!(Version::from_protocol_name("/starknet/headers/9.9.9"), None);
+            assert_eq!(protocols().count(), 6 * Version::ALL.len());
+        }
+    }
 }
 
 pub(crate) mod codec {
@@ -73,6 +144,15 @@ pub(crate) mod codec {
         FOUR_MIB,
     >;
 
+    pub type ClassesByHash = SyncCodec<
+        protocol::ClassesByHash,
+        class::ClassesByHashRequest,
+        class::ClassesByHashResponse,
+        proto::class::ClassesByHashRequest,
+        proto::class::ClassesByHashResponse,
+        FOUR_MIB,
+    >;
+
     pub type Transactions = SyncCodec<
         protocol::Transactions,
         transaction::TransactionsRequest,
@@ -5,7 +5,12 @@ use fake::{Fake, Faker};
 use futures::{FutureExt, SinkExt, StreamExt};
 use libp2p::identity::Keypair;
 use libp2p::multiaddr::Protocol;
-use p2p_proto::class::{ClassesRequest, ClassesResponse};
+use p2p_proto::class::{
+    ClassesByHashRequest,
+    ClassesByHashResponse,
+    ClassesRequest,
+    ClassesResponse,
+};
 use p2p_proto::event::{EventsRequest, EventsResponse};
 use p2p_proto::header::{BlockHeadersRequest, BlockHeadersResponse, NewBlock};
 use p2p_proto::state::{StateDiffsRequest, StateDiffsResponse};
@@ -858,11 +863,14 @@ mod successful_sync {
                 let mut tx_ready = filter_events(peer1.event_receiver, move |event| match event {
                     Event::$event_variant {
                         from,
+                        version,
                         channel,
                         request: actual_request,
                     } => {
                         // Peer 1 should receive the request from peer2
                         assert_eq!(from, peer2.peer_id);
+                        // The newest version is negotiated
+                        assert_eq!(version, crate::SyncProtocolVersion::ALL[0]);
                         // Received request should match what peer2 sent
                         assert_eq!(expected_request, actual_request);
                         Some(channel)
@@ -931,6 +939,14 @@ mod successful_sync {
         send_classes_sync_request
     );
 
+    define_test!(
+        sync_classes_by_hash,
+        ClassesByHashRequest,
+        ClassesByHashResponse,
+        InboundClassesByHashSyncRequest,
+        send_classes_by_hash_sync_request
+    );
+
     define_test!(
         sync_state_diffs,
         StateDiffsRequest,
@@ -970,6 +986,7 @@ mod propagate_codec_errors_to_caller {
         Transactions,
         StateDiffs,
         Classes,
+        ClassesByHash,
         Events,
     }
 
@@ -1016,6 +1033,12 @@ mod propagate_codec_errors_to_caller {
                 codec::Classes::for_test().set_read_response_factory(error_factory()),
                 Default::default(),
             )),
+            BadCodec::ClassesByHash => {
+                bb.class_by_hash_sync_behaviour(p2p_stream::Behaviour::with_codec(
+                    codec::ClassesByHash::for_test().set_read_response_factory(error_factory()),
+                    Default::default(),
+                ))
+            }
             BadCodec::Events => bb.event_sync_behaviour(p2p_stream::Behaviour::with_codec(
                 codec::Events::for_test().set_read_response_factory(error_factory()),
                 Default::default(),
@@ -1086,6 +1109,7 @@ mod propagate_codec_errors_to_caller {
                         from,
                         channel,
                         request: actual_request,
+                        ..
                     } => {
                         // Peer 1 should receive the request from peer2
                         assert_eq!(from, peer2.peer_id);
@@ -1156,6 +1180,15 @@ mod propagate_codec_errors_to_caller {
         BadCodec::Classes
     );
 
+    define_test!(
+        sync_classes_by_hash,
+        ClassesByHashRequest,
+        ClassesByHashResponse,
+        InboundClassesByHashSyncRequest,
+        send_classes_by_hash_sync_request,
+        BadCodec::ClassesByHash
+    );
+
     define_test!(
         sync_state_diffs,
         StateDiffsRequest,
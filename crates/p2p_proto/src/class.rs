@@ -179,3 +179,47 @@ impl TryFromProtobuf<proto::class::ClassesResponse> for ClassesResponse {
         }
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, ToProtobuf, TryFromProtobuf, Dummy)]
+#[protobuf(name = "crate::proto::class::ClassesByHashRequest")]
+pub struct ClassesByHashRequest {
+    pub class_hashes: Vec<Hash>,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Dummy)]
+pub enum ClassesByHashResponse {
+    Class(Class),
+    #[default]
+    Fin,
+}
+
+impl ToProtobuf<proto::class::ClassesByHashResponse> for ClassesByHashResponse {
+    fn to_protobuf(self) -> proto::class::ClassesByHashResponse {
+        use proto::class::classes_by_hash_response::ClassMessage::{Class, Fin};
+        use proto::class::ClassesByHashResponse;
+        match self {
+            Self::Class(class) => ClassesByHashResponse {
+                class_message: Some(Class(class.to_protobuf())),
+            },
+            Self::Fin => ClassesByHashResponse {
+                class_message: Some(Fin(proto::common::Fin {})),
+            },
+        }
+    }
+}
+
+impl TryFromProtobuf<proto::class::ClassesByHashResponse> for ClassesByHashResponse {
+    fn try_from_protobuf(
+        input: proto::class::ClassesByHashResponse,
+        field_name: &'static str,
+    ) -> Result<Self, std::io::Error> {
+        use proto::class::classes_by_hash_response::ClassMessage::{Class, Fin};
+        match proto_field(input.class_message, field_name)? {
+            Class(c) => Ok(Self::Class(TryFromProtobuf::try_from_protobuf(
+                c, field_name,
+            )?)),
+            Fin(_) => Ok(Self::Fin),
+        }
+    }
+}
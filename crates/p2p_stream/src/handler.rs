@@ -44,10 +44,16 @@ use libp2p::swarm::handler::{
     StreamUpgradeError,
 };
 use libp2p::swarm::SubstreamProtocol;
+use libp2p::PeerId;
 
 use crate::codec::Codec;
 use crate::handler::protocol::Protocol;
-use crate::{InboundRequestId, OutboundRequestId, EMPTY_QUEUE_SHRINK_THRESHOLD};
+use crate::{
+    InboundRequestId,
+    InboundRequestValidator,
+    OutboundRequestId,
+    EMPTY_QUEUE_SHRINK_THRESHOLD,
+};
 
 /// A connection handler for a request/streaming-response
 /// [`Behaviour`](super::Behaviour) protocol.
@@ -69,6 +75,7 @@ where
     /// A channel for receiving inbound requests.
     inbound_receiver: mpsc::Receiver<(
         InboundRequestId,
+        TCodec::Protocol,
         TCodec::Request,
         mpsc::Sender<TCodec::Response>,
     )>,
@@ -76,6 +83,7 @@ where
     /// request.
     inbound_sender: mpsc::Sender<(
         InboundRequestId,
+        TCodec::Protocol,
         TCodec::Request,
         mpsc::Sender<TCodec::Response>,
     )>,
@@ -83,17 +91,23 @@ where
     /// for each outbound request.
     outbound_sender: mpsc::Sender<(
         OutboundRequestId,
+        TCodec::Protocol,
         mpsc::Receiver<std::io::Result<TCodec::Response>>,
     )>,
     /// The [`mpsc::Receiver`] for the above sender.
     outbound_receiver: mpsc::Receiver<(
         OutboundRequestId,
+        TCodec::Protocol,
         mpsc::Receiver<std::io::Result<TCodec::Response>>,
     )>,
 
     inbound_request_id: Arc<AtomicU64>,
 
     worker_streams: futures_bounded::FuturesMap<RequestId, Result<Event<TCodec>, io::Error>>,
+    /// The remote peer of this connection.
+    peer: PeerId,
+    /// Decides which inbound requests are forwarded to the behaviour.
+    inbound_request_validator: Option<InboundRequestValidator<TCodec>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -112,6 +126,8 @@ where
         substream_timeout: Duration,
         inbound_request_id: Arc<AtomicU64>,
         max_concurrent_streams: usize,
+        peer: PeerId,
+        inbound_request_validator: Option<InboundRequestValidator<TCodec>>,
     ) -> Self {
         let (inbound_sender, inbound_receiver) = mpsc::channel(0);
         let (outbound_sender, outbound_receiver) = mpsc::channel(0);
@@ -130,6 +146,8 @@ where
                 substream_timeout,
                 max_concurrent_streams,
             ),
+            peer,
+            inbound_request_validator,
         }
     }
 
@@ -151,6 +169,8 @@ where
         let mut codec = self.codec.clone();
         let request_id = self.next_inbound_request_id();
         let mut sender = self.inbound_sender.clone();
+        let peer = self.peer;
+        let validator = self.inbound_request_validator.clone();
 
         let recv_request_then_fwd_outgoing_responses = async move {
             let (rs_send, mut rs_recv) = mpsc::channel(0);
@@ -158,8 +178,20 @@ where
             let read = codec.read_request(&protocol, &mut stream);
             let request = read.await?;
 
+            if let Some(validator) = validator {
+                if let Err(response) = validator(&peer, protocol.as_ref(), &request) {
+                    // Let the peer know why there will be no responses instead of just
+                    // dropping the stream
+                    let write = codec.write_response(&protocol, &mut stream, response);
+                    write.await?;
+                    stream.close().await?;
+
+                    return Ok(Event::InboundRequestRejected(request_id));
+                }
+            }
+
             sender
-                .send((request_id, request, rs_send))
+                .send((request_id, protocol.clone(), request, rs_send))
                 .await
                 .expect("`ConnectionHandler` owns both ends of the channel");
             drop(sender);
@@ -216,7 +248,7 @@ where
             stream.close().await?;
 
             sender
-                .send((request_id, rs_recv))
+                .send((request_id, protocol.clone(), rs_recv))
                 .await
                 .expect("`ConnectionHandler` owns both ends of the channel");
             drop(sender);
@@ -315,6 +347,8 @@ where
     InboundRequest {
         /// The ID of the request.
         request_id: InboundRequestId,
+        /// The protocol negotiated for the request.
+        protocol: TCodec::Protocol,
         /// The request message.
         request: TCodec::Request,
         /// The channel through which we are expected to send responses.
@@ -324,6 +358,8 @@ where
     OutboundRequestSentAwaitingResponses {
         /// The ID of the outbound request.
         request_id: OutboundRequestId,
+        /// The protocol negotiated for the request.
+        protocol: TCodec::Protocol,
         /// The channel through which we can receive the responses.
         receiver: mpsc::Receiver<std::io::Result<TCodec::Response>>,
     },
@@ -343,6 +379,9 @@ where
     /// An inbound request timed out while waiting for the request
     /// or sending the response.
     InboundTimeout(InboundRequestId),
+    /// An inbound request was rejected by the validator and the stream was
+    /// closed after sending the rejection response.
+    InboundRequestRejected(InboundRequestId),
     InboundStreamFailed {
         request_id: InboundRequestId,
         error: io::Error,
@@ -354,18 +393,22 @@ impl<TCodec: Codec> fmt::Debug for Event<TCodec> {
         match self {
             Event::InboundRequest {
                 request_id,
+                protocol,
                 request: _,
                 sender: _,
             } => f
                 .debug_struct("Event::InboundRequest")
                 .field("request_id", request_id)
+                .field("protocol", &protocol.as_ref())
                 .finish(),
             Event::OutboundRequestSentAwaitingResponses {
                 request_id,
+                protocol,
                 receiver: _,
             } => f
                 .debug_struct("Event::OutboundRequestSentAwaitingResponses")
                 .field("request_id", request_id)
+                .field("protocol", &protocol.as_ref())
                 .finish(),
             Event::InboundResponseStreamClosed(request_id) => f
                 .debug_struct("Event::InboundResponseStreamClosed")
@@ -392,6 +435,10 @@ impl<TCodec: Codec> fmt::Debug for Event<TCodec> {
                 .debug_tuple("Event::InboundTimeout")
                 .field(request_id)
                 .finish(),
+            Event::InboundRequestRejected(request_id) => f
+                .debug_tuple("Event::InboundRequestRejected")
+                .field(request_id)
+                .finish(),
             Event::InboundStreamFailed { request_id, error } => f
                 .debug_struct("Event::InboundStreamFailed")
                 .field("request_id", &request_id)
@@ -486,11 +533,14 @@ where
         }
 
         // Check for inbound requests.
-        if let Poll::Ready(Some((id, rq, rs_sender))) = self.inbound_receiver.poll_next_unpin(cx) {
+        if let Poll::Ready(Some((id, protocol, rq, rs_sender))) =
+            self.inbound_receiver.poll_next_unpin(cx)
+        {
             // We received an inbound request.
             return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
                 Event::InboundRequest {
                     request_id: id,
+                    protocol,
                     request: rq,
                     sender: rs_sender,
                 },
@@ -508,10 +558,13 @@ where
         }
 
         // Check for readiness to receive inbound responses.
-        if let Poll::Ready(Some((id, rs_receiver))) = self.outbound_receiver.poll_next_unpin(cx) {
+        if let Poll::Ready(Some((id, protocol, rs_receiver))) =
+            self.outbound_receiver.poll_next_unpin(cx)
+        {
             return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
                 Event::OutboundRequestSentAwaitingResponses {
                     request_id: id,
+                    protocol,
                     receiver: rs_receiver,
                 },
             ));
@@ -43,6 +43,13 @@
 //! Inbound requests are received via [`Event::InboundRequest`] and responses
 //! are sent via [`Event::InboundRequest::channel`].
 //!
+//! Inbound requests can be inspected before they are handed to the application
+//! by installing a validator with
+//! [`Behaviour::with_inbound_request_validator`]. A rejected request is
+//! answered with the response chosen by the validator, the stream is closed
+//! and [`InboundFailure::Rejected`] is reported instead of
+//! [`Event::InboundRequest`].
+//!
 //! ## Protocol Families
 //!
 //! A single [`Behaviour`] instance can be used with an entire
@@ -91,6 +98,8 @@ pub enum Event<TRequest, TResponse, TChannelResponse = TResponse> {
         peer: PeerId,
         /// The ID of the request.
         request_id: InboundRequestId,
+        /// The name of the protocol negotiated for the request.
+        protocol: String,
         /// The request message.
         request: TRequest,
         /// The channel through which we are expected to send responses.
@@ -103,6 +112,8 @@ pub enum Event<TRequest, TResponse, TChannelResponse = TResponse> {
         peer: PeerId,
         /// The ID of the outbound request.
         request_id: OutboundRequestId,
+        /// The name of the protocol negotiated for the request.
+        protocol: String,
         /// The channel through which we can receive the responses.
         channel: mpsc::Receiver<std::io::Result<TResponse>>,
     },
@@ -191,6 +202,9 @@ pub enum InboundFailure {
     ConnectionClosed,
     /// An IO failure happened on an inbound stream.
     Io(io::Error),
+    /// The inbound request was rejected by the validator installed with
+    /// [`Behaviour::with_inbound_request_validator`].
+    Rejected,
 }
 
 impl fmt::Display for InboundFailure {
@@ -203,6 +217,7 @@ impl fmt::Display for InboundFailure {
                 write!(f, "Connection was closed before a response could be sent")
             }
             InboundFailure::Io(e) => write!(f, "IO error on inbound stream: {e}"),
+            InboundFailure::Rejected => write!(f, "Inbound request was rejected"),
         }
     }
 }
@@ -235,6 +250,17 @@ impl fmt::Display for OutboundRequestId {
     }
 }
 
+/// Inspects an inbound request before it is handed to the application.
+///
+/// Called with the remote peer, the negotiated protocol and the request.
+/// Returning an error rejects the request, in which case the error is written
+/// to the stream as the only response.
+pub type InboundRequestValidator<TCodec> = Arc<
+    dyn Fn(&PeerId, &str, &<TCodec as Codec>::Request) -> Result<(), <TCodec as Codec>::Response>
+        + Send
+        + Sync,
+>;
+
 /// The configuration for a `Behaviour` protocol.
 #[derive(Debug, Clone, Copy)]
 pub struct Config {
@@ -291,6 +317,8 @@ where
     /// Requests that have not yet been sent and are waiting for a connection
     /// to be established.
     pending_outbound_requests: HashMap<PeerId, Vec<OutboundMessage<TCodec>>>,
+    /// Decides which inbound requests are handed to the application.
+    inbound_request_validator: Option<InboundRequestValidator<TCodec>>,
 }
 
 impl<TCodec> Behaviour<TCodec>
@@ -339,9 +367,29 @@ where
             pending_events: VecDeque::new(),
             connected: HashMap::new(),
             pending_outbound_requests: HashMap::new(),
+            inbound_request_validator: None,
         }
     }
 
+    /// Installs a validator which is called for every inbound request before
+    /// [`Event::InboundRequest`] is emitted.
+    ///
+    /// This allows rejecting requests based on e.g. their size, the request
+    /// rate or the score of the requesting peer. Instead of dropping the
+    /// stream, the response returned by the validator is sent to the peer
+    /// before the stream is closed, so that the peer can tell a rejection
+    /// apart from a network failure.
+    pub fn with_inbound_request_validator<F>(mut self, validator: F) -> Self
+    where
+        F: Fn(&PeerId, &str, &TCodec::Request) -> Result<(), TCodec::Response>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.inbound_request_validator = Some(Arc::new(validator));
+        self
+    }
+
     /// Initiates sending a request.
     ///
     /// If the targeted peer is currently not connected, a dialing
@@ -598,6 +646,8 @@ where
             self.config.request_timeout,
             self.next_inbound_request_id.clone(),
             self.config.max_concurrent_streams,
+            peer,
+            self.inbound_request_validator.clone(),
         );
 
         self.preload_new_handler(&mut handler, peer, connection_id, None);
@@ -639,6 +689,8 @@ where
             self.config.request_timeout,
             self.next_inbound_request_id.clone(),
             self.config.max_concurrent_streams,
+            peer,
+            self.inbound_request_validator.clone(),
         );
 
         self.preload_new_handler(
@@ -672,6 +724,7 @@ where
         match event {
             handler::Event::OutboundRequestSentAwaitingResponses {
                 request_id,
+                protocol,
                 receiver,
             } => {
                 let removed =
@@ -685,12 +738,14 @@ where
                     Event::OutboundRequestSentAwaitingResponses {
                         peer,
                         request_id,
+                        protocol: protocol.as_ref().to_owned(),
                         channel: receiver,
                     },
                 ));
             }
             handler::Event::InboundRequest {
                 request_id,
+                protocol,
                 request,
                 sender,
             } => match self.get_connection_mut(&peer, connection) {
@@ -704,6 +759,7 @@ where
                         .push_back(ToSwarm::GenerateEvent(Event::InboundRequest {
                             peer,
                             request_id,
+                            protocol: protocol.as_ref().to_owned(),
                             request,
                             channel: sender,
                         }))
@@ -795,6 +851,14 @@ where
                     );
                 }
             }
+            handler::Event::InboundRequestRejected(request_id) => {
+                self.pending_events
+                    .push_back(ToSwarm::GenerateEvent(Event::InboundFailure {
+                        peer,
+                        request_id,
+                        error: InboundFailure::Rejected,
+                    }));
+            }
             handler::Event::InboundStreamFailed { request_id, error } => {
                 let removed =
                     self.remove_pending_inbound_response_stream(&peer, connection, request_id);
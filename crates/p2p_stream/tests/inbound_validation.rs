@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use futures::channel::oneshot;
+use futures::prelude::*;
+use libp2p_swarm_test::SwarmExt;
+use p2p_stream::InboundFailure;
+
+pub mod utils;
+
+use utils::{
+    new_swarm_with_timeout,
+    new_swarm_with_validator,
+    wait_inbound_failure,
+    wait_inbound_request,
+    wait_inbound_response_stream_closed,
+    wait_no_events,
+    wait_outbound_request_sent_awaiting_responses,
+    wait_outbound_response_stream_closed,
+    Action,
+};
+
+const REJECTION: Action = Action::SanityResponse(0xDEAD);
+
+#[test_log::test(tokio::test)]
+async fn rejected_request_is_answered_with_the_rejection() {
+    let (peer2_id, mut swarm2) = new_swarm_with_timeout(Duration::from_secs(10));
+    let (peer1_id, mut swarm1) = new_swarm_with_validator(move |peer, protocol, _| {
+        assert_eq!(peer, &peer2_id);
+        assert_eq!(protocol, "/test/1");
+        Err(REJECTION)
+    });
+
+    swarm1.listen().with_memory_addr_external().await;
+    swarm2.connect(&mut swarm1).await;
+
+    let (failure_tx, failure_rx) = oneshot::channel();
+
+    // The request never reaches the application. Keep the connection alive
+    // afterwards, otherwise swarm2 may receive `ConnectionClosed` instead of
+    // the rejection.
+    let server_task = async move {
+        let failure = wait_inbound_failure(&mut swarm1).await.unwrap();
+        failure_tx.send(failure).unwrap();
+        wait_no_events(&mut swarm1).await;
+    };
+
+    let client_task = async move {
+        let req_id = swarm2
+            .behaviour_mut()
+            .send_request(&peer1_id, Action::SanityRequest);
+
+        let (peer, req_id_done, mut resp_channel) =
+            wait_outbound_request_sent_awaiting_responses(&mut swarm2)
+                .await
+                .unwrap();
+        assert_eq!(peer, peer1_id);
+        assert_eq!(req_id_done, req_id);
+
+        assert!(matches!(resp_channel.next().await, Some(Ok(x)) if x == REJECTION));
+        assert!(resp_channel.next().await.is_none());
+
+        let (peer, req_id_done) = wait_inbound_response_stream_closed(&mut swarm2)
+            .await
+            .unwrap();
+        assert_eq!(peer, peer1_id);
+        assert_eq!(req_id_done, req_id);
+
+        let (peer, _, error) = failure_rx.await.unwrap();
+        assert_eq!(peer, peer2_id);
+        assert!(matches!(error, InboundFailure::Rejected));
+    };
+
+    tokio::spawn(server_task);
+
+    client_task.await;
+}
+
+#[test_log::test(tokio::test)]
+async fn accepted_request_is_handed_to_the_application() {
+    let (peer2_id, mut swarm2) = new_swarm_with_timeout(Duration::from_secs(10));
+    let (peer1_id, mut swarm1) = new_swarm_with_validator(|_, _, request| match request {
+        Action::SanityRequest => Ok(()),
+        _ => Err(REJECTION),
+    });
+
+    swarm1.listen().with_memory_addr_external().await;
+    swarm2.connect(&mut swarm1).await;
+
+    let server_task = async move {
+        let (peer, req_id, action, mut resp_channel) =
+            wait_inbound_request(&mut swarm1).await.unwrap();
+        assert_eq!(peer, peer2_id);
+        assert_eq!(action, Action::SanityRequest);
+
+        resp_channel.send(Action::SanityResponse(1)).await.unwrap();
+        drop(resp_channel);
+
+        let (peer, req_id_done) = wait_outbound_response_stream_closed(&mut swarm1)
+            .await
+            .unwrap();
+        assert_eq!(peer, peer2_id);
+        assert_eq!(req_id_done, req_id);
+    };
+
+    let client_task = async move {
+        swarm2
+            .behaviour_mut()
+            .send_request(&peer1_id, Action::SanityRequest);
+
+        let (_, _, mut resp_channel) = wait_outbound_request_sent_awaiting_responses(&mut swarm2)
+            .await
+            .unwrap();
+
+        assert!(matches!(resp_channel.next().await, Some(Ok(x)) if x == Action::SanityResponse(1)));
+        assert!(resp_channel.next().await.is_none());
+
+        wait_inbound_response_stream_closed(&mut swarm2)
+            .await
+            .unwrap();
+    };
+
+    tokio::join!(server_task, client_task);
+}
@@ -219,6 +219,22 @@ pub fn new_swarm() -> (PeerId, Swarm<p2p_stream::Behaviour<TestCodec>>) {
     new_swarm_with_timeout(Duration::from_millis(100))
 }
 
+pub fn new_swarm_with_validator(
+    validator: impl Fn(&PeerId, &str, &Action) -> Result<(), Action> + Send + Sync + 'static,
+) -> (PeerId, Swarm<p2p_stream::Behaviour<TestCodec>>) {
+    let protocols = iter::once(StreamProtocol::new("/test/1"));
+    let cfg = p2p_stream::Config::default().request_timeout(Duration::from_secs(10));
+
+    let swarm = new_ephemeral_with_tokio_executor(|_| {
+        p2p_stream::Behaviour::<TestCodec>::with_codec_and_protocols(TestCodec, protocols, cfg)
+            .with_inbound_request_validator(validator)
+    });
+
+    let peed_id = *swarm.local_peer_id();
+
+    (peed_id, swarm)
+}
+
 pub async fn wait_no_events(swarm: &mut Swarm<p2p_stream::Behaviour<TestCodec>>) {
     loop {
         if let Ok(ev) = swarm.select_next_some().await.try_into_behaviour_event() {
@@ -237,6 +253,7 @@ pub async fn wait_inbound_request(
                 request_id,
                 request,
                 channel,
+                ..
             }) => {
                 return Ok((peer, request_id, request, channel));
             }
@@ -259,6 +276,7 @@ pub async fn wait_outbound_request_sent_awaiting_responses(
                 peer,
                 request_id,
                 channel,
+                ..
             }) => {
                 return Ok((peer, request_id, channel));
             }
@@ -0,0 +1,336 @@
+/// Record and compare the outcome of re-executing blocks.
+///
+/// Before upgrading pathfinder (or blockifier) record a baseline of the
+/// execution outcome of every transaction in a range of blocks with the current
+/// version:
+///
+/// `cargo run --release -p pathfinder --example execution_baseline -- record
+/// ./mainnet.sqlite baseline.jsonl 50000 51000`
+///
+/// Then re-execute the same blocks with the new version and compare the
+/// results to the baseline:
+///
+/// `cargo run --release -p pathfinder --example execution_baseline -- compare
+/// ./mainnet.sqlite baseline.jsonl`
+///
+/// The comparison prints each transaction whose revert status, revert reason,
+/// consumed gas, computation steps or fee changed, followed by a summary of
+/// the affected blocks. The tool exits with an error if any outcome changed,
+/// so that it can be used as a gate before rolling out a new version.
+///
+/// The baseline is stored as one JSON object per transaction and line.
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, Write};
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use pathfinder_common::{BlockNumber, ChainId, TransactionHash};
+use pathfinder_executor::{ExecutionState, FeeTokens};
+use pathfinder_rpc::context::{ETH_FEE_TOKEN_ADDRESS, STRK_FEE_TOKEN_ADDRESS};
+use pathfinder_storage::{BlockId, Storage};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// The Cairo VM allocates felts on the stack, so during execution it's making
+// a huge number of allocations. We get roughly two times better execution
+// performance by using jemalloc (compared to the Linux glibc allocator).
+#[global_allocator]
+static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
+
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Re-execute a range of blocks and store the outcome of each
+    /// transaction.
+    Record {
+        database_path: PathBuf,
+        baseline_path: PathBuf,
+        first_block: u64,
+        /// Defaults to the latest block in the database.
+        last_block: Option<u64>,
+    },
+    /// Re-execute the blocks of a baseline and report the transactions whose
+    /// outcome changed.
+    Compare {
+        database_path: PathBuf,
+        baseline_path: PathBuf,
+    },
+}
+
+/// Execution outcome of a single transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Outcome {
+    block_number: u64,
+    transaction_hash: TransactionHash,
+    revert_reason: Option<String>,
+    l1_gas: u128,
+    l1_data_gas: u128,
+    l2_gas: u128,
+    steps: usize,
+    overall_fee: u128,
+}
+
+fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .compact()
+        .init();
+
+    match Cli::parse().command {
+        Command::Record {
+            database_path,
+            baseline_path,
+            first_block,
+            last_block,
+        } => record(database_path, baseline_path, first_block, last_block),
+        Command::Compare {
+            database_path,
+            baseline_path,
+        } => compare(database_path, baseline_path),
+    }
+}
+
+fn record(
+    database_path: PathBuf,
+    baseline_path: PathBuf,
+    first_block: u64,
+    last_block: Option<u64>,
+) -> anyhow::Result<()> {
+    let (storage, chain_id) = open_storage(database_path)?;
+
+    let last_block = match last_block {
+        Some(last_block) => last_block,
+        None => {
+            let mut db = storage.connection()?;
+            let tx = db.transaction()?;
+            tx.block_id(BlockId::Latest)?
+                .context("Database is empty")?
+                .0
+                .get()
+        }
+    };
+
+    tracing::info!(%first_block, %last_block, "Recording baseline");
+
+    let outcomes = execute_blocks(&storage, chain_id, (first_block..=last_block).collect())?;
+
+    let file = std::fs::File::create(&baseline_path).context("Creating baseline file")?;
+    let mut writer = std::io::BufWriter::new(file);
+    for outcome in &outcomes {
+        serde_json::to_writer(&mut writer, outcome).context("Writing baseline")?;
+        writeln!(writer).context("Writing baseline")?;
+    }
+    writer.flush().context("Writing baseline")?;
+
+    tracing::info!(
+        transactions=%outcomes.len(), path=%baseline_path.display(), "Baseline recorded"
+    );
+
+    Ok(())
+}
+
+fn compare(database_path: PathBuf, baseline_path: PathBuf) -> anyhow::Result<()> {
+    let (storage, chain_id) = open_storage(database_path)?;
+
+    let file = std::fs::File::open(&baseline_path).context("Opening baseline file")?;
+    let baseline = std::io::BufReader::new(file)
+        .lines()
+        .map(|line| {
+            let line = line.context("Reading baseline")?;
+            serde_json::from_str::<Outcome>(&line).context("Parsing baseline")
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let blocks = baseline
+        .iter()
+        .map(|outcome| outcome.block_number)
+        .collect::<BTreeSet<_>>();
+
+    tracing::info!(
+        blocks=%blocks.len(), transactions=%baseline.len(), "Comparing against baseline"
+    );
+
+    let outcomes = execute_blocks(&storage, chain_id, blocks.into_iter().collect())?
+        .into_iter()
+        .map(|outcome| (outcome.transaction_hash, outcome))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut changed_blocks = BTreeMap::<u64, usize>::new();
+    for expected in &baseline {
+        let actual = outcomes.get(&expected.transaction_hash);
+        if actual == Some(expected) {
+            continue;
+        }
+
+        println!(
+            "Block {} transaction {}:",
+            expected.block_number, expected.transaction_hash
+        );
+        match actual {
+            Some(actual) => report_changes(expected, actual),
+            None => println!("  not executed"),
+        }
+        *changed_blocks.entry(expected.block_number).or_default() += 1;
+    }
+
+    if changed_blocks.is_empty() {
+        println!("No execution outcome changed");
+        return Ok(());
+    }
+
+    println!();
+    println!("Blocks with changed execution outcomes:");
+    for (block_number, transactions) in &changed_blocks {
+        println!("  {block_number}: {transactions} transaction(s)");
+    }
+
+    anyhow::bail!(
+        "Execution outcome changed in {} block(s)",
+        changed_blocks.len()
+    )
+}
+
+fn report_changes(expected: &Outcome, actual: &Outcome) {
+    if expected.revert_reason != actual.revert_reason {
+        println!(
+            "  revert reason: {:?} -> {:?}",
+            expected.revert_reason, actual.revert_reason
+        );
+    }
+
+    let fields = [
+        ("l1_gas", expected.l1_gas, actual.l1_gas),
+        ("l1_data_gas", expected.l1_data_gas, actual.l1_data_gas),
+        ("l2_gas", expected.l2_gas, actual.l2_gas),
+        ("steps", expected.steps as u128, actual.steps as u128),
+        ("overall_fee", expected.overall_fee, actual.overall_fee),
+    ];
+    for (name, expected, actual) in fields {
+        if expected != actual {
+            println!("  {name}: {expected} -> {actual}");
+        }
+    }
+}
+
+fn open_storage(database_path: PathBuf) -> anyhow::Result<(Storage, ChainId)> {
+    let n_cpus = rayon::current_num_threads();
+
+    let storage = pathfinder_storage::StorageBuilder::file(database_path)
+        .migrate()?
+        .create_pool(NonZeroU32::new(n_cpus as u32 * 2).unwrap())?;
+
+    let chain_id = {
+        let mut db = storage.connection()?;
+        let tx = db.transaction()?;
+        get_chain_id(&tx)?
+    };
+
+    Ok((storage, chain_id))
+}
+
+fn get_chain_id(tx: &pathfinder_storage::Transaction<'_>) -> anyhow::Result<ChainId> {
+    use pathfinder_common::consts::{
+        MAINNET_GENESIS_HASH,
+        SEPOLIA_INTEGRATION_GENESIS_HASH,
+        SEPOLIA_TESTNET_GENESIS_HASH,
+    };
+
+    let (_, genesis_hash) = tx
+        .block_id(BlockNumber::GENESIS.into())?
+        .context("Getting genesis hash")?;
+
+    let chain = match genesis_hash {
+        MAINNET_GENESIS_HASH => ChainId::MAINNET,
+        SEPOLIA_TESTNET_GENESIS_HASH => ChainId::SEPOLIA_TESTNET,
+        SEPOLIA_INTEGRATION_GENESIS_HASH => ChainId::SEPOLIA_INTEGRATION,
+        _ => anyhow::bail!("Unknown chain"),
+    };
+
+    Ok(chain)
+}
+
+/// Re-executes the blocks in parallel and returns the outcome of their
+/// transactions, ordered by block.
+fn execute_blocks(
+    storage: &Storage,
+    chain_id: ChainId,
+    blocks: Vec<u64>,
+) -> anyhow::Result<Vec<Outcome>> {
+    let start_time = std::time::Instant::now();
+
+    let outcomes = blocks
+        .into_par_iter()
+        .map_with(storage.clone(), |storage, block_number| {
+            execute_block(storage, chain_id, block_number)
+                .with_context(|| format!("Re-executing block {block_number}"))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let elapsed = start_time.elapsed();
+    tracing::info!(blocks=%outcomes.len(), ?elapsed, "Re-executed blocks");
+
+    Ok(outcomes.into_iter().flatten().collect())
+}
+
+fn execute_block(
+    storage: &mut Storage,
+    chain_id: ChainId,
+    block_number: u64,
+) -> anyhow::Result<Vec<Outcome>> {
+    let mut connection = storage.connection()?;
+    let db_tx = connection.transaction()?;
+
+    let block_id = BlockId::Number(BlockNumber::new(block_number).context("Invalid block number")?);
+    let header = db_tx
+        .block_header(block_id)?
+        .context("Block header missing")?;
+    let transactions = db_tx
+        .transactions_for_block(block_id)?
+        .context("Block transactions missing")?;
+
+    let executor_transactions = transactions
+        .iter()
+        .map(|tx| pathfinder_rpc::compose_executor_transaction(tx, &db_tx))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Converting transactions")?;
+
+    let execution_state = ExecutionState::trace(
+        &db_tx,
+        chain_id,
+        header,
+        None,
+        None,
+        FeeTokens::new(ETH_FEE_TOKEN_ADDRESS, STRK_FEE_TOKEN_ADDRESS),
+    );
+
+    let simulations = pathfinder_executor::simulate(execution_state, executor_transactions)
+        .map_err(|error| anyhow::anyhow!("{error:?}"))?;
+
+    let outcomes = simulations
+        .iter()
+        .zip(&transactions)
+        .map(|(simulation, transaction)| {
+            let resources = simulation.trace.execution_resources();
+            Outcome {
+                block_number,
+                transaction_hash: transaction.hash,
+                revert_reason: simulation.revert_reason().map(ToOwned::to_owned),
+                l1_gas: resources.l1_gas,
+                l1_data_gas: resources.l1_data_gas,
+                l2_gas: resources.l2_gas,
+                steps: resources.computation_resources.steps,
+                overall_fee: simulation.fee_estimation.overall_fee.low_u128(),
+            }
+        })
+        .collect();
+
+    Ok(outcomes)
+}
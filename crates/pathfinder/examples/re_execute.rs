@@ -4,7 +4,7 @@ use anyhow::Context;
 use pathfinder_common::receipt::Receipt;
 use pathfinder_common::transaction::Transaction;
 use pathfinder_common::{BlockHeader, BlockNumber, ChainId};
-use pathfinder_executor::ExecutionState;
+use pathfinder_executor::{ExecutionState, FeeTokens};
 use pathfinder_rpc::context::{ETH_FEE_TOKEN_ADDRESS, STRK_FEE_TOKEN_ADDRESS};
 use pathfinder_storage::{BlockId, Storage};
 use rayon::prelude::*;
@@ -139,8 +139,7 @@ fn execute(storage: &mut Storage, chain_id: ChainId, work: Work) {
         work.header.clone(),
         None,
         None,
-        ETH_FEE_TOKEN_ADDRESS,
-        STRK_FEE_TOKEN_ADDRESS,
+        FeeTokens::new(ETH_FEE_TOKEN_ADDRESS, STRK_FEE_TOKEN_ADDRESS),
     );
 
     let transactions = work
@@ -170,9 +169,7 @@ fn execute(storage: &mut Storage, chain_id: ChainId, work: Work) {
                     tracing::warn!(block_number=%work.header.number, transaction_hash=%receipt.transaction_hash, ?simulated_revert_reason, ?actual_revert_reason, "Revert status differs");
                 }
 
-                let actual_fee = u128::from_be_bytes(
-                    receipt.actual_fee.0.to_be_bytes()[16..].try_into().unwrap(),
-                );
+                let actual_fee = receipt.actual_fee.0.low_u128();
 
                 // L1 handler transactions have a fee of zero in the receipt.
                 if actual_fee == 0 {
@@ -1,7 +1,7 @@
 use std::collections::HashSet;
 use std::fs::File;
 use std::net::SocketAddr;
-use std::num::{NonZeroU32, NonZeroUsize};
+use std::num::{NonZeroU32, NonZeroU64, NonZeroUsize};
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -11,11 +11,36 @@ use ipnet::IpNet;
 #[cfg(feature = "p2p")]
 use p2p::libp2p::Multiaddr;
 use pathfinder_common::consts::VERGEN_GIT_DESCRIBE;
-use pathfinder_common::AllowedOrigins;
-use pathfinder_executor::VersionedConstants;
-use pathfinder_storage::JournalMode;
+use pathfinder_common::version_matrix::VersionMatrix;
+use pathfinder_common::{AllowedOrigins, ContractAddress, EntryPoint};
+use pathfinder_compiler::limits::CompilationLimits;
+use pathfinder_crypto::Felt;
+use pathfinder_executor::types::PriceUnit;
+use pathfinder_executor::{
+    BlockHashContract,
+    EvictionPolicy,
+    ExecutionPolicy,
+    FeeToken,
+    TraceCacheConfig,
+    VersionedConstants,
+};
+use pathfinder_lib::disk_guard::DiskGuardConfig;
+use pathfinder_lib::lag_alert::LagAlertConfig;
+use pathfinder_lib::state::l2::TransactionHashVerification;
+use pathfinder_lib::state::throttle::WriteThrottleConfig;
+use pathfinder_lib::state::verification::GatewayDataVerification;
+use pathfinder_rpc::context::{
+    GatewayCircuitBreakerConfig,
+    MethodAccessConfig,
+    ResponseSizeLimits,
+};
+use pathfinder_rpc::request_log::{RequestLogConfig, RequestLogFile};
+use pathfinder_storage::{EventRetentionConfig, JournalMode};
+use primitive_types::H256;
 use reqwest::Url;
 
+mod file;
+
 #[derive(Parser)]
 #[command(name = "Pathfinder")]
 #[command(author = "Equilibrium Labs")]
@@ -23,7 +48,33 @@ use reqwest::Url;
 #[command(
     about = "A Starknet node implemented by Equilibrium Labs. Submit bug reports and issues at https://github.com/eqlabs/pathfinder."
 )]
+#[command(
+    after_long_help = "Run `pathfinder config validate [OPTIONS]` to check the configuration \
+                       without starting the node, or `pathfinder config print-effective \
+                       [OPTIONS]` to print the configuration resulting from all sources as a \
+                       configuration file."
+)]
 struct Cli {
+    #[arg(
+        long = "config",
+        long_help = r"Path to a TOML configuration file. Every option can be set in the file, with the dots of the option's name mapping to tables. Options given on the command line or as environment variables take precedence over the file.
+
+Example:
+    network = 'mainnet'
+
+    [ethereum]
+    url = 'wss://eth-mainnet.g.alchemy.com/v2/<PROJECT_ID>'
+
+    [rpc]
+    execution-threads = 8
+    disabled-methods = ['starknet_trace*']",
+        value_name = "FILE",
+        value_hint = clap::ValueHint::FilePath,
+        env = "PATHFINDER_CONFIG"
+    )]
+    // Read before the arguments are parsed, see [file::path].
+    _config: Option<PathBuf>,
+
     #[arg(
         long,
         value_name = "DIR", 
@@ -55,6 +106,41 @@ Examples:
     )]
     ethereum_url: Url,
 
+    #[arg(
+        long = "l1-mode",
+        long_help = r"How the Starknet state on L1 is obtained.
+
+rpc:          trust the Ethereum JSON-RPC endpoint given by --ethereum.url.
+light-client: verify the core contract's state against an embedded beacon chain light client.
+              Requires --ethereum.beacon-url and --ethereum.light-client-checkpoint.",
+        value_enum,
+        default_value = "rpc",
+        env = "PATHFINDER_L1_MODE"
+    )]
+    l1_mode: L1ModeCli,
+
+    #[arg(
+        long = "ethereum.beacon-url",
+        long_help = "The beacon node REST API used by the light client. Only used with \
+                     `--l1-mode light-client`.",
+        value_name = "HTTP(s) URL",
+        value_hint = clap::ValueHint::Url,
+        env = "PATHFINDER_ETHEREUM_BEACON_URL",
+        required_if_eq("l1_mode", "light-client")
+    )]
+    ethereum_beacon_url: Option<Url>,
+
+    #[arg(
+        long = "ethereum.light-client-checkpoint",
+        long_help = "A trusted, finalized beacon block root the light client bootstraps from. \
+                     Only used with `--l1-mode light-client`.",
+        value_name = "BLOCK ROOT",
+        env = "PATHFINDER_ETHEREUM_LIGHT_CLIENT_CHECKPOINT",
+        value_parser = parse_light_client_checkpoint,
+        required_if_eq("l1_mode", "light-client")
+    )]
+    ethereum_light_client_checkpoint: Option<H256>,
+
     #[arg(
         long = "http-rpc",
         long_help = "HTTP-RPC listening address",
@@ -104,6 +190,42 @@ Examples:
     )]
     monitor_address: Option<SocketAddr>,
 
+    #[arg(
+        long = "monitor.ready-max-sync-lag",
+        long_help = "The `/ready` monitoring endpoint fails if the node is more than this number \
+                     of blocks behind the chain tip. By default the sync lag is only reported.",
+        value_name = "BLOCKS",
+        env = "PATHFINDER_MONITOR_READY_MAX_SYNC_LAG"
+    )]
+    monitor_ready_max_sync_lag: Option<u64>,
+
+    #[arg(
+        long = "monitor.alert-webhook-url",
+        long_help = "A URL which is sent a JSON POST request when the sync lag exceeds one of \
+                     the `monitor.alert-*` thresholds, and again once it has recovered.",
+        value_name = "URL",
+        value_hint = clap::ValueHint::Url,
+        env = "PATHFINDER_MONITOR_ALERT_WEBHOOK_URL"
+    )]
+    monitor_alert_webhook_url: Option<Url>,
+
+    #[arg(
+        long = "monitor.alert-max-block-age",
+        long_help = "Alert once the latest block is older than this many seconds.",
+        value_name = "SECONDS",
+        env = "PATHFINDER_MONITOR_ALERT_MAX_BLOCK_AGE"
+    )]
+    monitor_alert_max_block_age: Option<u64>,
+
+    #[arg(
+        long = "monitor.alert-max-l1-l2-gap",
+        long_help = "Alert once the latest block is more than this many blocks ahead of the \
+                     latest block accepted on L1.",
+        value_name = "BLOCKS",
+        env = "PATHFINDER_MONITOR_ALERT_MAX_L1_L2_GAP"
+    )]
+    monitor_alert_max_l1_l2_gap: Option<u64>,
+
     #[clap(flatten)]
     network: NetworkCli,
 
@@ -229,6 +351,17 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     is_rpc_enabled: bool,
 
+    #[arg(
+        long = "rpc.persistent-class-cache",
+        long_help = "Keep class definitions prepared for execution in the database. This speeds \
+                     up the first calls and traces involving a class after a restart at the cost \
+                     of some disk space.",
+        env = "PATHFINDER_RPC_PERSISTENT_CLASS_CACHE",
+        default_value = "true",
+        action = ArgAction::Set
+    )]
+    rpc_persistent_class_cache: bool,
+
     #[arg(
         long = "gateway-api-key",
         value_name = "API_KEY",
@@ -255,6 +388,26 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     feeder_gateway_fetch_concurrency: std::num::NonZeroUsize,
 
+    #[arg(
+        long = "gateway.class-fetch-concurrency",
+        long_help = "How many classes declared in a block to download concurrently from the \
+                     feeder gateway",
+        env = "PATHFINDER_GATEWAY_CLASS_FETCH_CONCURRENCY",
+        default_value = "8"
+    )]
+    feeder_gateway_class_fetch_concurrency: std::num::NonZeroUsize,
+
+    #[arg(
+        long = "gateway.schema-drift-check-interval",
+        value_name = "Seconds",
+        long_help = "How often to check that the feeder gateway's replies still match the \
+                     expected schema, as an early warning for Starknet version updates. Set to 0 \
+                     to disable the check.",
+        env = "PATHFINDER_GATEWAY_SCHEMA_DRIFT_CHECK_INTERVAL",
+        default_value = "3600"
+    )]
+    gateway_schema_drift_check_interval: u64,
+
     #[arg(
         long = "storage.event-filter-cache-size",
         long_help = format!(
@@ -290,6 +443,420 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     get_events_max_uncached_event_filters_to_load: std::num::NonZeroUsize,
 
+    #[arg(
+        long = "rpc.get-events-max-chunk-size",
+        long_help = "The maximum chunk size of a starknet_getEvents request. Larger requests are \
+                     rejected with a PAGE_SIZE_TOO_BIG error.",
+        env = "PATHFINDER_RPC_GET_EVENTS_MAX_CHUNK_SIZE",
+        default_value = "1024"
+    )]
+    get_events_max_chunk_size: std::num::NonZeroUsize,
+
+    #[arg(
+        long = "rpc.event-filter-max-keys",
+        long_help = format!(
+            "The maximum number of key positions in the filter of starknet_getEvents and \
+             starknet_subscribeEvents requests. Larger filters are rejected with a \
+             TOO_MANY_KEYS_IN_FILTER error. Can be at most {}.",
+            pathfinder_storage::EVENT_KEY_FILTER_LIMIT
+        ),
+        env = "PATHFINDER_RPC_EVENT_FILTER_MAX_KEYS",
+        default_value = "16",
+        value_parser = parse_event_filter_max_keys
+    )]
+    event_filter_max_keys: std::num::NonZeroUsize,
+
+    #[arg(
+        long = "rpc.max-request-body-size",
+        long_help = "The maximum size of an HTTP JSON-RPC request body in MiB. Larger requests \
+                     are rejected.",
+        value_name = "MiB",
+        env = "PATHFINDER_RPC_MAX_REQUEST_BODY_SIZE",
+        default_value = "10"
+    )]
+    rpc_max_request_body_size: NonZeroUsize,
+
+    #[arg(
+        long = "rpc.max-response-size",
+        long_help = "The maximum size of a JSON-RPC method result in MiB. Methods producing \
+                     larger results return a 'Response too large' error instead, asking the \
+                     caller to narrow their query. Unlimited if not set.",
+        value_name = "MiB",
+        env = "PATHFINDER_RPC_MAX_RESPONSE_SIZE"
+    )]
+    rpc_max_response_size: Option<NonZeroUsize>,
+
+    #[arg(
+        long = "rpc.max-response-size-per-method",
+        long_help = r"Comma separated list of per-method response size limits in MiB, overriding --rpc.max-response-size.
+
+Example:
+    starknet_getEvents=16,starknet_traceBlockTransactions=256",
+        value_name = "METHOD=MiB LIST",
+        value_delimiter = ',',
+        value_parser = parse_method_response_size,
+        env = "PATHFINDER_RPC_MAX_RESPONSE_SIZE_PER_METHOD"
+    )]
+    rpc_max_response_size_per_method: Vec<(String, NonZeroUsize)>,
+
+    #[arg(
+        long = "rpc.trace-cache-size",
+        long_help = "The maximum number of blocks whose transaction traces are kept in memory.",
+        value_name = "BLOCKS",
+        env = "PATHFINDER_RPC_TRACE_CACHE_SIZE",
+        default_value = "128"
+    )]
+    rpc_trace_cache_size: NonZeroUsize,
+
+    #[arg(
+        long = "rpc.trace-cache-max-memory",
+        long_help = "The maximum estimated memory used by cached transaction traces in MiB. \
+                     Blocks are evicted once either this or --rpc.trace-cache-size is exceeded. \
+                     Unlimited if not set.",
+        value_name = "MiB",
+        env = "PATHFINDER_RPC_TRACE_CACHE_MAX_MEMORY"
+    )]
+    rpc_trace_cache_max_memory: Option<NonZeroUsize>,
+
+    #[arg(
+        long = "rpc.trace-cache-eviction-policy",
+        long_help = "Which block to evict from the transaction trace cache once it is full. `lru` \
+                     evicts the least recently used block, `lfu` the least frequently used one.",
+        value_name = "POLICY",
+        value_enum,
+        env = "PATHFINDER_RPC_TRACE_CACHE_EVICTION_POLICY",
+        default_value = "lru"
+    )]
+    rpc_trace_cache_eviction_policy: EvictionPolicyCli,
+
+    #[arg(
+        long = "rpc.response-cache-max-memory",
+        long_help = "The maximum memory used by cached responses to queries for blocks, \
+                     transactions, receipts, classes and traces addressed by hash, in MiB. Only \
+                     final data is cached, the cache is cleared on reorgs. Disabled if not set.",
+        value_name = "MiB",
+        env = "PATHFINDER_RPC_RESPONSE_CACHE_MAX_MEMORY"
+    )]
+    rpc_response_cache_max_memory: Option<NonZeroUsize>,
+
+    #[arg(
+        long = "rpc.request-log.sample-rate",
+        long_help = "The fraction of JSON-RPC requests logged with their method, params hash, \
+                     duration and outcome, between 0 and 1. Entries are logged with the \
+                     `rpc_request_log` target. Request logging is disabled if this is 0 and \
+                     --rpc.request-log.slow-threshold is not set.",
+        value_name = "RATE",
+        env = "PATHFINDER_RPC_REQUEST_LOG_SAMPLE_RATE",
+        default_value = "0",
+        value_parser = parse_sample_rate
+    )]
+    rpc_request_log_sample_rate: f64,
+
+    #[arg(
+        long = "rpc.request-log.slow-threshold",
+        long_help = "JSON-RPC requests taking longer than this many milliseconds are logged \
+                     regardless of --rpc.request-log.sample-rate.",
+        value_name = "MILLISECONDS",
+        env = "PATHFINDER_RPC_REQUEST_LOG_SLOW_THRESHOLD"
+    )]
+    rpc_request_log_slow_threshold: Option<u64>,
+
+    #[arg(
+        long = "rpc.request-log.max-params-size",
+        long_help = "Params larger than this many bytes are redacted from request log entries, \
+                     leaving only their hash and size.",
+        value_name = "BYTES",
+        env = "PATHFINDER_RPC_REQUEST_LOG_MAX_PARAMS_SIZE",
+        default_value = "1024"
+    )]
+    rpc_request_log_max_params_size: usize,
+
+    #[arg(
+        long = "rpc.request-log.file",
+        long_help = "Also append request log entries as JSON lines to this file. The file is \
+                     rotated once it reaches --rpc.request-log.file-max-size.",
+        value_name = "PATH",
+        env = "PATHFINDER_RPC_REQUEST_LOG_FILE"
+    )]
+    rpc_request_log_file: Option<PathBuf>,
+
+    #[arg(
+        long = "rpc.request-log.file-max-size",
+        long_help = "The size in MiB at which the request log file is rotated.",
+        value_name = "MiB",
+        env = "PATHFINDER_RPC_REQUEST_LOG_FILE_MAX_SIZE",
+        default_value = "100"
+    )]
+    rpc_request_log_file_max_size: NonZeroU64,
+
+    #[arg(
+        long = "rpc.request-log.file-max-count",
+        long_help = "The number of rotated request log files kept. Older ones are deleted.",
+        value_name = "FILES",
+        env = "PATHFINDER_RPC_REQUEST_LOG_FILE_MAX_COUNT",
+        default_value = "5"
+    )]
+    rpc_request_log_file_max_count: NonZeroUsize,
+
+    #[arg(
+        long = "rpc.gateway-circuit-breaker-threshold",
+        long_help = "The number of consecutive feeder gateway failures after which RPC methods \
+                     falling back to the gateway fail immediately with a `Feeder gateway is \
+                     unavailable` error instead of waiting for the gateway to time out.",
+        value_name = "FAILURES",
+        env = "PATHFINDER_RPC_GATEWAY_CIRCUIT_BREAKER_THRESHOLD",
+        default_value = "5"
+    )]
+    rpc_gateway_circuit_breaker_threshold: NonZeroU32,
+
+    #[arg(
+        long = "rpc.gateway-circuit-breaker-cooldown",
+        long_help = "How long, in seconds, RPC methods falling back to the feeder gateway fail \
+                     immediately once --rpc.gateway-circuit-breaker-threshold is reached. A \
+                     single request is then let through to check whether the gateway is available \
+                     again.",
+        value_name = "SECONDS",
+        env = "PATHFINDER_RPC_GATEWAY_CIRCUIT_BREAKER_COOLDOWN",
+        default_value = "30"
+    )]
+    rpc_gateway_circuit_breaker_cooldown: std::num::NonZeroU64,
+
+    #[arg(
+        long = "rpc.additional-fee-tokens",
+        long_help = r"Comma separated list of fee token contracts accepted besides the chain's ETH and STRK tokens, e.g. on appchains. The unit is either `wei` or `fri` and selects the gas prices fees paid in the token are based on.
+
+Example:
+    fri=0x123,wei=0x456",
+        value_name = "UNIT=ADDRESS LIST",
+        value_delimiter = ',',
+        value_parser = parse_fee_token,
+        env = "PATHFINDER_RPC_ADDITIONAL_FEE_TOKENS"
+    )]
+    rpc_additional_fee_tokens: Vec<FeeToken>,
+
+    #[arg(
+        long = "rpc.execution-blocklist",
+        long_help = r"Comma separated list of contracts whose execution is refused by `starknet_call`, fee estimation and simulation, e.g. contracts abused to exhaust the resources of public endpoints. An entry is either a contract address, blocking the whole contract, or a contract address and an entry point selector separated by a colon.
+
+Example:
+    0x123,0x456:0x789",
+        value_name = "ADDRESS[:SELECTOR] LIST",
+        value_delimiter = ',',
+        value_parser = parse_execution_block,
+        env = "PATHFINDER_RPC_EXECUTION_BLOCKLIST"
+    )]
+    rpc_execution_blocklist: Vec<(ContractAddress, Option<EntryPoint>)>,
+
+    #[arg(
+        long = "rpc.block-hash-contract",
+        long_help = "The contract whose storage the `get_block_hash` syscall reads block hashes \
+                     from, keyed by block number. Only appchains not using Starknet's setup need \
+                     to change this.",
+        value_name = "ADDRESS",
+        value_parser = parse_contract_address,
+        default_value = "0x1",
+        env = "PATHFINDER_RPC_BLOCK_HASH_CONTRACT"
+    )]
+    rpc_block_hash_contract: ContractAddress,
+
+    #[arg(
+        long = "rpc.block-hashes-from-headers",
+        long_help = "Serve reads of the block hash contract's storage from the stored block \
+                     headers instead of from the contract's storage.",
+        default_value = "false",
+        action = ArgAction::Set,
+        env = "PATHFINDER_RPC_BLOCK_HASHES_FROM_HEADERS"
+    )]
+    rpc_block_hashes_from_headers: bool,
+
+    #[arg(
+        long = "rpc.block-hash-contract-retained",
+        long_help = "The number of latest block hashes the block hash contract retains, older \
+                     ones read as zero. All hashes are available if not set. Requires \
+                     '--rpc.block-hashes-from-headers'.",
+        value_name = "N",
+        requires = "rpc_block_hashes_from_headers",
+        env = "PATHFINDER_RPC_BLOCK_HASH_CONTRACT_RETAINED"
+    )]
+    rpc_block_hash_contract_retained: Option<NonZeroU64>,
+
+    #[arg(
+        long = "rpc.reconstruct-gateway-trace-events",
+        long_help = "Traces of blocks older than Starknet 0.13.1.1 are fetched from the feeder \
+                     gateway, which omits the events and messages of individual calls for old \
+                     blocks. When enabled, these are reconstructed from the stored transaction \
+                     receipts so that the traces match locally executed ones.",
+        env = "PATHFINDER_RPC_RECONSTRUCT_GATEWAY_TRACE_EVENTS",
+        default_value = "false",
+        action=ArgAction::Set
+    )]
+    rpc_reconstruct_gateway_trace_events: bool,
+
+    #[arg(
+        long = "rpc.idempotency-key-ttl",
+        long_help = "How long, in seconds, the response to a transaction submission carrying an \
+                     `idempotency_key` is remembered. Retries with the same key within this \
+                     period return the original response instead of being submitted again.",
+        value_name = "SECONDS",
+        env = "PATHFINDER_RPC_IDEMPOTENCY_KEY_TTL",
+        default_value = "300"
+    )]
+    rpc_idempotency_key_ttl: NonZeroU64,
+
+    #[arg(
+        long = "rpc.gateway-outbox",
+        long_help = "Persist transactions which could not be forwarded to the gateway because of \
+                     a timeout, connection error or 5xx response, and resubmit them in the \
+                     background with exponential backoff. The submission succeeds with the \
+                     locally computed transaction hash instead of failing. Queued transactions \
+                     can be inspected with `pathfinder_getGatewayOutbox` and resubmitted \
+                     immediately with `pathfinder_flushGatewayOutbox`. These methods are only \
+                     served to clients presenting one of the keys in --rpc.api-keys.",
+        env = "PATHFINDER_RPC_GATEWAY_OUTBOX",
+        default_value = "false",
+        action=ArgAction::Set
+    )]
+    rpc_gateway_outbox: bool,
+
+    #[arg(
+        long = "rpc.rejected-transaction-archive",
+        long_help = "Persist transactions submitted through this node which the gateway \
+                     rejected, together with the rejection reason. Archived transactions can be \
+                     looked up with `pathfinder_getRejectedTransaction`.",
+        env = "PATHFINDER_RPC_REJECTED_TRANSACTION_ARCHIVE",
+        default_value = "false",
+        action=ArgAction::Set
+    )]
+    rpc_rejected_transaction_archive: bool,
+
+    #[arg(
+        long = "rpc.declare-precheck",
+        long_help = "Check declare transactions locally before forwarding them to the gateway. \
+                     Declares of classes which exceed --compiler.max-class-size, which are \
+                     declared already or whose Sierra fails to compile are rejected with the \
+                     error the gateway would have returned, sparing the gateway's rate limit.",
+        env = "PATHFINDER_RPC_DECLARE_PRECHECK",
+        default_value = "false",
+        action=ArgAction::Set
+    )]
+    rpc_declare_precheck: bool,
+
+    #[arg(
+        long = "rpc.get-proof-max-keys",
+        long_help = "The maximum number of storage keys in a single pathfinder_getProof request. \
+                     Proofs of large batches are generated in parallel.",
+        env = "PATHFINDER_RPC_GET_PROOF_MAX_KEYS",
+        default_value = "100"
+    )]
+    rpc_get_proof_max_keys: NonZeroUsize,
+
+    #[arg(
+        long = "rpc.execution-threads",
+        long_help = "The number of threads dedicated to execution work, i.e. calls, fee \
+                     estimations, simulations and traces. Defaults to the execution concurrency.",
+        env = "PATHFINDER_RPC_EXECUTION_THREADS"
+    )]
+    rpc_execution_threads: Option<NonZeroUsize>,
+
+    #[arg(
+        long = "rpc.storage-read-threads",
+        long_help = "The number of threads dedicated to database reads. These are kept separate \
+                     from the execution threads so that long running traces cannot delay quick \
+                     queries. Defaults to the size of the RPC database connection pool.",
+        env = "PATHFINDER_RPC_STORAGE_READ_THREADS"
+    )]
+    rpc_storage_read_threads: Option<NonZeroUsize>,
+
+    #[arg(
+        long = "compiler.max-class-size",
+        long_help = "Sierra classes whose definition is larger than this many bytes are not \
+                     compiled to CASM. Sync fetches the CASM of such classes from the feeder \
+                     gateway instead, while declare transactions are rejected.",
+        value_name = "BYTES",
+        default_value = "16777216",
+        env = "PATHFINDER_COMPILER_MAX_CLASS_SIZE"
+    )]
+    compiler_max_class_size: NonZeroUsize,
+
+    #[arg(
+        long = "compiler.timeout",
+        long_help = "Compilations of Sierra classes to CASM which take longer than this are \
+                     abandoned.",
+        value_name = "SECONDS",
+        default_value = "60",
+        env = "PATHFINDER_COMPILER_TIMEOUT"
+    )]
+    compiler_timeout: NonZeroU64,
+
+    #[arg(
+        long = "compiler.threads",
+        long_help = "The number of threads compiling Sierra classes to CASM. This also bounds the \
+                     number of abandoned compilations running at the same time. Defaults to the \
+                     number of CPU cores.",
+        env = "PATHFINDER_COMPILER_THREADS"
+    )]
+    compiler_threads: Option<NonZeroUsize>,
+
+    #[arg(
+        long = "rpc.disabled-methods",
+        long_help = r"Comma separated list of JSON-RPC methods which are not served. Calls to these return a 'Method not found' error. A trailing '*' matches all methods with the given prefix.
+
+Example:
+    starknet_trace*,starknet_simulateTransactions",
+        value_name = "METHOD LIST",
+        value_delimiter = ',',
+        env = "PATHFINDER_RPC_DISABLED_METHODS"
+    )]
+    rpc_disabled_methods: Vec<String>,
+
+    #[arg(
+        long = "rpc.restricted-methods",
+        long_help = "Comma separated list of JSON-RPC methods which are only served to clients \
+                     presenting one of the keys in --rpc.api-keys as an 'Authorization: Bearer \
+                     <key>' header. Other clients receive a 'Method restricted' error. A trailing \
+                     '*' matches all methods with the given prefix.",
+        value_name = "METHOD LIST",
+        value_delimiter = ',',
+        env = "PATHFINDER_RPC_RESTRICTED_METHODS"
+    )]
+    rpc_restricted_methods: Vec<String>,
+
+    #[arg(
+        long = "rpc.api-keys",
+        long_help = "Comma separated list of API keys granting access to the methods in \
+                     --rpc.restricted-methods.",
+        value_name = "KEY LIST",
+        value_delimiter = ',',
+        env = "PATHFINDER_RPC_API_KEYS"
+    )]
+    rpc_api_keys: Vec<String>,
+
+    #[arg(
+        long = "rpc.log-filter-changes",
+        long_help = "Allow changing the log filter at runtime with `pathfinder_setLogFilter`, e.g. \
+                     to enable `pathfinder_executor=debug` for ten minutes. The method is only \
+                     served to clients presenting one of the keys in --rpc.api-keys.",
+        default_value = "false",
+        action = ArgAction::Set,
+        requires = "rpc_api_keys",
+        env = "PATHFINDER_RPC_LOG_FILTER_CHANGES"
+    )]
+    rpc_log_filter_changes: bool,
+
+    #[arg(
+        long = "rpc.trace-cache-warmup",
+        long_help = "Serve `pathfinder_getTraceCacheBlocks`, which lists the blocks whose traces \
+                     are cached, and `pathfinder_warmTraceCache`, which traces blocks into the \
+                     cache in the background. Together they let a freshly started replica be \
+                     warmed up with the popular blocks of a running one. The methods are only \
+                     served to clients presenting one of the keys in --rpc.api-keys.",
+        default_value = "false",
+        action = ArgAction::Set,
+        requires = "rpc_api_keys",
+        env = "PATHFINDER_RPC_TRACE_CACHE_WARMUP"
+    )]
+    rpc_trace_cache_warmup: bool,
+
     #[arg(
         long = "storage.state-tries",
         long_help = "When set to `archive` all historical Merkle trie state is preserved. When set to an integer N, only the last N+1 states of the Merkle tries are kept in the database. \
@@ -301,6 +868,83 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     state_tries: Option<StateTries>,
 
+    #[arg(
+        long = "storage.min-free-space-mb",
+        long_help = "Free disk space in MiB below which the node enters read-only degraded mode: \
+                     sync is paused and transactions are no longer accepted, while all other RPC \
+                     methods keep working. Set to 0 to disable the check.",
+        value_name = "MiB",
+        env = "PATHFINDER_STORAGE_MIN_FREE_SPACE_MB",
+        default_value = "1024"
+    )]
+    storage_min_free_space_mb: u64,
+
+    #[arg(
+        long = "storage.resume-free-space-mb",
+        long_help = "Free disk space in MiB above which the node leaves degraded mode again. \
+                     Defaults to twice --storage.min-free-space-mb.",
+        value_name = "MiB",
+        env = "PATHFINDER_STORAGE_RESUME_FREE_SPACE_MB"
+    )]
+    storage_resume_free_space_mb: Option<u64>,
+
+    #[arg(
+        long = "storage.migration-batch-size",
+        long_help = "Number of blocks database migrations of large amounts of data process before \
+                     committing. Smaller batches keep the journal small at the cost of slower \
+                     migrations.",
+        value_name = "BLOCKS",
+        env = "PATHFINDER_STORAGE_MIGRATION_BATCH_SIZE",
+        default_value = "1000"
+    )]
+    storage_migration_batch_size: NonZeroUsize,
+
+    #[arg(
+        long = "storage.event-retention-blocks",
+        long_help = "Only keep the events of the latest N blocks, apart from those emitted by \
+                     contracts in --storage.event-retention-allowlist. Older events are deleted \
+                     and queries for them, as well as for the receipts of the affected blocks, \
+                     are rejected. All events are kept if not set.",
+        value_name = "N",
+        env = "PATHFINDER_STORAGE_EVENT_RETENTION_BLOCKS"
+    )]
+    storage_event_retention_blocks: Option<u64>,
+
+    #[arg(
+        long = "storage.event-retention-allowlist",
+        long_help = "Comma separated list of contract addresses whose full event history is kept \
+                     regardless of --storage.event-retention-blocks. Events pruned before a \
+                     contract was added to the list cannot be restored.",
+        value_name = "ADDRESS LIST",
+        value_delimiter = ',',
+        value_parser = parse_contract_address,
+        requires = "storage_event_retention_blocks",
+        env = "PATHFINDER_STORAGE_EVENT_RETENTION_ALLOWLIST"
+    )]
+    storage_event_retention_allowlist: Vec<ContractAddress>,
+
+    #[arg(
+        long = "storage.background-recompression",
+        long_help = "Re-encode stored transactions and events which were written with an older \
+                     compression codec in the background.",
+        env = "PATHFINDER_STORAGE_BACKGROUND_RECOMPRESSION",
+        default_value = "false",
+        action = ArgAction::Set
+    )]
+    storage_background_recompression: bool,
+
+    #[arg(
+        long = "storage.defer-event-filters",
+        long_help = "Skip building event filters during initial sync and build them in the \
+                     background once sync has caught up. This makes initial sync faster, but \
+                     `starknet_getEvents` is unavailable for all but the most recent blocks \
+                     until the filters are built. Only takes effect on a new database.",
+        env = "PATHFINDER_STORAGE_DEFER_EVENT_FILTERS",
+        default_value = "false",
+        action = ArgAction::Set
+    )]
+    storage_defer_event_filters: bool,
+
     #[arg(
         long = "rpc.custom-versioned-constants-json-path",
         long_help = "Path to a JSON file containing the versioned constants to use for execution",
@@ -317,6 +961,82 @@ This should only be enabled for debugging purposes as it adds substantial proces
     )]
     fetch_casm_from_fgw: bool,
 
+    #[arg(
+        long = "sync.max-blocks-per-second",
+        long_help = "Limit the number of blocks written to the database per second while catching \
+                     up with the chain tip. Unlimited if not set.",
+        value_name = "BLOCKS",
+        env = "PATHFINDER_SYNC_MAX_BLOCKS_PER_SECOND"
+    )]
+    sync_max_blocks_per_second: Option<NonZeroU32>,
+
+    #[arg(
+        long = "sync.max-write-mb-per-second",
+        long_help = "Limit the (estimated) amount of block data written to the database per \
+                     second while catching up with the chain tip. Unlimited if not set.",
+        value_name = "MiB",
+        env = "PATHFINDER_SYNC_MAX_WRITE_MB_PER_SECOND"
+    )]
+    sync_max_write_mb_per_second: Option<NonZeroU32>,
+
+    #[arg(
+        long = "sync.low-priority",
+        long_help = "Run the catch-up phase of sync at a low priority. After writing each block \
+                     the node idles for as long as the write took, leaving disk I/O to other \
+                     processes sharing the machine.",
+        env = "PATHFINDER_SYNC_LOW_PRIORITY",
+        default_value = "false",
+        action=ArgAction::Set
+    )]
+    sync_low_priority: bool,
+
+    #[arg(
+        long = "sync.transaction-hash-verification",
+        long_help = "How to handle transactions whose hash does not match the hash recomputed \
+                     from their contents. 'enforce' rejects the block while 'log-only' only logs \
+                     the mismatch and stores the block as received from the gateway.",
+        value_enum,
+        env = "PATHFINDER_SYNC_TRANSACTION_HASH_VERIFICATION",
+        default_value = "enforce"
+    )]
+    sync_transaction_hash_verification: TransactionHashVerificationCli,
+
+    #[arg(
+        long = "sync.verification-concurrency",
+        long_help = "How many blocks to verify at the same time while catching up with the feeder \
+                     gateway. Downloading blocks is limited separately by \
+                     '--gateway.fetch-concurrency'.",
+        env = "PATHFINDER_SYNC_VERIFICATION_CONCURRENCY",
+        default_value = "8"
+    )]
+    sync_verification_concurrency: std::num::NonZeroUsize,
+
+    #[arg(
+        long = "verify-gateway-data",
+        long_help = "How much of the data received from the gateway to verify before storing it. \
+                     'strict' rejects blocks whose block hash, transaction hashes, signature, \
+                     state diff commitment, class hashes or state root do not match and records \
+                     them in the quarantine table. It implies \
+                     '--sync.transaction-hash-verification=enforce'. 'default' only logs \
+                     signature and state diff commitment mismatches.",
+        value_enum,
+        env = "PATHFINDER_VERIFY_GATEWAY_DATA",
+        default_value = "default"
+    )]
+    verify_gateway_data: GatewayDataVerificationCli,
+
+    #[arg(
+        long = "sync.class-stats",
+        long_help = "Aggregate the number of transactions and the gas consumed per class of the \
+                     contract they target for each synced block. These statistics are served by \
+                     pathfinder_getClassStats. Blocks synced while this is disabled are not \
+                     covered.",
+        env = "PATHFINDER_SYNC_CLASS_STATS",
+        default_value = "false",
+        action=ArgAction::Set
+    )]
+    sync_class_stats: bool,
+
     #[arg(
         long = "shutdown.grace-period",
         value_name = "Seconds",
@@ -348,6 +1068,157 @@ impl Color {
     }
 }
 
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum TransactionHashVerificationCli {
+    Enforce,
+    LogOnly,
+}
+
+impl From<TransactionHashVerificationCli> for TransactionHashVerification {
+    fn from(value: TransactionHashVerificationCli) -> Self {
+        match value {
+            TransactionHashVerificationCli::Enforce => Self::Enforce,
+            TransactionHashVerificationCli::LogOnly => Self::LogOnly,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum GatewayDataVerificationCli {
+    Default,
+    Strict,
+}
+
+impl From<GatewayDataVerificationCli> for GatewayDataVerification {
+    fn from(value: GatewayDataVerificationCli) -> Self {
+        match value {
+            GatewayDataVerificationCli::Default => Self::Default,
+            GatewayDataVerificationCli::Strict => Self::Strict,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum EvictionPolicyCli {
+    Lru,
+    Lfu,
+}
+
+impl From<EvictionPolicyCli> for EvictionPolicy {
+    fn from(value: EvictionPolicyCli) -> Self {
+        match value {
+            EvictionPolicyCli::Lru => Self::Lru,
+            EvictionPolicyCli::Lfu => Self::Lfu,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
+enum L1ModeCli {
+    Rpc,
+    LightClient,
+}
+
+fn parse_light_client_checkpoint(s: &str) -> Result<H256, String> {
+    let bytes =
+        hex::decode(s.trim_start_matches("0x")).map_err(|e| format!("Invalid block root: {e}"))?;
+    if bytes.len() != 32 {
+        return Err("Block root must be 32 bytes".to_string());
+    }
+    Ok(H256::from_slice(&bytes))
+}
+
+fn parse_event_filter_max_keys(s: &str) -> Result<NonZeroUsize, String> {
+    let max_keys: NonZeroUsize = s.trim().parse().map_err(|e| format!("{e}"))?;
+    if max_keys.get() > pathfinder_storage::EVENT_KEY_FILTER_LIMIT {
+        return Err(format!(
+            "Can be at most {}, the number of key positions covered by event filters",
+            pathfinder_storage::EVENT_KEY_FILTER_LIMIT
+        ));
+    }
+    Ok(max_keys)
+}
+
+fn parse_sample_rate(s: &str) -> Result<f64, String> {
+    let rate: f64 = s.trim().parse().map_err(|e| format!("{e}"))?;
+    if !(0.0..=1.0).contains(&rate) {
+        return Err("Must be between 0 and 1".to_string());
+    }
+    Ok(rate)
+}
+
+fn parse_method_response_size(s: &str) -> Result<(String, NonZeroUsize), String> {
+    let (method, size) = s
+        .split_once('=')
+        .ok_or_else(|| "Expected METHOD=MiB".to_string())?;
+    let size = size
+        .trim()
+        .parse()
+        .map_err(|e| format!("Invalid size for {method}: {e}"))?;
+    Ok((method.trim().to_string(), size))
+}
+
+fn parse_fee_token(s: &str) -> Result<FeeToken, String> {
+    let (unit, address) = s
+        .split_once('=')
+        .ok_or_else(|| "Expected UNIT=ADDRESS".to_string())?;
+    let unit = match unit.trim() {
+        "wei" => PriceUnit::Wei,
+        "fri" => PriceUnit::Fri,
+        other => return Err(format!("Invalid unit {other}, expected `wei` or `fri`")),
+    };
+    let address = Felt::from_hex_str(address.trim())
+        .ok()
+        .and_then(ContractAddress::new)
+        .ok_or_else(|| format!("Invalid fee token address: {address}"))?;
+    Ok(FeeToken { address, unit })
+}
+
+fn parse_execution_block(s: &str) -> Result<(ContractAddress, Option<EntryPoint>), String> {
+    let (address, selector) = match s.split_once(':') {
+        Some((address, selector)) => (address, Some(selector)),
+        None => (s, None),
+    };
+    let address = parse_contract_address(address)?;
+    let selector = selector
+        .map(|selector| {
+            Felt::from_hex_str(selector.trim())
+                .map(EntryPoint)
+                .map_err(|_| format!("Invalid entry point selector: {selector}"))
+        })
+        .transpose()?;
+    Ok((address, selector))
+}
+
+fn parse_contract_address(s: &str) -> Result<ContractAddress, String> {
+    Felt::from_hex_str(s.trim())
+        .ok()
+        .and_then(ContractAddress::new)
+        .ok_or_else(|| format!("Invalid contract address: {s}"))
+}
+
+/// Methods which are restricted to authenticated clients if the log filter may
+/// be changed.
+const LOG_FILTER_METHODS: [&str; 2] = ["pathfinder_getLogFilter", "pathfinder_setLogFilter"];
+
+/// Methods which are restricted to authenticated clients if the gateway outbox
+/// is enabled.
+const GATEWAY_OUTBOX_METHODS: [&str; 2] = [
+    "pathfinder_getGatewayOutbox",
+    "pathfinder_flushGatewayOutbox",
+];
+
+/// Methods which are restricted to authenticated clients if trace cache
+/// warm-up is enabled.
+const TRACE_CACHE_METHODS: [&str; 2] = [
+    "pathfinder_getTraceCacheBlocks",
+    "pathfinder_warmTraceCache",
+];
+
+fn mib_to_bytes(mib: NonZeroUsize) -> NonZeroUsize {
+    mib.saturating_mul(NonZeroUsize::new(1024 * 1024).unwrap())
+}
+
 #[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
 pub enum RootRpcVersion {
     V07,
@@ -411,6 +1282,17 @@ Note that 'custom' requires also setting the --gateway-url and --feeder-gateway-
         required_if_eq("network", Network::Custom),
     )]
     gateway: Option<Url>,
+
+    #[arg(
+        long = "starknet-version-matrix-path",
+        value_name = "PATH",
+        long_help = "Path to a JSON file describing the capabilities of each Starknet version of \
+                     a custom network: whether its blocks can be traced locally, which hash \
+                     scheme they use and which gas model applies. Replaces the built-in table. \
+                     Requires '--network custom'.",
+        env = "PATHFINDER_STARKNET_VERSION_MATRIX_PATH"
+    )]
+    version_matrix_path: Option<PathBuf>,
 }
 
 #[cfg(feature = "p2p")]
@@ -424,10 +1306,22 @@ struct P2PCli {
         env = "PATHFINDER_P2P_PROXY"
     )]
     proxy: bool,
+    #[arg(
+        long = "p2p.head-race",
+        long_help = "When syncing from the feeder gateway, also follow the new block announcements \
+                     of the p2p network. Announced blocks are looked up on the feeder gateway \
+                     right away instead of at the next poll, which reduces the latency of the \
+                     latest block. Requires '--p2p.proxy'.",
+        default_value = "false",
+        action = clap::ArgAction::Set,
+        env = "PATHFINDER_P2P_HEAD_RACE"
+    )]
+    head_race: bool,
     #[arg(
         long = "p2p.identity-config-file",
         long_help = "Path to file containing the private key of the node. If not provided, a new \
-                     random key will be generated.",
+                     random key will be generated. Use the 'pathfinder identity' subcommands to \
+                     generate, import, export or rotate the key.",
         value_name = "PATH",
         env = "PATHFINDER_P2P_IDENTITY_CONFIG_FILE"
     )]
@@ -694,6 +1588,21 @@ pub fn parse_versioned_constants_or_exit(path: PathBuf) -> VersionedConstants {
     }
 }
 
+fn parse_version_matrix_or_exit(path: PathBuf) -> VersionMatrix {
+    use clap::error::ErrorKind;
+
+    let matrix = std::fs::read(&path)
+        .map_err(|error| anyhow::anyhow!("Reading {}: {error}", path.display()))
+        .and_then(|json| VersionMatrix::from_json(&json));
+
+    match matrix {
+        Ok(matrix) => matrix,
+        Err(error) => Cli::command()
+            .error(ErrorKind::ValueValidation, format!("{error:#}"))
+            .exit(),
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 enum ParseVersionedConstantsError {
     #[error("IO error while reading versioned constants: {0}.")]
@@ -710,6 +1619,8 @@ pub struct Config {
     pub rpc_root_version: RootRpcVersion,
     pub websocket: WebsocketConfig,
     pub monitor_address: Option<SocketAddr>,
+    pub monitor_ready_max_sync_lag: Option<u64>,
+    pub monitor_lag_alert: LagAlertConfig,
     pub network: Option<NetworkConfig>,
     pub execution_concurrency: Option<std::num::NonZeroU32>,
     pub sqlite_wal: JournalMode,
@@ -725,21 +1636,66 @@ pub struct Config {
     pub rpc_batch_concurrency_limit: NonZeroUsize,
     pub is_sync_enabled: bool,
     pub is_rpc_enabled: bool,
+    pub rpc_persistent_class_cache: bool,
     pub gateway_api_key: Option<String>,
     pub gateway_timeout: Duration,
     pub event_filter_cache_size: NonZeroUsize,
     pub get_events_max_blocks_to_scan: NonZeroUsize,
     pub get_events_max_uncached_event_filters_to_load: NonZeroUsize,
+    pub get_events_max_chunk_size: NonZeroUsize,
+    pub event_filter_max_keys: NonZeroUsize,
+    pub rpc_max_request_body_size: usize,
+    pub rpc_response_size_limits: ResponseSizeLimits,
+    pub rpc_trace_cache: TraceCacheConfig,
+    pub rpc_response_cache_max_size: Option<NonZeroUsize>,
+    pub rpc_request_log: Option<RequestLogConfig>,
+    pub rpc_gateway_circuit_breaker: GatewayCircuitBreakerConfig,
+    pub rpc_additional_fee_tokens: Vec<FeeToken>,
+    pub rpc_execution_policy: ExecutionPolicy,
+    pub rpc_block_hash_contract: BlockHashContract,
+    pub rpc_reconstruct_gateway_trace_events: bool,
+    pub rpc_idempotency_key_ttl: Duration,
+    pub rpc_gateway_outbox: bool,
+    pub rpc_rejected_transaction_archive: bool,
+    pub rpc_declare_precheck: bool,
+    pub rpc_get_proof_max_keys: NonZeroUsize,
+    pub rpc_execution_threads: Option<NonZeroUsize>,
+    pub rpc_storage_read_threads: Option<NonZeroUsize>,
+    pub compilation_limits: CompilationLimits,
+    pub rpc_method_access: MethodAccessConfig,
+    pub rpc_log_filter_changes: bool,
+    pub rpc_trace_cache_warmup: bool,
     pub state_tries: Option<StateTries>,
+    /// [None] if the check is disabled.
+    pub disk_guard: Option<DiskGuardConfig>,
+    pub storage_migration_batch_size: NonZeroUsize,
+    /// [None] if all events are kept.
+    pub event_retention: Option<EventRetentionConfig>,
+    pub background_recompression: bool,
+    pub defer_event_filters: bool,
     pub custom_versioned_constants: Option<VersionedConstants>,
     pub feeder_gateway_fetch_concurrency: NonZeroUsize,
+    pub feeder_gateway_class_fetch_concurrency: NonZeroUsize,
+    /// [None] if the check is disabled.
+    pub gateway_schema_drift_check_interval: Option<Duration>,
     pub fetch_casm_from_fgw: bool,
+    pub sync_write_throttle: WriteThrottleConfig,
+    pub sync_transaction_hash_verification: TransactionHashVerification,
+    pub sync_verification_concurrency: NonZeroUsize,
+    pub gateway_data_verification: GatewayDataVerification,
+    pub sync_class_stats: bool,
     pub shutdown_grace_period: Duration,
 }
 
 pub struct Ethereum {
     pub url: Url,
     pub password: Option<String>,
+    pub l1_mode: L1Mode,
+}
+
+pub enum L1Mode {
+    Rpc,
+    LightClient { beacon_url: Url, checkpoint: H256 },
 }
 
 #[derive(Clone)]
@@ -751,6 +1707,7 @@ pub enum NetworkConfig {
         gateway: Url,
         feeder_gateway: Url,
         chain_id: String,
+        version_matrix: Option<VersionMatrix>,
     },
 }
 
@@ -758,6 +1715,7 @@ pub enum NetworkConfig {
 #[derive(Clone)]
 pub struct P2PConfig {
     pub proxy: bool,
+    pub head_race: bool,
     pub identity_config_file: Option<std::path::PathBuf>,
     pub listen_on: Vec<Multiaddr>,
     pub bootstrap_addresses: Vec<Multiaddr>,
@@ -791,22 +1749,24 @@ impl NetworkConfig {
             args.gateway,
             args.feeder_gateway,
             args.chain_id,
+            args.version_matrix_path,
         ) {
-            (None, None, None, None) => return None,
-            (Some(Custom), Some(gateway), Some(feeder_gateway), Some(chain_id)) => {
+            (None, None, None, None, None) => return None,
+            (Some(Custom), Some(gateway), Some(feeder_gateway), Some(chain_id), version_matrix) => {
                 NetworkConfig::Custom {
                     gateway,
                     feeder_gateway,
                     chain_id,
+                    version_matrix: version_matrix.map(parse_version_matrix_or_exit),
                 }
             }
-            (Some(Custom), _, _, _) => {
+            (Some(Custom), _, _, _, _) => {
                 unreachable!("`--network custom` requirements are handled by clap derive")
             }
             // Handle non-custom variants in an inner match so that the compiler will force
             // us to handle a new network variants explicitly. Otherwise we end up with a
             // catch-all arm that would swallow new variants silently.
-            (Some(non_custom), None, None, None) => match non_custom {
+            (Some(non_custom), None, None, None, None) => match non_custom {
                 Mainnet => NetworkConfig::Mainnet,
                 SepoliaTestnet => NetworkConfig::SepoliaTestnet,
                 SepoliaIntegration => NetworkConfig::SepoliaIntegration,
@@ -820,8 +1780,8 @@ impl NetworkConfig {
                 Cli::command()
                     .error(
                         ErrorKind::ArgumentConflict,
-                        "--gateway-url, --feeder-gateway-url and --chain-id may only be used with \
-                         --network custom",
+                        "--gateway-url, --feeder-gateway-url, --chain-id and \
+                         --starknet-version-matrix-path may only be used with --network custom",
                     )
                     .exit()
             }
@@ -889,6 +1849,15 @@ impl P2PConfig {
                 .exit()
         }
 
+        if args.head_race && !args.proxy {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "p2p.head-race requires p2p.proxy",
+                )
+                .exit()
+        }
+
         if args.kad_name.iter().any(|x| !x.starts_with('/')) {
             Cli::command()
                 .error(
@@ -908,6 +1877,7 @@ impl P2PConfig {
                 .unwrap(),
             max_outbound_connections: args.max_outbound_connections.try_into().unwrap(),
             proxy: args.proxy,
+            head_race: args.head_race,
             identity_config_file: args.identity_config_file,
             listen_on: parse_multiaddr_vec("p2p.listen-on", args.listen_on),
             bootstrap_addresses: parse_multiaddr_vec(
@@ -982,10 +1952,50 @@ impl DebugConfig {
 }
 
 impl Config {
-    #[cfg_attr(not(feature = "p2p"), allow(clippy::unit_arg))]
+    /// Parses the configuration from the command line, the environment and
+    /// the configuration file, in this order of precedence.
+    ///
+    /// Exits the process after handling the `config validate` and
+    /// `config print-effective` subcommands.
     pub fn parse() -> Self {
-        let cli = Cli::parse();
+        use clap::error::ErrorKind;
+        use clap::FromArgMatches;
+
+        let (action, args) = match file::split_action(std::env::args_os().collect()) {
+            Ok(x) => x,
+            Err(error) => Cli::command()
+                .error(ErrorKind::InvalidSubcommand, error)
+                .exit(),
+        };
+
+        if let Some(path) = file::path(&args) {
+            if let Err(error) = file::apply(&Cli::command(), &path) {
+                Cli::command()
+                    .error(ErrorKind::ValueValidation, format!("{error:#}"))
+                    .exit()
+            }
+        }
+
+        let command = Cli::command();
+        let matches = command.clone().get_matches_from(args);
+        let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
 
+        match action {
+            file::Action::Run => Self::from_cli(cli),
+            file::Action::Validate => {
+                Self::from_cli(cli);
+                println!("Configuration is valid");
+                std::process::exit(0)
+            }
+            file::Action::PrintEffective => {
+                print!("{}", file::effective(&command, &matches));
+                std::process::exit(0)
+            }
+        }
+    }
+
+    #[cfg_attr(not(feature = "p2p"), allow(clippy::unit_arg))]
+    fn from_cli(cli: Cli) -> Self {
         let network = NetworkConfig::from_components(cli.network);
 
         Config {
@@ -993,12 +2003,29 @@ impl Config {
             ethereum: Ethereum {
                 password: cli.ethereum_password,
                 url: cli.ethereum_url,
+                l1_mode: match cli.l1_mode {
+                    L1ModeCli::Rpc => L1Mode::Rpc,
+                    L1ModeCli::LightClient => L1Mode::LightClient {
+                        beacon_url: cli
+                            .ethereum_beacon_url
+                            .expect("Required by clap for light client mode"),
+                        checkpoint: cli
+                            .ethereum_light_client_checkpoint
+                            .expect("Required by clap for light client mode"),
+                    },
+                },
             },
             rpc_address: cli.rpc_address,
             rpc_cors_domains: parse_cors_or_exit(cli.rpc_cors_domains),
             rpc_root_version: cli.rpc_root_version,
             websocket: cli.websocket,
             monitor_address: cli.monitor_address,
+            monitor_ready_max_sync_lag: cli.monitor_ready_max_sync_lag,
+            monitor_lag_alert: LagAlertConfig {
+                webhook: cli.monitor_alert_webhook_url,
+                max_block_age: cli.monitor_alert_max_block_age.map(Duration::from_secs),
+                max_l1_l2_gap: cli.monitor_alert_max_l1_l2_gap,
+            },
             network,
             execution_concurrency: cli.execution_concurrency,
             sqlite_wal: match cli.sqlite_wal {
@@ -1017,18 +2044,146 @@ impl Config {
             rpc_batch_concurrency_limit: cli.rpc_batch_concurrency_limit,
             is_sync_enabled: cli.is_sync_enabled,
             is_rpc_enabled: cli.is_rpc_enabled,
+            rpc_persistent_class_cache: cli.rpc_persistent_class_cache,
             gateway_api_key: cli.gateway_api_key,
             event_filter_cache_size: cli.event_filter_cache_size,
             get_events_max_blocks_to_scan: cli.get_events_max_blocks_to_scan,
             get_events_max_uncached_event_filters_to_load: cli
                 .get_events_max_uncached_event_filters_to_load,
+            get_events_max_chunk_size: cli.get_events_max_chunk_size,
+            event_filter_max_keys: cli.event_filter_max_keys,
+            rpc_max_request_body_size: mib_to_bytes(cli.rpc_max_request_body_size).get(),
+            rpc_response_size_limits: ResponseSizeLimits {
+                default: cli.rpc_max_response_size.map(mib_to_bytes),
+                per_method: cli
+                    .rpc_max_response_size_per_method
+                    .into_iter()
+                    .map(|(method, size)| (method, mib_to_bytes(size)))
+                    .collect(),
+            },
+            rpc_trace_cache: TraceCacheConfig {
+                max_entries: cli.rpc_trace_cache_size,
+                max_size: cli.rpc_trace_cache_max_memory.map(mib_to_bytes),
+                eviction_policy: cli.rpc_trace_cache_eviction_policy.into(),
+            },
+            rpc_response_cache_max_size: cli.rpc_response_cache_max_memory.map(mib_to_bytes),
+            rpc_request_log: (cli.rpc_request_log_sample_rate > 0.0
+                || cli.rpc_request_log_slow_threshold.is_some())
+            .then(|| RequestLogConfig {
+                sample_rate: cli.rpc_request_log_sample_rate,
+                slow_threshold: cli
+                    .rpc_request_log_slow_threshold
+                    .map(Duration::from_millis),
+                max_params_size: cli.rpc_request_log_max_params_size,
+                file: cli.rpc_request_log_file.map(|path| RequestLogFile {
+                    path,
+                    max_size: cli
+                        .rpc_request_log_file_max_size
+                        .get()
+                        .saturating_mul(1024 * 1024),
+                    max_files: cli.rpc_request_log_file_max_count,
+                }),
+            }),
+            rpc_gateway_circuit_breaker: GatewayCircuitBreakerConfig {
+                failure_threshold: cli.rpc_gateway_circuit_breaker_threshold,
+                open_duration: Duration::from_secs(cli.rpc_gateway_circuit_breaker_cooldown.get()),
+            },
+            rpc_additional_fee_tokens: cli.rpc_additional_fee_tokens,
+            rpc_block_hash_contract: BlockHashContract {
+                address: cli.rpc_block_hash_contract,
+                retained: cli.rpc_block_hash_contract_retained,
+                from_headers: cli.rpc_block_hashes_from_headers,
+            },
+            rpc_execution_policy: ExecutionPolicy::new(
+                cli.rpc_execution_blocklist
+                    .iter()
+                    .filter(|(_, selector)| selector.is_none())
+                    .map(|(address, _)| *address),
+                cli.rpc_execution_blocklist
+                    .iter()
+                    .filter_map(|(address, selector)| Some((*address, (*selector)?))),
+            ),
+            rpc_reconstruct_gateway_trace_events: cli.rpc_reconstruct_gateway_trace_events,
+            rpc_idempotency_key_ttl: Duration::from_secs(cli.rpc_idempotency_key_ttl.get()),
+            rpc_gateway_outbox: cli.rpc_gateway_outbox,
+            rpc_rejected_transaction_archive: cli.rpc_rejected_transaction_archive,
+            rpc_declare_precheck: cli.rpc_declare_precheck,
+            rpc_get_proof_max_keys: cli.rpc_get_proof_max_keys,
+            rpc_execution_threads: cli.rpc_execution_threads,
+            rpc_storage_read_threads: cli.rpc_storage_read_threads,
+            compilation_limits: {
+                let defaults = CompilationLimits::default();
+                CompilationLimits {
+                    max_definition_size: cli.compiler_max_class_size.get(),
+                    timeout: Duration::from_secs(cli.compiler_timeout.get()),
+                    workers: cli.compiler_threads.unwrap_or(defaults.workers),
+                }
+            },
+            rpc_log_filter_changes: cli.rpc_log_filter_changes,
+            rpc_trace_cache_warmup: cli.rpc_trace_cache_warmup,
+            rpc_method_access: MethodAccessConfig {
+                disabled: cli.rpc_disabled_methods,
+                restricted: {
+                    let mut restricted = cli.rpc_restricted_methods;
+                    if cli.rpc_log_filter_changes {
+                        restricted.extend(LOG_FILTER_METHODS.map(str::to_owned));
+                    }
+                    if cli.rpc_gateway_outbox {
+                        restricted.extend(GATEWAY_OUTBOX_METHODS.map(str::to_owned));
+                    }
+                    if cli.rpc_trace_cache_warmup {
+                        restricted.extend(TRACE_CACHE_METHODS.map(str::to_owned));
+                    }
+                    restricted
+                },
+                api_keys: cli.rpc_api_keys.into_iter().collect(),
+            },
             gateway_timeout: Duration::from_secs(cli.gateway_timeout.get()),
             feeder_gateway_fetch_concurrency: cli.feeder_gateway_fetch_concurrency,
+            feeder_gateway_class_fetch_concurrency: cli.feeder_gateway_class_fetch_concurrency,
+            gateway_schema_drift_check_interval: (cli.gateway_schema_drift_check_interval > 0)
+                .then(|| Duration::from_secs(cli.gateway_schema_drift_check_interval)),
             state_tries: cli.state_tries,
+            disk_guard: (cli.storage_min_free_space_mb > 0).then(|| {
+                let degraded_below = cli.storage_min_free_space_mb;
+                let resume_above = cli
+                    .storage_resume_free_space_mb
+                    .unwrap_or(degraded_below.saturating_mul(2))
+                    .max(degraded_below);
+                DiskGuardConfig {
+                    degraded_below: degraded_below.saturating_mul(1024 * 1024),
+                    resume_above: resume_above.saturating_mul(1024 * 1024),
+                }
+            }),
+            storage_migration_batch_size: cli.storage_migration_batch_size,
+            event_retention: cli.storage_event_retention_blocks.map(|blocks_kept| {
+                EventRetentionConfig {
+                    blocks_kept,
+                    allowlist: cli.storage_event_retention_allowlist.into_iter().collect(),
+                }
+            }),
+            background_recompression: cli.storage_background_recompression,
+            defer_event_filters: cli.storage_defer_event_filters,
             custom_versioned_constants: cli
                 .custom_versioned_constants_path
                 .map(parse_versioned_constants_or_exit),
             fetch_casm_from_fgw: cli.fetch_casm_from_fgw,
+            sync_transaction_hash_verification: match cli.verify_gateway_data {
+                GatewayDataVerificationCli::Strict => TransactionHashVerification::Enforce,
+                GatewayDataVerificationCli::Default => {
+                    cli.sync_transaction_hash_verification.into()
+                }
+            },
+            sync_verification_concurrency: cli.sync_verification_concurrency,
+            gateway_data_verification: cli.verify_gateway_data.into(),
+            sync_class_stats: cli.sync_class_stats,
+            sync_write_throttle: WriteThrottleConfig {
+                max_blocks_per_second: cli.sync_max_blocks_per_second,
+                max_bytes_per_second: cli
+                    .sync_max_write_mb_per_second
+                    .and_then(|mb| NonZeroU64::new(u64::from(mb.get()) * 1024 * 1024)),
+                low_priority: cli.sync_low_priority,
+            },
             shutdown_grace_period: Duration::from_secs(cli.shutdown_grace_period.get()),
         }
     }
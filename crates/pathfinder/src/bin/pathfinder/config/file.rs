@@ -0,0 +1,359 @@
+//! Loading options from a TOML configuration file.
+//!
+//! The file mirrors the command line options, with the dots of an option's name
+//! mapping to tables. For example `--rpc.execution-threads 8` is written as
+//!
+//! ```toml
+//! [rpc]
+//! execution-threads = 8
+//! ```
+//!
+//! and options without a dot, such as `--network`, are top level keys. Lists
+//! are written as arrays.
+//!
+//! Options given on the command line take precedence over environment
+//! variables, which take precedence over the file. This is achieved by setting
+//! the environment variable of each option in the file unless it is already
+//! set, leaving the precedence of the others to clap.
+
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+/// Environment variable which can be used instead of `--config`.
+const CONFIG_ENV: &str = "PATHFINDER_CONFIG";
+
+/// Options whose values are not printed by `config print-effective`.
+const SECRET_OPTIONS: &[&str] = &["ethereum.password", "gateway-api-key", "rpc.api-keys"];
+
+/// What to do with the parsed configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Run the node.
+    Run,
+    /// `pathfinder config validate`: check the configuration and exit.
+    Validate,
+    /// `pathfinder config print-effective`: print the configuration after
+    /// merging all sources and exit.
+    PrintEffective,
+}
+
+/// Splits the `config <validate|print-effective>` subcommand off the command
+/// line arguments. The remaining arguments are the regular options.
+pub fn split_action(mut args: Vec<OsString>) -> Result<(Action, Vec<OsString>), String> {
+    if args.get(1).map(OsString::as_os_str) != Some(OsStr::new("config")) {
+        return Ok((Action::Run, args));
+    }
+
+    let action = match args.get(2).and_then(|arg| arg.to_str()) {
+        Some("validate") => Action::Validate,
+        Some("print-effective") => Action::PrintEffective,
+        Some(other) => {
+            return Err(format!(
+                "Unknown config command '{other}', expected 'validate' or 'print-effective'"
+            ))
+        }
+        None => return Err("Expected 'validate' or 'print-effective'".to_owned()),
+    };
+    args.drain(1..3);
+
+    Ok((action, args))
+}
+
+/// Returns the path of the configuration file given by `--config` or the
+/// `PATHFINDER_CONFIG` environment variable.
+pub fn path(args: &[OsString]) -> Option<PathBuf> {
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let Some(arg) = arg.to_str() else {
+            continue;
+        };
+        if arg == "--" {
+            break;
+        }
+        if arg == "--config" {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+
+    std::env::var_os(CONFIG_ENV).map(PathBuf::from)
+}
+
+/// Reads the configuration file at `path` and sets the environment variable of
+/// each option it contains, unless the variable is already set.
+///
+/// This must be called before any other threads are spawned.
+pub fn apply(command: &clap::Command, path: &Path) -> anyhow::Result<()> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Reading configuration file {}", path.display()))?;
+    let table: toml::Table = contents
+        .parse()
+        .with_context(|| format!("Parsing configuration file {}", path.display()))?;
+    let options = flatten(&table)?;
+
+    for (env, value) in env_vars(command, options)? {
+        if std::env::var_os(&env).is_none() {
+            std::env::set_var(env, value);
+        }
+    }
+
+    Ok(())
+}
+
+/// Flattens the tables of the configuration file into `(option, value)` pairs,
+/// e.g. `("rpc.execution-threads", "8")`.
+fn flatten(table: &toml::Table) -> anyhow::Result<Vec<(String, String)>> {
+    fn flatten_into(
+        prefix: &str,
+        table: &toml::Table,
+        options: &mut Vec<(String, String)>,
+    ) -> anyhow::Result<()> {
+        for (key, value) in table {
+            let name = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{prefix}.{key}")
+            };
+            match value {
+                toml::Value::Table(table) => flatten_into(&name, table, options)?,
+                toml::Value::Array(values) => {
+                    let values = values
+                        .iter()
+                        .map(scalar)
+                        .collect::<Option<Vec<_>>>()
+                        .with_context(|| format!("Option {name} must be an array of values"))?;
+                    options.push((name, values.join(",")));
+                }
+                value => {
+                    let value = scalar(value).context("Unexpected value type")?;
+                    options.push((name, value));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn scalar(value: &toml::Value) -> Option<String> {
+        match value {
+            toml::Value::String(s) => Some(s.clone()),
+            toml::Value::Integer(i) => Some(i.to_string()),
+            toml::Value::Float(f) => Some(f.to_string()),
+            toml::Value::Boolean(b) => Some(b.to_string()),
+            toml::Value::Datetime(d) => Some(d.to_string()),
+            toml::Value::Array(_) | toml::Value::Table(_) => None,
+        }
+    }
+
+    let mut options = Vec::new();
+    flatten_into("", table, &mut options)?;
+    Ok(options)
+}
+
+/// Maps options to the environment variables clap reads them from.
+fn env_vars(
+    command: &clap::Command,
+    options: Vec<(String, String)>,
+) -> anyhow::Result<Vec<(OsString, String)>> {
+    options
+        .into_iter()
+        .map(|(name, value)| {
+            let env = command
+                .get_arguments()
+                .find(|arg| arg.get_long() == Some(name.as_str()))
+                .and_then(|arg| arg.get_env())
+                .filter(|env| *env != OsStr::new(CONFIG_ENV))
+                .with_context(|| format!("Unknown option {name} in configuration file"))?;
+            Ok((env.to_owned(), value))
+        })
+        .collect()
+}
+
+/// Renders the value of every option, whether set on the command line, in the
+/// environment, in the configuration file or by default, as a configuration
+/// file.
+pub fn effective(command: &clap::Command, matches: &clap::ArgMatches) -> String {
+    let mut table = toml::Table::new();
+
+    for arg in command.get_arguments() {
+        let Some(name) = arg.get_long() else {
+            continue;
+        };
+        if name == "config" {
+            continue;
+        }
+        let Ok(Some(raw)) = matches.try_get_raw(arg.get_id().as_str()) else {
+            continue;
+        };
+        let values = raw
+            .map(|value| value.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+
+        let value = if SECRET_OPTIONS.contains(&name) {
+            toml::Value::String("<redacted>".to_owned())
+        } else if arg.get_value_delimiter().is_some() || values.len() > 1 {
+            toml::Value::Array(values.iter().map(|value| typed(value)).collect())
+        } else if let Some(value) = values.first() {
+            typed(value)
+        } else {
+            continue;
+        };
+
+        let mut path = name.split('.').peekable();
+        let mut current = &mut table;
+        while let Some(key) = path.next() {
+            if path.peek().is_none() {
+                current.insert(key.to_owned(), value);
+                break;
+            }
+            current = current
+                .entry(key)
+                .or_insert_with(|| toml::Value::Table(Default::default()))
+                .as_table_mut()
+                .expect("Option names are not prefixes of each other");
+        }
+    }
+
+    toml::to_string(&table).expect("Serializing a TOML table cannot fail")
+}
+
+/// Keeps numbers and booleans typed so the output reads like a hand written
+/// configuration file.
+fn typed(value: &str) -> toml::Value {
+    if let Ok(b) = value.parse() {
+        toml::Value::Boolean(b)
+    } else if let Ok(i) = value.parse() {
+        toml::Value::Integer(i)
+    } else {
+        toml::Value::String(value.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+    use crate::config::Cli;
+
+    #[test]
+    fn flatten_maps_tables_to_option_names() {
+        let table: toml::Table = r#"
+            network = "mainnet"
+
+            [rpc]
+            execution-threads = 8
+            disabled-methods = ["starknet_trace*", "starknet_simulateTransactions"]
+
+            [sync]
+            enable = false
+        "#
+        .parse()
+        .unwrap();
+
+        let mut options = flatten(&table).unwrap();
+        options.sort();
+        assert_eq!(
+            options,
+            vec![
+                ("network".to_owned(), "mainnet".to_owned()),
+                (
+                    "rpc.disabled-methods".to_owned(),
+                    "starknet_trace*,starknet_simulateTransactions".to_owned()
+                ),
+                ("rpc.execution-threads".to_owned(), "8".to_owned()),
+                ("sync.enable".to_owned(), "false".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn options_map_to_their_environment_variables() {
+        let command = Cli::command();
+        let env = env_vars(
+            &command,
+            vec![
+                ("rpc.execution-threads".to_owned(), "8".to_owned()),
+                ("data-directory".to_owned(), "/data".to_owned()),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            env,
+            vec![
+                ("PATHFINDER_RPC_EXECUTION_THREADS".into(), "8".to_owned()),
+                ("PATHFINDER_DATA_DIRECTORY".into(), "/data".to_owned()),
+            ]
+        );
+
+        let error = env_vars(&command, vec![("rpc.unknown".to_owned(), "1".to_owned())])
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("rpc.unknown"), "{error}");
+
+        // The file cannot point to another file.
+        env_vars(
+            &command,
+            vec![("config".to_owned(), "other.toml".to_owned())],
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn split_action() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+
+        assert_eq!(
+            super::split_action(args(&["pathfinder", "--network", "mainnet"])).unwrap(),
+            (Action::Run, args(&["pathfinder", "--network", "mainnet"]))
+        );
+        assert_eq!(
+            super::split_action(args(&["pathfinder", "config", "validate", "--config", "a"]))
+                .unwrap(),
+            (Action::Validate, args(&["pathfinder", "--config", "a"]))
+        );
+        assert_eq!(
+            super::split_action(args(&["pathfinder", "config", "print-effective"])).unwrap(),
+            (Action::PrintEffective, args(&["pathfinder"]))
+        );
+        super::split_action(args(&["pathfinder", "config", "edit"])).unwrap_err();
+    }
+
+    #[test]
+    fn effective_configuration_round_trips() {
+        let command = Cli::command();
+        let matches = command
+            .clone()
+            .try_get_matches_from([
+                "pathfinder",
+                "--network",
+                "mainnet",
+                "--ethereum.url",
+                "https://eth.example.com",
+                "--ethereum.password",
+                "hunter2",
+                "--rpc.execution-threads",
+                "8",
+                "--rpc.disabled-methods",
+                "starknet_trace*,starknet_simulateTransactions",
+            ])
+            .unwrap();
+
+        let effective = effective(&command, &matches);
+        assert!(!effective.contains("hunter2"), "{effective}");
+
+        let table: toml::Table = effective.parse().unwrap();
+        assert_eq!(table["network"].as_str(), Some("mainnet"));
+        assert_eq!(table["rpc"]["execution-threads"].as_integer(), Some(8));
+
+        let options = flatten(&table).unwrap();
+        assert!(options.contains(&(
+            "rpc.disabled-methods".to_owned(),
+            "starknet_trace*,starknet_simulateTransactions".to_owned()
+        )));
+        env_vars(&command, options).unwrap();
+    }
+}
@@ -0,0 +1,189 @@
+//! Offline database maintenance, invoked as `pathfinder database <COMMAND>`.
+//!
+//! These commands must not be run while a node is using the database.
+
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use pathfinder_common::BlockNumber;
+use pathfinder_lib::state::revert;
+use pathfinder_storage::{Inconsistency, TransactionBehavior};
+
+mod trie_export;
+
+#[derive(Parser)]
+#[command(name = "pathfinder database")]
+#[command(about = "Offline maintenance of a pathfinder database. Stop the node first.")]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Verifies invariants between tables which the schema does not enforce.
+    ///
+    /// Checks that every block with transactions has its transaction data
+    /// stored, that deployed and replaced classes are known, that the roots of
+    /// the Merkle tries exist and that the event filters cover all blocks.
+    Check {
+        #[arg(
+            long,
+            value_name = "FILE",
+            value_hint = clap::ValueHint::FilePath,
+            long_help = "Path to the database file, e.g. <DATA_DIRECTORY>/mainnet.sqlite"
+        )]
+        database: PathBuf,
+        #[arg(
+            long,
+            long_help = "Roll the database back to the block before the first inconsistency. The \
+                         removed blocks are downloaded again the next time the node is started."
+        )]
+        fix: bool,
+    },
+    /// Exports the Merkle tries at a block to a portable file.
+    ///
+    /// The file contains newline delimited JSON records: a header with the
+    /// block's commitments, every node of the contract, contract storage and
+    /// class tries keyed by its hash, and the contract states and compiled
+    /// class hashes the leaves commit to. Use `verify-tries` to check an
+    /// export.
+    ExportTries {
+        #[arg(
+            long,
+            value_name = "FILE",
+            value_hint = clap::ValueHint::FilePath,
+            long_help = "Path to the database file, e.g. <DATA_DIRECTORY>/mainnet.sqlite"
+        )]
+        database: PathBuf,
+        #[arg(
+            long,
+            value_name = "NUMBER",
+            long_help = "The block to export the tries of. Unless the database stores the tries \
+                         of all blocks this has to be one of the latest blocks."
+        )]
+        block: u64,
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        output: PathBuf,
+    },
+    /// Verifies a file created by `export-tries`.
+    ///
+    /// Recomputes the hash of every node, checks that the tries are complete
+    /// and that their roots and leaves match the commitments in the header.
+    VerifyTries {
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        input: PathBuf,
+    },
+}
+
+/// Runs the command if the first argument is `database`. Returns `None`
+/// otherwise so that the node is started as usual.
+pub fn run_if_requested() -> Option<anyhow::Result<()>> {
+    if std::env::args().nth(1).as_deref() != Some("database") {
+        return None;
+    }
+
+    // Drop the binary name so that `database` takes its place.
+    let cli = Cli::parse_from(std::env::args().skip(1));
+    let result = match cli.command {
+        Command::Check { database, fix } => check(database, fix),
+        Command::ExportTries {
+            database,
+            block,
+            output,
+        } => BlockNumber::new(block)
+            .context("Block number is out of range")
+            .and_then(|block| trie_export::export(database, block, output)),
+        Command::VerifyTries { input } => trie_export::verify(input),
+    };
+
+    Some(result)
+}
+
+fn check(database: PathBuf, fix: bool) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        database.exists(),
+        "Database file {} does not exist",
+        database.display()
+    );
+
+    let storage = pathfinder_storage::StorageBuilder::file(database)
+        .migrate()
+        .context("Opening database")?
+        .create_pool(std::num::NonZeroU32::new(1).unwrap())
+        .context("Creating database connection")?;
+    let mut db = storage
+        .connection()
+        .context("Creating database connection")?;
+    let transaction = db
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .context("Creating database transaction")?;
+
+    println!("Checking database consistency, this may take a while...");
+    let inconsistencies = transaction
+        .consistency_check()
+        .context("Checking database consistency")?;
+
+    let Some(first_affected) = inconsistencies.first().map(Inconsistency::block) else {
+        println!("No inconsistencies found.");
+        return Ok(());
+    };
+
+    println!("Found {} inconsistencies:", inconsistencies.len());
+    for inconsistency in &inconsistencies {
+        println!("  - {inconsistency}");
+    }
+
+    let Some(head) = transaction
+        .block_number(pathfinder_storage::BlockId::Latest)
+        .context("Querying latest block number")?
+    else {
+        println!("\nThe database contains no blocks, delete it and sync from scratch.");
+        return Ok(());
+    };
+    // Event filter gaps may extend past the latest block.
+    let first_affected = first_affected.min(head);
+
+    if !fix {
+        println!(
+            "\nBlocks {first_affected}..={head} are affected. Run this command with --fix to \
+             remove them, they will be downloaded again the next time the node is started."
+        );
+        return Ok(());
+    }
+
+    println!("\nRemoving blocks {first_affected}..={head}");
+    rollback(&transaction, head, first_affected).context(
+        "Rolling back the database failed. If the Merkle tries are damaged the database has to be \
+         synced from scratch.",
+    )?;
+    transaction
+        .commit()
+        .context("Committing database transaction")?;
+    println!(
+        "Done. The removed blocks will be downloaded again the next time the node is started."
+    );
+
+    Ok(())
+}
+
+fn rollback(
+    transaction: &pathfinder_storage::Transaction<'_>,
+    head: BlockNumber,
+    first_affected: BlockNumber,
+) -> anyhow::Result<()> {
+    revert::purge_blocks(transaction, head, first_affected)?;
+
+    // Make sure the fix actually helped.
+    let remaining = transaction
+        .consistency_check()
+        .context("Checking database consistency after rollback")?;
+    anyhow::ensure!(
+        remaining.is_empty(),
+        "{} inconsistencies remain after rolling back",
+        remaining.len()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,679 @@
+//! Export of the Merkle tries at a block in a portable format, and its
+//! verification.
+//!
+//! The export is a file of newline delimited JSON records, so that it can be
+//! consumed without knowledge of pathfinder's storage format:
+//!
+//! - a `header` record with the block and its commitments,
+//! - a `binary` or `edge` record for every node of the contract, contract
+//!   storage and class tries, mapping the node's hash to its children,
+//! - a `contract` record for every contract, with the preimage of its leaf in
+//!   the contract trie,
+//! - a `class` record for every Sierra class, with the preimage of its leaf in
+//!   the class trie.
+//!
+//! Leaves are not exported as nodes, they are the children of the nodes at the
+//! bottom of a trie.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::Context;
+use bitvec::prelude::{BitSlice, BitVec, Msb0};
+use pathfinder_common::hash::{FeltHash, PedersenHash, PoseidonHash};
+use pathfinder_common::trie::TrieNode;
+use pathfinder_common::{
+    calculate_class_commitment_leaf_hash,
+    BlockNumber,
+    CasmHash,
+    ClassCommitment,
+    ClassHash,
+    ContractAddress,
+    ContractNonce,
+    ContractRoot,
+    StateCommitment,
+    StorageCommitment,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_merkle_tree::contract_state::calculate_contract_state_hash;
+use pathfinder_merkle_tree::{ClassCommitmentTree, ContractsStorageTree, StorageCommitmentTree};
+use serde::{Deserialize, Serialize};
+
+/// Version of the export format.
+const VERSION: u32 = 1;
+
+/// Height of all Starknet tries.
+const HEIGHT: usize = 251;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Trie {
+    /// The trie of contract state hashes, whose root is the storage commitment.
+    Contracts,
+    /// The storage trie of a single contract.
+    ContractStorage,
+    /// The trie of compiled class hashes, whose root is the class commitment.
+    Classes,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Record {
+    Header {
+        version: u32,
+        block_number: u64,
+        block_hash: Felt,
+        state_commitment: Felt,
+        storage_commitment: Felt,
+        class_commitment: Felt,
+    },
+    Binary {
+        trie: Trie,
+        /// Set for nodes of [Trie::ContractStorage].
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        contract_address: Option<Felt>,
+        hash: Felt,
+        left: Felt,
+        right: Felt,
+    },
+    Edge {
+        trie: Trie,
+        /// Set for nodes of [Trie::ContractStorage].
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        contract_address: Option<Felt>,
+        hash: Felt,
+        child: Felt,
+        /// The path as a felt, of which the last `length` bits are used.
+        path: Felt,
+        length: usize,
+    },
+    Contract {
+        address: Felt,
+        class_hash: Felt,
+        nonce: Felt,
+        storage_root: Felt,
+    },
+    Class {
+        class_hash: Felt,
+        compiled_class_hash: Felt,
+    },
+}
+
+impl Record {
+    fn node(trie: Trie, contract_address: Option<Felt>, node: TrieNode, hash: Felt) -> Self {
+        match node {
+            TrieNode::Binary { left, right } => Self::Binary {
+                trie,
+                contract_address,
+                hash,
+                left,
+                right,
+            },
+            TrieNode::Edge { child, path } => Self::Edge {
+                trie,
+                contract_address,
+                hash,
+                child,
+                length: path.len(),
+                path: Felt::from_bits(&path).expect("Paths are at most 251 bits"),
+            },
+        }
+    }
+}
+
+/// Returns the `(key, value)` pairs of the leaves among the children of `node`
+/// at `path`.
+fn leaves(path: &BitSlice<u8, Msb0>, node: &TrieNode) -> Vec<(Felt, Felt)> {
+    let key = |path: &BitSlice<u8, Msb0>| Felt::from_bits(path).expect("Paths are 251 bits");
+
+    match node {
+        TrieNode::Binary { left, right } if path.len() + 1 == HEIGHT => {
+            let mut leaf_path = path.to_bitvec();
+            leaf_path.push(false);
+            let left_key = key(&leaf_path);
+            leaf_path.set(HEIGHT - 1, true);
+            vec![(left_key, *left), (key(&leaf_path), *right)]
+        }
+        TrieNode::Edge { child, path: edge } if path.len() + edge.len() == HEIGHT => {
+            let mut leaf_path = path.to_bitvec();
+            leaf_path.extend_from_bitslice(edge);
+            vec![(key(&leaf_path), *child)]
+        }
+        _ => vec![],
+    }
+}
+
+/// Exports the contract, contract storage and class tries at `block` to
+/// `output`.
+pub(super) fn export(database: PathBuf, block: BlockNumber, output: PathBuf) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        database.exists(),
+        "Database file {} does not exist",
+        database.display()
+    );
+
+    let storage = pathfinder_storage::StorageBuilder::file(database)
+        .migrate()
+        .context("Opening database")?
+        .create_pool(std::num::NonZeroU32::new(1).unwrap())
+        .context("Creating database connection")?;
+    let mut db = storage
+        .connection()
+        .context("Creating database connection")?;
+    let tx = db.transaction().context("Creating database transaction")?;
+
+    let header = tx
+        .block_header(block.into())
+        .context("Querying block header")?
+        .with_context(|| format!("Block {block} not found"))?;
+
+    let file = File::create(&output)
+        .with_context(|| format!("Creating export file {}", output.display()))?;
+    let mut writer = BufWriter::new(file);
+    let mut write = |record: &Record| -> anyhow::Result<()> {
+        serde_json::to_writer(&mut writer, record).context("Writing record")?;
+        writer.write_all(b"\n").context("Writing record")
+    };
+
+    write(&Record::Header {
+        version: VERSION,
+        block_number: header.number.get(),
+        block_hash: header.hash.0,
+        state_commitment: header.state_commitment.0,
+        storage_commitment: header.storage_commitment.0,
+        class_commitment: header.class_commitment.0,
+    })?;
+
+    const PRUNED_HINT: &str = "The tries of old blocks are not available if the database was \
+                               created with --storage.state-tries other than 'archive'";
+
+    let mut contracts = Vec::new();
+    if let Some(root) = tx
+        .storage_root_index(block)
+        .context("Querying contract trie root")?
+    {
+        println!("Exporting contract trie...");
+        StorageCommitmentTree::visit_nodes(&tx, block, root, |path, node, hash| {
+            contracts.extend(leaves(path, &node).into_iter().map(|(key, _)| key));
+            write(&Record::node(Trie::Contracts, None, node, hash))
+        })
+        .with_context(|| format!("Exporting contract trie. {PRUNED_HINT}"))?;
+    }
+
+    println!("Exporting storage of {} contracts...", contracts.len());
+    for address in contracts {
+        let contract = ContractAddress(address);
+        let class_hash = if contract.is_system_contract() {
+            ClassHash::ZERO
+        } else {
+            tx.contract_class_hash(block.into(), contract)
+                .context("Querying class hash")?
+                .with_context(|| format!("Class hash of contract {contract} is missing"))?
+        };
+        let nonce = tx
+            .contract_nonce(contract, block.into())
+            .context("Querying nonce")?
+            .unwrap_or_default();
+        let storage_root = tx
+            .contract_root(block, contract)
+            .context("Querying contract storage root")?
+            .unwrap_or_default();
+
+        write(&Record::Contract {
+            address,
+            class_hash: class_hash.0,
+            nonce: nonce.0,
+            storage_root: storage_root.0,
+        })?;
+
+        let Some(root) = tx
+            .contract_root_index(block, contract)
+            .context("Querying contract storage root index")?
+        else {
+            continue;
+        };
+        ContractsStorageTree::visit_nodes(&tx, contract, block, root, |_, node, hash| {
+            write(&Record::node(
+                Trie::ContractStorage,
+                Some(address),
+                node,
+                hash,
+            ))
+        })
+        .with_context(|| format!("Exporting storage trie of contract {contract}. {PRUNED_HINT}"))?;
+    }
+
+    let mut classes = Vec::new();
+    if let Some(root) = tx
+        .class_root_index(block)
+        .context("Querying class trie root")?
+    {
+        println!("Exporting class trie...");
+        ClassCommitmentTree::visit_nodes(&tx, block, root, |path, node, hash| {
+            classes.extend(leaves(path, &node).into_iter().map(|(key, _)| key));
+            write(&Record::node(Trie::Classes, None, node, hash))
+        })
+        .with_context(|| format!("Exporting class trie. {PRUNED_HINT}"))?;
+    }
+
+    for class_hash in classes {
+        let compiled_class_hash = tx
+            .casm_hash_at(block.into(), ClassHash(class_hash))
+            .context("Querying compiled class hash")?
+            .with_context(|| format!("Compiled class hash of class {class_hash} is missing"))?;
+
+        write(&Record::Class {
+            class_hash,
+            compiled_class_hash: compiled_class_hash.0,
+        })?;
+    }
+
+    drop(write);
+    writer.flush().context("Flushing export file")?;
+    println!(
+        "Exported the tries of block {block} to {}",
+        output.display()
+    );
+
+    Ok(())
+}
+
+/// Nodes of an export, keyed by their hash.
+#[derive(Default)]
+struct Nodes {
+    nodes: HashMap<Felt, TrieNode>,
+}
+
+impl Nodes {
+    /// Verifies the hash of `node` and adds it.
+    fn insert<H: FeltHash>(&mut self, hash: Felt, node: TrieNode) -> anyhow::Result<()> {
+        let computed = node.hash::<H>();
+        anyhow::ensure!(
+            computed == hash,
+            "Node hash mismatch, {hash} does not match its children which hash to {computed}"
+        );
+        self.nodes.insert(hash, node);
+        Ok(())
+    }
+
+    /// Walks the trie with the given root and returns its leaves, failing if
+    /// any node is missing.
+    fn leaves(&self, root: Felt) -> anyhow::Result<HashMap<Felt, Felt>> {
+        let mut leaves = HashMap::new();
+        if root == Felt::ZERO {
+            return Ok(leaves);
+        }
+
+        let mut visiting = vec![(root, BitVec::<u8, Msb0>::new())];
+        while let Some((hash, path)) = visiting.pop() {
+            let node = self
+                .nodes
+                .get(&hash)
+                .with_context(|| format!("Node {hash} is missing"))?;
+
+            match node {
+                TrieNode::Binary { left, right } => {
+                    anyhow::ensure!(path.len() < HEIGHT, "Node {hash} is too deep");
+                    if path.len() + 1 < HEIGHT {
+                        let mut left_path = path.clone();
+                        left_path.push(false);
+                        visiting.push((*left, left_path));
+                        let mut right_path = path.clone();
+                        right_path.push(true);
+                        visiting.push((*right, right_path));
+                    }
+                }
+                TrieNode::Edge { child, path: edge } => {
+                    anyhow::ensure!(path.len() + edge.len() <= HEIGHT, "Node {hash} is too deep");
+                    if path.len() + edge.len() < HEIGHT {
+                        let mut child_path = path.clone();
+                        child_path.extend_from_bitslice(edge);
+                        visiting.push((*child, child_path));
+                    }
+                }
+            }
+            leaves.extend(self::leaves(&path, node));
+        }
+
+        Ok(leaves)
+    }
+}
+
+/// Verifies that the export at `input` is complete and consistent with the
+/// state commitment in its header.
+pub(super) fn verify(input: PathBuf) -> anyhow::Result<()> {
+    let file =
+        File::open(&input).with_context(|| format!("Opening export file {}", input.display()))?;
+    let (block_number, nodes) = verify_records(BufReader::new(file))?;
+
+    println!("Verified {nodes} nodes of the tries of block {block_number}.");
+    Ok(())
+}
+
+/// Verifies the records of an export, returning the block number and the
+/// number of nodes.
+fn verify_records(reader: impl BufRead) -> anyhow::Result<(u64, usize)> {
+    let mut lines = reader.lines().enumerate();
+
+    let (_, header) = lines.next().context("Export is empty")?;
+    let header =
+        serde_json::from_str(&header.context("Reading header")?).context("Parsing header")?;
+    let Record::Header {
+        version,
+        block_number,
+        state_commitment,
+        storage_commitment,
+        class_commitment,
+        ..
+    } = header
+    else {
+        anyhow::bail!("Export must start with a header");
+    };
+    anyhow::ensure!(
+        version == VERSION,
+        "Unsupported export version {version}, expected {VERSION}"
+    );
+    anyhow::ensure!(
+        StateCommitment::calculate(
+            StorageCommitment(storage_commitment),
+            ClassCommitment(class_commitment)
+        )
+        .0 == state_commitment,
+        "State commitment does not match the storage and class commitments"
+    );
+
+    // The contract and contract storage tries share the hash function, so their
+    // nodes are kept together.
+    let mut pedersen_nodes = Nodes::default();
+    let mut poseidon_nodes = Nodes::default();
+    let mut contracts = HashMap::new();
+    let mut classes = HashMap::new();
+    let mut node_count = 0;
+
+    for (index, line) in lines {
+        let line_number = index + 1;
+        let line = line.with_context(|| format!("Reading line {line_number}"))?;
+        let record: Record =
+            serde_json::from_str(&line).with_context(|| format!("Parsing line {line_number}"))?;
+
+        let (trie, hash, node) = match record {
+            Record::Header { .. } => anyhow::bail!("Unexpected header on line {line_number}"),
+            Record::Contract {
+                address,
+                class_hash,
+                nonce,
+                storage_root,
+            } => {
+                contracts.insert(address, (class_hash, nonce, storage_root));
+                continue;
+            }
+            Record::Class {
+                class_hash,
+                compiled_class_hash,
+            } => {
+                classes.insert(class_hash, compiled_class_hash);
+                continue;
+            }
+            Record::Binary {
+                trie,
+                hash,
+                left,
+                right,
+                ..
+            } => (trie, hash, TrieNode::Binary { left, right }),
+            Record::Edge {
+                trie,
+                hash,
+                child,
+                path,
+                length,
+                ..
+            } => {
+                anyhow::ensure!(
+                    (1..=HEIGHT).contains(&length),
+                    "Invalid edge length {length} on line {line_number}"
+                );
+                let path = path.view_bits()[256 - length..].to_bitvec();
+                (trie, hash, TrieNode::Edge { child, path })
+            }
+        };
+
+        match trie {
+            Trie::Contracts | Trie::ContractStorage => {
+                pedersen_nodes.insert::<PedersenHash>(hash, node)
+            }
+            Trie::Classes => poseidon_nodes.insert::<PoseidonHash>(hash, node),
+        }
+        .with_context(|| format!("Verifying node on line {line_number}"))?;
+        node_count += 1;
+    }
+
+    let contract_leaves = pedersen_nodes
+        .leaves(storage_commitment)
+        .context("Walking contract trie")?;
+    anyhow::ensure!(
+        contract_leaves.len() == contracts.len(),
+        "The contract trie has {} leaves but {} contracts are exported",
+        contract_leaves.len(),
+        contracts.len()
+    );
+    for (address, state_hash) in contract_leaves {
+        let (class_hash, nonce, storage_root) = contracts
+            .get(&address)
+            .with_context(|| format!("Contract {address} is missing"))?;
+        let computed = calculate_contract_state_hash(
+            ClassHash(*class_hash),
+            ContractRoot(*storage_root),
+            ContractNonce(*nonce),
+        );
+        anyhow::ensure!(
+            computed.0 == state_hash,
+            "State hash of contract {address} does not match its leaf"
+        );
+        pedersen_nodes
+            .leaves(*storage_root)
+            .with_context(|| format!("Walking storage trie of contract {address}"))?;
+    }
+
+    let class_leaves = poseidon_nodes
+        .leaves(class_commitment)
+        .context("Walking class trie")?;
+    anyhow::ensure!(
+        class_leaves.len() == classes.len(),
+        "The class trie has {} leaves but {} classes are exported",
+        class_leaves.len(),
+        classes.len()
+    );
+    for (class_hash, leaf) in class_leaves {
+        let compiled_class_hash = classes
+            .get(&class_hash)
+            .with_context(|| format!("Class {class_hash} is missing"))?;
+        anyhow::ensure!(
+            calculate_class_commitment_leaf_hash(CasmHash(*compiled_class_hash)).0 == leaf,
+            "Compiled class hash of class {class_hash} does not match its leaf"
+        );
+    }
+
+    Ok((block_number, node_count))
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    /// Builds an export of a state with two contracts, one of them with
+    /// storage, and a class.
+    fn export() -> Vec<u8> {
+        let storage_address = storage_address!("0x5");
+        let contract = contract_address!("0x123");
+        let other_contract = contract_address!("0x456");
+        let class_hash = class_hash!("0xc1");
+        let sierra_hash = sierra_hash!("0xc2");
+        let casm_hash = casm_hash!("0xc3");
+        let key = |key: Felt| key.view_bits()[256 - HEIGHT..].to_bitvec();
+
+        let mut records = Vec::new();
+        let mut add_tree =
+            |trie: Trie, contract: Option<Felt>, leaves: Vec<(Felt, Felt)>, poseidon: bool| {
+                let (root, nodes) = if poseidon {
+                    tree::<PoseidonHash>(leaves.iter().map(|(k, v)| (key(*k), *v)))
+                } else {
+                    tree::<PedersenHash>(leaves.iter().map(|(k, v)| (key(*k), *v)))
+                };
+                records.extend(
+                    nodes
+                        .into_iter()
+                        .map(|(hash, node)| Record::node(trie, contract, node, hash)),
+                );
+                root
+            };
+
+        let storage_root = add_tree(
+            Trie::ContractStorage,
+            Some(contract.0),
+            vec![(storage_address.0, felt!("0x1"))],
+            false,
+        );
+        let state_hash = calculate_contract_state_hash(
+            class_hash,
+            ContractRoot(storage_root),
+            ContractNonce(felt!("0x2")),
+        );
+        let other_state_hash =
+            calculate_contract_state_hash(class_hash, ContractRoot::ZERO, ContractNonce::ZERO);
+        let storage_commitment = add_tree(
+            Trie::Contracts,
+            None,
+            vec![
+                (contract.0, state_hash.0),
+                (other_contract.0, other_state_hash.0),
+            ],
+            false,
+        );
+        let class_commitment = add_tree(
+            Trie::Classes,
+            None,
+            vec![(
+                sierra_hash.0,
+                calculate_class_commitment_leaf_hash(casm_hash).0,
+            )],
+            true,
+        );
+
+        records.push(Record::Contract {
+            address: contract.0,
+            class_hash: class_hash.0,
+            nonce: felt!("0x2"),
+            storage_root,
+        });
+        records.push(Record::Contract {
+            address: other_contract.0,
+            class_hash: class_hash.0,
+            nonce: Felt::ZERO,
+            storage_root: Felt::ZERO,
+        });
+        records.push(Record::Class {
+            class_hash: sierra_hash.0,
+            compiled_class_hash: casm_hash.0,
+        });
+        records.insert(
+            0,
+            Record::Header {
+                version: VERSION,
+                block_number: 7,
+                block_hash: felt!("0xb"),
+                state_commitment: StateCommitment::calculate(
+                    StorageCommitment(storage_commitment),
+                    ClassCommitment(class_commitment),
+                )
+                .0,
+                storage_commitment,
+                class_commitment,
+            },
+        );
+
+        let mut output = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut output, &record).unwrap();
+            output.push(b'\n');
+        }
+        output
+    }
+
+    /// Builds the nodes of a trie with the given leaves, returning its root.
+    fn tree<H: FeltHash>(
+        leaves: impl Iterator<Item = (BitVec<u8, Msb0>, Felt)>,
+    ) -> (Felt, Vec<(Felt, TrieNode)>) {
+        fn build<H: FeltHash>(
+            depth: usize,
+            leaves: &[(BitVec<u8, Msb0>, Felt)],
+            nodes: &mut Vec<(Felt, TrieNode)>,
+        ) -> Felt {
+            if depth == HEIGHT {
+                return leaves[0].1;
+            }
+            let (left, right): (Vec<_>, Vec<_>) =
+                leaves.iter().cloned().partition(|(key, _)| !key[depth]);
+            let node = match (left.is_empty(), right.is_empty()) {
+                (false, false) => TrieNode::Binary {
+                    left: build::<H>(depth + 1, &left, nodes),
+                    right: build::<H>(depth + 1, &right, nodes),
+                },
+                _ => {
+                    // A single remaining leaf, or a shared prefix, forms an edge to the
+                    // point where the leaves diverge.
+                    let first = &leaves[0].0;
+                    let mut end = depth;
+                    while end < HEIGHT && leaves.iter().all(|(key, _)| key[end] == first[end]) {
+                        end += 1;
+                    }
+                    TrieNode::Edge {
+                        child: build::<H>(end, leaves, nodes),
+                        path: first[depth..end].to_bitvec(),
+                    }
+                }
+            };
+            let hash = node.hash::<H>();
+            nodes.push((hash, node));
+            hash
+        }
+
+        let leaves = leaves.collect::<Vec<_>>();
+        let mut nodes = Vec::new();
+        let root = build::<H>(0, &leaves, &mut nodes);
+        (root, nodes)
+    }
+
+    #[test]
+    fn verifies_consistent_export() {
+        let (block_number, nodes) = verify_records(export().as_slice()).unwrap();
+        assert_eq!(block_number, 7);
+        assert!(nodes > 0);
+    }
+
+    #[test]
+    fn detects_missing_node() {
+        let export = String::from_utf8(export()).unwrap();
+        let without_node = export
+            .lines()
+            .filter(|line| !line.contains("\"contract_storage\""))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let error = verify_records(without_node.as_bytes()).unwrap_err();
+        assert!(format!("{error:#}").contains("missing"), "{error:#}");
+    }
+
+    #[test]
+    fn detects_tampered_leaf_preimage() {
+        let export = String::from_utf8(export()).unwrap();
+        let tampered = export.replace("\"nonce\":\"0x2\"", "\"nonce\":\"0x3\"");
+        assert_ne!(export, tampered);
+
+        verify_records(tampered.as_bytes()).unwrap_err();
+    }
+}
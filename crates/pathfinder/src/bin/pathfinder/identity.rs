@@ -0,0 +1,304 @@
+//! Management of the node's p2p identity, invoked as
+//! `pathfinder identity <COMMAND>`.
+//!
+//! The identity is an ed25519 key pair stored in a keystore file, which is
+//! passed to the node with `--p2p.identity-config-file`. The file contains the
+//! base64 encoded protobuf encoding of the private key, and for convenience
+//! the peer ID derived from it:
+//!
+//! ```json
+//! {"private_key":"CAESQ...","peer_id":"12D3KooW..."}
+//! ```
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use p2p::libp2p::identity::Keypair;
+use p2p::libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+#[derive(Parser)]
+#[command(name = "pathfinder identity")]
+#[command(about = "Management of the node's p2p identity.")]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generates a new ed25519 identity and stores it in a keystore file.
+    Generate {
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        keystore: PathBuf,
+        #[arg(long, long_help = "Overwrite the keystore file if it exists.")]
+        force: bool,
+    },
+    /// Stores an existing identity in a keystore file.
+    ///
+    /// The input is either a keystore file or a file containing only the
+    /// base64 encoded private key.
+    Import {
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        keystore: PathBuf,
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        from: PathBuf,
+        #[arg(long, long_help = "Overwrite the keystore file if it exists.")]
+        force: bool,
+    },
+    /// Prints the keystore, including the private key, to stdout.
+    Export {
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        keystore: PathBuf,
+    },
+    /// Replaces the identity in a keystore file with a newly generated one.
+    ///
+    /// The previous keystore is kept next to it, with the previous peer ID
+    /// and `.bak` appended to the file name.
+    Rotate {
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        keystore: PathBuf,
+    },
+    /// Prints the peer ID of the identity in a keystore file.
+    PeerId {
+        #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+        keystore: PathBuf,
+    },
+}
+
+/// Runs the command if the first argument is `identity`. Returns `None`
+/// otherwise so that the node is started as usual.
+pub fn run_if_requested() -> Option<anyhow::Result<()>> {
+    if std::env::args().nth(1).as_deref() != Some("identity") {
+        return None;
+    }
+
+    // Drop the binary name so that `identity` takes its place.
+    let cli = Cli::parse_from(std::env::args().skip(1));
+    let result = match cli.command {
+        Command::Generate { keystore, force } => {
+            generate(&keystore, force).map(|peer_id| println!("{peer_id}"))
+        }
+        Command::Import {
+            keystore,
+            from,
+            force,
+        } => import(&keystore, &from, force).map(|peer_id| println!("{peer_id}")),
+        Command::Export { keystore } => export(&keystore).map(|json| println!("{}", *json)),
+        Command::Rotate { keystore } => rotate(&keystore).map(|(previous, new)| {
+            println!("Rotated identity {previous} to {new}");
+        }),
+        Command::PeerId { keystore } => load(&keystore).map(|keypair| {
+            println!("{}", keypair.public().to_peer_id());
+        }),
+    };
+
+    Some(result)
+}
+
+#[derive(Serialize, Deserialize)]
+struct Keystore {
+    private_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    peer_id: Option<String>,
+}
+
+impl zeroize::Zeroize for Keystore {
+    fn zeroize(&mut self) {
+        self.private_key.zeroize()
+    }
+}
+
+impl Keystore {
+    fn new(keypair: &Keypair) -> anyhow::Result<Self> {
+        let private_key = Zeroizing::new(
+            keypair
+                .to_protobuf_encoding()
+                .context("Encoding private key")?,
+        );
+
+        Ok(Self {
+            private_key: base64::encode(&*private_key),
+            peer_id: Some(keypair.public().to_peer_id().to_string()),
+        })
+    }
+
+    fn keypair(&self) -> anyhow::Result<Keypair> {
+        let private_key = Zeroizing::new(
+            base64::decode(self.private_key.trim().as_bytes()).context("Decoding private key")?,
+        );
+        Keypair::from_protobuf_encoding(&private_key).context("Parsing private key")
+    }
+}
+
+/// Loads the identity from a keystore file.
+pub fn load(path: &Path) -> anyhow::Result<Keypair> {
+    let json = Zeroizing::new(
+        std::fs::read_to_string(path)
+            .with_context(|| format!("Reading keystore {}", path.display()))?,
+    );
+    let keystore = Zeroizing::new(
+        serde_json::from_str::<Keystore>(&json)
+            .with_context(|| format!("Parsing keystore {}", path.display()))?,
+    );
+    keystore.keypair()
+}
+
+/// Writes the keystore file, readable by the owner only.
+fn store(path: &Path, keypair: &Keypair, force: bool) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        force || !path.exists(),
+        "Keystore {} already exists",
+        path.display()
+    );
+
+    let json = Zeroizing::new(
+        serde_json::to_string(&*Zeroizing::new(Keystore::new(keypair)?))
+            .context("Serializing keystore")?,
+    );
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut file = tempfile::NamedTempFile::new_in(dir).context("Creating keystore file")?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.as_file()
+            .set_permissions(std::fs::Permissions::from_mode(0o600))
+            .context("Restricting keystore permissions")?;
+    }
+    file.write_all(json.as_bytes())
+        .context("Writing keystore")?;
+    file.persist(path)
+        .with_context(|| format!("Writing keystore {}", path.display()))?;
+
+    Ok(())
+}
+
+fn generate(keystore: &Path, force: bool) -> anyhow::Result<PeerId> {
+    let keypair = Keypair::generate_ed25519();
+    store(keystore, &keypair, force)?;
+    Ok(keypair.public().to_peer_id())
+}
+
+fn import(keystore: &Path, from: &Path, force: bool) -> anyhow::Result<PeerId> {
+    let input = Zeroizing::new(
+        std::fs::read_to_string(from).with_context(|| format!("Reading {}", from.display()))?,
+    );
+    let keypair = if input.trim_start().starts_with('{') {
+        Zeroizing::new(serde_json::from_str::<Keystore>(&input).context("Parsing keystore")?)
+            .keypair()?
+    } else {
+        Zeroizing::new(Keystore {
+            private_key: input.trim().to_owned(),
+            peer_id: None,
+        })
+        .keypair()?
+    };
+
+    store(keystore, &keypair, force)?;
+    Ok(keypair.public().to_peer_id())
+}
+
+fn export(keystore: &Path) -> anyhow::Result<Zeroizing<String>> {
+    let keypair = load(keystore)?;
+    let keystore = Zeroizing::new(Keystore::new(&keypair)?);
+    serde_json::to_string(&*keystore)
+        .map(Zeroizing::new)
+        .context("Serializing keystore")
+}
+
+/// Returns the previous and the new peer ID.
+fn rotate(keystore: &Path) -> anyhow::Result<(PeerId, PeerId)> {
+    let previous = load(keystore)?.public().to_peer_id();
+
+    let mut backup = keystore.as_os_str().to_owned();
+    backup.push(format!(".{previous}.bak"));
+    std::fs::copy(keystore, &backup).context("Backing up keystore")?;
+
+    let new = generate(keystore, true)?;
+    Ok((previous, new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = dir.path().join("identity.json");
+
+        let peer_id = generate(&keystore, false).unwrap();
+        assert_eq!(load(&keystore).unwrap().public().to_peer_id(), peer_id);
+
+        generate(&keystore, false).unwrap_err();
+        let overwritten = generate(&keystore, true).unwrap();
+        assert_ne!(overwritten, peer_id);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&keystore).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn import_keystore_and_raw_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("original.json");
+        let peer_id = generate(&original, false).unwrap();
+
+        let imported = dir.path().join("imported.json");
+        assert_eq!(import(&imported, &original, false).unwrap(), peer_id);
+
+        let raw = dir.path().join("raw.key");
+        let keystore: Keystore =
+            serde_json::from_str(&std::fs::read_to_string(&original).unwrap()).unwrap();
+        std::fs::write(&raw, format!("{}\n", keystore.private_key)).unwrap();
+        let imported = dir.path().join("imported_raw.json");
+        assert_eq!(import(&imported, &raw, false).unwrap(), peer_id);
+
+        let exported = export(&imported).unwrap();
+        let exported: Keystore = serde_json::from_str(&exported).unwrap();
+        assert_eq!(exported.private_key, keystore.private_key);
+        assert_eq!(exported.peer_id, Some(peer_id.to_string()));
+    }
+
+    #[test]
+    fn rotate_keeps_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = dir.path().join("identity.json");
+        let original = generate(&keystore, false).unwrap();
+
+        let (previous, new) = rotate(&keystore).unwrap();
+        assert_eq!(previous, original);
+        assert_ne!(new, original);
+        assert_eq!(load(&keystore).unwrap().public().to_peer_id(), new);
+
+        let backup = dir.path().join(format!("identity.json.{original}.bak"));
+        assert_eq!(load(&backup).unwrap().public().to_peer_id(), original);
+    }
+
+    #[test]
+    fn existing_identity_files_are_supported() {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = dir.path().join("identity.json");
+        std::fs::write(
+            &keystore,
+            r#"{"private_key":"CAESQArAo83bMrNgftGfokSJ0XcP26bgn6WL3vXUhqUR8BbVVPsL0F/dGWu+VZPcnP3DhH24s5EaHVOinqv2BEkbfdc="}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            load(&keystore).unwrap().public().to_peer_id().to_string(),
+            "12D3KooWFY6SaqJkRxJDepwvBi4Rw36iMUGZrejW69qkjYQQ2ydQ"
+        );
+    }
+}
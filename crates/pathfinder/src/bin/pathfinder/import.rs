@@ -0,0 +1,134 @@
+//! Trusted import of blocks pushed by a sequencer, invoked as
+//! `pathfinder import`.
+//!
+//! Meant for appchain deployments where the sequencer hands its blocks to the
+//! node rather than serving them through a feeder gateway. The bundles are
+//! JSON objects in the format of [Bundle]:
+//!
+//! ```json
+//! {"block":{..},"state_update":{..},"signature":{..},"classes":[{..}]}
+//! ```
+//!
+//! The database must not be written to by anything else while importing, so
+//! a node serving it has to run with `--sync.enable=false`.
+
+use std::io::BufReader;
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::Parser;
+use pathfinder_common::{Chain, ChainId, PublicKey};
+use pathfinder_crypto::Felt;
+use pathfinder_lib::state::import::{import, Bundle, ImportContext};
+
+#[derive(Parser)]
+#[command(name = "pathfinder import")]
+#[command(about = "Applies signed blocks pushed by a sequencer to a database.")]
+pub struct Cli {
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_hint = clap::ValueHint::FilePath,
+        long_help = "Path to the database file, e.g. <DATA_DIRECTORY>/mainnet.sqlite"
+    )]
+    database: PathBuf,
+    #[arg(
+        long = "chain-id",
+        value_name = "CHAIN ID",
+        long_help = "The chain ID of the network, e.g. SN_MAIN or the ID of the appchain"
+    )]
+    chain_id: String,
+    #[arg(
+        long = "sequencer-public-key",
+        value_name = "KEY",
+        value_parser = parse_public_key,
+        long_help = "The public key the sequencer signs block hashes with"
+    )]
+    sequencer_public_key: PublicKey,
+    #[arg(
+        long = "verify-tree-hashes",
+        long_help = "Verify the hashes of the Merkle tree nodes updated by each block"
+    )]
+    verify_tree_hashes: bool,
+    #[arg(
+        value_name = "BUNDLE",
+        required = true,
+        long_help = "Files containing the bundles to import in order, or `-` to read them from \
+                     stdin. A file may contain several bundles one after another."
+    )]
+    bundles: Vec<PathBuf>,
+}
+
+/// Runs the command if the first argument is `import`. Returns `None`
+/// otherwise so that the node is started as usual.
+pub fn run_if_requested() -> Option<anyhow::Result<()>> {
+    if std::env::args().nth(1).as_deref() != Some("import") {
+        return None;
+    }
+
+    // Drop the binary name so that `import` takes its place.
+    let cli = Cli::parse_from(std::env::args().skip(1));
+    let result = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .context("Creating runtime")
+        .and_then(|runtime| runtime.block_on(run(cli)));
+
+    Some(result)
+}
+
+async fn run(cli: Cli) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        cli.database.exists(),
+        "Database file {} does not exist",
+        cli.database.display()
+    );
+
+    let chain_id = ChainId(Felt::from_short_string(&cli.chain_id).context("Parsing chain ID")?);
+    let chain = match chain_id {
+        ChainId::MAINNET => Chain::Mainnet,
+        ChainId::SEPOLIA_TESTNET => Chain::SepoliaTestnet,
+        ChainId::SEPOLIA_INTEGRATION => Chain::SepoliaIntegration,
+        _ => Chain::Custom,
+    };
+
+    let storage = pathfinder_storage::StorageBuilder::file(cli.database)
+        .migrate()
+        .context("Opening database")?
+        // Contract state updates are computed in parallel using extra connections.
+        .create_pool(NonZeroU32::new(8).unwrap())
+        .context("Creating database connection pool")?;
+
+    let context = ImportContext {
+        storage,
+        chain,
+        chain_id,
+        sequencer_public_key: cli.sequencer_public_key,
+        verify_tree_hashes: cli.verify_tree_hashes,
+    };
+
+    for path in cli.bundles {
+        let reader: Box<dyn std::io::Read> = if path.as_os_str() == "-" {
+            Box::new(std::io::stdin().lock())
+        } else {
+            let file = std::fs::File::open(&path)
+                .with_context(|| format!("Opening {}", path.display()))?;
+            Box::new(BufReader::new(file))
+        };
+
+        for bundle in serde_json::Deserializer::from_reader(reader).into_iter::<Bundle>() {
+            let bundle = bundle.with_context(|| format!("Parsing bundle in {}", path.display()))?;
+            let block_number = import(&context, bundle).await?;
+            println!("Imported block {block_number}");
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_public_key(s: &str) -> Result<PublicKey, String> {
+    Felt::from_hex_str(s.trim())
+        .map(PublicKey)
+        .map_err(|_| format!("Invalid public key: {s}"))
+}
@@ -1,7 +1,7 @@
 #![deny(rust_2018_idioms)]
 
 use std::net::SocketAddr;
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
@@ -13,9 +13,10 @@ use pathfinder_common::consts::VERGEN_GIT_DESCRIBE;
 use pathfinder_common::{BlockNumber, Chain, ChainId, EthereumChain};
 use pathfinder_ethereum::{EthereumApi, EthereumClient};
 use pathfinder_lib::monitoring::{self};
-use pathfinder_lib::state;
 use pathfinder_lib::state::SyncContext;
+use pathfinder_lib::{disk_guard, event_filter_backfill, lag_alert, recompression, state};
 use pathfinder_rpc::context::{EthContractAddresses, WebsocketContext};
+use pathfinder_rpc::log_filter::LogFilter;
 use pathfinder_rpc::{Notifications, SyncState};
 use pathfinder_storage::Storage;
 use starknet_gateway_client::GatewayApi;
@@ -26,6 +27,10 @@ use tracing::{info, warn};
 use crate::config::{NetworkConfig, StateTries};
 
 mod config;
+mod database;
+mod identity;
+mod import;
+mod schema_drift;
 mod update;
 
 // The Cairo VM allocates felts on the stack, so during execution it's making
@@ -35,6 +40,16 @@ mod update;
 static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
 fn main() -> anyhow::Result<()> {
+    if let Some(result) = database::run_if_requested() {
+        return result;
+    }
+    if let Some(result) = identity::run_if_requested() {
+        return result;
+    }
+    if let Some(result) = import::run_if_requested() {
+        return result;
+    }
+
     tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .thread_stack_size(8 * 1024 * 1024)
@@ -54,7 +69,7 @@ async fn async_main() -> anyhow::Result<Storage> {
 
     let mut config = config::Config::parse();
 
-    setup_tracing(
+    let log_filter = setup_tracing(
         config.color,
         config.debug.pretty_log,
         config.log_output_json,
@@ -87,9 +102,13 @@ async fn async_main() -> anyhow::Result<Storage> {
 
     let sync_state = Arc::new(SyncState::default());
 
-    let ethereum = EthereumContext::setup(config.ethereum.url.clone(), &config.ethereum.password)
-        .await
-        .context("Creating Ethereum context")?;
+    let ethereum = EthereumContext::setup(
+        config.ethereum.url.clone(),
+        &config.ethereum.password,
+        &config.ethereum.l1_mode,
+    )
+    .await
+    .context("Creating Ethereum context")?;
 
     // Use the default starknet network if none was configured.
     let network = match config.network {
@@ -124,6 +143,20 @@ async fn async_main() -> anyhow::Result<Storage> {
 
     // Setup and verify database
 
+    if let Some(guard) = config.disk_guard {
+        guard
+            .ensure_free_space(&config.data_directory)
+            .context("Checking free disk space before migrating the database")?;
+    }
+    let available_space = match disk_guard::available_space(&config.data_directory) {
+        // Migrations should not use up the space reserved by the disk guard.
+        Ok(free) => Some(free.saturating_sub(config.disk_guard.map_or(0, |g| g.degraded_below))),
+        Err(error) => {
+            tracing::warn!(%error, "Failed to check free disk space");
+            None
+        }
+    };
+
     let storage_manager =
         pathfinder_storage::StorageBuilder::file(pathfinder_context.database.clone())
             .journal_mode(config.sqlite_wal)
@@ -135,6 +168,10 @@ async fn async_main() -> anyhow::Result<Storage> {
                 Some(StateTries::Archive) => Some(pathfinder_storage::TriePruneMode::Archive),
                 None => None,
             })
+            .migration_config(pathfinder_storage::MigrationConfig {
+                batch_size: config.storage_migration_batch_size,
+                available_space,
+            })
             .migrate()?;
 
     let sync_storage = storage_manager
@@ -148,6 +185,29 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
       Try increasing the file limit to using `ulimit` or similar tooling.",
         )?;
 
+    let event_filters_deferred = {
+        let mut db = sync_storage
+            .connection()
+            .context("Creating database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+        if config.defer_event_filters && !tx.event_filters_deferred() {
+            let is_new_database = tx
+                .block_number(pathfinder_storage::BlockId::Latest)
+                .context("Querying latest block number")?
+                .is_none();
+            if is_new_database {
+                tx.defer_event_filters()
+                    .context("Deferring event filters")?;
+                tracing::info!("Event filters are built once initial sync has caught up");
+            } else {
+                tracing::warn!("Event filters can only be deferred on a new database, ignoring");
+            }
+        }
+        let deferred = tx.event_filters_deferred();
+        tx.commit().context("Committing database transaction")?;
+        deferred
+    };
+
     // Set the rpc file connection limit to a fraction of the RPC connections.
     // Having this be too large is counter productive as disk IO will then slow down
     // all queries.
@@ -157,18 +217,29 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         .try_into()
         .expect("usize should cast to u32");
     let rpc_storage = std::cmp::max(10, max_rpc_connections / 8);
-    let rpc_storage = NonZeroU32::new(rpc_storage).expect("A non-zero minimum is set");
-    let rpc_storage = storage_manager.create_read_only_pool(rpc_storage).context(
-        r"Creating database connection pool for RPC
+    let rpc_storage_pool_size = NonZeroU32::new(rpc_storage).expect("A non-zero minimum is set");
+    let rpc_storage = storage_manager
+        .create_read_only_pool(rpc_storage_pool_size)
+        .context(
+            r"Creating database connection pool for RPC
 
 Hint: This is usually caused by exceeding the file descriptor limit of your system.
       Try increasing the file limit to using `ulimit` or similar tooling.",
-    )?;
+        )?;
 
     let execution_storage_pool_size = config.execution_concurrency.unwrap_or_else(|| {
         std::num::NonZeroU32::new(available_parallelism.get() as u32)
             .expect("The number of CPU cores should be non-zero")
     });
+    util::task::configure_pools(util::task::PoolSizes {
+        execution: config
+            .rpc_execution_threads
+            .unwrap_or(NonZeroUsize::try_from(execution_storage_pool_size)?),
+        storage: config
+            .rpc_storage_read_threads
+            .unwrap_or(NonZeroUsize::try_from(rpc_storage_pool_size)?),
+    })?;
+
     let execution_storage = storage_manager
         .create_read_only_pool(execution_storage_pool_size)
         .context(
@@ -188,6 +259,17 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
       Try increasing the file limit to using `ulimit` or similar tooling.",
         )?;
     info!(location=?pathfinder_context.database, "Database migrated.");
+    pathfinder_executor::configure_block_hash_contract(config.rpc_block_hash_contract)
+        .context("Configuring the block hash contract")?;
+    pathfinder_compiler::limits::configure(config.compilation_limits)
+        .context("Configuring the compilation limits")?;
+    if config.rpc_persistent_class_cache {
+        let class_cache_storage = storage_manager
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .context("Creating database connection pool for the class cache")?;
+        pathfinder_executor::enable_persistent_class_cache(class_cache_storage)
+            .context("Enabling persistent class cache")?;
+    }
     verify_database(
         &sync_storage,
         pathfinder_context.network,
@@ -204,12 +286,53 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         .prune_tries()
         .context("Pruning tries on startup")?;
 
+    if let Some(event_retention) = &config.event_retention {
+        let mut connection = sync_storage
+            .connection()
+            .context("Creating database connection")?;
+        // Batches are committed one by one so that the first run against an
+        // archive database does not rewrite the whole event history in a
+        // single transaction.
+        loop {
+            let tx = connection
+                .transaction()
+                .context("Creating database transaction")?;
+            let pruning = tx
+                .prune_events(
+                    event_retention,
+                    pathfinder_storage::EVENT_PRUNING_BATCH_SIZE,
+                )
+                .context("Pruning events on startup")?;
+            tx.commit().context("Committing database transaction")?;
+
+            if pruning.remaining == 0 {
+                info!(
+                    pruned_before=%pruning.retention.pruned_before,
+                    allowlisted=%pruning.retention.allowlist.len(),
+                    "Pruned events outside of the retention window"
+                );
+                break;
+            }
+
+            info!(
+                pruned_before=%pruning.retention.pruned_before,
+                remaining=%pruning.remaining,
+                "Pruning events outside of the retention window"
+            );
+        }
+    }
+
     // Register signal handlers here, because we want to be able to interrupt long
     // running migrations or trie pruning. No tasks are spawned before this point so
     // we don't worry about detachment.
     let mut term_signal = signal(SignalKind::terminate())?;
     let mut int_signal = signal(SignalKind::interrupt())?;
 
+    let disk_degraded = match config.disk_guard {
+        Some(guard) => disk_guard::spawn(guard, config.data_directory.clone()),
+        None => tokio::sync::watch::channel(false).1,
+    };
+
     let (tx_pending, rx_pending) = tokio::sync::watch::channel(Default::default());
 
     let rpc_config = pathfinder_rpc::context::RpcConfig {
@@ -218,6 +341,23 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         get_events_max_uncached_event_filters_to_load: config
             .get_events_max_uncached_event_filters_to_load,
         custom_versioned_constants: config.custom_versioned_constants.take(),
+        max_request_body_size: config.rpc_max_request_body_size,
+        response_size_limits: config.rpc_response_size_limits.clone(),
+        trace_cache: config.rpc_trace_cache,
+        gateway_circuit_breaker: config.rpc_gateway_circuit_breaker,
+        additional_fee_tokens: config.rpc_additional_fee_tokens.clone(),
+        execution_policy: Arc::new(config.rpc_execution_policy.clone()),
+        reconstruct_gateway_trace_events: config.rpc_reconstruct_gateway_trace_events,
+        idempotency_key_ttl: config.rpc_idempotency_key_ttl,
+        gateway_outbox: config.rpc_gateway_outbox,
+        rejected_transaction_archive: config.rpc_rejected_transaction_archive,
+        declare_precheck: config.rpc_declare_precheck,
+        trace_cache_warmup: config.rpc_trace_cache_warmup,
+        get_proof_max_keys: config.rpc_get_proof_max_keys,
+        event_filter_max_keys: config.event_filter_max_keys,
+        get_events_max_chunk_size: config.get_events_max_chunk_size,
+        method_access: config.rpc_method_access.clone(),
+        response_cache_max_size: config.rpc_response_cache_max_size,
     };
 
     let notifications = Notifications::default();
@@ -244,6 +384,37 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
     } else {
         context
     };
+    let context = context.with_degraded_mode(disk_degraded.clone());
+    let context = if config.rpc_log_filter_changes {
+        context.with_log_filter(log_filter)
+    } else {
+        context
+    };
+    let context = match config.rpc_request_log.clone() {
+        Some(request_log) => context.with_request_log(
+            pathfinder_rpc::request_log::RequestLog::new(request_log)
+                .context("Creating RPC request log")?,
+        ),
+        None => context,
+    };
+
+    if config.rpc_gateway_outbox {
+        pathfinder_rpc::outbox::spawn(context.clone());
+    }
+
+    if config.background_recompression {
+        let recompression_storage = storage_manager
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .context("Creating database connection pool for recompression")?;
+        recompression::spawn(recompression_storage);
+    }
+
+    if event_filters_deferred {
+        let backfill_storage = storage_manager
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .context("Creating database connection pool for event filters")?;
+        event_filter_backfill::spawn(backfill_storage, sync_state.clone());
+    }
 
     let default_version = match config.rpc_root_version {
         config::RootRpcVersion::V07 => pathfinder_rpc::RpcVersion::V07,
@@ -256,6 +427,12 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
         None => rpc_server,
     };
 
+    // The lag gauges are only useful if they are exported, and the alerts only if
+    // there is somewhere to send them.
+    if config.monitor_address.is_some() || config.monitor_lag_alert.webhook.is_some() {
+        lag_alert::spawn(config.monitor_lag_alert.clone(), sync_storage.clone());
+    }
+
     // Spawn monitoring if configured.
     if let Some(address) = config.monitor_address {
         spawn_monitoring(
@@ -263,6 +440,13 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
             address,
             readiness.clone(),
             sync_state.clone(),
+            disk_degraded.clone(),
+            monitoring::Dependencies {
+                storage: sync_storage.clone(),
+                gateway: pathfinder_context.gateway.clone(),
+                ethereum: ethereum.client.clone(),
+                max_sync_lag: config.monitor_ready_max_sync_lag,
+            },
         )
         .await
         .context("Starting monitoring task")?;
@@ -275,7 +459,7 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
     // and wait for them to finish. Only then can we exit the process and return an
     // error if some of the tasks failed or no error if we have received a signal.
 
-    let (p2p_handle, gossiper, p2p_client) = start_p2p(
+    let (p2p_handle, gossiper, p2p_client, p2p_announcements) = start_p2p(
         pathfinder_context.network_id,
         p2p_storage,
         config.p2p.clone(),
@@ -288,9 +472,17 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
             )),
             Default::default(),
             None,
+            None,
         )
     });
 
+    if let Some(interval) = config.gateway_schema_drift_check_interval {
+        util::task::spawn(schema_drift::probe_gateway_schema(
+            pathfinder_context.gateway.clone(),
+            interval,
+        ));
+    }
+
     let sync_handle = if config.is_sync_enabled {
         start_sync(
             sync_storage.clone(),
@@ -304,7 +496,9 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
             gossiper,
             gateway_public_key,
             p2p_client,
+            p2p_announcements,
             config.verify_tree_hashes,
+            disk_degraded,
         )
     } else {
         tokio::task::spawn(futures::future::pending())
@@ -372,17 +566,21 @@ Hint: This is usually caused by exceeding the file descriptor limit of your syst
 }
 
 #[cfg(feature = "tokio-console")]
-fn setup_tracing(color: config::Color, pretty_log: bool, json_log: bool) {
+fn setup_tracing(color: config::Color, pretty_log: bool, json_log: bool) -> LogFilter {
     use tracing_subscriber::prelude::*;
 
     // EnvFilter isn't really a Filter, so this we need this ugly workaround for
     // filtering with it. See https://github.com/tokio-rs/tracing/issues/1868 for more details.
-    let env_filter = Arc::new(tracing_subscriber::EnvFilter::from_default_env());
+    let env_filter = Arc::new(std::sync::RwLock::new(
+        tracing_subscriber::EnvFilter::from_default_env(),
+    ));
     let fmt_layer = tracing_subscriber::fmt::layer()
         .with_ansi(color.is_color_enabled())
         .with_target(pretty_log);
-    let filter =
-        tracing_subscriber::filter::dynamic_filter_fn(move |m, c| env_filter.enabled(m, c.clone()));
+    let filter = tracing_subscriber::filter::dynamic_filter_fn({
+        let env_filter = env_filter.clone();
+        move |m, c| env_filter.read().unwrap().enabled(m, c.clone())
+    });
 
     if json_log {
         tracing_subscriber::registry()
@@ -400,30 +598,61 @@ fn setup_tracing(color: config::Color, pretty_log: bool, json_log: bool) {
             .with(console_subscriber::spawn())
             .init();
     }
+
+    LogFilter::new(initial_log_filter(), move |filter| {
+        *env_filter.write().unwrap() = tracing_subscriber::EnvFilter::try_new(filter)?;
+        Ok(())
+    })
 }
 
 #[cfg(not(feature = "tokio-console"))]
-fn setup_tracing(color: config::Color, pretty_log: bool, json_log: bool) {
+fn setup_tracing(color: config::Color, pretty_log: bool, json_log: bool) -> LogFilter {
     use time::macros::format_description;
+    use tracing_subscriber::reload::Handle;
+    use tracing_subscriber::EnvFilter;
+
+    fn log_filter<S: 'static>(handle: Handle<EnvFilter, S>) -> LogFilter {
+        LogFilter::new(initial_log_filter(), move |filter| {
+            handle.reload(EnvFilter::try_new(filter)?)?;
+            Ok(())
+        })
+    }
 
     let time_fmt = format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]");
     let time_fmt = tracing_subscriber::fmt::time::UtcTime::new(time_fmt);
 
     let subscriber = tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_env_filter(EnvFilter::from_default_env())
         .with_target(pretty_log)
         .with_timer(time_fmt)
         .with_ansi(color.is_color_enabled());
 
     if json_log {
-        subscriber.json().flatten_event(true).init();
+        let subscriber = subscriber
+            .json()
+            .flatten_event(true)
+            .with_filter_reloading();
+        let handle = subscriber.reload_handle();
+        subscriber.init();
+        log_filter(handle)
     } else if pretty_log {
-        subscriber.pretty().init();
+        let subscriber = subscriber.pretty().with_filter_reloading();
+        let handle = subscriber.reload_handle();
+        subscriber.init();
+        log_filter(handle)
     } else {
-        subscriber.compact().init();
+        let subscriber = subscriber.compact().with_filter_reloading();
+        let handle = subscriber.reload_handle();
+        subscriber.init();
+        log_filter(handle)
     }
 }
 
+/// The filter the node was started with, see [LogFilter].
+fn initial_log_filter() -> String {
+    std::env::var("RUST_LOG").unwrap_or_default()
+}
+
 fn permission_check(base: &std::path::Path) -> Result<(), anyhow::Error> {
     tempfile::tempfile_in(base).with_context(|| {
         format!(
@@ -447,44 +676,25 @@ async fn start_p2p(
     tokio::task::JoinHandle<anyhow::Result<()>>,
     state::Gossiper,
     Option<p2p::client::peer_agnostic::Client>,
+    Option<p2p::HeadRx>,
 )> {
-    use std::path::Path;
     use std::time::Duration;
 
     use p2p::libp2p::identity::Keypair;
     use pathfinder_lib::p2p_network::P2PContext;
-    use serde::Deserialize;
-    use zeroize::Zeroizing;
-
-    #[derive(Clone, Deserialize)]
-    struct IdentityConfig {
-        pub private_key: String,
-    }
-
-    impl IdentityConfig {
-        pub fn from_file(path: &Path) -> anyhow::Result<Self> {
-            Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
-        }
-    }
-
-    impl zeroize::Zeroize for IdentityConfig {
-        fn zeroize(&mut self) {
-            self.private_key.zeroize()
-        }
-    }
 
     let keypair = match config.identity_config_file {
-        Some(path) => {
-            let config = Zeroizing::new(IdentityConfig::from_file(path.as_path())?);
-            let private_key = Zeroizing::new(base64::decode(config.private_key.as_bytes())?);
-            Keypair::from_protobuf_encoding(&private_key)?
-        }
+        Some(path) => crate::identity::load(&path)?,
         None => {
-            tracing::info!("No private key configured, generating a new one");
+            tracing::info!(
+                "No private key configured, generating a new one. Use `pathfinder identity \
+                 generate` to create a persistent identity"
+            );
             Keypair::generate_ed25519()
         }
     };
 
+    let head_race = config.proxy && config.head_race;
     let context = P2PContext {
         cfg: p2p::Config {
             direct_connection_timeout: config.direct_connection_timeout,
@@ -506,19 +716,21 @@ async fn start_p2p(
         chain_id,
         storage,
         proxy: config.proxy,
+        head_race,
         keypair,
         listen_on: config.listen_on,
         bootstrap_addresses: config.bootstrap_addresses,
         predefined_peers: config.predefined_peers,
     };
 
-    let (p2p_client, _head_receiver, p2p_handle) =
+    let (p2p_client, head_receiver, p2p_handle) =
         pathfinder_lib::p2p_network::start(context).await?;
 
     Ok((
         p2p_handle,
         state::Gossiper::new(p2p_client.clone()),
         Some(p2p_client),
+        head_race.then_some(head_receiver),
     ))
 }
 
@@ -531,10 +743,11 @@ async fn start_p2p(
     tokio::task::JoinHandle<anyhow::Result<()>>,
     state::Gossiper,
     Option<p2p::client::peer_agnostic::Client>,
+    Option<p2p::HeadRx>,
 )> {
     let join_handle = tokio::task::spawn(futures::future::pending());
 
-    Ok((join_handle, Default::default(), None))
+    Ok((join_handle, Default::default(), None, None))
 }
 
 #[cfg(feature = "p2p")]
@@ -551,7 +764,9 @@ fn start_sync(
     gossiper: state::Gossiper,
     gateway_public_key: pathfinder_common::PublicKey,
     p2p_client: Option<p2p::client::peer_agnostic::Client>,
+    p2p_announcements: Option<p2p::HeadRx>,
     verify_tree_hashes: bool,
+    disk_degraded: tokio::sync::watch::Receiver<bool>,
 ) -> tokio::task::JoinHandle<anyhow::Result<()>> {
     if config.p2p.proxy {
         start_feeder_gateway_sync(
@@ -565,6 +780,8 @@ fn start_sync(
             notifications,
             gossiper,
             gateway_public_key,
+            p2p_announcements,
+            disk_degraded,
         )
     } else {
         let p2p_client = p2p_client.expect("P2P client is expected with the p2p feature enabled");
@@ -594,7 +811,9 @@ fn start_sync(
     gossiper: state::Gossiper,
     gateway_public_key: pathfinder_common::PublicKey,
     _p2p_client: Option<p2p::client::peer_agnostic::Client>,
+    _p2p_announcements: Option<p2p::HeadRx>,
     _verify_tree_hashes: bool,
+    disk_degraded: tokio::sync::watch::Receiver<bool>,
 ) -> tokio::task::JoinHandle<anyhow::Result<()>> {
     start_feeder_gateway_sync(
         storage,
@@ -607,6 +826,8 @@ fn start_sync(
         notifications,
         gossiper,
         gateway_public_key,
+        None,
+        disk_degraded,
     )
 }
 
@@ -622,6 +843,8 @@ fn start_feeder_gateway_sync(
     notifications: Notifications,
     gossiper: state::Gossiper,
     gateway_public_key: pathfinder_common::PublicKey,
+    p2p_announcements: Option<p2p::HeadRx>,
+    disk_degraded: tokio::sync::watch::Receiver<bool>,
 ) -> tokio::task::JoinHandle<anyhow::Result<()>> {
     let sync_context = SyncContext {
         storage,
@@ -635,6 +858,8 @@ fn start_feeder_gateway_sync(
         l1_poll_interval: config.l1_poll_interval,
         pending_data: tx_pending,
         block_validation_mode: state::l2::BlockValidationMode::Strict,
+        transaction_hash_verification: config.sync_transaction_hash_verification,
+        gateway_data_verification: config.gateway_data_verification,
         websocket_txs,
         notifications,
         block_cache_size: 1_000,
@@ -643,7 +868,14 @@ fn start_feeder_gateway_sync(
         gossiper,
         sequencer_public_key: gateway_public_key,
         fetch_concurrency: config.feeder_gateway_fetch_concurrency,
+        class_fetch_concurrency: config.feeder_gateway_class_fetch_concurrency,
+        verification_concurrency: config.sync_verification_concurrency,
         fetch_casm_from_fgw: config.fetch_casm_from_fgw,
+        write_throttle: config.sync_write_throttle,
+        class_stats: config.sync_class_stats,
+        event_retention: config.event_retention.clone(),
+        p2p_announcements,
+        disk_degraded,
     };
 
     util::task::spawn(state::sync(sync_context, state::l1::sync, state::l2::sync))
@@ -682,6 +914,8 @@ async fn spawn_monitoring(
     address: SocketAddr,
     readiness: Arc<AtomicBool>,
     sync_state: Arc<SyncState>,
+    disk_degraded: tokio::sync::watch::Receiver<bool>,
+    dependencies: monitoring::Dependencies,
 ) -> anyhow::Result<tokio::task::JoinHandle<()>> {
     let prometheus_handle = PrometheusBuilder::new()
         .add_global_label("network", network)
@@ -695,8 +929,15 @@ async fn spawn_monitoring(
         Err(err) => tracing::error!("Failed to read system time: {:?}", err),
     }
 
-    let (_, handle) =
-        monitoring::spawn_server(address, readiness, sync_state, prometheus_handle).await?;
+    let (_, handle) = monitoring::spawn_server(
+        address,
+        readiness,
+        sync_state,
+        prometheus_handle,
+        disk_degraded,
+        Some(dependencies),
+    )
+    .await?;
     Ok(handle)
 }
 
@@ -709,7 +950,11 @@ struct EthereumContext {
 impl EthereumContext {
     /// Configure an [EthereumContext]'s transport and read the chain ID using
     /// it.
-    async fn setup(mut url: reqwest::Url, password: &Option<String>) -> anyhow::Result<Self> {
+    async fn setup(
+        mut url: reqwest::Url,
+        password: &Option<String>,
+        l1_mode: &config::L1Mode,
+    ) -> anyhow::Result<Self> {
         // Make sure the URL is a WS URL
         if url.scheme().eq("http") {
             warn!("The provided Ethereum URL is using HTTP, converting to WS");
@@ -733,6 +978,25 @@ impl EthereumContext {
 Hint: Make sure the provided ethereum.url and ethereum.password are good.",
         )?;
 
+        let client = match l1_mode {
+            config::L1Mode::Rpc => client,
+            config::L1Mode::LightClient {
+                beacon_url,
+                checkpoint,
+            } => {
+                let network =
+                    pathfinder_ethereum::BeaconNetwork::for_chain(chain).with_context(|| {
+                        format!("Light client mode is not supported on Ethereum chain {chain:?}")
+                    })?;
+                info!("Verifying L1 state using the embedded light client");
+                client.with_light_client(pathfinder_ethereum::LightClientConfig {
+                    beacon_url: beacon_url.clone(),
+                    checkpoint: *checkpoint,
+                    network,
+                })
+            }
+        };
+
         Ok(Self { client, chain })
     }
 
@@ -768,6 +1032,7 @@ mod pathfinder_context {
     use std::time::Duration;
 
     use anyhow::Context;
+    use pathfinder_common::version_matrix::{self, VersionMatrix};
     use pathfinder_common::{Chain, ChainId};
     use pathfinder_ethereum::core_addr;
     use pathfinder_rpc::context::EthContractAddresses;
@@ -813,10 +1078,12 @@ mod pathfinder_context {
                     gateway,
                     feeder_gateway,
                     chain_id,
+                    version_matrix,
                 } => Self::configure_custom(
                     gateway,
                     feeder_gateway,
                     chain_id,
+                    version_matrix,
                     data_directory,
                     api_key,
                     gateway_timeout,
@@ -836,6 +1103,7 @@ mod pathfinder_context {
             gateway: Url,
             feeder: Url,
             chain_id: String,
+            version_matrix: Option<VersionMatrix>,
             data_directory: &Path,
             api_key: Option<String>,
             gateway_timeout: Duration,
@@ -848,7 +1116,7 @@ mod pathfinder_context {
                 .with_api_key(api_key);
 
             let network_id =
-                ChainId(Felt::from_be_slice(chain_id.as_bytes()).context("Parsing chain ID")?);
+                ChainId(Felt::from_short_string(&chain_id).context("Parsing chain ID")?);
 
             let reply_contract_addresses = gateway
                 .eth_contract_addresses()
@@ -874,6 +1142,14 @@ mod pathfinder_context {
                 tracing::info!(%network, "Proxy gateway detected");
             }
 
+            if let Some(version_matrix) = version_matrix {
+                anyhow::ensure!(
+                    network == Chain::Custom,
+                    "The Starknet version matrix of {network} cannot be replaced"
+                );
+                version_matrix::install(version_matrix).context("Installing version matrix")?;
+            }
+
             let context = Self {
                 network,
                 network_id,
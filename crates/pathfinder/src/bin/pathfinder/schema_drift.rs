@@ -0,0 +1,107 @@
+//! Early warning for changes to the feeder gateway's reply format.
+//!
+//! Our gateway types reject unknown fields, so a Starknet version bump which
+//! adds fields to blocks, state updates or classes breaks sync. This probe
+//! periodically parses recent replies with those same types, outside of the
+//! sync path, and alerts once they stop matching.
+
+use std::time::Duration;
+
+use anyhow::Context;
+use pathfinder_common::class_definition::{Cairo, Sierra};
+use pathfinder_common::{BlockId, ClassHash};
+use starknet_gateway_client::{Client, GatewayApi};
+use starknet_gateway_types::reply::{Block, StateUpdate};
+
+/// Periodically fetches the latest block, its state update and a class
+/// declared in it, and checks that they still parse.
+pub async fn probe_gateway_schema(gateway: Client, interval: Duration) {
+    loop {
+        if let Err(error) = probe(&gateway).await {
+            // Failing to reach the gateway is not drift, and sync will report
+            // it anyway.
+            tracing::debug!(%error, "Gateway schema drift probe failed");
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn probe(gateway: &Client) -> anyhow::Result<()> {
+    let reply = gateway
+        .state_update_with_block_raw(BlockId::Latest)
+        .await
+        .context("Fetching latest state update")?;
+    let reply: serde_json::Value =
+        serde_json::from_slice(&reply).context("Parsing state update as JSON")?;
+
+    check::<Block>("block", reply["block"].to_string().as_bytes());
+    check::<StateUpdate>("state_update", reply["state_update"].to_string().as_bytes());
+
+    let state_diff = &reply["state_update"]["state_diff"];
+    let sierra = state_diff["declared_classes"][0]["class_hash"].as_str();
+    let cairo = state_diff["old_declared_contracts"][0].as_str();
+    let (class_hash, is_sierra) = match (sierra, cairo) {
+        (Some(class_hash), _) => (class_hash, true),
+        (None, Some(class_hash)) => (class_hash, false),
+        // Nothing was declared in this block, try again next time.
+        (None, None) => return Ok(()),
+    };
+    let class_hash: ClassHash =
+        serde_json::from_value(class_hash.into()).context("Parsing class hash")?;
+
+    let class = gateway
+        .pending_class_by_hash(class_hash)
+        .await
+        .context("Fetching class definition")?;
+    if is_sierra {
+        check::<Sierra<'_>>("sierra_class", &class);
+    } else {
+        check::<Cairo<'_>>("cairo_class", &class);
+    }
+
+    Ok(())
+}
+
+/// Parses `json` as `T`, reporting drift for `endpoint` if this fails.
+fn check<'a, T: serde::Deserialize<'a>>(endpoint: &'static str, json: &'a [u8]) -> bool {
+    match serde_json::from_slice::<T>(json) {
+        Ok(_) => {
+            metrics::gauge!("gateway_schema_drift", 0.0, "endpoint" => endpoint);
+            true
+        }
+        Err(error) => {
+            tracing::warn!(
+                %endpoint, %error,
+                "Feeder gateway reply no longer matches the expected schema, a Starknet version \
+                 update may be imminent. Please check for a new pathfinder release."
+            );
+            metrics::gauge!("gateway_schema_drift", 1.0, "endpoint" => endpoint);
+            metrics::increment_counter!("gateway_schema_drift_detected_total", "endpoint" => endpoint);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_field_is_drift() {
+        let class = br#"{
+            "abi": [],
+            "program": {},
+            "entry_points_by_type": {"CONSTRUCTOR": [], "EXTERNAL": [], "L1_HANDLER": []}
+        }"#;
+        assert!(check::<Cairo<'_>>("cairo_class", class));
+
+        let class = br#"{
+            "abi": [],
+            "program": {},
+            "entry_points_by_type": {"CONSTRUCTOR": [], "EXTERNAL": [], "L1_HANDLER": []},
+            "new_field": 1
+        }"#;
+        assert!(!check::<Cairo<'_>>("cairo_class", class));
+    }
+}
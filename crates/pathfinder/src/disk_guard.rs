@@ -0,0 +1,170 @@
+//! Guards against the database volume running out of space.
+//!
+//! Once free space drops below a threshold the node enters read-only degraded
+//! mode: the sync consumer checkpoints the WAL and pauses before its next
+//! write, and the RPC stops accepting transactions. Sync resumes once enough
+//! space has been freed. This avoids crashing mid-write, or in the worst case
+//! corrupting the WAL, when the disk fills up.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context;
+use tokio::sync::watch;
+
+/// How often free disk space is checked.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+const MIB: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskGuardConfig {
+    /// Degraded mode is entered once free space drops below this many bytes.
+    pub degraded_below: u64,
+    /// Degraded mode is left once free space is back above this many bytes.
+    pub resume_above: u64,
+}
+
+impl DiskGuardConfig {
+    /// Whether the node should be in degraded mode. The gap between the two
+    /// thresholds keeps the node from flapping between modes.
+    fn is_degraded(&self, currently_degraded: bool, free: u64) -> bool {
+        if currently_degraded {
+            free < self.resume_above
+        } else {
+            free < self.degraded_below
+        }
+    }
+
+    /// Fails if the volume containing `path` is already below the degraded
+    /// threshold. Used before work which cannot be paused, like database
+    /// migrations.
+    pub fn ensure_free_space(&self, path: &Path) -> anyhow::Result<()> {
+        let free = available_space(path)?;
+        anyhow::ensure!(
+            !self.is_degraded(false, free),
+            "Only {} MiB of disk space left at {}, at least {} MiB are required",
+            free / MIB,
+            path.display(),
+            self.degraded_below / MIB
+        );
+        Ok(())
+    }
+}
+
+/// Spawns the guard watching the volume containing `path`. The returned
+/// receiver is `true` while the node is in degraded mode.
+pub fn spawn(config: DiskGuardConfig, path: PathBuf) -> watch::Receiver<bool> {
+    let (tx, rx) = watch::channel(false);
+    util::task::spawn(guard(config, path, tx));
+    rx
+}
+
+async fn guard(config: DiskGuardConfig, path: PathBuf, degraded: watch::Sender<bool>) {
+    loop {
+        match available_space(&path) {
+            Ok(free) => update(&config, &degraded, free),
+            Err(error) => tracing::warn!(%error, "Failed to check free disk space"),
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+fn update(config: &DiskGuardConfig, degraded: &watch::Sender<bool>, free: u64) {
+    let was_degraded = *degraded.borrow();
+    let is_degraded = config.is_degraded(was_degraded, free);
+
+    metrics::gauge!("disk_free_bytes", free as f64);
+    metrics::gauge!("disk_degraded_mode", if is_degraded { 1.0 } else { 0.0 });
+
+    if is_degraded == was_degraded {
+        return;
+    }
+
+    if is_degraded {
+        tracing::error!(
+            free_mib = free / MIB,
+            threshold_mib = config.degraded_below / MIB,
+            "Low on disk space, pausing sync and entering read-only degraded mode"
+        );
+    } else {
+        tracing::info!(
+            free_mib = free / MIB,
+            "Disk space freed, resuming sync and leaving degraded mode"
+        );
+    }
+    degraded.send_replace(is_degraded);
+}
+
+/// Disk space available to unprivileged users on the volume containing
+/// `path`, in bytes.
+#[cfg(unix)]
+pub fn available_space(path: &Path) -> anyhow::Result<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes())
+        .with_context(|| format!("Invalid path {}", path.display()))?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `c_path` is a valid nul-terminated string and `stat` points to
+    // memory large enough for `statvfs`.
+    let result = unsafe { libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error())
+            .with_context(|| format!("Querying file system of {}", path.display()));
+    }
+    // SAFETY: `statvfs` succeeded, so `stat` is initialized.
+    let stat = unsafe { stat.assume_init() };
+
+    #[allow(clippy::useless_conversion)]
+    Ok(u64::from(stat.f_bavail).saturating_mul(u64::from(stat.f_frsize)))
+}
+
+#[cfg(not(unix))]
+pub fn available_space(_path: &Path) -> anyhow::Result<u64> {
+    anyhow::bail!("Checking free disk space is not supported on this platform")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: DiskGuardConfig = DiskGuardConfig {
+        degraded_below: 100,
+        resume_above: 200,
+    };
+
+    #[test]
+    fn thresholds_have_hysteresis() {
+        let (tx, rx) = watch::channel(false);
+
+        update(&CONFIG, &tx, 150);
+        assert!(!*rx.borrow());
+
+        update(&CONFIG, &tx, 99);
+        assert!(*rx.borrow());
+
+        // Freeing some space is not enough to leave degraded mode.
+        update(&CONFIG, &tx, 150);
+        assert!(*rx.borrow());
+
+        update(&CONFIG, &tx, 200);
+        assert!(!*rx.borrow());
+
+        update(&CONFIG, &tx, 150);
+        assert!(!*rx.borrow());
+    }
+
+    #[test]
+    fn ensure_free_space() {
+        let dir = tempfile::tempdir().unwrap();
+
+        CONFIG.ensure_free_space(dir.path()).unwrap();
+
+        let config = DiskGuardConfig {
+            degraded_below: u64::MAX,
+            resume_above: u64::MAX,
+        };
+        config.ensure_free_space(dir.path()).unwrap_err();
+    }
+}
@@ -0,0 +1,124 @@
+//! Builds the event filters which were not stored during initial sync, see
+//! [Transaction::defer_event_filters](pathfinder_storage::Transaction::defer_event_filters).
+//!
+//! Building only starts once sync has caught up with the chain tip so that it
+//! does not compete with sync for resources. Each block range is stored in its
+//! own database transaction, so an interrupted run resumes where it stopped.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use pathfinder_common::BlockNumber;
+use pathfinder_rpc::types::syncing::Syncing;
+use pathfinder_rpc::SyncState;
+use pathfinder_storage::{Storage, TransactionBehavior};
+
+/// How often to check whether building can start or continue.
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Sync is considered caught up if it is fewer blocks than this behind the
+/// chain tip.
+const MAX_BLOCKS_BEHIND: u64 = 6;
+
+enum Step {
+    Built {
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+    },
+    /// The remaining blocks have not been committed yet.
+    Waiting,
+    Done,
+}
+
+/// Spawns the job, which ends once all deferred event filters are built.
+pub fn spawn(storage: Storage, sync_state: Arc<SyncState>) {
+    util::task::spawn(async move {
+        if let Err(error) = backfill(storage, sync_state).await {
+            tracing::warn!(?error, "Building deferred event filters failed");
+        }
+    });
+}
+
+async fn backfill(storage: Storage, sync_state: Arc<SyncState>) -> anyhow::Result<()> {
+    let mut announced = false;
+    loop {
+        if !caught_up(&sync_state).await {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            continue;
+        }
+
+        if !announced {
+            tracing::info!("Building deferred event filters, getEvents is unavailable until done");
+            announced = true;
+        }
+
+        let storage = storage.clone();
+        let step = util::task::spawn_blocking(move |_| build_next(&storage))
+            .await
+            .context("Joining blocking task")??;
+
+        match step {
+            Step::Built {
+                from_block,
+                to_block,
+            } => tracing::debug!(%from_block, %to_block, "Built deferred event filter"),
+            Step::Waiting => tokio::time::sleep(POLL_INTERVAL).await,
+            Step::Done => {
+                tracing::info!("Built deferred event filters");
+                return Ok(());
+            }
+        }
+    }
+}
+
+async fn caught_up(sync_state: &SyncState) -> bool {
+    match &*sync_state.status.read().await {
+        Syncing::Status(status) => {
+            status
+                .highest
+                .number
+                .get()
+                .saturating_sub(status.current.number.get())
+                < MAX_BLOCKS_BEHIND
+        }
+        Syncing::False => false,
+    }
+}
+
+fn build_next(storage: &Storage) -> anyhow::Result<Step> {
+    let mut db = storage
+        .connection()
+        .context("Creating database connection")?;
+
+    // Built in a read transaction so that sync is not blocked meanwhile.
+    let filter = db
+        .transaction()
+        .context("Creating database transaction")?
+        .build_deferred_event_filter()
+        .context("Building event filter")?;
+
+    let tx = db
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .context("Creating database transaction")?;
+    let step = match filter {
+        Some(filter) => {
+            tx.insert_deferred_event_filter(&filter)
+                .context("Inserting event filter")?;
+            Step::Built {
+                from_block: filter.from_block(),
+                to_block: filter.to_block(),
+            }
+        }
+        None if tx
+            .end_event_filter_deferral()
+            .context("Ending event filter deferral")? =>
+        {
+            Step::Done
+        }
+        None => Step::Waiting,
+    };
+    tx.commit().context("Committing database transaction")?;
+
+    Ok(step)
+}
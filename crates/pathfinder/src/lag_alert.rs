@@ -0,0 +1,212 @@
+//! Reports how far behind the node is and optionally notifies a webhook when
+//! the lag exceeds the configured thresholds.
+//!
+//! The age of the latest block and the gap between the L2 head and the latest
+//! block accepted on L1 are exported as the `latest_l2_block_age_seconds` and
+//! `l1_l2_head_gap` metrics. The webhook is called once when a threshold is
+//! first exceeded and once more when the node has caught up again, not on
+//! every check.
+
+use std::time::Duration;
+
+use anyhow::Context;
+use pathfinder_storage::{BlockId, Storage};
+use reqwest::Url;
+
+/// How often the lag is checked.
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Timeout for a single webhook request.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct LagAlertConfig {
+    /// Notified with a JSON payload when the alert state changes.
+    pub webhook: Option<Url>,
+    /// Alert if the latest block is older than this.
+    pub max_block_age: Option<Duration>,
+    /// Alert if the L2 head is more than this many blocks ahead of the latest
+    /// block accepted on L1.
+    pub max_l1_l2_gap: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Lag {
+    block_age: Duration,
+    /// [None] if no block has been accepted on L1 yet.
+    l1_l2_gap: Option<u64>,
+}
+
+impl LagAlertConfig {
+    /// The reasons the node is considered lagging, empty if it is not.
+    fn exceeded(&self, lag: &Lag) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if let Some(max) = self.max_block_age {
+            if lag.block_age > max {
+                reasons.push(format!(
+                    "latest block is {}s old, threshold is {}s",
+                    lag.block_age.as_secs(),
+                    max.as_secs()
+                ));
+            }
+        }
+        if let (Some(max), Some(gap)) = (self.max_l1_l2_gap, lag.l1_l2_gap) {
+            if gap > max {
+                reasons.push(format!(
+                    "L2 head is {gap} blocks ahead of L1, threshold is {max}"
+                ));
+            }
+        }
+        reasons
+    }
+}
+
+/// Spawns the task. It runs until the process exits.
+pub fn spawn(config: LagAlertConfig, storage: Storage) {
+    util::task::spawn(monitor(config, storage));
+}
+
+async fn monitor(config: LagAlertConfig, storage: Storage) {
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .expect("Webhook client settings are valid");
+    let mut alerting = false;
+
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+
+        let storage = storage.clone();
+        let lag = match util::task::spawn_blocking(move |_| current_lag(&storage)).await {
+            Ok(Ok(Some(lag))) => lag,
+            // Nothing has been synced yet.
+            Ok(Ok(None)) => continue,
+            Ok(Err(error)) => {
+                tracing::warn!(?error, "Failed to determine sync lag");
+                continue;
+            }
+            Err(error) => {
+                tracing::warn!(%error, "Sync lag task failed");
+                continue;
+            }
+        };
+
+        metrics::gauge!("latest_l2_block_age_seconds", lag.block_age.as_secs_f64());
+        if let Some(gap) = lag.l1_l2_gap {
+            metrics::gauge!("l1_l2_head_gap", gap as f64);
+        }
+
+        let reasons = config.exceeded(&lag);
+        let lagging = !reasons.is_empty();
+        if lagging == alerting {
+            continue;
+        }
+        alerting = lagging;
+
+        if alerting {
+            tracing::warn!(reasons=%reasons.join("; "), "Sync lag threshold exceeded");
+        } else {
+            tracing::info!("Sync lag is back within thresholds");
+        }
+
+        if let Some(webhook) = &config.webhook {
+            if let Err(error) = notify(&client, webhook, alerting, &reasons, &lag).await {
+                tracing::warn!(?error, "Failed to notify sync lag webhook");
+            }
+        }
+    }
+}
+
+fn current_lag(storage: &Storage) -> anyhow::Result<Option<Lag>> {
+    let mut db = storage
+        .connection()
+        .context("Creating database connection")?;
+    let tx = db.transaction().context("Creating database transaction")?;
+
+    let Some(latest) = tx
+        .block_header(BlockId::Latest)
+        .context("Fetching latest block header")?
+    else {
+        return Ok(None);
+    };
+    let l1 = tx.latest_l1_state().context("Fetching latest L1 state")?;
+
+    let now = time::OffsetDateTime::now_utc().unix_timestamp() as u64;
+    Ok(Some(Lag {
+        block_age: Duration::from_secs(now.saturating_sub(latest.timestamp.get())),
+        l1_l2_gap: l1.map(|l1| latest.number.get().saturating_sub(l1.block_number.get())),
+    }))
+}
+
+async fn notify(
+    client: &reqwest::Client,
+    webhook: &Url,
+    alerting: bool,
+    reasons: &[String],
+    lag: &Lag,
+) -> anyhow::Result<()> {
+    let payload = serde_json::json!({
+        "status": if alerting { "lagging" } else { "recovered" },
+        "reasons": reasons,
+        "latest_l2_block_age_seconds": lag.block_age.as_secs(),
+        "l1_l2_head_gap": lag.l1_l2_gap,
+    });
+
+    client
+        .post(webhook.clone())
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(payload.to_string())
+        .send()
+        .await
+        .context("Sending request")?
+        .error_for_status()
+        .context("Webhook returned an error")?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exceeded_thresholds() {
+        let config = LagAlertConfig {
+            webhook: None,
+            max_block_age: Some(Duration::from_secs(60)),
+            max_l1_l2_gap: Some(100),
+        };
+
+        let lag = Lag {
+            block_age: Duration::from_secs(60),
+            l1_l2_gap: Some(100),
+        };
+        assert!(config.exceeded(&lag).is_empty());
+
+        let lag = Lag {
+            block_age: Duration::from_secs(61),
+            l1_l2_gap: None,
+        };
+        assert_eq!(config.exceeded(&lag).len(), 1);
+
+        let lag = Lag {
+            block_age: Duration::from_secs(61),
+            l1_l2_gap: Some(101),
+        };
+        assert_eq!(config.exceeded(&lag).len(), 2);
+    }
+
+    #[test]
+    fn thresholds_are_optional() {
+        let config = LagAlertConfig {
+            webhook: None,
+            max_block_age: None,
+            max_l1_l2_gap: None,
+        };
+        let lag = Lag {
+            block_age: Duration::MAX,
+            l1_l2_gap: Some(u64::MAX),
+        };
+        assert!(config.exceeded(&lag).is_empty());
+    }
+}
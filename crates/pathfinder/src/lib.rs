@@ -1,6 +1,10 @@
 #![deny(rust_2018_idioms)]
 
+pub mod disk_guard;
+pub mod event_filter_backfill;
+pub mod lag_alert;
 pub mod monitoring;
 pub mod p2p_network;
+pub mod recompression;
 pub mod state;
 pub mod sync;
@@ -1,16 +1,40 @@
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
+use std::time::Duration;
 
+use anyhow::Context;
 use metrics_exporter_prometheus::PrometheusHandle;
+use pathfinder_ethereum::{EthereumApi, EthereumClient};
 use pathfinder_rpc::types::syncing::Syncing;
 use pathfinder_rpc::SyncState;
+use pathfinder_storage::{Storage, TransactionBehavior};
+use starknet_gateway_client::GatewayApi;
+use tokio::sync::watch;
+
+/// How long each dependency check of `/health` and `/ready` may take.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
 
 #[derive(Clone)]
 struct State {
     readiness: Arc<AtomicBool>,
     sync: Arc<SyncState>,
     prometheus: PrometheusHandle,
+    disk_degraded: watch::Receiver<bool>,
+    dependencies: Option<Dependencies>,
+}
+
+/// The services checked by `/health` and `/ready`.
+#[derive(Clone)]
+pub struct Dependencies {
+    /// Checked for reads and writes.
+    pub storage: Storage,
+    pub gateway: starknet_gateway_client::Client,
+    pub ethereum: EthereumClient,
+    /// `/ready` fails if the node is more blocks behind the chain tip than
+    /// this. The lag is only reported if not set.
+    pub max_sync_lag: Option<u64>,
 }
 
 /// Spawns a server which hosts a `/health` endpoint.
@@ -19,9 +43,12 @@ pub async fn spawn_server(
     readiness: Arc<AtomicBool>,
     sync_state: Arc<SyncState>,
     prometheus_handle: PrometheusHandle,
+    disk_degraded: watch::Receiver<bool>,
+    dependencies: Option<Dependencies>,
 ) -> anyhow::Result<(SocketAddr, tokio::task::JoinHandle<()>)> {
     let app = axum::Router::new()
         .route("/health", axum::routing::get(health_route))
+        .route("/health/disk", axum::routing::get(disk_route))
         .route("/ready", axum::routing::get(ready_route))
         .route("/ready/synced", axum::routing::get(synced_route))
         .route("/metrics", axum::routing::get(metrics_route))
@@ -29,6 +56,8 @@ pub async fn spawn_server(
             readiness,
             sync: sync_state,
             prometheus: prometheus_handle,
+            disk_degraded,
+            dependencies,
         });
     let listener = tokio::net::TcpListener::bind(addr.into()).await?;
     let addr = listener.local_addr()?;
@@ -41,25 +70,181 @@ pub async fn spawn_server(
     Ok((addr, spawn))
 }
 
-/// Always returns `Ok(200)` at `/health`.
-async fn health_route() -> http::StatusCode {
-    http::StatusCode::OK
+#[derive(Debug, Default, serde::Serialize)]
+struct Check {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// Number of blocks behind the chain tip, only set for the sync check.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lag: Option<u64>,
 }
 
-/// Returns `Ok` if `readiness == true`, or `SERVICE_UNAVAILABLE` otherwise.
-async fn ready_route(axum::extract::State(state): axum::extract::State<State>) -> http::StatusCode {
-    if state.readiness.load(std::sync::atomic::Ordering::Relaxed) {
+impl Check {
+    fn from_result(result: anyhow::Result<()>) -> Self {
+        match result {
+            Ok(()) => Self {
+                ok: true,
+                ..Default::default()
+            },
+            Err(error) => Self {
+                ok: false,
+                error: Some(format!("{error:#}")),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Report {
+    ok: bool,
+    checks: BTreeMap<&'static str, Check>,
+}
+
+/// Runs the dependency checks concurrently.
+async fn check_dependencies(state: &State) -> BTreeMap<&'static str, Check> {
+    let mut checks = BTreeMap::new();
+    checks.insert(
+        "startup",
+        Check::from_result(
+            state
+                .readiness
+                .load(std::sync::atomic::Ordering::Relaxed)
+                .then_some(())
+                .context("Still starting up"),
+        ),
+    );
+
+    let lag = match &*state.sync.status.read().await {
+        Syncing::Status(status) => Some(
+            status
+                .highest
+                .number
+                .get()
+                .saturating_sub(status.current.number.get()),
+        ),
+        Syncing::False => None,
+    };
+    let max_sync_lag = state.dependencies.as_ref().and_then(|d| d.max_sync_lag);
+    checks.insert(
+        "sync",
+        Check {
+            lag,
+            ..Check::from_result(match (lag, max_sync_lag) {
+                (Some(lag), Some(max)) if lag > max => {
+                    Err(anyhow::anyhow!("{lag} blocks behind the chain tip"))
+                }
+                _ => Ok(()),
+            })
+        },
+    );
+
+    let Some(dependencies) = &state.dependencies else {
+        return checks;
+    };
+    let (database, gateway, ethereum) = tokio::join!(
+        with_timeout(check_database(dependencies.storage.clone())),
+        with_timeout(async {
+            dependencies
+                .gateway
+                .head()
+                .await
+                .map(|_| ())
+                .context("Fetching latest block")
+        }),
+        with_timeout(async {
+            dependencies
+                .ethereum
+                .get_chain()
+                .await
+                .map(|_| ())
+                .context("Fetching chain id")
+        }),
+    );
+    checks.insert("database", Check::from_result(database));
+    checks.insert("gateway", Check::from_result(gateway));
+    checks.insert("ethereum", Check::from_result(ethereum));
+
+    checks
+}
+
+async fn with_timeout(
+    check: impl std::future::Future<Output = anyhow::Result<()>>,
+) -> anyhow::Result<()> {
+    tokio::time::timeout(CHECK_TIMEOUT, check)
+        .await
+        .unwrap_or_else(|_| Err(anyhow::anyhow!("Timed out")))
+}
+
+/// Reads the latest block while holding the write lock, without writing.
+async fn check_database(storage: Storage) -> anyhow::Result<()> {
+    util::task::spawn_blocking(move |_| {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let tx = db
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .context("Acquiring write lock")?;
+        tx.block_id(pathfinder_storage::BlockId::Latest)
+            .context("Reading latest block")?;
+        // Dropping the transaction rolls it back.
+        Ok(())
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+/// Reports the dependency checks at `/health` as JSON. Fails only if the
+/// database is unusable, as restarting does not help with other failures.
+async fn health_route(
+    axum::extract::State(state): axum::extract::State<State>,
+) -> (http::StatusCode, axum::Json<Report>) {
+    let checks = check_dependencies(&state).await;
+    let ok = checks.get("database").map_or(true, |check| check.ok);
+    let status = if ok {
         http::StatusCode::OK
     } else {
         http::StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, axum::Json(Report { ok, checks }))
+}
+
+/// Returns `SERVICE_UNAVAILABLE` at `/health/disk` while the node is in
+/// degraded mode because it is low on disk space, or `Ok` otherwise.
+async fn disk_route(axum::extract::State(state): axum::extract::State<State>) -> http::StatusCode {
+    if *state.disk_degraded.borrow() {
+        http::StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        http::StatusCode::OK
     }
 }
 
-/// Returns `Ok` if `readiness == true`, or `SERVICE_UNAVAILABLE` otherwise.
+/// Reports the dependency checks at `/ready` as JSON. Returns `Ok` if all
+/// checks pass, i.e. startup finished, or `SERVICE_UNAVAILABLE` otherwise.
+async fn ready_route(
+    axum::extract::State(state): axum::extract::State<State>,
+) -> (http::StatusCode, axum::Json<Report>) {
+    let checks = check_dependencies(&state).await;
+    let ok = checks.values().all(|check| check.ok);
+    let status = if ok {
+        http::StatusCode::OK
+    } else {
+        http::StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, axum::Json(Report { ok, checks }))
+}
+
+/// Returns `Ok` if `readiness == true` and the node is close to the chain tip,
+/// or `SERVICE_UNAVAILABLE` otherwise. A node whose sync is paused because it
+/// is low on disk space is never considered synced.
 async fn synced_route(
     axum::extract::State(state): axum::extract::State<State>,
 ) -> http::StatusCode {
-    if !state.readiness.load(std::sync::atomic::Ordering::Relaxed) {
+    if !state.readiness.load(std::sync::atomic::Ordering::Relaxed) || *state.disk_degraded.borrow()
+    {
         return http::StatusCode::SERVICE_UNAVAILABLE;
     }
 
@@ -89,7 +274,7 @@ mod tests {
     use pathfinder_common::BlockNumber;
     use pathfinder_rpc::types::syncing::{NumberedBlock, Status, Syncing};
     use pathfinder_rpc::SyncState;
-    use tokio::sync::RwLock;
+    use tokio::sync::{watch, RwLock};
 
     async fn wait_healthy(client: &reqwest::Client, url: reqwest::Url) {
         let url = url.join("health").unwrap();
@@ -115,6 +300,8 @@ mod tests {
             readiness.clone(),
             Default::default(),
             handle,
+            watch::channel(false).1,
+            None,
         )
         .await
         .unwrap();
@@ -135,6 +322,8 @@ mod tests {
             readiness.clone(),
             Default::default(),
             handle,
+            watch::channel(false).1,
+            None,
         )
         .await
         .unwrap();
@@ -152,6 +341,18 @@ mod tests {
         readiness.store(true, std::sync::atomic::Ordering::Relaxed);
         let resp = client.get(url).send().await.unwrap();
         assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let report: serde_json::Value =
+            serde_json::from_slice(&resp.bytes().await.unwrap()).unwrap();
+        assert_eq!(
+            report,
+            serde_json::json!({
+                "ok": true,
+                "checks": {
+                    "startup": {"ok": true},
+                    "sync": {"ok": true},
+                },
+            })
+        );
     }
 
     #[tokio::test]
@@ -166,6 +367,8 @@ mod tests {
             readiness.clone(),
             sync_state.clone(),
             handle,
+            watch::channel(false).1,
+            None,
         )
         .await
         .unwrap();
@@ -219,6 +422,41 @@ mod tests {
         assert_eq!(resp.status(), reqwest::StatusCode::OK);
     }
 
+    #[tokio::test]
+    async fn disk() {
+        let readiness = Arc::new(AtomicBool::new(true));
+        let handle = PrometheusBuilder::new().build_recorder().handle();
+        let (degraded_tx, degraded_rx) = watch::channel(false);
+        let (addr, _) = super::spawn_server(
+            ([127, 0, 0, 1], 0),
+            readiness.clone(),
+            Default::default(),
+            handle,
+            degraded_rx,
+            None,
+        )
+        .await
+        .unwrap();
+        let url = reqwest::Url::parse(&format!("http://{addr}")).unwrap();
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        wait_healthy(&client, url.clone()).await;
+
+        let url = url.join("health/disk").unwrap();
+        let resp = client.get(url.clone()).send().await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+
+        degraded_tx.send_replace(true);
+        let resp = client.get(url.clone()).send().await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+
+        degraded_tx.send_replace(false);
+        let resp = client.get(url).send().await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+    }
+
     #[tokio::test]
     async fn metrics() {
         use pathfinder_common::test_utils::metrics::ScopedRecorderGuard;
@@ -240,6 +478,8 @@ mod tests {
             readiness.clone(),
             Default::default(),
             handle,
+            watch::channel(false).1,
+            None,
         )
         .await
         .unwrap();
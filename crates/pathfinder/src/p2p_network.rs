@@ -10,7 +10,14 @@ use tracing::Instrument;
 
 mod sync_handlers;
 
-use sync_handlers::{get_classes, get_events, get_headers, get_state_diffs, get_transactions};
+use sync_handlers::{
+    get_classes,
+    get_classes_by_hash,
+    get_events,
+    get_headers,
+    get_state_diffs,
+    get_transactions,
+};
 
 // Silence clippy
 pub type P2PNetworkHandle = (
@@ -24,6 +31,9 @@ pub struct P2PContext {
     pub chain_id: ChainId,
     pub storage: Storage,
     pub proxy: bool,
+    /// Subscribe to new block announcements while proxying, for the feeder
+    /// gateway sync to race them against its polling.
+    pub head_race: bool,
     pub keypair: Keypair,
     pub listen_on: Vec<Multiaddr>,
     pub bootstrap_addresses: Vec<Multiaddr>,
@@ -37,6 +47,7 @@ pub async fn start(context: P2PContext) -> anyhow::Result<P2PNetworkHandle> {
         chain_id,
         storage,
         proxy,
+        head_race,
         keypair,
         listen_on,
         bootstrap_addresses,
@@ -92,7 +103,7 @@ pub async fn start(context: P2PContext) -> anyhow::Result<P2PNetworkHandle> {
 
     let block_propagation_topic = format!("blocks/{}", chain_id.to_hex_str());
 
-    if !proxy {
+    if !proxy || head_race {
         p2p_client.subscribe_topic(&block_propagation_topic).await?;
         tracing::info!(topic=%block_propagation_topic, "Subscribed to");
     }
@@ -135,29 +146,52 @@ async fn handle_p2p_event(
 ) -> anyhow::Result<()> {
     match event {
         p2p::Event::InboundHeadersSyncRequest {
-            request, channel, ..
+            version,
+            request,
+            channel,
+            ..
         } => {
-            get_headers(storage, request, channel).await?;
+            get_headers(storage, version, request, channel).await?;
         }
         p2p::Event::InboundClassesSyncRequest {
-            request, channel, ..
+            version,
+            request,
+            channel,
+            ..
         } => {
-            get_classes(storage, request, channel).await?;
+            get_classes(storage, version, request, channel).await?;
+        }
+        p2p::Event::InboundClassesByHashSyncRequest {
+            version,
+            request,
+            channel,
+            ..
+        } => {
+            get_classes_by_hash(storage, version, request, channel).await?;
         }
         p2p::Event::InboundStateDiffsSyncRequest {
-            request, channel, ..
+            version,
+            request,
+            channel,
+            ..
         } => {
-            get_state_diffs(storage, request, channel).await?;
+            get_state_diffs(storage, version, request, channel).await?;
         }
         p2p::Event::InboundTransactionsSyncRequest {
-            request, channel, ..
+            version,
+            request,
+            channel,
+            ..
         } => {
-            get_transactions(storage, request, channel).await?;
+            get_transactions(storage, version, request, channel).await?;
         }
         p2p::Event::InboundEventsSyncRequest {
-            request, channel, ..
+            version,
+            request,
+            channel,
+            ..
         } => {
-            get_events(storage, request, channel).await?;
+            get_events(storage, version, request, channel).await?;
         }
         p2p::Event::BlockPropagation { from, new_block } => {
             tracing::info!(%from, ?new_block, "Block Propagation");
@@ -1,7 +1,14 @@
 use anyhow::Context;
 use futures::SinkExt;
 use p2p::client::conv::ToDto;
-use p2p_proto::class::{Class, ClassesRequest, ClassesResponse};
+use p2p::SyncProtocolVersion;
+use p2p_proto::class::{
+    Class,
+    ClassesByHashRequest,
+    ClassesByHashResponse,
+    ClassesRequest,
+    ClassesResponse,
+};
 use p2p_proto::common::{
     Address,
     BlockNumberOrHash,
@@ -21,7 +28,7 @@ use p2p_proto::state::{
     StateDiffsResponse,
 };
 use p2p_proto::transaction::{TransactionWithReceipt, TransactionsRequest, TransactionsResponse};
-use pathfinder_common::{class_definition, BlockHash, BlockNumber, SignedBlockHeader};
+use pathfinder_common::{class_definition, BlockHash, BlockNumber, ClassHash, SignedBlockHeader};
 use pathfinder_storage::{Storage, Transaction};
 use tokio::sync::mpsc;
 
@@ -36,44 +43,91 @@ const MAX_COUNT_IN_TESTS: u64 = 10;
 #[cfg(test)]
 const MAX_BLOCKS_COUNT: u64 = MAX_COUNT_IN_TESTS;
 
+// Requests are served in the protocol version they were made in. Each version
+// has its own conversion path from storage to the wire types, so that older
+// versions keep being served after the message shapes change.
+
+/// Maximum number of classes served for a single class-by-hash request.
+const MAX_CLASSES_BY_HASH_COUNT: usize = 100;
+/// Number of class definitions read from the database at once.
+const CLASSES_BY_HASH_BATCH_SIZE: usize = 10;
+
 pub async fn get_headers(
     storage: Storage,
+    version: SyncProtocolVersion,
     request: BlockHeadersRequest,
     tx: futures::channel::mpsc::Sender<BlockHeadersResponse>,
 ) -> anyhow::Result<()> {
-    spawn_blocking_get(request, storage, blocking::get_headers, tx).await
+    match version {
+        SyncProtocolVersion::V0_1_0Rc0 => {
+            spawn_blocking_get(request, storage, blocking::get_headers, tx).await
+        }
+    }
 }
 
 pub async fn get_classes(
     storage: Storage,
+    version: SyncProtocolVersion,
     request: ClassesRequest,
     tx: futures::channel::mpsc::Sender<ClassesResponse>,
 ) -> anyhow::Result<()> {
-    spawn_blocking_get(request, storage, blocking::get_classes, tx).await
+    match version {
+        SyncProtocolVersion::V0_1_0Rc0 => {
+            spawn_blocking_get(request, storage, blocking::get_classes, tx).await
+        }
+    }
+}
+
+pub async fn get_classes_by_hash(
+    storage: Storage,
+    version: SyncProtocolVersion,
+    request: ClassesByHashRequest,
+    tx: futures::channel::mpsc::Sender<ClassesByHashResponse>,
+) -> anyhow::Result<()> {
+    match version {
+        SyncProtocolVersion::V0_1_0Rc0 => {
+            spawn_blocking_get(request, storage, blocking::get_classes_by_hash, tx).await
+        }
+    }
 }
 
 pub async fn get_state_diffs(
     storage: Storage,
+    version: SyncProtocolVersion,
     request: StateDiffsRequest,
     tx: futures::channel::mpsc::Sender<StateDiffsResponse>,
 ) -> anyhow::Result<()> {
-    spawn_blocking_get(request, storage, blocking::get_state_diffs, tx).await
+    match version {
+        SyncProtocolVersion::V0_1_0Rc0 => {
+            spawn_blocking_get(request, storage, blocking::get_state_diffs, tx).await
+        }
+    }
 }
 
 pub async fn get_transactions(
     storage: Storage,
+    version: SyncProtocolVersion,
     request: TransactionsRequest,
     tx: futures::channel::mpsc::Sender<TransactionsResponse>,
 ) -> anyhow::Result<()> {
-    spawn_blocking_get(request, storage, blocking::get_transactions, tx).await
+    match version {
+        SyncProtocolVersion::V0_1_0Rc0 => {
+            spawn_blocking_get(request, storage, blocking::get_transactions, tx).await
+        }
+    }
 }
 
 pub async fn get_events(
     storage: Storage,
+    version: SyncProtocolVersion,
     request: EventsRequest,
     tx: futures::channel::mpsc::Sender<EventsResponse>,
 ) -> anyhow::Result<()> {
-    spawn_blocking_get(request, storage, blocking::get_events, tx).await
+    match version {
+        SyncProtocolVersion::V0_1_0Rc0 => {
+            spawn_blocking_get(request, storage, blocking::get_events, tx).await
+        }
+    }
 }
 
 pub(crate) mod blocking {
@@ -97,6 +151,51 @@ pub(crate) mod blocking {
         iterate(db_tx, request.iteration, get_classes_for_block, tx)
     }
 
+    #[tracing::instrument(skip(db_tx, tx))]
+    pub(crate) fn get_classes_by_hash(
+        db_tx: Transaction<'_>,
+        request: ClassesByHashRequest,
+        tx: mpsc::Sender<ClassesByHashResponse>,
+    ) -> anyhow::Result<()> {
+        let class_hashes = request
+            .class_hashes
+            .into_iter()
+            .take(MAX_CLASSES_BY_HASH_COUNT)
+            .map(|hash| ClassHash(hash.0))
+            .collect::<Vec<_>>();
+
+        for batch in class_hashes.chunks(CLASSES_BY_HASH_BATCH_SIZE) {
+            let definitions = db_tx.declared_class_definitions(batch)?;
+
+            for (&class_hash, definition) in batch.iter().zip(definitions) {
+                let Some(definition) = definition else {
+                    tracing::trace!(?class_hash, "Class definition not found, skipping");
+                    continue;
+                };
+                let definition = if db_tx.is_sierra(class_hash)?.unwrap_or_default() {
+                    ClassDefinition::Sierra {
+                        sierra: definition,
+                        _casm: Vec::new(), // TODO casm
+                    }
+                } else {
+                    ClassDefinition::Cairo(definition)
+                };
+
+                tracing::trace!(?class_hash, "Sending class definition");
+
+                tx.blocking_send(ClassesByHashResponse::Class(class_dto(
+                    class_hash, definition,
+                )?))
+                .map_err(|_| anyhow::anyhow!("Sending class"))?;
+            }
+        }
+
+        tracing::trace!("Sending FIN");
+
+        tx.blocking_send(ClassesByHashResponse::Fin)
+            .map_err(|_| anyhow::anyhow!("Sending Fin"))
+    }
+
     #[tracing::instrument(skip(db_tx, tx))]
     pub(crate) fn get_state_diffs(
         db_tx: Transaction<'_>,
@@ -187,29 +286,7 @@ fn get_classes_for_block(
 
         tracing::trace!(?class_hash, "Sending class definition");
 
-        let class: Class = match class_definition {
-            ClassDefinition::Cairo(definition) => {
-                let cairo_class =
-                    serde_json::from_slice::<class_definition::Cairo<'_>>(&definition)?;
-                Class::Cairo0 {
-                    class: cairo_class.to_dto(),
-                    domain: 0, // TODO
-                    class_hash: Hash(class_hash.0),
-                }
-            }
-            ClassDefinition::Sierra {
-                sierra,
-                _casm: _, /* TODO */
-            } => {
-                let sierra_class = serde_json::from_slice::<class_definition::Sierra<'_>>(&sierra)?;
-
-                Class::Cairo1 {
-                    class: sierra_class.to_dto(),
-                    domain: 0, // TODO
-                    class_hash: Hash(class_hash.0),
-                }
-            }
-        };
+        let class = class_dto(class_hash, class_definition)?;
 
         tx.blocking_send(ClassesResponse::Class(class))
             .map_err(|_| anyhow::anyhow!("Sending class"))?;
@@ -218,6 +295,31 @@ fn get_classes_for_block(
     Ok(true)
 }
 
+fn class_dto(class_hash: ClassHash, class_definition: ClassDefinition) -> anyhow::Result<Class> {
+    Ok(match class_definition {
+        ClassDefinition::Cairo(definition) => {
+            let cairo_class = serde_json::from_slice::<class_definition::Cairo<'_>>(&definition)?;
+            Class::Cairo0 {
+                class: cairo_class.to_dto(),
+                domain: 0, // TODO
+                class_hash: Hash(class_hash.0),
+            }
+        }
+        ClassDefinition::Sierra {
+            sierra,
+            _casm: _, /* TODO */
+        } => {
+            let sierra_class = serde_json::from_slice::<class_definition::Sierra<'_>>(&sierra)?;
+
+            Class::Cairo1 {
+                class: sierra_class.to_dto(),
+                domain: 0, // TODO
+                class_hash: Hash(class_hash.0),
+            }
+        }
+    })
+}
+
 fn get_state_diff(
     db_tx: &Transaction<'_>,
     block_number: BlockNumber,
@@ -287,11 +389,13 @@ fn get_transactions_for_block(
     block_number: BlockNumber,
     tx: &mpsc::Sender<TransactionsResponse>,
 ) -> anyhow::Result<bool> {
-    let Some(txn_data) = db_tx.transaction_data_for_block(block_number.into())? else {
+    // Events are not part of the response, so only transactions and receipts are
+    // read.
+    let Some(txn_data) = db_tx.transactions_with_receipts_for_block(block_number.into())? else {
         return Ok(false);
     };
 
-    for (txn, receipt, _) in txn_data {
+    for (txn, receipt) in txn_data {
         tracing::trace!(transaction_hash=%txn.hash, "Sending transaction");
 
         let receipt = (&txn.variant, receipt).to_dto();
@@ -316,13 +420,22 @@ fn get_events_for_block(
     block_number: BlockNumber,
     tx: &mpsc::Sender<EventsResponse>,
 ) -> anyhow::Result<bool> {
-    let Some(txn_data) = db_tx.transaction_data_for_block(block_number.into())? else {
+    // Pruned events are incomplete and would fail the peer's event commitment
+    // check, so the block is treated as missing.
+    if db_tx.events_pruned(block_number)?.is_some() {
         return Ok(false);
+    }
+
+    // Only the events are read, decoding the transactions and receipts of the block
+    // as well would multiply the memory held per block.
+    let Some(events) = db_tx.events_for_block(block_number.into())? else {
+        // Blocks without transactions have no events stored.
+        return db_tx.block_exists(block_number.into());
     };
 
-    for (_, r, events) in txn_data {
+    for (transaction_hash, events) in events {
         for event in events {
-            tx.blocking_send(EventsResponse::Event((r.transaction_hash, event).to_dto()))
+            tx.blocking_send(EventsResponse::Event((transaction_hash, event).to_dto()))
                 .map_err(|_| anyhow::anyhow!("Sending event"))?;
         }
     }
@@ -330,6 +443,10 @@ fn get_events_for_block(
     Ok(true)
 }
 
+/// Responses are sent as soon as they are read and the channel only holds a
+/// single response, so at most one block's worth of data is held in memory
+/// while the peer is slow to consume the responses.
+///
 /// Assupmtions:
 /// - `block_handler` returns `Ok(true)` if the iteration should continue,
 /// - `T::default()` always returns the `Fin` variant of the implementing type.
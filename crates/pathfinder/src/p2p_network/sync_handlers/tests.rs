@@ -83,7 +83,12 @@ mod boundary_conditions {
                 async fn $name(#[case] iteration: Iteration) {
                     let storage = StorageBuilder::in_memory().unwrap();
                     let (tx, mut rx) = mpsc::channel(0);
-                    let _jh = tokio::spawn($uut_name(storage, $request { iteration }, tx));
+                    let _jh = tokio::spawn($uut_name(
+                        storage,
+                        Default::default(),
+                        $request { iteration },
+                        tx,
+                    ));
                     assert_eq!(rx.next().await.unwrap(), Default::default());
                 }
             };
@@ -192,7 +197,8 @@ mod prop {
             let request = BlockHeadersRequest { iteration: Iteration { start: BlockNumberOrHash::Number(start_block), limit, step, direction, } };
             let mut responses = Runtime::new().unwrap().block_on(async {
                 let (tx, rx) = mpsc::channel(0);
-                let getter_fut = sync_handlers::get_headers(storage, request, tx);
+                let getter_fut =
+                    sync_handlers::get_headers(storage, Default::default(), request, tx);
                 // Waiting for both futures to run to completion is faster than spawning the getter
                 // and awaiting the receiver (almost 1s for 100 iterations on Ryzen 3700X).
                 // BTW, we cannot just await the getter and then the receiver
@@ -257,7 +263,8 @@ mod prop {
             let request = StateDiffsRequest { iteration: Iteration { start: BlockNumberOrHash::Number(start_block), limit, step, direction, } };
             let mut responses = Runtime::new().unwrap().block_on(async {
                 let (tx, rx) = mpsc::channel(0);
-                let getter_fut = sync_handlers::get_state_diffs(storage, request, tx);
+                let getter_fut =
+                    sync_handlers::get_state_diffs(storage, Default::default(), request, tx);
                 let (_, response) = tokio::join!(getter_fut, rx.collect::<Vec<_>>());
                 response
             });
@@ -348,7 +355,8 @@ mod prop {
             let request = ClassesRequest { iteration: Iteration { start: BlockNumberOrHash::Number(start_block), limit, step, direction, } };
             let mut responses = Runtime::new().unwrap().block_on(async {
                 let (tx, rx) = mpsc::channel(0);
-                let getter_fut = sync_handlers::get_classes(storage, request, tx);
+                let getter_fut =
+                    sync_handlers::get_classes(storage, Default::default(), request, tx);
                 let (_, response) = tokio::join!(getter_fut, rx.collect::<Vec<_>>());
                 response
             });
@@ -441,7 +449,8 @@ mod prop {
             let request = TransactionsRequest { iteration: Iteration { start: BlockNumberOrHash::Number(start_block), limit, step, direction, } };
             let mut responses = Runtime::new().unwrap().block_on(async {
                 let (tx, rx) = mpsc::channel(0);
-                let getter_fut = sync_handlers::get_transactions(storage, request, tx);
+                let getter_fut =
+                    sync_handlers::get_transactions(storage, Default::default(), request, tx);
                 let (_, responses) = tokio::join!(getter_fut, rx.collect::<Vec<_>>());
                 responses
             });
@@ -489,7 +498,8 @@ mod prop {
             let request = EventsRequest { iteration: Iteration { start: BlockNumberOrHash::Number(start_block), limit, step, direction, } };
             let mut responses = Runtime::new().unwrap().block_on(async {
                 let (tx, rx) = mpsc::channel(0);
-                let getter_fut = sync_handlers::get_events(storage, request, tx);
+                let getter_fut =
+                    sync_handlers::get_events(storage, Default::default(), request, tx);
                 let (_, response) = tokio::join!(getter_fut, rx.collect::<Vec<_>>());
                 response
             });
@@ -653,3 +663,134 @@ mod prop {
         }
     }
 }
+
+mod classes_by_hash {
+    use futures::channel::mpsc;
+    use futures::StreamExt;
+    use p2p::client::conv::{CairoDefinition, SierraDefinition, TryFromDto};
+    use p2p_proto::class::{Class, ClassesByHashRequest, ClassesByHashResponse};
+    use p2p_proto::common::Hash;
+    use pathfinder_common::felt;
+    use pathfinder_storage::fake::{fill, generate};
+    use pathfinder_storage::StorageBuilder;
+
+    use crate::p2p_network::sync_handlers::get_classes_by_hash;
+
+    #[tokio::test]
+    async fn served_in_request_order_skipping_unknown() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let blocks = generate::n_blocks(3);
+        fill(&storage, &blocks, None);
+
+        let mut expected = blocks
+            .iter()
+            .flat_map(|block| {
+                let cairo = block
+                    .cairo_defs
+                    .iter()
+                    .map(|(hash, definition)| (hash.0, definition.clone()));
+                let sierra = block
+                    .sierra_defs
+                    .iter()
+                    .map(|(hash, definition, _)| (hash.0, definition.clone()));
+                cairo.chain(sierra)
+            })
+            .collect::<Vec<_>>();
+        expected.reverse();
+
+        let mut class_hashes = expected
+            .iter()
+            .map(|(hash, _)| Hash(*hash))
+            .collect::<Vec<_>>();
+        class_hashes.insert(class_hashes.len() / 2, Hash(felt!("0xdeadbeef")));
+
+        let (tx, rx) = mpsc::channel(0);
+        let request = ClassesByHashRequest { class_hashes };
+        let (result, mut responses) = tokio::join!(
+            get_classes_by_hash(storage, Default::default(), request, tx),
+            rx.collect::<Vec<_>>()
+        );
+        result.unwrap();
+
+        assert_eq!(responses.pop().unwrap(), ClassesByHashResponse::Fin);
+        let actual = responses
+            .into_iter()
+            .map(|response| match response {
+                ClassesByHashResponse::Class(Class::Cairo0 {
+                    class, class_hash, ..
+                }) => (
+                    class_hash.0,
+                    CairoDefinition::try_from_dto(class).unwrap().0,
+                ),
+                ClassesByHashResponse::Class(Class::Cairo1 {
+                    class, class_hash, ..
+                }) => (
+                    class_hash.0,
+                    SierraDefinition::try_from_dto(class).unwrap().0,
+                ),
+                ClassesByHashResponse::Fin => panic!("unexpected Fin"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+}
+
+mod pruned_events {
+    use futures::channel::mpsc;
+    use futures::StreamExt;
+    use p2p_proto::common::{BlockNumberOrHash, Direction, Iteration, Step};
+    use p2p_proto::event::{EventsRequest, EventsResponse};
+    use pathfinder_storage::fake::{fill, generate};
+    use pathfinder_storage::{EventRetentionConfig, StorageBuilder, EVENT_PRUNING_BATCH_SIZE};
+
+    use crate::p2p_network::sync_handlers::get_events;
+
+    #[tokio::test]
+    async fn are_not_served() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let blocks = generate::n_blocks(3);
+        fill(&storage, &blocks, None);
+
+        let mut db = storage.connection().unwrap();
+        let db_tx = db.transaction().unwrap();
+        let config = EventRetentionConfig {
+            blocks_kept: 1,
+            allowlist: Default::default(),
+        };
+        db_tx
+            .prune_events(&config, EVENT_PRUNING_BATCH_SIZE)
+            .unwrap();
+        db_tx.commit().unwrap();
+
+        let query = |start| {
+            let storage = storage.clone();
+            async move {
+                let (tx, rx) = mpsc::channel(0);
+                let iteration = Iteration {
+                    start: BlockNumberOrHash::Number(start),
+                    direction: Direction::Forward,
+                    limit: 3,
+                    step: Step::from(Some(1)),
+                };
+                let (result, responses) = tokio::join!(
+                    get_events(storage, Default::default(), EventsRequest { iteration }, tx),
+                    rx.collect::<Vec<_>>()
+                );
+                result.unwrap();
+                responses
+            }
+        };
+
+        // Blocks 0 and 1 are pruned, so the iteration stops right away.
+        assert_eq!(query(0).await, vec![EventsResponse::Fin]);
+
+        let responses = query(2).await;
+        let expected = blocks[2]
+            .transaction_data
+            .iter()
+            .map(|(_, _, events)| events.len())
+            .sum::<usize>();
+        assert_eq!(responses.len(), expected + 1);
+        assert_eq!(responses.last(), Some(&EventsResponse::Fin));
+    }
+}
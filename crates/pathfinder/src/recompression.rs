@@ -0,0 +1,73 @@
+//! Re-encodes stored transactions and events which are not encoded with the
+//! current compression codec.
+//!
+//! Blobs record the format they were written in, so a change of codec does not
+//! require a migration: old rows stay readable and are rewritten in the
+//! background, a few blocks per database transaction so as not to hold up
+//! sync. While every format in use is encoded with the current codec the job
+//! exits right away.
+
+use std::time::Duration;
+
+use anyhow::Context;
+use pathfinder_common::BlockNumber;
+use pathfinder_storage::{transaction_data_needs_recompression, Storage, TransactionBehavior};
+
+/// The number of blocks re-encoded per database transaction.
+const BLOCKS_PER_BATCH: usize = 100;
+
+/// Pause between batches, giving other writers a chance to take the write
+/// lock.
+const BATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Spawns the job, which ends once all blocks have been processed.
+pub fn spawn(storage: Storage) {
+    util::task::spawn(async move {
+        if let Err(error) = recompress(storage).await {
+            tracing::warn!(?error, "Recompressing transaction data failed");
+        }
+    });
+}
+
+async fn recompress(storage: Storage) -> anyhow::Result<()> {
+    if !transaction_data_needs_recompression() {
+        tracing::debug!(
+            "Transaction data is encoded with the current codec, skipping recompression"
+        );
+        return Ok(());
+    }
+
+    let mut next = Some(BlockNumber::GENESIS);
+    let mut rewritten = 0;
+
+    tracing::info!("Recompressing transaction data in the background");
+    while let Some(from) = next {
+        let storage = storage.clone();
+        let (batch_rewritten, batch_next) = util::task::spawn_blocking(move |_| {
+            let mut db = storage
+                .connection()
+                .context("Creating database connection")?;
+            let tx = db
+                .transaction_with_behavior(TransactionBehavior::Immediate)
+                .context("Creating database transaction")?;
+            let result = tx
+                .recompress_transaction_data(from, BLOCKS_PER_BATCH)
+                .context("Recompressing transaction data")?;
+            tx.commit().context("Committing database transaction")?;
+            anyhow::Ok(result)
+        })
+        .await
+        .context("Joining blocking task")??;
+
+        rewritten += batch_rewritten;
+        next = batch_next;
+
+        if let Some(next) = next {
+            tracing::debug!(%next, %rewritten, "Recompressed transaction data");
+            tokio::time::sleep(BATCH_INTERVAL).await;
+        }
+    }
+    tracing::info!(%rewritten, "Recompressed transaction data");
+
+    Ok(())
+}
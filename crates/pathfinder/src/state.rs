@@ -1,4 +1,15 @@
 pub mod block_hash;
 mod sync;
 
-pub use sync::{l1, l2, revert, sync, Gossiper, SyncContext, RESET_DELAY_ON_FAILURE};
+pub use sync::{
+    import,
+    l1,
+    l2,
+    revert,
+    sync,
+    throttle,
+    verification,
+    Gossiper,
+    SyncContext,
+    RESET_DELAY_ON_FAILURE,
+};
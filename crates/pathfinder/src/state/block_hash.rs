@@ -4,8 +4,9 @@ use std::sync::LazyLock;
 use anyhow::{Context, Result};
 use pathfinder_common::event::Event;
 use pathfinder_common::hash::{FeltHash, PedersenHash, PoseidonHash};
-use pathfinder_common::receipt::{ExecutionStatus, Receipt};
+use pathfinder_common::receipt::Receipt;
 use pathfinder_common::transaction::{Transaction, TransactionVariant};
+use pathfinder_common::version_matrix::{self, HashScheme};
 use pathfinder_common::{
     felt_bytes,
     BlockHash,
@@ -26,14 +27,11 @@ use pathfinder_common::{
     TransactionHash,
     TransactionSignatureElem,
 };
-use pathfinder_crypto::hash::{pedersen_hash, poseidon_hash_many, HashChain, PoseidonHasher};
+use pathfinder_crypto::hash::{pedersen_hash, HashChain, PoseidonHasher};
 use pathfinder_crypto::{Felt, MontFelt};
 use pathfinder_merkle_tree::TransactionOrEventTree;
-use sha3::Digest;
 use starknet_gateway_types::reply::Block;
 
-const V_0_11_1: StarknetVersion = StarknetVersion::new(0, 11, 1, 0);
-
 #[derive(Debug, PartialEq, Eq)]
 pub enum VerifyResult {
     Match,
@@ -56,8 +54,11 @@ pub fn verify_gateway_block_commitments_and_hash(
     let mut bhd =
         BlockHeaderData::from_gateway_block(block, state_diff_commitment, state_diff_length)?;
 
-    let computed_transaction_commitment =
-        calculate_transaction_commitment(&block.transactions, block.starknet_version)?;
+    let schemes = HashSchemes::select(chain, block.block_number, block.starknet_version);
+
+    let computed_transaction_commitment = schemes
+        .transaction_commitment
+        .calculate(&block.transactions)?;
 
     // Older blocks on mainnet don't carry a precalculated transaction commitment.
     if block.transaction_commitment == TransactionCommitment::ZERO {
@@ -90,13 +91,12 @@ pub fn verify_gateway_block_commitments_and_hash(
         bhd.receipt_commitment = computed_receipt_commitment;
     }
 
-    let event_commitment = calculate_event_commitment(
+    let event_commitment = schemes.event_commitment.calculate(
         &block
             .transaction_receipts
             .iter()
             .map(|(receipt, events)| (receipt.transaction_hash, events.as_slice()))
             .collect::<Vec<_>>(),
-        block.starknet_version,
     )?;
 
     // Older blocks on mainnet don't carry a precalculated event
@@ -229,32 +229,37 @@ pub fn verify_block_hash(
     chain_id: ChainId,
 ) -> Result<VerifyResult> {
     let meta_info = meta::for_chain(chain);
+    let scheme = HashSchemes::select(chain, header.number, header.starknet_version).block_hash;
 
-    let verified = if meta_info.uses_pre_0_7_hash_algorithm(header.number) {
-        anyhow::ensure!(
-            chain != Chain::Custom,
-            "Chain::Custom should not have any pre 0.7 block hashes"
-        );
+    let verified = match scheme {
+        BlockHashScheme::Pre0_7 => {
+            anyhow::ensure!(
+                chain != Chain::Custom,
+                "Chain::Custom should not have any pre 0.7 block hashes"
+            );
 
-        let computed_hash = compute_final_hash_pre_0_7(&header, chain_id);
-        computed_hash == header.hash
-    } else if header.starknet_version < StarknetVersion::V_0_13_2 {
-        let computed_hash = compute_final_hash_pre_0_13_2(&header);
-        if computed_hash == header.hash {
-            true
-        } else if let Some(fallback_sequencer_address) = meta_info.fallback_sequencer_address {
-            // Try with the fallback sequencer address.
-            let computed_hash = compute_final_hash_pre_0_13_2(&BlockHeaderData {
-                sequencer_address: fallback_sequencer_address,
-                ..header
-            });
+            let computed_hash = compute_final_hash_pre_0_7(&header, chain_id);
+            computed_hash == header.hash
+        }
+        BlockHashScheme::Pedersen => {
+            let computed_hash = compute_final_hash_pre_0_13_2(&header);
+            if computed_hash == header.hash {
+                true
+            } else if let Some(fallback_sequencer_address) = meta_info.fallback_sequencer_address {
+                // Try with the fallback sequencer address.
+                let computed_hash = compute_final_hash_pre_0_13_2(&BlockHeaderData {
+                    sequencer_address: fallback_sequencer_address,
+                    ..header
+                });
+                computed_hash == header.hash
+            } else {
+                false
+            }
+        }
+        BlockHashScheme::PoseidonV0 | BlockHashScheme::PoseidonV1 => {
+            let computed_hash = compute_final_hash(&header);
             computed_hash == header.hash
-        } else {
-            false
         }
-    } else {
-        let computed_hash = compute_final_hash(&header);
-        computed_hash == header.hash
     };
 
     Ok(match verified {
@@ -263,6 +268,169 @@ pub fn verify_block_hash(
     })
 }
 
+/// The algorithms used to hash a block and to compute the commitments in its
+/// header.
+///
+/// Starknet changed these several times, most notably in 0.13.2 which moved
+/// from Pedersen to Poseidon and started committing to receipts. Selecting
+/// them by chain, block number and Starknet version allows verifying blocks
+/// from any point of a chain's history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HashSchemes {
+    pub block_hash: BlockHashScheme,
+    pub transaction_commitment: TransactionCommitmentScheme,
+    pub event_commitment: EventCommitmentScheme,
+    /// Whether the block hash commits to the receipts, which is the case
+    /// since Starknet 0.13.2.
+    pub commits_to_receipts: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockHashScheme {
+    /// Blocks before Starknet 0.7, whose hash includes the chain ID.
+    Pre0_7,
+    /// Pedersen hash chain used from Starknet 0.7 up to 0.13.2.
+    Pedersen,
+    /// Poseidon hash including the individual gas prices, used by Starknet
+    /// 0.13.2 and 0.13.3.
+    PoseidonV0,
+    /// Poseidon hash including the hash of the gas prices, used since
+    /// Starknet 0.13.4.
+    PoseidonV1,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionCommitmentScheme {
+    /// Pedersen, only the signatures of invoke transactions are included.
+    /// Used before Starknet 0.11.1.
+    PedersenInvokeSignatures,
+    /// Pedersen, the signatures of all transactions are included. Used before
+    /// Starknet 0.13.2.
+    Pedersen,
+    /// Poseidon, empty signatures are hashed as a single zero. Used by
+    /// Starknet 0.13.2 and 0.13.3.
+    PoseidonPaddedSignatures,
+    /// Poseidon, used since Starknet 0.13.4.
+    Poseidon,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventCommitmentScheme {
+    /// Pedersen hash of the events alone, used before Starknet 0.13.2.
+    Pedersen,
+    /// Poseidon hash of the events and their transaction hash, used since
+    /// Starknet 0.13.2.
+    Poseidon,
+}
+
+impl HashSchemes {
+    /// Selects the schemes used by block `number` of `chain`, which was
+    /// produced by Starknet `version`.
+    ///
+    /// Blocks old enough not to report a version are treated as produced by
+    /// the oldest versions.
+    pub fn select(chain: Chain, number: BlockNumber, version: StarknetVersion) -> Self {
+        let scheme = version_matrix::capabilities(version).hash_scheme;
+
+        let block_hash = if meta::for_chain(chain).uses_pre_0_7_hash_algorithm(number) {
+            BlockHashScheme::Pre0_7
+        } else {
+            match scheme {
+                HashScheme::PedersenInvokeSignatures | HashScheme::Pedersen => {
+                    BlockHashScheme::Pedersen
+                }
+                HashScheme::PoseidonV0 => BlockHashScheme::PoseidonV0,
+                HashScheme::PoseidonV1 => BlockHashScheme::PoseidonV1,
+            }
+        };
+
+        Self {
+            block_hash,
+            transaction_commitment: TransactionCommitmentScheme::for_version(version),
+            event_commitment: EventCommitmentScheme::for_version(version),
+            commits_to_receipts: scheme.is_poseidon(),
+        }
+    }
+}
+
+impl TransactionCommitmentScheme {
+    pub fn for_version(version: StarknetVersion) -> Self {
+        match version_matrix::capabilities(version).hash_scheme {
+            HashScheme::PedersenInvokeSignatures => Self::PedersenInvokeSignatures,
+            HashScheme::Pedersen => Self::Pedersen,
+            HashScheme::PoseidonV0 => Self::PoseidonPaddedSignatures,
+            HashScheme::PoseidonV1 => Self::Poseidon,
+        }
+    }
+
+    /// Calculates the transaction commitment with this scheme.
+    pub fn calculate(self, transactions: &[Transaction]) -> Result<TransactionCommitment> {
+        use rayon::prelude::*;
+
+        let final_hashes = transactions
+            .par_iter()
+            .map(|tx| match self {
+                Self::PedersenInvokeSignatures => {
+                    calculate_transaction_hash_with_signature_pre_0_11_1(tx)
+                }
+                Self::Pedersen => calculate_transaction_hash_with_signature_pre_0_13_2(tx),
+                Self::PoseidonPaddedSignatures => {
+                    calculate_transaction_hash_with_signature_pre_0_13_4(tx)
+                }
+                Self::Poseidon => calculate_transaction_hash_with_signature(tx),
+            })
+            .collect();
+
+        match self {
+            Self::PedersenInvokeSignatures | Self::Pedersen => {
+                calculate_commitment_root::<PedersenHash>(final_hashes).map(TransactionCommitment)
+            }
+            Self::PoseidonPaddedSignatures | Self::Poseidon => {
+                calculate_commitment_root::<PoseidonHash>(final_hashes).map(TransactionCommitment)
+            }
+        }
+    }
+}
+
+impl EventCommitmentScheme {
+    pub fn for_version(version: StarknetVersion) -> Self {
+        if version_matrix::capabilities(version)
+            .hash_scheme
+            .is_poseidon()
+        {
+            Self::Poseidon
+        } else {
+            Self::Pedersen
+        }
+    }
+
+    /// Calculates the event commitment with this scheme.
+    pub fn calculate(
+        self,
+        transaction_events: &[(TransactionHash, &[Event])],
+    ) -> Result<EventCommitment> {
+        use rayon::prelude::*;
+
+        let event_hashes = transaction_events
+            .par_iter()
+            .flat_map(|(tx_hash, events)| events.par_iter().map(|e| (*tx_hash, e)))
+            .map(|(tx_hash, e)| match self {
+                Self::Pedersen => calculate_event_hash_pre_0_13_2(e),
+                Self::Poseidon => e.hash(tx_hash),
+            })
+            .collect();
+
+        match self {
+            Self::Pedersen => {
+                calculate_commitment_root::<PedersenHash>(event_hashes).map(EventCommitment)
+            }
+            Self::Poseidon => {
+                calculate_commitment_root::<PoseidonHash>(event_hashes).map(EventCommitment)
+            }
+        }
+    }
+}
+
 mod meta {
     use pathfinder_common::{sequencer_address, BlockNumber, Chain, SequencerAddress};
 
@@ -459,10 +627,9 @@ fn compute_final_hash_v1(header: &BlockHeaderData) -> BlockHash {
 }
 
 pub fn compute_final_hash(header: &BlockHeaderData) -> BlockHash {
-    if header.starknet_version < StarknetVersion::V_0_13_4 {
-        compute_final_hash_v0(header)
-    } else {
-        compute_final_hash_v1(header)
+    match version_matrix::capabilities(header.starknet_version).hash_scheme {
+        HashScheme::PoseidonV1 => compute_final_hash_v1(header),
+        _ => compute_final_hash_v0(header),
     }
 }
 
@@ -476,79 +643,13 @@ pub fn calculate_transaction_commitment(
     transactions: &[Transaction],
     version: StarknetVersion,
 ) -> Result<TransactionCommitment> {
-    use rayon::prelude::*;
-
-    let final_hashes = transactions
-        .par_iter()
-        .map(|tx| {
-            if version < V_0_11_1 {
-                calculate_transaction_hash_with_signature_pre_0_11_1(tx)
-            } else if version < StarknetVersion::V_0_13_2 {
-                calculate_transaction_hash_with_signature_pre_0_13_2(tx)
-            } else if version < StarknetVersion::V_0_13_4 {
-                calculate_transaction_hash_with_signature_pre_0_13_4(tx)
-            } else {
-                calculate_transaction_hash_with_signature(tx)
-            }
-        })
-        .collect();
-
-    if version < StarknetVersion::V_0_13_2 {
-        calculate_commitment_root::<PedersenHash>(final_hashes).map(TransactionCommitment)
-    } else {
-        calculate_commitment_root::<PoseidonHash>(final_hashes).map(TransactionCommitment)
-    }
+    TransactionCommitmentScheme::for_version(version).calculate(transactions)
 }
 
 pub fn calculate_receipt_commitment(receipts: &[Receipt]) -> Result<ReceiptCommitment> {
     use rayon::prelude::*;
 
-    let hashes = receipts
-        .par_iter()
-        .map(|receipt| {
-            poseidon_hash_many(&[
-                receipt.transaction_hash.0.into(),
-                receipt.actual_fee.0.into(),
-                // Calculate hash of messages sent.
-                {
-                    let mut hasher = PoseidonHasher::new();
-                    hasher.write((receipt.l2_to_l1_messages.len() as u64).into());
-                    for msg in &receipt.l2_to_l1_messages {
-                        hasher.write(msg.from_address.0.into());
-                        hasher.write(msg.to_address.0.into());
-                        hasher.write((msg.payload.len() as u64).into());
-                        for payload in &msg.payload {
-                            hasher.write(payload.0.into());
-                        }
-                    }
-                    hasher.finish()
-                },
-                // Revert reason.
-                match &receipt.execution_status {
-                    ExecutionStatus::Succeeded => MontFelt::ZERO,
-                    ExecutionStatus::Reverted { reason } => {
-                        let mut keccak = sha3::Keccak256::default();
-                        keccak.update(reason.as_bytes());
-                        let mut hashed_bytes: [u8; 32] = keccak.finalize().into();
-                        hashed_bytes[0] &= 0b00000011_u8; // Discard the six MSBs.
-                        MontFelt::from_be_bytes(hashed_bytes)
-                    }
-                },
-                // Execution resources:
-                // L2 gas
-                MontFelt::ZERO,
-                // L1 gas consumed
-                receipt.execution_resources.total_gas_consumed.l1_gas.into(),
-                // L1 data gas consumed
-                receipt
-                    .execution_resources
-                    .total_gas_consumed
-                    .l1_data_gas
-                    .into(),
-            ])
-            .into()
-        })
-        .collect();
+    let hashes = receipts.par_iter().map(Receipt::hash).collect();
 
     calculate_commitment_root::<PoseidonHash>(hashes).map(ReceiptCommitment)
 }
@@ -738,25 +839,7 @@ pub fn calculate_event_commitment(
     transaction_events: &[(TransactionHash, &[Event])],
     version: StarknetVersion,
 ) -> Result<EventCommitment> {
-    use rayon::prelude::*;
-
-    let event_hashes = transaction_events
-        .par_iter()
-        .flat_map(|(tx_hash, events)| events.par_iter().map(|e| (*tx_hash, e)))
-        .map(|(tx_hash, e)| {
-            if version < StarknetVersion::V_0_13_2 {
-                calculate_event_hash_pre_0_13_2(e)
-            } else {
-                calculate_event_hash(e, tx_hash)
-            }
-        })
-        .collect();
-
-    if version < StarknetVersion::V_0_13_2 {
-        calculate_commitment_root::<PedersenHash>(event_hashes).map(EventCommitment)
-    } else {
-        calculate_commitment_root::<PoseidonHash>(event_hashes).map(EventCommitment)
-    }
+    EventCommitmentScheme::for_version(version).calculate(transaction_events)
 }
 
 /// Calculate the hash of a pre-v0.13.2 Starknet event.
@@ -784,28 +867,11 @@ fn calculate_event_hash_pre_0_13_2(event: &Event) -> Felt {
     event_hash.finalize()
 }
 
-/// Calculate the hash of an event.
-/// [Reference code from StarkWare](https://github.com/starkware-libs/starknet-api/blob/5565e5282f5fead364a41e49c173940fd83dee00/src/block_hash/event_commitment.rs#L33).
-fn calculate_event_hash(event: &Event, transaction_hash: TransactionHash) -> Felt {
-    let mut hasher = PoseidonHasher::new();
-    hasher.write(event.from_address.0.into());
-    hasher.write(transaction_hash.0.into());
-    hasher.write((event.keys.len() as u64).into());
-    for key in &event.keys {
-        hasher.write(key.0.into());
-    }
-    hasher.write((event.data.len() as u64).into());
-    for data in &event.data {
-        hasher.write(data.0.into());
-    }
-    hasher.finish().into()
-}
-
 #[cfg(test)]
 mod tests {
     use assert_matches::assert_matches;
     use pathfinder_common::macro_prelude::*;
-    use pathfinder_common::receipt::{ExecutionResources, L1Gas, L2ToL1Message};
+    use pathfinder_common::receipt::{ExecutionResources, ExecutionStatus, L1Gas, L2ToL1Message};
     use pathfinder_common::transaction::{
         EntryPointType,
         InvokeTransactionV0,
@@ -1029,6 +1095,68 @@ mod tests {
         );
     }
 
+    #[rstest::rstest]
+    #[case::mainnet_pre_0_7(
+        Chain::Mainnet,
+        832,
+        StarknetVersion::default(),
+        BlockHashScheme::Pre0_7,
+        TransactionCommitmentScheme::PedersenInvokeSignatures
+    )]
+    #[case::mainnet_0_7(
+        Chain::Mainnet,
+        833,
+        StarknetVersion::default(),
+        BlockHashScheme::Pedersen,
+        TransactionCommitmentScheme::PedersenInvokeSignatures
+    )]
+    #[case::sepolia_genesis(
+        Chain::SepoliaTestnet,
+        0,
+        StarknetVersion::new(0, 12, 3, 0),
+        BlockHashScheme::Pedersen,
+        TransactionCommitmentScheme::Pedersen
+    )]
+    #[case::v0_13_1(
+        Chain::Mainnet,
+        600_000,
+        StarknetVersion::new(0, 13, 1, 1),
+        BlockHashScheme::Pedersen,
+        TransactionCommitmentScheme::Pedersen
+    )]
+    #[case::v0_13_2(
+        Chain::Mainnet,
+        700_000,
+        StarknetVersion::V_0_13_2,
+        BlockHashScheme::PoseidonV0,
+        TransactionCommitmentScheme::PoseidonPaddedSignatures
+    )]
+    #[case::v0_13_4(
+        Chain::Mainnet,
+        1_000_000,
+        StarknetVersion::V_0_13_4,
+        BlockHashScheme::PoseidonV1,
+        TransactionCommitmentScheme::Poseidon
+    )]
+    fn hash_scheme_selection(
+        #[case] chain: Chain,
+        #[case] number: u64,
+        #[case] version: StarknetVersion,
+        #[case] block_hash: BlockHashScheme,
+        #[case] transaction_commitment: TransactionCommitmentScheme,
+    ) {
+        let schemes = HashSchemes::select(chain, BlockNumber::new_or_panic(number), version);
+
+        assert_eq!(schemes.block_hash, block_hash);
+        assert_eq!(schemes.transaction_commitment, transaction_commitment);
+        let poseidon = version >= StarknetVersion::V_0_13_2;
+        assert_eq!(schemes.commits_to_receipts, poseidon);
+        assert_eq!(
+            schemes.event_commitment == EventCommitmentScheme::Poseidon,
+            poseidon
+        );
+    }
+
     /// Source:
     /// https://github.com/starkware-libs/starknet-api/blob/5565e5282f5fead364a41e49c173940fd83dee00/src/block_hash/transaction_commitment_test.rs#L12-L29.
     #[test]
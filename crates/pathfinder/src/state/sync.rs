@@ -1,8 +1,13 @@
 mod class;
+pub mod head_race;
+pub mod import;
 pub mod l1;
 pub mod l2;
 mod pending;
+mod pipeline;
 pub mod revert;
+pub mod throttle;
+pub mod verification;
 
 use std::future::Future;
 use std::sync::Arc;
@@ -22,12 +27,18 @@ use pathfinder_ethereum::{EthereumApi, EthereumStateUpdate};
 use pathfinder_merkle_tree::starknet_state::update_starknet_state;
 use pathfinder_rpc::types::syncing::{self, NumberedBlock, Syncing};
 use pathfinder_rpc::{Notifications, PendingData, Reorg, SyncState, TopicBroadcasters};
-use pathfinder_storage::{Connection, Storage, TransactionBehavior};
+use pathfinder_storage::{
+    BlockEventFilter,
+    Connection,
+    EventRetentionConfig,
+    Storage,
+    TransactionBehavior,
+};
 use primitive_types::H160;
 use starknet_gateway_client::GatewayApi;
 use starknet_gateway_types::reply::{Block, PendingBlock};
 use tokio::sync::mpsc::{self, Receiver};
-use tokio::sync::watch::Sender as WatchSender;
+use tokio::sync::watch::{Receiver as WatchReceiver, Sender as WatchSender};
 
 use crate::state::l1::L1SyncContext;
 use crate::state::l2::{BlockChain, L2SyncContext};
@@ -85,6 +96,10 @@ pub struct SyncContext<G, E> {
     pub l1_poll_interval: Duration,
     pub pending_data: WatchSender<PendingData>,
     pub block_validation_mode: l2::BlockValidationMode,
+    pub transaction_hash_verification: l2::TransactionHashVerification,
+    /// Whether to reject and quarantine blocks failing any check, see
+    /// [verification].
+    pub gateway_data_verification: verification::GatewayDataVerification,
     pub websocket_txs: Option<TopicBroadcasters>,
     pub notifications: Notifications,
     pub block_cache_size: usize,
@@ -93,7 +108,21 @@ pub struct SyncContext<G, E> {
     pub gossiper: Gossiper,
     pub sequencer_public_key: PublicKey,
     pub fetch_concurrency: std::num::NonZeroUsize,
+    /// How many classes of a block to download at the same time.
+    pub class_fetch_concurrency: std::num::NonZeroUsize,
+    /// How many blocks to verify at the same time while catching up.
+    pub verification_concurrency: std::num::NonZeroUsize,
     pub fetch_casm_from_fgw: bool,
+    pub write_throttle: throttle::WriteThrottleConfig,
+    /// Aggregate per-class usage statistics of each synced block.
+    pub class_stats: bool,
+    /// Prune events outside of the retention window after each synced block.
+    pub event_retention: Option<EventRetentionConfig>,
+    /// New block announcements from the p2p network, raced against polling the
+    /// gateway for the latest block.
+    pub p2p_announcements: Option<head_race::Announcements>,
+    /// Set while the node is low on disk space, see [crate::disk_guard].
+    pub disk_degraded: WatchReceiver<bool>,
 }
 
 impl<G, E> From<&SyncContext<G, E>> for L1SyncContext<E>
@@ -120,9 +149,13 @@ where
             chain: value.chain,
             chain_id: value.chain_id,
             block_validation_mode: value.block_validation_mode,
+            transaction_hash_verification: value.transaction_hash_verification,
+            gateway_data_verification: value.gateway_data_verification,
             storage: value.storage.clone(),
             sequencer_public_key: value.sequencer_public_key,
             fetch_concurrency: value.fetch_concurrency,
+            class_fetch_concurrency: value.class_fetch_concurrency,
+            verification_concurrency: value.verification_concurrency,
             fetch_casm_from_fgw: value.fetch_casm_from_fgw,
         }
     }
@@ -196,6 +229,8 @@ where
         l1_poll_interval: _,
         pending_data,
         block_validation_mode: _,
+        transaction_hash_verification: _,
+        gateway_data_verification,
         websocket_txs,
         notifications,
         block_cache_size,
@@ -204,7 +239,14 @@ where
         gossiper,
         sequencer_public_key: _,
         fetch_concurrency: _,
+        class_fetch_concurrency,
+        verification_concurrency: _,
         fetch_casm_from_fgw,
+        write_throttle,
+        class_stats,
+        event_retention,
+        p2p_announcements,
+        disk_degraded,
     } = context;
 
     let mut db_conn = storage
@@ -232,11 +274,19 @@ where
 
     // Keep polling the sequencer for the latest block
     let (tx_latest, rx_latest) = tokio::sync::watch::channel(gateway_latest);
-    let mut latest_handle = util::task::spawn(l2::poll_latest(
-        sequencer.clone(),
-        head_poll_interval,
-        tx_latest,
-    ));
+    let mut latest_handle = match p2p_announcements {
+        Some(announcements) => util::task::spawn(head_race::track_latest(
+            sequencer.clone(),
+            head_poll_interval,
+            announcements,
+            tx_latest,
+        )),
+        None => util::task::spawn(l2::poll_latest(
+            sequencer.clone(),
+            head_poll_interval,
+            tx_latest,
+        )),
+    };
 
     // Start update sync-status process.
     let (starting_block_num, starting_block_hash, _) = l2_head.unwrap_or((
@@ -274,6 +324,8 @@ where
         rx_latest.clone(),
     ));
 
+    let _pending_age = util::task::spawn(pending::track_pending_age(pending_data.subscribe()));
+
     let (current_num, current_hash, _) = l2_head.unwrap_or_default();
     let (tx_current, rx_current) = tokio::sync::watch::channel((current_num, current_hash));
     let consumer_context = ConsumerContext {
@@ -283,6 +335,11 @@ where
         verify_tree_hashes: context.verify_tree_hashes,
         websocket_txs,
         notifications,
+        write_throttle,
+        class_stats,
+        event_retention,
+        disk_degraded,
+        gateway_data_verification,
     };
     let mut consumer_handle =
         util::task::spawn(consumer(event_receiver, consumer_context, tx_current));
@@ -295,6 +352,7 @@ where
         rx_latest.clone(),
         rx_current.clone(),
         fetch_casm_from_fgw,
+        class_fetch_concurrency,
     ));
 
     loop {
@@ -310,6 +368,7 @@ where
                     rx_latest.clone(),
                     rx_current.clone(),
                     fetch_casm_from_fgw,
+                    class_fetch_concurrency,
                 ));
             },
             _ = &mut latest_handle => {
@@ -352,6 +411,14 @@ where
                     }
                     Err(e) => {
                         tracing::warn!("L2 sync process terminated with: {e:?}");
+                        if gateway_data_verification.is_strict() {
+                            let quarantined = tokio::task::block_in_place(|| {
+                                verification::quarantine(&storage, &e)
+                            });
+                            if let Err(error) = quarantined {
+                                tracing::error!(?error, "Failed to quarantine block");
+                            }
+                        }
                     }
                 }
 
@@ -452,8 +519,18 @@ struct ConsumerContext {
     pub verify_tree_hashes: bool,
     pub websocket_txs: Option<TopicBroadcasters>,
     pub notifications: Notifications,
+    pub write_throttle: throttle::WriteThrottleConfig,
+    pub class_stats: bool,
+    pub event_retention: Option<EventRetentionConfig>,
+    pub disk_degraded: WatchReceiver<bool>,
+    pub gateway_data_verification: verification::GatewayDataVerification,
 }
 
+/// The write throttle only applies while the consumer is at least this many
+/// blocks behind the chain tip, so that a node which has caught up is never
+/// slowed down.
+const WRITE_THROTTLE_MIN_BLOCKS_BEHIND: u64 = 10;
+
 async fn consumer(
     mut events: Receiver<SyncEvent>,
     context: ConsumerContext,
@@ -466,8 +543,15 @@ async fn consumer(
         verify_tree_hashes,
         mut websocket_txs,
         mut notifications,
+        write_throttle,
+        class_stats,
+        event_retention,
+        mut disk_degraded,
+        gateway_data_verification,
     } = context;
 
+    let mut write_throttle = throttle::WriteThrottle::new(write_throttle);
+
     let mut last_block_start = std::time::Instant::now();
     let mut block_time_avg = std::time::Duration::ZERO;
     const BLOCK_TIME_WEIGHT: f32 = 0.05;
@@ -490,7 +574,13 @@ async fn consumer(
     })
     .context("Fetching latest block time")?;
 
-    while let Some(event) = events.recv().await {
+    let mut events = spawn_event_filter_stage(events);
+
+    while let Some((event, event_filter)) = events.recv().await {
+        if *disk_degraded.borrow() {
+            pause_while_degraded(&db_conn, &mut disk_degraded).await?;
+        }
+
         use SyncEvent::*;
         match event {
             L1Update(update) => {
@@ -519,8 +609,10 @@ async fn consumer(
                     .iter()
                     .map(|x| x.1.storage.len())
                     .sum();
+                let write_size = throttle::estimated_write_size(&block, &state_update);
+                let event_filter = event_filter.expect("Event filters are built for all blocks");
                 let update_t = std::time::Instant::now();
-                l2_update(
+                let result = l2_update(
                     &mut db_conn,
                     *block,
                     tx_comm,
@@ -529,13 +621,22 @@ async fn consumer(
                     *state_update,
                     *signature,
                     *state_diff_commitment,
+                    event_filter,
                     verify_tree_hashes,
+                    class_stats,
+                    event_retention.as_ref(),
                     storage.clone(),
                     &mut websocket_txs,
                     &mut notifications,
                 )
-                .await
-                .with_context(|| format!("Update L2 state to {block_number}"))?;
+                .await;
+                if let Err(error) = &result {
+                    if gateway_data_verification.is_strict() {
+                        tokio::task::block_in_place(|| verification::quarantine(&storage, error))
+                            .context("Quarantining block")?;
+                    }
+                }
+                result.with_context(|| format!("Update L2 state to {block_number}"))?;
                 let block_time = last_block_start.elapsed();
                 let update_t = update_t.elapsed();
                 last_block_start = std::time::Instant::now();
@@ -544,8 +645,8 @@ async fn consumer(
                     + block_time.mul_f32(BLOCK_TIME_WEIGHT);
 
                 // Update sync status
-                match &mut *state.status.write().await {
-                    Syncing::False => {}
+                let blocks_behind = match &mut *state.status.write().await {
+                    Syncing::False => 0,
                     Syncing::Status(status) => {
                         status.current = NumberedBlock::from((block_hash, block_number));
 
@@ -555,10 +656,13 @@ async fn consumer(
                             status.highest = status.current;
                             metrics::gauge!("highest_block", block_number.get() as f64);
                         }
+
+                        status.highest.number.get() - block_number.get()
                     }
-                }
+                };
 
                 _ = current.send((block_number, block_hash));
+                pending::discard_stale(&pending_data, block_hash);
 
                 let now_timestamp = time::OffsetDateTime::now_utc().unix_timestamp() as u64;
                 let latency = now_timestamp.saturating_sub(block_timestamp.get());
@@ -579,6 +683,13 @@ async fn consumer(
                 latest_timestamp = block_timestamp;
                 next_number += 1;
 
+                let throttle_delay =
+                    write_throttle.record(write_size, update_t, std::time::Instant::now());
+                if blocks_behind >= WRITE_THROTTLE_MIN_BLOCKS_BEHIND && !throttle_delay.is_zero() {
+                    metrics::histogram!("sync_write_throttle_seconds", throttle_delay);
+                    tokio::time::sleep(throttle_delay).await;
+                }
+
                 // Give a simple log under INFO level, and a more verbose log
                 // with timing information under DEBUG+ level.
                 //
@@ -614,6 +725,21 @@ async fn consumer(
 
                 next_number = reorg_tail;
 
+                let head_hash = tokio::task::block_in_place(|| {
+                    let tx = db_conn
+                        .transaction()
+                        .context("Creating database transaction")?;
+                    let head = tx
+                        .block_id(pathfinder_storage::BlockId::Latest)
+                        .context("Fetching latest block hash")?
+                        .map(|(_, hash)| hash)
+                        .unwrap_or_default();
+
+                    anyhow::Ok(head)
+                })
+                .context("Fetching latest block hash")?;
+                pending::discard_stale(&pending_data, head_hash);
+
                 let new_head = match reorg_tail {
                     BlockNumber::GENESIS => None,
                     other => Some(other - 1),
@@ -688,6 +814,12 @@ async fn consumer(
                     };
                     pending_data.send_replace(data);
                     tracing::debug!("Updated pending data");
+                } else {
+                    tracing::debug!(
+                        parent=%pending.0.parent_hash, latest=%hash,
+                        "Ignoring pending data which is not built on the latest block"
+                    );
+                    pending::discard_stale(&pending_data, hash);
                 }
             }
         }
@@ -696,6 +828,39 @@ async fn consumer(
     Ok(())
 }
 
+/// Builds the event filters of blocks ahead of the consumer, so that building
+/// the filter of a block overlaps with storing the previous one.
+fn spawn_event_filter_stage(
+    mut events: Receiver<SyncEvent>,
+) -> Receiver<(SyncEvent, Option<BlockEventFilter>)> {
+    let (tx, rx) = mpsc::channel(pipeline::STAGE_BUFFER);
+
+    util::task::spawn(async move {
+        while let Some(event) = events.recv().await {
+            let event_filter = match &event {
+                SyncEvent::Block((block, _), ..) => Some(tokio::task::block_in_place(|| {
+                    let t = std::time::Instant::now();
+                    let event_filter = BlockEventFilter::new(
+                        block
+                            .transaction_receipts
+                            .iter()
+                            .flat_map(|(_, events)| events.iter()),
+                    );
+                    pipeline::record_duration("compute_filters", t.elapsed());
+                    event_filter
+                })),
+                _ => None,
+            };
+
+            if tx.send((event, event_filter)).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    rx
+}
+
 async fn latest_n_blocks(
     connection: &mut Connection,
     n: usize,
@@ -807,6 +972,29 @@ async fn propagate_head(gossiper: &Gossiper, last_propagated: &mut Instant, head
     *last_propagated = Instant::now();
 }
 
+/// Blocks until the node leaves degraded mode. The WAL is checkpointed first
+/// so that the space it occupies is returned to the file system.
+async fn pause_while_degraded(
+    connection: &Connection,
+    disk_degraded: &mut WatchReceiver<bool>,
+) -> anyhow::Result<()> {
+    tracing::warn!("Sync paused until disk space is freed");
+
+    match tokio::task::block_in_place(|| connection.checkpoint_wal()) {
+        Ok(true) => {}
+        Ok(false) => tracing::warn!("WAL checkpoint did not complete, database is busy"),
+        Err(error) => tracing::warn!(%error, "Failed to checkpoint WAL"),
+    }
+
+    disk_degraded
+        .wait_for(|degraded| !degraded)
+        .await
+        .context("Disk guard task exited")?;
+
+    tracing::info!("Sync resumed");
+    Ok(())
+}
+
 async fn l1_update(
     connection: &mut Connection,
     update: &EthereumStateUpdate,
@@ -857,7 +1045,10 @@ async fn l2_update(
     state_update: StateUpdate,
     signature: BlockCommitmentSignature,
     state_diff_commitment: StateDiffCommitment,
+    event_filter: BlockEventFilter,
     verify_tree_hashes: bool,
+    class_stats: bool,
+    event_retention: Option<&EventRetentionConfig>,
     // we need this so that we can create extra read-only transactions for
     // parallel contract state updates
     storage: Storage,
@@ -868,6 +1059,7 @@ async fn l2_update(
         let transaction = connection
             .transaction_with_behavior(TransactionBehavior::Immediate)
             .context("Create database transaction")?;
+        let t_tries = std::time::Instant::now();
         let (storage_commitment, class_commitment) = update_starknet_state(
             &transaction,
             (&state_update).into(),
@@ -876,15 +1068,26 @@ async fn l2_update(
             storage,
         )
         .context("Updating Starknet state")?;
+        pipeline::record_duration("compute_tries", t_tries.elapsed());
         let state_commitment = StateCommitment::calculate(storage_commitment, class_commitment);
 
         // Ensure that roots match.. what should we do if it doesn't? For now the whole
         // sync process ends..
-        anyhow::ensure!(
-            state_commitment == block.state_commitment,
-            "State root mismatch"
-        );
+        let state_root_matches = state_commitment == block.state_commitment;
+        verification::record(verification::Check::StateRoot, state_root_matches);
+        if !state_root_matches {
+            return Err(verification::VerificationFailure::new(
+                verification::Check::StateRoot,
+                block.block_number,
+                block.block_hash,
+                format!(
+                    "State root mismatch, computed {state_commitment} instead of {}",
+                    block.state_commitment
+                ),
+            ));
+        }
 
+        let t_persist = std::time::Instant::now();
         let transaction_count = block.transactions.len();
         let event_count = block
             .transaction_receipts
@@ -945,7 +1148,12 @@ async fn l2_update(
             .unzip();
 
         transaction
-            .insert_transaction_data(header.number, &transactions_data, Some(&events_data))
+            .insert_transaction_data_with_event_filter(
+                header.number,
+                &transactions_data,
+                &events_data,
+                event_filter,
+            )
             .context("Insert transaction data into database")?;
 
         // Insert state updates
@@ -953,6 +1161,30 @@ async fn l2_update(
             .insert_state_update(block.block_number, &state_update)
             .context("Insert state update into database")?;
 
+        if class_stats {
+            transaction
+                .insert_class_stats(block.block_number)
+                .context("Insert class statistics into database")?;
+        }
+
+        if let Some(event_retention) = event_retention {
+            // Bounded so that a jump of the horizon does not stall sync, any
+            // blocks left over are pruned along with the next blocks.
+            let pruning = transaction
+                .prune_events(
+                    event_retention,
+                    pathfinder_storage::EVENT_PRUNING_BATCH_SIZE,
+                )
+                .context("Prune events")?;
+            if pruning.remaining > 0 {
+                tracing::debug!(
+                    pruned_before=%pruning.retention.pruned_before,
+                    remaining=%pruning.remaining,
+                    "Pruning events outside of the retention window"
+                );
+            }
+        }
+
         // Insert signature
         transaction
             .insert_signature(block.block_number, &signature)
@@ -980,6 +1212,7 @@ async fn l2_update(
         transaction
             .commit()
             .context("Commit database transaction")?;
+        pipeline::record_duration("persist", t_persist.elapsed());
 
         if let Some(sender) = websocket_txs {
             if let Err(e) = sender.new_head.send_if_receiving(header.clone().into()) {
@@ -1028,7 +1261,7 @@ async fn l2_reorg(
             .transaction_with_behavior(TransactionBehavior::Immediate)
             .context("Create database transaction")?;
 
-        let mut head = transaction
+        let head = transaction
             .block_id(pathfinder_storage::BlockId::Latest)
             .context("Querying latest block number")?
             .context("Latest block number is none during reorg")?
@@ -1047,58 +1280,7 @@ async fn l2_reorg(
             .increment_reorg_counter()
             .context("Incrementing reorg counter")?;
 
-        // Roll back Merkle trie updates.
-        //
-        // If we're rolling back genesis then there will be no blocks left so state will
-        // be empty.
-        if let Some(target_block) = reorg_tail.parent() {
-            let target_header = transaction
-                .block_header(target_block.into())
-                .context("Fetching target block header")?
-                .context("Expected target header to exist")?;
-            revert::revert_starknet_state(&transaction, head, target_block, target_header)?;
-        }
-
-        // Purge each block one at a time.
-        //
-        // This is done 1-by-1 to allow sending the reorg'd block data
-        // to websocket subscriptions while keeping a constant memory footprint.
-        //
-        // This is acceptable performance because reorgs are rare and need not be
-        // 100% optimal. However a large reorg could cause a massive memory spike
-        // which is not acceptable.
-        while head >= reorg_tail {
-            transaction
-                .purge_block(head)
-                .with_context(|| format!("Purging block {head} from database"))?;
-
-            // No further blocks to purge if we just purged genesis.
-            if head == BlockNumber::GENESIS {
-                break;
-            }
-
-            head -= 1;
-        }
-
-        transaction
-            .reset()
-            .context("Resetting local DB state after reorg")?;
-
-        // Track combined L1 and L2 state.
-        let l1_l2_head = transaction.l1_l2_pointer().context("Query L1-L2 head")?;
-        if let Some(l1_l2_head) = l1_l2_head {
-            if reorg_tail == BlockNumber::GENESIS {
-                // If we purged genesis then unset the L1 L2 pointer as well since there
-                // are now no blocks remaining.
-                transaction
-                    .update_l1_l2_pointer(None)
-                    .context("Unsetting L1-L2 head")?;
-            } else if l1_l2_head >= reorg_tail {
-                transaction
-                    .update_l1_l2_pointer(Some(reorg_tail - 1))
-                    .context("Updating L1-L2 head")?;
-            }
-        }
+        revert::purge_blocks(&transaction, head, reorg_tail)?;
 
         transaction
             .commit()
@@ -1145,7 +1327,7 @@ mod tests {
         TransactionCommitment,
     };
     use pathfinder_crypto::Felt;
-    use pathfinder_rpc::SyncState;
+    use pathfinder_rpc::{PendingData, SyncState};
     use pathfinder_storage::StorageBuilder;
     use starknet_gateway_types::reply::{self, Block, GasPrices};
 
@@ -1290,6 +1472,11 @@ mod tests {
             verify_tree_hashes: false,
             websocket_txs: None,
             notifications: Default::default(),
+            write_throttle: Default::default(),
+            class_stats: false,
+            event_retention: None,
+            disk_degraded: tokio::sync::watch::channel(false).1,
+            gateway_data_verification: Default::default(),
         };
 
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
@@ -1344,6 +1531,11 @@ mod tests {
             verify_tree_hashes: false,
             websocket_txs: None,
             notifications: Default::default(),
+            write_throttle: Default::default(),
+            class_stats: false,
+            event_retention: None,
+            disk_degraded: tokio::sync::watch::channel(false).1,
+            gateway_data_verification: Default::default(),
         };
 
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
@@ -1364,6 +1556,80 @@ mod tests {
         assert!(!block_2_exists);
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reorg_discards_orphaned_pending_data() {
+        let storage = StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
+            pathfinder_storage::TriePruneMode::Archive,
+            std::num::NonZeroU32::new(5).unwrap(),
+        )
+        .unwrap();
+
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(100);
+
+        let blocks = generate_block_data();
+        let blocks_len = blocks.len();
+        let head = blocks.last().unwrap().0 .0.block_hash;
+        for (a, b, c, d, e) in blocks {
+            event_tx
+                .send(SyncEvent::Block(a, b, c, d, e))
+                .await
+                .unwrap();
+        }
+        drop(event_tx);
+
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        let context = ConsumerContext {
+            storage: storage.clone(),
+            state: Arc::new(SyncState::default()),
+            pending_data: tx,
+            verify_tree_hashes: false,
+            websocket_txs: None,
+            notifications: Default::default(),
+            write_throttle: Default::default(),
+            class_stats: false,
+            event_retention: None,
+            disk_degraded: tokio::sync::watch::channel(false).1,
+            gateway_data_verification: Default::default(),
+        };
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        consumer(event_rx, context, tx).await.unwrap();
+
+        // Reorg away the parent of pending data built on top of the head.
+        let (event_tx, event_rx) = tokio::sync::mpsc::channel(1);
+        event_tx
+            .send(SyncEvent::Reorg(BlockNumber::new_or_panic(2)))
+            .await
+            .unwrap();
+        drop(event_tx);
+
+        let pending = PendingData {
+            block: Arc::new(reply::PendingBlock {
+                parent_hash: head,
+                ..Default::default()
+            }),
+            state_update: Default::default(),
+            number: BlockNumber::new_or_panic(blocks_len as u64),
+        };
+        let (tx, rx) = tokio::sync::watch::channel(pending);
+        let context = ConsumerContext {
+            storage,
+            state: Arc::new(SyncState::default()),
+            pending_data: tx,
+            verify_tree_hashes: false,
+            websocket_txs: None,
+            notifications: Default::default(),
+            write_throttle: Default::default(),
+            class_stats: false,
+            event_retention: None,
+            disk_degraded: tokio::sync::watch::channel(false).1,
+            gateway_data_verification: Default::default(),
+        };
+        let (tx, _rx) = tokio::sync::watch::channel(Default::default());
+        consumer(event_rx, context, tx).await.unwrap();
+
+        assert_eq!(*rx.borrow(), PendingData::default());
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn blocks_are_not_skipped_after_a_reorg() {
         // A bug caused reorg'd block numbers to be skipped. This
@@ -1412,6 +1678,11 @@ mod tests {
             verify_tree_hashes: false,
             websocket_txs: None,
             notifications: Default::default(),
+            write_throttle: Default::default(),
+            class_stats: false,
+            event_retention: None,
+            disk_degraded: tokio::sync::watch::channel(false).1,
+            gateway_data_verification: Default::default(),
         };
 
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
@@ -1465,6 +1736,11 @@ mod tests {
             verify_tree_hashes: false,
             websocket_txs: None,
             notifications: Default::default(),
+            write_throttle: Default::default(),
+            class_stats: false,
+            event_retention: None,
+            disk_degraded: tokio::sync::watch::channel(false).1,
+            gateway_data_verification: Default::default(),
         };
 
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
@@ -1507,6 +1783,11 @@ mod tests {
             verify_tree_hashes: false,
             websocket_txs: None,
             notifications: Default::default(),
+            write_throttle: Default::default(),
+            class_stats: false,
+            event_retention: None,
+            disk_degraded: tokio::sync::watch::channel(false).1,
+            gateway_data_verification: Default::default(),
         };
 
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
@@ -1552,6 +1833,11 @@ mod tests {
             verify_tree_hashes: false,
             websocket_txs: None,
             notifications: Default::default(),
+            write_throttle: Default::default(),
+            class_stats: false,
+            event_retention: None,
+            disk_degraded: tokio::sync::watch::channel(false).1,
+            gateway_data_verification: Default::default(),
         };
 
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
@@ -1600,6 +1886,11 @@ mod tests {
             verify_tree_hashes: false,
             websocket_txs: None,
             notifications: Default::default(),
+            write_throttle: Default::default(),
+            class_stats: false,
+            event_retention: None,
+            disk_degraded: tokio::sync::watch::channel(false).1,
+            gateway_data_verification: Default::default(),
         };
 
         let (tx, _rx) = tokio::sync::watch::channel(Default::default());
@@ -1,6 +1,9 @@
 use anyhow::Context;
 use pathfinder_common::{ClassHash, SierraHash};
 use starknet_gateway_client::GatewayApi;
+use starknet_gateway_types::class_hash::{compute_class_hash, ComputedClassHash};
+
+use super::verification::{self, Check, ClassHashMismatch};
 
 pub enum DownloadedClass {
     Cairo {
@@ -14,44 +17,40 @@ pub enum DownloadedClass {
     },
 }
 
+/// Number of times a class definition is downloaded before a mismatch between
+/// its computed and expected class hash is treated as an error.
+const DOWNLOAD_ATTEMPTS: usize = 3;
+
+/// Downloads the class definition and verifies that it hashes to `class_hash`,
+/// so that corrupted definitions served by the gateway are never persisted.
 pub async fn download_class<SequencerClient: GatewayApi>(
     sequencer: &SequencerClient,
     class_hash: ClassHash,
     fetch_casm_from_fgw: bool,
 ) -> Result<DownloadedClass, anyhow::Error> {
-    use starknet_gateway_types::class_hash::compute_class_hash;
-
-    let definition = sequencer
-        .pending_class_by_hash(class_hash)
-        .await
-        .with_context(|| format!("Downloading class {}", class_hash.0))?
-        .to_vec();
-
-    let (tx, rx) = tokio::sync::oneshot::channel();
-    rayon::spawn(move || {
-        let computed_hash = compute_class_hash(&definition).context("Computing class hash");
-        let _ = tx.send((computed_hash, definition));
-    });
-    let (hash, definition) = rx.await.context("Panic on rayon thread")?;
-    let hash = hash?;
-
-    use starknet_gateway_types::class_hash::ComputedClassHash;
-    match hash {
-        ComputedClassHash::Cairo(hash) => {
-            if class_hash != hash {
-                tracing::warn!(expected=%class_hash, computed=%hash, "Cairo 0 class hash mismatch");
-            }
+    let mut attempt = 1;
+    let (definition, hash) = loop {
+        let (definition, hash) = download_definition(sequencer, class_hash).await?;
+        let matches = hash.hash() == class_hash;
+        verification::record(Check::ClassHash, matches);
+        if matches {
+            break (definition, hash);
+        }
 
-            Ok(DownloadedClass::Cairo { definition, hash })
+        if attempt == DOWNLOAD_ATTEMPTS {
+            return Err(anyhow::Error::new(ClassHashMismatch {
+                expected: class_hash,
+                computed: hash.hash(),
+            })
+            .context(format!("Downloaded {DOWNLOAD_ATTEMPTS} times")));
         }
-        starknet_gateway_types::class_hash::ComputedClassHash::Sierra(hash) => {
-            anyhow::ensure!(
-                class_hash == hash,
-                "Class hash mismatch, {} instead of {}",
-                hash,
-                class_hash.0
-            );
+        tracing::warn!(expected=%class_hash, computed=%hash.hash(), %attempt, "Class hash mismatch, downloading class again");
+        attempt += 1;
+    };
 
+    match hash {
+        ComputedClassHash::Cairo(hash) => Ok(DownloadedClass::Cairo { definition, hash }),
+        ComputedClassHash::Sierra(hash) => {
             // FIXME(integration reset): work-around for integration containing Sierra
             // classes that are incompatible with production compiler. This will
             // get "fixed" in the future by resetting integration to remove
@@ -104,3 +103,76 @@ pub async fn download_class<SequencerClient: GatewayApi>(
         }
     }
 }
+
+/// Downloads a class definition and computes its class hash.
+async fn download_definition<SequencerClient: GatewayApi>(
+    sequencer: &SequencerClient,
+    class_hash: ClassHash,
+) -> anyhow::Result<(Vec<u8>, ComputedClassHash)> {
+    let definition = sequencer
+        .pending_class_by_hash(class_hash)
+        .await
+        .with_context(|| format!("Downloading class {}", class_hash.0))?
+        .to_vec();
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    rayon::spawn(move || {
+        let computed_hash = compute_class_hash(&definition).context("Computing class hash");
+        let _ = tx.send((computed_hash, definition));
+    });
+    let (hash, definition) = rx.await.context("Panic on rayon thread")?;
+
+    Ok((definition, hash?))
+}
+
+#[cfg(test)]
+mod tests {
+    use starknet_gateway_client::MockGatewayApi;
+    use starknet_gateway_test_fixtures::class_definitions::{
+        CONTRACT_DEFINITION,
+        CONTRACT_DEFINITION_CLASS_HASH,
+        DUMMY_ACCOUNT,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn corrupted_definition_is_downloaded_again() {
+        let mut sequencer = MockGatewayApi::new();
+        let mut seq = mockall::Sequence::new();
+        sequencer
+            .expect_pending_class_by_hash()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_| Ok(bytes::Bytes::from_static(DUMMY_ACCOUNT)));
+        sequencer
+            .expect_pending_class_by_hash()
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_| Ok(bytes::Bytes::from_static(CONTRACT_DEFINITION)));
+
+        let class = download_class(&sequencer, CONTRACT_DEFINITION_CLASS_HASH, false)
+            .await
+            .unwrap();
+
+        let DownloadedClass::Cairo { definition, hash } = class else {
+            panic!("Expected a Cairo class");
+        };
+        assert_eq!(hash, CONTRACT_DEFINITION_CLASS_HASH);
+        assert_eq!(definition, CONTRACT_DEFINITION);
+    }
+
+    #[tokio::test]
+    async fn persistent_mismatch_is_an_error() {
+        let mut sequencer = MockGatewayApi::new();
+        sequencer
+            .expect_pending_class_by_hash()
+            .times(DOWNLOAD_ATTEMPTS)
+            .returning(|_| Ok(bytes::Bytes::from_static(DUMMY_ACCOUNT)));
+
+        let error = download_class(&sequencer, CONTRACT_DEFINITION_CLASS_HASH, false)
+            .await
+            .unwrap_err();
+        assert!(error.downcast_ref::<ClassHashMismatch>().is_some());
+    }
+}
@@ -0,0 +1,244 @@
+//! Tracks the latest block using both the feeder gateway and the new block
+//! announcements of the p2p network.
+//!
+//! The gateway is polled as usual. In addition, a block announced by a peer is
+//! looked up on the gateway right away instead of at the next poll, so the
+//! latest block advances as soon as either source learns about it. An
+//! announced block only becomes the latest block once the gateway returns the
+//! same hash for it. The gateway remains the source of truth: announcements it
+//! disagrees with are dropped, and a gateway head below an announced one is
+//! only accepted once the announced block is gone from the gateway.
+
+use std::time::Duration;
+
+use pathfinder_common::{BlockHash, BlockId, BlockNumber};
+use starknet_gateway_client::GatewayApi;
+use starknet_gateway_types::error::{KnownStarknetErrorCode, SequencerError};
+use tokio::sync::watch;
+
+/// Delay between lookups of an announced block the gateway does not serve yet.
+const LOOKUP_RETRY_DELAY: Duration = Duration::from_millis(200);
+/// Number of lookups of an announced block before giving up on it. The next
+/// gateway poll picks it up in that case.
+const LOOKUP_ATTEMPTS: usize = 10;
+
+pub type Announcements = watch::Receiver<Option<(BlockNumber, BlockHash)>>;
+
+#[derive(Debug, PartialEq)]
+enum Lookup {
+    Match,
+    Mismatch(BlockHash),
+    NotFound,
+}
+
+/// Like [poll_latest](super::l2::poll_latest), but also advances the latest
+/// block when an announced block is confirmed by the gateway.
+pub async fn track_latest(
+    gateway: impl GatewayApi,
+    interval: Duration,
+    mut announcements: Announcements,
+    sender: watch::Sender<(BlockNumber, BlockHash)>,
+) {
+    let mut interval = tokio::time::interval(interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    // The latest block if it was taken from an announcement the gateway has not
+    // caught up with yet.
+    let mut announced: Option<(BlockNumber, BlockHash)> = None;
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let Ok(latest) = gateway
+                    .block_header(BlockId::Latest)
+                    .await
+                    .inspect_err(|e| tracing::debug!(error=%e, "Error requesting latest block ID"))
+                else {
+                    continue;
+                };
+
+                if let Some(head) = announced {
+                    if latest.0 < head.0 {
+                        // Gateway replicas can lag behind each other, so only give up on the
+                        // announced block once the gateway no longer knows it.
+                        match lookup(&gateway, head).await {
+                            Lookup::Match => continue,
+                            Lookup::Mismatch(_) | Lookup::NotFound => {
+                                tracing::info!(
+                                    announced=%head.0, gateway=%latest.0,
+                                    "Announced block is no longer on the gateway"
+                                );
+                            }
+                        }
+                    }
+                    announced = None;
+                }
+
+                if latest.0 > sender.borrow().0 {
+                    metrics::counter!("head_race_wins_total", 1, "source" => "gateway");
+                }
+                if sender.send(latest).is_err() {
+                    tracing::debug!("Channel closed, exiting");
+                    break;
+                }
+            }
+            changed = announcements.changed() => {
+                if changed.is_err() {
+                    tracing::debug!("Announcement channel closed, polling gateway only");
+                    return super::l2::poll_latest(gateway, interval.period(), sender).await;
+                }
+
+                let Some(head) = *announcements.borrow_and_update() else {
+                    continue;
+                };
+                if head.0 <= sender.borrow().0 {
+                    continue;
+                }
+
+                match lookup_with_retries(&gateway, head).await {
+                    Lookup::Match => {
+                        tracing::trace!(number=%head.0, "Announced block confirmed by gateway");
+                        metrics::counter!("head_race_wins_total", 1, "source" => "p2p");
+                        announced = Some(head);
+                        if sender.send(head).is_err() {
+                            tracing::debug!("Channel closed, exiting");
+                            break;
+                        }
+                    }
+                    Lookup::Mismatch(gateway_hash) => {
+                        tracing::warn!(
+                            number=%head.0, announced=%head.1, gateway=%gateway_hash,
+                            "Announced block hash does not match the gateway"
+                        );
+                        metrics::counter!("head_race_mismatches_total", 1);
+                    }
+                    Lookup::NotFound => {
+                        tracing::debug!(number=%head.0, "Announced block not found on gateway");
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn lookup_with_retries(gateway: &impl GatewayApi, head: (BlockNumber, BlockHash)) -> Lookup {
+    for _ in 1..LOOKUP_ATTEMPTS {
+        match lookup(gateway, head).await {
+            Lookup::NotFound => tokio::time::sleep(LOOKUP_RETRY_DELAY).await,
+            found => return found,
+        }
+    }
+
+    lookup(gateway, head).await
+}
+
+async fn lookup(gateway: &impl GatewayApi, (number, hash): (BlockNumber, BlockHash)) -> Lookup {
+    match gateway.block_header(number.into()).await {
+        Ok((_, gateway_hash)) if gateway_hash == hash => Lookup::Match,
+        Ok((_, gateway_hash)) => Lookup::Mismatch(gateway_hash),
+        Err(SequencerError::StarknetError(err))
+            if err.code == KnownStarknetErrorCode::BlockNotFound.into() =>
+        {
+            Lookup::NotFound
+        }
+        Err(error) => {
+            tracing::debug!(%number, %error, "Error looking up announced block");
+            Lookup::NotFound
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use starknet_gateway_client::MockGatewayApi;
+    use starknet_gateway_types::error::StarknetError;
+
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_secs(3600);
+
+    fn expect_block_header(
+        mock: &mut MockGatewayApi,
+        block: BlockId,
+        result: fn() -> Result<(BlockNumber, BlockHash), SequencerError>,
+    ) {
+        mock.expect_block_header()
+            .with(mockall::predicate::eq(block))
+            .returning(move |_| result());
+    }
+
+    async fn wait_for_head(
+        latest: &mut watch::Receiver<(BlockNumber, BlockHash)>,
+        head: (BlockNumber, BlockHash),
+    ) {
+        tokio::time::timeout(Duration::from_secs(5), latest.wait_for(|l| *l == head))
+            .await
+            .expect("Head was not updated")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn confirmed_announcement_advances_head_before_next_poll() {
+        let mut mock = MockGatewayApi::new();
+        expect_block_header(&mut mock, BlockId::Latest, || {
+            Ok((BlockNumber::new_or_panic(10), block_hash!("0x10")))
+        });
+        expect_block_header(&mut mock, BlockNumber::new_or_panic(11).into(), || {
+            Ok((BlockNumber::new_or_panic(11), block_hash!("0x11")))
+        });
+        expect_block_header(&mut mock, BlockNumber::new_or_panic(12).into(), || {
+            Ok((BlockNumber::new_or_panic(12), block_hash!("0x12")))
+        });
+
+        let (announce, announcements) = watch::channel(None);
+        let (sender, mut latest) = watch::channel(Default::default());
+        let _handle = tokio::spawn(track_latest(mock, INTERVAL, announcements, sender));
+
+        let polled = (BlockNumber::new_or_panic(10), block_hash!("0x10"));
+        wait_for_head(&mut latest, polled).await;
+
+        // The gateway disagrees, the announcement is dropped.
+        announce
+            .send(Some((BlockNumber::new_or_panic(11), block_hash!("0xbad"))))
+            .unwrap();
+        // Stale announcements are ignored.
+        announce
+            .send(Some((BlockNumber::new_or_panic(9), block_hash!("0x9"))))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(*latest.borrow(), polled);
+
+        let announced = (BlockNumber::new_or_panic(12), block_hash!("0x12"));
+        announce.send(Some(announced)).unwrap();
+        wait_for_head(&mut latest, announced).await;
+    }
+
+    #[tokio::test]
+    async fn announced_block_unknown_to_gateway_is_dropped() {
+        let mut mock = MockGatewayApi::new();
+        expect_block_header(&mut mock, BlockId::Latest, || {
+            Ok((BlockNumber::new_or_panic(10), block_hash!("0x10")))
+        });
+        expect_block_header(&mut mock, BlockNumber::new_or_panic(11).into(), || {
+            Err(SequencerError::StarknetError(StarknetError {
+                code: KnownStarknetErrorCode::BlockNotFound.into(),
+                message: String::new(),
+            }))
+        });
+
+        let head = (BlockNumber::new_or_panic(11), block_hash!("0x11"));
+        assert_eq!(lookup(&mock, head).await, Lookup::NotFound);
+
+        let (announce, announcements) = watch::channel(None);
+        let (sender, mut latest) = watch::channel(Default::default());
+        let _handle = tokio::spawn(track_latest(mock, INTERVAL, announcements, sender));
+
+        let polled = (BlockNumber::new_or_panic(10), block_hash!("0x10"));
+        wait_for_head(&mut latest, polled).await;
+
+        announce.send(Some(head)).unwrap();
+        tokio::time::sleep(LOOKUP_RETRY_DELAY * LOOKUP_ATTEMPTS as u32 + LOOKUP_RETRY_DELAY).await;
+        assert_eq!(*latest.borrow(), polled);
+    }
+}
@@ -0,0 +1,310 @@
+//! Trusted import of blocks pushed by a sequencer.
+//!
+//! Appchain deployments may hand blocks to the node directly instead of having
+//! it poll a feeder gateway. A [Bundle] carries everything needed to apply a
+//! block: the block and its state update in the feeder gateway's format, the
+//! sequencer's signature of the block hash and the definitions of the classes
+//! the block introduces.
+//!
+//! Bundles are applied without consulting the gateway, but are verified just
+//! like synced blocks: the signature has to match the sequencer's public key,
+//! the block hash and commitments have to match the block's contents and the
+//! block has to extend the current head.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Context;
+use pathfinder_common::prelude::*;
+use pathfinder_common::state_update::ContractClassUpdate;
+use pathfinder_common::{Chain, PublicKey, ReceiptCommitment, StateDiffCommitment};
+use pathfinder_rpc::Notifications;
+use pathfinder_storage::{Connection, Storage, TransactionBehavior};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use starknet_gateway_types::class_hash::{compute_class_hash, ComputedClassHash};
+use starknet_gateway_types::reply::{self, BlockSignature};
+
+use super::l2::{self, BlockValidationMode, TransactionHashVerification};
+use super::verification::GatewayDataVerification;
+
+/// A block as pushed by the sequencer.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bundle {
+    pub block: reply::Block,
+    pub state_update: reply::StateUpdate,
+    pub signature: BlockSignature,
+    /// Definitions of the classes declared or deployed by the block. Classes
+    /// the node already knows can be left out.
+    #[serde(default)]
+    pub classes: Vec<Box<RawValue>>,
+}
+
+pub struct ImportContext {
+    pub storage: Storage,
+    pub chain: Chain,
+    pub chain_id: ChainId,
+    pub sequencer_public_key: PublicKey,
+    pub verify_tree_hashes: bool,
+}
+
+/// Verifies the bundle and applies its block on top of the current head.
+/// Returns the number of the imported block.
+pub async fn import(context: &ImportContext, bundle: Bundle) -> anyhow::Result<BlockNumber> {
+    let Bundle {
+        block,
+        state_update,
+        signature,
+        classes,
+    } = bundle;
+    let state_update = StateUpdate::from(state_update);
+    let block_number = block.block_number;
+
+    let mut connection = context
+        .storage
+        .connection()
+        .context("Creating database connection")?;
+
+    let (transaction_commitment, event_commitment, receipt_commitment, state_diff_commitment) =
+        tokio::task::block_in_place(|| {
+            verify(&mut connection, context, &block, &state_update, &signature)
+        })?;
+
+    tokio::task::block_in_place(|| insert_classes(&mut connection, &state_update, classes))
+        .with_context(|| format!("Inserting classes of block {block_number}"))?;
+
+    super::l2_update(
+        &mut connection,
+        block,
+        transaction_commitment,
+        receipt_commitment,
+        event_commitment,
+        state_update,
+        signature.signature(),
+        state_diff_commitment,
+        context.verify_tree_hashes,
+        false,
+        None,
+        context.storage.clone(),
+        &mut None,
+        &mut Notifications::default(),
+    )
+    .await
+    .with_context(|| format!("Update L2 state to {block_number}"))?;
+
+    Ok(block_number)
+}
+
+fn verify(
+    connection: &mut Connection,
+    context: &ImportContext,
+    block: &reply::Block,
+    state_update: &StateUpdate,
+    signature: &BlockSignature,
+) -> anyhow::Result<(
+    TransactionCommitment,
+    EventCommitment,
+    ReceiptCommitment,
+    StateDiffCommitment,
+)> {
+    let head = connection
+        .transaction()
+        .context("Creating database transaction")?
+        .block_id(pathfinder_storage::BlockId::Latest)
+        .context("Querying latest block")?;
+    let (expected_number, expected_parent) = match head {
+        Some((number, hash)) => (number + 1, hash),
+        None => (BlockNumber::GENESIS, BlockHash::ZERO),
+    };
+    anyhow::ensure!(
+        block.block_number == expected_number,
+        "Block {} does not extend the head, expected block {expected_number}",
+        block.block_number
+    );
+    anyhow::ensure!(
+        block.parent_block_hash == expected_parent,
+        "Parent hash {} of block {} does not match the head's hash {}",
+        block.parent_block_hash,
+        block.block_number,
+        expected_parent
+    );
+
+    anyhow::ensure!(
+        signature.block_hash == block.block_hash,
+        "Signature is for block hash {} instead of {}",
+        signature.block_hash,
+        block.block_hash
+    );
+    l2::verify_signature(
+        block.block_hash,
+        signature,
+        context.sequencer_public_key,
+        BlockValidationMode::Strict,
+    )
+    .context("Verifying block signature")?;
+
+    l2::verify_block_and_state_update(
+        block,
+        state_update,
+        context.chain,
+        context.chain_id,
+        BlockValidationMode::Strict,
+        TransactionHashVerification::Enforce,
+        GatewayDataVerification::Strict,
+    )
+    .context("Verifying block contents")
+}
+
+/// Inserts the definitions of the classes introduced by the block which are
+/// not in the database yet.
+fn insert_classes(
+    connection: &mut Connection,
+    state_update: &StateUpdate,
+    definitions: Vec<Box<RawValue>>,
+) -> anyhow::Result<()> {
+    let new_classes = state_update
+        .contract_updates
+        .values()
+        .filter_map(|update| match update.class {
+            Some(ContractClassUpdate::Deploy(hash)) => Some(hash),
+            _ => None,
+        })
+        .chain(state_update.declared_cairo_classes.iter().copied())
+        .chain(
+            state_update
+                .declared_sierra_classes
+                .keys()
+                .map(|hash| ClassHash(hash.0)),
+        )
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    if new_classes.is_empty() {
+        return Ok(());
+    }
+
+    let mut definitions = definitions
+        .into_iter()
+        .map(|definition| {
+            let definition = definition.get().as_bytes().to_vec();
+            let hash = compute_class_hash(&definition).context("Computing class hash")?;
+            Ok((hash.hash(), (definition, hash)))
+        })
+        .collect::<anyhow::Result<HashMap<_, _>>>()?;
+
+    let transaction = connection
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .context("Creating database transaction")?;
+    let exists = transaction
+        .class_definitions_exist(&new_classes)
+        .context("Querying class existence in database")?;
+
+    for (class_hash, _) in new_classes
+        .into_iter()
+        .zip(exists)
+        .filter(|(_, exists)| !exists)
+    {
+        let (definition, hash) = definitions
+            .remove(&class_hash)
+            .with_context(|| format!("Definition of class {class_hash} is missing"))?;
+
+        match hash {
+            ComputedClassHash::Cairo(hash) => transaction
+                .insert_cairo_class(hash, &definition)
+                .context("Inserting cairo class")?,
+            ComputedClassHash::Sierra(hash) => {
+                let sierra_hash = SierraHash(hash.0);
+                let casm_hash = state_update
+                    .declared_sierra_classes
+                    .get(&sierra_hash)
+                    .with_context(|| format!("Sierra class {sierra_hash} is not declared"))?;
+                let casm_definition = pathfinder_compiler::compile_to_casm(&definition)
+                    .with_context(|| format!("Compiling Sierra class {sierra_hash}"))?;
+                transaction
+                    .insert_sierra_class(&sierra_hash, &definition, casm_hash, &casm_definition)
+                    .context("Inserting sierra class")?;
+            }
+        }
+    }
+
+    transaction
+        .commit()
+        .context("Committing database transaction")
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_storage::StorageBuilder;
+    use starknet_gateway_types::reply::state_update::StateDiff;
+
+    use super::*;
+
+    fn context() -> ImportContext {
+        ImportContext {
+            storage: StorageBuilder::in_memory().unwrap(),
+            chain: Chain::Custom,
+            chain_id: ChainId::SEPOLIA_TESTNET,
+            sequencer_public_key: PublicKey::ZERO,
+            verify_tree_hashes: true,
+        }
+    }
+
+    fn bundle(block_number: BlockNumber, block_hash: BlockHash) -> Bundle {
+        Bundle {
+            block: reply::Block {
+                block_number,
+                block_hash,
+                ..Default::default()
+            },
+            state_update: reply::StateUpdate {
+                block_hash,
+                new_root: StateCommitment::ZERO,
+                old_root: StateCommitment::ZERO,
+                state_diff: StateDiff::default(),
+            },
+            signature: BlockSignature {
+                block_hash,
+                signature: [
+                    block_commitment_signature_elem!("0x1"),
+                    block_commitment_signature_elem!("0x2"),
+                ],
+            },
+            classes: vec![],
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn block_must_extend_head() {
+        let context = context();
+
+        let error = import(
+            &context,
+            bundle(BlockNumber::new_or_panic(1), block_hash!("0x1")),
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            error.to_string().contains("does not extend the head"),
+            "{error}"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn signature_is_verified() {
+        let context = context();
+
+        let error = import(&context, bundle(BlockNumber::GENESIS, block_hash!("0x1")))
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Verifying block signature");
+
+        let mut connection = context.storage.connection().unwrap();
+        let head = connection
+            .transaction()
+            .unwrap()
+            .block_id(pathfinder_storage::BlockId::Latest)
+            .unwrap();
+        assert_eq!(head, None);
+    }
+}
@@ -1,7 +1,9 @@
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::num::NonZeroUsize;
 use std::time::Duration;
 
 use anyhow::{anyhow, Context};
+use futures::stream::FuturesUnordered;
 use futures::{StreamExt, TryStreamExt};
 use pathfinder_common::state_update::{ContractClassUpdate, StateUpdateData};
 use pathfinder_common::{
@@ -30,14 +32,20 @@ use tokio::sync::mpsc;
 use tracing::Instrument;
 
 use crate::state::block_hash::{
-    calculate_event_commitment,
     calculate_receipt_commitment,
-    calculate_transaction_commitment,
     verify_block_hash,
     BlockHeaderData,
+    HashSchemes,
 };
 use crate::state::sync::class::{download_class, DownloadedClass};
-use crate::state::sync::SyncEvent;
+use crate::state::sync::verification::{
+    self,
+    Check,
+    ClassHashMismatch,
+    GatewayDataVerification,
+    VerificationFailure,
+};
+use crate::state::sync::{pipeline, SyncEvent};
 
 #[derive(Default, Debug, Clone, Copy)]
 pub struct Timings {
@@ -105,9 +113,15 @@ pub struct L2SyncContext<GatewayClient> {
     pub chain: Chain,
     pub chain_id: ChainId,
     pub block_validation_mode: BlockValidationMode,
+    pub transaction_hash_verification: TransactionHashVerification,
+    pub gateway_data_verification: GatewayDataVerification,
     pub storage: Storage,
     pub sequencer_public_key: PublicKey,
     pub fetch_concurrency: std::num::NonZeroUsize,
+    /// How many classes of a block to download at the same time.
+    pub class_fetch_concurrency: std::num::NonZeroUsize,
+    /// How many blocks to verify at the same time while catching up.
+    pub verification_concurrency: std::num::NonZeroUsize,
     pub fetch_casm_from_fgw: bool,
 }
 
@@ -137,9 +151,13 @@ where
         chain,
         chain_id,
         block_validation_mode,
+        transaction_hash_verification,
+        gateway_data_verification,
         storage,
         sequencer_public_key,
         fetch_concurrency: _,
+        class_fetch_concurrency,
+        verification_concurrency: _,
         fetch_casm_from_fgw,
     } = context;
 
@@ -173,6 +191,8 @@ where
                 head_meta.map(|h| h.1),
                 &sequencer,
                 block_validation_mode,
+                transaction_hash_verification,
+                gateway_data_verification,
             )
             .await?
             {
@@ -199,6 +219,8 @@ where
                             &tx_event,
                             &sequencer,
                             block_validation_mode,
+                            transaction_hash_verification,
+                            gateway_data_verification,
                             &blocks,
                         )
                         .await
@@ -228,6 +250,8 @@ where
                     &tx_event,
                     &sequencer,
                     block_validation_mode,
+                    transaction_hash_verification,
+                    gateway_data_verification,
                     &blocks,
                 )
                 .await
@@ -249,8 +273,13 @@ where
             &sequencer,
             storage.clone(),
             fetch_casm_from_fgw,
+            class_fetch_concurrency,
         )
         .await
+        .map_err(|error| match error.downcast_ref::<ClassHashMismatch>() {
+            Some(_) => VerificationFailure::new(Check::ClassHash, next, block.block_hash, &error),
+            None => error,
+        })
         .with_context(|| format!("Handling newly declared classes for block {next:?}"))?;
         emit_events_for_downloaded_classes(
             &tx_event,
@@ -309,7 +338,16 @@ where
                 let (verify_result, signature, state_update) =
                     rx.await.context("Panic on rayon thread")?;
 
+                verification::record(Check::Signature, verify_result.is_ok());
                 if let Err(error) = verify_result {
+                    if gateway_data_verification.is_strict() {
+                        return Err(VerificationFailure::new(
+                            Check::Signature,
+                            block.block_number,
+                            block.block_hash,
+                            error,
+                        ));
+                    }
                     tracing::warn!(%error, block_number=%block.block_number, "Block commitment signature mismatch");
                 }
                 (signature, state_update)
@@ -383,12 +421,72 @@ pub async fn poll_latest(
 /// can show up in `replaced_classes`. This is caused by DECLARE v0 transactions
 /// that were _failing_ but the sequencer has still added the class to its list
 /// of known classes...
+///
+/// At most `concurrency` classes are downloaded at the same time.
 pub async fn download_new_classes(
     state_update: &StateUpdate,
     sequencer: &impl GatewayApi,
     storage: Storage,
     fetch_casm_from_fgw: bool,
+    concurrency: NonZeroUsize,
 ) -> Result<Vec<DownloadedClass>, anyhow::Error> {
+    let require_downloading = missing_classes(state_update, storage).await?;
+
+    let downloaded_classes = download_classes(
+        require_downloading,
+        sequencer,
+        fetch_casm_from_fgw,
+        concurrency,
+    )
+    .try_collect()
+    .await?;
+
+    Ok(downloaded_classes)
+}
+
+/// Same as [download_new_classes], but a failed download does not discard the
+/// classes which were downloaded successfully.
+///
+/// Used to fetch the classes of the pending block ahead of time, so that the
+/// block does not have to wait for them once it is final even if the pending
+/// data itself is incomplete.
+pub async fn prefetch_new_classes(
+    state_update: &StateUpdate,
+    sequencer: &impl GatewayApi,
+    storage: Storage,
+    fetch_casm_from_fgw: bool,
+    concurrency: NonZeroUsize,
+) -> (Vec<DownloadedClass>, anyhow::Result<()>) {
+    let require_downloading = match missing_classes(state_update, storage).await {
+        Ok(classes) => classes,
+        Err(error) => return (vec![], Err(error)),
+    };
+
+    let mut downloaded_classes = Vec::with_capacity(require_downloading.len());
+    let mut result = Ok(());
+    let mut stream = download_classes(
+        require_downloading,
+        sequencer,
+        fetch_casm_from_fgw,
+        concurrency,
+    );
+    while let Some(class) = stream.next().await {
+        match class {
+            Ok(class) => downloaded_classes.push(class),
+            Err(error) if result.is_ok() => result = Err(error),
+            Err(error) => tracing::debug!(reason=?error, "Failed to download class"),
+        }
+    }
+
+    (downloaded_classes, result)
+}
+
+/// Returns the classes new in the state update which are not yet in the
+/// database.
+async fn missing_classes(
+    state_update: &StateUpdate,
+    storage: Storage,
+) -> anyhow::Result<HashSet<ClassHash>> {
     let deployed_classes = state_update
         .contract_updates
         .iter()
@@ -412,10 +510,10 @@ pub async fn download_new_classes(
         .collect::<Vec<_>>();
 
     if new_classes.is_empty() {
-        return Ok(vec![]);
+        return Ok(HashSet::new());
     }
 
-    let require_downloading = util::task::spawn_blocking(move |_| {
+    util::task::spawn_blocking(move |_| {
         let mut db_conn = storage
             .connection()
             .context("Creating database connection")?;
@@ -437,9 +535,17 @@ pub async fn download_new_classes(
     })
     .await
     .context("Joining database task")?
-    .context("Querying database for missing classes")?;
+    .context("Querying database for missing classes")
+}
 
-    let futures = require_downloading.into_iter().map(|class_hash| {
+/// Downloads the classes, at most `concurrency` at the same time.
+fn download_classes<'a>(
+    classes: HashSet<ClassHash>,
+    sequencer: &'a impl GatewayApi,
+    fetch_casm_from_fgw: bool,
+    concurrency: NonZeroUsize,
+) -> impl futures::Stream<Item = anyhow::Result<DownloadedClass>> + 'a {
+    let futures = classes.into_iter().map(move |class_hash| {
         async move {
             download_class(sequencer, class_hash, fetch_casm_from_fgw)
                 .await
@@ -448,11 +554,7 @@ pub async fn download_new_classes(
         .in_current_span()
     });
 
-    let stream = futures::stream::iter(futures).buffer_unordered(4);
-
-    let downloaded_classes = stream.try_collect().await?;
-
-    Ok(downloaded_classes)
+    futures::stream::iter(futures).buffer_unordered(concurrency.get())
 }
 
 enum DownloadBlock {
@@ -475,6 +577,17 @@ pub enum BlockValidationMode {
     AllowMismatch,
 }
 
+/// How the sync handles transactions whose hash does not match the hash
+/// recomputed from their contents.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TransactionHashVerification {
+    /// Reject the block.
+    #[default]
+    Enforce,
+    /// Log the mismatch and store the block with the gateway provided hashes.
+    LogOnly,
+}
+
 async fn download_block(
     block_number: BlockNumber,
     chain: Chain,
@@ -482,8 +595,9 @@ async fn download_block(
     prev_block_hash: Option<BlockHash>,
     sequencer: &impl GatewayApi,
     mode: BlockValidationMode,
+    tx_hash_verification: TransactionHashVerification,
+    verification: GatewayDataVerification,
 ) -> anyhow::Result<DownloadBlock> {
-    use rayon::prelude::*;
     use starknet_gateway_types::error::KnownStarknetErrorCode::BlockNotFound;
 
     match sequencer.state_update_with_block(block_number).await {
@@ -495,17 +609,21 @@ async fn download_block(
             // sure these are correct first.
             let (send, recv) = tokio::sync::oneshot::channel();
             rayon::spawn(move || {
-                let result = block
-                    .transactions
-                    .par_iter()
-                    .enumerate()
-                    .try_for_each(|(i, txn)| {
-                        if !txn.verify_hash(chain_id) {
-                            anyhow::bail!("Transaction hash mismatch: block {block_number} idx {i}")
-                        };
-                        Ok(())
-                    })
-                    .map(|_| block);
+                let result = verify_transaction_hashes(
+                    block_number,
+                    &block.transactions,
+                    chain_id,
+                    tx_hash_verification,
+                )
+                .map_err(|error| {
+                    VerificationFailure::new(
+                        Check::TransactionHashes,
+                        block.block_number,
+                        block.block_hash,
+                        error,
+                    )
+                })
+                .map(|_| block);
 
                 let _ = send.send(result);
             });
@@ -534,6 +652,11 @@ async fn download_block(
             let (block, state_update, state_diff_commitment, verify_result) =
                 rx.await.context("Panic on rayon thread")?;
             let verify_result = verify_result.context("Verify block hash")?;
+            verification::record(
+                Check::BlockHash,
+                matches!(verify_result, VerifyResult::Match(_)),
+            );
+            verify_state_diff_commitment(&block, state_diff_commitment, verification)?;
 
             match (block.status, verify_result, mode) {
                 (
@@ -557,7 +680,12 @@ async fn download_block(
                     state_diff_commitment,
                 )),
                 (_, VerifyResult::Mismatch, BlockValidationMode::Strict) => {
-                    Err(anyhow!("Block hash mismatch"))
+                    Err(VerificationFailure::new(
+                        Check::BlockHash,
+                        block.block_number,
+                        block.block_hash,
+                        "Block hash mismatch",
+                    ))
                 }
                 _ => Err(anyhow!(
                     "Rejecting block as its status is {}, and only accepted blocks are allowed",
@@ -599,6 +727,27 @@ async fn download_block(
     }
 }
 
+/// A block downloaded by [bulk_sync], together with its signature.
+struct DownloadedBlock {
+    block: Block,
+    state_update: StateUpdate,
+    signature: BlockSignature,
+    timings: Timings,
+}
+
+/// A block which passed verification in [bulk_sync].
+struct VerifiedBlock {
+    downloaded: DownloadedBlock,
+    transaction_commitment: TransactionCommitment,
+    event_commitment: EventCommitment,
+    receipt_commitment: ReceiptCommitment,
+    state_diff_commitment: StateDiffCommitment,
+}
+
+/// Catches up to `tail` by running blocks through the [pipeline] stages.
+///
+/// Stops at the first block which fails any stage, leaving it to the tracking
+/// sync to continue from `head`.
 async fn bulk_sync<GatewayClient>(
     tx_event: mpsc::Sender<SyncEvent>,
     context: L2SyncContext<GatewayClient>,
@@ -614,13 +763,17 @@ where
         chain,
         chain_id,
         block_validation_mode,
+        transaction_hash_verification,
+        gateway_data_verification,
         storage,
         sequencer_public_key,
         fetch_concurrency,
+        class_fetch_concurrency,
+        verification_concurrency,
         fetch_casm_from_fgw,
     } = context;
 
-    let mut start = match head {
+    let start = match head {
         Some(head) => head.0.get() + 1,
         None => BlockNumber::GENESIS.get(),
     };
@@ -631,223 +784,296 @@ where
 
     tracing::trace!(%start, %end, "Catching up to the latest block");
 
-    let mut futures = (start..=end)
-        .map(|block_number| {
-            let block_number = BlockNumber::new_or_panic(block_number);
-
-            let _span =
-                tracing::debug_span!("download_and_verify_block_data", %block_number).entered();
-            tracing::trace!("Downloading block");
-
-            let sequencer = sequencer.clone();
-            let storage = storage.clone();
+    let downloaded = spawn_block_download(sequencer.clone(), start, end, fetch_concurrency);
 
+    let verified = pipeline::pipe(
+        downloaded,
+        "verify",
+        verification_concurrency,
+        move |downloaded: DownloadedBlock| {
+            let span = tracing::debug_span!(
+                "verify_block",
+                block_number=%downloaded.block.block_number
+            );
             async move {
-                let t_block = std::time::Instant::now();
-                let (block, state_update) = sequencer.state_update_with_block(block_number).await?;
-                let t_block = t_block.elapsed();
-
-                let t_signature = std::time::Instant::now();
-                let signature = sequencer.signature(block_number.into()).await?;
-                let t_signature = t_signature.elapsed();
-
-                let span = tracing::Span::current();
-
                 let (tx, rx) = tokio::sync::oneshot::channel();
-
                 rayon::spawn(move || {
                     let _span = span.entered();
-
-                    let t_verification = std::time::Instant::now();
-
-                    let result = verify_block_and_state_update(
-                        &block,
-                        &state_update,
+                    let _ = tx.send(verify_downloaded_block(
+                        downloaded,
                         chain,
                         chain_id,
                         block_validation_mode,
-                    )
-                    .and_then(
-                        |(
-                            transaction_commitment,
-                            event_commitment,
-                            receipt_commitment,
-                            state_diff_commitment,
-                        )| {
-                            verify_signature(
-                                block.block_hash,
-                                &signature,
-                                sequencer_public_key,
-                                BlockValidationMode::AllowMismatch,
-                            )
-                            .map_err(|err| err.into())
-                            .map(|_| {
-                                (
-                                    block,
-                                    state_update,
-                                    signature,
-                                    transaction_commitment,
-                                    event_commitment,
-                                    receipt_commitment,
-                                    state_diff_commitment,
-                                )
-                            })
-                        },
-                    );
-
-                    let t_verification = t_verification.elapsed();
-                    tracing::trace!(elapsed=?t_verification, "Block verification done");
-
-                    let _ = tx.send(result);
+                        transaction_hash_verification,
+                        gateway_data_verification,
+                        sequencer_public_key,
+                    ));
                 });
 
-                let (
-                    block,
-                    state_update,
-                    signature,
-                    transaction_commitment,
-                    event_commitment,
-                    receipt_commitment,
-                    state_diff_commitment,
-                ) = rx
-                    .await
+                rx.await
                     .expect("Panic on rayon thread while verifying block")
-                    .context("Verifying block contents")?;
+                    .context("Verifying block contents")
+            }
+        },
+    );
 
+    let mut completed = pipeline::pipe(
+        verified,
+        "download_classes",
+        fetch_concurrency,
+        move |mut verified: VerifiedBlock| {
+            let sequencer = sequencer.clone();
+            let storage = storage.clone();
+            async move {
+                let block_number = verified.downloaded.block.block_number;
                 let t_declare = std::time::Instant::now();
-                let downloaded_classes =
-                    download_new_classes(&state_update, &sequencer, storage, fetch_casm_from_fgw)
-                        .await
-                        .with_context(|| {
-                            format!("Handling newly declared classes for block {block_number:?}")
-                        })?;
-                let t_declare = t_declare.elapsed();
-
-                let timings = Timings {
-                    block_download: t_block,
-                    class_declaration: t_declare,
-                    signature_download: t_signature,
-                };
+                let downloaded_classes = download_new_classes(
+                    &verified.downloaded.state_update,
+                    &sequencer,
+                    storage,
+                    fetch_casm_from_fgw,
+                    class_fetch_concurrency,
+                )
+                .await
+                .with_context(|| {
+                    format!("Handling newly declared classes for block {block_number:?}")
+                })?;
+                verified.downloaded.timings.class_declaration = t_declare.elapsed();
 
-                Ok::<_, anyhow::Error>((
+                Ok((verified, downloaded_classes))
+            }
+        },
+    );
+
+    while let Some(result) = completed.recv().await {
+        let (verified, downloaded_classes) = match result {
+            Ok(ok) => ok,
+            Err(error) => {
+                // `head` has been updated to the last synced block so our "tracking" sync will
+                // just continue from there.
+                tracing::info!(
+                    "Error during bulk syncing blocks, falling back to normal sync: {}",
+                    error
+                );
+                return Ok(());
+            }
+        };
+        let VerifiedBlock {
+            downloaded:
+                DownloadedBlock {
                     block,
                     state_update,
                     signature,
-                    transaction_commitment,
-                    event_commitment,
-                    receipt_commitment,
-                    state_diff_commitment,
-                    downloaded_classes,
                     timings,
-                ))
-            }
-            .in_current_span()
-        })
-        .peekable();
+                },
+            transaction_commitment,
+            event_commitment,
+            receipt_commitment,
+            state_diff_commitment,
+        } = verified;
+
+        *head = Some((
+            block.block_number,
+            block.block_hash,
+            state_update.state_commitment,
+        ));
+        blocks.push(
+            block.block_number,
+            block.block_hash,
+            state_update.state_commitment,
+        );
 
+        emit_events_for_downloaded_classes(
+            &tx_event,
+            downloaded_classes,
+            &state_update.declared_sierra_classes,
+        )
+        .await?;
+
+        tx_event
+            .send(SyncEvent::Block(
+                (
+                    Box::new(block),
+                    (transaction_commitment, event_commitment, receipt_commitment),
+                ),
+                Box::new(state_update),
+                Box::new(signature.signature()),
+                Box::new(state_diff_commitment),
+                timings,
+            ))
+            .await
+            .context("Event channel closed")?;
+    }
+
+    Ok(())
+}
+
+/// Spawns the first stage of [bulk_sync], which downloads blocks `start..=end`
+/// and their signatures, up to `concurrency` blocks at a time.
+///
+/// If a download fails, the blocks preceding the failed one are still
+/// forwarded, followed by the error.
+fn spawn_block_download<GatewayClient>(
+    sequencer: GatewayClient,
+    start: u64,
+    end: u64,
+    concurrency: NonZeroUsize,
+) -> pipeline::StageReceiver<DownloadedBlock>
+where
+    GatewayClient: GatewayApi + Clone + Send + 'static,
+{
     // We want to download blocks in an unordered fashion, but still have a limit on
     // the size of the cache that is used to then sort the downloaded blocks before
-    // emitting them. (Tries need to be updated in order, hence the sorting.)
+    // forwarding them. (Tries need to be updated in order, hence the sorting.)
     //
     // The limit is needed because if we encounter problems downloading a block, at
     // some point we need to wait for it, otherwise the cache would balloon being
-    // filled with endless newer and newer blocks that we cannot emit and this would
-    // lead to oom.
+    // filled with endless newer and newer blocks that we cannot forward and this
+    // would lead to oom.
     const UNORDERED_CACHE_CAPACITY_FACTOR: usize = 32;
+    let cache_capacity = concurrency.get() * UNORDERED_CACHE_CAPACITY_FACTOR;
 
-    while futures.peek().is_some() {
-        let futures_chunk = futures
-            .by_ref()
-            .take(fetch_concurrency.get() * UNORDERED_CACHE_CAPACITY_FACTOR);
+    let (tx, rx) = mpsc::channel(pipeline::STAGE_BUFFER);
 
-        let mut stream =
-            futures::stream::iter(futures_chunk).buffer_unordered(fetch_concurrency.get());
+    util::task::spawn(
+        async move {
+            let mut numbers = (start..=end).map(BlockNumber::new_or_panic);
+            let mut next = start;
+            let mut in_flight = FuturesUnordered::new();
+            let mut cache = BTreeMap::new();
+            let mut failure: Option<(BlockNumber, anyhow::Error)> = None;
+
+            loop {
+                while failure.is_none()
+                    && in_flight.len() < concurrency.get()
+                    && cache.len() < cache_capacity
+                {
+                    let Some(block_number) = numbers.next() else {
+                        break;
+                    };
+                    let sequencer = sequencer.clone();
+                    in_flight.push(
+                        async move {
+                            let result = download_block_data(&sequencer, block_number).await;
+                            (block_number, result)
+                        }
+                        .instrument(tracing::debug_span!("download_block_data", %block_number)),
+                    );
+                }
 
-        let mut ordered_blocks = BTreeMap::new();
+                let Some((block_number, result)) = in_flight.next().await else {
+                    break;
+                };
+                match result {
+                    Ok(downloaded) => {
+                        cache.insert(block_number.get(), downloaded);
+                    }
+                    Err(error) => {
+                        if failure
+                            .as_ref()
+                            .map_or(true, |(failed, _)| block_number < *failed)
+                        {
+                            failure = Some((block_number, error));
+                        }
+                    }
+                }
 
-        while let Some(result) = stream.next().await {
-            let Ok(ok) = result else {
-                // We've hit an error, so we stop the loop and return. `head` has been updated
-                // to the last synced block so our "tracking" sync will just
-                // continue from there.
-                tracing::info!(
-                    "Error during bulk syncing blocks, falling back to normal sync: {}",
-                    result.err().unwrap()
-                );
-                return Ok(());
-            };
+                tracing::trace!(next, len = cache.len(), "Cached blocks");
 
-            ordered_blocks.insert(ok.0.block_number.get(), ok);
+                // Blocks following a failed one are never forwarded, since the failed
+                // block is missing from the cache.
+                while let Some(downloaded) = cache.remove(&next) {
+                    if tx.send(Ok(downloaded)).await.is_err() {
+                        return;
+                    }
+                    next += 1;
+                }
+            }
 
-            let keys = ordered_blocks.keys().copied().collect::<Vec<_>>();
+            if let Some((_, error)) = failure {
+                let _ = tx.send(Err(error)).await;
+            }
+        }
+        .in_current_span(),
+    );
 
-            tracing::trace!(start, len = ordered_blocks.len(), ?keys, "Cached blocks");
+    rx
+}
 
-            // Find number of elems till the first gap that we can emit right now
-            let num_to_emit = ordered_blocks
-                .keys()
-                .take_while(|block_number| {
-                    if **block_number == start {
-                        start += 1;
-                        true
-                    } else {
-                        false
-                    }
-                })
-                .count();
+async fn download_block_data(
+    sequencer: &impl GatewayApi,
+    block_number: BlockNumber,
+) -> anyhow::Result<DownloadedBlock> {
+    tracing::trace!("Downloading block");
 
-            for _ in 0..num_to_emit {
-                let (
-                    _,
-                    (
-                        block,
-                        state_update,
-                        signature,
-                        transaction_commitment,
-                        event_commitment,
-                        receipt_commitment,
-                        state_diff_commitment,
-                        downloaded_classes,
-                        timings,
-                    ),
-                ) = ordered_blocks.pop_first().expect("num_to_emit > 0");
+    let t_block = std::time::Instant::now();
+    let (block, state_update) = sequencer.state_update_with_block(block_number).await?;
+    let t_block = t_block.elapsed();
 
-                *head = Some((
-                    block.block_number,
-                    block.block_hash,
-                    state_update.state_commitment,
-                ));
-                blocks.push(
-                    block.block_number,
-                    block.block_hash,
-                    state_update.state_commitment,
-                );
+    let t_signature = std::time::Instant::now();
+    let signature = sequencer.signature(block_number.into()).await?;
+    let t_signature = t_signature.elapsed();
 
-                emit_events_for_downloaded_classes(
-                    &tx_event,
-                    downloaded_classes,
-                    &state_update.declared_sierra_classes,
-                )
-                .await?;
+    pipeline::record_duration("download_block", t_block + t_signature);
 
-                tx_event
-                    .send(SyncEvent::Block(
-                        (
-                            Box::new(block),
-                            (transaction_commitment, event_commitment, receipt_commitment),
-                        ),
-                        Box::new(state_update),
-                        Box::new(signature.signature()),
-                        Box::new(state_diff_commitment),
-                        timings,
-                    ))
-                    .await
-                    .context("Event channel closed")?;
-            }
-        }
-    }
+    Ok(DownloadedBlock {
+        block,
+        state_update,
+        signature,
+        timings: Timings {
+            block_download: t_block,
+            class_declaration: Duration::ZERO,
+            signature_download: t_signature,
+        },
+    })
+}
 
-    Ok(())
+fn verify_downloaded_block(
+    downloaded: DownloadedBlock,
+    chain: Chain,
+    chain_id: ChainId,
+    block_validation_mode: BlockValidationMode,
+    transaction_hash_verification: TransactionHashVerification,
+    gateway_data_verification: GatewayDataVerification,
+    sequencer_public_key: PublicKey,
+) -> anyhow::Result<VerifiedBlock> {
+    let t_verification = std::time::Instant::now();
+
+    let (transaction_commitment, event_commitment, receipt_commitment, state_diff_commitment) =
+        verify_block_and_state_update(
+            &downloaded.block,
+            &downloaded.state_update,
+            chain,
+            chain_id,
+            block_validation_mode,
+            transaction_hash_verification,
+            gateway_data_verification,
+        )?;
+
+    // Strict mode falls back to the tracking sync for blocks with an invalid
+    // signature, which rejects them.
+    let signature_validation_mode = if gateway_data_verification.is_strict() {
+        block_validation_mode
+    } else {
+        BlockValidationMode::AllowMismatch
+    };
+    verify_signature(
+        downloaded.block.block_hash,
+        &downloaded.signature,
+        sequencer_public_key,
+        signature_validation_mode,
+    )?;
+
+    let t_verification = t_verification.elapsed();
+    tracing::trace!(elapsed=?t_verification, "Block verification done");
+
+    Ok(VerifiedBlock {
+        downloaded,
+        transaction_commitment,
+        event_commitment,
+        receipt_commitment,
+        state_diff_commitment,
+    })
 }
 
 pub(super) async fn emit_events_for_downloaded_classes(
@@ -908,12 +1134,14 @@ pub(super) async fn emit_events_for_downloaded_classes(
     Ok(())
 }
 
-fn verify_block_and_state_update(
+pub(super) fn verify_block_and_state_update(
     block: &Block,
     state_update: &StateUpdate,
     chain: Chain,
     chain_id: ChainId,
     mode: BlockValidationMode,
+    tx_hash_verification: TransactionHashVerification,
+    verification: GatewayDataVerification,
 ) -> anyhow::Result<(
     TransactionCommitment,
     EventCommitment,
@@ -933,6 +1161,10 @@ fn verify_block_and_state_update(
         chain_id,
     )
     .context("Verify block hash")?;
+    verification::record(
+        Check::BlockHash,
+        matches!(verify_result, VerifyResult::Match(_)),
+    );
 
     let (transaction_commitment, event_commitment, receipt_commitment) =
         match (block.status, verify_result, mode) {
@@ -945,7 +1177,12 @@ fn verify_block_and_state_update(
                 BlockValidationMode::AllowMismatch,
             ) => Ok(Default::default()),
             (_, VerifyResult::Mismatch, BlockValidationMode::Strict) => {
-                Err(anyhow!("Block hash mismatch"))
+                Err(VerificationFailure::new(
+                    Check::BlockHash,
+                    block.block_number,
+                    block.block_hash,
+                    "Block hash mismatch",
+                ))
             }
             _ => Err(anyhow!(
                 "Rejecting block as its status is {}, and only accepted blocks are allowed",
@@ -954,24 +1191,25 @@ fn verify_block_and_state_update(
         }?;
 
     // Check if transaction hashes are valid
-    verify_transaction_hashes(block.block_number, &block.transactions, chain_id)
-        .context("Verify transaction hashes")?;
+    verify_transaction_hashes(
+        block.block_number,
+        &block.transactions,
+        chain_id,
+        tx_hash_verification,
+    )
+    .map_err(|error| {
+        VerificationFailure::new(
+            Check::TransactionHashes,
+            block.block_number,
+            block.block_hash,
+            error,
+        )
+    })
+    .context("Verify transaction hashes")?;
 
     // Always compute the state diff commitment from the state update.
-    // If any of the feeder gateway replies (block or signature) contain a state
-    // diff commitment, check if the value matches. If it doesn't, just log the
-    // fact.
     let computed_state_diff_commitment = state_update.compute_state_diff_commitment();
-
-    if let Some(x) = block.state_diff_commitment {
-        if x != computed_state_diff_commitment {
-            tracing::warn!(
-                "State diff commitment mismatch: computed {:x}, feeder gateway {:x}",
-                computed_state_diff_commitment.0,
-                x.0
-            );
-        }
-    }
+    verify_state_diff_commitment(block, computed_state_diff_commitment, verification)?;
 
     Ok((
         transaction_commitment,
@@ -982,26 +1220,92 @@ fn verify_block_and_state_update(
 }
 
 /// Check that transaction hashes match the actual contents.
+///
+/// Mismatches fail verification in [TransactionHashVerification::Enforce]
+/// mode and are only logged otherwise.
 fn verify_transaction_hashes(
     block_number: BlockNumber,
     transactions: &[pathfinder_common::transaction::Transaction],
     chain_id: ChainId,
+    mode: TransactionHashVerification,
 ) -> anyhow::Result<()> {
     use rayon::prelude::*;
 
-    transactions
+    let mismatches = transactions
         .par_iter()
         .enumerate()
-        .try_for_each(|(i, txn)| {
-            if !txn.verify_hash(chain_id) {
-                anyhow::bail!("Transaction hash mismatch: block {block_number} idx {i}")
-            };
+        .filter(|(_, txn)| !txn.verify_hash(chain_id))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    verification::record(Check::TransactionHashes, mismatches.is_empty());
+
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+
+    metrics::counter!(
+        "sync_transaction_hash_mismatches_total",
+        mismatches.len() as u64
+    );
+
+    match mode {
+        TransactionHashVerification::Enforce => {
+            anyhow::bail!(
+                "Transaction hash mismatch: block {block_number} idx {}",
+                mismatches[0]
+            )
+        }
+        TransactionHashVerification::LogOnly => {
+            for i in mismatches {
+                tracing::warn!(
+                    %block_number,
+                    idx=%i,
+                    hash=%transactions[i].hash,
+                    version=?transactions[i].version(),
+                    "Transaction hash mismatch"
+                );
+            }
             Ok(())
-        })
+        }
+    }
+}
+
+/// If the feeder gateway reply contains a state diff commitment, check that it
+/// matches the one computed from the state update. Mismatches are only logged
+/// unless verification is strict.
+fn verify_state_diff_commitment(
+    block: &Block,
+    computed: StateDiffCommitment,
+    verification: GatewayDataVerification,
+) -> anyhow::Result<()> {
+    let Some(reported) = block.state_diff_commitment else {
+        return Ok(());
+    };
+    let matches = reported == computed;
+    verification::record(Check::StateDiffCommitment, matches);
+    if matches {
+        return Ok(());
+    }
+
+    let reason = format!(
+        "State diff commitment mismatch: computed {:x}, feeder gateway {:x}",
+        computed.0, reported.0
+    );
+    if verification.is_strict() {
+        return Err(VerificationFailure::new(
+            Check::StateDiffCommitment,
+            block.block_number,
+            block.block_hash,
+            reason,
+        ));
+    }
+    tracing::warn!("{reason}");
+
+    Ok(())
 }
 
 /// Check block commitment signature.
-fn verify_signature(
+pub(super) fn verify_signature(
     block_hash: BlockHash,
     signature: &BlockSignature,
     sequencer_public_key: PublicKey,
@@ -1030,8 +1334,11 @@ fn verify_gateway_block_commitments_and_hash(
     let mut bhd =
         BlockHeaderData::from_gateway_block(block, state_diff_commitment, state_diff_length)?;
 
-    let computed_transaction_commitment =
-        calculate_transaction_commitment(&block.transactions, block.starknet_version)?;
+    let schemes = HashSchemes::select(chain, block.block_number, block.starknet_version);
+
+    let computed_transaction_commitment = schemes
+        .transaction_commitment
+        .calculate(&block.transactions)?;
 
     // Older blocks on mainnet don't carry a precalculated transaction commitment.
     if block.transaction_commitment == TransactionCommitment::ZERO {
@@ -1067,8 +1374,7 @@ fn verify_gateway_block_commitments_and_hash(
         .iter()
         .map(|(receipt, events)| (receipt.transaction_hash, events.as_slice()))
         .collect::<Vec<_>>();
-    let event_commitment =
-        calculate_event_commitment(&events_with_tx_hashes, block.starknet_version)?;
+    let event_commitment = schemes.event_commitment.calculate(&events_with_tx_hashes)?;
 
     // Older blocks on mainnet don't carry a precalculated event
     // commitment.
@@ -1088,28 +1394,27 @@ fn verify_gateway_block_commitments_and_hash(
             // the legacy commitments. The P2P protocol requires that all
             // commitments in block headers are the 0.13.2 variants for legacy
             // blocks.
-            let (transaction_commitment, event_commitment, receipt_commitment) = if block
-                .starknet_version
-                < StarknetVersion::V_0_13_2
-            {
-                let transaction_commitment = calculate_transaction_commitment(
-                    &block.transactions,
-                    StarknetVersion::V_0_13_2,
-                )?;
-                let event_commitment =
-                    calculate_event_commitment(&events_with_tx_hashes, StarknetVersion::V_0_13_2)?;
-                (
-                    transaction_commitment,
-                    event_commitment,
-                    computed_receipt_commitment,
-                )
-            } else {
-                (
-                    computed_transaction_commitment,
-                    event_commitment,
-                    computed_receipt_commitment,
-                )
-            };
+            let (transaction_commitment, event_commitment, receipt_commitment) =
+                if !schemes.commits_to_receipts {
+                    let schemes =
+                        HashSchemes::select(chain, block.block_number, StarknetVersion::V_0_13_2);
+                    let transaction_commitment = schemes
+                        .transaction_commitment
+                        .calculate(&block.transactions)?;
+                    let event_commitment =
+                        schemes.event_commitment.calculate(&events_with_tx_hashes)?;
+                    (
+                        transaction_commitment,
+                        event_commitment,
+                        computed_receipt_commitment,
+                    )
+                } else {
+                    (
+                        computed_transaction_commitment,
+                        event_commitment,
+                        computed_receipt_commitment,
+                    )
+                };
 
             VerifyResult::Match((transaction_commitment, event_commitment, receipt_commitment))
         }
@@ -1124,6 +1429,8 @@ async fn reorg(
     tx_event: &mpsc::Sender<SyncEvent>,
     sequencer: &impl GatewayApi,
     mode: BlockValidationMode,
+    tx_hash_verification: TransactionHashVerification,
+    verification: GatewayDataVerification,
     blocks: &BlockChain,
 ) -> anyhow::Result<Option<(BlockNumber, BlockHash, StateCommitment)>> {
     // Go back in history until we find an L2 block that does still exist.
@@ -1147,6 +1454,8 @@ async fn reorg(
             Some(previous.0),
             sequencer,
             mode,
+            tx_hash_verification,
+            verification,
         )
         .await
         .with_context(|| format!("Download block {previous_block_number} from sequencer"))?
@@ -1347,9 +1656,13 @@ mod tests {
                 chain: Chain::SepoliaTestnet,
                 chain_id: ChainId::SEPOLIA_TESTNET,
                 block_validation_mode: MODE,
+                transaction_hash_verification: Default::default(),
+                gateway_data_verification: Default::default(),
                 storage,
                 sequencer_public_key: PublicKey::ZERO,
                 fetch_concurrency: std::num::NonZeroUsize::new(1).unwrap(),
+                class_fetch_concurrency: std::num::NonZeroUsize::new(1).unwrap(),
+                verification_concurrency: std::num::NonZeroUsize::new(1).unwrap(),
                 fetch_casm_from_fgw: false,
             };
 
@@ -1379,9 +1692,13 @@ mod tests {
                 chain: Chain::SepoliaTestnet,
                 chain_id: ChainId::SEPOLIA_TESTNET,
                 block_validation_mode: MODE,
+                transaction_hash_verification: Default::default(),
+                gateway_data_verification: Default::default(),
                 storage,
                 sequencer_public_key: PublicKey::ZERO,
                 fetch_concurrency: std::num::NonZeroUsize::new(2).unwrap(),
+                class_fetch_concurrency: std::num::NonZeroUsize::new(2).unwrap(),
+                verification_concurrency: std::num::NonZeroUsize::new(2).unwrap(),
                 fetch_casm_from_fgw: false,
             };
 
@@ -1605,19 +1922,6 @@ mod tests {
                 .return_once(move |_| returned_result);
         }
 
-        fn expect_state_update_with_block_no_sequence_at_most_once(
-            mock: &mut MockGatewayApi,
-            block: BlockNumber,
-            returned_result: Result<(reply::Block, StateUpdate), SequencerError>,
-        ) {
-            use mockall::predicate::eq;
-
-            mock.expect_state_update_with_block()
-                .with(eq(block))
-                .times(..=1)
-                .return_once(move |_| returned_result);
-        }
-
         /// Convenience wrapper
         fn expect_block_header(
             mock: &mut MockGatewayApi,
@@ -1664,19 +1968,6 @@ mod tests {
                 .return_once(|_| returned_result);
         }
 
-        fn expect_signature_no_sequence_at_most_once(
-            mock: &mut MockGatewayApi,
-            block: BlockId,
-            returned_result: Result<reply::BlockSignature, SequencerError>,
-        ) {
-            use mockall::predicate::eq;
-
-            mock.expect_signature()
-                .with(eq(block))
-                .times(..=1)
-                .return_once(|_| returned_result);
-        }
-
         /// Convenience wrapper
         fn expect_class_by_hash(
             mock: &mut MockGatewayApi,
@@ -1703,17 +1994,6 @@ mod tests {
                 .return_once(|_| returned_result);
         }
 
-        fn expect_class_by_hash_no_sequence_at_most_once(
-            mock: &mut MockGatewayApi,
-            class_hash: ClassHash,
-            returned_result: Result<bytes::Bytes, SequencerError>,
-        ) {
-            mock.expect_pending_class_by_hash()
-                .withf(move |x| x == &class_hash)
-                .times(..=1)
-                .return_once(|_| returned_result);
-        }
-
         /// Convenience wrapper
         fn block_not_found() -> SequencerError {
             SequencerError::StarknetError(StarknetError {
@@ -1871,6 +2151,8 @@ mod tests {
                     chain: Chain::SepoliaTestnet,
                     chain_id: ChainId::SEPOLIA_TESTNET,
                     block_validation_mode: MODE,
+                    transaction_hash_verification: Default::default(),
+                    gateway_data_verification: Default::default(),
                     storage: StorageBuilder::in_memory_with_trie_pruning_and_pool_size(
                         pathfinder_storage::TriePruneMode::Archive,
                         NonZeroU32::new(5).unwrap(),
@@ -1878,6 +2160,8 @@ mod tests {
                     .unwrap(),
                     sequencer_public_key: PublicKey::ZERO,
                     fetch_concurrency: std::num::NonZeroUsize::new(1).unwrap(),
+                    class_fetch_concurrency: std::num::NonZeroUsize::new(1).unwrap(),
+                    verification_concurrency: std::num::NonZeroUsize::new(1).unwrap(),
                     fetch_casm_from_fgw: false,
                 };
                 let latest_track = tokio::sync::watch::channel(Default::default());
@@ -3167,19 +3451,18 @@ mod tests {
                 let (tx_event, mut rx_event) = tokio::sync::mpsc::channel(1);
                 let mut mock = MockGatewayApi::new();
 
-                // Downloading the genesis block data is racing against the failure of block 1,
-                // hence "at most once"
-                expect_state_update_with_block_no_sequence_at_most_once(
+                // The genesis block precedes the failed block, so it is still synced
+                expect_state_update_with_block_no_sequence(
                     &mut mock,
                     BLOCK0_NUMBER,
                     Ok((BLOCK0.clone(), STATE_UPDATE0.clone())),
                 );
-                expect_class_by_hash_no_sequence_at_most_once(
+                expect_class_by_hash_no_sequence(
                     &mut mock,
                     CONTRACT0_HASH,
                     Ok(CONTRACT0_DEF.clone()),
                 );
-                expect_signature_no_sequence_at_most_once(
+                expect_signature_no_sequence(
                     &mut mock,
                     BLOCK0_NUMBER.into(),
                     Ok(BLOCK0_SIGNATURE.clone()),
@@ -3194,12 +3477,19 @@ mod tests {
                 // Let's run the UUT
                 let jh = spawn_bulk_sync(tx_event, mock);
 
-                // The entire unemitted, yet cached batch is rejected
+                assert_matches!(rx_event.recv().await.unwrap(),
+                    SyncEvent::CairoClass { hash, .. } => {
+                        assert_eq!(hash, CONTRACT0_HASH);
+                });
+                assert_matches!(rx_event.recv().await.unwrap(), SyncEvent::Block((block, _), _, _, _, _) => {
+                    assert_eq!(*block, *BLOCK0);
+                });
+                // Blocks following the failed one are never emitted
                 assert!(rx_event.recv().await.is_none());
 
                 // Bulk sync should _not_ fail if the block is not found
                 let result = jh.await.unwrap();
-                assert_matches!(result, Ok(None));
+                assert_matches!(result, Ok(Some((BLOCK0_NUMBER, BLOCK0_HASH, _))));
             }
         }
     }
@@ -3282,4 +3572,109 @@ mod tests {
             assert!(uut.get(&BlockNumber::new_or_panic(3)).is_none());
         }
     }
+
+    mod transaction_hashes {
+        use pathfinder_common::macro_prelude::*;
+        use pathfinder_common::transaction::{Transaction, TransactionVariant};
+        use pathfinder_common::{BlockNumber, ChainId};
+
+        use crate::state::l2::{verify_transaction_hashes, TransactionHashVerification};
+
+        #[test]
+        fn mismatch_handling_depends_on_mode() {
+            let variant = TransactionVariant::default();
+            let valid = Transaction {
+                hash: variant.calculate_hash(ChainId::SEPOLIA_TESTNET, false),
+                variant: variant.clone(),
+            };
+            let invalid = Transaction {
+                hash: transaction_hash!("0x1"),
+                variant,
+            };
+            let transactions = [valid, invalid];
+
+            let result = verify_transaction_hashes(
+                BlockNumber::GENESIS,
+                &transactions[..1],
+                ChainId::SEPOLIA_TESTNET,
+                TransactionHashVerification::Enforce,
+            );
+            assert!(result.is_ok());
+
+            let result = verify_transaction_hashes(
+                BlockNumber::GENESIS,
+                &transactions,
+                ChainId::SEPOLIA_TESTNET,
+                TransactionHashVerification::Enforce,
+            );
+            assert!(result.is_err());
+
+            let result = verify_transaction_hashes(
+                BlockNumber::GENESIS,
+                &transactions,
+                ChainId::SEPOLIA_TESTNET,
+                TransactionHashVerification::LogOnly,
+            );
+            assert!(result.is_ok());
+        }
+    }
+
+    mod prefetch_new_classes {
+        use std::num::NonZeroUsize;
+
+        use pathfinder_common::macro_prelude::*;
+        use pathfinder_common::StateUpdate;
+        use pathfinder_storage::StorageBuilder;
+        use starknet_gateway_client::MockGatewayApi;
+        use starknet_gateway_test_fixtures::class_definitions::{
+            CONTRACT_DEFINITION,
+            CONTRACT_DEFINITION_CLASS_HASH,
+        };
+        use starknet_gateway_types::error::{
+            KnownStarknetErrorCode,
+            SequencerError,
+            StarknetError,
+        };
+
+        use crate::state::l2::prefetch_new_classes;
+        use crate::state::sync::class::DownloadedClass;
+
+        #[tokio::test]
+        async fn keeps_classes_downloaded_before_a_failure() {
+            let missing_class = class_hash!("0xdead");
+            let mut sequencer = MockGatewayApi::new();
+            sequencer
+                .expect_pending_class_by_hash()
+                .returning(move |class_hash| {
+                    if class_hash == CONTRACT_DEFINITION_CLASS_HASH {
+                        Ok(bytes::Bytes::from_static(CONTRACT_DEFINITION))
+                    } else {
+                        Err(SequencerError::StarknetError(StarknetError {
+                            code: KnownStarknetErrorCode::UndeclaredClass.into(),
+                            message: String::new(),
+                        }))
+                    }
+                });
+
+            let state_update = StateUpdate::default()
+                .with_declared_cairo_class(CONTRACT_DEFINITION_CLASS_HASH)
+                .with_declared_cairo_class(missing_class);
+
+            let (downloaded, result) = prefetch_new_classes(
+                &state_update,
+                &sequencer,
+                StorageBuilder::in_memory().unwrap(),
+                false,
+                NonZeroUsize::new(2).unwrap(),
+            )
+            .await;
+
+            result.unwrap_err();
+            assert_eq!(downloaded.len(), 1);
+            let DownloadedClass::Cairo { hash, .. } = &downloaded[0] else {
+                panic!("Expected a Cairo class");
+            };
+            assert_eq!(*hash, CONTRACT_DEFINITION_CLASS_HASH);
+        }
+    }
 }
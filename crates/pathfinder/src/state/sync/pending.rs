@@ -1,6 +1,9 @@
+use std::num::NonZeroUsize;
 use std::sync::Arc;
+use std::time::Duration;
 
 use pathfinder_common::{BlockHash, BlockNumber};
+use pathfinder_rpc::PendingData;
 use pathfinder_storage::Storage;
 use starknet_gateway_client::GatewayApi;
 use tokio::sync::watch;
@@ -18,6 +21,7 @@ pub async fn poll_pending<S: GatewayApi + Clone + Send + 'static>(
     latest: watch::Receiver<(BlockNumber, BlockHash)>,
     current: watch::Receiver<(BlockNumber, BlockHash)>,
     fetch_casm_from_fgw: bool,
+    class_fetch_concurrency: NonZeroUsize,
 ) {
     let mut prev_tx_count = 0;
     let mut prev_hash = BlockHash::default();
@@ -56,28 +60,31 @@ pub async fn poll_pending<S: GatewayApi + Clone + Send + 'static>(
         // Download, process and emit all missing classes. This can occasionally
         // fail when querying a desync'd feeder gateway which isn't aware of the
         // new pending classes. In this case, ignore the new pending data as it
-        // is incomplete.
-        match super::l2::download_new_classes(
+        // is incomplete. The classes which were downloaded are emitted anyway so
+        // that they are already stored once the block is final.
+        let (downloaded_classes, download_result) = super::l2::prefetch_new_classes(
             &state_update,
             &sequencer,
             storage.clone(),
             fetch_casm_from_fgw,
+            class_fetch_concurrency,
+        )
+        .await;
+
+        if let Err(e) = super::l2::emit_events_for_downloaded_classes(
+            &tx_event,
+            downloaded_classes,
+            &state_update.declared_sierra_classes,
         )
         .await
         {
-            Err(e) => tracing::debug!(reason=?e, "Failed to download pending classes"),
-            Ok(downloaded_classes) => {
-                if let Err(e) = super::l2::emit_events_for_downloaded_classes(
-                    &tx_event,
-                    downloaded_classes,
-                    &state_update.declared_sierra_classes,
-                )
-                .await
-                {
-                    tracing::error!(error=%e, "Event channel closed unexpectedly. Ending pending stream.");
-                    break;
-                }
+            tracing::error!(error=%e, "Event channel closed unexpectedly. Ending pending stream.");
+            break;
+        }
 
+        match download_result {
+            Err(e) => tracing::debug!(reason=?e, "Failed to download pending classes"),
+            Ok(()) => {
                 prev_tx_count = block.transactions.len();
                 prev_hash = block.parent_hash;
                 tracing::trace!("Emitting a pending update");
@@ -97,6 +104,52 @@ pub async fn poll_pending<S: GatewayApi + Clone + Send + 'static>(
     }
 }
 
+/// Clears the pending data if it is not built on top of `latest`, i.e. after a
+/// reorg orphaned its parent or once its successor has been stored.
+///
+/// RPC methods validate the parent of the pending data on every read, but
+/// subscriptions which watch the channel directly would otherwise keep seeing
+/// it until the next pending update.
+///
+/// Returns `true` if the pending data was cleared.
+pub(super) fn discard_stale(pending_data: &watch::Sender<PendingData>, latest: BlockHash) -> bool {
+    pending_data.send_if_modified(|data| {
+        if data.block.parent_hash == latest || *data == PendingData::default() {
+            return false;
+        }
+
+        tracing::debug!(
+            parent=%data.block.parent_hash, %latest,
+            "Discarding pending data with an outdated parent block"
+        );
+        *data = PendingData::default();
+        true
+    })
+}
+
+/// Periodically reports the time since the pending data was last updated as
+/// the `pending_age_seconds` metric. Discarding stale pending data does not
+/// count as an update.
+pub(super) async fn track_pending_age(mut pending_data: watch::Receiver<PendingData>) {
+    const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+    let mut updated_at = Instant::now();
+    loop {
+        metrics::gauge!("pending_age_seconds", updated_at.elapsed().as_secs_f64());
+
+        match tokio::time::timeout(REPORT_INTERVAL, pending_data.changed()).await {
+            Ok(Ok(())) => {
+                if *pending_data.borrow_and_update() != PendingData::default() {
+                    updated_at = Instant::now();
+                }
+            }
+            // The sender was dropped, sync has stopped.
+            Ok(Err(_)) => break,
+            Err(_) => {}
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::{Arc, LazyLock};
@@ -124,7 +177,7 @@ mod tests {
     };
     use tokio::sync::watch;
 
-    use super::poll_pending;
+    use super::{discard_stale, poll_pending};
     use crate::state::sync::SyncEvent;
 
     const PARENT_HASH: BlockHash = block_hash!("0x1234");
@@ -208,6 +261,7 @@ mod tests {
                 latest,
                 current,
                 false,
+                std::num::NonZeroUsize::new(1).unwrap(),
             )
             .await
         });
@@ -281,6 +335,7 @@ mod tests {
                 rx_latest,
                 rx_current,
                 false,
+                std::num::NonZeroUsize::new(1).unwrap(),
             )
             .await
         });
@@ -299,4 +354,23 @@ mod tests {
 
         assert_matches!(result2, SyncEvent::Pending(x) if *x.0 == b1 && *x.1 == *PENDING_UPDATE);
     }
+
+    #[test]
+    fn discard_stale_keeps_data_built_on_latest() {
+        let pending = pathfinder_rpc::PendingData {
+            block: Arc::new(PENDING_BLOCK.clone()),
+            state_update: Arc::new(PENDING_UPDATE.clone()),
+            number: BlockNumber::new_or_panic(2),
+        };
+        let (tx, rx) = watch::channel(pending.clone());
+
+        assert!(!discard_stale(&tx, PARENT_HASH));
+        assert_eq!(*rx.borrow(), pending);
+
+        assert!(discard_stale(&tx, block_hash!("0xdead")));
+        assert_eq!(*rx.borrow(), Default::default());
+
+        // Already empty.
+        assert!(!discard_stale(&tx, block_hash!("0xdead")));
+    }
 }
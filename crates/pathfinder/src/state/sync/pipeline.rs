@@ -0,0 +1,117 @@
+//! Stages of catching up with the feeder gateway.
+//!
+//! While far behind the chain tip, blocks flow through separate stages, i.e.
+//! downloading, verification and class download, each running as its own task
+//! with its own concurrency. Stages are connected by bounded channels, so a
+//! slow stage fills up its input channel and pauses the stages in front of it
+//! instead of letting downloaded blocks pile up in memory.
+//!
+//! Every stage forwards its items in block order and stops after forwarding
+//! the first error. The time spent on each item is recorded per stage in the
+//! `sync_stage_duration_seconds` histogram, along with the stages of storing a
+//! block: building its event filter, updating the tries and persisting it.
+
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Capacity of the channels connecting the stages.
+pub(super) const STAGE_BUFFER: usize = 8;
+
+pub(super) type StageReceiver<T> = mpsc::Receiver<anyhow::Result<T>>;
+
+pub(super) fn record_duration(stage: &'static str, duration: Duration) {
+    metrics::histogram!("sync_stage_duration_seconds", duration, "stage" => stage);
+}
+
+/// Spawns a stage which maps the items received from `input` with `f`,
+/// processing up to `concurrency` items at a time.
+pub(super) fn pipe<I, O, F, Fut>(
+    input: StageReceiver<I>,
+    stage: &'static str,
+    concurrency: NonZeroUsize,
+    f: F,
+) -> StageReceiver<O>
+where
+    I: Send + 'static,
+    O: Send + 'static,
+    F: Fn(I) -> Fut + Send + 'static,
+    Fut: Future<Output = anyhow::Result<O>> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(STAGE_BUFFER);
+
+    util::task::spawn(async move {
+        let mut outputs = ReceiverStream::new(input)
+            .map(move |input| {
+                let output = input.map(&f);
+                async move {
+                    let t = Instant::now();
+                    let output = output?.await;
+                    record_duration(stage, t.elapsed());
+                    output
+                }
+            })
+            .buffered(concurrency.get());
+
+        while let Some(output) = outputs.next().await {
+            let is_err = output.is_err();
+            if tx.send(output).await.is_err() || is_err {
+                return;
+            }
+        }
+    });
+
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(items: Vec<anyhow::Result<u64>>) -> StageReceiver<u64> {
+        let (tx, rx) = mpsc::channel(items.len().max(1));
+        for item in items {
+            tx.try_send(item).unwrap();
+        }
+        rx
+    }
+
+    #[tokio::test]
+    async fn items_are_forwarded_in_order() {
+        let concurrency = NonZeroUsize::new(4).unwrap();
+        // Earlier items take longer, so they finish last.
+        let mut rx = pipe(
+            source((0..8).map(Ok).collect()),
+            "test",
+            concurrency,
+            |i| async move {
+                tokio::time::sleep(Duration::from_millis(8 - i)).await;
+                Ok(i * 2)
+            },
+        );
+
+        let mut outputs = Vec::new();
+        while let Some(output) = rx.recv().await {
+            outputs.push(output.unwrap());
+        }
+        assert_eq!(outputs, vec![0, 2, 4, 6, 8, 10, 12, 14]);
+    }
+
+    #[tokio::test]
+    async fn stops_after_the_first_error() {
+        let concurrency = NonZeroUsize::new(2).unwrap();
+        let items = vec![Ok(1), Ok(2), Err(anyhow::anyhow!("upstream")), Ok(3)];
+        let mut rx = pipe(source(items), "test", concurrency, |i| async move {
+            anyhow::ensure!(i != 2, "stage");
+            Ok(i)
+        });
+
+        assert_eq!(rx.recv().await.unwrap().unwrap(), 1);
+        assert_eq!(rx.recv().await.unwrap().unwrap_err().to_string(), "stage");
+        assert!(rx.recv().await.is_none());
+    }
+}
@@ -10,6 +10,69 @@ use pathfinder_common::{
 use pathfinder_merkle_tree::{ClassCommitmentTree, StorageCommitmentTree};
 use pathfinder_storage::Transaction;
 
+/// Removes blocks `reorg_tail..=head` from storage, reverting the Starknet
+/// state to that of the block before `reorg_tail`.
+pub fn purge_blocks(
+    transaction: &Transaction<'_>,
+    mut head: BlockNumber,
+    reorg_tail: BlockNumber,
+) -> anyhow::Result<()> {
+    // Roll back Merkle trie updates.
+    //
+    // If we're rolling back genesis then there will be no blocks left so state will
+    // be empty.
+    if let Some(target_block) = reorg_tail.parent() {
+        let target_header = transaction
+            .block_header(target_block.into())
+            .context("Fetching target block header")?
+            .context("Expected target header to exist")?;
+        revert_starknet_state(transaction, head, target_block, target_header)?;
+    }
+
+    // Purge each block one at a time.
+    //
+    // This is done 1-by-1 to allow sending the reorg'd block data
+    // to websocket subscriptions while keeping a constant memory footprint.
+    //
+    // This is acceptable performance because reorgs are rare and need not be
+    // 100% optimal. However a large reorg could cause a massive memory spike
+    // which is not acceptable.
+    while head >= reorg_tail {
+        transaction
+            .purge_block(head)
+            .with_context(|| format!("Purging block {head} from database"))?;
+
+        // No further blocks to purge if we just purged genesis.
+        if head == BlockNumber::GENESIS {
+            break;
+        }
+
+        head -= 1;
+    }
+
+    transaction
+        .reset()
+        .context("Resetting local DB state after reorg")?;
+
+    // Track combined L1 and L2 state.
+    let l1_l2_head = transaction.l1_l2_pointer().context("Query L1-L2 head")?;
+    if let Some(l1_l2_head) = l1_l2_head {
+        if reorg_tail == BlockNumber::GENESIS {
+            // If we purged genesis then unset the L1 L2 pointer as well since there
+            // are now no blocks remaining.
+            transaction
+                .update_l1_l2_pointer(None)
+                .context("Unsetting L1-L2 head")?;
+        } else if l1_l2_head >= reorg_tail {
+            transaction
+                .update_l1_l2_pointer(Some(reorg_tail - 1))
+                .context("Updating L1-L2 head")?;
+        }
+    }
+
+    Ok(())
+}
+
 /// Revert Starknet state by applying reverse-updates.
 ///
 /// Computes the contract and Sierra class reverse-updates then applies those to
@@ -0,0 +1,191 @@
+use std::num::{NonZeroU32, NonZeroU64};
+use std::time::{Duration, Instant};
+
+use pathfinder_common::StateUpdate;
+use starknet_gateway_types::reply::Block;
+
+/// Rough per-transaction storage cost (transaction, receipt and indices) used
+/// when estimating how many bytes a block adds to the database.
+const TRANSACTION_WRITE_ESTIMATE: u64 = 512;
+/// Rough per-state-diff-item cost including the Merkle trie nodes it touches.
+const STATE_DIFF_ITEM_WRITE_ESTIMATE: u64 = 256;
+
+/// Limits on how fast the sync consumer is allowed to write blocks to the
+/// database while it is backfilling historical blocks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WriteThrottleConfig {
+    pub max_blocks_per_second: Option<NonZeroU32>,
+    pub max_bytes_per_second: Option<NonZeroU64>,
+    /// In low priority mode the consumer additionally idles for as long as
+    /// each block took to write, leaving at least half of the available disk
+    /// time to other processes.
+    pub low_priority: bool,
+}
+
+impl WriteThrottleConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.max_blocks_per_second.is_some()
+            || self.max_bytes_per_second.is_some()
+            || self.low_priority
+    }
+}
+
+/// Tracks the write budget of the sync consumer.
+///
+/// The budget is accounted over a sliding one second window. Once a window's
+/// budget is exhausted the consumer is asked to wait until the written amount
+/// fits the configured rate again.
+#[derive(Debug)]
+pub struct WriteThrottle {
+    config: WriteThrottleConfig,
+    window_start: Instant,
+    blocks_in_window: u64,
+    bytes_in_window: u64,
+}
+
+impl WriteThrottle {
+    const WINDOW: Duration = Duration::from_secs(1);
+
+    pub fn new(config: WriteThrottleConfig) -> Self {
+        Self {
+            config,
+            window_start: Instant::now(),
+            blocks_in_window: 0,
+            bytes_in_window: 0,
+        }
+    }
+
+    /// Records a committed block and returns how long the consumer should wait
+    /// before writing the next one.
+    pub fn record(&mut self, bytes: u64, write_time: Duration, now: Instant) -> Duration {
+        if !self.config.is_enabled() {
+            return Duration::ZERO;
+        }
+
+        if now.duration_since(self.window_start) >= Self::WINDOW {
+            self.window_start = now;
+            self.blocks_in_window = 0;
+            self.bytes_in_window = 0;
+        }
+
+        self.blocks_in_window += 1;
+        self.bytes_in_window += bytes;
+
+        let elapsed = now.duration_since(self.window_start);
+
+        let block_delay = self
+            .config
+            .max_blocks_per_second
+            .map(|max| {
+                Duration::from_secs_f64(self.blocks_in_window as f64 / max.get() as f64)
+                    .saturating_sub(elapsed)
+            })
+            .unwrap_or_default();
+
+        let bytes_delay = self
+            .config
+            .max_bytes_per_second
+            .map(|max| {
+                Duration::from_secs_f64(self.bytes_in_window as f64 / max.get() as f64)
+                    .saturating_sub(elapsed)
+            })
+            .unwrap_or_default();
+
+        let low_priority_delay = if self.config.low_priority {
+            write_time
+        } else {
+            Duration::ZERO
+        };
+
+        block_delay.max(bytes_delay).max(low_priority_delay)
+    }
+}
+
+/// Estimates the number of bytes a block adds to the database.
+///
+/// This is deliberately cheap and only meant to give the write throttle a
+/// sense of scale; it does not account for compression or index overhead.
+pub fn estimated_write_size(block: &Block, state_update: &StateUpdate) -> u64 {
+    let event_felts: usize = block
+        .transaction_receipts
+        .iter()
+        .flat_map(|(_, events)| events.iter())
+        .map(|event| event.keys.len() + event.data.len() + 1)
+        .sum();
+
+    block.transactions.len() as u64 * TRANSACTION_WRITE_ESTIMATE
+        + state_update.state_diff_length() * STATE_DIFF_ITEM_WRITE_ESTIMATE
+        + event_felts as u64 * 32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_never_waits() {
+        let mut throttle = WriteThrottle::new(WriteThrottleConfig::default());
+        let now = Instant::now();
+        for _ in 0..1000 {
+            assert_eq!(
+                throttle.record(u64::MAX / 2000, Duration::from_secs(1), now),
+                Duration::ZERO
+            );
+        }
+    }
+
+    #[test]
+    fn block_rate_is_enforced() {
+        let mut throttle = WriteThrottle::new(WriteThrottleConfig {
+            max_blocks_per_second: NonZeroU32::new(2),
+            ..Default::default()
+        });
+        let start = throttle.window_start;
+
+        assert_eq!(
+            throttle.record(0, Duration::ZERO, start),
+            Duration::from_millis(500)
+        );
+        assert_eq!(
+            throttle.record(0, Duration::ZERO, start + Duration::from_millis(500)),
+            Duration::from_millis(500)
+        );
+        // A new window starts once a second has passed.
+        assert_eq!(
+            throttle.record(0, Duration::ZERO, start + Duration::from_secs(1)),
+            Duration::from_millis(500)
+        );
+    }
+
+    #[test]
+    fn byte_rate_is_enforced() {
+        let mut throttle = WriteThrottle::new(WriteThrottleConfig {
+            max_bytes_per_second: NonZeroU64::new(1000),
+            ..Default::default()
+        });
+        let start = throttle.window_start;
+
+        assert_eq!(
+            throttle.record(250, Duration::ZERO, start),
+            Duration::from_millis(250)
+        );
+        assert_eq!(
+            throttle.record(750, Duration::ZERO, start + Duration::from_millis(250)),
+            Duration::from_millis(750)
+        );
+    }
+
+    #[test]
+    fn low_priority_yields_write_time() {
+        let mut throttle = WriteThrottle::new(WriteThrottleConfig {
+            low_priority: true,
+            ..Default::default()
+        });
+        let start = throttle.window_start;
+
+        assert_eq!(
+            throttle.record(0, Duration::from_millis(40), start),
+            Duration::from_millis(40)
+        );
+    }
+}
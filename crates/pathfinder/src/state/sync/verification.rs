@@ -0,0 +1,180 @@
+//! Verification of the data received from the feeder gateway.
+//!
+//! Block hashes, and with them the commitments they include, transaction
+//! hashes, class hashes and state roots are always verified. In
+//! [strict](GatewayDataVerification::Strict) mode mismatching block signatures
+//! and state diff commitments are rejected as well instead of only being
+//! logged, and blocks failing any check are recorded in the quarantine table.
+//!
+//! Every check is counted in the `gateway_verification_checks_total` metric,
+//! labelled by `check` and `result`.
+
+use anyhow::Context;
+use pathfinder_common::{BlockHash, BlockNumber, ClassHash};
+use pathfinder_storage::{QuarantinedBlock, Storage};
+
+/// How much of the data received from the gateway is verified before it is
+/// stored.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum GatewayDataVerification {
+    /// Signature and state diff commitment mismatches are only logged.
+    #[default]
+    Default,
+    /// Every check is enforced and blocks failing one are quarantined.
+    Strict,
+}
+
+impl GatewayDataVerification {
+    pub fn is_strict(self) -> bool {
+        self == Self::Strict
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Check {
+    BlockHash,
+    TransactionHashes,
+    Signature,
+    StateDiffCommitment,
+    ClassHash,
+    StateRoot,
+}
+
+impl Check {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Check::BlockHash => "block_hash",
+            Check::TransactionHashes => "transaction_hashes",
+            Check::Signature => "signature",
+            Check::StateDiffCommitment => "state_diff_commitment",
+            Check::ClassHash => "class_hash",
+            Check::StateRoot => "state_root",
+        }
+    }
+}
+
+impl std::fmt::Display for Check {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Counts the outcome of a check.
+pub fn record(check: Check, passed: bool) {
+    metrics::increment_counter!(
+        "gateway_verification_checks_total",
+        "check" => check.as_str(),
+        "result" => if passed { "pass" } else { "fail" }
+    );
+}
+
+/// A block which failed a check.
+#[derive(Debug, thiserror::Error)]
+#[error("Block {block_number} failed the {check} check: {reason}")]
+pub struct VerificationFailure {
+    pub check: Check,
+    pub block_number: BlockNumber,
+    pub block_hash: BlockHash,
+    pub reason: String,
+}
+
+impl VerificationFailure {
+    pub fn new(
+        check: Check,
+        block_number: BlockNumber,
+        block_hash: BlockHash,
+        reason: impl std::fmt::Display,
+    ) -> anyhow::Error {
+        anyhow::Error::new(Self {
+            check,
+            block_number,
+            block_hash,
+            reason: format!("{reason:#}"),
+        })
+    }
+}
+
+/// A downloaded class definition which repeatedly did not hash to the
+/// expected class hash.
+#[derive(Debug, thiserror::Error)]
+#[error("Class hash mismatch, {computed} instead of {expected}")]
+pub struct ClassHashMismatch {
+    pub expected: ClassHash,
+    pub computed: ClassHash,
+}
+
+/// Records the block in the quarantine table if `error` was caused by a
+/// [VerificationFailure]. A failure is only recorded once, even though the
+/// block is downloaded again after each restart of sync.
+pub fn quarantine(storage: &Storage, error: &anyhow::Error) -> anyhow::Result<()> {
+    let Some(failure) = error.downcast_ref::<VerificationFailure>() else {
+        return Ok(());
+    };
+
+    let mut db = storage
+        .connection()
+        .context("Creating database connection")?;
+    let tx = db.transaction().context("Creating database transaction")?;
+
+    let check = failure.check.as_str();
+    let already_quarantined = tx
+        .quarantined_blocks()
+        .context("Querying quarantined blocks")?
+        .iter()
+        .any(|block| block.block_hash == failure.block_hash && block.check == check);
+    if already_quarantined {
+        return Ok(());
+    }
+
+    tx.insert_quarantined_block(&QuarantinedBlock {
+        block_number: failure.block_number,
+        block_hash: failure.block_hash,
+        check: check.to_owned(),
+        reason: failure.reason.clone(),
+        quarantined_at: time::OffsetDateTime::now_utc().unix_timestamp() as u64,
+    })
+    .context("Inserting quarantined block")?;
+    tx.commit().context("Committing database transaction")?;
+
+    tracing::warn!(
+        block_number=%failure.block_number,
+        block_hash=%failure.block_hash,
+        %check,
+        "Quarantined block which failed verification"
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn failures_are_quarantined_once() {
+        let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
+        let failure = || {
+            VerificationFailure::new(
+                Check::Signature,
+                BlockNumber::new_or_panic(1),
+                block_hash!("0x1"),
+                "Invalid signature",
+            )
+            .context("Sync L2 block")
+        };
+
+        quarantine(&storage, &failure()).unwrap();
+        quarantine(&storage, &failure()).unwrap();
+        quarantine(&storage, &anyhow::anyhow!("Network error")).unwrap();
+
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        let quarantined = tx.quarantined_blocks().unwrap();
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].block_number, BlockNumber::new_or_panic(1));
+        assert_eq!(quarantined[0].check, "signature");
+        assert_eq!(quarantined[0].reason, "Invalid signature");
+    }
+}
@@ -10,6 +10,7 @@ use p2p::client::peer_agnostic::traits::{
     ClassStream,
     EventStream,
     HeaderStream,
+    PeerPenalty,
     StateDiffStream,
     StreamItem,
     TransactionStream,
@@ -68,6 +69,7 @@ where
         + ClassStream
         + EventStream
         + HeaderStream
+        + PeerPenalty
         + StateDiffStream
         + TransactionStream
         + Clone
@@ -82,8 +84,13 @@ where
     }
 
     async fn handle_recoverable_error(&self, err: &error::SyncError) {
-        // TODO
-        tracing::debug!(%err, "Log and punish as appropriate");
+        match err.peer() {
+            Some(peer) => {
+                tracing::debug!(%err, %peer, "Penalizing peer");
+                self.p2p.clone().penalize(peer).await;
+            }
+            None => tracing::debug!(%err, "Recoverable sync error"),
+        }
     }
 
     /// Retry forever until a valid L1 checkpoint is retrieved
@@ -1071,6 +1078,10 @@ mod tests {
         }
     }
 
+    impl PeerPenalty for FakeP2PClient {
+        async fn penalize(self, _: PeerId) {}
+    }
+
     #[derive(Clone)]
     struct FakeFgw {
         head: (BlockNumber, BlockHash),
@@ -311,11 +311,16 @@ async fn handle_header_stream(
     InfallibleSource::from_stream(stream)
         .spawn()
         .pipe(headers::BackwardContinuity::new(head.0, head.1), 10)
+        .pipe(headers::AttachPeer, 10)
+        .try_chunks(1000, 10)
         .pipe(
-            headers::VerifyHashAndSignature::new(chain_id, public_key, block_hash_db),
+            headers::VerifyHashAndSignatureBatch(headers::VerifyHashAndSignature::new(
+                chain_id,
+                public_key,
+                block_hash_db,
+            )),
             10,
         )
-        .try_chunks(1000, 10)
         .pipe(
             headers::Persist {
                 connection: storage.connection().context("Creating db connection")?,
@@ -974,6 +979,36 @@ mod tests {
             );
         }
 
+        #[tokio::test]
+        async fn bad_signature_is_attributed_to_its_peer() {
+            let Setup {
+                mut streamed_headers,
+                storage,
+                head,
+                public_key,
+                block_hash_db,
+                ..
+            } = setup_from_fake(10);
+
+            let peer = p2p::libp2p::PeerId::random();
+            let invalid = &mut streamed_headers[3];
+            invalid.peer = peer;
+            invalid.data.signature.s = invalid.data.signature.r;
+
+            assert_matches!(
+                handle_header_stream(
+                    stream::iter(streamed_headers),
+                    head,
+                    ChainId::SEPOLIA_TESTNET,
+                    public_key,
+                    block_hash_db,
+                    storage.clone(),
+                )
+                .await,
+                Err(SyncError::BadHeaderSignature(x)) => assert_eq!(x, peer)
+            );
+        }
+
         #[tokio::test]
         async fn db_failure() {
             let Setup {
@@ -1340,6 +1375,27 @@ mod tests {
             );
         }
 
+        #[test]
+        fn length_mismatch() {
+            use crate::sync::state_updates::{ExpectedStateDiff, VerifyCommitment};
+            use crate::sync::stream::ProcessStage;
+
+            let mut state_diff = StateUpdateData::default();
+            state_diff.declared_cairo_classes.insert(Faker.fake());
+            let expected = ExpectedStateDiff {
+                commitment: state_diff.compute_state_diff_commitment(),
+                length: 2,
+            };
+
+            assert_matches!(
+                VerifyCommitment.map(
+                    &PeerId::random(),
+                    (state_diff, BlockNumber::GENESIS, expected)
+                ),
+                Err(SyncError::IncorrectStateDiffCount(_))
+            );
+        }
+
         #[tokio::test]
         async fn stream_failure() {
             assert_matches!(
@@ -114,6 +114,39 @@ impl PartialEq for SyncError {
     }
 }
 
+impl SyncError {
+    /// The peer which served the data that failed verification, if any.
+    pub fn peer(&self) -> Option<PeerId> {
+        match self {
+            SyncError::Fatal(_) | SyncError::FetchingCasmFailed => None,
+            SyncError::BadBlockHash(peer)
+            | SyncError::BadClassHash(peer)
+            | SyncError::BadClassLayout(peer)
+            | SyncError::BadHeaderSignature(peer)
+            | SyncError::BadTransactionHash(peer)
+            | SyncError::CairoDefinitionError(peer)
+            | SyncError::ClassDefinitionsDeclarationsMismatch(peer)
+            | SyncError::ClassHashComputationError(peer)
+            | SyncError::ContractClassMissing(peer)
+            | SyncError::Discontinuity(peer)
+            | SyncError::EventCommitmentMismatch(peer)
+            | SyncError::EventsTransactionsMismatch(peer)
+            | SyncError::IncorrectClassDefinitionCount(peer)
+            | SyncError::IncorrectStateDiffCount(peer)
+            | SyncError::InvalidDto(peer)
+            | SyncError::SierraDefinitionError(peer)
+            | SyncError::StateDiffCommitmentMismatch(peer)
+            | SyncError::StateRootMismatch(peer)
+            | SyncError::TooFewEvents(peer)
+            | SyncError::TooFewTransactions(peer)
+            | SyncError::TooManyEvents(peer)
+            | SyncError::TooManyTransactions(peer)
+            | SyncError::TransactionCommitmentMismatch(peer)
+            | SyncError::UnexpectedClass(peer) => Some(*peer),
+        }
+    }
+}
+
 impl From<anyhow::Error> for SyncError {
     fn from(e: anyhow::Error) -> Self {
         Self::Fatal(Arc::new(e))
@@ -145,6 +145,17 @@ pub struct VerifyHashAndSignature {
     block_hash_db: Option<pathfinder_block_hashes::BlockHashDb>,
 }
 
+/// Attaches the peer to each header, so that the peer which sent a header is
+/// still known once the headers are chunked for [VerifyHashAndSignatureBatch].
+pub struct AttachPeer;
+
+/// Ensures that the block hashes and signatures of a chunk of headers are
+/// correct, verifying them in parallel.
+///
+/// Used instead of [VerifyHashAndSignature] when backfilling large ranges of
+/// headers.
+pub struct VerifyHashAndSignatureBatch(pub VerifyHashAndSignature);
+
 impl ForwardContinuity {
     pub fn new(next: BlockNumber, parent_hash: BlockHash) -> Self {
         Self { next, parent_hash }
@@ -225,6 +236,57 @@ impl ProcessStage for VerifyHashAndSignature {
     }
 }
 
+impl ProcessStage for AttachPeer {
+    const NAME: &'static str = "Headers::AttachPeer";
+    type Input = SignedBlockHeader;
+    type Output = PeerData<SignedBlockHeader>;
+
+    fn map(&mut self, peer: &PeerId, input: Self::Input) -> Result<Self::Output, SyncError> {
+        Ok(PeerData::new(*peer, input))
+    }
+}
+
+impl ProcessStage for VerifyHashAndSignatureBatch {
+    const NAME: &'static str = "Headers::VerifyBatch";
+    type Input = Vec<PeerData<SignedBlockHeader>>;
+    type Output = Vec<SignedBlockHeader>;
+
+    fn map(&mut self, _: &PeerId, input: Self::Input) -> Result<Self::Output, SyncError> {
+        use rayon::prelude::*;
+
+        let verifier = &self.0;
+
+        if let Some(invalid) = input
+            .par_iter()
+            .find_first(|header| !verifier.verify_hash(&header.data.header))
+        {
+            return Err(SyncError::BadBlockHash(invalid.peer));
+        }
+
+        let signatures = input
+            .iter()
+            .map(|PeerData { data, .. }| {
+                (data.header.hash.0, data.signature.r.0, data.signature.s.0)
+            })
+            .collect::<Vec<_>>();
+        let results = pathfinder_crypto::signature::ecdsa_verify_partial_batch(
+            verifier.public_key.0,
+            &signatures,
+        );
+
+        if let Some((invalid, error)) = input
+            .iter()
+            .zip(results)
+            .find_map(|(header, result)| result.err().map(|error| (header, error)))
+        {
+            tracing::debug!(%error, header=?invalid.data, "Header signature verification failed");
+            return Err(SyncError::BadHeaderSignature(invalid.peer));
+        }
+
+        Ok(input.into_iter().map(|header| header.data).collect())
+    }
+}
+
 impl VerifyHashAndSignature {
     pub fn new(
         chain_id: ChainId,
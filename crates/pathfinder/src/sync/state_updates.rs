@@ -102,7 +102,7 @@ impl<T> FetchCommitmentFromDb<T> {
 impl<T> ProcessStage for FetchCommitmentFromDb<T> {
     const NAME: &'static str = "StateDiff::FetchCommitmentFromDb";
     type Input = (T, BlockNumber);
-    type Output = (T, BlockNumber, StateDiffCommitment);
+    type Output = (T, BlockNumber, ExpectedStateDiff);
 
     fn map(
         &mut self,
@@ -113,25 +113,36 @@ impl<T> ProcessStage for FetchCommitmentFromDb<T> {
             .db
             .transaction()
             .context("Creating database transaction")?;
-        let commitment = db
-            .state_diff_commitment(block_number)
+        let (commitment, length) = db
+            .state_diff_commitment_and_length(block_number)
             .context("Fetching state diff commitment")?
             // This is a fatal error because the block header is already expected to be in the
             // database
             .context("State diff commitment not found")?;
-        Ok((data, block_number, commitment))
+        Ok((data, block_number, ExpectedStateDiff { commitment, length }))
     }
 }
 
+/// The state diff commitment and length of a block, as committed to by its
+/// header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExpectedStateDiff {
+    pub commitment: StateDiffCommitment,
+    pub length: u64,
+}
+
+/// Rejects state diffs which do not match the commitment and length in their
+/// block's header before they are persisted.
 pub struct VerifyCommitment;
 
 impl ProcessStage for VerifyCommitment {
     const NAME: &'static str = "StateDiff::VerifyCommitment";
-    type Input = (StateUpdateData, BlockNumber, StateDiffCommitment);
+    type Input = (StateUpdateData, BlockNumber, ExpectedStateDiff);
     type Output = (StateUpdateData, BlockNumber);
 
     fn map(&mut self, peer: &PeerId, input: Self::Input) -> Result<Self::Output, SyncError> {
-        let (state_diff, block_number, expected_commitment) = input;
+        let (state_diff, block_number, expected) = input;
+        let expected_commitment = expected.commitment;
         let actual_commitment = state_diff.compute_state_diff_commitment();
 
         if actual_commitment != expected_commitment {
@@ -139,6 +150,17 @@ impl ProcessStage for VerifyCommitment {
             return Err(SyncError::StateDiffCommitmentMismatch(*peer));
         }
 
+        // Entries received more than once are merged into one, so the length has to be
+        // checked on the merged state diff as well.
+        let actual_length = state_diff.state_diff_length() as u64;
+        if actual_length != expected.length {
+            tracing::debug!(
+                %peer, %block_number, expected_length=%expected.length, %actual_length,
+                "State diff length mismatch"
+            );
+            return Err(SyncError::IncorrectStateDiffCount(*peer));
+        }
+
         Ok((state_diff, block_number))
     }
 }
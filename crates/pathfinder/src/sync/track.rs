@@ -34,7 +34,6 @@ use pathfinder_common::{
     SignedBlockHeader,
     StarknetVersion,
     StateCommitment,
-    StateDiffCommitment,
     StateUpdate,
     StorageCommitment,
     TransactionCommitment,
@@ -46,6 +45,7 @@ use starknet_gateway_client::GatewayApi;
 use tokio_stream::wrappers::ReceiverStream;
 
 use super::class_definitions::CompiledClass;
+use super::state_updates::ExpectedStateDiff;
 use super::{state_updates, transactions};
 use crate::sync::class_definitions::{self, ClassWithLayout};
 use crate::sync::error::SyncError;
@@ -536,7 +536,7 @@ struct StateDiffSource<P> {
 }
 
 impl<P> StateDiffSource<P> {
-    fn spawn(self) -> SyncReceiver<(StateUpdateData, BlockNumber, StateDiffCommitment)>
+    fn spawn(self) -> SyncReceiver<(StateUpdateData, BlockNumber, ExpectedStateDiff)>
     where
         P: Clone + BlockClient + Send + 'static,
     {
@@ -571,7 +571,10 @@ impl<P> StateDiffSource<P> {
                         (
                             state_diff,
                             header.header.number,
-                            header.header.state_diff_commitment,
+                            ExpectedStateDiff {
+                                commitment: header.header.state_diff_commitment,
+                                length: header.header.state_diff_length,
+                            },
                         ),
                     )))
                     .await
@@ -0,0 +1,294 @@
+//! Circuit breaker guarding the RPC methods which fall back to the feeder
+//! gateway.
+//!
+//! Once the gateway fails [GatewayCircuitBreakerConfig::failure_threshold]
+//! times in a row the breaker opens and requests fail immediately instead of
+//! waiting for the gateway to time out. After
+//! [GatewayCircuitBreakerConfig::open_duration] a single probe request is let
+//! through (the half-open state) and its outcome decides whether the breaker
+//! closes again or stays open for another period.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use starknet_gateway_types::error::SequencerError;
+use tokio::time::Instant;
+
+use crate::context::GatewayCircuitBreakerConfig;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GatewayCallError {
+    /// The breaker is open, the gateway was not contacted.
+    #[error("Feeder gateway is unavailable")]
+    Unavailable,
+    #[error(transparent)]
+    Gateway(#[from] SequencerError),
+}
+
+#[derive(Clone)]
+pub struct GatewayCircuitBreaker {
+    state: Arc<Mutex<State>>,
+    config: GatewayCircuitBreakerConfig,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Closed {
+        consecutive_failures: u32,
+    },
+    Open {
+        until: Instant,
+    },
+    /// A probe request is in flight.
+    HalfOpen,
+}
+
+impl State {
+    fn metric_value(&self) -> f64 {
+        match self {
+            State::Closed { .. } => 0.0,
+            State::Open { .. } => 1.0,
+            State::HalfOpen => 2.0,
+        }
+    }
+}
+
+impl Default for GatewayCircuitBreaker {
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl GatewayCircuitBreaker {
+    pub fn new(config: GatewayCircuitBreakerConfig) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State::Closed {
+                consecutive_failures: 0,
+            })),
+            config,
+        }
+    }
+
+    /// Performs `request` unless the breaker is open.
+    ///
+    /// Starknet errors returned by the gateway count as successes as the
+    /// gateway did respond.
+    pub async fn call<T>(
+        &self,
+        request: impl Future<Output = Result<T, SequencerError>>,
+    ) -> Result<T, GatewayCallError> {
+        let mut permit = self.acquire().ok_or_else(|| {
+            metrics::increment_counter!("rpc_gateway_circuit_breaker_rejected_total");
+            GatewayCallError::Unavailable
+        })?;
+
+        let result = request.await;
+        let is_success = !matches!(
+            result,
+            Err(SequencerError::ReqwestError(_) | SequencerError::InvalidStarknetErrorVariant)
+        );
+        permit.complete(is_success);
+
+        result.map_err(Into::into)
+    }
+
+    fn acquire(&self) -> Option<Permit<'_>> {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            State::Closed { .. } => {}
+            State::Open { until } if Instant::now() >= until => {
+                tracing::debug!("Probing feeder gateway");
+                self.transition(&mut state, State::HalfOpen);
+            }
+            State::Open { .. } | State::HalfOpen => return None,
+        }
+
+        Some(Permit {
+            breaker: self,
+            completed: false,
+        })
+    }
+
+    fn record(&self, is_success: bool) {
+        let mut state = self.state.lock().unwrap();
+        let next = match (*state, is_success) {
+            (State::HalfOpen, true) => {
+                tracing::info!("Feeder gateway is available again");
+                State::Closed {
+                    consecutive_failures: 0,
+                }
+            }
+            (_, true) => State::Closed {
+                consecutive_failures: 0,
+            },
+            (
+                State::Closed {
+                    consecutive_failures,
+                },
+                false,
+            ) if consecutive_failures + 1 < self.config.failure_threshold.get() => State::Closed {
+                consecutive_failures: consecutive_failures + 1,
+            },
+            (State::Closed { .. } | State::HalfOpen, false) => {
+                tracing::warn!(
+                    open_duration=?self.config.open_duration,
+                    "Feeder gateway is unavailable, failing gateway requests immediately"
+                );
+                metrics::increment_counter!("rpc_gateway_circuit_breaker_opened_total");
+                State::Open {
+                    until: Instant::now() + self.config.open_duration,
+                }
+            }
+            // Another request opened the breaker in the meantime.
+            (State::Open { .. }, false) => return,
+        };
+        self.transition(&mut state, next);
+    }
+
+    fn transition(&self, state: &mut State, next: State) {
+        *state = next;
+        metrics::gauge!("rpc_gateway_circuit_breaker_state", state.metric_value());
+    }
+}
+
+/// Permission to perform a gateway request.
+///
+/// Dropping the permit without completing it, e.g. because the RPC request was
+/// cancelled, allows another probe to be made right away if this one was a
+/// probe.
+struct Permit<'a> {
+    breaker: &'a GatewayCircuitBreaker,
+    completed: bool,
+}
+
+impl Permit<'_> {
+    fn complete(&mut self, is_success: bool) {
+        self.completed = true;
+        self.breaker.record(is_success);
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.completed {
+            return;
+        }
+
+        let mut state = self.breaker.state.lock().unwrap();
+        if *state == State::HalfOpen {
+            self.breaker.transition(
+                &mut state,
+                State::Open {
+                    until: Instant::now(),
+                },
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+    use std::time::Duration;
+
+    use starknet_gateway_types::error::{KnownStarknetErrorCode, StarknetError};
+
+    use super::*;
+
+    const OPEN_DURATION: Duration = Duration::from_secs(30);
+
+    fn breaker() -> GatewayCircuitBreaker {
+        GatewayCircuitBreaker::new(GatewayCircuitBreakerConfig {
+            failure_threshold: NonZeroU32::new(2).unwrap(),
+            open_duration: OPEN_DURATION,
+        })
+    }
+
+    async fn fail(breaker: &GatewayCircuitBreaker) -> GatewayCallError {
+        breaker
+            .call(async { Err::<(), _>(SequencerError::InvalidStarknetErrorVariant) })
+            .await
+            .unwrap_err()
+    }
+
+    async fn succeed(breaker: &GatewayCircuitBreaker) -> Result<(), GatewayCallError> {
+        breaker.call(async { Ok(()) }).await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn opens_after_consecutive_failures() {
+        let breaker = breaker();
+
+        fail(&breaker).await;
+        succeed(&breaker).await.unwrap();
+        fail(&breaker).await;
+        assert_matches::assert_matches!(fail(&breaker).await, GatewayCallError::Gateway(_));
+
+        assert_matches::assert_matches!(
+            succeed(&breaker).await,
+            Err(GatewayCallError::Unavailable)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn starknet_errors_are_not_failures() {
+        let breaker = breaker();
+        let starknet_error = || async {
+            Err::<(), _>(SequencerError::StarknetError(StarknetError {
+                code: KnownStarknetErrorCode::TransactionFailed.into(),
+                message: Default::default(),
+            }))
+        };
+
+        for _ in 0..3 {
+            breaker.call(starknet_error()).await.unwrap_err();
+        }
+        succeed(&breaker).await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn successful_probe_closes() {
+        let breaker = breaker();
+        fail(&breaker).await;
+        fail(&breaker).await;
+
+        tokio::time::advance(OPEN_DURATION).await;
+        succeed(&breaker).await.unwrap();
+
+        fail(&breaker).await;
+        succeed(&breaker).await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_probe_reopens() {
+        let breaker = breaker();
+        fail(&breaker).await;
+        fail(&breaker).await;
+
+        tokio::time::advance(OPEN_DURATION).await;
+        assert_matches::assert_matches!(fail(&breaker).await, GatewayCallError::Gateway(_));
+
+        assert_matches::assert_matches!(
+            succeed(&breaker).await,
+            Err(GatewayCallError::Unavailable)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn only_one_probe_at_a_time() {
+        let breaker = breaker();
+        fail(&breaker).await;
+        fail(&breaker).await;
+        tokio::time::advance(OPEN_DURATION).await;
+
+        let probe = breaker.acquire().unwrap();
+        assert_matches::assert_matches!(
+            succeed(&breaker).await,
+            Err(GatewayCallError::Unavailable)
+        );
+
+        // An abandoned probe allows the next request through.
+        drop(probe);
+        succeed(&breaker).await.unwrap();
+    }
+}
@@ -1,15 +1,22 @@
-use std::num::NonZeroUsize;
+use std::collections::{HashMap, HashSet};
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::sync::Arc;
+use std::time::Duration;
 
 use pathfinder_common::{contract_address, ChainId, ContractAddress};
 use pathfinder_ethereum::EthereumClient;
-use pathfinder_executor::{TraceCache, VersionedConstants};
+use pathfinder_executor::{FeeTokens, TraceCache, TraceCacheConfig, VersionedConstants};
 use pathfinder_storage::Storage;
 use primitive_types::{H160, H256};
 
+use crate::circuit_breaker::GatewayCircuitBreaker;
+use crate::idempotency::SubmissionCache;
 pub use crate::jsonrpc::websocket::WebsocketContext;
 use crate::jsonrpc::Notifications;
+use crate::log_filter::LogFilter;
 use crate::pending::{PendingData, PendingWatcher};
+use crate::request_log::RequestLog;
+use crate::response_cache::ResponseCache;
 use crate::SyncState;
 
 type SequencerClient = starknet_gateway_client::Client;
@@ -69,6 +76,126 @@ pub struct RpcConfig {
     pub get_events_max_blocks_to_scan: NonZeroUsize,
     pub get_events_max_uncached_event_filters_to_load: NonZeroUsize,
     pub custom_versioned_constants: Option<VersionedConstants>,
+    /// Maximum size of an HTTP request body in bytes.
+    pub max_request_body_size: usize,
+    pub response_size_limits: ResponseSizeLimits,
+    pub trace_cache: TraceCacheConfig,
+    pub gateway_circuit_breaker: GatewayCircuitBreakerConfig,
+    /// Fee tokens accepted besides the chain's ETH and STRK tokens, e.g. on
+    /// appchains.
+    pub additional_fee_tokens: Vec<pathfinder_executor::FeeToken>,
+    /// Contracts and entry points refused by `starknet_call`, fee estimation
+    /// and simulation.
+    pub execution_policy: Arc<pathfinder_executor::ExecutionPolicy>,
+    /// Reconstruct the events and messages of traces fetched from the feeder
+    /// gateway from the stored receipts if the gateway omitted them.
+    pub reconstruct_gateway_trace_events: bool,
+    /// How long responses to transaction submissions carrying an idempotency
+    /// key are returned for retries.
+    pub idempotency_key_ttl: Duration,
+    /// Persist transactions which could not be forwarded to the gateway
+    /// because of a transient failure and resubmit them in the background.
+    pub gateway_outbox: bool,
+    /// Persist transactions submitted through this node which the gateway
+    /// rejected, together with the rejection reason.
+    pub rejected_transaction_archive: bool,
+    /// Reject declares of classes which are too large, declared already or
+    /// fail to compile instead of forwarding them to the gateway.
+    pub declare_precheck: bool,
+    /// Serve `pathfinder_getTraceCacheBlocks` and `pathfinder_warmTraceCache`.
+    pub trace_cache_warmup: bool,
+    /// Maximum number of storage keys in a single `pathfinder_getProof`
+    /// request.
+    pub get_proof_max_keys: NonZeroUsize,
+    /// Maximum number of key positions in the filter of `starknet_getEvents`
+    /// and `starknet_subscribeEvents`. Must not exceed
+    /// [EVENT_KEY_FILTER_LIMIT](pathfinder_storage::EVENT_KEY_FILTER_LIMIT).
+    pub event_filter_max_keys: NonZeroUsize,
+    /// Maximum `chunk_size` of a `starknet_getEvents` request.
+    pub get_events_max_chunk_size: NonZeroUsize,
+    pub method_access: MethodAccessConfig,
+    /// Memory budget in bytes of the cache for responses to queries for data
+    /// addressed by hash. [None] disables the cache.
+    pub response_cache_max_size: Option<NonZeroUsize>,
+}
+
+/// Caps on the serialized size of method responses, in bytes. Serialization
+/// of a response is aborted as soon as it crosses its limit.
+#[derive(Clone, Debug, Default)]
+pub struct ResponseSizeLimits {
+    /// Applies to all methods without an explicit limit.
+    pub default: Option<NonZeroUsize>,
+    /// Limits for individual methods, keyed by method name (e.g.
+    /// `starknet_getEvents`).
+    pub per_method: HashMap<String, NonZeroUsize>,
+}
+
+impl ResponseSizeLimits {
+    pub fn limit_for(&self, method: &str) -> Option<NonZeroUsize> {
+        self.per_method.get(method).copied().or(self.default)
+    }
+}
+
+/// Methods which are disabled or only available to authenticated clients.
+///
+/// Method patterns are either exact method names or a prefix followed by `*`,
+/// e.g. `starknet_trace*`.
+#[derive(Clone, Debug, Default)]
+pub struct MethodAccessConfig {
+    /// Methods which are not served at all.
+    pub disabled: Vec<String>,
+    /// Methods which are only served to clients presenting one of `api_keys`.
+    pub restricted: Vec<String>,
+    /// API keys accepted as `Authorization: Bearer <key>` header.
+    pub api_keys: HashSet<String>,
+}
+
+impl MethodAccessConfig {
+    pub fn is_disabled(&self, method: &str) -> bool {
+        Self::matches_any(&self.disabled, method)
+    }
+
+    pub fn is_restricted(&self, method: &str) -> bool {
+        Self::matches_any(&self.restricted, method)
+    }
+
+    /// Returns true if the request headers carry one of the configured API
+    /// keys.
+    pub fn is_authenticated(&self, headers: &http::HeaderMap) -> bool {
+        headers
+            .get(http::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|key| self.api_keys.contains(key.trim()))
+    }
+
+    fn matches_any(patterns: &[String], method: &str) -> bool {
+        patterns
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => method.starts_with(prefix),
+                None => method == pattern,
+            })
+    }
+}
+
+/// Configuration of the circuit breaker guarding feeder gateway fallbacks.
+#[derive(Clone, Copy, Debug)]
+pub struct GatewayCircuitBreakerConfig {
+    /// Number of consecutive gateway failures after which requests fail
+    /// immediately.
+    pub failure_threshold: NonZeroU32,
+    /// How long requests fail immediately before the gateway is probed again.
+    pub open_duration: Duration,
+}
+
+impl Default for GatewayCircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: NonZeroU32::new(5).unwrap(),
+            open_duration: Duration::from_secs(30),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -81,10 +208,19 @@ pub struct RpcContext {
     pub chain_id: ChainId,
     pub contract_addresses: EthContractAddresses,
     pub sequencer: SequencerClient,
+    pub(crate) gateway_breaker: GatewayCircuitBreaker,
+    pub(crate) submissions: SubmissionCache,
+    pub(crate) responses: ResponseCache,
+    pub(crate) request_log: RequestLog,
+    /// Set if the tracing filter may be changed through the RPC API.
+    pub(crate) log_filter: Option<LogFilter>,
     pub websocket: Option<WebsocketContext>,
     pub notifications: Notifications,
     pub ethereum: EthereumClient,
     pub config: RpcConfig,
+    /// Set while the node is in read-only degraded mode because it is low on
+    /// disk space.
+    pub degraded: tokio_watch::Receiver<bool>,
 }
 
 impl RpcContext {
@@ -103,7 +239,7 @@ impl RpcContext {
     ) -> Self {
         let pending_data = PendingWatcher::new(pending_data);
         Self {
-            cache: Default::default(),
+            cache: TraceCache::new(config.trace_cache),
             storage,
             execution_storage,
             sync_status,
@@ -111,13 +247,31 @@ impl RpcContext {
             contract_addresses,
             pending_data,
             sequencer,
+            gateway_breaker: GatewayCircuitBreaker::new(config.gateway_circuit_breaker),
+            submissions: SubmissionCache::new(config.idempotency_key_ttl),
+            responses: ResponseCache::new(
+                config.response_cache_max_size,
+                notifications.reorgs.subscribe(),
+            ),
+            request_log: RequestLog::default(),
+            log_filter: None,
             websocket: None,
             notifications,
             ethereum,
             config,
+            degraded: tokio_watch::channel(false).1,
         }
     }
 
+    /// The chain's fee tokens along with the configured additional ones.
+    pub fn fee_tokens(&self) -> FeeTokens {
+        FeeTokens::new(
+            self.contract_addresses.eth_l2_token_address,
+            self.contract_addresses.strk_l2_token_address,
+        )
+        .with_additional(self.config.additional_fee_tokens.clone())
+    }
+
     #[cfg(test)]
     pub fn for_tests() -> Self {
         Self::for_tests_on(pathfinder_common::Chain::SepoliaTestnet)
@@ -170,6 +324,24 @@ impl RpcContext {
             get_events_max_blocks_to_scan: NonZeroUsize::new(1000).unwrap(),
             get_events_max_uncached_event_filters_to_load: NonZeroUsize::new(1000).unwrap(),
             custom_versioned_constants: None,
+            max_request_body_size: 10 * 1024 * 1024,
+            response_size_limits: Default::default(),
+            trace_cache: Default::default(),
+            gateway_circuit_breaker: Default::default(),
+            additional_fee_tokens: vec![],
+            execution_policy: Default::default(),
+            reconstruct_gateway_trace_events: false,
+            idempotency_key_ttl: Duration::from_secs(300),
+            gateway_outbox: false,
+            rejected_transaction_archive: false,
+            declare_precheck: false,
+            trace_cache_warmup: false,
+            get_proof_max_keys: NonZeroUsize::new(100).unwrap(),
+            event_filter_max_keys: NonZeroUsize::new(pathfinder_storage::EVENT_KEY_FILTER_LIMIT)
+                .unwrap(),
+            get_events_max_chunk_size: NonZeroUsize::new(1024).unwrap(),
+            method_access: Default::default(),
+            response_cache_max_size: None,
         };
 
         let ethereum =
@@ -197,6 +369,24 @@ impl RpcContext {
         }
     }
 
+    pub fn with_degraded_mode(self, degraded: tokio_watch::Receiver<bool>) -> Self {
+        Self { degraded, ..self }
+    }
+
+    pub fn with_request_log(self, request_log: RequestLog) -> Self {
+        Self {
+            request_log,
+            ..self
+        }
+    }
+
+    pub fn with_log_filter(self, log_filter: LogFilter) -> Self {
+        Self {
+            log_filter: Some(log_filter),
+            ..self
+        }
+    }
+
     pub fn with_pending_data(self, pending_data: tokio_watch::Receiver<PendingData>) -> Self {
         let pending_data = PendingWatcher::new(pending_data);
         Self {
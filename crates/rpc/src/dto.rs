@@ -1,5 +1,7 @@
 #![allow(unused)]
 
+use std::cell::Cell;
+
 use serde::de::{Error as SerdeError, IntoDeserializer};
 
 mod block;
@@ -12,6 +14,9 @@ mod simulation;
 mod state_update;
 mod transaction;
 
+#[cfg(test)]
+mod snapshots;
+
 pub use block::*;
 pub use class::*;
 pub use event::*;
@@ -41,16 +46,152 @@ pub(crate) type Error = <BaseSerializer as serde::Serializer>::Error;
 
 pub trait SerializeForVersion {
     fn serialize(&self, serializer: Serializer) -> Result<Ok, Error>;
+
+    /// Encodes `self` as JSON directly, skipping the intermediate
+    /// [serde_json::Value]s built by [SerializeForVersion::serialize].
+    ///
+    /// Only worth implementing for method outputs which can get very large.
+    /// Returns [None] by default, in which case the router falls back to
+    /// [SerializeForVersion::serialize].
+    fn serialize_raw(
+        &self,
+        serializer: Serializer,
+    ) -> Option<Result<Box<serde_json::value::RawValue>, Error>> {
+        None
+    }
+}
+
+thread_local! {
+    /// Limits the output serialized on this thread, see [with_size_limit].
+    static SIZE_BUDGET: Cell<Option<SizeBudget>> = const { Cell::new(None) };
+}
+
+#[derive(Clone, Copy)]
+struct SizeBudget {
+    remaining: usize,
+    exceeded: bool,
+}
+
+/// The output serialized by [with_size_limit] exceeded its limit.
+#[derive(Debug)]
+pub(crate) struct SizeLimitExceeded;
+
+/// Runs `serialize`, limiting the JSON encoding of everything it serializes to
+/// `limit` bytes.
+///
+/// The [Serializer] charges the encoded size of each value against the limit
+/// as the value is built and fails once the limit is crossed, so an oversized
+/// output is abandoned part way instead of being built in full. Flattened
+/// structs are charged a few bytes more than their encoding takes up.
+pub(crate) fn with_size_limit<T>(
+    limit: usize,
+    serialize: impl FnOnce() -> T,
+) -> Result<T, SizeLimitExceeded> {
+    /// Restores the previous budget, also if `serialize` panics.
+    struct Restore(Option<SizeBudget>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SIZE_BUDGET.set(self.0);
+        }
+    }
+
+    let _restore = Restore(SIZE_BUDGET.replace(Some(SizeBudget {
+        remaining: limit,
+        exceeded: false,
+    })));
+
+    let output = serialize();
+    match SIZE_BUDGET.get() {
+        Some(SizeBudget { exceeded: true, .. }) => Err(SizeLimitExceeded),
+        _ => Ok(output),
+    }
+}
+
+/// Charges `len` bytes of encoded output against the size budget, if there is
+/// one. The length is only computed if needed.
+fn charge(len: impl FnOnce() -> usize) -> Result<(), Error> {
+    SIZE_BUDGET.with(|budget| {
+        let Some(mut current) = budget.get() else {
+            return Ok(());
+        };
+
+        let result = match current.remaining.checked_sub(len()) {
+            Some(remaining) => {
+                current.remaining = remaining;
+                Ok(())
+            }
+            None => {
+                current.exceeded = true;
+                Err(Error::custom("Output size limit exceeded"))
+            }
+        };
+        budget.set(Some(current));
+        result
+    })
+}
+
+/// The length of the compact JSON encoding of `value`.
+fn encoded_len(value: &(impl serde::Serialize + ?Sized)) -> usize {
+    let mut counter = ByteCounter(0);
+    // Counting cannot fail, and neither can encoding the values counted here.
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
+
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Encodes `value` into a [RawValue](serde_json::value::RawValue) without going
+/// through [serde_json::Value].
+pub(crate) fn to_raw_json(
+    value: &impl serde::Serialize,
+) -> Result<Box<serde_json::value::RawValue>, Error> {
+    if SIZE_BUDGET.get().is_none() {
+        return serde_json::value::to_raw_value(value);
+    }
+
+    /// Charges the encoding against the size budget as it is written.
+    struct LimitedWriter(Vec<u8>);
+
+    impl std::io::Write for LimitedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            charge(|| buf.len()).map_err(std::io::Error::other)?;
+            self.0.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut writer = LimitedWriter(Vec::new());
+    serde_json::to_writer(&mut writer, value)?;
+    let json = String::from_utf8(writer.0).map_err(Error::custom)?;
+    serde_json::value::RawValue::from_string(json)
 }
 
 impl SerializeForVersion for serde_json::Value {
     fn serialize(&self, _serializer: Serializer) -> Result<Ok, Error> {
+        charge(|| encoded_len(self))?;
         Ok(self.clone())
     }
 }
 
 impl SerializeForVersion for &serde_json::Value {
     fn serialize(&self, _serializer: Serializer) -> Result<Ok, Error> {
+        charge(|| encoded_len(*self))?;
         Ok((*self).clone())
     }
 }
@@ -66,45 +207,54 @@ impl Serializer {
 
     pub fn serialize_unit(self) -> Result<Ok, Error> {
         use serde::Serializer;
+        charge(|| 4)?;
         BaseSerializer {}.serialize_unit()
     }
 
     pub fn serialize_str(self, value: &str) -> Result<Ok, Error> {
         use serde::Serializer;
+        charge(|| encoded_len(value))?;
         BaseSerializer {}.serialize_str(value)
     }
 
     pub fn serialize_i32(self, value: i32) -> Result<Ok, Error> {
         use serde::Serializer;
+        charge(|| encoded_len(&value))?;
         BaseSerializer {}.serialize_i32(value)
     }
 
     pub fn serialize_i64(self, value: i64) -> Result<Ok, Error> {
         use serde::Serializer;
+        charge(|| encoded_len(&value))?;
         BaseSerializer {}.serialize_i64(value)
     }
 
     pub fn serialize_u32(self, value: u32) -> Result<Ok, Error> {
         use serde::Serializer;
+        charge(|| encoded_len(&value))?;
         BaseSerializer {}.serialize_u32(value)
     }
 
     pub fn serialize_u64(self, value: u64) -> Result<Ok, Error> {
         use serde::Serializer;
+        charge(|| encoded_len(&value))?;
         BaseSerializer {}.serialize_u64(value)
     }
 
     pub fn serialize_u128(self, value: u128) -> Result<Ok, Error> {
         use serde::Serializer;
+        charge(|| encoded_len(&value))?;
         BaseSerializer {}.serialize_u128(value)
     }
 
     pub fn serialize_bool(self, value: bool) -> Result<Ok, Error> {
         use serde::Serializer;
+        charge(|| encoded_len(&value))?;
         BaseSerializer {}.serialize_bool(value)
     }
 
     pub fn serialize_struct(self) -> Result<SerializeStruct, Error> {
+        charge(|| "{}".len())?;
         Ok(SerializeStruct {
             version: self.version,
             fields: Default::default(),
@@ -119,8 +269,12 @@ impl Serializer {
         use serde::ser::SerializeSeq;
         use serde::Serializer;
 
+        charge(|| "[]".len())?;
         let mut serializer = BaseSerializer {}.serialize_seq(Some(len))?;
-        for value in values {
+        for (i, value) in values.enumerate() {
+            if i > 0 {
+                charge(|| ",".len())?;
+            }
             let value = self.serialize(&value)?;
             serializer.serialize_element(&value)?;
         }
@@ -134,6 +288,8 @@ impl SerializeStruct {
         key: &'static str,
         value: &dyn SerializeForVersion,
     ) -> Result<(), Error> {
+        let separator = if self.fields.is_empty() { 0 } else { 1 };
+        charge(|| separator + encoded_len(key) + ":".len())?;
         let value = value.serialize(Serializer::new(self.version))?;
         self.fields.insert(key.to_owned(), value);
         Ok(())
@@ -145,8 +301,13 @@ impl SerializeStruct {
         len: usize,
         values: &mut dyn Iterator<Item = impl SerializeForVersion>,
     ) -> Result<(), Error> {
+        let separator = if self.fields.is_empty() { 0 } else { 1 };
+        charge(|| separator + encoded_len(key) + ":".len())?;
+        // The sequence is charged as it is built, so it is inserted directly
+        // rather than through `serialize_field`, which would charge it again.
         let seq = Serializer::new(self.version).serialize_iter(len, values)?;
-        self.serialize_field(key, &seq)
+        self.fields.insert(key.to_owned(), seq);
+        Ok(())
     }
 
     /// Skips serialization if it's [`None`].
@@ -720,4 +881,62 @@ mod tests {
             assert_eq!(encoded, expected);
         }
     }
+
+    mod size_limit {
+        use super::*;
+
+        fn serialize() -> Result<Ok, Error> {
+            let mut uut = Serializer::default().serialize_struct()?;
+            uut.serialize_iter("a", 2, &mut [1u64, 2].iter())?;
+            uut.serialize_field("b", &"c")?;
+            uut.end()
+        }
+
+        #[test]
+        fn is_exact() {
+            let expected = json!({"a": [1, 2], "b": "c"});
+            let len = expected.to_string().len();
+
+            let encoded = with_size_limit(len, serialize).unwrap().unwrap();
+            assert_eq!(encoded, expected);
+
+            with_size_limit(len - 1, serialize).unwrap_err();
+        }
+
+        #[test]
+        fn aborts_serialization() {
+            let mut calls = 0;
+            let result = with_size_limit(2, || {
+                Serializer::default()
+                    .serialize_iter(3, &mut ["a", "b", "c"].into_iter().inspect(|_| calls += 1))
+            });
+
+            assert!(result.is_err());
+            assert_eq!(calls, 1);
+        }
+
+        #[test]
+        fn raw() {
+            let value = json!({"a": [1, 2], "b": "c"});
+            let len = value.to_string().len();
+
+            let encoded = with_size_limit(len, || to_raw_json(&value))
+                .unwrap()
+                .unwrap();
+            assert_eq!(encoded.get(), value.to_string());
+
+            with_size_limit(len - 1, || to_raw_json(&value)).unwrap_err();
+        }
+
+        #[test]
+        fn budget_is_restored() {
+            with_size_limit(1, serialize).unwrap_err();
+            with_size_limit(100, || {
+                with_size_limit(1, serialize).unwrap_err();
+                serialize().unwrap();
+            })
+            .unwrap();
+            serialize().unwrap();
+        }
+    }
 }
@@ -11,6 +11,7 @@ impl crate::dto::DeserializeForVersion for pathfinder_common::BlockId {
             match value.as_str() {
                 "latest" => Ok(Self::Latest),
                 "pending" => Ok(Self::Pending),
+                "l1_accepted" => Ok(Self::L1Accepted),
                 _ => Err(serde_json::Error::custom("Invalid block id")),
             }
         } else {
@@ -59,7 +60,7 @@ impl crate::dto::SerializeForVersion for pathfinder_common::BlockHeader {
                 price_in_fri: self.strk_l1_data_gas_price,
             },
         )?;
-        if serializer.version == RpcVersion::V08 {
+        if matches!(serializer.version, RpcVersion::V08 | RpcVersion::V09) {
             serializer.serialize_field(
                 "l2_gas_price",
                 &ResourcePrice {
@@ -103,7 +104,7 @@ impl crate::dto::SerializeForVersion for starknet_gateway_types::reply::PendingB
                 price_in_fri: self.l1_data_gas_price.price_in_fri,
             },
         )?;
-        if serializer.version == RpcVersion::V08 {
+        if matches!(serializer.version, RpcVersion::V08 | RpcVersion::V09) {
             serializer.serialize_field(
                 "l2_gas_price",
                 &ResourcePrice {
@@ -123,8 +124,8 @@ impl crate::dto::SerializeForVersion for starknet_gateway_types::reply::PendingB
     }
 }
 
-#[derive(Debug)]
-struct ResourcePrice {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourcePrice {
     pub price_in_wei: GasPrice,
     pub price_in_fri: GasPrice,
 }
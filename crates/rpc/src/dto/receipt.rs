@@ -11,6 +11,12 @@ use crate::{dto, RpcVersion};
 #[derive(Copy, Clone)]
 pub enum TxnStatus {
     Received,
+    /// Received by the sequencer and scheduled for the block being built.
+    /// Serialized as `RECEIVED` before RPC v0.9.
+    Candidate,
+    /// Executed as part of the block being built. Serialized as
+    /// `ACCEPTED_ON_L2` before RPC v0.9.
+    PreConfirmed,
     Rejected,
     AcceptedOnL2,
     AcceptedOnL1,
@@ -41,6 +47,9 @@ struct TxnExecutionStatusWithRevertReason<'a>(pub &'a pathfinder_common::receipt
 
 #[derive(Copy, Clone)]
 pub enum TxnFinalityStatus {
+    /// Executed as part of the block being built. Serialized as
+    /// `ACCEPTED_ON_L2` before RPC v0.9.
+    PreConfirmed,
     AcceptedOnL2,
     AcceptedOnL1,
 }
@@ -82,8 +91,13 @@ pub struct ComputationResources<'a>(pub &'a pathfinder_common::receipt::Executio
 
 impl SerializeForVersion for TxnStatus {
     fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let pre_confirmation = serializer.version >= RpcVersion::V09;
         match self {
             TxnStatus::Received => "RECEIVED",
+            TxnStatus::Candidate if pre_confirmation => "CANDIDATE",
+            TxnStatus::Candidate => "RECEIVED",
+            TxnStatus::PreConfirmed if pre_confirmation => "PRE_CONFIRMED",
+            TxnStatus::PreConfirmed => "ACCEPTED_ON_L2",
             TxnStatus::Rejected => "REJECTED",
             TxnStatus::AcceptedOnL2 => "ACCEPTED_ON_L2",
             TxnStatus::AcceptedOnL1 => "ACCEPTED_ON_L1",
@@ -130,6 +144,10 @@ impl SerializeForVersion for TxnExecutionStatusWithRevertReason<'_> {
 impl SerializeForVersion for TxnFinalityStatus {
     fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
         match self {
+            TxnFinalityStatus::PreConfirmed if serializer.version >= RpcVersion::V09 => {
+                "PRE_CONFIRMED"
+            }
+            TxnFinalityStatus::PreConfirmed => "ACCEPTED_ON_L2",
             TxnFinalityStatus::AcceptedOnL2 => "ACCEPTED_ON_L2",
             TxnFinalityStatus::AcceptedOnL1 => "ACCEPTED_ON_L1",
         }
@@ -467,6 +485,33 @@ mod tests {
         assert_eq!(encoded, expected);
     }
 
+    #[rstest]
+    #[case::v07(RpcVersion::V07, "RECEIVED", "ACCEPTED_ON_L2", "ACCEPTED_ON_L2")]
+    #[case::v08(RpcVersion::V08, "RECEIVED", "ACCEPTED_ON_L2", "ACCEPTED_ON_L2")]
+    #[case::v09(RpcVersion::V09, "CANDIDATE", "PRE_CONFIRMED", "PRE_CONFIRMED")]
+    fn pre_confirmation_statuses(
+        #[case] version: RpcVersion,
+        #[case] candidate: &str,
+        #[case] pre_confirmed: &str,
+        #[case] pre_confirmed_finality: &str,
+    ) {
+        let serializer = Serializer::new(version);
+        assert_eq!(
+            TxnStatus::Candidate.serialize(serializer).unwrap(),
+            json!(candidate)
+        );
+        assert_eq!(
+            TxnStatus::PreConfirmed.serialize(serializer).unwrap(),
+            json!(pre_confirmed)
+        );
+        assert_eq!(
+            TxnFinalityStatus::PreConfirmed
+                .serialize(serializer)
+                .unwrap(),
+            json!(pre_confirmed_finality)
+        );
+    }
+
     #[rstest]
     #[case::succeeded(TxnExecutionStatus::Succeeded, "SUCCEEDED")]
     #[case::reverted_missing_reason(TxnExecutionStatus::Reverted { reason: None }, "REVERTED")]
@@ -122,7 +122,7 @@ impl crate::dto::SerializeForVersion for &pathfinder_executor::types::FunctionIn
         serializer.serialize_iter("messages", self.messages.len(), &mut self.messages.iter())?;
         serializer.serialize_iter("result", self.result.len(), &mut self.result.iter())?;
         match serializer.version {
-            RpcVersion::V08 => {
+            RpcVersion::V08 | RpcVersion::V09 => {
                 serializer.serialize_field(
                     "execution_resources",
                     &InnerCallExecutionResources(&self.execution_resources),
@@ -380,7 +380,7 @@ impl crate::dto::SerializeForVersion for pathfinder_executor::types::ExecutionRe
         serializer: crate::dto::Serializer,
     ) -> Result<crate::dto::Ok, crate::dto::Error> {
         match serializer.version {
-            RpcVersion::V08 => {
+            RpcVersion::V08 | RpcVersion::V09 => {
                 let mut serializer = serializer.serialize_struct()?;
                 serializer.serialize_field("l1_gas", &self.l1_gas)?;
                 serializer.serialize_field("l1_data_gas", &self.l1_data_gas)?;
@@ -0,0 +1,432 @@
+//! Golden file tests for the serialization of DTOs in every [RpcVersion].
+//!
+//! Each fixture is rendered for every version and compared against
+//! `fixtures/snapshots/<version>/<fixture>.json`, so that a change to the
+//! output of any version, such as a field renamed between v0.7 and v0.8, shows
+//! up as a diff of the golden files at review time.
+//!
+//! Run `just update-snapshots`, i.e. the tests with `UPDATE_SNAPSHOTS=1`, to
+//! regenerate the golden files after an intended change. Missing golden files
+//! are written by a regular run as well, except on CI.
+
+use std::path::{Path, PathBuf};
+
+use pathfinder_common::event::Event;
+use pathfinder_common::macro_prelude::*;
+use pathfinder_common::receipt::{ExecutionStatus, L2ToL1Message, Receipt};
+use pathfinder_common::transaction::{Transaction, TransactionVariant};
+use pathfinder_common::{
+    BlockHeader,
+    BlockNumber,
+    BlockTimestamp,
+    GasPrice,
+    L1DataAvailabilityMode,
+    StarknetVersion,
+    StateUpdate,
+    TransactionHash,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_executor::types as executor;
+use primitive_types::U256;
+
+use super::{SerializeForVersion, Serializer};
+use crate::RpcVersion;
+
+const VERSIONS: [RpcVersion; 4] = [
+    RpcVersion::V07,
+    RpcVersion::V08,
+    RpcVersion::V09,
+    RpcVersion::PathfinderV01,
+];
+
+fn snapshot_dir(version: RpcVersion) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures/snapshots")
+        .join(version.to_str())
+}
+
+fn header() -> BlockHeader {
+    BlockHeader::builder()
+        .number(BlockNumber::new_or_panic(7))
+        .timestamp(BlockTimestamp::new_or_panic(1_700_000_000))
+        .eth_l1_gas_price(GasPrice(10))
+        .strk_l1_gas_price(GasPrice(11))
+        .eth_l1_data_gas_price(GasPrice(12))
+        .strk_l1_data_gas_price(GasPrice(13))
+        .eth_l2_gas_price(GasPrice(14))
+        .strk_l2_gas_price(GasPrice(15))
+        .sequencer_address(sequencer_address!("0x5e9"))
+        .l1_da_mode(L1DataAvailabilityMode::Blob)
+        .starknet_version(StarknetVersion::new(0, 13, 4, 0))
+        .parent_hash(block_hash!("0xb6"))
+        .state_commitment(state_commitment!("0x5c7"))
+        .finalize_with_hash(block_hash!("0xb7"))
+}
+
+fn transactions() -> Vec<(&'static str, Transaction)> {
+    use TransactionVariant::*;
+
+    [
+        ("declare_v0", DeclareV0(Default::default())),
+        ("declare_v1", DeclareV1(Default::default())),
+        ("declare_v2", DeclareV2(Default::default())),
+        ("declare_v3", DeclareV3(Default::default())),
+        ("deploy_v0", DeployV0(Default::default())),
+        ("deploy_v1", DeployV1(Default::default())),
+        ("deploy_account_v1", DeployAccountV1(Default::default())),
+        ("deploy_account_v3", DeployAccountV3(Default::default())),
+        ("invoke_v0", InvokeV0(Default::default())),
+        ("invoke_v1", InvokeV1(Default::default())),
+        ("invoke_v3", InvokeV3(Default::default())),
+        ("l1_handler", L1Handler(Default::default())),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(i, (name, variant))| {
+        let transaction = Transaction {
+            hash: TransactionHash(Felt::from_u64(0x7000 + i as u64)),
+            variant,
+        };
+        (name, transaction)
+    })
+    .collect()
+}
+
+fn receipt(transaction: &Transaction, execution_status: ExecutionStatus) -> Receipt {
+    Receipt {
+        actual_fee: fee!("0x100"),
+        l2_to_l1_messages: vec![L2ToL1Message {
+            from_address: contract_address!("0xc1"),
+            payload: vec![l2_to_l1_message_payload_elem!("0x1")],
+            to_address: contract_address!("0xe1"),
+        }],
+        execution_status,
+        transaction_hash: transaction.hash,
+        ..Default::default()
+    }
+}
+
+fn events() -> Vec<Event> {
+    vec![Event {
+        data: vec![event_data!("0xda7a")],
+        from_address: contract_address!("0xc1"),
+        keys: vec![event_key!("0x4e1")],
+    }]
+}
+
+fn state_update() -> StateUpdate {
+    StateUpdate::default()
+        .with_block_hash(block_hash!("0xb7"))
+        .with_parent_state_commitment(state_commitment!("0x5c6"))
+        .with_state_commitment(state_commitment!("0x5c7"))
+        .with_declared_cairo_class(class_hash!("0xca1"))
+        .with_declared_sierra_class(sierra_hash!("0x51e"), casm_hash!("0xca5"))
+        .with_deployed_contract(contract_address!("0xc1"), class_hash!("0xca1"))
+        .with_storage_update(
+            contract_address!("0xc1"),
+            storage_address!("0x5a"),
+            storage_value!("0x5b"),
+        )
+        .with_contract_nonce(contract_address!("0xc1"), contract_nonce!("0x2"))
+        .with_replaced_class(contract_address!("0xc2"), class_hash!("0xca2"))
+}
+
+fn fee_estimate() -> executor::FeeEstimate {
+    executor::FeeEstimate {
+        l1_gas_consumed: U256::from(1),
+        l1_gas_price: U256::from(2),
+        l1_data_gas_consumed: U256::from(3),
+        l1_data_gas_price: U256::from(4),
+        l2_gas_consumed: U256::from(5),
+        l2_gas_price: U256::from(6),
+        overall_fee: U256::from(44),
+        unit: executor::PriceUnit::Fri,
+    }
+}
+
+fn function_invocation() -> executor::FunctionInvocation {
+    executor::FunctionInvocation {
+        calldata: vec![felt!("0x1")],
+        contract_address: contract_address!("0xc1"),
+        selector: felt!("0x5e1"),
+        call_type: executor::CallType::Call,
+        caller_address: felt!("0xc0"),
+        internal_calls: vec![],
+        class_hash: Some(felt!("0xca1")),
+        entry_point_type: executor::EntryPointType::External,
+        events: vec![executor::Event {
+            order: 0,
+            data: vec![felt!("0xda7a")],
+            keys: vec![felt!("0x4e1")],
+        }],
+        messages: vec![executor::MsgToL1 {
+            order: 1,
+            payload: vec![felt!("0x1")],
+            to_address: felt!("0xe1"),
+            from_address: felt!("0xc1"),
+        }],
+        result: vec![felt!("0x0")],
+        computation_resources: executor::ComputationResources {
+            steps: 100,
+            range_check_builtin_applications: 2,
+            ..Default::default()
+        },
+        execution_resources: executor::InnerCallExecutionResources {
+            l1_gas: 1,
+            l2_gas: 2,
+        },
+        is_reverted: false,
+    }
+}
+
+fn trace_state_diff() -> executor::StateDiff {
+    executor::StateDiff {
+        storage_diffs: [(
+            contract_address!("0xc1"),
+            vec![executor::StorageDiff {
+                key: storage_address!("0x5a"),
+                value: storage_value!("0x5b"),
+            }],
+        )]
+        .into(),
+        deployed_contracts: vec![executor::DeployedContract {
+            address: contract_address!("0xc1"),
+            class_hash: class_hash!("0xca1"),
+        }],
+        deprecated_declared_classes: [class_hash!("0xca0")].into(),
+        declared_classes: vec![executor::DeclaredSierraClass {
+            class_hash: sierra_hash!("0x51e"),
+            compiled_class_hash: casm_hash!("0xca5"),
+        }],
+        nonces: [(contract_address!("0xc1"), contract_nonce!("0x2"))].into(),
+        replaced_classes: vec![executor::ReplacedClass {
+            contract_address: contract_address!("0xc2"),
+            class_hash: class_hash!("0xca2"),
+        }],
+    }
+}
+
+fn trace_execution_resources() -> executor::ExecutionResources {
+    executor::ExecutionResources {
+        computation_resources: executor::ComputationResources {
+            steps: 300,
+            memory_holes: 1,
+            pedersen_builtin_applications: 3,
+            ..Default::default()
+        },
+        data_availability: executor::DataAvailabilityResources {
+            l1_gas: 4,
+            l1_data_gas: 5,
+        },
+        l1_gas: 6,
+        l1_data_gas: 7,
+        l2_gas: 8,
+    }
+}
+
+fn traces() -> Vec<(&'static str, executor::TransactionTrace)> {
+    vec![
+        (
+            "trace_invoke",
+            executor::TransactionTrace::Invoke(executor::InvokeTransactionTrace {
+                validate_invocation: Some(function_invocation()),
+                execute_invocation: executor::ExecuteInvocation::FunctionInvocation(Some(
+                    function_invocation(),
+                )),
+                fee_transfer_invocation: Some(function_invocation()),
+                state_diff: trace_state_diff(),
+                execution_resources: trace_execution_resources(),
+            }),
+        ),
+        (
+            "trace_invoke_reverted",
+            executor::TransactionTrace::Invoke(executor::InvokeTransactionTrace {
+                validate_invocation: Some(function_invocation()),
+                execute_invocation: executor::ExecuteInvocation::RevertedReason(
+                    "Insufficient balance".to_owned(),
+                ),
+                fee_transfer_invocation: None,
+                state_diff: Default::default(),
+                execution_resources: trace_execution_resources(),
+            }),
+        ),
+        (
+            "trace_declare",
+            executor::TransactionTrace::Declare(executor::DeclareTransactionTrace {
+                validate_invocation: Some(function_invocation()),
+                fee_transfer_invocation: Some(function_invocation()),
+                state_diff: trace_state_diff(),
+                execution_resources: trace_execution_resources(),
+            }),
+        ),
+        (
+            "trace_deploy_account",
+            executor::TransactionTrace::DeployAccount(executor::DeployAccountTransactionTrace {
+                validate_invocation: Some(function_invocation()),
+                constructor_invocation: Some(function_invocation()),
+                fee_transfer_invocation: Some(function_invocation()),
+                state_diff: trace_state_diff(),
+                execution_resources: trace_execution_resources(),
+            }),
+        ),
+        (
+            "trace_l1_handler",
+            executor::TransactionTrace::L1Handler(executor::L1HandlerTransactionTrace {
+                function_invocation: Some(function_invocation()),
+                state_diff: trace_state_diff(),
+                execution_resources: trace_execution_resources(),
+            }),
+        ),
+    ]
+}
+
+/// Renders all fixtures in `version`.
+fn render(version: RpcVersion) -> Vec<(String, serde_json::Value)> {
+    let serializer = Serializer::new(version);
+    let mut rendered = Vec::new();
+    let mut add = |name: &str, value: &dyn SerializeForVersion| {
+        let value = match serializer.serialize(value) {
+            Ok(value) => value,
+            Err(error) => serde_json::json!({ "serialization_error": error.to_string() }),
+        };
+        rendered.push((name.to_owned(), value));
+    };
+
+    let header = header();
+    add("block_header", &header);
+    let pending = starknet_gateway_types::reply::PendingBlock {
+        parent_hash: header.parent_hash,
+        timestamp: header.timestamp,
+        sequencer_address: header.sequencer_address,
+        ..Default::default()
+    };
+    add("pending_block", &pending);
+
+    for (name, transaction) in transactions() {
+        add(
+            &format!("transaction_{name}"),
+            &super::TransactionWithHash(&transaction),
+        );
+
+        let receipt = receipt(&transaction, ExecutionStatus::Succeeded);
+        let events = events();
+        add(
+            &format!("receipt_{name}"),
+            &super::TxnReceiptWithBlockInfo {
+                block_hash: Some(&header.hash),
+                block_number: Some(header.number),
+                receipt: &receipt,
+                transaction: &transaction,
+                events: &events,
+                finality: super::TxnFinalityStatus::AcceptedOnL2,
+            },
+        );
+    }
+
+    let (_, transaction) = transactions().pop().expect("There are transactions");
+    let receipt = receipt(
+        &transaction,
+        ExecutionStatus::Reverted {
+            reason: "Insufficient balance".to_owned(),
+        },
+    );
+    add(
+        "receipt_reverted_pending",
+        &super::TxnReceiptWithBlockInfo {
+            block_hash: None,
+            block_number: None,
+            receipt: &receipt,
+            transaction: &transaction,
+            events: &[],
+            finality: super::TxnFinalityStatus::AcceptedOnL2,
+        },
+    );
+
+    let events = events();
+    add(
+        "event",
+        &super::Event {
+            address: &events[0].from_address,
+            keys: &events[0].keys,
+            data: &events[0].data,
+        },
+    );
+
+    let state_update = state_update();
+    add("state_update", &super::StateUpdate(&state_update));
+    add(
+        "pending_state_update",
+        &super::PendingStateUpdate(&state_update),
+    );
+
+    add("fee_estimate", &fee_estimate());
+
+    for (name, trace) in traces() {
+        add(
+            name,
+            &super::TransactionTrace {
+                trace,
+                include_state_diff: true,
+            },
+        );
+    }
+    let simulation = executor::TransactionSimulation {
+        trace: traces().swap_remove(0).1,
+        fee_estimation: fee_estimate(),
+    };
+    add("simulation", &simulation);
+
+    rendered
+}
+
+#[test]
+fn serialization_matches_snapshots() {
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    let on_ci = std::env::var_os("CI").is_some();
+    let mut failures = Vec::new();
+
+    for version in VERSIONS {
+        let dir = snapshot_dir(version);
+        for (name, value) in render(version) {
+            let path = dir.join(format!("{name}.json"));
+            let actual = serde_json::to_string_pretty(&value).unwrap() + "\n";
+
+            let expected = std::fs::read_to_string(&path).ok();
+            if expected.as_ref() == Some(&actual) {
+                continue;
+            }
+
+            if update || (expected.is_none() && !on_ci) {
+                std::fs::create_dir_all(&dir).unwrap();
+                std::fs::write(&path, actual).unwrap();
+                continue;
+            }
+
+            match expected {
+                Some(expected) => {
+                    let diff = expected
+                        .lines()
+                        .zip(actual.lines())
+                        .enumerate()
+                        .find(|(_, (expected, actual))| expected != actual)
+                        .map(|(line, (expected, actual))| {
+                            format!(
+                                "line {}\n  expected: {expected}\n  actual:   {actual}",
+                                line + 1
+                            )
+                        })
+                        .unwrap_or_else(|| "number of lines differs".to_owned());
+                    failures.push(format!("{}: {diff}", path.display()));
+                }
+                None => failures.push(format!("{}: missing", path.display())),
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "Serialization differs from the snapshots, run `just update-snapshots` if this is \
+         intended:\n{}",
+        failures.join("\n")
+    );
+}
@@ -9,6 +9,8 @@ use pathfinder_common::{
     StorageAddress,
     StorageValue,
 };
+use serde::ser::{SerializeMap, SerializeStruct};
+use serde_json::value::RawValue;
 
 use crate::dto;
 use crate::dto::{SerializeForVersion, Serializer};
@@ -37,6 +39,10 @@ impl SerializeForVersion for StateUpdate<'_> {
 
         serializer.end()
     }
+
+    fn serialize_raw(&self, _: Serializer) -> Option<Result<Box<RawValue>, crate::dto::Error>> {
+        Some(dto::to_raw_json(self))
+    }
 }
 
 impl SerializeForVersion for PendingStateUpdate<'_> {
@@ -48,6 +54,10 @@ impl SerializeForVersion for PendingStateUpdate<'_> {
 
         serializer.end()
     }
+
+    fn serialize_raw(&self, _: Serializer) -> Option<Result<Box<RawValue>, crate::dto::Error>> {
+        Some(dto::to_raw_json(self))
+    }
 }
 
 impl SerializeForVersion for StateDiff<'_> {
@@ -240,3 +250,222 @@ impl SerializeForVersion for DeployedContractItem<'_> {
         serializer.end()
     }
 }
+
+// The following [serde::Serialize] implementations back
+// [SerializeForVersion::serialize_raw]. State diffs can contain hundreds of
+// thousands of storage entries and encoding them directly saves building a
+// [serde_json::Value] for each of them. The output must match the
+// [SerializeForVersion] implementations above.
+
+impl serde::Serialize for StateUpdate<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut serializer = serializer.serialize_struct("StateUpdate", 4)?;
+        serializer.serialize_field("block_hash", &Hex(&self.0.block_hash.0))?;
+        serializer.serialize_field("old_root", &Hex(&self.0.state_commitment.0))?;
+        serializer.serialize_field("new_root", &Hex(&self.0.parent_state_commitment.0))?;
+        serializer.serialize_field("state_diff", &StateDiff(self.0))?;
+        serializer.end()
+    }
+}
+
+impl serde::Serialize for PendingStateUpdate<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut serializer = serializer.serialize_struct("PendingStateUpdate", 2)?;
+        serializer.serialize_field("old_root", &Hex(&self.0.state_commitment.0))?;
+        serializer.serialize_field("state_diff", &StateDiff(self.0))?;
+        serializer.end()
+    }
+}
+
+impl serde::Serialize for StateDiff<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        /// Serializes the items of an iterator as a JSON array without
+        /// collecting them first.
+        struct Seq<F>(F);
+
+        impl<F, I> serde::Serialize for Seq<F>
+        where
+            F: Fn() -> I,
+            I: Iterator,
+            I::Item: serde::Serialize,
+        {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq((self.0)())
+            }
+        }
+
+        /// A JSON object with two fields.
+        struct Pair<A, B>(&'static str, A, &'static str, B);
+
+        impl<A: serde::Serialize, B: serde::Serialize> serde::Serialize for Pair<A, B> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut serializer = serializer.serialize_map(Some(2))?;
+                serializer.serialize_entry(self.0, &self.1)?;
+                serializer.serialize_entry(self.2, &self.3)?;
+                serializer.end()
+            }
+        }
+
+        let update = self.0;
+        let storage_diffs = || {
+            let contract_diffs = update.contract_updates.iter().map(|(a, u)| (a, &u.storage));
+            let system_diffs = update
+                .system_contract_updates
+                .iter()
+                .map(|(a, u)| (a, &u.storage));
+            contract_diffs
+                .chain(system_diffs)
+                .filter(|(_, storage)| !storage.is_empty())
+                .map(|(address, storage)| {
+                    Pair(
+                        "address",
+                        Hex(&address.0),
+                        "storage_entries",
+                        Seq(move || {
+                            storage.iter().map(|(key, value)| {
+                                Pair("key", Hex(&key.0), "value", Hex(&value.0))
+                            })
+                        }),
+                    )
+                })
+        };
+        let deprecated_declared_classes = || {
+            update
+                .declared_cairo_classes
+                .iter()
+                .map(|class| Hex(&class.0))
+        };
+        let declared_classes = || {
+            update.declared_sierra_classes.iter().map(|(sierra, casm)| {
+                Pair(
+                    "class_hash",
+                    Hex(&sierra.0),
+                    "compiled_class_hash",
+                    Hex(&casm.0),
+                )
+            })
+        };
+        let deployed_contracts = || {
+            update
+                .contract_updates
+                .iter()
+                .filter_map(|(address, update)| {
+                    update.deployed_class().map(|class_hash| {
+                        Pair("address", Hex(&address.0), "class_hash", Hex(&class_hash.0))
+                    })
+                })
+        };
+        let replaced_classes = || {
+            update
+                .contract_updates
+                .iter()
+                .filter_map(|(address, update)| {
+                    update.replaced_class().map(|class_hash| {
+                        Pair(
+                            "contract_address",
+                            Hex(&address.0),
+                            "class_hash",
+                            Hex(&class_hash.0),
+                        )
+                    })
+                })
+        };
+        let nonces = || {
+            update
+                .contract_updates
+                .iter()
+                .filter_map(|(address, update)| {
+                    update.nonce.as_ref().map(|nonce| {
+                        Pair("contract_address", Hex(&address.0), "nonce", Hex(&nonce.0))
+                    })
+                })
+        };
+
+        let mut serializer = serializer.serialize_struct("StateDiff", 6)?;
+        serializer.serialize_field("storage_diffs", &Seq(storage_diffs))?;
+        serializer.serialize_field(
+            "deprecated_declared_classes",
+            &Seq(deprecated_declared_classes),
+        )?;
+        serializer.serialize_field("declared_classes", &Seq(declared_classes))?;
+        serializer.serialize_field("deployed_contracts", &Seq(deployed_contracts))?;
+        serializer.serialize_field("replaced_classes", &Seq(replaced_classes))?;
+        serializer.serialize_field("nonces", &Seq(nonces))?;
+        serializer.end()
+    }
+}
+
+/// Serializes a felt the same way as its [SerializeForVersion] implementation.
+struct Hex<'a>(&'a pathfinder_crypto::Felt);
+
+impl serde::Serialize for Hex<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&dto::hex_str::bytes_to_hex_str_stripped(
+            self.0.as_be_bytes(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    fn state_update() -> pathfinder_common::StateUpdate {
+        pathfinder_common::StateUpdate::default()
+            .with_block_hash(block_hash!("0x1"))
+            .with_state_commitment(state_commitment!("0x2"))
+            .with_parent_state_commitment(state_commitment!("0x3"))
+            .with_storage_update(
+                contract_address!("0x10"),
+                storage_address!("0x11"),
+                storage_value!("0x12"),
+            )
+            .with_storage_update(
+                contract_address!("0x10"),
+                storage_address!("0x13"),
+                storage_value!("0x14"),
+            )
+            .with_system_storage_update(
+                ContractAddress::ONE,
+                storage_address!("0x15"),
+                storage_value!("0x16"),
+            )
+            .with_contract_nonce(contract_address!("0x10"), contract_nonce!("0x17"))
+            .with_deployed_contract(contract_address!("0x20"), class_hash!("0x21"))
+            .with_replaced_class(contract_address!("0x30"), class_hash!("0x31"))
+            .with_declared_sierra_class(sierra_hash!("0x40"), casm_hash!("0x41"))
+            .with_declared_cairo_class(class_hash!("0x50"))
+    }
+
+    #[test]
+    fn raw_serialization_matches() {
+        let state_update = state_update();
+
+        let expected = StateUpdate(&state_update)
+            .serialize(Serializer::default())
+            .unwrap();
+        let raw = StateUpdate(&state_update)
+            .serialize_raw(Serializer::default())
+            .unwrap()
+            .unwrap();
+        let raw: serde_json::Value = serde_json::from_str(raw.get()).unwrap();
+        assert_eq!(raw, expected);
+    }
+
+    #[test]
+    fn raw_serialization_matches_for_pending() {
+        let state_update = state_update();
+
+        let expected = PendingStateUpdate(&state_update)
+            .serialize(Serializer::default())
+            .unwrap();
+        let raw = PendingStateUpdate(&state_update)
+            .serialize_raw(Serializer::default())
+            .unwrap()
+            .unwrap();
+        let raw: serde_json::Value = serde_json::from_str(raw.get()).unwrap();
+        assert_eq!(raw, expected);
+    }
+}
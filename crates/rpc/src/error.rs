@@ -5,7 +5,7 @@
 //! be used by each JSON-RPC method to trivially create its subset of
 //! [ApplicationError] along with the boilerplate involved.
 #![macro_use]
-use pathfinder_common::TransactionHash;
+use pathfinder_common::{BlockNumber, TransactionHash};
 use serde_json::json;
 
 #[derive(serde::Serialize, Clone, Copy, Debug)]
@@ -101,12 +101,20 @@ pub enum ApplicationError {
     StorageProofNotSupported,
     #[error("Proof is missing")]
     ProofMissing,
+    #[error("Invalid event index")]
+    InvalidEventIndex,
+    #[error("Requested events have been pruned")]
+    EventsPruned { available_from: BlockNumber },
     #[error("Invalid subscription id")]
     InvalidSubscriptionID,
     #[error("Too many addresses in filter sender_address filter")]
     TooManyAddressesInFilter,
     #[error("This method does not support being called on the pending block")]
     CallOnPending,
+    #[error("Execution refused by the node's execution policy")]
+    ExecutionRefused(pathfinder_executor::ExecutionRefused),
+    #[error("Idempotency key was already used for a different request")]
+    IdempotencyKeyReused,
     /// Internal errors are errors whose details we don't want to show to the
     /// end user. These are logged, and a simple "internal error" message is
     /// shown to the end user.
@@ -120,6 +128,17 @@ pub enum ApplicationError {
 }
 
 impl ApplicationError {
+    /// Wraps an internal error, unless it was caused by reading events which
+    /// have been pruned which is reported as [ApplicationError::EventsPruned].
+    pub(crate) fn internal(error: anyhow::Error) -> Self {
+        match error.downcast_ref::<pathfinder_storage::EventsPruned>() {
+            Some(pruned) => Self::EventsPruned {
+                available_from: pruned.available_from,
+            },
+            None => Self::Internal(error),
+        }
+    }
+
     pub fn code(&self) -> i32 {
         match self {
             // Taken from the official starknet json rpc api.
@@ -158,6 +177,10 @@ impl ApplicationError {
             // doc/rpc/pathfinder_rpc_api.json
             ApplicationError::ProofLimitExceeded { .. } => 10000,
             ApplicationError::ProofMissing => 10001,
+            ApplicationError::InvalidEventIndex => 10002,
+            ApplicationError::EventsPruned { .. } => 10003,
+            ApplicationError::ExecutionRefused(_) => 10004,
+            ApplicationError::IdempotencyKeyReused => 10005,
             ApplicationError::SubscriptionTransactionHashNotFound { .. } => 10029,
             ApplicationError::SubscriptionGatewayDown { .. } => 10030,
             // doc/rpc/starknet_ws_api.json
@@ -227,7 +250,7 @@ impl ApplicationError {
                 error,
                 error_stack,
             } => match version {
-                RpcVersion::V08 => {
+                RpcVersion::V08 | RpcVersion::V09 => {
                     let error_stack = error_stack_frames_to_json(&error_stack.0);
                     Some(json!({
                         "transaction_index": transaction_index,
@@ -257,7 +280,7 @@ impl ApplicationError {
                 revert_error,
                 revert_error_stack,
             } => match version {
-                RpcVersion::V08 => {
+                RpcVersion::V08 | RpcVersion::V09 => {
                     let revert_error_stack = error_stack_frames_to_json(&revert_error_stack.0);
                     Some(json!({
                         "revert_error": revert_error_stack
@@ -278,6 +301,20 @@ impl ApplicationError {
             })),
             ApplicationError::StorageProofNotSupported => None,
             ApplicationError::ProofMissing => None,
+            ApplicationError::InvalidEventIndex => None,
+            ApplicationError::EventsPruned { available_from } => Some(json!({
+                "available_from": available_from,
+            })),
+            ApplicationError::ExecutionRefused(refused) => {
+                let mut data = json!({
+                    "contract_address": refused.contract_address,
+                });
+                if let Some(selector) = refused.selector {
+                    data["selector"] = json!(selector);
+                }
+                Some(data)
+            }
+            ApplicationError::IdempotencyKeyReused => None,
             ApplicationError::SubscriptionTransactionHashNotFound {
                 subscription_id,
                 transaction_hash,
@@ -349,6 +386,9 @@ fn error_stack_frames_to_json(frames: &[pathfinder_executor::Frame]) -> serde_js
 /// 3. `impl From<NewEnum> for RpcError`
 /// 4. `impl From<anyhow::Error> for NewEnum`, mapping to the `Internal` variant
 ///
+/// Internal errors caused by reading pruned events are reported as
+/// [ApplicationError::EventsPruned], see [ApplicationError::internal].
+///
 /// ## Example with expansion
 /// This macro invocation:
 /// ```ignore
@@ -371,7 +411,7 @@ fn error_stack_frames_to_json(frames: &[pathfinder_executor::Frame]) -> serde_js
 ///         match x {
 ///             MyError::BlockNotFound => Self::BlockNotFound,
 ///             MyError::NoBlocks => Self::NoBlocks,
-///             MyError::Internal(internal) => Self::Internal(internal),
+///             MyError::Internal(internal) => Self::internal(internal),
 ///         }
 ///     }
 /// }
@@ -453,7 +493,7 @@ macro_rules! generate_rpc_error_subset {
     (@parse, $var:ident, $enum_name:ident, {$($arms:tt)*}, $(,)*) => {
         match $var {
             $($arms)*
-            $enum_name::Internal(internal) => Self::Internal(internal),
+            $enum_name::Internal(internal) => Self::internal(internal),
             $enum_name::Custom(error) => Self::Custom(error),
         }
     };
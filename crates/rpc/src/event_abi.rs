@@ -0,0 +1,696 @@
+//! Decoding of events using the ABI of the class which emitted them.
+//!
+//! Both Cairo 0 and Sierra ABIs are supported. ABIs are provided by whoever
+//! declared the class and are not verified by Starknet, so an event which
+//! does not match its ABI exactly is treated as undecodable rather than as an
+//! error.
+//!
+//! Felts, addresses and hashes are decoded as hex strings and integers as
+//! decimal strings. Structs become objects, arrays and tuples become arrays
+//! and enums become an object holding the value under the variant's name.
+
+use std::collections::{HashMap, HashSet};
+
+use pathfinder_common::{EntryPoint, EventData, EventKey};
+use pathfinder_crypto::Felt;
+use serde_json::{Map, Value};
+
+/// Limits how deeply types may nest, as ABIs are arbitrary user input.
+const MAX_DEPTH: usize = 32;
+
+/// The parts of a class's ABI needed to decode the events it emits.
+#[derive(Debug, Default)]
+pub(crate) struct EventAbi {
+    /// Cairo 0 events by selector, i.e. the first key of the emitted event.
+    cairo_events: HashMap<Felt, CairoEvent>,
+    /// Sierra events by name.
+    events: HashMap<String, Event>,
+    /// Sierra event enums which are not a variant of another event, i.e. the
+    /// `Event` enum of the contract.
+    root_events: Vec<String>,
+    /// Structs and enums by name.
+    types: HashMap<String, Type>,
+}
+
+#[derive(Debug)]
+struct CairoEvent {
+    name: String,
+    keys: Vec<Member>,
+    data: Vec<Member>,
+}
+
+#[derive(Debug)]
+enum Event {
+    Struct {
+        keys: Vec<Member>,
+        data: Vec<Member>,
+    },
+    Enum(Vec<EventVariant>),
+}
+
+#[derive(Debug)]
+struct EventVariant {
+    name: String,
+    r#type: String,
+    /// Flat variants do not add their selector to the keys.
+    flat: bool,
+}
+
+#[derive(Debug)]
+enum Type {
+    Struct(Vec<Member>),
+    Enum(Vec<Member>),
+}
+
+/// A struct member or enum variant.
+#[derive(Debug)]
+struct Member {
+    name: String,
+    r#type: String,
+}
+
+/// An event decoded by [EventAbi::decode].
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct DecodedEvent {
+    /// Name of the event, fully qualified for Sierra classes.
+    pub name: String,
+    /// The event's keys and data by member name.
+    pub fields: Map<String, Value>,
+}
+
+impl EventAbi {
+    /// Extracts the ABI from a class definition. Returns [None] if the class
+    /// has no ABI or it is not valid JSON.
+    pub(crate) fn from_definition(definition: &[u8]) -> Option<Self> {
+        #[derive(serde::Deserialize)]
+        struct Definition {
+            #[serde(default)]
+            abi: Value,
+        }
+
+        let definition = serde_json::from_slice::<Definition>(definition).ok()?;
+        let abi = match definition.abi {
+            // Sierra classes hold their ABI as a JSON encoded string.
+            Value::String(abi) => serde_json::from_str(&abi).ok()?,
+            abi => abi,
+        };
+
+        let mut result = Self::default();
+        for entry in abi.as_array()? {
+            // Entries which cannot be parsed are skipped, events using them
+            // will simply not be decodable.
+            let _ = result.add_entry(entry);
+        }
+
+        let variants = result
+            .events
+            .values()
+            .filter_map(|event| match event {
+                Event::Enum(variants) => Some(variants),
+                Event::Struct { .. } => None,
+            })
+            .flatten()
+            .map(|variant| variant.r#type.as_str())
+            .collect::<HashSet<_>>();
+        result.root_events = result
+            .events
+            .iter()
+            .filter(|(name, event)| {
+                matches!(event, Event::Enum(_)) && !variants.contains(name.as_str())
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        Some(result)
+    }
+
+    fn add_entry(&mut self, entry: &Value) -> Option<()> {
+        let name = entry.get("name")?.as_str()?.to_owned();
+
+        match entry.get("type")?.as_str()? {
+            "struct" => {
+                let members = members(entry.get("members")?)?;
+                self.types.insert(name, Type::Struct(members));
+            }
+            "enum" => {
+                let variants = members(entry.get("variants")?)?;
+                self.types.insert(name, Type::Enum(variants));
+            }
+            "event" => match entry.get("kind").and_then(Value::as_str) {
+                // Cairo 0 events have no kind.
+                None => {
+                    let keys = match entry.get("keys") {
+                        Some(keys) => members(keys)?,
+                        None => vec![],
+                    };
+                    let data = members(entry.get("data")?)?;
+                    let selector = EntryPoint::hashed(name.as_bytes()).0;
+                    self.cairo_events
+                        .insert(selector, CairoEvent { name, keys, data });
+                }
+                Some("struct") => {
+                    let mut keys = vec![];
+                    let mut data = vec![];
+                    for member in entry.get("members")?.as_array()? {
+                        let parsed = Member {
+                            name: member.get("name")?.as_str()?.to_owned(),
+                            r#type: member.get("type")?.as_str()?.to_owned(),
+                        };
+                        match member.get("kind")?.as_str()? {
+                            "key" => keys.push(parsed),
+                            "data" => data.push(parsed),
+                            _ => return None,
+                        }
+                    }
+                    self.events.insert(name, Event::Struct { keys, data });
+                }
+                Some("enum") => {
+                    let variants = entry
+                        .get("variants")?
+                        .as_array()?
+                        .iter()
+                        .map(|variant| {
+                            let flat = match variant.get("kind")?.as_str()? {
+                                "nested" => false,
+                                "flat" => true,
+                                _ => return None,
+                            };
+                            Some(EventVariant {
+                                name: variant.get("name")?.as_str()?.to_owned(),
+                                r#type: variant.get("type")?.as_str()?.to_owned(),
+                                flat,
+                            })
+                        })
+                        .collect::<Option<Vec<_>>>()?;
+                    self.events.insert(name, Event::Enum(variants));
+                }
+                Some(_) => return None,
+            },
+            _ => {}
+        }
+
+        Some(())
+    }
+
+    /// Decodes an event emitted by a contract of this class. Returns [None] if
+    /// no event of the ABI matches the keys and data exactly.
+    pub(crate) fn decode(&self, keys: &[EventKey], data: &[EventData]) -> Option<DecodedEvent> {
+        let keys = keys.iter().map(|key| key.0).collect::<Vec<_>>();
+        let data = data.iter().map(|data| data.0).collect::<Vec<_>>();
+
+        if let Some(event) = keys.first().and_then(|key| self.cairo_events.get(key)) {
+            let mut fields = Map::new();
+            let (mut keys, mut data) = (&keys[1..], data.as_slice());
+            self.decode_members(&event.keys, &mut keys, &mut fields, 0)?;
+            self.decode_members(&event.data, &mut data, &mut fields, 0)?;
+
+            return (keys.is_empty() && data.is_empty()).then(|| DecodedEvent {
+                name: event.name.clone(),
+                fields,
+            });
+        }
+
+        self.root_events.iter().find_map(|root| {
+            let (mut keys, mut data) = (keys.as_slice(), data.as_slice());
+            let decoded = self.decode_event(root, &mut keys, &mut data, 0)?;
+            (keys.is_empty() && data.is_empty()).then_some(decoded)
+        })
+    }
+
+    fn decode_event(
+        &self,
+        name: &str,
+        keys: &mut &[Felt],
+        data: &mut &[Felt],
+        depth: usize,
+    ) -> Option<DecodedEvent> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        match self.events.get(name)? {
+            Event::Struct {
+                keys: key_members,
+                data: data_members,
+            } => {
+                let mut fields = Map::new();
+                self.decode_members(key_members, keys, &mut fields, depth)?;
+                self.decode_members(data_members, data, &mut fields, depth)?;
+                Some(DecodedEvent {
+                    name: name.to_owned(),
+                    fields,
+                })
+            }
+            Event::Enum(variants) => variants.iter().find_map(|variant| {
+                let (mut variant_keys, mut variant_data) = (*keys, *data);
+                if !variant.flat
+                    && take(&mut variant_keys)? != EntryPoint::hashed(variant.name.as_bytes()).0
+                {
+                    return None;
+                }
+
+                let decoded = self.decode_event(
+                    &variant.r#type,
+                    &mut variant_keys,
+                    &mut variant_data,
+                    depth + 1,
+                )?;
+                *keys = variant_keys;
+                *data = variant_data;
+                Some(decoded)
+            }),
+        }
+    }
+
+    fn decode_members(
+        &self,
+        members: &[Member],
+        felts: &mut &[Felt],
+        fields: &mut Map<String, Value>,
+        depth: usize,
+    ) -> Option<()> {
+        // Cairo 0 pointers are preceded by a member holding their length.
+        let mut len = None;
+        for member in members {
+            let value = match member.r#type.strip_suffix('*') {
+                Some(element) => self.decode_array(element, len.take()?, felts, depth)?,
+                None => {
+                    len = (member.r#type == "felt")
+                        .then(|| felts.first().copied().and_then(to_usize))
+                        .flatten();
+                    self.decode_value(&member.r#type, felts, depth)?
+                }
+            };
+            fields.insert(member.name.clone(), value);
+        }
+        Some(())
+    }
+
+    fn decode_array(
+        &self,
+        element: &str,
+        len: usize,
+        felts: &mut &[Felt],
+        depth: usize,
+    ) -> Option<Value> {
+        // Bounds the work done for bogus lengths.
+        if len > felts.len() {
+            return None;
+        }
+        (0..len)
+            .map(|_| self.decode_value(element, felts, depth))
+            .collect::<Option<Vec<_>>>()
+            .map(Value::Array)
+    }
+
+    fn decode_value(&self, r#type: &str, felts: &mut &[Felt], depth: usize) -> Option<Value> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        let depth = depth + 1;
+
+        if let Some(elements) = tuple_elements(r#type) {
+            return elements
+                .into_iter()
+                .map(|element| self.decode_value(element, felts, depth))
+                .collect::<Option<Vec<_>>>()
+                .map(Value::Array);
+        }
+        if let Some(element) =
+            generic_argument(r#type, &["core::array::Array", "core::array::Span"])
+        {
+            let len = to_usize(take(felts)?)?;
+            return self.decode_array(element, len, felts, depth);
+        }
+        if let Some(inner) =
+            generic_argument(r#type, &["core::box::Box", "core::zeroable::NonZero"])
+        {
+            return self.decode_value(inner, felts, depth);
+        }
+
+        match r#type {
+            "felt"
+            | "core::felt252"
+            | "core::bytes_31::bytes31"
+            | "core::starknet::contract_address::ContractAddress"
+            | "core::starknet::class_hash::ClassHash"
+            | "core::starknet::eth_address::EthAddress"
+            | "core::starknet::storage_access::StorageAddress" => {
+                return Some(Value::String(take(felts)?.to_hex_str().into_owned()))
+            }
+            "core::bool" => {
+                let value = take(felts)?;
+                return (value == Felt::ZERO || value == Felt::ONE)
+                    .then(|| Value::Bool(value == Felt::ONE));
+            }
+            "core::integer::u8" => return unsigned(take(felts)?, 8),
+            "core::integer::u16" => return unsigned(take(felts)?, 16),
+            "core::integer::u32" | "core::integer::usize" => return unsigned(take(felts)?, 32),
+            "core::integer::u64" => return unsigned(take(felts)?, 64),
+            "core::integer::u128" => return unsigned(take(felts)?, 128),
+            "core::integer::i8" => return signed(take(felts)?, 8),
+            "core::integer::i16" => return signed(take(felts)?, 16),
+            "core::integer::i32" => return signed(take(felts)?, 32),
+            "core::integer::i64" => return signed(take(felts)?, 64),
+            "core::integer::i128" => return signed(take(felts)?, 128),
+            "core::integer::u256" => {
+                let low = take(felts)?;
+                let high = take(felts)?;
+                if low.high_u128() != 0 || high.high_u128() != 0 {
+                    return None;
+                }
+                let value = (primitive_types::U256::from(high.low_u128()) << 128)
+                    | primitive_types::U256::from(low.low_u128());
+                return Some(Value::String(value.to_string()));
+            }
+            "core::byte_array::ByteArray" => return byte_array(felts),
+            "()" => return Some(Value::Null),
+            _ => {}
+        }
+
+        match self.types.get(r#type)? {
+            Type::Struct(members) => {
+                let mut fields = Map::new();
+                self.decode_members(members, felts, &mut fields, depth)?;
+                Some(Value::Object(fields))
+            }
+            Type::Enum(variants) => {
+                let variant = variants.get(to_usize(take(felts)?)?)?;
+                let value = self.decode_value(&variant.r#type, felts, depth)?;
+                Some(Value::Object(Map::from_iter([(
+                    variant.name.clone(),
+                    value,
+                )])))
+            }
+        }
+    }
+}
+
+fn members(members: &Value) -> Option<Vec<Member>> {
+    members
+        .as_array()?
+        .iter()
+        .map(|member| {
+            Some(Member {
+                name: member.get("name")?.as_str()?.to_owned(),
+                r#type: member.get("type")?.as_str()?.to_owned(),
+            })
+        })
+        .collect()
+}
+
+fn take(felts: &mut &[Felt]) -> Option<Felt> {
+    let (first, rest) = felts.split_first()?;
+    *felts = rest;
+    Some(*first)
+}
+
+fn to_usize(felt: Felt) -> Option<usize> {
+    if felt.high_u128() != 0 {
+        return None;
+    }
+    usize::try_from(felt.low_u128()).ok()
+}
+
+fn unsigned(felt: Felt, bits: u32) -> Option<Value> {
+    let fits = felt.high_u128() == 0 && felt.low_u128().checked_shr(bits).unwrap_or(0) == 0;
+    fits.then(|| Value::String(felt.to_dec_string()))
+}
+
+/// Negative values are represented as `P - |value|`.
+fn signed(felt: Felt, bits: u32) -> Option<Value> {
+    let max = 1u128 << (bits - 1);
+    let negated = Felt::ZERO - felt;
+
+    if felt.high_u128() == 0 && felt.low_u128() < max {
+        Some(Value::String(felt.to_dec_string()))
+    } else if negated.high_u128() == 0 && negated.low_u128() <= max {
+        Some(Value::String(format!("-{}", negated.to_dec_string())))
+    } else {
+        None
+    }
+}
+
+/// Serialized as the full 31 byte words, followed by the last partial word
+/// and its length in bytes.
+fn byte_array(felts: &mut &[Felt]) -> Option<Value> {
+    let words = to_usize(take(felts)?)?;
+    if words > felts.len() {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(words * 31 + 31);
+    for _ in 0..words {
+        bytes.extend_from_slice(&take(felts)?.as_be_bytes()[1..]);
+    }
+    let pending_word = take(felts)?;
+    let pending_len = to_usize(take(felts)?)?;
+    if pending_len > 30 {
+        return None;
+    }
+    bytes.extend_from_slice(&pending_word.as_be_bytes()[32 - pending_len..]);
+
+    Some(Value::String(String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+/// The element types of a tuple type such as `(core::felt252, core::bool)`.
+fn tuple_elements(r#type: &str) -> Option<Vec<&str>> {
+    let inner = r#type.strip_prefix('(')?.strip_suffix(')')?;
+    if inner.trim().is_empty() {
+        // The unit type.
+        return None;
+    }
+
+    let mut elements = vec![];
+    let mut nesting = 0usize;
+    let mut start = 0;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '(' | '<' => nesting += 1,
+            ')' | '>' => nesting = nesting.checked_sub(1)?,
+            ',' if nesting == 0 => {
+                elements.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    elements.push(inner[start..].trim());
+
+    Some(elements)
+}
+
+/// The type argument of a generic type such as `core::array::Array::<T>`.
+fn generic_argument<'a>(r#type: &'a str, generics: &[&str]) -> Option<&'a str> {
+    generics.iter().find_map(|generic| {
+        r#type
+            .strip_prefix(generic)?
+            .strip_prefix("::<")?
+            .strip_suffix('>')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use serde_json::json;
+
+    use super::*;
+
+    fn selector(name: &str) -> EventKey {
+        EventKey(EntryPoint::hashed(name.as_bytes()).0)
+    }
+
+    fn sierra_abi(abi: Value) -> EventAbi {
+        let definition = json!({
+            "sierra_program": [],
+            "abi": abi.to_string(),
+        });
+        EventAbi::from_definition(definition.to_string().as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn cairo_event() {
+        let definition = json!({
+            "abi": [
+                {
+                    "type": "struct",
+                    "name": "Uint256",
+                    "size": 2,
+                    "members": [
+                        {"name": "low", "type": "felt", "offset": 0},
+                        {"name": "high", "type": "felt", "offset": 1}
+                    ]
+                },
+                {
+                    "type": "event",
+                    "name": "Transfer",
+                    "keys": [],
+                    "data": [
+                        {"name": "from_", "type": "felt"},
+                        {"name": "amount", "type": "Uint256"},
+                        {"name": "memo_len", "type": "felt"},
+                        {"name": "memo", "type": "felt*"}
+                    ]
+                }
+            ]
+        });
+        let abi = EventAbi::from_definition(definition.to_string().as_bytes()).unwrap();
+
+        let data = [
+            event_data!("0x1"),
+            event_data!("0x2"),
+            event_data!("0x0"),
+            event_data!("0x2"),
+            event_data!("0xa"),
+            event_data!("0xb"),
+        ];
+        let decoded = abi.decode(&[selector("Transfer")], &data).unwrap();
+        assert_eq!(decoded.name, "Transfer");
+        assert_eq!(
+            Value::Object(decoded.fields),
+            json!({
+                "from_": "0x1",
+                "amount": {"low": "0x2", "high": "0x0"},
+                "memo_len": "0x2",
+                "memo": ["0xa", "0xb"],
+            })
+        );
+
+        // Trailing data does not match the ABI.
+        let mut data = data.to_vec();
+        data.push(event_data!("0xc"));
+        assert_eq!(abi.decode(&[selector("Transfer")], &data), None);
+    }
+
+    #[test]
+    fn sierra_event() {
+        let abi = sierra_abi(json!([
+            {
+                "type": "struct",
+                "name": "core::integer::u256",
+                "members": [
+                    {"name": "low", "type": "core::integer::u128"},
+                    {"name": "high", "type": "core::integer::u128"}
+                ]
+            },
+            {
+                "type": "enum",
+                "name": "core::option::Option::<core::integer::i8>",
+                "variants": [
+                    {"name": "Some", "type": "core::integer::i8"},
+                    {"name": "None", "type": "()"}
+                ]
+            },
+            {
+                "type": "event",
+                "name": "token::Transfer",
+                "kind": "struct",
+                "members": [
+                    {
+                        "name": "from",
+                        "type": "core::starknet::contract_address::ContractAddress",
+                        "kind": "key"
+                    },
+                    {"name": "value", "type": "core::integer::u256", "kind": "data"},
+                    {
+                        "name": "delta",
+                        "type": "core::option::Option::<core::integer::i8>",
+                        "kind": "data"
+                    },
+                    {"name": "memo", "type": "core::byte_array::ByteArray", "kind": "data"},
+                    {"name": "flags", "type": "core::array::Span::<core::bool>", "kind": "data"}
+                ]
+            },
+            {
+                "type": "event",
+                "name": "token::Event",
+                "kind": "enum",
+                "variants": [
+                    {"name": "Transfer", "type": "token::Transfer", "kind": "nested"}
+                ]
+            }
+        ]));
+
+        let keys = [selector("Transfer"), event_key!("0x1")];
+        let data = [
+            // u256::MAX
+            EventData(Felt::from_u128(u128::MAX)),
+            EventData(Felt::from_u128(u128::MAX)),
+            // Some(-1)
+            event_data!("0x0"),
+            EventData(Felt::ZERO - Felt::ONE),
+            // "hi"
+            event_data!("0x0"),
+            EventData(Felt::from_be_slice(b"hi").unwrap()),
+            event_data!("0x2"),
+            // [true, false]
+            event_data!("0x2"),
+            event_data!("0x1"),
+            event_data!("0x0"),
+        ];
+        let decoded = abi.decode(&keys, &data).unwrap();
+        assert_eq!(decoded.name, "token::Transfer");
+        assert_eq!(
+            Value::Object(decoded.fields),
+            json!({
+                "from": "0x1",
+                "value": primitive_types::U256::MAX.to_string(),
+                "delta": {"Some": "-1"},
+                "memo": "hi",
+                "flags": [true, false],
+            })
+        );
+
+        // Unknown selector.
+        assert_eq!(abi.decode(&[selector("Approval")], &data), None);
+    }
+
+    #[test]
+    fn flat_component_event() {
+        let abi = sierra_abi(json!([
+            {
+                "type": "event",
+                "name": "component::Paused",
+                "kind": "struct",
+                "members": [
+                    {"name": "account", "type": "core::felt252", "kind": "data"}
+                ]
+            },
+            {
+                "type": "event",
+                "name": "component::Event",
+                "kind": "enum",
+                "variants": [
+                    {"name": "Paused", "type": "component::Paused", "kind": "nested"}
+                ]
+            },
+            {
+                "type": "event",
+                "name": "contract::Event",
+                "kind": "enum",
+                "variants": [
+                    {"name": "PausableEvent", "type": "component::Event", "kind": "flat"}
+                ]
+            }
+        ]));
+
+        let decoded = abi
+            .decode(&[selector("Paused")], &[event_data!("0x5")])
+            .unwrap();
+        assert_eq!(decoded.name, "component::Paused");
+        assert_eq!(Value::Object(decoded.fields), json!({"account": "0x5"}));
+    }
+
+    #[test]
+    fn integers_out_of_range_are_rejected() {
+        assert_eq!(unsigned(Felt::from_u64(255), 8), Some(json!("255")));
+        assert_eq!(unsigned(Felt::from_u64(256), 8), None);
+        assert_eq!(signed(Felt::from_u64(127), 8), Some(json!("127")));
+        assert_eq!(
+            signed(Felt::ZERO - Felt::from_u64(128), 8),
+            Some(json!("-128"))
+        );
+        assert_eq!(signed(Felt::from_u64(128), 8), None);
+    }
+}
@@ -25,6 +25,10 @@ use crate::types::request::{
 };
 use crate::types::SierraContractClass;
 
+mod sierra_mapping;
+
+pub(crate) use sierra_mapping::{annotate_call_error, annotate_execution_error};
+
 pub enum ExecutionStateError {
     BlockNotFound,
     Internal(anyhow::Error),
@@ -36,8 +40,11 @@ impl From<anyhow::Error> for ExecutionStateError {
     }
 }
 
-pub const VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY:
-    StarknetVersion = StarknetVersion::new(0, 13, 1, 1);
+/// Whether blocks of `version` can be traced locally. Traces of older blocks
+/// are fetched from the feeder gateway instead.
+pub fn can_trace_locally(version: StarknetVersion) -> bool {
+    pathfinder_executor::Backend::for_version(version).is_some()
+}
 
 pub(crate) fn map_broadcasted_transaction(
     transaction: &BroadcastedTransaction,
@@ -201,9 +208,7 @@ fn map_transaction_variant(
     match variant {
         TransactionVariant::DeclareV0(tx) => {
             let tx = starknet_api::transaction::DeclareTransactionV0V1 {
-                max_fee: Fee(u128::from_be_bytes(
-                    tx.max_fee.0.to_be_bytes()[16..].try_into().unwrap(),
-                )),
+                max_fee: Fee(tx.max_fee.0.low_u128()),
                 signature: TransactionSignature(
                     tx.signature.iter().map(|s| s.0.into_starkfelt()).collect(),
                 ),
@@ -221,9 +226,7 @@ fn map_transaction_variant(
         }
         TransactionVariant::DeclareV1(tx) => {
             let tx = starknet_api::transaction::DeclareTransactionV0V1 {
-                max_fee: Fee(u128::from_be_bytes(
-                    tx.max_fee.0.to_be_bytes()[16..].try_into().unwrap(),
-                )),
+                max_fee: Fee(tx.max_fee.0.low_u128()),
                 signature: TransactionSignature(
                     tx.signature.iter().map(|s| s.0.into_starkfelt()).collect(),
                 ),
@@ -241,9 +244,7 @@ fn map_transaction_variant(
         }
         TransactionVariant::DeclareV2(tx) => {
             let tx = starknet_api::transaction::DeclareTransactionV2 {
-                max_fee: Fee(u128::from_be_bytes(
-                    tx.max_fee.0.to_be_bytes()[16..].try_into().unwrap(),
-                )),
+                max_fee: Fee(tx.max_fee.0.low_u128()),
                 signature: TransactionSignature(
                     tx.signature.iter().map(|s| s.0.into_starkfelt()).collect(),
                 ),
@@ -310,9 +311,7 @@ fn map_transaction_variant(
         TransactionVariant::DeployAccountV1(tx) => {
             let tx = starknet_api::transaction::DeployAccountTransaction::V1(
                 starknet_api::transaction::DeployAccountTransactionV1 {
-                    max_fee: Fee(u128::from_be_bytes(
-                        tx.max_fee.0.to_be_bytes()[16..].try_into().unwrap(),
-                    )),
+                    max_fee: Fee(tx.max_fee.0.low_u128()),
                     signature: TransactionSignature(
                         tx.signature.iter().map(|s| s.0.into_starkfelt()).collect(),
                     ),
@@ -376,9 +375,7 @@ fn map_transaction_variant(
         TransactionVariant::InvokeV0(tx) => {
             let tx = starknet_api::transaction::InvokeTransactionV0 {
                 // TODO: maybe we should store tx.max_fee as u128 internally?
-                max_fee: Fee(u128::from_be_bytes(
-                    tx.max_fee.0.to_be_bytes()[16..].try_into().unwrap(),
-                )),
+                max_fee: Fee(tx.max_fee.0.low_u128()),
                 signature: TransactionSignature(
                     tx.signature.iter().map(|s| s.0.into_starkfelt()).collect(),
                 ),
@@ -401,9 +398,7 @@ fn map_transaction_variant(
         TransactionVariant::InvokeV1(tx) => {
             let tx = starknet_api::transaction::InvokeTransactionV1 {
                 // TODO: maybe we should store tx.max_fee as u128 internally?
-                max_fee: Fee(u128::from_be_bytes(
-                    tx.max_fee.0.to_be_bytes()[16..].try_into().unwrap(),
-                )),
+                max_fee: Fee(tx.max_fee.0.low_u128()),
                 signature: TransactionSignature(
                     tx.signature.iter().map(|s| s.0.into_starkfelt()).collect(),
                 ),
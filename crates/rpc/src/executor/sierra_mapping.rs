@@ -0,0 +1,217 @@
+//! Annotates the raw CASM program counters reported in execution error stacks
+//! with the Sierra statement and function they correspond to.
+
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use anyhow::Context;
+use pathfinder_common::ClassHash;
+use pathfinder_compiler::SierraStatementMap;
+use pathfinder_executor::{CallError, ErrorStack, Frame, TransactionExecutionError};
+use util::cache::{EvictionPolicy, WeightedCache};
+
+const PC_PREFIX: &str = "pc=0:";
+
+type StatementMaps = WeightedCache<ClassHash, Option<Arc<SierraStatementMap>>>;
+
+/// Statement maps by class hash, so that the same failing class is only
+/// compiled once. Classes which cannot be mapped are cached as `None`.
+static STATEMENT_MAPS: LazyLock<Mutex<StatementMaps>> = LazyLock::new(|| {
+    Mutex::new(WeightedCache::new(
+        NonZeroUsize::new(128),
+        None,
+        EvictionPolicy::Lru,
+    ))
+});
+
+pub(crate) fn annotate_call_error(
+    db: &pathfinder_storage::Transaction<'_>,
+    mut error: CallError,
+) -> CallError {
+    if let CallError::ContractError(_, error_stack) = &mut error {
+        annotate_error_stack(db, error_stack);
+    }
+    error
+}
+
+pub(crate) fn annotate_execution_error(
+    db: &pathfinder_storage::Transaction<'_>,
+    mut error: TransactionExecutionError,
+) -> TransactionExecutionError {
+    if let TransactionExecutionError::ExecutionError { error_stack, .. } = &mut error {
+        annotate_error_stack(db, error_stack);
+    }
+    error
+}
+
+/// Rewrites the string frames of the error stack so that every program counter
+/// in them is followed by its Sierra location. A string frame belongs to the
+/// call frame preceding it, and only frames of Sierra classes can be mapped.
+///
+/// Mapping requires recompiling the class, so this is only done when an error
+/// is actually returned and the result is cached. Any failure leaves the frame
+/// untouched.
+fn annotate_error_stack(db: &pathfinder_storage::Transaction<'_>, error_stack: &mut ErrorStack) {
+    let mut maps: HashMap<ClassHash, Option<Arc<SierraStatementMap>>> = HashMap::new();
+    let mut class_hash = None;
+
+    for frame in error_stack.0.iter_mut() {
+        match frame {
+            Frame::CallFrame(call_frame) => class_hash = Some(call_frame.class_hash),
+            Frame::StringFrame(message) => {
+                let Some(class_hash) = class_hash else {
+                    continue;
+                };
+                if !message.contains(PC_PREFIX) {
+                    continue;
+                }
+                let map = maps
+                    .entry(class_hash)
+                    .or_insert_with(|| statement_map(db, class_hash));
+                if let Some(map) = map {
+                    *message = annotate_pcs(message, |pc| {
+                        map.locate(pc).map(|location| location.to_string())
+                    });
+                }
+            }
+        }
+    }
+}
+
+fn statement_map(
+    db: &pathfinder_storage::Transaction<'_>,
+    class_hash: ClassHash,
+) -> Option<Arc<SierraStatementMap>> {
+    cached_statement_map(class_hash, || compile_statement_map(db, class_hash))
+}
+
+/// Returns the cached statement map of the class, compiling it on a miss.
+/// Failures to fetch the class are not cached.
+fn cached_statement_map(
+    class_hash: ClassHash,
+    compile: impl FnOnce() -> anyhow::Result<Option<SierraStatementMap>>,
+) -> Option<Arc<SierraStatementMap>> {
+    if let Some(map) = STATEMENT_MAPS.lock().unwrap().get(&class_hash) {
+        return map.clone();
+    }
+
+    // Compiled without holding the lock, concurrent misses may compile the
+    // same class twice.
+    let map = match compile() {
+        Ok(map) => map.map(Arc::new),
+        Err(error) => {
+            tracing::debug!(%class_hash, %error, "Failed to fetch class definition");
+            return None;
+        }
+    };
+    STATEMENT_MAPS
+        .lock()
+        .unwrap()
+        .set(class_hash, map.clone(), 1);
+
+    map
+}
+
+fn compile_statement_map(
+    db: &pathfinder_storage::Transaction<'_>,
+    class_hash: ClassHash,
+) -> anyhow::Result<Option<SierraStatementMap>> {
+    let definition = db
+        .class_definition(class_hash)?
+        .context("Class definition not found")?;
+
+    // Cairo 0 classes have no Sierra program to map to.
+    match pathfinder_compiler::sierra_statement_map(&definition) {
+        Ok(map) => Ok(Some(map)),
+        Err(error) => {
+            tracing::trace!(%class_hash, %error, "No Sierra statement mapping available");
+            Ok(None)
+        }
+    }
+}
+
+/// Appends `, <location>` after every `pc=0:<offset>` in the message for which
+/// `locate` returns a location.
+fn annotate_pcs(message: &str, locate: impl Fn(usize) -> Option<String>) -> String {
+    let mut annotated = String::with_capacity(message.len());
+    let mut rest = message;
+
+    while let Some(start) = rest.find(PC_PREFIX) {
+        let digits_start = start + PC_PREFIX.len();
+        let digits_len = rest[digits_start..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len() - digits_start);
+        let end = digits_start + digits_len;
+
+        annotated.push_str(&rest[..end]);
+        if let Some(location) = rest[digits_start..end].parse().ok().and_then(&locate) {
+            annotated.push_str(", ");
+            annotated.push_str(&location);
+        }
+        rest = &rest[end..];
+    }
+    annotated.push_str(rest);
+
+    annotated
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn annotates_every_pc() {
+        let message = "Error at pc=0:4273:\nCairo traceback (most recent call last):\nUnknown \
+                       location (pc=0:67)\nUnknown location (pc=0:1997)\n";
+
+        let annotated = annotate_pcs(message, |pc| (pc != 1997).then(|| format!("<{pc}>")));
+
+        assert_eq!(
+            annotated,
+            "Error at pc=0:4273, <4273>:\nCairo traceback (most recent call last):\nUnknown \
+             location (pc=0:67, <67>)\nUnknown location (pc=0:1997)\n"
+        );
+    }
+
+    #[test]
+    fn message_without_pcs_is_unchanged() {
+        let message = "Execution failed. Failure reason: 0x496e70757420746f6f206c6f6e67.";
+        assert_eq!(annotate_pcs(message, |_| Some("x".to_owned())), message);
+    }
+
+    #[test]
+    fn pc_at_end_of_message() {
+        assert_eq!(
+            annotate_pcs("pc=0:12", |pc| Some(pc.to_string())),
+            "pc=0:12, 12"
+        );
+        assert_eq!(annotate_pcs("pc=0:", |_| Some("x".to_owned())), "pc=0:");
+    }
+
+    #[test]
+    fn statement_maps_are_cached() {
+        let mut compilations = 0;
+
+        let cairo_0 = class_hash!("0x5e1e5a0");
+        for _ in 0..2 {
+            let map = cached_statement_map(cairo_0, || {
+                compilations += 1;
+                Ok(None)
+            });
+            assert!(map.is_none());
+        }
+        assert_eq!(compilations, 1);
+
+        let missing = class_hash!("0x5e1e5a1");
+        for _ in 0..2 {
+            cached_statement_map(missing, || {
+                compilations += 1;
+                Err(anyhow::anyhow!("Class definition not found"))
+            });
+        }
+        assert_eq!(compilations, 3);
+    }
+}
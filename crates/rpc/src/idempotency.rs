@@ -0,0 +1,314 @@
+//! Responses of transaction submissions keyed by client provided idempotency
+//! keys.
+//!
+//! Clients on flaky connections often retry a submission whose response got
+//! lost. Forwarding the retry to the gateway fails with a duplicate
+//! transaction or nonce error, so instead the response of the first successful
+//! submission is returned for as long as it is cached.
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{DefaultHasher, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// Returned when an idempotency key is reused for a different request.
+#[derive(Debug, thiserror::Error)]
+#[error("Idempotency key was already used for a different request")]
+pub struct IdempotencyKeyReused;
+
+/// Entries are keyed by the response type, i.e. by the submitting method, and
+/// the client provided key.
+type Key = (TypeId, String);
+
+#[derive(Clone)]
+pub struct SubmissionCache {
+    entries: Arc<Mutex<HashMap<Key, Entry>>>,
+    ttl: Duration,
+}
+
+struct Entry {
+    /// Hash of the request submitted with the key, see [request_hash].
+    request: u64,
+    state: State,
+}
+
+enum State {
+    /// The submission is still being performed. The sender is dropped once it
+    /// completes.
+    InFlight(watch::Receiver<()>),
+    Done {
+        response: Box<dyn Any + Send>,
+        expires_at: Instant,
+    },
+}
+
+impl Entry {
+    fn is_expired(&self, now: Instant) -> bool {
+        match self.state {
+            State::InFlight(_) => false,
+            State::Done { expires_at, .. } => expires_at <= now,
+        }
+    }
+}
+
+/// Hashes a request so that reuses of a key for different requests can be
+/// detected. The debug representation covers all fields of the request.
+pub(crate) fn request_hash(request: &impl std::fmt::Debug) -> u64 {
+    struct HashWriter(DefaultHasher);
+
+    impl std::fmt::Write for HashWriter {
+        fn write_str(&mut self, s: &str) -> std::fmt::Result {
+            self.0.write(s.as_bytes());
+            Ok(())
+        }
+    }
+
+    let mut writer = HashWriter(DefaultHasher::new());
+    std::fmt::Write::write_fmt(&mut writer, format_args!("{request:?}"))
+        .expect("Hashing cannot fail");
+    writer.0.finish()
+}
+
+/// Removes the entry of a submission unless it completed successfully, so
+/// that the key can be retried and waiting retries are woken up.
+struct InFlightGuard<'a> {
+    cache: &'a SubmissionCache,
+    key: Option<Key>,
+    _done: watch::Sender<()>,
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.cache.entries.lock().unwrap().remove(&key);
+        }
+    }
+}
+
+impl Default for SubmissionCache {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(300))
+    }
+}
+
+impl SubmissionCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Default::default(),
+            ttl,
+        }
+    }
+
+    /// Performs `submission` unless an earlier submission with the same key
+    /// succeeded within the TTL, in which case its response is returned.
+    /// Retries arriving while the first submission is still in flight wait for
+    /// its outcome instead of being submitted concurrently.
+    ///
+    /// `request` is the [request_hash] of the submitted request. Reusing a key
+    /// for a different request fails with [IdempotencyKeyReused].
+    ///
+    /// Failed submissions are not cached so that they can be retried. Keys are
+    /// scoped to the response type, i.e. to the submitting method.
+    pub async fn submit<T, E>(
+        &self,
+        key: Option<String>,
+        request: u64,
+        submission: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E>
+    where
+        T: Clone + Send + 'static,
+        E: From<IdempotencyKeyReused>,
+    {
+        let Some(key) = key else {
+            return submission.await;
+        };
+        let key = (TypeId::of::<T>(), key);
+
+        let mut guard = loop {
+            let mut in_flight = {
+                let now = Instant::now();
+                let mut entries = self.entries.lock().unwrap();
+                entries.retain(|_, entry| !entry.is_expired(now));
+
+                match entries.get(&key) {
+                    Some(entry) if entry.request != request => {
+                        return Err(IdempotencyKeyReused.into());
+                    }
+                    Some(Entry {
+                        state: State::Done { response, .. },
+                        ..
+                    }) => {
+                        metrics::increment_counter!("rpc_idempotent_submission_hits_total");
+                        let response = response
+                            .downcast_ref::<T>()
+                            .expect("Keys are scoped to the response type");
+                        return Ok(response.clone());
+                    }
+                    Some(Entry {
+                        state: State::InFlight(in_flight),
+                        ..
+                    }) => in_flight.clone(),
+                    None => {
+                        let (done, in_flight) = watch::channel(());
+                        entries.insert(
+                            key.clone(),
+                            Entry {
+                                request,
+                                state: State::InFlight(in_flight),
+                            },
+                        );
+                        break InFlightGuard {
+                            cache: self,
+                            key: Some(key),
+                            _done: done,
+                        };
+                    }
+                }
+            };
+
+            // Only fails once the in-flight submission completed, after which
+            // its entry is looked up again.
+            let _ = in_flight.changed().await;
+        };
+
+        let response = submission.await?;
+
+        let key = guard.key.take().expect("Key is set until completion");
+        self.entries.lock().unwrap().insert(
+            key,
+            Entry {
+                request,
+                state: State::Done {
+                    response: Box::new(response.clone()),
+                    expires_at: Instant::now() + self.ttl,
+                },
+            },
+        );
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    #[derive(Debug, PartialEq)]
+    enum Error {
+        Submission,
+        KeyReused,
+    }
+
+    impl From<IdempotencyKeyReused> for Error {
+        fn from(_: IdempotencyKeyReused) -> Self {
+            Self::KeyReused
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_return_first_response() {
+        let cache = SubmissionCache::new(TTL);
+        let key = || Some("key".to_owned());
+
+        let first = cache.submit(key(), 0, async { Ok::<_, Error>(1u32) }).await;
+        let retry = cache
+            .submit(key(), 0, async { Err::<u32, _>(Error::Submission) })
+            .await;
+        assert_eq!(first, Ok(1));
+        assert_eq!(retry, Ok(1));
+
+        tokio::time::advance(TTL).await;
+        let expired = cache.submit(key(), 0, async { Ok::<_, Error>(2u32) }).await;
+        assert_eq!(expired, Ok(2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failures_are_not_cached() {
+        let cache = SubmissionCache::new(TTL);
+        let key = || Some("key".to_owned());
+
+        let first = cache
+            .submit(key(), 0, async { Err::<u32, _>(Error::Submission) })
+            .await;
+        let retry = cache.submit(key(), 0, async { Ok::<_, Error>(1u32) }).await;
+        assert_eq!(first, Err(Error::Submission));
+        assert_eq!(retry, Ok(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn keys_are_scoped_to_the_response_type() {
+        let cache = SubmissionCache::new(TTL);
+        let key = || Some("key".to_owned());
+
+        cache
+            .submit(key(), 0, async { Ok::<_, Error>(1u32) })
+            .await
+            .unwrap();
+        let other = cache
+            .submit(key(), 1, async { Ok::<_, Error>("other") })
+            .await;
+        assert_eq!(other, Ok("other"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reusing_a_key_for_a_different_request_fails() {
+        let cache = SubmissionCache::new(TTL);
+        let key = || Some("key".to_owned());
+
+        let first = request_hash(&"first");
+        let second = request_hash(&"second");
+        assert_ne!(first, second);
+
+        cache
+            .submit(key(), first, async { Ok::<_, Error>(1u32) })
+            .await
+            .unwrap();
+        let reused = cache
+            .submit(key(), second, async { Ok::<_, Error>(2u32) })
+            .await;
+        assert_eq!(reused, Err(Error::KeyReused));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrent_retries_are_submitted_once() {
+        let cache = SubmissionCache::new(TTL);
+        let key = || Some("key".to_owned());
+        let submissions = std::sync::atomic::AtomicUsize::new(0);
+        let submission = || async {
+            submissions.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            Ok::<_, Error>(1u32)
+        };
+
+        let (first, retry) = tokio::join!(
+            cache.submit(key(), 0, submission()),
+            cache.submit(key(), 0, submission()),
+        );
+        assert_eq!(first, Ok(1));
+        assert_eq!(retry, Ok(1));
+        assert_eq!(submissions.into_inner(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_waiting_on_a_failed_submission_are_submitted() {
+        let cache = SubmissionCache::new(TTL);
+        let key = || Some("key".to_owned());
+
+        let (first, retry) = tokio::join!(
+            cache.submit(key(), 0, async {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                Err::<u32, _>(Error::Submission)
+            }),
+            cache.submit(key(), 0, async { Ok::<_, Error>(1u32) }),
+        );
+        assert_eq!(first, Err(Error::Submission));
+        assert_eq!(retry, Ok(1));
+    }
+}
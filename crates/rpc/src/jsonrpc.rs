@@ -8,8 +8,8 @@ use std::sync::Arc;
 
 pub use error::RpcError;
 use pathfinder_common::{BlockHash, BlockNumber};
-pub use request::RpcRequest;
-pub use response::RpcResponse;
+pub use request::{RawParams, RpcRequest};
+pub use response::{RpcOutput, RpcResponse};
 #[cfg(test)]
 pub use router::handle_json_rpc_socket;
 pub use router::{
@@ -23,6 +23,8 @@ pub use router::{
 use starknet_gateway_types::reply::Block;
 use tokio::sync::broadcast;
 
+use crate::method::subscribe_events::EventFanout;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum RequestId {
     Number(i64),
@@ -44,6 +46,8 @@ pub struct Notifications {
     pub block_headers: broadcast::Sender<Arc<pathfinder_common::BlockHeader>>,
     pub l2_blocks: broadcast::Sender<Arc<Block>>,
     pub reorgs: broadcast::Sender<Arc<Reorg>>,
+    /// Fans out the events of [Self::l2_blocks] to event subscriptions.
+    pub(crate) events: EventFanout,
 }
 
 #[derive(Debug, Clone)]
@@ -59,10 +63,12 @@ impl Default for Notifications {
         let (block_headers, _) = broadcast::channel(1024);
         let (l2_blocks, _) = broadcast::channel(1024);
         let (reorgs, _) = broadcast::channel(1024);
+        let events = EventFanout::new(l2_blocks.clone());
         Self {
             block_headers,
             l2_blocks,
             reorgs,
+            events,
         }
     }
 }
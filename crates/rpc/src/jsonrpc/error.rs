@@ -16,6 +16,15 @@ pub enum RpcError {
         subscription_id: u32,
         reason: String,
     },
+    /// The serialized result of a method exceeded its configured size limit.
+    ResponseTooLarge {
+        limit: usize,
+    },
+    /// The method is only available to authenticated clients.
+    MethodRestricted,
+    /// The node is in read-only degraded mode and does not accept
+    /// transactions.
+    ReadOnly,
 }
 
 impl PartialEq for RpcError {
@@ -38,6 +47,9 @@ impl RpcError {
             RpcError::InternalError(_) => -32603,
             RpcError::ApplicationError(err) => err.code(),
             RpcError::WebsocketSubscriptionClosed { .. } => -32099,
+            RpcError::ResponseTooLarge { .. } => -32098,
+            RpcError::MethodRestricted => -32097,
+            RpcError::ReadOnly => -32096,
         }
     }
 
@@ -50,6 +62,9 @@ impl RpcError {
             RpcError::InternalError(_) => "Internal error".into(),
             RpcError::ApplicationError(e) => e.message(version).into(),
             RpcError::WebsocketSubscriptionClosed { .. } => "Websocket subscription closed".into(),
+            RpcError::ResponseTooLarge { .. } => "Response too large".into(),
+            RpcError::MethodRestricted => "Method restricted".into(),
+            RpcError::ReadOnly => "Node is read-only".into(),
         }
     }
 
@@ -62,6 +77,18 @@ impl RpcError {
                 "id": subscription_id,
                 "reason": reason,
             })),
+            RpcError::ResponseTooLarge { limit } => Some(json!({
+                "limit": limit,
+                "reason": format!(
+                    "Response exceeds the limit of {limit} bytes for this method, narrow your query"
+                ),
+            })),
+            RpcError::MethodRestricted => Some(json!({
+                "reason": "This method requires a valid API key"
+            })),
+            RpcError::ReadOnly => Some(json!({
+                "reason": "The node is low on disk space and does not accept transactions"
+            })),
             RpcError::ApplicationError(e) => e.data(version),
             RpcError::InternalError(_) => None,
             RpcError::MethodNotFound => None,
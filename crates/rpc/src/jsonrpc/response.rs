@@ -1,10 +1,12 @@
 use axum::response::IntoResponse;
+use serde_json::value::RawValue;
 use serde_json::Value;
 
 use crate::dto::SerializeForVersion;
 use crate::error::ApplicationError;
 use crate::jsonrpc::error::RpcError;
 use crate::jsonrpc::RequestId;
+use crate::trace_provenance::TraceProvenance;
 use crate::RpcVersion;
 
 #[derive(Debug, PartialEq)]
@@ -12,6 +14,8 @@ pub struct RpcResponse {
     pub output: RpcResult,
     pub id: RequestId,
     pub version: RpcVersion,
+    /// Only set if the client opted in, see [crate::trace_provenance].
+    pub(crate) trace_provenance: Option<TraceProvenance>,
 }
 
 impl RpcResponse {
@@ -20,6 +24,7 @@ impl RpcResponse {
             output: Err(RpcError::ParseError(error)),
             id: RequestId::Null,
             version,
+            trace_provenance: None,
         }
     }
 
@@ -28,6 +33,7 @@ impl RpcResponse {
             output: Err(RpcError::InvalidRequest(error)),
             id: RequestId::Null,
             version,
+            trace_provenance: None,
         }
     }
 
@@ -36,6 +42,7 @@ impl RpcResponse {
             output: Err(RpcError::MethodNotFound),
             id,
             version,
+            trace_provenance: None,
         }
     }
 
@@ -44,6 +51,7 @@ impl RpcResponse {
             output: Err(RpcError::InvalidParams(error)),
             id,
             version,
+            trace_provenance: None,
         }
     }
 
@@ -52,11 +60,49 @@ impl RpcResponse {
             output: Err(RpcError::InternalError(anyhow::Error::msg(error))),
             id,
             version,
+            trace_provenance: None,
         }
     }
 }
 
-pub type RpcResult = Result<Value, RpcError>;
+pub type RpcResult = Result<RpcOutput, RpcError>;
+
+/// The result of a successful method call.
+#[derive(Debug)]
+pub enum RpcOutput {
+    Value(Value),
+    /// Encoded by [SerializeForVersion::serialize_raw], written to the
+    /// response as is.
+    Raw(Box<RawValue>),
+}
+
+impl From<Value> for RpcOutput {
+    fn from(value: Value) -> Self {
+        Self::Value(value)
+    }
+}
+
+impl PartialEq for RpcOutput {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Value(x), Self::Value(y)) => x == y,
+            (Self::Raw(x), Self::Raw(y)) => x.get() == y.get(),
+            _ => false,
+        }
+    }
+}
+
+impl crate::dto::SerializeForVersion for RpcOutput {
+    fn serialize(
+        &self,
+        _serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        match self {
+            Self::Value(value) => Ok(value.clone()),
+            Self::Raw(raw) => serde_json::from_str(raw.get()),
+        }
+    }
+}
 
 impl crate::dto::SerializeForVersion for RpcResponse {
     fn serialize(
@@ -67,7 +113,7 @@ impl crate::dto::SerializeForVersion for RpcResponse {
         obj.serialize_field("jsonrpc", &"2.0")?;
 
         match &self.output {
-            Ok(x) => obj.serialize_field("result", &x)?,
+            Ok(x) => obj.serialize_field("result", x)?,
             Err(e) => obj.serialize_field("error", e)?,
         };
 
@@ -78,6 +124,10 @@ impl crate::dto::SerializeForVersion for RpcResponse {
             RequestId::Notification => {}
         };
 
+        if let Some(provenance) = &self.trace_provenance {
+            obj.serialize_field("pathfinder", &Extensions { provenance })?;
+        }
+
         obj.end()
     }
 }
@@ -91,7 +141,7 @@ impl crate::dto::SerializeForVersion for &RpcResponse {
         obj.serialize_field("jsonrpc", &"2.0")?;
 
         match &self.output {
-            Ok(x) => obj.serialize_field("result", &x)?,
+            Ok(x) => obj.serialize_field("result", x)?,
             Err(e) => obj.serialize_field("error", e)?,
         };
 
@@ -102,6 +152,10 @@ impl crate::dto::SerializeForVersion for &RpcResponse {
             RequestId::Notification => {}
         };
 
+        if let Some(provenance) = &self.trace_provenance {
+            obj.serialize_field("pathfinder", &Extensions { provenance })?;
+        }
+
         obj.end()
     }
 }
@@ -120,13 +174,60 @@ impl IntoResponse for RpcResponse {
             _ => {}
         }
 
-        serde_json::to_vec(
-            &self
-                .serialize(crate::dto::Serializer::new(self.version))
-                .unwrap(),
-        )
+        String::from(Box::<str>::from(self.to_raw_json())).into_response()
+    }
+}
+
+impl RpcResponse {
+    /// Encodes the response as JSON. Unlike [SerializeForVersion] this writes
+    /// [RpcOutput::Raw] results as is instead of parsing them first.
+    pub fn to_raw_json(&self) -> Box<RawValue> {
+        #[derive(serde::Serialize)]
+        struct RawResponse<'a> {
+            jsonrpc: &'static str,
+            result: &'a RawValue,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            id: Option<Value>,
+        }
+
+        match &self.output {
+            // Response extensions are only added by the serialization below.
+            Ok(RpcOutput::Raw(result)) if self.trace_provenance.is_none() => {
+                let id = match &self.id {
+                    RequestId::Number(x) => Some(Value::from(*x)),
+                    RequestId::String(x) => Some(Value::from(x.as_str())),
+                    RequestId::Null => Some(Value::Null),
+                    RequestId::Notification => None,
+                };
+                crate::dto::to_raw_json(&RawResponse {
+                    jsonrpc: "2.0",
+                    result,
+                    id,
+                })
+            }
+            _ => crate::dto::to_raw_json(
+                &self
+                    .serialize(crate::dto::Serializer::new(self.version))
+                    .unwrap(),
+            ),
+        }
         .unwrap()
-        .into_response()
+    }
+}
+
+/// Pathfinder specific response extensions clients opted into.
+struct Extensions<'a> {
+    provenance: &'a TraceProvenance,
+}
+
+impl SerializeForVersion for Extensions<'_> {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("trace_provenance", self.provenance)?;
+        serializer.end()
     }
 }
 
@@ -145,6 +246,7 @@ mod tests {
             output: Err(RpcError::InvalidParams(parsing_err.clone())),
             id: RequestId::Number(1),
             version: RpcVersion::V07,
+            trace_provenance: None,
         };
         let parsing_err = RpcError::InvalidParams(parsing_err);
 
@@ -168,9 +270,10 @@ mod tests {
     #[test]
     fn output_is_ok() {
         let serialized = RpcResponse {
-            output: Ok(Value::String("foobar".to_owned())),
+            output: Ok(Value::String("foobar".to_owned()).into()),
             id: RequestId::Number(1),
             version: RpcVersion::V07,
+            trace_provenance: None,
         }
         .serialize(crate::dto::Serializer::new(RpcVersion::V07))
         .unwrap();
@@ -183,4 +286,28 @@ mod tests {
 
         assert_eq!(serialized, expected);
     }
+
+    #[test]
+    fn trace_provenance_extension() {
+        let response = RpcResponse {
+            output: Ok(RpcOutput::Raw(
+                RawValue::from_string(r#"{"trace":1}"#.to_owned()).unwrap(),
+            )),
+            id: RequestId::Number(1),
+            version: RpcVersion::V07,
+            trace_provenance: Some(TraceProvenance::Gateway),
+        };
+
+        let expected = json!({
+            "jsonrpc": "2.0",
+            "result": {"trace": 1},
+            "id": 1,
+            "pathfinder": {
+                "trace_provenance": {"source": "gateway"},
+            },
+        });
+
+        let raw: Value = serde_json::from_str(response.to_raw_json().get()).unwrap();
+        assert_eq!(raw, expected);
+    }
 }
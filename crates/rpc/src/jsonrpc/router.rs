@@ -27,6 +27,12 @@ pub struct RpcRouter {
     method_endpoints: &'static HashMap<&'static str, Box<dyn RpcMethodEndpoint>>,
     subscription_endpoints: &'static HashMap<&'static str, Box<dyn RpcSubscriptionEndpoint>>,
     pub version: RpcVersion,
+    /// Whether the client presented a valid API key, see
+    /// [MethodAccessConfig](crate::context::MethodAccessConfig).
+    authenticated: bool,
+    /// Whether the client opted into [trace
+    /// provenance](crate::trace_provenance).
+    trace_provenance: bool,
 }
 
 pub struct RpcRouterBuilder {
@@ -81,6 +87,8 @@ impl RpcRouterBuilder {
             method_endpoints: methods,
             subscription_endpoints: subscriptions,
             version: self.version,
+            authenticated: false,
+            trace_provenance: false,
         }
     }
 
@@ -98,6 +106,24 @@ impl RpcRouter {
         RpcRouterBuilder::new(version)
     }
 
+    /// Rejects calls to disabled methods as not found, calls to restricted
+    /// methods by unauthenticated clients and transaction submissions while
+    /// the node is in read-only degraded mode.
+    fn check_access(&self, method_name: &'static str) -> Result<(), RpcError> {
+        let access = &self.context.config.method_access;
+        if access.is_disabled(method_name) {
+            return Err(RpcError::MethodNotFound);
+        }
+        if !self.authenticated && access.is_restricted(method_name) {
+            metrics::increment_counter!("rpc_method_calls_restricted_total", "method" => method_name, "version" => self.version.to_str());
+            return Err(RpcError::MethodRestricted);
+        }
+        if is_submission(method_name) && *self.context.degraded.borrow() {
+            return Err(RpcError::ReadOnly);
+        }
+        Ok(())
+    }
+
     /// Parses and executes a request. Returns [None] if its a notification.
     async fn run_request(&self, request: &str) -> Option<RpcResponse> {
         tracing::trace!(%request, "Running request");
@@ -122,10 +148,76 @@ impl RpcRouter {
             return Some(RpcResponse::method_not_found(request.id, self.version));
         };
 
+        let started = std::time::Instant::now();
+        let params = request.params.0;
+        let response = self.run_method(method_name, &**method, request).await;
+        self.context.request_log.record(
+            method_name,
+            params,
+            self.version,
+            started.elapsed(),
+            &response.output,
+        );
+
+        Some(response)
+    }
+
+    /// Executes a request for a method, unless the client may not call it or
+    /// the response is cached.
+    async fn run_method(
+        &self,
+        method_name: &'static str,
+        method: &dyn RpcMethodEndpoint,
+        request: RpcRequest<'_>,
+    ) -> RpcResponse {
+        if let Err(e) = self.check_access(method_name) {
+            return RpcResponse {
+                output: Err(e),
+                id: request.id,
+                version: self.version,
+                trace_provenance: None,
+            };
+        }
+
         metrics::increment_counter!("rpc_method_calls_total", "method" => method_name, "version" => self.version.to_str());
 
-        let method = method.invoke(self.context.clone(), request.params, self.version);
-        let result = std::panic::AssertUnwindSafe(method).catch_unwind().await;
+        // Cached responses carry no trace provenance.
+        let cache_key = (!self.trace_provenance)
+            .then(|| {
+                self.context
+                    .responses
+                    .key(method_name, &request.params, self.version)
+            })
+            .flatten();
+        if let Some(output) = cache_key
+            .as_ref()
+            .and_then(|key| self.context.responses.get(key))
+        {
+            return RpcResponse {
+                output: Ok(output),
+                id: request.id,
+                version: self.version,
+                trace_provenance: None,
+            };
+        }
+
+        let size_limit = self
+            .context
+            .config
+            .response_size_limits
+            .limit_for(method_name);
+        let method = method.invoke(
+            self.context.clone(),
+            request.params,
+            self.version,
+            size_limit,
+        );
+        let method = std::panic::AssertUnwindSafe(method).catch_unwind();
+        let (result, trace_provenance) = if self.trace_provenance {
+            crate::trace_provenance::collect(method).await
+        } else {
+            (method.await, None)
+        };
 
         let output = match result {
             Ok(output) => output,
@@ -137,18 +229,36 @@ impl RpcRouter {
             }
         };
 
+        if let Err(RpcError::ResponseTooLarge { .. }) = output {
+            metrics::increment_counter!("rpc_method_responses_too_large_total", "method" => method_name, "version" => self.version.to_str());
+        }
+
         if output.is_err() {
             metrics::increment_counter!("rpc_method_calls_failed_total", "method" => method_name, "version" => self.version.to_str());
         }
 
-        Some(RpcResponse {
+        if let (Some(key), Ok(output)) = (cache_key, &output) {
+            if key.is_stored(&self.context.storage).await {
+                self.context.responses.insert(key, output);
+            }
+        }
+
+        RpcResponse {
+            trace_provenance: trace_provenance.filter(|_| output.is_ok()),
             output,
             id: request.id,
             version: self.version,
-        })
+        }
     }
 }
 
+/// Transaction submissions are rejected in read-only degraded mode. Sync is
+/// paused then, so the node could neither report the status of submitted
+/// transactions nor serve their up-to-date nonces.
+fn is_submission(method_name: &str) -> bool {
+    method_name.starts_with("starknet_add") && method_name.ends_with("Transaction")
+}
+
 // A slight variation on the axum json extractor.
 fn is_utf8_encoded_json(headers: http::HeaderMap) -> bool {
     let Some(content_type) = headers.get(http::header::CONTENT_TYPE) else {
@@ -180,12 +290,19 @@ fn is_utf8_encoded_json(headers: http::HeaderMap) -> bool {
 
 #[axum::debug_handler]
 pub async fn rpc_handler(
-    State(state): State<RpcRouter>,
+    State(mut state): State<RpcRouter>,
     headers: http::HeaderMap,
     method: http::Method,
     ws: Option<WebSocketUpgrade>,
     body: axum::body::Bytes,
 ) -> impl axum::response::IntoResponse {
+    state.authenticated = state
+        .context
+        .config
+        .method_access
+        .is_authenticated(&headers);
+    state.trace_provenance = crate::trace_provenance::requested(&headers);
+
     match ws {
         Some(ws) => ws.on_upgrade(|ws| async move {
             let (ws_tx, ws_rx) = split_ws(ws, state.version);
@@ -206,14 +323,9 @@ pub async fn rpc_handler(
                     RpcResponses::Empty => ().into_response(),
                     RpcResponses::Single(response) => response.into_response(),
                     RpcResponses::Multiple(responses) => {
-                        use crate::dto::SerializeForVersion;
                         let values = responses
-                            .into_iter()
-                            .map(|response| {
-                                response
-                                    .serialize(crate::dto::Serializer::new(state.version))
-                                    .unwrap()
-                            })
+                            .iter()
+                            .map(RpcResponse::to_raw_json)
                             .collect::<Vec<_>>();
                         serde_json::to_string(&values).unwrap().into_response()
                     }
@@ -740,7 +852,7 @@ mod tests {
     #[tokio::test]
     async fn rejects_non_json_content_header() {
         async fn always_success(_ctx: RpcContext) -> RpcResult {
-            Ok(json!("Success"))
+            Ok(json!("Success").into())
         }
 
         let router = RpcRouter::builder(Default::default())
@@ -770,7 +882,7 @@ mod tests {
     #[tokio::test]
     async fn accepts_json_with_charset_utf8() {
         async fn always_success(_ctx: RpcContext) -> RpcResult {
-            Ok(json!("Success"))
+            Ok(json!("Success").into())
         }
 
         let router = RpcRouter::builder(Default::default())
@@ -807,7 +919,7 @@ mod tests {
     #[tokio::test]
     async fn rejects_json_with_charset_utf16() {
         async fn always_success(_ctx: RpcContext) -> RpcResult {
-            Ok(json!("Success"))
+            Ok(json!("Success").into())
         }
 
         let router = RpcRouter::builder(Default::default())
@@ -881,6 +993,142 @@ mod tests {
         assert_eq!(res, expected);
     }
 
+    #[tokio::test]
+    async fn response_size_limit() {
+        fn large() -> &'static str {
+            "This response is too large"
+        }
+
+        fn small() -> &'static str {
+            "Ok"
+        }
+
+        let mut context = RpcContext::for_tests();
+        context.config.response_size_limits = crate::context::ResponseSizeLimits {
+            default: NonZeroUsize::new(10),
+            per_method: [("small".to_owned(), NonZeroUsize::new(4).unwrap())].into(),
+        };
+
+        let router = RpcRouter::builder(Default::default())
+            .register("large", large)
+            .register("small", small)
+            .build(context);
+
+        let res = serve_and_query(
+            router.clone(),
+            json!({"jsonrpc": "2.0", "method": "small", "id": 1}),
+        )
+        .await;
+        assert_eq!(res, json!({"jsonrpc": "2.0", "result": "Ok", "id": 1}));
+
+        let res = serve_and_query(
+            router,
+            json!({"jsonrpc": "2.0", "method": "large", "id": 1}),
+        )
+        .await;
+        let error = RpcError::ResponseTooLarge { limit: 10 };
+        assert_eq!(
+            res,
+            json!({
+                "jsonrpc": "2.0",
+                "error": {
+                    "code": error.code(),
+                    "message": error.message(RpcVersion::default()),
+                    "data": error.data(RpcVersion::default()),
+                },
+                "id": 1,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn method_access() {
+        fn trace() -> &'static str {
+            "Ok"
+        }
+
+        fn simulate() -> &'static str {
+            "Ok"
+        }
+
+        let mut context = RpcContext::for_tests();
+        context.config.method_access = crate::context::MethodAccessConfig {
+            disabled: vec!["trace*".to_owned()],
+            restricted: vec!["simulate".to_owned()],
+            api_keys: ["secret".to_owned()].into(),
+        };
+
+        let router = RpcRouter::builder(Default::default())
+            .register("trace", trace)
+            .register("simulate", simulate)
+            .build(context);
+        let url = spawn_server(router).await;
+        let client = reqwest::Client::new();
+        let query = |method: &'static str, key: Option<&'static str>| {
+            let mut request = client
+                .post(url.clone())
+                .json(&json!({"jsonrpc": "2.0", "method": method, "id": 1}));
+            if let Some(key) = key {
+                request = request.bearer_auth(key);
+            }
+            async move { request.send().await.unwrap().json::<Value>().await.unwrap() }
+        };
+
+        let res = query("trace", Some("secret")).await;
+        assert_eq!(res["error"]["code"], json!(-32601));
+
+        let res = query("simulate", None).await;
+        assert_eq!(
+            res["error"]["code"],
+            json!(RpcError::MethodRestricted.code())
+        );
+
+        let res = query("simulate", Some("wrong")).await;
+        assert_eq!(
+            res["error"]["code"],
+            json!(RpcError::MethodRestricted.code())
+        );
+
+        let res = query("simulate", Some("secret")).await;
+        assert_eq!(res, json!({"jsonrpc": "2.0", "result": "Ok", "id": 1}));
+    }
+
+    #[tokio::test]
+    async fn read_only_rejects_submissions() {
+        fn submit() -> &'static str {
+            "Ok"
+        }
+
+        let (degraded_tx, degraded) = tokio::sync::watch::channel(false);
+        let context = RpcContext::for_tests().with_degraded_mode(degraded);
+
+        let router = RpcRouter::builder(Default::default())
+            .register("starknet_addInvokeTransaction", submit)
+            .register("starknet_blockNumber", submit)
+            .build(context);
+        let url = spawn_server(router).await;
+        let client = reqwest::Client::new();
+        let query = |method: &'static str| {
+            let request = client
+                .post(url.clone())
+                .json(&json!({"jsonrpc": "2.0", "method": method, "id": 1}));
+            async move { request.send().await.unwrap().json::<Value>().await.unwrap() }
+        };
+
+        let res = query("starknet_addInvokeTransaction").await;
+        assert_eq!(res["result"], json!("Ok"));
+
+        degraded_tx.send_replace(true);
+        let res = query("starknet_addInvokeTransaction").await;
+        assert_eq!(res["error"]["code"], json!(RpcError::ReadOnly.code()));
+        let res = query("starknet_blockNumber").await;
+        assert_eq!(res["result"], json!("Ok"));
+
+        degraded_tx.send_replace(false);
+        let res = query("starknet_addInvokeTransaction").await;
+        assert_eq!(res["result"], json!("Ok"));
+    }
+
     #[tokio::test]
     async fn response_hash_content_type_json() {
         fn always_success() -> &'static str {
@@ -2,6 +2,7 @@
 
 use std::future::Future;
 use std::marker::PhantomData;
+use std::num::NonZeroUsize;
 
 use axum::async_trait;
 use serde_json::value::RawValue;
@@ -18,7 +19,7 @@ use super::{
 use crate::context::RpcContext;
 use crate::dto::{DeserializeForVersion, SerializeForVersion, Serializer};
 use crate::jsonrpc::request::RawParams;
-use crate::jsonrpc::response::RpcResult;
+use crate::jsonrpc::response::{RpcOutput, RpcResult};
 use crate::jsonrpc::router::RpcEndpointInner;
 use crate::jsonrpc::{RpcError, RpcResponse};
 use crate::RpcVersion;
@@ -30,9 +31,36 @@ pub(super) trait RpcMethodEndpoint: Send + Sync {
         state: RpcContext,
         input: RawParams<'a>,
         version: RpcVersion,
+        size_limit: Option<NonZeroUsize>,
     ) -> RpcResult;
 }
 
+/// Serializes a method's output, preferring
+/// [SerializeForVersion::serialize_raw] if the output implements it.
+///
+/// Serialization is aborted with [RpcError::ResponseTooLarge] as soon as the
+/// output grows past `size_limit` bytes.
+fn serialize_output(
+    output: &impl SerializeForVersion,
+    version: RpcVersion,
+    size_limit: Option<NonZeroUsize>,
+) -> RpcResult {
+    let serialize = || match output.serialize_raw(Serializer::new(version)) {
+        Some(raw) => raw.map(RpcOutput::Raw),
+        None => output
+            .serialize(Serializer::new(version))
+            .map(RpcOutput::Value),
+    };
+
+    let output = match size_limit {
+        Some(limit) => crate::dto::with_size_limit(limit.get(), serialize)
+            .map_err(|_| RpcError::ResponseTooLarge { limit: limit.get() })?,
+        None => serialize(),
+    };
+
+    output.map_err(|e| RpcError::InternalError(e.into()))
+}
+
 /// Helper to scope the responses so we can set the content-type afterwards
 /// instead of dealing with branches / early exits.
 pub async fn handle_json_rpc_body(
@@ -128,13 +156,13 @@ where
                 state: RpcContext,
                 input: RawParams<'a>,
                 version: RpcVersion,
+                size_limit: Option<NonZeroUsize>,
             ) -> RpcResult {
                 let input = input.deserialize_for_version(version)?;
                 (self.f)(state, input, version)
                     .await
-                    .map_err(Into::into)?
-                    .serialize(Serializer::new(version))
-                    .map_err(|e| RpcError::InternalError(e.into()))
+                    .map_err(Into::into)
+                    .and_then(|output| serialize_output(&output, version, size_limit))
             }
         }
 
@@ -177,13 +205,13 @@ where
                 state: RpcContext,
                 input: RawParams<'a>,
                 version: RpcVersion,
+                size_limit: Option<NonZeroUsize>,
             ) -> RpcResult {
                 let input = input.deserialize_for_version(version)?;
                 (self.f)(state, input)
                     .await
-                    .map_err(Into::into)?
-                    .serialize(Serializer::new(version))
-                    .map_err(|e| RpcError::InternalError(e.into()))
+                    .map_err(Into::into)
+                    .and_then(|output| serialize_output(&output, version, size_limit))
             }
         }
 
@@ -226,13 +254,13 @@ where
                 _state: RpcContext,
                 input: RawParams<'a>,
                 version: RpcVersion,
+                size_limit: Option<NonZeroUsize>,
             ) -> RpcResult {
                 let input = input.deserialize_for_version(version)?;
                 (self.f)(input)
                     .await
-                    .map_err(Into::into)?
-                    .serialize(Serializer::new(version))
-                    .map_err(|e| RpcError::InternalError(e.into()))
+                    .map_err(Into::into)
+                    .and_then(|output| serialize_output(&output, version, size_limit))
             }
         }
 
@@ -273,6 +301,7 @@ where
                 state: RpcContext,
                 input: RawParams<'a>,
                 version: RpcVersion,
+                size_limit: Option<NonZeroUsize>,
             ) -> RpcResult {
                 if !input.is_empty() {
                     return Err(RpcError::InvalidParams(
@@ -281,9 +310,8 @@ where
                 }
                 (self.f)(state)
                     .await
-                    .map_err(Into::into)?
-                    .serialize(Serializer::new(version))
-                    .map_err(|e| RpcError::InternalError(e.into()))
+                    .map_err(Into::into)
+                    .and_then(|output| serialize_output(&output, version, size_limit))
             }
         }
 
@@ -324,6 +352,7 @@ where
                 _state: RpcContext,
                 input: RawParams<'a>,
                 version: RpcVersion,
+                size_limit: Option<NonZeroUsize>,
             ) -> RpcResult {
                 if !input.is_empty() {
                     return Err(RpcError::InvalidParams(
@@ -332,9 +361,8 @@ where
                 }
                 (self.f)()
                     .await
-                    .map_err(Into::into)?
-                    .serialize(Serializer::new(version))
-                    .map_err(|e| RpcError::InternalError(e.into()))
+                    .map_err(Into::into)
+                    .and_then(|output| serialize_output(&output, version, size_limit))
             }
         }
 
@@ -368,15 +396,14 @@ where
                 _state: RpcContext,
                 input: RawParams<'a>,
                 version: RpcVersion,
+                size_limit: Option<NonZeroUsize>,
             ) -> RpcResult {
                 if !input.is_empty() {
                     return Err(RpcError::InvalidParams(
                         "This method takes no inputs".to_owned(),
                     ));
                 }
-                (self.f)()
-                    .serialize(Serializer::new(version))
-                    .map_err(|e| RpcError::InternalError(e.into()))
+                serialize_output(&(self.f)(), version, size_limit)
             }
         }
         RpcEndpoint(RpcEndpointInner::Method(Box::new(Helper { f: self })))
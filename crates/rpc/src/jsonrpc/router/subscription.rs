@@ -68,7 +68,7 @@ pub trait RpcSubscriptionFlow: Send + Sync {
 
     /// Validate the subscription parameters. If the parameters are invalid,
     /// return an error.
-    fn validate_params(_params: &Self::Params) -> Result<(), RpcError> {
+    fn validate_params(_context: &RpcContext, _params: &Self::Params) -> Result<(), RpcError> {
         Ok(())
     }
 
@@ -150,7 +150,7 @@ where
         let params = T::Params::deserialize(crate::dto::Value::new(input, router.version))
             .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
 
-        T::validate_params(&params)?;
+        T::validate_params(&router.context, &params)?;
 
         let tx = SubscriptionSender {
             subscription_id,
@@ -170,20 +170,21 @@ where
                 // No need to catch up. The code below will subscribe to new blocks.
                 None
             }
-            first_block @ (BlockId::Number(_) | BlockId::Hash(_)) => {
+            first_block @ (BlockId::Number(_) | BlockId::Hash(_) | BlockId::L1Accepted) => {
                 // Load the first block number, return an error if it's invalid.
                 let first_block = pathfinder_storage::BlockId::try_from(first_block)
                     .map_err(|e| RpcError::InvalidParams(e.to_string()))?;
                 let storage = router.context.storage.clone();
-                let current_block = util::task::spawn_blocking(move |_| -> Result<_, RpcError> {
-                    let mut conn = storage.connection().map_err(RpcError::InternalError)?;
-                    let db = conn.transaction().map_err(RpcError::InternalError)?;
-                    db.block_number(first_block)
-                        .map_err(RpcError::InternalError)?
-                        .ok_or_else(|| ApplicationError::BlockNotFound.into())
-                })
-                .await
-                .map_err(|e| RpcError::InternalError(e.into()))??;
+                let current_block =
+                    util::task::spawn_blocking_storage(move |_| -> Result<_, RpcError> {
+                        let mut conn = storage.connection().map_err(RpcError::InternalError)?;
+                        let db = conn.transaction().map_err(RpcError::InternalError)?;
+                        db.block_number(first_block)
+                            .map_err(RpcError::InternalError)?
+                            .ok_or_else(|| ApplicationError::BlockNotFound.into())
+                    })
+                    .await
+                    .map_err(|e| RpcError::InternalError(e.into()))??;
                 Some(current_block)
             }
         };
@@ -619,13 +620,15 @@ async fn handle_request(
                 )),
                 id: req_id.clone(),
                 version: state.version,
+                trace_provenance: None,
             })?;
         handle.abort();
         metrics::increment_counter!("rpc_method_calls_total", "method" => "starknet_unsubscribe", "version" => state.version.to_str());
         return Ok(Some(RpcResponse {
-            output: Ok(true.into()),
+            output: Ok(serde_json::Value::Bool(true).into()),
             id: req_id,
             version: state.version,
+            trace_provenance: None,
         }));
     }
 
@@ -633,6 +636,12 @@ async fn handle_request(
         .subscription_endpoints
         .get_key_value(rpc_request.method.as_ref())
         .ok_or_else(|| RpcResponse::method_not_found(req_id.clone(), state.version))?;
+    state.check_access(method_name).map_err(|e| RpcResponse {
+        output: Err(e),
+        id: req_id.clone(),
+        version: state.version,
+        trace_provenance: None,
+    })?;
     metrics::increment_counter!("rpc_method_calls_total", "method" => method_name, "version" => state.version.to_str());
 
     let params = serde_json::to_value(rpc_request.params)
@@ -660,15 +669,18 @@ async fn handle_request(
             Ok(Some(RpcResponse {
                 output: Ok(subscription_id
                     .serialize(crate::dto::Serializer::new(state.version))
-                    .unwrap()),
+                    .unwrap()
+                    .into()),
                 id: req_id,
                 version: state.version,
+                trace_provenance: None,
             }))
         }
         Err(e) => Err(RpcResponse {
             output: Err(e),
             id: req_id,
             version: state.version,
+            trace_provenance: None,
         }),
     }
 }
@@ -1021,6 +1033,9 @@ mod tests {
                 pathfinder_ethereum::core_addr::MAINNET,
             ),
             sequencer: Client::mainnet(Duration::from_secs(10)),
+            gateway_breaker: Default::default(),
+            submissions: Default::default(),
+            responses: Default::default(),
             websocket: None,
             notifications,
             ethereum: EthereumClient::new("wss://eth-sepolia.g.alchemy.com/v2/just-for-tests")
@@ -1030,6 +1045,23 @@ mod tests {
                 get_events_max_blocks_to_scan: 1.try_into().unwrap(),
                 get_events_max_uncached_event_filters_to_load: 1.try_into().unwrap(),
                 custom_versioned_constants: None,
+                max_request_body_size: 10 * 1024 * 1024,
+                response_size_limits: Default::default(),
+                trace_cache: Default::default(),
+                gateway_circuit_breaker: Default::default(),
+                additional_fee_tokens: vec![],
+                execution_policy: Default::default(),
+                reconstruct_gateway_trace_events: false,
+                idempotency_key_ttl: Duration::from_secs(300),
+                gateway_outbox: false,
+                rejected_transaction_archive: false,
+                declare_precheck: false,
+                trace_cache_warmup: false,
+                get_proof_max_keys: 100.try_into().unwrap(),
+                event_filter_max_keys: 16.try_into().unwrap(),
+                get_events_max_chunk_size: 1024.try_into().unwrap(),
+                method_access: Default::default(),
+                response_cache_max_size: None,
             },
         };
         RpcRouter::builder(crate::RpcVersion::V08)
@@ -238,6 +238,7 @@ impl crate::dto::SerializeForVersion for ResponseEvent {
                 }),
                 id: RequestId::Null,
                 version: serializer.version,
+                trace_provenance: None,
             }
             .serialize(serializer),
             ResponseEvent::Responses(responses) => responses.serialize(serializer),
@@ -263,9 +264,10 @@ where
 {
     let payload = payload.serialize(crate::dto::Serializer::new(version))?;
     Ok(RpcResponse {
-        output: Ok(payload),
+        output: Ok(payload.into()),
         id: request_id,
         version,
+        trace_provenance: None,
     })
 }
 
@@ -722,6 +722,7 @@ mod tests {
                 )),
                 id: RequestId::Null,
                 version: RpcVersion::V07,
+                trace_provenance: None,
             })
             .await;
 
@@ -805,9 +806,10 @@ mod tests {
 
         client
             .expect_response(&RpcResponse {
-                output: Ok(json!("0x534e5f5345504f4c4941")),
+                output: Ok(json!("0x534e5f5345504f4c4941").into()),
                 id: RequestId::Number(1),
                 version: RpcVersion::V07,
+                trace_provenance: None,
             })
             .await;
 
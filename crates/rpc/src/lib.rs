@@ -1,16 +1,25 @@
 //! Starknet node JSON-RPC related modules.
+mod circuit_breaker;
 pub mod context;
 mod dto;
 mod error;
+mod event_abi;
 mod executor;
 mod felt;
+mod idempotency;
 mod jsonrpc;
+pub mod log_filter;
 pub(crate) mod method;
 pub mod middleware;
+pub mod outbox;
 mod pathfinder;
 mod pending;
+mod rejected_transactions;
+pub mod request_log;
+mod response_cache;
 #[cfg(test)]
 mod test_setup;
+mod trace_provenance;
 pub mod types;
 pub mod v07;
 pub mod v08;
@@ -46,6 +55,8 @@ pub enum RpcVersion {
     #[default]
     V07,
     V08,
+    /// Only used for serialization so far, there are no v0.9 routes yet.
+    V09,
     PathfinderV01,
 }
 
@@ -54,13 +65,12 @@ impl RpcVersion {
         match self {
             RpcVersion::V07 => "v0.7",
             RpcVersion::V08 => "v0.8",
+            RpcVersion::V09 => "v0.9",
             RpcVersion::PathfinderV01 => "v0.1",
         }
     }
 }
 
-// TODO: make this configurable
-const REQUEST_MAX_SIZE: usize = 10 * 1024 * 1024;
 // TODO: make this configurable
 const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
 
@@ -99,8 +109,6 @@ impl RpcServer {
     pub async fn spawn(
         self,
     ) -> Result<(JoinHandle<anyhow::Result<()>>, SocketAddr), anyhow::Error> {
-        use axum::routing::{get, post};
-
         let listener = match tokio::net::TcpListener::bind(self.addr).await {
             Ok(listener) => listener,
             Err(e) => {
@@ -145,61 +153,15 @@ impl RpcServer {
             // make sure to set request ids before the request reaches `TraceLayer`
             .set_x_request_id(middleware::request_id::RequestIdSource::default())
             .concurrency_limit(self.max_connections)
-            .layer(DefaultBodyLimit::max(REQUEST_MAX_SIZE))
+            .layer(DefaultBodyLimit::max(
+                self.context.config.max_request_body_size,
+            ))
             .timeout(REQUEST_TIMEOUT)
             .layer(middleware::tracing::trace_layer())
             .option_layer(self.cors)
             .propagate_x_request_id();
 
-        /// Returns success for requests with an empty body without reading
-        /// the entire body.
-        async fn empty_body(request: axum::extract::Request) -> impl IntoResponse {
-            if request.body().is_end_stream() {
-                axum::http::StatusCode::OK
-            } else {
-                axum::http::StatusCode::METHOD_NOT_ALLOWED
-            }
-        }
-
-        let v07_routes = v07::register_routes().build(self.context.clone());
-        let v08_routes = v08::register_routes().build(self.context.clone());
-        let pathfinder_routes = pathfinder::register_routes().build(self.context.clone());
-
-        let default_router = match self.default_version {
-            RpcVersion::V07 => v07_routes.clone(),
-            RpcVersion::V08 => v08_routes.clone(),
-            RpcVersion::PathfinderV01 => {
-                anyhow::bail!("Did not expect default RPC version to be Pathfinder v0.1")
-            }
-        };
-
-        let router = axum::Router::new()
-            // Also return success for get's with an empty body. These are often
-            // used by monitoring bots to check service health.
-            .route("/", get(empty_body).post(rpc_handler))
-            .with_state(default_router.clone())
-            .route("/rpc/v0_7", post(rpc_handler))
-            .with_state(v07_routes.clone())
-            // TODO Uncomment once RPC 0.8 is ready.
-            .route("/rpc/v0_8", post(rpc_handler).get(rpc_handler))
-            .with_state(v08_routes.clone())
-            .route("/rpc/pathfinder/v0.1", post(rpc_handler))
-            .route("/rpc/pathfinder/v0_1", post(rpc_handler))
-            .with_state(pathfinder_routes.clone());
-
-        let router = if self.context.websocket.is_some() {
-            router
-                .route("/ws", get(websocket_handler))
-                .with_state(default_router)
-                .route("/ws/rpc/v0_7", get(websocket_handler))
-                .with_state(v07_routes)
-                .route("/ws/rpc/pathfinder/v0_1", get(websocket_handler))
-                .with_state(pathfinder_routes)
-        } else {
-            router.with_state(default_router)
-        };
-
-        let router = router.layer(middleware);
+        let router = routes(&self.context, self.default_version)?.layer(middleware);
 
         let server_handle = util::task::spawn(async move {
             axum::serve(listener, router.into_make_service())
@@ -219,6 +181,73 @@ impl RpcServer {
     }
 }
 
+/// The routes serving the JSON-RPC API, over HTTP and websockets.
+fn routes(context: &RpcContext, default_version: RpcVersion) -> anyhow::Result<axum::Router> {
+    use axum::extract::{State, WebSocketUpgrade};
+    use axum::http::{HeaderMap, Method, StatusCode};
+    use axum::routing::{get, post};
+
+    /// Upgrades websocket requests to a connection serving the default API.
+    /// Other requests succeed if their body is empty, which is checked
+    /// without reading the entire body.
+    async fn root_get(
+        state: State<jsonrpc::RpcRouter>,
+        headers: HeaderMap,
+        ws: Option<WebSocketUpgrade>,
+        request: axum::extract::Request,
+    ) -> axum::response::Response {
+        match ws {
+            Some(ws) => rpc_handler(state, headers, Method::GET, Some(ws), Default::default())
+                .await
+                .into_response(),
+            None if request.body().is_end_stream() => StatusCode::OK.into_response(),
+            None => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+        }
+    }
+
+    let v07_routes = v07::register_routes().build(context.clone());
+    let v08_routes = v08::register_routes().build(context.clone());
+    let pathfinder_routes = pathfinder::register_routes().build(context.clone());
+
+    let default_router = match default_version {
+        RpcVersion::V07 => v07_routes.clone(),
+        RpcVersion::V08 => v08_routes.clone(),
+        RpcVersion::V09 => anyhow::bail!("RPC v0.9 is not served yet"),
+        RpcVersion::PathfinderV01 => {
+            anyhow::bail!("Did not expect default RPC version to be Pathfinder v0.1")
+        }
+    };
+
+    // GET requests on the RPC routes upgrade to a websocket connection serving
+    // the same API, including its subscriptions.
+    let router = axum::Router::new()
+        // Also return success for get's with an empty body. These are often
+        // used by monitoring bots to check service health.
+        .route("/", get(root_get).post(rpc_handler))
+        .with_state(default_router.clone())
+        .route("/rpc/v0_7", post(rpc_handler).get(rpc_handler))
+        .with_state(v07_routes.clone())
+        .route("/rpc/v0_8", post(rpc_handler).get(rpc_handler))
+        .with_state(v08_routes.clone())
+        .route("/rpc/pathfinder/v0.1", post(rpc_handler).get(rpc_handler))
+        .route("/rpc/pathfinder/v0_1", post(rpc_handler).get(rpc_handler))
+        .with_state(pathfinder_routes.clone());
+
+    let router = if context.websocket.is_some() {
+        router
+            .route("/ws", get(websocket_handler))
+            .with_state(default_router)
+            .route("/ws/rpc/v0_7", get(websocket_handler))
+            .with_state(v07_routes)
+            .route("/ws/rpc/pathfinder/v0_1", get(websocket_handler))
+            .with_state(pathfinder_routes)
+    } else {
+        router.with_state(default_router)
+    };
+
+    Ok(router)
+}
+
 pub struct SyncState {
     pub status: RwLock<Syncing>,
 }
@@ -912,17 +941,55 @@ mod tests {
         }
     }
 
+    /// Methods of the pathfinder specification which are only served on the
+    /// pathfinder routes.
+    const PATHFINDER_ONLY_METHODS: &[&str] = &[
+        "pathfinder_version",
+        // get_transaction_status is now part of the official spec, so we are phasing it out.
+        "pathfinder_getTransactionStatus",
+        "pathfinder_getBlockStateCommitments",
+        "pathfinder_estimateStateDiffSize",
+        "pathfinder_getReceiptProof",
+        "pathfinder_getEventProof",
+        "pathfinder_getGasPriceEstimate",
+        "pathfinder_simulateL1Message",
+        "pathfinder_estimateFeePerToken",
+        "pathfinder_getClassStats",
+        "pathfinder_subscribeStorageChanges",
+        "pathfinder_getOsInput",
+        "pathfinder_suggestMaxFee",
+        "pathfinder_traceBlockTransactionsRange",
+        "pathfinder_getTransactionReceiptsByBlock",
+        "pathfinder_getGatewayOutbox",
+        "pathfinder_flushGatewayOutbox",
+        "pathfinder_getRejectedTransaction",
+        "pathfinder_getLogFilter",
+        "pathfinder_setLogFilter",
+        "pathfinder_getNonceForSubmission",
+        "pathfinder_getNonces",
+        "pathfinder_explainFee",
+        "pathfinder_callWithProof",
+        "pathfinder_getDecodedEvents",
+        "pathfinder_getBlockByTimestamp",
+        "pathfinder_getTransactionsBySender",
+        "pathfinder_estimateFeeWithValidation",
+        "pathfinder_traceTransactionChrome",
+        "pathfinder_getTraceCacheBlocks",
+        "pathfinder_warmTraceCache",
+        "pathfinder_getStorageAtHistorically",
+        "pathfinder_getStorageWrites",
+    ];
+
     #[rustfmt::skip]
     #[rstest::rstest]
-    #[case::root_api("/", "v06/starknet_api_openrpc.json",       &[], Api::HttpOnly)]
+    #[case::root_api("/", "v06/starknet_api_openrpc.json",       &[], Api::Both)]
     #[case::root_api_websocket("/ws", "v06/starknet_api_openrpc.json",       &[], Api::WebsocketOnly)]
-    #[case::root_trace("/", "v06/starknet_trace_api_openrpc.json", &[], Api::HttpOnly)]
+    #[case::root_trace("/", "v06/starknet_trace_api_openrpc.json", &[], Api::Both)]
     #[case::root_trace_websocket("/ws", "v06/starknet_trace_api_openrpc.json", &[], Api::WebsocketOnly)]
-    #[case::root_write("/", "v06/starknet_write_api.json",         &[], Api::HttpOnly)]
+    #[case::root_write("/", "v06/starknet_write_api.json",         &[], Api::Both)]
     #[case::root_write_websocket("/ws", "v06/starknet_write_api.json",         &[], Api::WebsocketOnly)]
-    // get_transaction_status is now part of the official spec, so we are phasing it out.
-    #[case::root_pathfinder("/", "pathfinder_rpc_api.json", &["pathfinder_version", "pathfinder_getTransactionStatus"], Api::HttpOnly)]
-    #[case::root_pathfinder_websocket("/ws", "pathfinder_rpc_api.json", &["pathfinder_version", "pathfinder_getTransactionStatus"], Api::WebsocketOnly)]
+    #[case::root_pathfinder("/", "pathfinder_rpc_api.json", PATHFINDER_ONLY_METHODS, Api::Both)]
+    #[case::root_pathfinder_websocket("/ws", "pathfinder_rpc_api.json", PATHFINDER_ONLY_METHODS, Api::WebsocketOnly)]
 
     #[case::v0_8_api("/rpc/v0_8", "v08/starknet_api_openrpc.json", &[], Api::Both)]
     #[case::v0_8_executables("/rpc/v0_8", "v08/starknet_executables.json", &[], Api::Both)]
@@ -940,21 +1007,23 @@ mod tests {
             "starknet_subscriptionReorg"
         ],
         Api::WebsocketOnly)]
-    // get_transaction_status is now part of the official spec, so we are phasing it out.
-    #[case::v0_8_pathfinder("/rpc/v0_8", "pathfinder_rpc_api.json", &["pathfinder_version", "pathfinder_getTransactionStatus"], Api::Both)]
+    #[case::v0_8_pathfinder("/rpc/v0_8", "pathfinder_rpc_api.json", PATHFINDER_ONLY_METHODS, Api::Both)]
 
-    #[case::v0_7_api("/rpc/v0_7", "v07/starknet_api_openrpc.json", &[], Api::HttpOnly)]
+    #[case::v0_7_api("/rpc/v0_7", "v07/starknet_api_openrpc.json", &[], Api::Both)]
     #[case::v0_7_api_websocket("/ws/rpc/v0_7", "v07/starknet_api_openrpc.json", &[], Api::WebsocketOnly)]
-    #[case::v0_7_trace("/rpc/v0_7", "v07/starknet_trace_api_openrpc.json", &[], Api::HttpOnly)]
+    #[case::v0_7_trace("/rpc/v0_7", "v07/starknet_trace_api_openrpc.json", &[], Api::Both)]
     #[case::v0_7_trace_websocket("/ws/rpc/v0_7", "v07/starknet_trace_api_openrpc.json", &[], Api::WebsocketOnly)]
-    #[case::v0_7_write("/rpc/v0_7", "v07/starknet_write_api.json", &[], Api::HttpOnly)]
+    #[case::v0_7_write("/rpc/v0_7", "v07/starknet_write_api.json", &[], Api::Both)]
     #[case::v0_7_write_websocket("/ws/rpc/v0_7", "v07/starknet_write_api.json", &[], Api::WebsocketOnly)]
-    // get_transaction_status is now part of the official spec, so we are phasing it out.
-    #[case::v0_7_pathfinder("/rpc/v0_7", "pathfinder_rpc_api.json", &["pathfinder_version", "pathfinder_getTransactionStatus"], Api::HttpOnly)]
-    #[case::v0_7_pathfinder_websocket("/ws/rpc/v0_7", "pathfinder_rpc_api.json", &["pathfinder_version", "pathfinder_getTransactionStatus"], Api::WebsocketOnly)]
-
-    #[case::pathfinder("/rpc/pathfinder/v0.1", "pathfinder_rpc_api.json", &[], Api::HttpOnly)]
-    #[case::pathfinder("/ws/rpc/pathfinder/v0_1", "pathfinder_rpc_api.json", &[], Api::WebsocketOnly)]
+    #[case::v0_7_pathfinder("/rpc/v0_7", "pathfinder_rpc_api.json", PATHFINDER_ONLY_METHODS, Api::Both)]
+    #[case::v0_7_pathfinder_websocket("/ws/rpc/v0_7", "pathfinder_rpc_api.json", PATHFINDER_ONLY_METHODS, Api::WebsocketOnly)]
+
+    // Subscriptions are only served over websockets.
+    #[case::pathfinder("/rpc/pathfinder/v0.1", "pathfinder_rpc_api.json", &["pathfinder_subscribeStorageChanges"], Api::HttpOnly)]
+    #[case::pathfinder_websocket("/rpc/pathfinder/v0.1", "pathfinder_rpc_api.json", &[], Api::WebsocketOnly)]
+    #[case::pathfinder_v0_1("/rpc/pathfinder/v0_1", "pathfinder_rpc_api.json", &["pathfinder_subscribeStorageChanges"], Api::HttpOnly)]
+    #[case::pathfinder_v0_1_websocket("/rpc/pathfinder/v0_1", "pathfinder_rpc_api.json", &[], Api::WebsocketOnly)]
+    #[case::pathfinder_ws("/ws/rpc/pathfinder/v0_1", "pathfinder_rpc_api.json", &[], Api::WebsocketOnly)]
 
     #[tokio::test]
     async fn rpc_routing(
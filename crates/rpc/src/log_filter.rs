@@ -0,0 +1,175 @@
+//! Changing the node's tracing filter at runtime, e.g. enabling
+//! `pathfinder_executor=debug` for a few minutes to investigate an execution
+//! discrepancy which only shows up in production, without restarting.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Replaces the filter of the tracing subscriber with one parsed from
+/// `RUST_LOG` style directives.
+pub type Reload = dyn Fn(&str) -> anyhow::Result<()> + Send + Sync;
+
+/// Handle to the node's tracing filter.
+#[derive(Clone)]
+pub struct LogFilter(Arc<Inner>);
+
+struct Inner {
+    /// The filter the node was started with, restored once a temporary filter
+    /// expires.
+    initial: String,
+    reload: Box<Reload>,
+    state: Mutex<State>,
+}
+
+struct State {
+    current: String,
+    reverts_at: Option<SystemTime>,
+    /// Incremented on every change so that a pending revert does not undo a
+    /// later change.
+    generation: u64,
+}
+
+/// The filter in effect.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CurrentLogFilter {
+    pub filter: String,
+    pub initial: String,
+    /// When the filter reverts to the initial one, if it is temporary.
+    pub reverts_at: Option<SystemTime>,
+}
+
+impl LogFilter {
+    pub fn new(
+        initial: String,
+        reload: impl Fn(&str) -> anyhow::Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(Inner {
+            state: Mutex::new(State {
+                current: initial.clone(),
+                reverts_at: None,
+                generation: 0,
+            }),
+            initial,
+            reload: Box::new(reload),
+        }))
+    }
+
+    pub fn current(&self) -> CurrentLogFilter {
+        let state = self.0.state.lock().unwrap();
+        CurrentLogFilter {
+            filter: state.current.clone(),
+            initial: self.0.initial.clone(),
+            reverts_at: state.reverts_at,
+        }
+    }
+
+    /// Replaces the filter, reverting to the initial filter after
+    /// `revert_after` if set. Must be called from within a tokio runtime.
+    pub fn set(
+        &self,
+        filter: &str,
+        revert_after: Option<Duration>,
+    ) -> anyhow::Result<CurrentLogFilter> {
+        let generation = {
+            let mut state = self.0.state.lock().unwrap();
+            (self.0.reload)(filter)?;
+            state.current = filter.to_owned();
+            state.reverts_at = revert_after.map(|after| SystemTime::now() + after);
+            state.generation += 1;
+            state.generation
+        };
+        tracing::info!(%filter, ?revert_after, "Changed log filter");
+
+        if let Some(revert_after) = revert_after {
+            let this = self.clone();
+            util::task::spawn(async move {
+                tokio::time::sleep(revert_after).await;
+                this.revert(generation);
+            });
+        }
+
+        Ok(self.current())
+    }
+
+    /// Restores the initial filter unless the filter was changed again since
+    /// `generation`.
+    fn revert(&self, generation: u64) {
+        let mut state = self.0.state.lock().unwrap();
+        if state.generation != generation {
+            return;
+        }
+
+        if let Err(error) = (self.0.reload)(&self.0.initial) {
+            tracing::warn!(%error, "Restoring initial log filter failed");
+            return;
+        }
+        state.current = self.0.initial.clone();
+        state.reverts_at = None;
+        state.generation += 1;
+        drop(state);
+
+        tracing::info!(filter=%self.0.initial, "Restored initial log filter");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_filter() -> (LogFilter, Arc<Mutex<Vec<String>>>) {
+        let reloads = Arc::new(Mutex::new(Vec::new()));
+        let log_filter = LogFilter::new("pathfinder=info".to_owned(), {
+            let reloads = reloads.clone();
+            move |filter| {
+                anyhow::ensure!(!filter.contains(' '), "Invalid filter");
+                reloads.lock().unwrap().push(filter.to_owned());
+                Ok(())
+            }
+        });
+        (log_filter, reloads)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn temporary_filter_is_reverted() {
+        let (log_filter, reloads) = log_filter();
+
+        let current = log_filter
+            .set("pathfinder_executor=debug", Some(Duration::from_secs(600)))
+            .unwrap();
+        assert_eq!(current.filter, "pathfinder_executor=debug");
+        assert!(current.reverts_at.is_some());
+
+        tokio::time::sleep(Duration::from_secs(601)).await;
+
+        assert_eq!(log_filter.current().filter, "pathfinder=info");
+        assert_eq!(log_filter.current().reverts_at, None);
+        assert_eq!(
+            *reloads.lock().unwrap(),
+            vec!["pathfinder_executor=debug", "pathfinder=info"]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn later_change_is_not_reverted() {
+        let (log_filter, reloads) = log_filter();
+
+        log_filter
+            .set("pathfinder_executor=debug", Some(Duration::from_secs(60)))
+            .unwrap();
+        log_filter.set("pathfinder=debug", None).unwrap();
+
+        tokio::time::sleep(Duration::from_secs(61)).await;
+
+        assert_eq!(log_filter.current().filter, "pathfinder=debug");
+        assert_eq!(reloads.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn invalid_filter_is_rejected() {
+        let (log_filter, _) = log_filter();
+
+        log_filter.set("not a filter", None).unwrap_err();
+
+        assert_eq!(log_filter.current().filter, "pathfinder=info");
+    }
+}
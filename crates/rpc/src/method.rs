@@ -66,4 +66,4 @@ pub use syncing::syncing;
 pub use trace_block_transactions::trace_block_transactions;
 pub use trace_transaction::trace_transaction;
 
-const REORG_SUBSCRIPTION_NAME: &str = "starknet_subscriptionReorg";
+pub(crate) const REORG_SUBSCRIPTION_NAME: &str = "starknet_subscriptionReorg";
@@ -1,14 +1,20 @@
+use anyhow::Context;
+use pathfinder_common::transaction::TransactionVariant;
 use pathfinder_common::{ClassHash, TransactionHash};
+use pathfinder_compiler::limits::ResourcesExceeded;
 use starknet_gateway_client::GatewayApi;
+use starknet_gateway_types::class_hash::compute_class_hash;
 use starknet_gateway_types::error::SequencerError;
 use starknet_gateway_types::request::add_transaction::{
+    self,
     CairoContractDefinition,
     ContractDefinition,
     SierraContractDefinition,
 };
 
 use crate::context::RpcContext;
-use crate::types::request::BroadcastedDeclareTransaction;
+use crate::idempotency::IdempotencyKeyReused;
+use crate::types::request::{BroadcastedDeclareTransaction, BroadcastedTransaction};
 
 #[derive(Debug)]
 pub enum AddDeclareTransactionError {
@@ -24,6 +30,7 @@ pub enum AddDeclareTransactionError {
     NonAccount,
     UnsupportedTransactionVersion,
     UnsupportedContractClassVersion,
+    IdempotencyKeyReused,
     UnexpectedError(String),
 }
 
@@ -54,11 +61,29 @@ impl From<AddDeclareTransactionError> for crate::error::ApplicationError {
             AddDeclareTransactionError::UnsupportedContractClassVersion => {
                 Self::UnsupportedContractClassVersion
             }
+            AddDeclareTransactionError::IdempotencyKeyReused => Self::IdempotencyKeyReused,
             AddDeclareTransactionError::UnexpectedError(data) => Self::UnexpectedError { data },
         }
     }
 }
 
+impl From<IdempotencyKeyReused> for AddDeclareTransactionError {
+    fn from(_: IdempotencyKeyReused) -> Self {
+        Self::IdempotencyKeyReused
+    }
+}
+
+impl From<PrecheckError> for AddDeclareTransactionError {
+    fn from(value: PrecheckError) -> Self {
+        match value {
+            PrecheckError::ClassAlreadyDeclared => Self::ClassAlreadyDeclared,
+            PrecheckError::CompilationFailed(message) => Self::CompilationFailed(message),
+            PrecheckError::ContractClassSizeIsTooLarge => Self::ContractClassSizeIsTooLarge,
+            PrecheckError::Internal(error) => Self::UnexpectedError(error.to_string()),
+        }
+    }
+}
+
 impl From<anyhow::Error> for AddDeclareTransactionError {
     fn from(value: anyhow::Error) -> Self {
         AddDeclareTransactionError::UnexpectedError(value.to_string())
@@ -152,6 +177,9 @@ pub struct Input {
     // An undocumented parameter that we forward to the sequencer API
     // A deploy token is required to deploy contracts on Starknet mainnet only.
     token: Option<String>,
+    /// Retries carrying the same key return the response of the first
+    /// successful submission instead of being submitted again.
+    idempotency_key: Option<String>,
 }
 
 impl crate::dto::DeserializeForVersion for Input {
@@ -159,15 +187,17 @@ impl crate::dto::DeserializeForVersion for Input {
         value.deserialize_map(|value| {
             let declare_transaction = value.deserialize("declare_transaction")?;
             let token = value.deserialize_optional_serde("token")?;
+            let idempotency_key = value.deserialize_optional_serde("idempotency_key")?;
             Ok(Self {
                 declare_transaction,
                 token,
+                idempotency_key,
             })
         })
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Output {
     transaction_hash: TransactionHash,
     class_hash: ClassHash,
@@ -177,107 +207,234 @@ pub async fn add_declare_transaction(
     context: RpcContext,
     input: Input,
 ) -> Result<Output, AddDeclareTransactionError> {
-    use starknet_gateway_types::request::add_transaction;
+    let Transaction::Declare(tx) = input.declare_transaction;
+    let request = crate::idempotency::request_hash(&tx);
+    context
+        .submissions
+        .submit(input.idempotency_key, request, async {
+            if context.config.declare_precheck {
+                precheck(context.clone(), tx.clone()).await?;
+            }
+            let request = declare_request(tx.clone())?;
+            match context
+                .sequencer
+                .add_declare_transaction(request, input.token.clone())
+                .await
+            {
+                Ok(response) => Ok(Output {
+                    transaction_hash: response.transaction_hash,
+                    class_hash: response.class_hash,
+                }),
+                Err(error) if crate::outbox::should_enqueue(&context, &error) => {
+                    let transaction = crate::outbox::enqueue(
+                        &context,
+                        BroadcastedTransaction::Declare(tx),
+                        input.token,
+                        &error,
+                    )
+                    .await?;
+                    let class_hash = match transaction.variant {
+                        TransactionVariant::DeclareV0(tx) | TransactionVariant::DeclareV1(tx) => {
+                            tx.class_hash
+                        }
+                        TransactionVariant::DeclareV2(tx) => tx.class_hash,
+                        TransactionVariant::DeclareV3(tx) => tx.class_hash,
+                        _ => unreachable!("Declare transaction"),
+                    };
+                    Ok(Output {
+                        transaction_hash: transaction.hash,
+                        class_hash,
+                    })
+                }
+                Err(error) => {
+                    crate::rejected_transactions::record(
+                        &context,
+                        BroadcastedTransaction::Declare(tx),
+                        &error,
+                    )
+                    .await;
+                    Err(error.into())
+                }
+            }
+        })
+        .await
+}
+
+/// Why [precheck] rejected a declare.
+#[derive(Debug)]
+pub(crate) enum PrecheckError {
+    ClassAlreadyDeclared,
+    CompilationFailed(String),
+    ContractClassSizeIsTooLarge,
+    Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for PrecheckError {
+    fn from(value: anyhow::Error) -> Self {
+        Self::Internal(value)
+    }
+}
+
+/// Rejects declares the gateway is certain to refuse without spending any of
+/// its quota on them: classes larger than the compiler accepts, classes which
+/// are declared already and Sierra classes which fail to compile.
+///
+/// Malformed classes and compilations abandoned for taking too long are
+/// forwarded, so that the gateway reports its own error or might still
+/// manage them.
+pub(crate) async fn precheck(
+    context: RpcContext,
+    declare_transaction: BroadcastedDeclareTransaction,
+) -> Result<(), PrecheckError> {
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_execution(move |_| {
+        let _g = span.enter();
+
+        let (definition, is_sierra) = match &declare_transaction {
+            // Refused when converting it into the gateway's request format.
+            BroadcastedDeclareTransaction::V0(_) => return Ok(()),
+            BroadcastedDeclareTransaction::V1(tx) => (tx.contract_class.serialize_to_json(), false),
+            BroadcastedDeclareTransaction::V2(tx) => (tx.contract_class.serialize_to_json(), true),
+            BroadcastedDeclareTransaction::V3(tx) => (tx.contract_class.serialize_to_json(), true),
+        };
+        let definition = match definition {
+            Ok(definition) => definition,
+            Err(error) => {
+                tracing::debug!(%error, "Forwarding declare of malformed class");
+                return Ok(());
+            }
+        };
+
+        let max_size = pathfinder_compiler::limits::configured().max_definition_size;
+        if definition.len() > max_size {
+            tracing::debug!(size=%definition.len(), %max_size, "Rejecting declare of large class");
+            return Err(PrecheckError::ContractClassSizeIsTooLarge);
+        }
+
+        let class_hash = match compute_class_hash(&definition) {
+            Ok(class_hash) => class_hash.hash(),
+            Err(error) => {
+                tracing::debug!(%error, "Forwarding declare of malformed class");
+                return Ok(());
+            }
+        };
+
+        let mut db = context
+            .execution_storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+        let declared = tx
+            .compressed_class_definition_at(pathfinder_storage::BlockId::Latest, class_hash)?
+            .is_some()
+            || context
+                .pending_data
+                .get(&tx)
+                .context("Querying pending data")?
+                .state_update
+                .class_is_declared(class_hash);
+        if declared {
+            tracing::debug!(%class_hash, "Rejecting declare of declared class");
+            return Err(PrecheckError::ClassAlreadyDeclared);
+        }
+        drop(tx);
+        drop(db);
+
+        if is_sierra {
+            if let Err(error) = pathfinder_compiler::compile_to_casm(&definition) {
+                match error.downcast_ref::<ResourcesExceeded>() {
+                    Some(ResourcesExceeded::DefinitionSize { .. }) => {
+                        return Err(PrecheckError::ContractClassSizeIsTooLarge);
+                    }
+                    Some(ResourcesExceeded::Timeout(_)) => {}
+                    None => {
+                        tracing::debug!(%class_hash, %error, "Rejecting declare of invalid class");
+                        return Err(PrecheckError::CompilationFailed(format!("{error:#}")));
+                    }
+                }
+            }
+        }
 
-    match input.declare_transaction {
-        Transaction::Declare(BroadcastedDeclareTransaction::V0(_)) => {
+        Ok(())
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+/// Converts the transaction into the gateway's request format.
+pub(crate) fn declare_request(
+    declare_transaction: BroadcastedDeclareTransaction,
+) -> Result<add_transaction::Declare, AddDeclareTransactionError> {
+    match declare_transaction {
+        BroadcastedDeclareTransaction::V0(_) => {
             Err(AddDeclareTransactionError::UnsupportedTransactionVersion)
         }
-        Transaction::Declare(BroadcastedDeclareTransaction::V1(tx)) => {
+        BroadcastedDeclareTransaction::V1(tx) => {
             let contract_definition: CairoContractDefinition = tx
                 .contract_class
                 .try_into()
                 .map_err(|e| anyhow::anyhow!("Failed to convert contract definition: {}", e))?;
 
-            let response = context
-                .sequencer
-                .add_declare_transaction(
-                    add_transaction::Declare::V1(add_transaction::DeclareV0V1V2 {
-                        version: tx.version,
-                        max_fee: tx.max_fee,
-                        signature: tx.signature,
-                        contract_class: ContractDefinition::Cairo(contract_definition),
-                        sender_address: tx.sender_address,
-                        nonce: tx.nonce,
-                        compiled_class_hash: None,
-                    }),
-                    input.token,
-                )
-                .await?;
-
-            Ok(Output {
-                transaction_hash: response.transaction_hash,
-                class_hash: response.class_hash,
-            })
+            Ok(add_transaction::Declare::V1(
+                add_transaction::DeclareV0V1V2 {
+                    version: tx.version,
+                    max_fee: tx.max_fee,
+                    signature: tx.signature,
+                    contract_class: ContractDefinition::Cairo(contract_definition),
+                    sender_address: tx.sender_address,
+                    nonce: tx.nonce,
+                    compiled_class_hash: None,
+                },
+            ))
         }
-        Transaction::Declare(BroadcastedDeclareTransaction::V2(tx)) => {
+        BroadcastedDeclareTransaction::V2(tx) => {
             let contract_definition: SierraContractDefinition = tx
                 .contract_class
                 .try_into()
                 .map_err(|e| anyhow::anyhow!("Failed to convert contract definition: {}", e))?;
 
-            let response = context
-                .sequencer
-                .add_declare_transaction(
-                    add_transaction::Declare::V2(add_transaction::DeclareV0V1V2 {
-                        version: tx.version,
-                        max_fee: tx.max_fee,
-                        signature: tx.signature,
-                        contract_class: ContractDefinition::Sierra(contract_definition),
-                        sender_address: tx.sender_address,
-                        nonce: tx.nonce,
-                        compiled_class_hash: Some(tx.compiled_class_hash),
-                    }),
-                    input.token,
-                )
-                .await?;
-
-            Ok(Output {
-                transaction_hash: response.transaction_hash,
-                class_hash: response.class_hash,
-            })
+            Ok(add_transaction::Declare::V2(
+                add_transaction::DeclareV0V1V2 {
+                    version: tx.version,
+                    max_fee: tx.max_fee,
+                    signature: tx.signature,
+                    contract_class: ContractDefinition::Sierra(contract_definition),
+                    sender_address: tx.sender_address,
+                    nonce: tx.nonce,
+                    compiled_class_hash: Some(tx.compiled_class_hash),
+                },
+            ))
         }
-        Transaction::Declare(BroadcastedDeclareTransaction::V3(tx)) => {
+        BroadcastedDeclareTransaction::V3(tx) => {
             let contract_definition: SierraContractDefinition = tx
                 .contract_class
                 .try_into()
                 .map_err(|e| anyhow::anyhow!("Failed to convert contract definition: {}", e))?;
 
-            let response = context
-                .sequencer
-                .add_declare_transaction(
-                    add_transaction::Declare::V3(add_transaction::DeclareV3 {
-                        signature: tx.signature,
-                        nonce: tx.nonce,
-                        nonce_data_availability_mode:
-                            pathfinder_common::transaction::DataAvailabilityMode::from(
-                                tx.nonce_data_availability_mode,
-                            )
-                            .into(),
-                        fee_data_availability_mode:
-                            pathfinder_common::transaction::DataAvailabilityMode::from(
-                                tx.fee_data_availability_mode,
-                            )
-                            .into(),
-                        resource_bounds: pathfinder_common::transaction::ResourceBounds::from(
-                            tx.resource_bounds,
-                        )
-                        .into(),
-                        tip: tx.tip,
-                        paymaster_data: tx.paymaster_data,
-                        contract_class: contract_definition,
-                        compiled_class_hash: tx.compiled_class_hash,
-                        sender_address: tx.sender_address,
-                        account_deployment_data: tx.account_deployment_data,
-                    }),
-                    input.token,
+            Ok(add_transaction::Declare::V3(add_transaction::DeclareV3 {
+                signature: tx.signature,
+                nonce: tx.nonce,
+                nonce_data_availability_mode:
+                    pathfinder_common::transaction::DataAvailabilityMode::from(
+                        tx.nonce_data_availability_mode,
+                    )
+                    .into(),
+                fee_data_availability_mode:
+                    pathfinder_common::transaction::DataAvailabilityMode::from(
+                        tx.fee_data_availability_mode,
+                    )
+                    .into(),
+                resource_bounds: pathfinder_common::transaction::ResourceBounds::from(
+                    tx.resource_bounds,
                 )
-                .await?;
-
-            Ok(Output {
-                transaction_hash: response.transaction_hash,
-                class_hash: response.class_hash,
-            })
+                .into(),
+                tip: tx.tip,
+                paymaster_data: tx.paymaster_data,
+                contract_class: contract_definition,
+                compiled_class_hash: tx.compiled_class_hash,
+                sender_address: tx.sender_address,
+                account_deployment_data: tx.account_deployment_data,
+            }))
         }
     }
 }
@@ -411,6 +568,7 @@ mod tests {
                 let expected = Input {
                     declare_transaction: test_declare_txn(),
                     token: None,
+                    idempotency_key: None,
                 };
                 assert_eq!(input, expected);
             }
@@ -434,6 +592,7 @@ mod tests {
                 let expected = Input {
                     declare_transaction: test_declare_txn(),
                     token: Some("token".to_owned()),
+                    idempotency_key: None,
                 };
                 assert_eq!(input, expected);
             }
@@ -509,6 +668,7 @@ mod tests {
                 let expected = Input {
                     declare_transaction: test_declare_txn(),
                     token: None,
+                    idempotency_key: None,
                 };
                 pretty_assertions_sorted::assert_eq!(input, expected);
             }
@@ -534,12 +694,113 @@ mod tests {
                 let expected = Input {
                     declare_transaction: test_declare_txn(),
                     token: Some("token".to_owned()),
+                    idempotency_key: None,
                 };
                 pretty_assertions_sorted::assert_eq!(input, expected);
             }
         }
     }
 
+    mod precheck {
+        use pathfinder_common::{BlockHeader, BlockNumber, StateUpdate};
+
+        use super::*;
+
+        fn context() -> RpcContext {
+            let mut context = RpcContext::for_tests();
+            context.config.declare_precheck = true;
+            context
+        }
+
+        fn declare_v1(contract_class: CairoContractClass) -> Input {
+            Input {
+                declare_transaction: Transaction::Declare(BroadcastedDeclareTransaction::V1(
+                    BroadcastedDeclareTransactionV1 {
+                        version: TransactionVersion::ONE,
+                        max_fee: fee!("0xfffffffffff"),
+                        signature: vec![],
+                        nonce: TransactionNonce(Default::default()),
+                        contract_class,
+                        sender_address: contract_address!("0x1"),
+                    },
+                )),
+                token: None,
+                idempotency_key: None,
+            }
+        }
+
+        #[tokio::test]
+        async fn declared_class_is_rejected() {
+            let context = context();
+            let class_hash = CONTRACT_CLASS.class_hash().unwrap().hash();
+
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.insert_cairo_class(class_hash, CONTRACT_DEFINITION)
+                .unwrap();
+            let block_number = BlockNumber::new_or_panic(3);
+            let header = BlockHeader::builder()
+                .number(block_number)
+                .finalize_with_hash(block_hash!("0xb3"));
+            tx.insert_block_header(&header).unwrap();
+            tx.insert_state_update(
+                block_number,
+                &StateUpdate::default()
+                    .with_block_hash(header.hash)
+                    .with_declared_cairo_class(class_hash),
+            )
+            .unwrap();
+            tx.commit().unwrap();
+
+            let error = add_declare_transaction(context, declare_v1(CONTRACT_CLASS.clone()))
+                .await
+                .unwrap_err();
+            assert_matches::assert_matches!(
+                error,
+                AddDeclareTransactionError::ClassAlreadyDeclared
+            );
+        }
+
+        #[tokio::test]
+        async fn malformed_class_is_forwarded() {
+            let contract_class = CairoContractClass {
+                program: "not base64".to_owned(),
+                ..CONTRACT_CLASS.clone()
+            };
+            let Transaction::Declare(tx) = declare_v1(contract_class).declare_transaction;
+
+            precheck(context(), tx).await.unwrap();
+        }
+
+        #[tokio::test]
+        async fn uncompilable_class_is_rejected() {
+            let input = Input {
+                declare_transaction: Transaction::Declare(BroadcastedDeclareTransaction::V2(
+                    BroadcastedDeclareTransactionV2 {
+                        version: TransactionVersion::TWO,
+                        max_fee: fee!("0xfffffffffff"),
+                        signature: vec![],
+                        nonce: TransactionNonce(Default::default()),
+                        contract_class: SierraContractClass {
+                            sierra_program: vec![],
+                            ..SIERRA_CLASS.clone()
+                        },
+                        sender_address: contract_address!("0x1"),
+                        compiled_class_hash: casm_hash!("0x1"),
+                    },
+                )),
+                token: None,
+                idempotency_key: None,
+            };
+
+            let error = add_declare_transaction(context(), input).await.unwrap_err();
+            assert_matches::assert_matches!(
+                error,
+                AddDeclareTransactionError::CompilationFailed(_)
+            );
+        }
+    }
+
     #[test_log::test(tokio::test)]
     #[ignore = "gateway 429"]
     async fn invalid_contract_definition_v1() {
@@ -564,6 +825,7 @@ mod tests {
         let input = Input {
             declare_transaction,
             token: None,
+            idempotency_key: None,
         };
         let error = add_declare_transaction(context, input).await.unwrap_err();
         assert_matches::assert_matches!(error, AddDeclareTransactionError::UnexpectedError(_));
@@ -598,6 +860,7 @@ mod tests {
         let input = Input {
             declare_transaction,
             token: None,
+            idempotency_key: None,
         };
         let error = add_declare_transaction(context, input).await.unwrap_err();
         assert_matches::assert_matches!(error, AddDeclareTransactionError::UnexpectedError(_));
@@ -622,6 +885,7 @@ mod tests {
         let input = Input {
             declare_transaction,
             token: None,
+            idempotency_key: None,
         };
         let error = add_declare_transaction(context, input).await.unwrap_err();
         assert_matches::assert_matches!(error, AddDeclareTransactionError::UnexpectedError(_));
@@ -646,6 +910,7 @@ mod tests {
         let input = Input {
             declare_transaction,
             token: None,
+            idempotency_key: None,
         };
         let error = add_declare_transaction(context, input).await.unwrap_err();
         assert_matches::assert_matches!(error, AddDeclareTransactionError::DuplicateTransaction);
@@ -673,6 +938,7 @@ mod tests {
         let input = Input {
             declare_transaction,
             token: None,
+            idempotency_key: None,
         };
         let err = add_declare_transaction(context, input).await.unwrap_err();
         assert_matches::assert_matches!(
@@ -703,6 +969,7 @@ mod tests {
         let input = Input {
             declare_transaction,
             token: None,
+            idempotency_key: None,
         };
         let err = add_declare_transaction(context, input).await.unwrap_err();
         assert_matches::assert_matches!(
@@ -756,6 +1023,7 @@ mod tests {
         let input = Input {
             declare_transaction: Transaction::Declare(BroadcastedDeclareTransaction::V3(input)),
             token: None,
+            idempotency_key: None,
         };
 
         let err = add_declare_transaction(context, input).await.unwrap_err();
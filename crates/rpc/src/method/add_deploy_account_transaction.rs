@@ -4,9 +4,11 @@ use starknet_gateway_client::GatewayApi;
 use starknet_gateway_types::error::{KnownStarknetErrorCode, SequencerError};
 
 use crate::context::RpcContext;
+use crate::idempotency::IdempotencyKeyReused;
 use crate::types::request::{
     BroadcastedDeployAccountTransaction,
     BroadcastedDeployAccountTransactionV1,
+    BroadcastedTransaction,
 };
 
 #[derive(Debug, PartialEq, Eq)]
@@ -29,6 +31,9 @@ impl crate::dto::DeserializeForVersion for Transaction {
 #[derive(Debug, PartialEq, Eq)]
 pub struct Input {
     deploy_account_transaction: Transaction,
+    /// Retries carrying the same key return the response of the first
+    /// successful submission instead of being submitted again.
+    idempotency_key: Option<String>,
 }
 
 impl crate::dto::DeserializeForVersion for Input {
@@ -36,12 +41,13 @@ impl crate::dto::DeserializeForVersion for Input {
         value.deserialize_map(|value| {
             Ok(Self {
                 deploy_account_transaction: value.deserialize("deploy_account_transaction")?,
+                idempotency_key: value.deserialize_optional_serde("idempotency_key")?,
             })
         })
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Output {
     transaction_hash: TransactionHash,
     contract_address: ContractAddress,
@@ -57,6 +63,7 @@ pub enum AddDeployAccountTransactionError {
     DuplicateTransaction,
     NonAccount,
     UnsupportedTransactionVersion,
+    IdempotencyKeyReused,
     UnexpectedError(String),
 }
 
@@ -72,11 +79,18 @@ impl From<AddDeployAccountTransactionError> for crate::error::ApplicationError {
             DuplicateTransaction => Self::DuplicateTransaction,
             NonAccount => Self::NonAccount,
             UnsupportedTransactionVersion => Self::UnsupportedTxVersion,
+            IdempotencyKeyReused => Self::IdempotencyKeyReused,
             UnexpectedError(data) => Self::UnexpectedError { data },
         }
     }
 }
 
+impl From<IdempotencyKeyReused> for AddDeployAccountTransactionError {
+    fn from(_: IdempotencyKeyReused) -> Self {
+        Self::IdempotencyKeyReused
+    }
+}
+
 impl From<SequencerError> for AddDeployAccountTransactionError {
     fn from(e: SequencerError) -> Self {
         use starknet_gateway_types::error::KnownStarknetErrorCode::{
@@ -127,12 +141,42 @@ pub async fn add_deploy_account_transaction(
         Transaction::DeployAccount(tx) => tx.deployed_contract_address(),
     };
     let Transaction::DeployAccount(tx) = input.deploy_account_transaction;
-    let response = add_deploy_account_transaction_impl(&context, tx).await?;
-
-    Ok(Output {
-        transaction_hash: response.transaction_hash,
-        contract_address,
-    })
+    let request = crate::idempotency::request_hash(&tx);
+    context
+        .submissions
+        .submit(input.idempotency_key, request, async {
+            let transaction_hash = match add_deploy_account_transaction_impl(&context, tx.clone())
+                .await
+            {
+                Ok(response) => response.transaction_hash,
+                Err(error) if crate::outbox::should_enqueue(&context, &error) => {
+                    crate::outbox::enqueue(
+                        &context,
+                        BroadcastedTransaction::DeployAccount(tx),
+                        None,
+                        &error,
+                    )
+                    .await
+                    .map_err(|e| AddDeployAccountTransactionError::UnexpectedError(e.to_string()))?
+                    .hash
+                }
+                Err(error) => {
+                    crate::rejected_transactions::record(
+                        &context,
+                        BroadcastedTransaction::DeployAccount(tx),
+                        &error,
+                    )
+                    .await;
+                    return Err(error.into());
+                }
+            };
+
+            Ok(Output {
+                transaction_hash,
+                contract_address,
+            })
+        })
+        .await
 }
 
 pub(crate) async fn add_deploy_account_transaction_impl(
@@ -338,6 +382,7 @@ mod tests {
                     ),
                 }),
             ),
+            idempotency_key: None,
         }
     }
 
@@ -402,6 +447,7 @@ mod tests {
             deploy_account_transaction: Transaction::DeployAccount(
                 BroadcastedDeployAccountTransaction::V3(input),
             ),
+            idempotency_key: None,
         };
 
         let error = add_deploy_account_transaction(context, input)
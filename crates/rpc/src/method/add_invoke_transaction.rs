@@ -4,7 +4,8 @@ use starknet_gateway_client::GatewayApi;
 use starknet_gateway_types::error::SequencerError;
 
 use crate::context::RpcContext;
-use crate::types::request::BroadcastedInvokeTransaction;
+use crate::idempotency::IdempotencyKeyReused;
+use crate::types::request::{BroadcastedInvokeTransaction, BroadcastedTransaction};
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Transaction {
@@ -26,6 +27,9 @@ impl crate::dto::DeserializeForVersion for Transaction {
 #[derive(Debug, PartialEq, Eq)]
 pub struct Input {
     invoke_transaction: Transaction,
+    /// Retries carrying the same key return the response of the first
+    /// successful submission instead of being submitted again.
+    idempotency_key: Option<String>,
 }
 
 impl crate::dto::DeserializeForVersion for Input {
@@ -33,12 +37,13 @@ impl crate::dto::DeserializeForVersion for Input {
         value.deserialize_map(|value| {
             Ok(Self {
                 invoke_transaction: value.deserialize("invoke_transaction")?,
+                idempotency_key: value.deserialize_optional_serde("idempotency_key")?,
             })
         })
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Output {
     transaction_hash: TransactionHash,
 }
@@ -52,6 +57,7 @@ pub enum AddInvokeTransactionError {
     DuplicateTransaction,
     NonAccount,
     UnsupportedTransactionVersion,
+    IdempotencyKeyReused,
     UnexpectedError(String),
 }
 
@@ -71,11 +77,18 @@ impl From<AddInvokeTransactionError> for crate::error::ApplicationError {
             AddInvokeTransactionError::DuplicateTransaction => Self::DuplicateTransaction,
             AddInvokeTransactionError::NonAccount => Self::NonAccount,
             AddInvokeTransactionError::UnsupportedTransactionVersion => Self::UnsupportedTxVersion,
+            AddInvokeTransactionError::IdempotencyKeyReused => Self::IdempotencyKeyReused,
             AddInvokeTransactionError::UnexpectedError(data) => Self::UnexpectedError { data },
         }
     }
 }
 
+impl From<IdempotencyKeyReused> for AddInvokeTransactionError {
+    fn from(_: IdempotencyKeyReused) -> Self {
+        Self::IdempotencyKeyReused
+    }
+}
+
 impl From<SequencerError> for AddInvokeTransactionError {
     fn from(e: SequencerError) -> Self {
         use starknet_gateway_types::error::KnownStarknetErrorCode::{
@@ -119,11 +132,37 @@ pub async fn add_invoke_transaction(
     input: Input,
 ) -> Result<Output, AddInvokeTransactionError> {
     let Transaction::Invoke(tx) = input.invoke_transaction;
-    let response = add_invoke_transaction_impl(&context, tx).await?;
+    let request = crate::idempotency::request_hash(&tx);
+    context
+        .submissions
+        .submit(input.idempotency_key, request, async {
+            let transaction_hash = match add_invoke_transaction_impl(&context, tx.clone()).await {
+                Ok(response) => response.transaction_hash,
+                Err(error) if crate::outbox::should_enqueue(&context, &error) => {
+                    crate::outbox::enqueue(
+                        &context,
+                        BroadcastedTransaction::Invoke(tx),
+                        None,
+                        &error,
+                    )
+                    .await
+                    .map_err(|e| AddInvokeTransactionError::UnexpectedError(e.to_string()))?
+                    .hash
+                }
+                Err(error) => {
+                    crate::rejected_transactions::record(
+                        &context,
+                        BroadcastedTransaction::Invoke(tx),
+                        &error,
+                    )
+                    .await;
+                    return Err(error.into());
+                }
+            };
 
-    Ok(Output {
-        transaction_hash: response.transaction_hash,
-    })
+            Ok(Output { transaction_hash })
+        })
+        .await
 }
 
 pub(crate) async fn add_invoke_transaction_impl(
@@ -284,6 +323,7 @@ mod tests {
                     .unwrap();
             let expected = Input {
                 invoke_transaction: test_invoke_txn(),
+                idempotency_key: None,
             };
             pretty_assertions_sorted::assert_eq!(input, expected);
         }
@@ -318,6 +358,7 @@ mod tests {
                 Input::deserialize(crate::dto::Value::new(named, crate::RpcVersion::V07)).unwrap();
             let expected = Input {
                 invoke_transaction: test_invoke_txn(),
+                idempotency_key: None,
             };
             assert_eq!(input, expected);
         }
@@ -387,6 +428,7 @@ mod tests {
 
         let input = Input {
             invoke_transaction: Transaction::Invoke(BroadcastedInvokeTransaction::V1(input)),
+            idempotency_key: None,
         };
 
         let error = add_invoke_transaction(context, input).await.unwrap_err();
@@ -452,6 +494,7 @@ mod tests {
 
         let input = Input {
             invoke_transaction: Transaction::Invoke(BroadcastedInvokeTransaction::V3(input)),
+            idempotency_key: None,
         };
 
         let error = add_invoke_transaction(context, input).await.unwrap_err();
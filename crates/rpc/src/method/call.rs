@@ -16,6 +16,7 @@ pub enum CallError {
         revert_error: Option<String>,
         revert_error_stack: pathfinder_executor::ErrorStack,
     },
+    ExecutionRefused(pathfinder_executor::ExecutionRefused),
 }
 
 impl From<anyhow::Error> for CallError {
@@ -34,6 +35,7 @@ impl From<pathfinder_executor::CallError> for CallError {
                 revert_error: Some(format!("Execution error: {}", error)),
                 revert_error_stack: error_stack,
             },
+            ExecutionRefused(refused) => Self::ExecutionRefused(refused),
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -63,6 +65,7 @@ impl From<CallError> for ApplicationError {
                 revert_error,
                 revert_error_stack,
             },
+            CallError::ExecutionRefused(refused) => ApplicationError::ExecutionRefused(refused),
             CallError::Internal(e) => ApplicationError::Internal(e),
             CallError::Custom(e) => ApplicationError::Custom(e),
         }
@@ -111,7 +114,7 @@ pub struct Output(pub Vec<CallResultValue>);
 
 pub async fn call(context: RpcContext, input: Input) -> Result<Output, CallError> {
     let span = tracing::Span::current();
-    let result = util::task::spawn_blocking(move |_| {
+    let result = util::task::spawn_blocking_execution(move |_| {
         let _g = span.enter();
 
         let mut db = context
@@ -147,16 +150,17 @@ pub async fn call(context: RpcContext, input: Input) -> Result<Output, CallError
             pending,
             L1BlobDataAvailability::Disabled,
             context.config.custom_versioned_constants,
-            context.contract_addresses.eth_l2_token_address,
-            context.contract_addresses.strk_l2_token_address,
-        );
+            context.fee_tokens(),
+        )
+        .with_execution_policy(context.config.execution_policy.clone());
 
         let result = pathfinder_executor::call(
             state,
             input.request.contract_address,
             input.request.entry_point_selector,
             input.request.calldata,
-        )?;
+        )
+        .map_err(|error| crate::executor::annotate_call_error(&db, error))?;
 
         Ok(result)
     })
@@ -231,6 +235,7 @@ mod tests {
     }
 
     mod in_memory {
+        use std::sync::Arc;
 
         use pathfinder_common::{
             felt,
@@ -245,6 +250,7 @@ mod tests {
             StorageAddress,
             StorageValue,
         };
+        use pathfinder_executor::{ExecutionPolicy, ExecutionRefused};
         use starknet_gateway_test_fixtures::class_definitions::{
             CONTRACT_DEFINITION,
             CONTRACT_DEFINITION_CLASS_HASH,
@@ -334,6 +340,45 @@ mod tests {
             assert_eq!(result, Output(vec![CallResultValue(test_value.0)]));
         }
 
+        #[tokio::test]
+        async fn refused_by_execution_policy() {
+            let (mut context, _last_block_header, contract_address, test_key, _test_value) =
+                test_context().await;
+
+            let input = || Input {
+                request: FunctionCall {
+                    contract_address,
+                    entry_point_selector: EntryPoint::hashed(b"get_value"),
+                    calldata: vec![CallParam(*test_key.get())],
+                },
+                block_id: BlockId::Latest,
+            };
+
+            context.config.execution_policy =
+                Arc::new(ExecutionPolicy::new([contract_address], []));
+            let error = call(context.clone(), input()).await.unwrap_err();
+            assert_matches::assert_matches!(
+                error,
+                CallError::ExecutionRefused(ExecutionRefused {
+                    contract_address: address,
+                    selector: None,
+                }) => assert_eq!(address, contract_address)
+            );
+
+            context.config.execution_policy = Arc::new(ExecutionPolicy::new(
+                [],
+                [(contract_address, EntryPoint::hashed(b"get_value"))],
+            ));
+            let error = call(context, input()).await.unwrap_err();
+            assert_matches::assert_matches!(
+                error,
+                CallError::ExecutionRefused(ExecutionRefused {
+                    selector: Some(_),
+                    ..
+                })
+            );
+        }
+
         #[tokio::test]
         async fn storage_updated_in_pending() {
             let (context, last_block_header, contract_address, test_key, test_value) =
@@ -47,7 +47,7 @@ pub struct Output(Vec<pathfinder_executor::types::FeeEstimate>);
 
 pub async fn estimate_fee(context: RpcContext, input: Input) -> Result<Output, EstimateFeeError> {
     let span = tracing::Span::current();
-    let result = util::task::spawn_blocking(move |_| {
+    let result = util::task::spawn_blocking_execution(move |_| {
         let _g = span.enter();
         let mut db = context
             .execution_storage
@@ -82,9 +82,9 @@ pub async fn estimate_fee(context: RpcContext, input: Input) -> Result<Output, E
             pending,
             L1BlobDataAvailability::Enabled,
             context.config.custom_versioned_constants,
-            context.contract_addresses.eth_l2_token_address,
-            context.contract_addresses.strk_l2_token_address,
-        );
+            context.fee_tokens(),
+        )
+        .with_execution_policy(context.config.execution_policy.clone());
 
         let skip_validate = input
             .simulation_flags
@@ -104,7 +104,8 @@ pub async fn estimate_fee(context: RpcContext, input: Input) -> Result<Output, E
             })
             .collect::<Result<Vec<_>, _>>()?;
 
-        let result = pathfinder_executor::estimate(state, transactions)?;
+        let result = pathfinder_executor::estimate(state, transactions)
+            .map_err(|error| crate::executor::annotate_execution_error(&db, error))?;
 
         Ok::<_, EstimateFeeError>(result)
     })
@@ -124,11 +125,19 @@ pub enum EstimateFeeError {
         error: String,
         error_stack: pathfinder_executor::ErrorStack,
     },
+    ExecutionRefused(pathfinder_executor::ExecutionRefused),
 }
 
 impl From<anyhow::Error> for EstimateFeeError {
     fn from(e: anyhow::Error) -> Self {
-        Self::Internal(e)
+        // Declared classes exceeding the compilation limits are the caller's problem.
+        if e.downcast_ref::<pathfinder_compiler::limits::ResourcesExceeded>()
+            .is_some()
+        {
+            Self::Custom(e)
+        } else {
+            Self::Internal(e)
+        }
     }
 }
 
@@ -145,6 +154,7 @@ impl From<pathfinder_executor::TransactionExecutionError> for EstimateFeeError {
                 error,
                 error_stack,
             },
+            ExecutionRefused(refused) => Self::ExecutionRefused(refused),
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -174,6 +184,9 @@ impl From<EstimateFeeError> for ApplicationError {
                 error,
                 error_stack,
             },
+            EstimateFeeError::ExecutionRefused(refused) => {
+                ApplicationError::ExecutionRefused(refused)
+            }
             EstimateFeeError::Internal(e) => ApplicationError::Internal(e),
             EstimateFeeError::Custom(e) => ApplicationError::Custom(e),
         }
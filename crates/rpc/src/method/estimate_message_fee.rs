@@ -65,7 +65,7 @@ pub async fn estimate_message_fee(
     input: EstimateMessageFeeInput,
 ) -> Result<Output, EstimateMessageFeeError> {
     let span = tracing::Span::current();
-    let mut result = util::task::spawn_blocking(move |_| {
+    let mut result = util::task::spawn_blocking_execution(move |_| {
         let _g = span.enter();
         let mut db = context
             .storage
@@ -104,13 +104,14 @@ pub async fn estimate_message_fee(
             pending,
             L1BlobDataAvailability::Enabled,
             context.config.custom_versioned_constants,
-            context.contract_addresses.eth_l2_token_address,
-            context.contract_addresses.strk_l2_token_address,
-        );
+            context.fee_tokens(),
+        )
+        .with_execution_policy(context.config.execution_policy.clone());
 
-        let transaction = create_executor_transaction(input, context.chain_id)?;
+        let transaction = create_executor_transaction(input.message, Fee(1), context.chain_id)?;
 
-        let result = pathfinder_executor::estimate(state, vec![transaction])?;
+        let result = pathfinder_executor::estimate(state, vec![transaction])
+            .map_err(|error| crate::executor::annotate_execution_error(&db, error))?;
 
         Ok::<_, EstimateMessageFeeError>(result)
     })
@@ -137,18 +138,21 @@ pub async fn estimate_message_fee(
     }))
 }
 
-fn create_executor_transaction(
-    input: EstimateMessageFeeInput,
+/// Creates the L1 handler transaction consuming `message`, with
+/// `paid_fee_on_l1` as the fee paid for the message on L1.
+pub(crate) fn create_executor_transaction(
+    message: MsgFromL1,
+    paid_fee_on_l1: Fee,
     chain_id: ChainId,
 ) -> anyhow::Result<pathfinder_executor::Transaction> {
     let from_address =
-        Felt::from_be_slice(input.message.from_address.0.as_bytes()).expect("This cannot overflow");
+        Felt::from_be_slice(message.from_address.0.as_bytes()).expect("This cannot overflow");
     let calldata = std::iter::once(CallParam(from_address))
-        .chain(input.message.payload)
+        .chain(message.payload)
         .collect();
     let transaction = pathfinder_common::transaction::L1HandlerTransaction {
-        contract_address: input.message.to_address,
-        entry_point_selector: input.message.entry_point_selector,
+        contract_address: message.to_address,
+        entry_point_selector: message.entry_point_selector,
         nonce: TransactionNonce::ZERO,
         calldata,
     };
@@ -163,7 +167,7 @@ fn create_executor_transaction(
                 .expect("A ContractAddress should be the right size"),
         ),
         entry_point_selector: starknet_api::core::EntryPointSelector(
-            input.message.entry_point_selector.0.into_starkfelt(),
+            transaction.entry_point_selector.0.into_starkfelt(),
         ),
         calldata: Calldata(Arc::new(
             transaction
@@ -178,7 +182,7 @@ fn create_executor_transaction(
         starknet_api::transaction::Transaction::L1Handler(tx),
         starknet_api::transaction::TransactionHash(transaction_hash.0.into_starkfelt()),
         None,
-        Some(Fee(1)),
+        Some(paid_fee_on_l1),
         None,
         pathfinder_executor::AccountTransactionExecutionFlags::default(),
     )?;
@@ -203,6 +207,7 @@ pub enum EstimateMessageFeeError {
         revert_error: String,
         revert_error_stack: pathfinder_executor::ErrorStack,
     },
+    ExecutionRefused(pathfinder_executor::ExecutionRefused),
     Custom(anyhow::Error),
 }
 
@@ -222,6 +227,7 @@ impl From<pathfinder_executor::TransactionExecutionError> for EstimateMessageFee
                 revert_error: format!("Execution error: {}", error),
                 revert_error_stack: error_stack,
             },
+            ExecutionRefused(refused) => Self::ExecutionRefused(refused),
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -250,6 +256,9 @@ impl From<EstimateMessageFeeError> for ApplicationError {
                 revert_error: Some(revert_error),
                 revert_error_stack,
             },
+            EstimateMessageFeeError::ExecutionRefused(refused) => {
+                ApplicationError::ExecutionRefused(refused)
+            }
             EstimateMessageFeeError::Internal(e) => ApplicationError::Internal(e),
             EstimateMessageFeeError::Custom(e) => ApplicationError::Custom(e),
         }
@@ -29,7 +29,7 @@ pub async fn get_block_transaction_count(
     input: Input,
 ) -> Result<Output, Error> {
     let span = tracing::Span::current();
-    util::task::spawn_blocking(move |_| {
+    util::task::spawn_blocking_storage(move |_| {
         let _g = span.enter();
         let mut db = context
             .storage
@@ -37,7 +37,7 @@ crate::error::generate_rpc_error_subset!(Error: BlockNotFound);
 
 pub async fn get_block_with_receipts(context: RpcContext, input: Input) -> Result<Output, Error> {
     let span = tracing::Span::current();
-    util::task::spawn_blocking(move |_| {
+    util::task::spawn_blocking_storage(move |_| {
         let _g = span.enter();
         let mut db = context
             .storage
@@ -62,6 +62,7 @@ pub async fn get_block_with_receipts(context: RpcContext, input: Input) -> Resul
             .block_header(block_id)
             .context("Fetching block header")?
             .ok_or(Error::BlockNotFound)?;
+        db.ensure_events_retained(header.number)?;
 
         let body = db
             .transaction_data_for_block(block_id)
@@ -122,6 +123,7 @@ impl crate::dto::SerializeForVersion for Output {
                 )?;
             }
             Output::Pending(block) => {
+                let finality = crate::pending::finality_status(block);
                 serializer.flatten(block.as_ref())?;
                 serializer.serialize_iter(
                     "transactions",
@@ -134,7 +136,7 @@ impl crate::dto::SerializeForVersion for Output {
                             transaction,
                             receipt,
                             events,
-                            finality: crate::dto::TxnFinalityStatus::AcceptedOnL2,
+                            finality,
                         }),
                 )?;
             }
@@ -182,6 +184,7 @@ impl crate::dto::SerializeForVersion for TransactionWithReceipt<'_> {
 
 #[cfg(test)]
 mod tests {
+    use pathfinder_common::BlockNumber;
     use pretty_assertions_sorted::assert_eq;
 
     use super::*;
@@ -340,6 +343,39 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[tokio::test]
+    async fn events_pruned() {
+        let context = RpcContext::for_tests();
+        let mut db = context.storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+        let config = pathfinder_storage::EventRetentionConfig {
+            blocks_kept: 1,
+            allowlist: Default::default(),
+        };
+        tx.prune_events(&config, pathfinder_storage::EVENT_PRUNING_BATCH_SIZE)
+            .unwrap();
+        tx.commit().unwrap();
+
+        let input = Input {
+            block_id: BlockId::Number(BlockNumber::GENESIS),
+        };
+        // The output is not Debug, so `unwrap_err` cannot be used.
+        let error = get_block_with_receipts(context.clone(), input)
+            .await
+            .err()
+            .unwrap();
+        assert_matches::assert_matches!(
+            crate::error::ApplicationError::from(error),
+            crate::error::ApplicationError::EventsPruned { available_from }
+                if available_from == BlockNumber::new_or_panic(2)
+        );
+
+        let input = Input {
+            block_id: BlockId::Latest,
+        };
+        get_block_with_receipts(context, input).await.unwrap();
+    }
+
     #[tokio::test]
     async fn latest() {
         let context = RpcContext::for_tests_with_pending().await;
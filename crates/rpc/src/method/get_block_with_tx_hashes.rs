@@ -37,7 +37,7 @@ pub enum Output {
 /// Get block information with transaction hashes given the block id
 pub async fn get_block_with_tx_hashes(context: RpcContext, input: Input) -> Result<Output, Error> {
     let span = tracing::Span::current();
-    util::task::spawn_blocking(move |_| {
+    util::task::spawn_blocking_storage(move |_| {
         let _g = span.enter();
         let mut connection = context
             .storage
@@ -43,7 +43,7 @@ impl From<ContractClass> for Output {
 /// Get a contract class.
 pub async fn get_class(context: RpcContext, input: Input) -> Result<Output, Error> {
     let span = tracing::Span::current();
-    let jh = util::task::spawn_blocking(move |_| -> Result<Output, Error> {
+    let jh = util::task::spawn_blocking_storage(move |_| -> Result<Output, Error> {
         let _g = span.enter();
         let mut db = context
             .storage
@@ -193,6 +193,34 @@ mod tests {
         assert_matches!(error, Error::ClassHashNotFound);
     }
 
+    #[tokio::test]
+    async fn declared_in_pending_block() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let class_hash = class_hash_bytes!(b"pending class 0 hash");
+
+        super::get_class(
+            context.clone(),
+            Input {
+                block_id: BlockId::Pending,
+                class_hash,
+            },
+        )
+        .await
+        .unwrap();
+
+        // The class is not declared in a canonical block yet.
+        let error = super::get_class(
+            context,
+            Input {
+                block_id: BlockId::Latest,
+                class_hash,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_matches!(error, Error::ClassHashNotFound);
+    }
+
     #[tokio::test]
     async fn latest() {
         let context = RpcContext::for_tests();
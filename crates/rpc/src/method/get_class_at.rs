@@ -52,7 +52,7 @@ impl SerializeForVersion for Output {
 /// Get a contract class.
 pub async fn get_class_at(context: RpcContext, input: Input) -> Result<Output, Error> {
     let span = tracing::Span::current();
-    let jh = util::task::spawn_blocking(move |_| {
+    let jh = util::task::spawn_blocking_storage(move |_| {
         let _g = span.enter();
         let mut db = context
             .storage
@@ -192,6 +192,33 @@ mod tests {
         assert_matches!(error, Error::ContractNotFound);
     }
 
+    #[tokio::test]
+    async fn class_declared_in_pending_block() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let contract_address = contract_address_bytes!(b"pending contract 0 address");
+
+        super::get_class_at(
+            context.clone(),
+            Input {
+                block_id: BlockId::Pending,
+                contract_address,
+            },
+        )
+        .await
+        .unwrap();
+
+        let error = super::get_class_at(
+            context,
+            Input {
+                block_id: BlockId::Latest,
+                contract_address,
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_matches!(error, Error::ContractNotFound);
+    }
+
     #[tokio::test]
     async fn latest() {
         let context = RpcContext::for_tests();
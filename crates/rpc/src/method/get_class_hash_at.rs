@@ -27,7 +27,7 @@ pub struct Output(ClassHash);
 
 pub async fn get_class_hash_at(context: RpcContext, input: Input) -> Result<Output, Error> {
     let span = tracing::Span::current();
-    util::task::spawn_blocking(move |_| {
+    util::task::spawn_blocking_storage(move |_| {
         let _g = span.enter();
         let mut db = context
             .storage
@@ -1,6 +1,7 @@
 use std::str::FromStr;
 
 use anyhow::Context;
+use pathfinder_common::event::Event;
 use pathfinder_common::{
     BlockHash,
     BlockId,
@@ -10,7 +11,7 @@ use pathfinder_common::{
     EventKey,
     TransactionHash,
 };
-use pathfinder_storage::{EventFilterError, EVENT_KEY_FILTER_LIMIT};
+use pathfinder_storage::{EventFilterError, EventOrder};
 use starknet_gateway_types::reply::PendingBlock;
 use tokio::task::JoinHandle;
 
@@ -18,8 +19,6 @@ use crate::context::RpcContext;
 use crate::dto::{self, SerializeForVersion, Serializer};
 use crate::pending::PendingData;
 
-pub const EVENT_PAGE_SIZE_LIMIT: usize = 1024;
-
 #[derive(Debug)]
 pub enum GetEventsError {
     Internal(anyhow::Error),
@@ -28,6 +27,7 @@ pub enum GetEventsError {
     PageSizeTooBig,
     InvalidContinuationToken,
     TooManyKeysInFilter { limit: usize, requested: usize },
+    EventsPruned { available_from: BlockNumber },
 }
 
 impl From<anyhow::Error> for GetEventsError {
@@ -47,6 +47,9 @@ impl From<GetEventsError> for crate::error::ApplicationError {
             GetEventsError::TooManyKeysInFilter { limit, requested } => {
                 Self::TooManyKeysInFilter { limit, requested }
             }
+            GetEventsError::EventsPruned { available_from } => {
+                Self::EventsPruned { available_from }
+            }
         }
     }
 }
@@ -76,6 +79,9 @@ pub struct EventFilter {
     pub chunk_size: usize,
     /// Offset, measured in events, which points to the requested chunk
     pub continuation_token: Option<String>,
+    /// Pathfinder extension, events are returned in ascending order unless
+    /// `"DESC"` is requested.
+    pub order: EventOrder,
 }
 
 impl crate::dto::DeserializeForVersion for EventFilter {
@@ -92,12 +98,30 @@ impl crate::dto::DeserializeForVersion for EventFilter {
                     .unwrap_or_default(),
                 chunk_size: value.deserialize("chunk_size")?,
                 continuation_token: value.deserialize_optional_serde("continuation_token")?,
+                order: value.deserialize_optional("order")?.unwrap_or_default(),
             })
         })
     }
 }
 
-/// Returns events matching the specified filter
+impl crate::dto::DeserializeForVersion for EventOrder {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        let s: String = value.deserialize()?;
+        match s.as_str() {
+            "ASC" => Ok(Self::Ascending),
+            "DESC" => Ok(Self::Descending),
+            _ => Err(serde::de::Error::unknown_variant(&s, &["ASC", "DESC"])),
+        }
+    }
+}
+
+/// Returns events matching the specified filter.
+///
+/// Events are ordered by block number, then by the index of their transaction
+/// within the block, then by their index within the transaction, which is the
+/// order they were emitted in. The filter's `order` reverses this, returning
+/// the most recent events first. Events of the pending block are only
+/// available in ascending order.
 pub async fn get_events(
     context: RpcContext,
     input: GetEventsInput,
@@ -121,7 +145,13 @@ pub async fn get_events(
     //      and return a continuation token for the pending block
     //  d) else if empty / partially full -> append events from start of pending
     //      if there are more pending events return a continuation token
-    //      with the appropriate offset within the pending block
+    //      with the position of the next event within the pending block
+    //
+    // Continuation tokens pointing into the pending block hold the position of
+    // the next event, i.e. the index of its transaction and its index within the
+    // transaction, rather than an offset among the matching events. The position
+    // of an event does not change when it becomes part of a canonical block, in
+    // which case the token is converted to an offset within that block.
 
     use BlockId::*;
 
@@ -129,22 +159,36 @@ pub async fn get_events(
 
     let continuation_token = match &request.continuation_token {
         Some(s) => Some(
-            s.parse::<ContinuationToken>()
+            s.parse::<Continuation>()
                 .map_err(|_| GetEventsError::InvalidContinuationToken)?,
         ),
         None => None,
     };
 
-    if request.keys.len() > EVENT_KEY_FILTER_LIMIT {
+    let max_keys = context.config.event_filter_max_keys.get();
+    if request.keys.len() > max_keys {
         return Err(GetEventsError::TooManyKeysInFilter {
-            limit: EVENT_KEY_FILTER_LIMIT,
+            limit: max_keys,
             requested: request.keys.len(),
         });
     }
-    if request.chunk_size > EVENT_PAGE_SIZE_LIMIT {
+    if request.chunk_size > context.config.get_events_max_chunk_size.get() {
         return Err(GetEventsError::PageSizeTooBig);
     }
 
+    let descending = request.order == EventOrder::Descending;
+    if descending {
+        if matches!(request.from_block, Some(Pending)) || matches!(request.to_block, Some(Pending))
+        {
+            return Err(GetEventsError::Custom(anyhow::anyhow!(
+                "The pending block is not supported in descending order"
+            )));
+        }
+        if matches!(continuation_token, Some(Continuation::Pending(_))) {
+            return Err(GetEventsError::InvalidContinuationToken);
+        }
+    }
+
     let storage = context.storage.clone();
 
     // truncate empty key lists from the end of the key filter
@@ -152,148 +196,193 @@ pub async fn get_events(
     if let Some(last_non_empty) = keys.iter().rposition(|keys| !keys.is_empty()) {
         keys.truncate(last_non_empty + 1);
     }
+    let matcher = EventMatcher::new(request.address, &keys);
 
     // blocking task to perform database event query
     let span = tracing::Span::current();
-    let db_events: JoinHandle<Result<_, GetEventsError>> = util::task::spawn_blocking(move |_| {
-        let _g = span.enter();
-        let mut connection = storage
-            .connection()
-            .context("Opening database connection")?;
-
-        let transaction = connection
-            .transaction()
-            .context("Creating database transaction")?;
-
-        // Handle the trivial (1), (2) and (4a) cases.
-        match (&request.from_block, &request.to_block) {
-            (Some(Pending), id) if !matches!(id, Some(Pending) | None) => {
-                return Ok(GetEventsResult {
-                    events: Vec::new(),
-                    continuation_token: None,
-                });
-            }
-            (Some(Pending), Some(Pending) | None) => {
-                let pending = context
-                    .pending_data
-                    .get(&transaction)
-                    .context("Querying pending data")?;
-                return get_pending_events(&request, &pending, continuation_token);
-            }
-            (Some(BlockId::Number(from_block)), Some(BlockId::Pending)) => {
-                let pending = context
-                    .pending_data
-                    .get(&transaction)
-                    .context("Querying pending data")?;
-
-                // `from_block` is larger than or equal to pending block's number
-                if from_block >= &pending.number {
+    let db_events: JoinHandle<Result<_, GetEventsError>> =
+        util::task::spawn_blocking_storage(move |_| {
+            let _g = span.enter();
+            let mut connection = storage
+                .connection()
+                .context("Opening database connection")?;
+
+            let transaction = connection
+                .transaction()
+                .context("Creating database transaction")?;
+
+            // Handle the trivial (1), (2) and (4a) cases.
+            match (&request.from_block, &request.to_block) {
+                (Some(Pending), id) if !matches!(id, Some(Pending) | None) => {
                     return Ok(GetEventsResult {
                         events: Vec::new(),
                         continuation_token: None,
                     });
                 }
+                (Some(Pending), Some(Pending) | None) => {
+                    let pending = context
+                        .pending_data
+                        .get(&transaction)
+                        .context("Querying pending data")?;
+                    let start = pending_start(continuation_token, pending.number)?;
+                    return Ok(get_pending_events(
+                        &pending,
+                        start,
+                        request.chunk_size,
+                        &matcher,
+                    ));
+                }
+                (Some(BlockId::Number(from_block)), Some(BlockId::Pending)) => {
+                    let pending = context
+                        .pending_data
+                        .get(&transaction)
+                        .context("Querying pending data")?;
+
+                    // `from_block` is larger than or equal to pending block's number
+                    if from_block >= &pending.number {
+                        return Ok(GetEventsResult {
+                            events: Vec::new(),
+                            continuation_token: None,
+                        });
+                    }
+                }
+                _ => {}
             }
-            _ => {}
-        }
 
-        let from_block = map_from_block_to_number(&transaction, request.from_block)?;
-        let to_block = map_to_block_to_number(&transaction, request.to_block)?;
+            let from_block = map_from_block_to_number(&transaction, request.from_block)?;
+            let to_block = map_to_block_to_number(&transaction, request.to_block)?;
 
-        // Handle cases (3) and (4) where `from_block` is non-pending.
+            // A token pointing into a block which was pending when the token was issued.
+            // Either the block is still pending, or it has become canonical since.
+            if let Some(Continuation::Pending(token)) = continuation_token {
+                let pending = context
+                    .pending_data
+                    .get(&transaction)
+                    .context("Querying pending data")?;
 
-        let (from_block, requested_offset) = match continuation_token {
-            Some(token) => token.start_block_and_offset(from_block)?,
-            None => (from_block, 0),
-        };
+                if token.block_number == pending.number {
+                    if !matches!(request.to_block, Some(Pending)) {
+                        return Ok(GetEventsResult {
+                            events: Vec::new(),
+                            continuation_token: None,
+                        });
+                    }
+                    return Ok(get_pending_events(
+                        &pending,
+                        token.position,
+                        request.chunk_size,
+                        &matcher,
+                    ));
+                }
+            }
 
-        let constraints = pathfinder_storage::EventConstraints {
-            from_block,
-            to_block,
-            contract_address: request.address,
-            keys: keys.clone(),
-            page_size: request.chunk_size,
-            offset: requested_offset,
-        };
+            // Handle cases (3) and (4) where `from_block` is non-pending.
 
-        let page = transaction
-            .events(
-                &constraints,
-                context.config.get_events_max_blocks_to_scan,
-                context.config.get_events_max_uncached_event_filters_to_load,
-            )
-            .map_err(|e| match e {
-                EventFilterError::Internal(e) => GetEventsError::Internal(e),
-                EventFilterError::PageSizeTooSmall => GetEventsError::Custom(e.into()),
-            })?;
-
-        let mut events = GetEventsResult {
-            events: page.events.into_iter().map(|e| e.into()).collect(),
-            continuation_token: page.continuation_token.map(|token| {
-                ContinuationToken {
-                    block_number: token.block_number,
-                    offset: token.offset,
+            let (from_block, to_block, requested_offset) = match continuation_token {
+                // In descending order the token points to the upper end of the
+                // remaining range.
+                Some(Continuation::Canonical(token)) if descending => {
+                    let (to_block, offset) = token.end_block_and_offset(from_block, to_block)?;
+                    (from_block, to_block, offset)
                 }
-                .to_string()
-            }),
-        };
-
-        // Append pending data if required.
-        if events.continuation_token.is_none() && matches!(request.to_block, Some(Pending)) {
-            let pending = context
-                .pending_data
-                .get(&transaction)
-                .context("Querying pending data")?;
+                Some(Continuation::Canonical(token)) => {
+                    let (from_block, offset) = token.start_block_and_offset(from_block)?;
+                    (from_block, to_block, offset)
+                }
+                Some(Continuation::Pending(token)) => {
+                    let offset = offset_in_canonical_block(
+                        &transaction,
+                        token.block_number,
+                        token.position,
+                        &matcher,
+                    )?;
+                    let token = ContinuationToken {
+                        block_number: token.block_number,
+                        offset,
+                    };
+                    let (from_block, offset) = token.start_block_and_offset(from_block)?;
+                    (from_block, to_block, offset)
+                }
+                None => (from_block, to_block, 0),
+            };
 
-            if events.events.len() < request.chunk_size {
-                let amount = request.chunk_size - events.events.len();
+            let constraints = pathfinder_storage::EventConstraints {
+                from_block,
+                to_block,
+                contract_address: request.address,
+                keys,
+                page_size: request.chunk_size,
+                offset: requested_offset,
+                order: request.order,
+            };
 
-                let current_offset = match continuation_token {
-                    Some(continuation_token) => {
-                        continuation_token.offset_in_block(pending.number)?
+            let page = transaction
+                .events(
+                    &constraints,
+                    context.config.get_events_max_blocks_to_scan,
+                    context.config.get_events_max_uncached_event_filters_to_load,
+                )
+                .map_err(|e| match e {
+                    EventFilterError::Internal(e) => GetEventsError::Internal(e),
+                    EventFilterError::PageSizeTooSmall
+                    | EventFilterError::FiltersDeferred { .. } => GetEventsError::Custom(e.into()),
+                    EventFilterError::Pruned { available_from } => {
+                        GetEventsError::EventsPruned { available_from }
                     }
-                    None => 0,
-                };
+                })?;
 
-                let keys: Vec<std::collections::HashSet<_>> = request
-                    .keys
-                    .into_iter()
-                    .map(|keys| keys.into_iter().collect())
-                    .collect();
-
-                let is_last_page = append_pending_events(
-                    &pending.block,
-                    &mut events.events,
-                    current_offset,
-                    amount,
-                    request.address,
-                    keys,
-                );
-
-                events.continuation_token = if is_last_page {
-                    None
-                } else {
-                    let continuation_token = ContinuationToken {
-                        block_number: pending.number,
-                        offset: current_offset + amount,
-                    };
-                    Some(continuation_token.to_string())
-                };
-            } else {
-                // We have a full page from the database, but there might be more pending
-                // events. Return a continuation token for the pending block.
-                events.continuation_token = Some(
+            let mut events = GetEventsResult {
+                events: page.events.into_iter().map(|e| e.into()).collect(),
+                continuation_token: page.continuation_token.map(|token| {
                     ContinuationToken {
-                        block_number: pending.number,
-                        offset: 0,
+                        block_number: token.block_number,
+                        offset: token.offset,
                     }
-                    .to_string(),
-                );
+                    .to_string()
+                }),
+            };
+
+            // Append pending data if required.
+            if events.continuation_token.is_none() && matches!(request.to_block, Some(Pending)) {
+                let pending = context
+                    .pending_data
+                    .get(&transaction)
+                    .context("Querying pending data")?;
+
+                if events.events.len() < request.chunk_size {
+                    let amount = request.chunk_size - events.events.len();
+                    let start = pending_start(continuation_token, pending.number)?;
+
+                    let next = append_pending_events(
+                        &pending.block,
+                        &mut events.events,
+                        start,
+                        amount,
+                        &matcher,
+                    );
+
+                    events.continuation_token = next.map(|position| {
+                        PendingContinuationToken {
+                            block_number: pending.number,
+                            position,
+                        }
+                        .to_string()
+                    });
+                } else {
+                    // We have a full page from the database, but there might be more pending
+                    // events. Return a continuation token for the pending block.
+                    events.continuation_token = Some(
+                        PendingContinuationToken {
+                            block_number: pending.number,
+                            position: EventPosition::default(),
+                        }
+                        .to_string(),
+                    );
+                }
             }
-        }
 
-        Ok(events)
-    });
+            Ok(events)
+        });
 
     db_events
         .await
@@ -303,48 +392,80 @@ pub async fn get_events(
 // Handle the case when we're querying events exclusively from the pending
 // block.
 fn get_pending_events(
-    request: &EventFilter,
     pending: &PendingData,
-    continuation_token: Option<ContinuationToken>,
-) -> Result<GetEventsResult, GetEventsError> {
-    let current_offset = match continuation_token {
-        Some(continuation_token) => continuation_token.offset_in_block(pending.number)?,
-        None => 0,
-    };
-
-    let keys: Vec<std::collections::HashSet<_>> = request
-        .keys
-        .iter()
-        .map(|keys| keys.iter().copied().collect())
-        .collect();
-
+    start: EventPosition,
+    chunk_size: usize,
+    matcher: &EventMatcher,
+) -> GetEventsResult {
     let mut events = Vec::new();
 
-    let is_last_page = append_pending_events(
-        &pending.block,
-        &mut events,
-        current_offset,
-        request.chunk_size,
-        request.address,
-        keys,
-    );
+    let next = append_pending_events(&pending.block, &mut events, start, chunk_size, matcher);
 
-    let continuation_token = if is_last_page {
-        None
-    } else {
-        Some(
-            ContinuationToken {
-                block_number: pending.number,
-                offset: current_offset + request.chunk_size,
-            }
-            .to_string(),
-        )
-    };
+    let continuation_token = next.map(|position| {
+        PendingContinuationToken {
+            block_number: pending.number,
+            position,
+        }
+        .to_string()
+    });
 
-    Ok(GetEventsResult {
+    GetEventsResult {
         events,
         continuation_token,
-    })
+    }
+}
+
+/// Returns the position within the pending block to continue from.
+fn pending_start(
+    continuation_token: Option<Continuation>,
+    pending_block_number: BlockNumber,
+) -> Result<EventPosition, GetEventsError> {
+    use std::cmp::Ordering;
+
+    let (block_number, position) = match continuation_token {
+        Some(Continuation::Canonical(token)) => (token.block_number, None),
+        Some(Continuation::Pending(token)) => (token.block_number, Some(token.position)),
+        None => return Ok(EventPosition::default()),
+    };
+
+    match (Ord::cmp(&block_number, &pending_block_number), position) {
+        (Ordering::Less, _) => Ok(EventPosition::default()),
+        (Ordering::Equal, Some(position)) => Ok(position),
+        _ => Err(GetEventsError::InvalidContinuationToken),
+    }
+}
+
+/// Converts the position of an event in a block which has become canonical
+/// since the continuation token was issued to the number of matching events
+/// preceding it in the block.
+fn offset_in_canonical_block(
+    tx: &pathfinder_storage::Transaction<'_>,
+    block_number: BlockNumber,
+    position: EventPosition,
+    matcher: &EventMatcher,
+) -> Result<usize, GetEventsError> {
+    let events = tx
+        .events_for_block(block_number.into())
+        .context("Querying events for block")?
+        .ok_or(GetEventsError::InvalidContinuationToken)?;
+
+    let offset = events
+        .iter()
+        .enumerate()
+        .flat_map(|(transaction_index, (_, events))| {
+            events.iter().enumerate().map(move |(event_index, event)| {
+                let position = EventPosition {
+                    transaction_index,
+                    event_index,
+                };
+                (position, event)
+            })
+        })
+        .take_while(|(event_position, _)| *event_position < position)
+        .filter(|(_, event)| matcher.matches(event))
+        .count();
+
+    Ok(offset)
 }
 
 // Maps `to_block` BlockId to a block number which can be used by the events
@@ -407,66 +528,106 @@ fn map_from_block_to_number(
     }
 }
 
-/// Append's pending events to `dst` based on the filter requirements and
-/// returns true if this was the last pending data i.e. `is_last_page`.
-fn append_pending_events(
-    pending_block: &PendingBlock,
-    dst: &mut Vec<EmittedEvent>,
-    skip: usize,
-    amount: usize,
+/// The address and key constraints of an event filter.
+struct EventMatcher {
     address: Option<ContractAddress>,
     keys: Vec<std::collections::HashSet<EventKey>>,
-) -> bool {
-    let original_len = dst.len();
+}
+
+impl EventMatcher {
+    fn new(address: Option<ContractAddress>, keys: &[Vec<EventKey>]) -> Self {
+        Self {
+            address,
+            keys: keys
+                .iter()
+                .map(|keys| keys.iter().copied().collect())
+                .collect(),
+        }
+    }
 
-    let key_filter_is_empty = keys.iter().flatten().count() == 0;
+    fn matches(&self, event: &Event) -> bool {
+        if self
+            .address
+            .is_some_and(|address| event.from_address != address)
+        {
+            return false;
+        }
+
+        if self.keys.iter().all(|keys| keys.is_empty()) {
+            return true;
+        }
+
+        if event.keys.len() < self.keys.len() {
+            return false;
+        }
+
+        event
+            .keys
+            .iter()
+            .zip(self.keys.iter())
+            .all(|(key, filter)| filter.is_empty() || filter.contains(key))
+    }
+}
 
-    let pending_events = pending_block
+/// Append's up to `amount` matching pending events, starting at `start`, to
+/// `dst` and returns the position of the next matching event, if any.
+fn append_pending_events(
+    pending_block: &PendingBlock,
+    dst: &mut Vec<EmittedEvent>,
+    start: EventPosition,
+    amount: usize,
+    matcher: &EventMatcher,
+) -> Option<EventPosition> {
+    let mut pending_events = pending_block
         .transaction_receipts
         .iter()
-        .flat_map(|(receipt, events)| {
-            events
-                .iter()
-                .zip(std::iter::repeat(receipt.transaction_hash))
-        })
-        .filter(|(event, _)| match address {
-            Some(address) => event.from_address == address,
-            None => true,
+        .enumerate()
+        .flat_map(|(transaction_index, (receipt, events))| {
+            events.iter().enumerate().map(move |(event_index, event)| {
+                let position = EventPosition {
+                    transaction_index,
+                    event_index,
+                };
+                (position, event, receipt.transaction_hash)
+            })
         })
-        .filter(|(event, _)| {
-            if key_filter_is_empty {
-                return true;
-            }
+        .filter(|(position, ..)| *position >= start)
+        .filter(|(_, event, _)| matcher.matches(event));
+
+    dst.extend(
+        pending_events
+            .by_ref()
+            .take(amount)
+            .map(|(_, event, tx_hash)| EmittedEvent {
+                data: event.data.clone(),
+                keys: event.keys.clone(),
+                from_address: event.from_address,
+                block_hash: None,
+                block_number: None,
+                transaction_hash: tx_hash,
+            }),
+    );
 
-            if event.keys.len() < keys.len() {
-                return false;
-            }
+    pending_events.next().map(|(position, ..)| position)
+}
 
-            event
-                .keys
-                .iter()
-                .zip(keys.iter())
-                .all(|(key, filter)| filter.is_empty() || filter.contains(key))
-        })
-        .skip(skip)
-        // We need to take an extra event to determine is_last_page.
-        .take(amount + 1)
-        .map(|(event, tx_hash)| EmittedEvent {
-            data: event.data.clone(),
-            keys: event.keys.clone(),
-            from_address: event.from_address,
-            block_hash: None,
-            block_number: None,
-            transaction_hash: tx_hash,
-        });
+/// A continuation token as given by the client.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Continuation {
+    Canonical(ContinuationToken),
+    Pending(PendingContinuationToken),
+}
 
-    dst.extend(pending_events);
-    let is_last_page = dst.len() <= (original_len + amount);
-    if !is_last_page {
-        dst.pop();
-    }
+impl FromStr for Continuation {
+    type Err = ParseContinuationTokenError;
 
-    is_last_page
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with('p') {
+            s.parse().map(Continuation::Pending)
+        } else {
+            s.parse().map(Continuation::Canonical)
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -504,15 +665,6 @@ impl std::fmt::Display for ContinuationToken {
 }
 
 impl ContinuationToken {
-    fn offset_in_block(&self, block_number: BlockNumber) -> Result<usize, GetEventsError> {
-        use std::cmp::Ordering;
-        match Ord::cmp(&self.block_number, &block_number) {
-            Ordering::Equal => Ok(self.offset),
-            Ordering::Less => Ok(0),
-            Ordering::Greater => Err(GetEventsError::InvalidContinuationToken),
-        }
-    }
-
     fn start_block_and_offset(
         &self,
         from_block: Option<BlockNumber>,
@@ -531,6 +683,82 @@ impl ContinuationToken {
             }
         }
     }
+
+    /// The block to continue from and the offset within it in descending
+    /// order, where the block is the upper end of the remaining range.
+    fn end_block_and_offset(
+        &self,
+        from_block: Option<BlockNumber>,
+        to_block: Option<BlockNumber>,
+    ) -> Result<(Option<BlockNumber>, usize), GetEventsError> {
+        let below_range = from_block.is_some_and(|from_block| self.block_number < from_block);
+        let above_range = to_block.is_some_and(|to_block| self.block_number > to_block);
+        if below_range || above_range {
+            Err(GetEventsError::InvalidContinuationToken)
+        } else {
+            Ok((Some(self.block_number), self.offset))
+        }
+    }
+}
+
+/// The position of an event within a block.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct EventPosition {
+    transaction_index: usize,
+    event_index: usize,
+}
+
+/// Points to an event of the pending block, formatted as
+/// `p<block number>-<transaction index>-<event index>`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct PendingContinuationToken {
+    block_number: BlockNumber,
+    position: EventPosition,
+}
+
+impl FromStr for PendingContinuationToken {
+    type Err = ParseContinuationTokenError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s
+            .strip_prefix('p')
+            .ok_or(ParseContinuationTokenError)?
+            .split('-')
+            .map(|part| part.parse::<u64>().map_err(|_| ParseContinuationTokenError));
+
+        let (Some(block_number), Some(transaction_index), Some(event_index), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(ParseContinuationTokenError);
+        };
+
+        let block_number = BlockNumber::new(block_number?).ok_or(ParseContinuationTokenError)?;
+        let position = EventPosition {
+            transaction_index: transaction_index?
+                .try_into()
+                .map_err(|_| ParseContinuationTokenError)?,
+            event_index: event_index?
+                .try_into()
+                .map_err(|_| ParseContinuationTokenError)?,
+        };
+
+        Ok(PendingContinuationToken {
+            block_number,
+            position,
+        })
+    }
+}
+
+impl std::fmt::Display for PendingContinuationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "p{}-{}-{}",
+            self.block_number.get(),
+            self.position.transaction_index,
+            self.position.event_index
+        )
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -641,6 +869,7 @@ mod tests {
                 keys: vec![vec![event_key!("0x2")], vec![]],
                 chunk_size: 3,
                 continuation_token: Some("4".to_string()),
+                order: EventOrder::Ascending,
             }
         } else {
             EventFilter {
@@ -683,6 +912,32 @@ mod tests {
                 offset: 4567
             }
         );
+
+        assert_matches!(
+            "p1234-5".parse::<Continuation>(),
+            Err(ParseContinuationTokenError)
+        );
+        assert_matches!(
+            "p1234-5-6-7".parse::<Continuation>(),
+            Err(ParseContinuationTokenError)
+        );
+        assert_matches!(
+            "1234-5-6".parse::<Continuation>(),
+            Err(ParseContinuationTokenError)
+        );
+
+        let pending = PendingContinuationToken {
+            block_number: BlockNumber::new_or_panic(1234),
+            position: EventPosition {
+                transaction_index: 5,
+                event_index: 6,
+            },
+        };
+        assert_eq!(pending.to_string(), "p1234-5-6");
+        assert_eq!(
+            "p1234-5-6".parse::<Continuation>().unwrap(),
+            Continuation::Pending(pending)
+        );
     }
 
     fn setup() -> (RpcContext, Vec<EmittedEvent>) {
@@ -745,6 +1000,7 @@ mod tests {
                 keys: vec![vec![], vec![event_key!("0xdeadbeef")]],
                 chunk_size: test_utils::NUM_EVENTS,
                 continuation_token: None,
+                order: EventOrder::Ascending,
             },
         };
         let result = get_events(context.clone(), input.clone()).await.unwrap();
@@ -820,11 +1076,43 @@ mod tests {
         assert_eq!(GetEventsError::PageSizeTooBig, error);
     }
 
+    #[tokio::test]
+    async fn get_events_with_configured_limits() {
+        let (mut context, _) = setup();
+        context.config.event_filter_max_keys = 2.try_into().unwrap();
+        context.config.get_events_max_chunk_size = 5.try_into().unwrap();
+
+        let input = GetEventsInput {
+            filter: EventFilter {
+                chunk_size: 6,
+                ..Default::default()
+            },
+        };
+        let error = get_events(context.clone(), input).await.unwrap_err();
+        assert_eq!(GetEventsError::PageSizeTooBig, error);
+
+        let input = GetEventsInput {
+            filter: EventFilter {
+                keys: vec![vec![event_key!("01")]; 3],
+                chunk_size: 5,
+                ..Default::default()
+            },
+        };
+        let error = get_events(context, input).await.unwrap_err();
+        assert_eq!(
+            GetEventsError::TooManyKeysInFilter {
+                limit: 2,
+                requested: 3
+            },
+            error
+        );
+    }
+
     #[tokio::test]
     async fn get_events_with_too_many_keys_in_filter() {
         let (context, _) = setup();
 
-        let limit = EVENT_KEY_FILTER_LIMIT;
+        let limit = context.config.event_filter_max_keys.get();
 
         let keys = [vec![event_key!("01")]]
             .iter()
@@ -924,7 +1212,47 @@ mod tests {
         assert_eq!(result.continuation_token, None);
     }
 
+    #[tokio::test]
+    async fn get_events_in_descending_order_with_paging() {
+        let (context, mut events) = setup();
+        events.reverse();
+
+        let input = json!({"filter": {"chunk_size": 4, "order": "DESC"}});
+        let mut input =
+            GetEventsInput::deserialize(crate::dto::Value::new(input, RpcVersion::V07)).unwrap();
+
+        let mut result = vec![];
+        loop {
+            let page = get_events(context.clone(), input.clone()).await.unwrap();
+            result.extend(page.events);
+
+            match page.continuation_token {
+                Some(token) => input.filter.continuation_token = Some(token),
+                None => break,
+            }
+        }
+
+        assert_eq!(result, events);
+    }
+
+    #[tokio::test]
+    async fn descending_order_does_not_support_pending() {
+        let (context, _) = setup();
+
+        let input = GetEventsInput {
+            filter: EventFilter {
+                to_block: Some(BlockId::Pending),
+                chunk_size: 1,
+                order: EventOrder::Descending,
+                ..Default::default()
+            },
+        };
+        let error = get_events(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(error, GetEventsError::Custom(_));
+    }
+
     mod pending {
+        use pathfinder_common::BlockHeader;
         use pretty_assertions_sorted::assert_eq;
 
         use super::*;
@@ -1002,7 +1330,7 @@ mod tests {
             input.filter.continuation_token = None;
             let result = get_events(context.clone(), input.clone()).await.unwrap();
             assert_eq!(result.events, &all[0..1]);
-            assert_eq!(result.continuation_token, Some("3-0".to_string()));
+            assert_eq!(result.continuation_token, Some("p3-0-0".to_string()));
 
             // Page includes a DB event and an event from the pending block, but there are
             // more pending events for the next page
@@ -1010,13 +1338,13 @@ mod tests {
             input.filter.continuation_token = None;
             let result = get_events(context.clone(), input.clone()).await.unwrap();
             assert_eq!(result.events, &all[0..2]);
-            assert_eq!(result.continuation_token, Some("3-1".to_string()));
+            assert_eq!(result.continuation_token, Some("p3-0-1".to_string()));
 
             input.filter.chunk_size = 1;
             input.filter.continuation_token = result.continuation_token;
             let result = get_events(context.clone(), input.clone()).await.unwrap();
             assert_eq!(result.events, &all[2..3]);
-            assert_eq!(result.continuation_token, Some("3-2".to_string()));
+            assert_eq!(result.continuation_token, Some("p3-0-2".to_string()));
 
             input.filter.chunk_size = 100; // Only a single event remains though
             input.filter.continuation_token = result.continuation_token;
@@ -1034,7 +1362,7 @@ mod tests {
 
             // nonexistent page: offset too large
             input.filter.chunk_size = 123; // Does not matter
-            input.filter.continuation_token = Some("3-3".to_string()); // Points to after the last event
+            input.filter.continuation_token = Some("p3-1-0".to_string()); // Points to after the last event
             let result = get_events(context.clone(), input.clone()).await.unwrap();
             assert_eq!(result.events, &[]);
             assert_eq!(result.continuation_token, None);
@@ -1061,6 +1389,7 @@ mod tests {
                     ]],
                     chunk_size: 1024,
                     continuation_token: None,
+                    order: EventOrder::Ascending,
                 },
             };
 
@@ -1074,7 +1403,69 @@ mod tests {
             input.filter.chunk_size = 1;
             let result = get_events(context.clone(), input.clone()).await.unwrap();
             assert_eq!(result.events, &all[0..1]);
-            assert_eq!(result.continuation_token, Some("3-0".to_string()));
+            assert_eq!(result.continuation_token, Some("p3-0-0".to_string()));
+        }
+
+        #[tokio::test]
+        async fn continuation_token_survives_pending_block_becoming_canonical() {
+            let context = RpcContext::for_tests_with_pending().await;
+
+            let mut input = GetEventsInput {
+                filter: EventFilter {
+                    to_block: Some(BlockId::Pending),
+                    keys: vec![vec![event_key_bytes!(b"pending key")]],
+                    chunk_size: 1,
+                    ..Default::default()
+                },
+            };
+
+            let result = get_events(context.clone(), input.clone()).await.unwrap();
+            assert_eq!(result.events.len(), 1);
+            assert_eq!(result.continuation_token, Some("p3-0-1".to_string()));
+            let pending_event = &result.events[0];
+            assert_eq!(pending_event.block_number, None);
+
+            // Store the pending block as block 3, which leaves an empty pending block.
+            let pending = context.pending_data.get_unchecked();
+            let storage = context.storage.clone();
+            tokio::task::spawn_blocking(move || {
+                let mut db = storage.connection().unwrap();
+                let tx = db.transaction().unwrap();
+                let header = BlockHeader::builder()
+                    .number(pending.number)
+                    .parent_hash(pending.block.parent_hash)
+                    .finalize_with_hash(block_hash_bytes!(b"block 3 hash"));
+                tx.insert_block_header(&header).unwrap();
+                let (receipts, events): (Vec<_>, Vec<_>) =
+                    pending.block.transaction_receipts.iter().cloned().unzip();
+                let transaction_data = pending
+                    .block
+                    .transactions
+                    .iter()
+                    .cloned()
+                    .zip(receipts)
+                    .collect::<Vec<_>>();
+                tx.insert_transaction_data(header.number, &transaction_data, Some(&events))
+                    .unwrap();
+                tx.commit().unwrap();
+            })
+            .await
+            .unwrap();
+
+            input.filter.chunk_size = 10;
+            input.filter.continuation_token = result.continuation_token;
+            let result = get_events(context.clone(), input.clone()).await.unwrap();
+            assert_eq!(result.continuation_token, None);
+            assert_eq!(result.events.len(), 1);
+            let event = &result.events[0];
+            assert_eq!(event.block_number, Some(BlockNumber::new_or_panic(3)));
+            assert_eq!(
+                event.keys,
+                vec![
+                    event_key_bytes!(b"pending key"),
+                    event_key_bytes!(b"second pending key")
+                ]
+            );
         }
 
         #[tokio::test]
@@ -1089,6 +1480,7 @@ mod tests {
                     keys: vec![],
                     chunk_size: 1024,
                     continuation_token: None,
+                    order: EventOrder::Ascending,
                 },
             };
 
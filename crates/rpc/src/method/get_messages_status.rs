@@ -26,6 +26,7 @@ impl crate::dto::DeserializeForVersion for Input {
 enum FinalityStatus {
     Received,
     Rejected,
+    PreConfirmed,
     AcceptedOnL2,
     AcceptedOnL1,
 }
@@ -38,6 +39,10 @@ impl crate::dto::SerializeForVersion for FinalityStatus {
         let status_str = match self {
             FinalityStatus::Received => "RECEIVED",
             FinalityStatus::Rejected => "REJECTED",
+            FinalityStatus::PreConfirmed if serializer.version >= crate::RpcVersion::V09 => {
+                "PRE_CONFIRMED"
+            }
+            FinalityStatus::PreConfirmed => "ACCEPTED_ON_L2",
             FinalityStatus::AcceptedOnL2 => "ACCEPTED_ON_L2",
             FinalityStatus::AcceptedOnL1 => "ACCEPTED_ON_L1",
         };
@@ -51,6 +56,7 @@ impl crate::dto::DeserializeForVersion for FinalityStatus {
         match status_str.as_str() {
             "RECEIVED" => Ok(Self::Received),
             "REJECTED" => Ok(Self::Rejected),
+            "PRE_CONFIRMED" => Ok(Self::PreConfirmed),
             "ACCEPTED_ON_L2" => Ok(Self::AcceptedOnL2),
             "ACCEPTED_ON_L1" => Ok(Self::AcceptedOnL1),
             _ => Err(serde::de::Error::custom("Invalid finality status")),
@@ -100,6 +106,7 @@ pub async fn get_messages_status(context: RpcContext, input: Input) -> Result<Ou
         let finality_status = match status {
             TxStatus::Received => FinalityStatus::Received,
             TxStatus::Rejected { .. } => FinalityStatus::Rejected,
+            TxStatus::PreConfirmed(_) => FinalityStatus::PreConfirmed,
             TxStatus::AcceptedOnL1(_) => FinalityStatus::AcceptedOnL1,
             TxStatus::AcceptedOnL2(_) => FinalityStatus::AcceptedOnL2,
         };
@@ -27,7 +27,7 @@ crate::error::generate_rpc_error_subset!(Error: BlockNotFound, ContractNotFound)
 
 pub async fn get_nonce(context: RpcContext, input: Input) -> Result<Output, Error> {
     let span = tracing::Span::current();
-    util::task::spawn_blocking(move |_| -> Result<_, Error> {
+    util::task::spawn_blocking_storage(move |_| -> Result<_, Error> {
         let _g = span.enter();
         let mut db = context
             .storage
@@ -157,6 +157,27 @@ mod tests {
         assert_eq!(nonce.0, contract_nonce!("0x10"));
     }
 
+    #[tokio::test]
+    async fn l1_accepted() {
+        let context = RpcContext::for_tests();
+
+        // Only the genesis block is accepted on L1 in `setup_storage`. Contract 0 is
+        // deployed there, contract 1 only in block 1.
+        let input = Input {
+            block_id: BlockId::L1Accepted,
+            contract_address: contract_address_bytes!(b"contract 0"),
+        };
+        let nonce = get_nonce(context.clone(), input).await.unwrap();
+        assert_eq!(nonce.0, contract_nonce!("0x1"));
+
+        let input = Input {
+            block_id: BlockId::L1Accepted,
+            contract_address: contract_address_bytes!(b"contract 1"),
+        };
+        let result = get_nonce(context, input).await;
+        assert_matches!(result, Err(Error::ContractNotFound));
+    }
+
     #[tokio::test]
     async fn pending() {
         let context = RpcContext::for_tests_with_pending().await;
@@ -35,12 +35,22 @@ impl dto::SerializeForVersion for Output {
             Output::Pending(pending) => dto::PendingStateUpdate(pending).serialize(serializer),
         }
     }
+
+    fn serialize_raw(
+        &self,
+        serializer: dto::Serializer,
+    ) -> Option<Result<Box<serde_json::value::RawValue>, dto::Error>> {
+        match self {
+            Output::Full(full) => dto::StateUpdate(full).serialize_raw(serializer),
+            Output::Pending(pending) => dto::PendingStateUpdate(pending).serialize_raw(serializer),
+        }
+    }
 }
 
 pub async fn get_state_update(context: RpcContext, input: Input) -> Result<Output, Error> {
     let storage = context.storage.clone();
     let span = tracing::Span::current();
-    let jh = util::task::spawn_blocking(move |_| {
+    let jh = util::task::spawn_blocking_storage(move |_| {
         let _g = span.enter();
         let mut db = storage
             .connection()
@@ -612,6 +622,8 @@ mod tests {
     #[case::pending_by_name(json!({"block_id": "pending"}), BlockId::Pending)]
     #[case::latest_by_position(json!(["latest"]), BlockId::Latest)]
     #[case::latest_by_name(json!({"block_id": "latest"}), BlockId::Latest)]
+    #[case::l1_accepted_by_position(json!(["l1_accepted"]), BlockId::L1Accepted)]
+    #[case::l1_accepted_by_name(json!({"block_id": "l1_accepted"}), BlockId::L1Accepted)]
     #[case::number_by_position(json!([{"block_number":123}]), BlockNumber::new_or_panic(123).into())]
     #[case::number_by_name(json!({"block_id": {"block_number":123}}), BlockNumber::new_or_panic(123).into())]
     #[case::hash_by_position(json!([{"block_hash": "0xbeef"}]), block_hash!("0xbeef").into())]
@@ -30,7 +30,7 @@ pub async fn get_transaction_by_hash(
 ) -> Result<Output, GetTransactionByHashError> {
     let storage = context.storage.clone();
     let span = tracing::Span::current();
-    let jh = util::task::spawn_blocking(move |_| {
+    let jh = util::task::spawn_blocking_storage(move |_| {
         let _g = span.enter();
         let mut db = storage
             .connection()
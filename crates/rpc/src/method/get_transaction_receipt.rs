@@ -34,6 +34,7 @@ pub enum Output {
         receipt: Receipt,
         transaction: Transaction,
         events: Vec<Event>,
+        finality: dto::TxnFinalityStatus,
     },
 }
 
@@ -62,13 +63,14 @@ impl crate::dto::SerializeForVersion for Output {
                 receipt,
                 transaction,
                 events,
+                finality,
             } => dto::TxnReceiptWithBlockInfo {
                 block_hash: None,
                 block_number: None,
                 receipt,
                 transaction,
                 events,
-                finality: dto::TxnFinalityStatus::AcceptedOnL2,
+                finality: *finality,
             },
         }
         .serialize(serializer)
@@ -79,7 +81,7 @@ crate::error::generate_rpc_error_subset!(Error: TxnHashNotFound);
 
 pub async fn get_transaction_receipt(context: RpcContext, input: Input) -> Result<Output, Error> {
     let span = tracing::Span::current();
-    util::task::spawn_blocking(move |_| {
+    util::task::spawn_blocking_storage(move |_| {
         let _g = span.enter();
         let mut db = context
             .storage
@@ -105,6 +107,7 @@ pub async fn get_transaction_receipt(context: RpcContext, input: Input) -> Resul
                 receipt,
                 transaction,
                 events,
+                finality: crate::pending::finality_status(&pending.block),
             });
         }
 
@@ -112,6 +115,7 @@ pub async fn get_transaction_receipt(context: RpcContext, input: Input) -> Resul
             .transaction_with_receipt(input.transaction_hash)
             .context("Reading transaction receipt from database")?
             .ok_or(Error::TxnHashNotFound)?;
+        db_tx.ensure_events_retained(block_number)?;
 
         let block_hash = db_tx
             .block_hash(block_number.into())
@@ -1,6 +1,7 @@
 use anyhow::Context;
 use pathfinder_common::TransactionHash;
 
+use crate::circuit_breaker::GatewayCallError;
 use crate::context::RpcContext;
 use crate::dto::TxnExecutionStatus;
 use crate::RpcVersion;
@@ -33,6 +34,7 @@ pub enum Output {
         // Reject error message optional for backward compatibility with gateway.
         error_message: Option<String>,
     },
+    PreConfirmed(TxnExecutionStatus),
     AcceptedOnL1(TxnExecutionStatus),
     AcceptedOnL2(TxnExecutionStatus),
 }
@@ -42,7 +44,7 @@ crate::error::generate_rpc_error_subset!(Error: TxnHashNotFound);
 pub async fn get_transaction_status(context: RpcContext, input: Input) -> Result<Output, Error> {
     // Check database.
     let span = tracing::Span::current();
-    let db_status = util::task::spawn_blocking(move |_| {
+    let db_status = util::task::spawn_blocking_storage(move |_| {
         let _g = span.enter();
 
         let mut db = context
@@ -51,18 +53,25 @@ pub async fn get_transaction_status(context: RpcContext, input: Input) -> Result
             .context("Opening database connection")?;
         let db_tx = db.transaction().context("Creating database transaction")?;
 
-        if let Some((receipt, _)) = context
+        let pending = context
             .pending_data
             .get(&db_tx)
-            .context("Querying pending data")?
+            .context("Querying pending data")?;
+        if let Some((receipt, _)) = pending
             .block
             .transaction_receipts
             .iter()
             .find(|(rx, _)| rx.transaction_hash == input.transaction_hash)
         {
-            return Ok(Some(Output::AcceptedOnL2(
-                (&receipt.execution_status).into(),
-            )));
+            let execution_status = (&receipt.execution_status).into();
+            return Ok(Some(
+                match crate::pending::finality_status(&pending.block) {
+                    crate::dto::TxnFinalityStatus::PreConfirmed => {
+                        Output::PreConfirmed(execution_status)
+                    }
+                    _ => Output::AcceptedOnL2(execution_status),
+                },
+            ));
         }
 
         let Some((_, receipt, _, block_hash)) = db_tx
@@ -92,11 +101,15 @@ pub async fn get_transaction_status(context: RpcContext, input: Input) -> Result
     // Check gateway for rejected transactions.
     use starknet_gateway_client::GatewayApi;
     context
-        .sequencer
-        .transaction_status(input.transaction_hash)
+        .gateway_breaker
+        .call(context.sequencer.transaction_status(input.transaction_hash))
         .await
-        .context("Fetching transaction from gateway")
-        .map_err(Error::Internal)
+        .map_err(|e| match e {
+            GatewayCallError::Unavailable => Error::Custom(e.into()),
+            GatewayCallError::Gateway(e) => {
+                Error::Internal(anyhow::Error::new(e).context("Fetching transaction from gateway"))
+            }
+        })
         .and_then(|tx| {
             use starknet_gateway_types::reply::transaction_status::{
                 ExecutionStatus as GatewayExecutionStatus,
@@ -137,6 +150,7 @@ impl Output {
         match self {
             Output::Received => TxnStatus::Received,
             Output::Rejected { .. } => TxnStatus::Rejected,
+            Output::PreConfirmed(_) => TxnStatus::PreConfirmed,
             Output::AcceptedOnL1(_) => TxnStatus::AcceptedOnL1,
             Output::AcceptedOnL2(_) => TxnStatus::AcceptedOnL2,
         }
@@ -145,6 +159,7 @@ impl Output {
     fn execution_status(&self) -> Option<TxnExecutionStatus> {
         match self {
             Output::Received | Output::Rejected { .. } => None,
+            Output::PreConfirmed(x) => Some(x.clone()),
             Output::AcceptedOnL1(x) => Some(x.clone()),
             Output::AcceptedOnL2(x) => Some(x.clone()),
         }
@@ -153,6 +168,7 @@ impl Output {
     fn failure_reason(&self) -> Option<String> {
         match self {
             Output::Rejected { error_message } => error_message.clone(),
+            Output::PreConfirmed(TxnExecutionStatus::Reverted { reason }) => reason.clone(),
             Output::AcceptedOnL1(TxnExecutionStatus::Reverted { reason }) => reason.clone(),
             Output::AcceptedOnL2(TxnExecutionStatus::Reverted { reason }) => reason.clone(),
             _ => None,
@@ -196,6 +212,10 @@ mod tests {
         Output::AcceptedOnL2(TxnExecutionStatus::Reverted{ reason: None }),
         json!({"finality_status":"ACCEPTED_ON_L2","execution_status":"REVERTED"})
     )]
+    #[case::pre_confirmed(
+        Output::PreConfirmed(TxnExecutionStatus::Succeeded),
+        json!({"finality_status":"ACCEPTED_ON_L2","execution_status":"SUCCEEDED"})
+    )]
     fn output_serialization(#[case] output: Output, #[case] expected: serde_json::Value) {
         use crate::dto::SerializeForVersion;
         let encoded = output.serialize(Default::default()).unwrap();
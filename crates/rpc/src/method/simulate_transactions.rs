@@ -1,3 +1,5 @@
+use std::collections::BTreeSet;
+
 use anyhow::Context;
 use pathfinder_common::BlockId;
 use pathfinder_executor::TransactionExecutionError;
@@ -11,6 +13,19 @@ pub struct SimulateTransactionInput {
     pub block_id: BlockId,
     pub transactions: Vec<BroadcastedTransaction>,
     pub simulation_flags: crate::dto::SimulationFlags,
+    pub bundle: SimulationBundle,
+}
+
+/// Pathfinder specific options for simulating a sequence of dependent
+/// transactions, e.g. an approval followed by a swap.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SimulationBundle {
+    /// Indices of the transactions which only prepare state for the following
+    /// ones. Their fee estimation is left out of the output.
+    pub setup_transactions: BTreeSet<usize>,
+    /// Whether to return the state diff accumulated by the transactions up to
+    /// and including each transaction.
+    pub intermediate_state_diffs: bool,
 }
 
 impl crate::dto::DeserializeForVersion for SimulateTransactionInput {
@@ -22,19 +37,46 @@ impl crate::dto::DeserializeForVersion for SimulateTransactionInput {
                     BroadcastedTransaction::deserialize(value)
                 })?,
                 simulation_flags: value.deserialize("simulation_flags")?,
+                bundle: SimulationBundle {
+                    setup_transactions: value
+                        .deserialize_optional_array("setup_transactions", |value| {
+                            value.deserialize()
+                        })?
+                        .unwrap_or_default()
+                        .into_iter()
+                        .collect(),
+                    intermediate_state_diffs: value
+                        .deserialize_optional("intermediate_state_diffs")?
+                        .unwrap_or_default(),
+                },
             })
         })
     }
 }
 
-pub struct Output(Vec<pathfinder_executor::types::TransactionSimulation>);
+pub struct Output {
+    pub(crate) simulations: Vec<pathfinder_executor::types::TransactionSimulation>,
+    pub(crate) bundle: SimulationBundle,
+}
 
 pub async fn simulate_transactions(
     context: RpcContext,
     input: SimulateTransactionInput,
 ) -> Result<Output, SimulateTransactionError> {
+    if let Some(index) = input
+        .bundle
+        .setup_transactions
+        .last()
+        .filter(|index| **index >= input.transactions.len())
+    {
+        return Err(SimulateTransactionError::Custom(anyhow::anyhow!(
+            "Setup transaction index {index} is out of range for {} transactions",
+            input.transactions.len()
+        )));
+    }
+
     let span = tracing::Span::current();
-    util::task::spawn_blocking(move |_| {
+    util::task::spawn_blocking_execution(move |_| {
         let _g = span.enter();
 
         let skip_validate = input
@@ -83,9 +125,9 @@ pub async fn simulate_transactions(
             pending,
             pathfinder_executor::L1BlobDataAvailability::Enabled,
             context.config.custom_versioned_constants,
-            context.contract_addresses.eth_l2_token_address,
-            context.contract_addresses.strk_l2_token_address,
-        );
+            context.fee_tokens(),
+        )
+        .with_execution_policy(context.config.execution_policy.clone());
 
         let transactions = input
             .transactions
@@ -100,8 +142,12 @@ pub async fn simulate_transactions(
             })
             .collect::<Result<Vec<_>, _>>()?;
 
-        let txs = pathfinder_executor::simulate(state, transactions)?;
-        Ok(Output(txs))
+        let txs = pathfinder_executor::simulate(state, transactions)
+            .map_err(|error| crate::executor::annotate_execution_error(&db, error))?;
+        Ok(Output {
+            simulations: txs,
+            bundle: input.bundle,
+        })
     })
     .await
     .context("Simulating transaction")?
@@ -112,11 +158,67 @@ impl crate::dto::SerializeForVersion for Output {
         &self,
         serializer: crate::dto::Serializer,
     ) -> Result<crate::dto::Ok, crate::dto::Error> {
-        serializer.serialize_iter(self.0.len(), &mut self.0.iter().map(TransactionSimulation))
+        if self.bundle == SimulationBundle::default() {
+            return serializer.serialize_iter(
+                self.simulations.len(),
+                &mut self.simulations.iter().map(TransactionSimulation),
+            );
+        }
+
+        let mut state_diff = pathfinder_executor::types::StateDiff::default();
+        let mut simulations = Vec::with_capacity(self.simulations.len());
+        for (index, simulation) in self.simulations.iter().enumerate() {
+            let state_diff_after = if self.bundle.intermediate_state_diffs {
+                state_diff.merge(simulation.trace.state_diff());
+                Some(state_diff.clone())
+            } else {
+                None
+            };
+
+            simulations.push(BundledTransactionSimulation {
+                simulation,
+                setup: self.bundle.setup_transactions.contains(&index),
+                state_diff_after,
+            });
+        }
+
+        serializer.serialize_iter(simulations.len(), &mut simulations.into_iter())
     }
 }
 
-struct TransactionSimulation<'a>(&'a pathfinder_executor::types::TransactionSimulation);
+pub(crate) struct TransactionSimulation<'a>(
+    pub(crate) &'a pathfinder_executor::types::TransactionSimulation,
+);
+
+/// A simulated transaction of a [SimulationBundle].
+struct BundledTransactionSimulation<'a> {
+    simulation: &'a pathfinder_executor::types::TransactionSimulation,
+    setup: bool,
+    state_diff_after: Option<pathfinder_executor::types::StateDiff>,
+}
+
+impl crate::dto::SerializeForVersion for BundledTransactionSimulation<'_> {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        if !self.setup {
+            serializer.serialize_field("fee_estimation", &self.simulation.fee_estimation)?;
+        }
+        serializer.serialize_field(
+            "transaction_trace",
+            &crate::dto::TransactionTrace {
+                trace: self.simulation.trace.clone(),
+                include_state_diff: true,
+            },
+        )?;
+        if let Some(state_diff) = &self.state_diff_after {
+            serializer.serialize_field("state_diff_after", state_diff)?;
+        }
+        serializer.end()
+    }
+}
 
 impl crate::dto::SerializeForVersion for TransactionSimulation<'_> {
     fn serialize(
@@ -146,11 +248,19 @@ pub enum SimulateTransactionError {
         error: String,
         error_stack: pathfinder_executor::ErrorStack,
     },
+    ExecutionRefused(pathfinder_executor::ExecutionRefused),
 }
 
 impl From<anyhow::Error> for SimulateTransactionError {
     fn from(e: anyhow::Error) -> Self {
-        Self::Internal(e)
+        // Declared classes exceeding the compilation limits are the caller's problem.
+        if e.downcast_ref::<pathfinder_compiler::limits::ResourcesExceeded>()
+            .is_some()
+        {
+            Self::Custom(e)
+        } else {
+            Self::Internal(e)
+        }
     }
 }
 
@@ -169,6 +279,7 @@ impl From<SimulateTransactionError> for crate::error::ApplicationError {
                 error,
                 error_stack,
             },
+            SimulateTransactionError::ExecutionRefused(refused) => Self::ExecutionRefused(refused),
         }
     }
 }
@@ -186,6 +297,7 @@ impl From<TransactionExecutionError> for SimulateTransactionError {
                 error,
                 error_stack,
             },
+            ExecutionRefused(refused) => Self::ExecutionRefused(refused),
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -203,7 +315,7 @@ impl From<ExecutionStateError> for SimulateTransactionError {
 
 #[cfg(test)]
 pub(crate) mod tests {
-    use std::collections::{BTreeMap, HashSet};
+    use std::collections::{BTreeMap, BTreeSet};
 
     use pathfinder_common::macro_prelude::*;
     use pathfinder_common::{
@@ -292,7 +404,8 @@ pub(crate) mod tests {
         let value = crate::dto::Value::new(input_json, RpcVersion::V07);
         let input = SimulateTransactionInput::deserialize(value).unwrap();
 
-        let expected = crate::method::simulate_transactions::Output(vec![
+        let expected = crate::method::simulate_transactions::Output {
+            simulations: vec![
             pathfinder_executor::types::TransactionSimulation{
                 fee_estimation: pathfinder_executor::types::FeeEstimate {
                     l1_gas_consumed: 19.into(),
@@ -352,7 +465,7 @@ pub(crate) mod tests {
                         fee_transfer_invocation: None,
                         state_diff: pathfinder_executor::types::StateDiff {
                             storage_diffs: BTreeMap::new(),
-                            deprecated_declared_classes: HashSet::new(),
+                            deprecated_declared_classes: BTreeSet::new(),
                             declared_classes: vec![],
                             deployed_contracts: vec![
                                 pathfinder_executor::types::DeployedContract {
@@ -376,7 +489,9 @@ pub(crate) mod tests {
                     },
                 ),
             }
-        ]).serialize(Serializer {
+        ],
+            bundle: Default::default(),
+        }.serialize(Serializer {
             version: RpcVersion::V07,
         }).unwrap();
 
@@ -423,11 +538,13 @@ pub(crate) mod tests {
             block_id: last_block_header.number.into(),
             transactions: vec![declare],
             simulation_flags: crate::dto::SimulationFlags(vec![]),
+            bundle: Default::default(),
         };
 
         const OVERALL_FEE: u64 = 15720;
 
-        let expected = crate::method::simulate_transactions::Output(vec![
+        let expected = crate::method::simulate_transactions::Output {
+            simulations: vec![
             pathfinder_executor::types::TransactionSimulation{
                 trace: pathfinder_executor::types::TransactionTrace::Declare(pathfinder_executor::types::DeclareTransactionTrace {
                     validate_invocation: Some(
@@ -503,7 +620,7 @@ pub(crate) mod tests {
                                 },
                             ]),
                         ]),
-                        deprecated_declared_classes: HashSet::from([
+                        deprecated_declared_classes: BTreeSet::from([
                             CAIRO0_HASH
                         ]),
                         declared_classes: vec![],
@@ -541,7 +658,9 @@ pub(crate) mod tests {
                     unit: pathfinder_executor::types::PriceUnit::Wei,
                 }
             }
-        ]).serialize(Serializer {
+        ],
+            bundle: Default::default(),
+        }.serialize(Serializer {
             version: RpcVersion::V07,
         }).unwrap();
 
@@ -880,7 +999,7 @@ pub(crate) mod tests {
                             })
                             .collect::<Vec<_>>(),
                     ),
-                    deprecated_declared_classes: HashSet::new(),
+                    deprecated_declared_classes: BTreeSet::new(),
                     declared_classes: vec![pathfinder_executor::types::DeclaredSierraClass {
                         class_hash: SierraHash(SIERRA_HASH.0),
                         compiled_class_hash: CASM_HASH,
@@ -1187,7 +1306,7 @@ pub(crate) mod tests {
                             })
                             .collect::<Vec<_>>(),
                     ),
-                    deprecated_declared_classes: HashSet::new(),
+                    deprecated_declared_classes: BTreeSet::new(),
                     declared_classes: vec![],
                     deployed_contracts: vec![pathfinder_executor::types::DeployedContract {
                         address: DEPLOYED_CONTRACT_ADDRESS,
@@ -1600,7 +1719,7 @@ pub(crate) mod tests {
                             })
                             .collect::<Vec<_>>(),
                     ),
-                    deprecated_declared_classes: HashSet::new(),
+                    deprecated_declared_classes: BTreeSet::new(),
                     declared_classes: vec![],
                     deployed_contracts: vec![],
                     replaced_classes: vec![],
@@ -1957,7 +2076,7 @@ pub(crate) mod tests {
                             })
                             .collect::<Vec<_>>(),
                     ),
-                    deprecated_declared_classes: HashSet::new(),
+                    deprecated_declared_classes: BTreeSet::new(),
                     declared_classes: vec![],
                     deployed_contracts: vec![],
                     replaced_classes: vec![],
@@ -2006,6 +2125,7 @@ pub(crate) mod tests {
             ],
             block_id: BlockId::Number(last_block_header.number),
             simulation_flags: crate::dto::SimulationFlags(vec![]),
+            bundle: Default::default(),
         };
         let result = simulate_transactions(context, input).await.unwrap();
 
@@ -2013,7 +2133,7 @@ pub(crate) mod tests {
             version: RpcVersion::V07,
         };
 
-        let result_serializable = result.0.into_iter().collect::<Vec<_>>();
+        let result_serializable = result.simulations.into_iter().collect::<Vec<_>>();
 
         let result_serialized = serializer
             .serialize_iter(
@@ -2081,26 +2201,30 @@ pub(crate) mod tests {
             simulation_flags: crate::dto::SimulationFlags(vec![
                 crate::dto::SimulationFlag::SkipFeeCharge,
             ]),
+            bundle: Default::default(),
         };
         let result = simulate_transactions(context, input).await.unwrap();
 
-        let expected = super::Output(vec![
-            fixtures::expected_output_0_13_1_1::declare_without_fee_transfer(
-                account_contract_address,
-            ),
-            fixtures::expected_output_0_13_1_1::universal_deployer_without_fee_transfer(
-                account_contract_address,
-                universal_deployer_address,
-            ),
-            fixtures::expected_output_0_13_1_1::invoke_without_fee_transfer(
-                account_contract_address,
-                test_storage_value,
-            ),
-            fixtures::expected_output_0_13_1_1::invoke_v3_without_fee_transfer(
-                account_contract_address,
-                test_storage_value,
-            ),
-        ]);
+        let expected = super::Output {
+            simulations: vec![
+                fixtures::expected_output_0_13_1_1::declare_without_fee_transfer(
+                    account_contract_address,
+                ),
+                fixtures::expected_output_0_13_1_1::universal_deployer_without_fee_transfer(
+                    account_contract_address,
+                    universal_deployer_address,
+                ),
+                fixtures::expected_output_0_13_1_1::invoke_without_fee_transfer(
+                    account_contract_address,
+                    test_storage_value,
+                ),
+                fixtures::expected_output_0_13_1_1::invoke_v3_without_fee_transfer(
+                    account_contract_address,
+                    test_storage_value,
+                ),
+            ],
+            bundle: Default::default(),
+        };
 
         pretty_assertions_sorted::assert_eq!(
             result
@@ -2141,29 +2265,33 @@ pub(crate) mod tests {
             simulation_flags: crate::dto::SimulationFlags(vec![
                 crate::dto::SimulationFlag::SkipValidate,
             ]),
+            bundle: Default::default(),
         };
 
-        let expected = super::Output(vec![
-            fixtures::expected_output_0_13_1_1::declare_without_validate(
-                account_contract_address,
-                &last_block_header,
-            ),
-            fixtures::expected_output_0_13_1_1::universal_deployer_without_validate(
-                account_contract_address,
-                &last_block_header,
-                universal_deployer_address,
-            ),
-            fixtures::expected_output_0_13_1_1::invoke_without_validate(
-                account_contract_address,
-                &last_block_header,
-                test_storage_value,
-            ),
-            fixtures::expected_output_0_13_1_1::invoke_v3_without_validate(
-                account_contract_address,
-                &last_block_header,
-                test_storage_value,
-            ),
-        ]);
+        let expected = super::Output {
+            simulations: vec![
+                fixtures::expected_output_0_13_1_1::declare_without_validate(
+                    account_contract_address,
+                    &last_block_header,
+                ),
+                fixtures::expected_output_0_13_1_1::universal_deployer_without_validate(
+                    account_contract_address,
+                    &last_block_header,
+                    universal_deployer_address,
+                ),
+                fixtures::expected_output_0_13_1_1::invoke_without_validate(
+                    account_contract_address,
+                    &last_block_header,
+                    test_storage_value,
+                ),
+                fixtures::expected_output_0_13_1_1::invoke_v3_without_validate(
+                    account_contract_address,
+                    &last_block_header,
+                    test_storage_value,
+                ),
+            ],
+            bundle: Default::default(),
+        };
 
         let result = simulate_transactions(context, input).await.unwrap();
 
@@ -2180,4 +2308,107 @@ pub(crate) mod tests {
                 .unwrap(),
         );
     }
+
+    #[test]
+    fn bundle_options_are_parsed() {
+        let input = serde_json::json!({
+            "block_id": "latest",
+            "transactions": [],
+            "simulation_flags": [],
+            "setup_transactions": [2, 0],
+            "intermediate_state_diffs": true,
+        });
+        let input =
+            SimulateTransactionInput::deserialize(crate::dto::Value::new(input, RpcVersion::V07))
+                .unwrap();
+
+        assert_eq!(
+            input.bundle,
+            super::SimulationBundle {
+                setup_transactions: BTreeSet::from([0, 2]),
+                intermediate_state_diffs: true,
+            }
+        );
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn setup_transaction_out_of_range() {
+        let (storage, last_block_header, account_contract_address, _, _) =
+            setup_storage_with_starknet_version(StarknetVersion::new(0, 13, 1, 1)).await;
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let input = SimulateTransactionInput {
+            transactions: vec![fixtures::input::declare(account_contract_address)],
+            block_id: BlockId::Number(last_block_header.number),
+            simulation_flags: crate::dto::SimulationFlags(vec![]),
+            bundle: super::SimulationBundle {
+                setup_transactions: BTreeSet::from([1]),
+                intermediate_state_diffs: false,
+            },
+        };
+
+        let error = simulate_transactions(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(error, super::SimulateTransactionError::Custom(_));
+    }
+
+    #[test_log::test(tokio::test)]
+    async fn bundle_with_setup_transactions_and_intermediate_state_diffs() {
+        let (storage, last_block_header, account_contract_address, universal_deployer_address, _) =
+            setup_storage_with_starknet_version(StarknetVersion::new(0, 13, 1, 1)).await;
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let input = SimulateTransactionInput {
+            transactions: vec![
+                fixtures::input::declare(account_contract_address),
+                fixtures::input::universal_deployer(
+                    account_contract_address,
+                    universal_deployer_address,
+                ),
+                fixtures::input::invoke(account_contract_address),
+            ],
+            block_id: BlockId::Number(last_block_header.number),
+            simulation_flags: crate::dto::SimulationFlags(vec![]),
+            bundle: super::SimulationBundle {
+                setup_transactions: BTreeSet::from([0, 1]),
+                intermediate_state_diffs: true,
+            },
+        };
+        let result = simulate_transactions(context, input).await.unwrap();
+
+        let mut expected_state_diff = pathfinder_executor::types::StateDiff::default();
+        let mut expected_state_diffs = Vec::new();
+        for simulation in &result.simulations {
+            expected_state_diff.merge(simulation.trace.state_diff());
+            expected_state_diffs.push(
+                expected_state_diff
+                    .serialize(Serializer {
+                        version: RpcVersion::V07,
+                    })
+                    .unwrap(),
+            );
+        }
+
+        // The state diff after the last transaction contains the effects of
+        // all of them.
+        assert_eq!(expected_state_diff.declared_classes.len(), 1);
+        assert_eq!(expected_state_diff.deployed_contracts.len(), 1);
+        assert_eq!(
+            expected_state_diff.nonces.get(&account_contract_address),
+            Some(&contract_nonce!("0x3"))
+        );
+
+        let serialized = result
+            .serialize(Serializer {
+                version: RpcVersion::V07,
+            })
+            .unwrap();
+        let serialized = serialized.as_array().unwrap();
+        assert_eq!(serialized.len(), 3);
+        assert!(serialized[0].get("fee_estimation").is_none());
+        assert!(serialized[1].get("fee_estimation").is_none());
+        assert!(serialized[2].get("fee_estimation").is_some());
+        for (simulation, expected) in serialized.iter().zip(expected_state_diffs) {
+            assert_eq!(simulation["state_diff_after"], expected);
+        }
+    }
 }
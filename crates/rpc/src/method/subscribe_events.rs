@@ -2,16 +2,19 @@ use std::sync::Arc;
 
 use axum::async_trait;
 use pathfinder_common::{BlockId, BlockNumber, ContractAddress, EventKey};
-use pathfinder_storage::{AGGREGATE_BLOOM_BLOCK_RANGE_LEN, EVENT_KEY_FILTER_LIMIT};
+use pathfinder_storage::AGGREGATE_BLOOM_BLOCK_RANGE_LEN;
 use tokio::sync::mpsc;
 
+pub(crate) use self::fanout::EventFanout;
+use self::fanout::EventFilter;
 use super::REORG_SUBSCRIPTION_NAME;
 use crate::context::RpcContext;
 use crate::error::ApplicationError;
 use crate::jsonrpc::{CatchUp, RpcError, RpcSubscriptionFlow, SubscriptionMessage};
-use crate::method::get_events::EmittedEvent;
 use crate::Reorg;
 
+mod fanout;
+
 pub struct SubscribeEvents;
 
 #[derive(Debug, Clone, Default)]
@@ -67,16 +70,17 @@ impl RpcSubscriptionFlow for SubscribeEvents {
     type Notification = Notification;
     const CATCH_UP_BATCH_SIZE: u64 = AGGREGATE_BLOOM_BLOCK_RANGE_LEN;
 
-    fn validate_params(params: &Self::Params) -> Result<(), RpcError> {
+    fn validate_params(context: &RpcContext, params: &Self::Params) -> Result<(), RpcError> {
         if let Some(params) = params {
             if let Some(BlockId::Pending) = params.block_id {
                 return Err(RpcError::ApplicationError(ApplicationError::CallOnPending));
             }
             if let Some(keys) = &params.keys {
-                if keys.len() > EVENT_KEY_FILTER_LIMIT {
+                let max_keys = context.config.event_filter_max_keys.get();
+                if keys.len() > max_keys {
                     return Err(RpcError::ApplicationError(
                         ApplicationError::TooManyKeysInFilter {
-                            limit: EVENT_KEY_FILTER_LIMIT,
+                            limit: max_keys,
                             requested: keys.len(),
                         },
                     ));
@@ -101,22 +105,23 @@ impl RpcSubscriptionFlow for SubscribeEvents {
     ) -> Result<CatchUp<Self::Notification>, RpcError> {
         let params = params.clone().unwrap_or_default();
         let storage = state.storage.clone();
-        let (events, last_block) = util::task::spawn_blocking(move |_| -> Result<_, RpcError> {
-            let mut conn = storage.connection().map_err(RpcError::InternalError)?;
-            let db = conn.transaction().map_err(RpcError::InternalError)?;
-            let events = db
-                .events_in_range(
-                    from,
-                    to,
-                    params.from_address,
-                    params.keys.unwrap_or_default(),
-                )
-                .map_err(RpcError::InternalError)?;
-
-            Ok(events)
-        })
-        .await
-        .map_err(|e| RpcError::InternalError(e.into()))??;
+        let (events, last_block) =
+            util::task::spawn_blocking_storage(move |_| -> Result<_, RpcError> {
+                let mut conn = storage.connection().map_err(RpcError::InternalError)?;
+                let db = conn.transaction().map_err(RpcError::InternalError)?;
+                let events = db
+                    .events_in_range(
+                        from,
+                        to,
+                        params.from_address,
+                        params.keys.unwrap_or_default(),
+                    )
+                    .map_err(|e| RpcError::ApplicationError(ApplicationError::internal(e)))?;
+
+                Ok(events)
+            })
+            .await
+            .map_err(|e| RpcError::InternalError(e.into()))??;
         let messages = events
             .into_iter()
             .map(|event| {
@@ -139,11 +144,10 @@ impl RpcSubscriptionFlow for SubscribeEvents {
         params: Self::Params,
         tx: mpsc::Sender<SubscriptionMessage<Self::Notification>>,
     ) -> Result<(), RpcError> {
-        let mut blocks = state.notifications.l2_blocks.subscribe();
-        let mut reorgs = state.notifications.reorgs.subscribe();
         let params = params.unwrap_or_default();
-        let keys = params.keys.unwrap_or_default();
-        let key_filter_is_empty = keys.iter().flatten().count() == 0;
+        let filter = EventFilter::new(params.from_address, params.keys.unwrap_or_default());
+        let mut events = state.notifications.events.subscribe(filter);
+        let mut reorgs = state.notifications.reorgs.subscribe();
         loop {
             tokio::select! {
                 reorg = reorgs.recv() => {
@@ -168,58 +172,27 @@ impl RpcSubscriptionFlow for SubscribeEvents {
                         }
                     }
                 }
-                block = blocks.recv() => {
-                    match block {
-                        Ok(block) => {
-                            let block_number = block.block_number;
-                            let block_hash = block.block_hash;
-                            for (receipt, events) in block.transaction_receipts.iter() {
-                                for event in events {
-                                    // Check if the event matches the filter.
-                                    if let Some(from_address) = params.from_address {
-                                        if event.from_address != from_address {
-                                            continue;
-                                        }
-                                    }
-                                    let matches_keys = if key_filter_is_empty {
-                                        true
-                                    } else if event.keys.len() < keys.len() {
-                                        false
-                                    } else {
-                                        event
-                                            .keys
-                                            .iter()
-                                            .zip(keys.iter())
-                                            .all(|(key, filter)| filter.is_empty() || filter.contains(key))
-                                    };
-                                    if !matches_keys {
-                                        continue;
-                                    }
-                                    if tx.send(SubscriptionMessage {
-                                        notification: Notification::EmittedEvent(EmittedEvent {
-                                            data: event.data.clone(),
-                                            keys: event.keys.clone(),
-                                            from_address: event.from_address,
-                                            block_hash: Some(block_hash),
-                                            block_number: Some(block_number),
-                                            transaction_hash: receipt.transaction_hash,
-                                        }),
-                                        block_number,
-                                        subscription_name: SUBSCRIPTION_NAME,
-                                    }).await.is_err() {
-                                        break;
-                                    }
-                                }
+                event = events.recv() => {
+                    match event {
+                        Ok(Some((block_number, event))) => {
+                            if tx.send(SubscriptionMessage {
+                                notification: Notification::EmittedEvent(event),
+                                block_number,
+                                subscription_name: SUBSCRIPTION_NAME,
+                            }).await.is_err() {
+                                break;
                             }
                         }
-                        Err(e) => {
-                            tracing::debug!(
-                                "Error receiving block header from notifications channel, node might be \
-                                 lagging: {:?}",
-                                e
-                            );
+                        Ok(None) => {
+                            tracing::debug!("Event fan-out stopped, closing subscription");
                             break;
                         }
+                        Err(lagging) => {
+                            tracing::debug!("Subscription lagging, closing it");
+                            return Err(RpcError::ApplicationError(ApplicationError::Custom(
+                                lagging.into(),
+                            )));
+                        }
                     }
                 }
             }
@@ -734,6 +707,9 @@ mod tests {
                 pathfinder_ethereum::core_addr::MAINNET,
             ),
             sequencer: Client::mainnet(Duration::from_secs(10)),
+            gateway_breaker: Default::default(),
+            submissions: Default::default(),
+            responses: Default::default(),
             websocket: None,
             notifications,
             ethereum: EthereumClient::new("wss://eth-sepolia.g.alchemy.com/v2/just-for-tests")
@@ -743,6 +719,23 @@ mod tests {
                 get_events_max_blocks_to_scan: 1024.try_into().unwrap(),
                 get_events_max_uncached_event_filters_to_load: 1.try_into().unwrap(),
                 custom_versioned_constants: None,
+                max_request_body_size: 10 * 1024 * 1024,
+                response_size_limits: Default::default(),
+                trace_cache: Default::default(),
+                gateway_circuit_breaker: Default::default(),
+                additional_fee_tokens: vec![],
+                execution_policy: Default::default(),
+                reconstruct_gateway_trace_events: false,
+                idempotency_key_ttl: Duration::from_secs(300),
+                gateway_outbox: false,
+                rejected_transaction_archive: false,
+                declare_precheck: false,
+                trace_cache_warmup: false,
+                get_proof_max_keys: 100.try_into().unwrap(),
+                event_filter_max_keys: 16.try_into().unwrap(),
+                get_events_max_chunk_size: 1024.try_into().unwrap(),
+                method_access: Default::default(),
+                response_cache_max_size: None,
             },
         };
         v08::register_routes().build(ctx)
@@ -0,0 +1,492 @@
+//! Server-side fan-out of L2 block events to `starknet_subscribeEvents`
+//! subscriptions.
+//!
+//! Instead of every subscription walking every block on its own, a single
+//! dispatcher task walks the events of each block once and looks up the
+//! interested subscriptions in an index keyed on contract address and first
+//! event key. Only those candidates evaluate their full filter.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use pathfinder_common::event::Event;
+use pathfinder_common::{BlockNumber, ContractAddress, EventKey};
+use starknet_gateway_types::reply::Block;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::method::get_events::EmittedEvent;
+
+/// Number of events buffered per subscription. A subscription which falls
+/// further behind than this is considered to be lagging and is closed with
+/// [Lagging].
+const SUBSCRIBER_BUFFER_SIZE: usize = 1024;
+
+/// Returned to a subscription which was closed because it missed events,
+/// either because it fell behind or because the dispatcher did. The client
+/// has to resubscribe to catch up from storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Subscription fell behind and missed events, resubscribe to catch up")]
+pub(crate) struct Lagging;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct EventFilter {
+    from_address: Option<ContractAddress>,
+    keys: Vec<Vec<EventKey>>,
+}
+
+impl EventFilter {
+    pub fn new(from_address: Option<ContractAddress>, keys: Vec<Vec<EventKey>>) -> Self {
+        // A filter without any keys at all matches every event, regardless of
+        // how many key positions it lists.
+        let keys = if keys.iter().all(Vec::is_empty) {
+            Vec::new()
+        } else {
+            keys
+        };
+        Self { from_address, keys }
+    }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        if let Some(from_address) = self.from_address {
+            if event.from_address != from_address {
+                return false;
+            }
+        }
+        if event.keys.len() < self.keys.len() {
+            return false;
+        }
+        event
+            .keys
+            .iter()
+            .zip(self.keys.iter())
+            .all(|(key, filter)| filter.is_empty() || filter.contains(key))
+    }
+}
+
+/// Shared registry of event subscriptions, fed by a single dispatcher task.
+///
+/// The dispatcher subscribes to the L2 block channel when the first
+/// subscription is registered and exits once the last one is gone.
+#[derive(Clone)]
+pub(crate) struct EventFanout {
+    registry: Arc<Mutex<Registry>>,
+    blocks: broadcast::Sender<Arc<Block>>,
+}
+
+impl std::fmt::Debug for EventFanout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let subscribers = self.registry.lock().unwrap().subscribers.len();
+        f.debug_struct("EventFanout")
+            .field("subscribers", &subscribers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl EventFanout {
+    pub fn new(blocks: broadcast::Sender<Arc<Block>>) -> Self {
+        Self {
+            registry: Default::default(),
+            blocks,
+        }
+    }
+
+    /// Registers a new subscription. Events matching `filter` from blocks
+    /// received after this call are delivered to the returned handle.
+    pub fn subscribe(&self, filter: EventFilter) -> EventSubscription {
+        let (tx, rx) = mpsc::channel(SUBSCRIBER_BUFFER_SIZE);
+        let lagging = Arc::new(AtomicBool::new(false));
+        let mut registry = self.registry.lock().unwrap();
+        let id = registry.insert(filter, tx, lagging.clone());
+        if !registry.dispatcher_running {
+            registry.dispatcher_running = true;
+            // Subscribe while holding the lock so that no block sent after this
+            // call can be missed.
+            let blocks = self.blocks.subscribe();
+            util::task::spawn(dispatch(self.registry.clone(), blocks));
+        }
+        EventSubscription {
+            id,
+            rx,
+            lagging,
+            registry: self.registry.clone(),
+        }
+    }
+}
+
+/// Handle to a registered subscription. Unregisters itself when dropped.
+pub(crate) struct EventSubscription {
+    id: u64,
+    rx: mpsc::Receiver<(BlockNumber, EmittedEvent)>,
+    /// Set by the dispatcher before it closes a subscription which missed
+    /// events.
+    lagging: Arc<AtomicBool>,
+    registry: Arc<Mutex<Registry>>,
+}
+
+impl EventSubscription {
+    /// Receives the next matching event. Events buffered before the
+    /// subscription was closed are still delivered. Afterwards [Lagging] is
+    /// returned if events were missed, or [`None`] if the block channel
+    /// closed.
+    pub async fn recv(&mut self) -> Result<Option<(BlockNumber, EmittedEvent)>, Lagging> {
+        match self.rx.recv().await {
+            Some(event) => Ok(Some(event)),
+            None if self.lagging.load(Ordering::Relaxed) => Err(Lagging),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Drop for EventSubscription {
+    fn drop(&mut self) {
+        self.registry.lock().unwrap().remove(self.id);
+    }
+}
+
+struct Subscriber {
+    filter: EventFilter,
+    tx: mpsc::Sender<(BlockNumber, EmittedEvent)>,
+    lagging: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct Registry {
+    next_id: u64,
+    subscribers: HashMap<u64, Subscriber>,
+    /// Subscriptions with a `from_address` filter.
+    by_address: HashMap<ContractAddress, HashSet<u64>>,
+    /// Subscriptions without a `from_address` filter but with a non-empty
+    /// first key position, indexed by each of the accepted first keys.
+    by_first_key: HashMap<EventKey, HashSet<u64>>,
+    /// Subscriptions which have to be checked against every event.
+    unindexed: HashSet<u64>,
+    dispatcher_running: bool,
+}
+
+impl Registry {
+    fn insert(
+        &mut self,
+        filter: EventFilter,
+        tx: mpsc::Sender<(BlockNumber, EmittedEvent)>,
+        lagging: Arc<AtomicBool>,
+    ) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        match (filter.from_address, filter.keys.first()) {
+            (Some(address), _) => {
+                self.by_address.entry(address).or_default().insert(id);
+            }
+            (None, Some(first_keys)) if !first_keys.is_empty() => {
+                for key in first_keys {
+                    self.by_first_key.entry(*key).or_default().insert(id);
+                }
+            }
+            (None, _) => {
+                self.unindexed.insert(id);
+            }
+        }
+        self.subscribers.insert(
+            id,
+            Subscriber {
+                filter,
+                tx,
+                lagging,
+            },
+        );
+
+        id
+    }
+
+    fn remove(&mut self, id: u64) {
+        let Some(subscriber) = self.subscribers.remove(&id) else {
+            return;
+        };
+        match (
+            subscriber.filter.from_address,
+            subscriber.filter.keys.first(),
+        ) {
+            (Some(address), _) => remove_from_index(&mut self.by_address, &address, id),
+            (None, Some(first_keys)) if !first_keys.is_empty() => {
+                for key in first_keys {
+                    remove_from_index(&mut self.by_first_key, key, id);
+                }
+            }
+            (None, _) => {
+                self.unindexed.remove(&id);
+            }
+        }
+    }
+
+    /// Closes a subscription which missed events, notifying it with [Lagging]
+    /// once it has received the events already buffered.
+    fn close_lagging(&mut self, id: u64) {
+        if let Some(subscriber) = self.subscribers.get(&id) {
+            subscriber.lagging.store(true, Ordering::Relaxed);
+        }
+        self.remove(id);
+    }
+
+    fn clear(&mut self) {
+        self.subscribers.clear();
+        self.by_address.clear();
+        self.by_first_key.clear();
+        self.unindexed.clear();
+    }
+
+    /// Candidate subscriptions for an event. The index sets are disjoint, so
+    /// no subscription is yielded twice.
+    fn candidates<'a>(&'a self, event: &Event) -> impl Iterator<Item = u64> + 'a {
+        let by_address = self.by_address.get(&event.from_address);
+        let by_first_key = event
+            .keys
+            .first()
+            .and_then(|key| self.by_first_key.get(key));
+        by_address
+            .into_iter()
+            .flatten()
+            .chain(by_first_key.into_iter().flatten())
+            .chain(self.unindexed.iter())
+            .copied()
+    }
+
+    /// Delivers the matching events of `block` to the subscriptions. Those
+    /// whose buffer is full are closed with [Lagging], the others are not held
+    /// up by them.
+    fn dispatch(&mut self, block: &Block) {
+        let mut lagging = HashSet::new();
+        for (receipt, events) in block.transaction_receipts.iter() {
+            for event in events {
+                for id in self.candidates(event) {
+                    if lagging.contains(&id) {
+                        continue;
+                    }
+                    let subscriber = &self.subscribers[&id];
+                    if !subscriber.filter.matches(event) {
+                        continue;
+                    }
+                    let emitted = EmittedEvent {
+                        data: event.data.clone(),
+                        keys: event.keys.clone(),
+                        from_address: event.from_address,
+                        block_hash: Some(block.block_hash),
+                        block_number: Some(block.block_number),
+                        transaction_hash: receipt.transaction_hash,
+                    };
+                    if subscriber
+                        .tx
+                        .try_send((block.block_number, emitted))
+                        .is_err()
+                    {
+                        lagging.insert(id);
+                    }
+                }
+            }
+        }
+        for id in lagging {
+            tracing::debug!(subscription=%id, "Event subscription lagging, closing it");
+            self.close_lagging(id);
+        }
+    }
+}
+
+fn remove_from_index<K: std::hash::Hash + Eq>(
+    index: &mut HashMap<K, HashSet<u64>>,
+    key: &K,
+    id: u64,
+) {
+    if let Some(ids) = index.get_mut(key) {
+        ids.remove(&id);
+        if ids.is_empty() {
+            index.remove(key);
+        }
+    }
+}
+
+async fn dispatch(registry: Arc<Mutex<Registry>>, mut blocks: broadcast::Receiver<Arc<Block>>) {
+    loop {
+        let block = blocks.recv().await;
+        let mut registry = registry.lock().unwrap();
+        match block {
+            Ok(block) => registry.dispatch(&block),
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                // Every subscription missed these blocks, close them all so that
+                // clients can resubscribe and catch up from storage.
+                tracing::debug!(%skipped, "Event fan-out lagging, closing all subscriptions");
+                let ids = registry.subscribers.keys().copied().collect::<Vec<_>>();
+                for id in ids {
+                    registry.close_lagging(id);
+                }
+            }
+            Err(broadcast::error::RecvError::Closed) => registry.clear(),
+        }
+        if registry.subscribers.is_empty() {
+            registry.dispatcher_running = false;
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::receipt::Receipt;
+
+    use super::*;
+
+    fn event(from_address: ContractAddress, keys: Vec<EventKey>) -> Event {
+        Event {
+            data: vec![],
+            from_address,
+            keys,
+        }
+    }
+
+    fn block(number: u64, events: Vec<Event>) -> Block {
+        Block {
+            block_number: BlockNumber::new_or_panic(number),
+            block_hash: block_hash!("0x1"),
+            transaction_receipts: vec![(
+                Receipt {
+                    transaction_hash: transaction_hash!("0x2"),
+                    ..Default::default()
+                },
+                events,
+            )],
+            ..Default::default()
+        }
+    }
+
+    const ADDRESS_A: ContractAddress = contract_address!("0xa");
+    const ADDRESS_B: ContractAddress = contract_address!("0xb");
+    const KEY_1: EventKey = event_key!("0x1");
+    const KEY_2: EventKey = event_key!("0x2");
+
+    #[test]
+    fn filter_matches() {
+        let any = EventFilter::new(None, vec![vec![], vec![]]);
+        assert!(any.matches(&event(ADDRESS_A, vec![])));
+
+        let address = EventFilter::new(Some(ADDRESS_A), vec![]);
+        assert!(address.matches(&event(ADDRESS_A, vec![KEY_1])));
+        assert!(!address.matches(&event(ADDRESS_B, vec![KEY_1])));
+
+        let second_key = EventFilter::new(None, vec![vec![], vec![KEY_2]]);
+        assert!(second_key.matches(&event(ADDRESS_B, vec![KEY_1, KEY_2])));
+        assert!(!second_key.matches(&event(ADDRESS_B, vec![KEY_2, KEY_1])));
+        assert!(!second_key.matches(&event(ADDRESS_B, vec![KEY_1])));
+    }
+
+    #[test]
+    fn index_placement_and_removal() {
+        let mut registry = Registry::default();
+        let (tx, _rx) = mpsc::channel(1);
+        let lagging = Arc::new(AtomicBool::new(false));
+
+        let address = registry.insert(
+            EventFilter::new(Some(ADDRESS_A), vec![vec![KEY_1]]),
+            tx.clone(),
+            lagging.clone(),
+        );
+        let first_key = registry.insert(
+            EventFilter::new(None, vec![vec![KEY_1, KEY_2]]),
+            tx.clone(),
+            lagging.clone(),
+        );
+        let second_key = registry.insert(
+            EventFilter::new(None, vec![vec![], vec![KEY_2]]),
+            tx,
+            lagging,
+        );
+
+        assert_eq!(registry.by_address[&ADDRESS_A], HashSet::from([address]));
+        assert_eq!(registry.by_first_key[&KEY_1], HashSet::from([first_key]));
+        assert_eq!(registry.by_first_key[&KEY_2], HashSet::from([first_key]));
+        assert_eq!(registry.unindexed, HashSet::from([second_key]));
+
+        let mut candidates = registry
+            .candidates(&event(ADDRESS_B, vec![KEY_2]))
+            .collect::<Vec<_>>();
+        candidates.sort();
+        assert_eq!(candidates, vec![first_key, second_key]);
+
+        registry.remove(address);
+        registry.remove(first_key);
+        registry.remove(second_key);
+        assert!(registry.subscribers.is_empty());
+        assert!(registry.by_address.is_empty());
+        assert!(registry.by_first_key.is_empty());
+        assert!(registry.unindexed.is_empty());
+    }
+
+    #[test]
+    fn lagging_subscriber_is_closed() {
+        let mut registry = Registry::default();
+        let (tx, mut rx) = mpsc::channel(1);
+        let lagging = Arc::new(AtomicBool::new(false));
+        registry.insert(EventFilter::default(), tx, lagging.clone());
+        let (other_tx, mut other_rx) = mpsc::channel(2);
+        let other_lagging = Arc::new(AtomicBool::new(false));
+        registry.insert(EventFilter::default(), other_tx, other_lagging.clone());
+
+        registry.dispatch(&block(0, vec![event(ADDRESS_A, vec![])]));
+        assert_eq!(registry.subscribers.len(), 2);
+
+        registry.dispatch(&block(1, vec![event(ADDRESS_A, vec![])]));
+        assert_eq!(registry.subscribers.len(), 1);
+        assert!(lagging.load(Ordering::Relaxed));
+
+        let (block_number, _) = rx.try_recv().unwrap();
+        assert_eq!(block_number, BlockNumber::GENESIS);
+        // The sender was dropped together with the subscriber.
+        assert!(rx.try_recv().is_err());
+
+        // Subscribers keeping up are not affected.
+        assert!(!other_lagging.load(Ordering::Relaxed));
+        assert_eq!(other_rx.try_recv().unwrap().0, BlockNumber::GENESIS);
+        assert_eq!(other_rx.try_recv().unwrap().0, BlockNumber::new_or_panic(1));
+    }
+
+    #[tokio::test]
+    async fn lagging_is_reported() {
+        let (blocks, _) = broadcast::channel(16);
+        let fanout = EventFanout::new(blocks.clone());
+        let mut subscription = fanout.subscribe(EventFilter::default());
+
+        let events = vec![event(ADDRESS_A, vec![]); SUBSCRIBER_BUFFER_SIZE + 1];
+        blocks.send(Arc::new(block(0, events))).unwrap();
+
+        for _ in 0..SUBSCRIBER_BUFFER_SIZE {
+            assert!(subscription.recv().await.unwrap().is_some());
+        }
+        assert_eq!(subscription.recv().await, Err(Lagging));
+    }
+
+    #[tokio::test]
+    async fn shared_dispatcher() {
+        let (blocks, _) = broadcast::channel(16);
+        let fanout = EventFanout::new(blocks.clone());
+
+        let mut a = fanout.subscribe(EventFilter::new(Some(ADDRESS_A), vec![]));
+        let mut b = fanout.subscribe(EventFilter::new(None, vec![vec![KEY_2]]));
+        // Both subscriptions share a single receiver on the block channel.
+        assert_eq!(blocks.receiver_count(), 1);
+
+        blocks
+            .send(Arc::new(block(
+                0,
+                vec![event(ADDRESS_A, vec![KEY_1]), event(ADDRESS_B, vec![KEY_2])],
+            )))
+            .unwrap();
+
+        let (_, event_a) = a.recv().await.unwrap().unwrap();
+        assert_eq!(event_a.from_address, ADDRESS_A);
+        let (_, event_b) = b.recv().await.unwrap().unwrap();
+        assert_eq!(event_b.from_address, ADDRESS_B);
+
+        drop(a);
+        drop(b);
+        assert_eq!(fanout.registry.lock().unwrap().subscribers.len(), 0);
+    }
+}
@@ -56,7 +56,7 @@ impl RpcSubscriptionFlow for SubscribeNewHeads {
     type Params = Option<Params>;
     type Notification = Notification;
 
-    fn validate_params(params: &Self::Params) -> Result<(), RpcError> {
+    fn validate_params(_context: &RpcContext, params: &Self::Params) -> Result<(), RpcError> {
         if let Some(params) = params {
             if let Some(BlockId::Pending) = params.block_id {
                 return Err(RpcError::ApplicationError(ApplicationError::CallOnPending));
@@ -79,7 +79,7 @@ impl RpcSubscriptionFlow for SubscribeNewHeads {
         to: BlockNumber,
     ) -> Result<CatchUp<Self::Notification>, RpcError> {
         let storage = state.storage.clone();
-        let headers = util::task::spawn_blocking(move |_| -> Result<_, RpcError> {
+        let headers = util::task::spawn_blocking_storage(move |_| -> Result<_, RpcError> {
             let mut conn = storage.connection().map_err(RpcError::InternalError)?;
             let db = conn.transaction().map_err(RpcError::InternalError)?;
             db.block_range(from, to).map_err(RpcError::InternalError)
@@ -542,6 +542,9 @@ mod tests {
                 pathfinder_ethereum::core_addr::MAINNET,
             ),
             sequencer: Client::mainnet(Duration::from_secs(10)),
+            gateway_breaker: Default::default(),
+            submissions: Default::default(),
+            responses: Default::default(),
             websocket: None,
             notifications,
             ethereum: EthereumClient::new("wss://eth-sepolia.g.alchemy.com/v2/just-for-tests")
@@ -551,6 +554,23 @@ mod tests {
                 get_events_max_blocks_to_scan: 1.try_into().unwrap(),
                 get_events_max_uncached_event_filters_to_load: 1.try_into().unwrap(),
                 custom_versioned_constants: None,
+                max_request_body_size: 10 * 1024 * 1024,
+                response_size_limits: Default::default(),
+                trace_cache: Default::default(),
+                gateway_circuit_breaker: Default::default(),
+                additional_fee_tokens: vec![],
+                execution_policy: Default::default(),
+                reconstruct_gateway_trace_events: false,
+                idempotency_key_ttl: Duration::from_secs(300),
+                gateway_outbox: false,
+                rejected_transaction_archive: false,
+                declare_precheck: false,
+                trace_cache_warmup: false,
+                get_proof_max_keys: 100.try_into().unwrap(),
+                event_filter_max_keys: 16.try_into().unwrap(),
+                get_events_max_chunk_size: 1024.try_into().unwrap(),
+                method_access: Default::default(),
+                response_cache_max_size: None,
             },
         };
         v08::register_routes().build(ctx)
@@ -486,6 +486,9 @@ mod tests {
                 pathfinder_ethereum::core_addr::MAINNET,
             ),
             sequencer: Client::mainnet(Duration::from_secs(10)),
+            gateway_breaker: Default::default(),
+            submissions: Default::default(),
+            responses: Default::default(),
             websocket: None,
             notifications,
             ethereum: EthereumClient::new("wss://eth-sepolia.g.alchemy.com/v2/just-for-tests")
@@ -495,6 +498,23 @@ mod tests {
                 get_events_max_blocks_to_scan: 1.try_into().unwrap(),
                 get_events_max_uncached_event_filters_to_load: 1.try_into().unwrap(),
                 custom_versioned_constants: None,
+                max_request_body_size: 10 * 1024 * 1024,
+                response_size_limits: Default::default(),
+                trace_cache: Default::default(),
+                gateway_circuit_breaker: Default::default(),
+                additional_fee_tokens: vec![],
+                execution_policy: Default::default(),
+                reconstruct_gateway_trace_events: false,
+                idempotency_key_ttl: Duration::from_secs(300),
+                gateway_outbox: false,
+                rejected_transaction_archive: false,
+                declare_precheck: false,
+                trace_cache_warmup: false,
+                get_proof_max_keys: 100.try_into().unwrap(),
+                event_filter_max_keys: 16.try_into().unwrap(),
+                get_events_max_chunk_size: 1024.try_into().unwrap(),
+                method_access: Default::default(),
+                response_cache_max_size: None,
             },
         };
         let router = v08::register_routes().build(ctx);
@@ -147,7 +147,7 @@ impl RpcSubscriptionFlow for SubscribeTransactionStatus {
                 // Check if we have the transaction in our database, and if so, send the
                 // relevant transaction status updates.
                 let (first_block, l1_state, tx_with_receipt) =
-                    util::task::spawn_blocking(move |_| -> Result<_, RpcError> {
+                    util::task::spawn_blocking_storage(move |_| -> Result<_, RpcError> {
                         let mut conn = storage.connection().map_err(RpcError::InternalError)?;
                         let db = conn.transaction().map_err(RpcError::InternalError)?;
                         let first_block = db
@@ -340,7 +340,7 @@ impl RpcSubscriptionFlow for SubscribeTransactionStatus {
                                 // here because it guarantees that the ACCEPTED_ON_L2 update will be
                                 // sent before the ACCEPTED_ON_L1 update.
                                 let storage = state.storage.clone();
-                                let l1_state = util::task::spawn_blocking(move |_| -> Result<_, RpcError> {
+                                let l1_state = util::task::spawn_blocking_storage(move |_| -> Result<_, RpcError> {
                                     let mut conn = storage.connection().map_err(RpcError::InternalError)?;
                                     let db = conn.transaction().map_err(RpcError::InternalError)?;
                                     let l1_state = db.latest_l1_state().map_err(RpcError::InternalError)?;
@@ -1165,6 +1165,9 @@ mod tests {
                 pathfinder_ethereum::core_addr::MAINNET,
             ),
             sequencer: Client::mainnet(Duration::from_secs(10)),
+            gateway_breaker: Default::default(),
+            submissions: Default::default(),
+            responses: Default::default(),
             websocket: None,
             notifications,
             ethereum: EthereumClient::new("wss://eth-sepolia.g.alchemy.com/v2/just-for-tests")
@@ -1174,6 +1177,23 @@ mod tests {
                 get_events_max_blocks_to_scan: 1.try_into().unwrap(),
                 get_events_max_uncached_event_filters_to_load: 1.try_into().unwrap(),
                 custom_versioned_constants: None,
+                max_request_body_size: 10 * 1024 * 1024,
+                response_size_limits: Default::default(),
+                trace_cache: Default::default(),
+                gateway_circuit_breaker: Default::default(),
+                additional_fee_tokens: vec![],
+                execution_policy: Default::default(),
+                reconstruct_gateway_trace_events: false,
+                idempotency_key_ttl: Duration::from_secs(300),
+                gateway_outbox: false,
+                rejected_transaction_archive: false,
+                declare_precheck: false,
+                trace_cache_warmup: false,
+                get_proof_max_keys: 100.try_into().unwrap(),
+                event_filter_max_keys: 16.try_into().unwrap(),
+                get_events_max_chunk_size: 1024.try_into().unwrap(),
+                method_access: Default::default(),
+                response_cache_max_size: None,
             },
         };
         (v08::register_routes().build(ctx), pending_data_sender)
@@ -1,15 +1,15 @@
 use anyhow::Context;
-use pathfinder_common::BlockId;
+use pathfinder_common::receipt::Receipt;
+use pathfinder_common::{BlockId, ContractAddress};
 use pathfinder_executor::types::InnerCallExecutionResources;
 use pathfinder_executor::TransactionExecutionError;
 use starknet_gateway_client::GatewayApi;
 
+use crate::circuit_breaker::GatewayCallError;
 use crate::compose_executor_transaction;
 use crate::context::RpcContext;
-use crate::executor::{
-    ExecutionStateError,
-    VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY,
-};
+use crate::executor::{can_trace_locally, ExecutionStateError};
+use crate::trace_provenance::{self, TraceProvenance};
 
 #[derive(Debug, Clone)]
 pub struct TraceBlockTransactionsInput {
@@ -27,11 +27,11 @@ impl crate::dto::DeserializeForVersion for TraceBlockTransactionsInput {
 }
 
 pub struct TraceBlockTransactionsOutput {
-    traces: Vec<(
+    pub(crate) traces: Vec<(
         pathfinder_common::TransactionHash,
         pathfinder_executor::types::TransactionTrace,
     )>,
-    include_state_diffs: bool,
+    pub(crate) include_state_diffs: bool,
 }
 
 pub async fn trace_block_transactions(
@@ -39,14 +39,18 @@ pub async fn trace_block_transactions(
     input: TraceBlockTransactionsInput,
 ) -> Result<TraceBlockTransactionsOutput, TraceBlockTransactionsError> {
     enum LocalExecution {
-        Success(TraceBlockTransactionsOutput),
-        Unsupported(Vec<pathfinder_common::transaction::Transaction>),
+        Success(TraceBlockTransactionsOutput, TraceProvenance),
+        Unsupported(
+            BlockId,
+            Vec<pathfinder_common::transaction::Transaction>,
+            Option<Vec<ReceiptWithEvents>>,
+        ),
     }
 
     let span = tracing::Span::current();
 
     let storage = context.execution_storage.clone();
-    let traces = util::task::spawn_blocking(move |_| {
+    let traces = util::task::spawn_blocking_execution(move |_| {
         let _g = span.enter();
 
         let mut db = storage.connection()?;
@@ -86,19 +90,31 @@ pub async fn trace_block_transactions(
             }
         };
 
-        if header.starknet_version
-            < VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY
-        {
+        // The feeder gateway does not support every block tag, so traces are
+        // fetched by the hash of the resolved block instead.
+        let gateway_block_id = match input.block_id {
+            BlockId::Pending => BlockId::Pending,
+            _ => BlockId::Hash(header.hash),
+        };
+
+        if !can_trace_locally(header.starknet_version) {
             match input.block_id {
                 BlockId::Pending => {
                     return Err(TraceBlockTransactionsError::Internal(anyhow::anyhow!(
                         "Traces are not supported for pending blocks by the feeder gateway"
                     )))
                 }
-                _ => {
+                other => {
+                    let receipts = receipts_for_reconstruction(
+                        &db,
+                        other,
+                        context.config.reconstruct_gateway_trace_events,
+                    )?;
                     return Ok::<_, TraceBlockTransactionsError>(LocalExecution::Unsupported(
+                        gateway_block_id,
                         transactions,
-                    ))
+                        receipts,
+                    ));
                 }
             }
         }
@@ -109,19 +125,37 @@ pub async fn trace_block_transactions(
             .collect::<Result<Vec<_>, _>>()?;
 
         let hash = header.hash;
+        let backend = pathfinder_executor::Backend::for_version(header.starknet_version)
+            .context("No execution backend for block")?;
+        let provenance = TraceProvenance::Local {
+            cached: cache.contains(&hash),
+            blockifier_version: backend.blockifier_version(),
+            versioned_constants: pathfinder_executor::versioned_constants_name(
+                &header.starknet_version,
+                context.config.custom_versioned_constants.is_some(),
+            ),
+        };
         let state = pathfinder_executor::ExecutionState::trace(
             &db,
             context.chain_id,
             header,
             None,
             context.config.custom_versioned_constants,
-            context.contract_addresses.eth_l2_token_address,
-            context.contract_addresses.strk_l2_token_address,
+            context.fee_tokens(),
         );
         let traces = match pathfinder_executor::trace(state, cache, hash, executor_transactions) {
             Ok(traces) => traces,
             Err(TransactionExecutionError::ExecutionError { .. }) => {
-                return Ok(LocalExecution::Unsupported(transactions))
+                let receipts = receipts_for_reconstruction(
+                    &db,
+                    input.block_id,
+                    context.config.reconstruct_gateway_trace_events,
+                )?;
+                return Ok(LocalExecution::Unsupported(
+                    gateway_block_id,
+                    transactions,
+                    receipts,
+                ));
             }
             Err(e) => return Err(e.into()),
         };
@@ -131,34 +165,54 @@ pub async fn trace_block_transactions(
             .map(|(hash, trace)| Ok((hash, trace)))
             .collect::<Result<Vec<_>, TraceBlockTransactionsError>>()?;
 
-        Ok(LocalExecution::Success(TraceBlockTransactionsOutput {
-            traces,
-            include_state_diffs: true,
-        }))
+        Ok(LocalExecution::Success(
+            TraceBlockTransactionsOutput {
+                traces,
+                include_state_diffs: true,
+            },
+            provenance,
+        ))
     })
     .await
     .context("trace_block_transactions: fetch block & transactions")??;
 
-    let transactions = match traces {
-        LocalExecution::Success(output) => return Ok(output),
-        LocalExecution::Unsupported(transactions) => transactions,
+    let (gateway_block_id, transactions, receipts) = match traces {
+        LocalExecution::Success(output, provenance) => {
+            trace_provenance::record(provenance);
+            return Ok(output);
+        }
+        LocalExecution::Unsupported(gateway_block_id, transactions, receipts) => {
+            (gateway_block_id, transactions, receipts)
+        }
     };
 
+    trace_provenance::record(TraceProvenance::Gateway);
+
     context
-        .sequencer
-        .block_traces(input.block_id)
+        .gateway_breaker
+        .call(context.sequencer.block_traces(gateway_block_id))
         .await
-        .context("Forwarding to feeder gateway")
-        .map_err(TraceBlockTransactionsError::from)
+        .map_err(|e| match e {
+            GatewayCallError::Unavailable => TraceBlockTransactionsError::Custom(e.into()),
+            GatewayCallError::Gateway(e) => TraceBlockTransactionsError::Internal(
+                anyhow::Error::new(e).context("Forwarding to feeder gateway"),
+            ),
+        })
         .map(|trace| {
             Ok(TraceBlockTransactionsOutput {
                 traces: trace
                     .traces
                     .into_iter()
                     .zip(transactions.into_iter())
-                    .map(|(trace, tx)| {
+                    .enumerate()
+                    .map(|(index, (trace, tx))| {
                         let transaction_hash = tx.hash;
-                        let trace_root = map_gateway_trace(tx, trace)?;
+                        let mut trace_root = map_gateway_trace(tx, trace)?;
+                        if let Some((receipt, events)) =
+                            receipts.as_ref().and_then(|receipts| receipts.get(index))
+                        {
+                            reconstruct_events_and_messages(&mut trace_root, receipt, events);
+                        }
 
                         Ok((transaction_hash, trace_root))
                     })
@@ -169,6 +223,44 @@ pub async fn trace_block_transactions(
         })?
 }
 
+pub(crate) type ReceiptWithEvents = (Receipt, Vec<pathfinder_common::event::Event>);
+
+/// Receipts of the block's transactions if events and messages missing from
+/// traces fetched from the feeder gateway should be reconstructed from them.
+///
+/// Always `None` for the pending block.
+fn receipts_for_reconstruction(
+    db: &pathfinder_storage::Transaction<'_>,
+    block_id: BlockId,
+    enabled: bool,
+) -> anyhow::Result<Option<Vec<ReceiptWithEvents>>> {
+    if !enabled {
+        return Ok(None);
+    }
+    let Ok(block_id) = pathfinder_storage::BlockId::try_from(block_id) else {
+        return Ok(None);
+    };
+
+    // Pruned events would reconstruct incomplete traces.
+    let block_number = db
+        .block_number(block_id)
+        .context("Fetching block number")?
+        .context("Block number missing")?;
+    if db.events_pruned(block_number)?.is_some() {
+        return Ok(None);
+    }
+
+    let receipts = db
+        .transaction_data_for_block(block_id)
+        .context("Fetching transaction receipts")?
+        .context("Transaction receipts missing")?
+        .into_iter()
+        .map(|(_, receipt, events)| (receipt, events))
+        .collect();
+
+    Ok(Some(receipts))
+}
+
 pub(crate) fn map_gateway_trace(
     transaction: pathfinder_common::transaction::Transaction,
     trace: starknet_gateway_types::trace::TransactionTrace,
@@ -548,6 +640,185 @@ fn map_gateway_computation_resources(
     }
 }
 
+/// Fills in the events and messages of a trace fetched from the feeder gateway
+/// from the transaction's receipt, if the gateway omitted them as it does for
+/// old blocks.
+///
+/// The receipt only names the contract which emitted an event or message, not
+/// the invocation. Each one is assigned to the next invocation of that contract
+/// in execution order, starting from the invocation the previous one was
+/// assigned to. If there is none, it is assigned to the closest invocation of
+/// the contract before that, as is the case for a parent emitting after its
+/// internal calls returned. This matches a locally executed trace unless the
+/// same contract is invoked several times in unrelated calls.
+///
+/// The trace is left untouched if the receipt names a contract which is not
+/// part of it.
+pub(crate) fn reconstruct_events_and_messages(
+    trace: &mut pathfinder_executor::types::TransactionTrace,
+    receipt: &Receipt,
+    events: &[pathfinder_common::event::Event],
+) {
+    let invocations = root_invocations(trace);
+    if invocations
+        .iter()
+        .any(|invocation| has_events_or_messages(invocation))
+    {
+        return;
+    }
+
+    let mut addresses = Vec::new();
+    for invocation in &invocations {
+        collect_contract_addresses(invocation, &mut addresses);
+    }
+
+    let Some(event_targets) =
+        assign_to_invocations(&addresses, events.iter().map(|event| event.from_address))
+    else {
+        return;
+    };
+    let Some(message_targets) = assign_to_invocations(
+        &addresses,
+        receipt
+            .l2_to_l1_messages
+            .iter()
+            .map(|message| message.from_address),
+    ) else {
+        return;
+    };
+
+    let mut events_per_invocation = std::iter::repeat_with(Vec::new)
+        .take(addresses.len())
+        .collect::<Vec<_>>();
+    for (order, (event, target)) in events.iter().zip(event_targets).enumerate() {
+        events_per_invocation[target].push(pathfinder_executor::types::Event {
+            order: order as i64,
+            data: event.data.iter().map(|data| data.0).collect(),
+            keys: event.keys.iter().map(|key| key.0).collect(),
+        });
+    }
+
+    let mut messages_per_invocation = std::iter::repeat_with(Vec::new)
+        .take(addresses.len())
+        .collect::<Vec<_>>();
+    for (order, (message, target)) in receipt
+        .l2_to_l1_messages
+        .iter()
+        .zip(message_targets)
+        .enumerate()
+    {
+        messages_per_invocation[target].push(pathfinder_executor::types::MsgToL1 {
+            order,
+            payload: message.payload.iter().map(|elem| elem.0).collect(),
+            to_address: message.to_address.0,
+            from_address: message.from_address.0,
+        });
+    }
+
+    let mut index = 0;
+    for invocation in invocations {
+        distribute_events_and_messages(
+            invocation,
+            &mut index,
+            &mut events_per_invocation,
+            &mut messages_per_invocation,
+        );
+    }
+}
+
+/// The top level invocations of the trace, in execution order.
+fn root_invocations(
+    trace: &mut pathfinder_executor::types::TransactionTrace,
+) -> Vec<&mut pathfinder_executor::types::FunctionInvocation> {
+    use pathfinder_executor::types::{ExecuteInvocation, TransactionTrace};
+
+    let invocations = match trace {
+        TransactionTrace::Declare(trace) => vec![
+            trace.validate_invocation.as_mut(),
+            trace.fee_transfer_invocation.as_mut(),
+        ],
+        // The constructor is executed before the account is validated.
+        TransactionTrace::DeployAccount(trace) => vec![
+            trace.constructor_invocation.as_mut(),
+            trace.validate_invocation.as_mut(),
+            trace.fee_transfer_invocation.as_mut(),
+        ],
+        TransactionTrace::Invoke(trace) => {
+            let execute_invocation = match &mut trace.execute_invocation {
+                ExecuteInvocation::FunctionInvocation(invocation) => invocation.as_mut(),
+                ExecuteInvocation::RevertedReason(_) => None,
+            };
+            vec![
+                trace.validate_invocation.as_mut(),
+                execute_invocation,
+                trace.fee_transfer_invocation.as_mut(),
+            ]
+        }
+        TransactionTrace::L1Handler(trace) => vec![trace.function_invocation.as_mut()],
+    };
+
+    invocations.into_iter().flatten().collect()
+}
+
+fn has_events_or_messages(invocation: &pathfinder_executor::types::FunctionInvocation) -> bool {
+    !invocation.events.is_empty()
+        || !invocation.messages.is_empty()
+        || invocation.internal_calls.iter().any(has_events_or_messages)
+}
+
+/// Collects the contract address of each invocation, parents before their
+/// internal calls.
+fn collect_contract_addresses(
+    invocation: &pathfinder_executor::types::FunctionInvocation,
+    addresses: &mut Vec<ContractAddress>,
+) {
+    addresses.push(invocation.contract_address);
+    for call in &invocation.internal_calls {
+        collect_contract_addresses(call, addresses);
+    }
+}
+
+/// Returns the index of the invocation each emitter is assigned to, or `None`
+/// if one of them was not invoked at all.
+fn assign_to_invocations(
+    addresses: &[ContractAddress],
+    emitters: impl Iterator<Item = ContractAddress>,
+) -> Option<Vec<usize>> {
+    let mut current = 0;
+    emitters
+        .map(|emitter| {
+            let index = addresses[current..]
+                .iter()
+                .position(|address| *address == emitter)
+                .map(|offset| current + offset)
+                .or_else(|| {
+                    addresses[..current]
+                        .iter()
+                        .rposition(|address| *address == emitter)
+                })?;
+            current = index;
+            Some(index)
+        })
+        .collect()
+}
+
+/// Moves the events and messages to their invocations, visiting them in the
+/// same order as [collect_contract_addresses].
+fn distribute_events_and_messages(
+    invocation: &mut pathfinder_executor::types::FunctionInvocation,
+    index: &mut usize,
+    events: &mut [Vec<pathfinder_executor::types::Event>],
+    messages: &mut [Vec<pathfinder_executor::types::MsgToL1>],
+) {
+    invocation.events = std::mem::take(&mut events[*index]);
+    invocation.messages = std::mem::take(&mut messages[*index]);
+    *index += 1;
+
+    for call in &mut invocation.internal_calls {
+        distribute_events_and_messages(call, index, events, messages);
+    }
+}
+
 impl crate::dto::SerializeForVersion for TraceBlockTransactionsOutput {
     fn serialize(
         &self,
@@ -633,6 +904,7 @@ impl From<TransactionExecutionError> for TraceBlockTransactionsError {
                 transaction_index,
                 error
             )),
+            ExecutionRefused(refused) => Self::Custom(refused.into()),
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -1109,4 +1381,115 @@ pub(crate) mod tests {
         .await
         .unwrap();
     }
+
+    #[test]
+    fn reconstruct_events_and_messages_from_receipt() {
+        use pathfinder_common::event::Event;
+        use pathfinder_common::macro_prelude::*;
+        use pathfinder_common::receipt::L2ToL1Message;
+        use pathfinder_common::{EventKey, L2ToL1MessagePayloadElem};
+        use pathfinder_executor::types::{
+            CallType,
+            EntryPointType,
+            ExecuteInvocation,
+            FunctionInvocation,
+            InvokeTransactionTrace,
+            MsgToL1,
+            TransactionTrace,
+        };
+
+        const ACCOUNT: ContractAddress = contract_address!("0xa");
+        const TOKEN: ContractAddress = contract_address!("0xb");
+        const FEE_TOKEN: ContractAddress = contract_address!("0xfee");
+
+        fn invocation(
+            contract_address: ContractAddress,
+            internal_calls: Vec<FunctionInvocation>,
+        ) -> FunctionInvocation {
+            FunctionInvocation {
+                calldata: vec![],
+                contract_address,
+                selector: Felt::ZERO,
+                call_type: CallType::Call,
+                caller_address: Felt::ZERO,
+                internal_calls,
+                class_hash: None,
+                entry_point_type: EntryPointType::External,
+                events: vec![],
+                messages: vec![],
+                result: vec![],
+                computation_resources: Default::default(),
+                execution_resources: Default::default(),
+                is_reverted: false,
+            }
+        }
+
+        fn event(from_address: ContractAddress, key: Felt) -> Event {
+            Event {
+                data: vec![],
+                from_address,
+                keys: vec![EventKey(key)],
+            }
+        }
+
+        let mut trace = TransactionTrace::Invoke(InvokeTransactionTrace {
+            validate_invocation: Some(invocation(ACCOUNT, vec![])),
+            execute_invocation: ExecuteInvocation::FunctionInvocation(Some(invocation(
+                ACCOUNT,
+                vec![invocation(TOKEN, vec![])],
+            ))),
+            fee_transfer_invocation: Some(invocation(FEE_TOKEN, vec![])),
+            state_diff: Default::default(),
+            execution_resources: Default::default(),
+        });
+        let receipt = Receipt {
+            l2_to_l1_messages: vec![L2ToL1Message {
+                from_address: TOKEN,
+                payload: vec![L2ToL1MessagePayloadElem(felt!("0x1"))],
+                to_address: contract_address!("0xe"),
+            }],
+            ..Default::default()
+        };
+        // The account emits after the token call returned.
+        let events = vec![
+            event(TOKEN, felt!("0x1")),
+            event(ACCOUNT, felt!("0x2")),
+            event(FEE_TOKEN, felt!("0x3")),
+        ];
+
+        reconstruct_events_and_messages(&mut trace, &receipt, &events);
+
+        let TransactionTrace::Invoke(trace) = trace else {
+            unreachable!()
+        };
+        let ExecuteInvocation::FunctionInvocation(Some(execute)) = trace.execute_invocation else {
+            unreachable!()
+        };
+        let keys = |invocation: &FunctionInvocation| {
+            invocation
+                .events
+                .iter()
+                .map(|event| (event.order, event.keys.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&trace.validate_invocation.unwrap()), vec![]);
+        assert_eq!(keys(&execute), vec![(1, vec![felt!("0x2")])]);
+        assert_eq!(
+            keys(&execute.internal_calls[0]),
+            vec![(0, vec![felt!("0x1")])]
+        );
+        assert_eq!(
+            execute.internal_calls[0].messages,
+            vec![MsgToL1 {
+                order: 0,
+                payload: vec![felt!("0x1")],
+                to_address: felt!("0xe"),
+                from_address: TOKEN.0,
+            }]
+        );
+        assert_eq!(
+            keys(&trace.fee_transfer_invocation.unwrap()),
+            vec![(2, vec![felt!("0x3")])]
+        );
+    }
 }
@@ -3,15 +3,18 @@ use pathfinder_common::TransactionHash;
 use pathfinder_executor::TransactionExecutionError;
 use starknet_gateway_client::GatewayApi;
 
+use crate::circuit_breaker::GatewayCallError;
 use crate::compose_executor_transaction;
 use crate::context::RpcContext;
 use crate::dto::TransactionTrace;
 use crate::error::{ApplicationError, TraceError};
-use crate::executor::{
-    ExecutionStateError,
-    VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY,
+use crate::executor::{can_trace_locally, ExecutionStateError};
+use crate::method::trace_block_transactions::{
+    map_gateway_trace,
+    reconstruct_events_and_messages,
+    ReceiptWithEvents,
 };
-use crate::method::trace_block_transactions::map_gateway_trace;
+use crate::trace_provenance::{self, TraceProvenance};
 
 #[derive(Debug)]
 pub struct Input {
@@ -40,19 +43,31 @@ impl crate::dto::SerializeForVersion for Output {
     }
 }
 
+impl Output {
+    pub fn into_trace(self) -> pathfinder_executor::types::TransactionTrace {
+        self.0.trace
+    }
+}
+
 pub async fn trace_transaction(
     context: RpcContext,
     input: Input,
 ) -> Result<Output, TraceTransactionError> {
     #[allow(clippy::large_enum_variant)]
     enum LocalExecution {
-        Success(pathfinder_executor::types::TransactionTrace),
-        Unsupported(pathfinder_common::transaction::Transaction),
+        Success(
+            pathfinder_executor::types::TransactionTrace,
+            TraceProvenance,
+        ),
+        Unsupported(
+            pathfinder_common::transaction::Transaction,
+            Option<ReceiptWithEvents>,
+        ),
     }
 
     let span = tracing::Span::current();
-    let local =
-        util::task::spawn_blocking(move |_| -> Result<LocalExecution, TraceTransactionError> {
+    let local = util::task::spawn_blocking_execution(
+        move |_| -> Result<LocalExecution, TraceTransactionError> {
             let _g = span.enter();
 
             let mut db = context
@@ -75,10 +90,8 @@ pub async fn trace_transaction(
             {
                 let header = pending.header();
 
-                if header.starknet_version
-                    < VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY
-                {
-                    return Ok(LocalExecution::Unsupported(pending_tx.clone()));
+                if !can_trace_locally(header.starknet_version) {
+                    return Ok(LocalExecution::Unsupported(pending_tx.clone(), None));
                 }
 
                 (
@@ -97,15 +110,25 @@ pub async fn trace_transaction(
                     .context("Fetching block header")?
                     .context("Block header is missing")?;
 
-                if header.starknet_version
-                    < VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY
-                {
-                    let transaction = db
-                        .transaction(input.transaction_hash)
-                        .context("Fetching transaction data")?
-                        .context("Transaction data missing")?;
-
-                    return Ok(LocalExecution::Unsupported(transaction));
+                if !can_trace_locally(header.starknet_version) {
+                    // Pruned events would reconstruct incomplete traces.
+                    let (transaction, receipt) = if context.config.reconstruct_gateway_trace_events
+                        && db.events_pruned(header.number)?.is_none()
+                    {
+                        let (transaction, receipt, events, _) = db
+                            .transaction_with_receipt(input.transaction_hash)
+                            .context("Fetching transaction data")?
+                            .context("Transaction data missing")?;
+                        (transaction, Some((receipt, events)))
+                    } else {
+                        let transaction = db
+                            .transaction(input.transaction_hash)
+                            .context("Fetching transaction data")?
+                            .context("Transaction data missing")?;
+                        (transaction, None)
+                    };
+
+                    return Ok(LocalExecution::Unsupported(transaction, receipt));
                 }
 
                 let transactions = db
@@ -120,14 +143,24 @@ pub async fn trace_transaction(
             };
 
             let hash = header.hash;
+            let block_number = header.number;
+            let backend = pathfinder_executor::Backend::for_version(header.starknet_version)
+                .context("No execution backend for block")?;
+            let provenance = TraceProvenance::Local {
+                cached: cache.contains(&hash),
+                blockifier_version: backend.blockifier_version(),
+                versioned_constants: pathfinder_executor::versioned_constants_name(
+                    &header.starknet_version,
+                    context.config.custom_versioned_constants.is_some(),
+                ),
+            };
             let state = pathfinder_executor::ExecutionState::trace(
                 &db,
                 context.chain_id,
                 header,
                 None,
                 context.config.custom_versioned_constants,
-                context.contract_addresses.eth_l2_token_address,
-                context.contract_addresses.strk_l2_token_address,
+                context.fee_tokens(),
             );
 
             let executor_transactions = transactions
@@ -152,40 +185,64 @@ pub async fn trace_transaction(
                                 input.transaction_hash
                             ))
                         })?;
-                    Ok(LocalExecution::Success(trace))
+                    Ok(LocalExecution::Success(trace, provenance))
                 }
                 Err(TransactionExecutionError::ExecutionError { .. }) => {
+                    let receipt = if context.config.reconstruct_gateway_trace_events
+                        && db.events_pruned(block_number)?.is_none()
+                    {
+                        // Not found for pending transactions.
+                        db.transaction_with_receipt(input.transaction_hash)
+                            .context("Fetching transaction receipt")?
+                            .map(|(_, receipt, events, _)| (receipt, events))
+                    } else {
+                        None
+                    };
+
                     Ok(LocalExecution::Unsupported(
                         transactions
                             .into_iter()
                             .find(|tx| tx.hash == input.transaction_hash)
                             .unwrap()
                             .clone(),
+                        receipt,
                     ))
                 }
                 Err(e) => Err(e.into()),
             }
-        })
-        .await
-        .context("trace_transaction: execution")??;
-
-    let transaction = match local {
-        LocalExecution::Success(trace) => {
+        },
+    )
+    .await
+    .context("trace_transaction: execution")??;
+
+    let (transaction, receipt) = match local {
+        LocalExecution::Success(trace, provenance) => {
+            trace_provenance::record(provenance);
             return Ok(Output(TransactionTrace {
                 trace: trace.clone(),
                 include_state_diff: false,
             }));
         }
-        LocalExecution::Unsupported(tx) => tx,
+        LocalExecution::Unsupported(tx, receipt) => (tx, receipt),
     };
 
+    trace_provenance::record(TraceProvenance::Gateway);
+
     let trace = context
-        .sequencer
-        .transaction_trace(input.transaction_hash)
+        .gateway_breaker
+        .call(context.sequencer.transaction_trace(input.transaction_hash))
         .await
-        .context("Proxying call to feeder gateway")?;
-
-    let trace = map_gateway_trace(transaction, trace)?;
+        .map_err(|e| match e {
+            GatewayCallError::Unavailable => TraceTransactionError::Custom(e.into()),
+            GatewayCallError::Gateway(e) => TraceTransactionError::Internal(
+                anyhow::Error::new(e).context("Proxying call to feeder gateway"),
+            ),
+        })?;
+
+    let mut trace = map_gateway_trace(transaction, trace)?;
+    if let Some((receipt, events)) = receipt {
+        reconstruct_events_and_messages(&mut trace, &receipt, &events);
+    }
 
     Ok(Output(TransactionTrace {
         trace: trace.clone(),
@@ -224,6 +281,7 @@ impl From<TransactionExecutionError> for TraceTransactionError {
                 transaction_index,
                 error
             )),
+            ExecutionRefused(refused) => Self::Custom(refused.into()),
             Internal(e) => Self::Internal(e),
             Custom(e) => Self::Custom(e),
         }
@@ -0,0 +1,372 @@
+//! Transactions which could not be forwarded to the gateway because of a
+//! transient failure, i.e. a timeout, connection error or 5xx response.
+//!
+//! Instead of failing the submission the transaction is persisted and the
+//! client receives its locally computed hash. A background task resubmits
+//! persisted transactions with exponential backoff until the gateway either
+//! accepts or rejects them, so that submissions are not lost on gateway
+//! hiccups.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use pathfinder_common::{ContractAddress, TransactionNonce};
+use pathfinder_storage::OutboxEntry;
+use starknet_gateway_client::GatewayApi;
+use starknet_gateway_types::error::SequencerError;
+
+use crate::context::RpcContext;
+use crate::dto::{DeserializeForVersion, SerializeForVersion};
+use crate::types::request::{
+    BroadcastedDeclareTransaction,
+    BroadcastedDeployAccountTransaction,
+    BroadcastedInvokeTransaction,
+    BroadcastedTransaction,
+};
+use crate::RpcVersion;
+
+/// How often the outbox is checked for transactions due for resubmission.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Delay before the first resubmission, doubled after every failure.
+const BASE_DELAY_SECS: u64 = 5;
+const MAX_DELAY_SECS: u64 = 600;
+
+/// Version of the format transactions are persisted in.
+const FORMAT_VERSION: RpcVersion = RpcVersion::V08;
+
+/// Serializes resubmissions so that flushing the outbox does not race the
+/// background task.
+static RESUBMISSION: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Whether a failed submission should be queued for resubmission instead of
+/// returning the error.
+pub(crate) fn should_enqueue(context: &RpcContext, error: &SequencerError) -> bool {
+    context.config.gateway_outbox && is_transient(error)
+}
+
+fn is_transient(error: &SequencerError) -> bool {
+    match error {
+        SequencerError::ReqwestError(e) => {
+            e.is_timeout()
+                || e.is_connect()
+                || e.status().is_some_and(|status| status.is_server_error())
+        }
+        SequencerError::StarknetError(_)
+        | SequencerError::InvalidStarknetErrorVariant
+        | SequencerError::UnsupportedBlockTag => false,
+    }
+}
+
+/// Delay before the next resubmission of a transaction which failed
+/// `attempts` times.
+fn backoff(attempts: u32) -> u64 {
+    BASE_DELAY_SECS
+        .checked_shl(attempts.saturating_sub(1))
+        .unwrap_or(u64::MAX)
+        .min(MAX_DELAY_SECS)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Persists a transaction whose submission failed with `error`. Returns the
+/// transaction including its locally computed hash.
+pub(crate) async fn enqueue(
+    context: &RpcContext,
+    transaction: BroadcastedTransaction,
+    token: Option<String>,
+    error: &SequencerError,
+) -> anyhow::Result<pathfinder_common::transaction::Transaction> {
+    let json = to_json(&transaction)?;
+    let transaction = transaction.into_common(context.chain_id);
+    let error = error.to_string();
+
+    let storage = context.storage.clone();
+    let hash = transaction.hash;
+    util::task::spawn_blocking_storage(move |_| {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+        let now = now();
+        db.insert_outbox_entry(hash, &json, token.as_deref(), now, now + backoff(1), &error)?;
+        db.commit().context("Committing database transaction")
+    })
+    .await
+    .context("Joining blocking task")??;
+
+    tracing::warn!(
+        transaction_hash=%hash,
+        %error,
+        "Gateway submission failed, queued for resubmission"
+    );
+    metrics::increment_counter!("rpc_gateway_outbox_enqueued_total");
+
+    Ok(transaction)
+}
+
+/// Resubmits queued transactions in the background.
+pub fn spawn(context: RpcContext) -> tokio::task::JoinHandle<()> {
+    util::task::spawn(async move {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            if let Err(error) = resubmit_queued(&context, false).await {
+                tracing::warn!(%error, "Failed to resubmit queued transactions");
+            }
+        }
+    })
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ResubmissionSummary {
+    /// Transactions accepted by the gateway.
+    pub delivered: usize,
+    /// Transactions rejected by the gateway, which are dropped from the outbox.
+    pub rejected: usize,
+    /// Transactions which failed again and remain queued.
+    pub failed: usize,
+}
+
+/// Resubmits the transactions due for resubmission, or all queued
+/// transactions if `all` is set.
+pub(crate) async fn resubmit_queued(
+    context: &RpcContext,
+    all: bool,
+) -> anyhow::Result<ResubmissionSummary> {
+    let _guard = RESUBMISSION.lock().await;
+
+    let storage = context.storage.clone();
+    let entries = util::task::spawn_blocking_storage(move |_| {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+        if all {
+            db.outbox_entries()
+        } else {
+            db.due_outbox_entries(now())
+        }
+    })
+    .await
+    .context("Joining blocking task")??;
+
+    let mut summary = ResubmissionSummary::default();
+    for entry in entries {
+        let result = resubmit(context, &entry).await;
+        let hash = entry.transaction_hash;
+        let reschedule = match result {
+            Ok(()) => {
+                tracing::info!(transaction_hash=%hash, "Resubmitted queued transaction");
+                summary.delivered += 1;
+                None
+            }
+            Err(error) if is_transient(&error) => {
+                tracing::debug!(transaction_hash=%hash, %error, "Resubmission failed");
+                summary.failed += 1;
+                Some(error.to_string())
+            }
+            Err(error) => {
+                tracing::warn!(
+                    transaction_hash=%hash,
+                    %error,
+                    "Queued transaction rejected by the gateway"
+                );
+                crate::rejected_transactions::record_json(
+                    context,
+                    hash,
+                    entry.transaction_json.clone(),
+                    &error,
+                )
+                .await;
+                summary.rejected += 1;
+                None
+            }
+        };
+
+        let storage = context.storage.clone();
+        util::task::spawn_blocking_storage(move |_| {
+            let mut db = storage
+                .connection()
+                .context("Creating database connection")?;
+            let db = db.transaction().context("Creating database transaction")?;
+            match reschedule {
+                Some(error) => db.reschedule_outbox_entry(
+                    entry.id,
+                    now() + backoff(entry.attempts + 1),
+                    &error,
+                )?,
+                None => db.delete_outbox_entry(entry.id)?,
+            }
+            db.commit().context("Committing database transaction")
+        })
+        .await
+        .context("Joining blocking task")??;
+    }
+
+    metrics::counter!(
+        "rpc_gateway_outbox_delivered_total",
+        summary.delivered as u64
+    );
+    metrics::counter!("rpc_gateway_outbox_rejected_total", summary.rejected as u64);
+
+    Ok(summary)
+}
+
+async fn resubmit(context: &RpcContext, entry: &OutboxEntry) -> Result<(), SequencerError> {
+    // Entries are written by this module, so failing to read one back is a bug
+    // and resubmitting it again would not help.
+    let transaction = match parse(&entry.transaction_json) {
+        Ok(transaction) => transaction,
+        Err(error) => {
+            tracing::error!(
+                transaction_hash=%entry.transaction_hash,
+                %error,
+                "Dropping unreadable queued transaction"
+            );
+            return Err(SequencerError::InvalidStarknetErrorVariant);
+        }
+    };
+
+    match transaction {
+        BroadcastedTransaction::Invoke(tx) => {
+            crate::method::add_invoke_transaction::add_invoke_transaction_impl(context, tx)
+                .await
+                .map(|_| ())
+        }
+        BroadcastedTransaction::DeployAccount(tx) => {
+            crate::method::add_deploy_account_transaction::add_deploy_account_transaction_impl(
+                context, tx,
+            )
+            .await
+            .map(|_| ())
+        }
+        BroadcastedTransaction::Declare(tx) => {
+            // The conversion succeeded when the transaction was first submitted.
+            let request = crate::method::add_declare_transaction::declare_request(tx)
+                .map_err(|_| SequencerError::InvalidStarknetErrorVariant)?;
+            context
+                .sequencer
+                .add_declare_transaction(request, entry.token.clone())
+                .await
+                .map(|_| ())
+        }
+    }
+}
+
+/// The nonces of the queued transactions sent by `sender_address`.
+pub(crate) fn queued_nonces(
+    entries: &[OutboxEntry],
+    sender_address: ContractAddress,
+) -> Vec<TransactionNonce> {
+    entries
+        .iter()
+        .filter_map(|entry| parse(&entry.transaction_json).ok())
+        .filter_map(|transaction| sender_and_nonce(&transaction))
+        .filter(|(sender, _)| *sender == sender_address)
+        .map(|(_, nonce)| nonce)
+        .collect()
+}
+
+/// [None] for transaction versions without a nonce.
+fn sender_and_nonce(
+    transaction: &BroadcastedTransaction,
+) -> Option<(ContractAddress, TransactionNonce)> {
+    match transaction {
+        BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V0(_)) => None,
+        BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V1(tx)) => {
+            Some((tx.sender_address, tx.nonce))
+        }
+        BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V2(tx)) => {
+            Some((tx.sender_address, tx.nonce))
+        }
+        BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V3(tx)) => {
+            Some((tx.sender_address, tx.nonce))
+        }
+        BroadcastedTransaction::DeployAccount(BroadcastedDeployAccountTransaction::V1(tx)) => {
+            Some((tx.deployed_contract_address(), tx.nonce))
+        }
+        BroadcastedTransaction::DeployAccount(BroadcastedDeployAccountTransaction::V3(tx)) => {
+            Some((tx.deployed_contract_address(), tx.nonce))
+        }
+        BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V0(_)) => None,
+        BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(tx)) => {
+            Some((tx.sender_address, tx.nonce))
+        }
+        BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V3(tx)) => {
+            Some((tx.sender_address, tx.nonce))
+        }
+    }
+}
+
+/// Serializes a transaction into the format it is persisted in. Rejected
+/// transactions are archived in the same format.
+pub(crate) fn to_json(transaction: &BroadcastedTransaction) -> anyhow::Result<Vec<u8>> {
+    let json = transaction
+        .serialize(crate::dto::Serializer::new(FORMAT_VERSION))
+        .context("Serializing transaction")?;
+    serde_json::to_vec(&json).context("Serializing transaction")
+}
+
+fn parse(json: &[u8]) -> anyhow::Result<BroadcastedTransaction> {
+    let json = serde_json::from_slice(json).context("Parsing JSON")?;
+    BroadcastedTransaction::deserialize(crate::dto::Value::new(json, FORMAT_VERSION))
+        .context("Parsing transaction")
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{Fee, TransactionVersion};
+
+    use super::*;
+    use crate::types::request::{BroadcastedInvokeTransaction, BroadcastedInvokeTransactionV1};
+
+    #[test]
+    fn backoff_doubles_up_to_limit() {
+        let delays = (1..=10).map(backoff).collect::<Vec<_>>();
+        assert_eq!(delays, [5, 10, 20, 40, 80, 160, 320, 600, 600, 600]);
+        assert_eq!(backoff(u32::MAX), MAX_DELAY_SECS);
+    }
+
+    #[tokio::test]
+    async fn enqueued_transactions_roundtrip() {
+        let context = RpcContext::for_tests();
+        let transaction = BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(
+            BroadcastedInvokeTransactionV1 {
+                version: TransactionVersion::ONE,
+                max_fee: Fee(felt!("0x123")),
+                signature: vec![transaction_signature_elem!("0x456")],
+                nonce: transaction_nonce!("0x1"),
+                sender_address: contract_address!("0xabc"),
+                calldata: vec![call_param!("0x1")],
+            },
+        ));
+
+        let queued = enqueue(
+            &context,
+            transaction.clone(),
+            None,
+            &SequencerError::InvalidStarknetErrorVariant,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            queued.hash,
+            transaction.clone().into_common(context.chain_id).hash
+        );
+
+        let mut db = context.storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+        let entries = db.outbox_entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].transaction_hash, queued.hash);
+        assert_eq!(entries[0].attempts, 1);
+        assert_eq!(parse(&entries[0].transaction_json).unwrap(), transaction);
+    }
+}
@@ -9,4 +9,35 @@ pub fn register_routes() -> RpcRouterBuilder {
         .register("pathfinder_getProof",             methods::get_proof)
         .register("pathfinder_getClassProof",        methods::get_class_proof)
         .register("pathfinder_getTransactionStatus", methods::get_transaction_status)
+        .register("pathfinder_getBlockStateCommitments", methods::get_block_state_commitments)
+        .register("pathfinder_estimateStateDiffSize", methods::estimate_state_diff_size)
+        .register("pathfinder_getReceiptProof",      methods::get_receipt_proof)
+        .register("pathfinder_getEventProof",        methods::get_event_proof)
+        .register("pathfinder_getGasPriceEstimate",  methods::get_gas_price_estimate)
+        .register("pathfinder_simulateL1Message",    methods::simulate_l1_message)
+        .register("pathfinder_estimateFeePerToken",  methods::estimate_fee_per_token)
+        .register("pathfinder_getClassStats",        methods::get_class_stats)
+        .register("pathfinder_subscribeStorageChanges", methods::SubscribeStorageChanges)
+        .register("pathfinder_getOsInput",           methods::get_os_input)
+        .register("pathfinder_suggestMaxFee",        methods::suggest_max_fee)
+        .register("pathfinder_traceBlockTransactionsRange", methods::trace_block_transactions_range)
+        .register("pathfinder_traceTransactionChrome", methods::trace_transaction_chrome)
+        .register("pathfinder_getTraceCacheBlocks",  methods::get_trace_cache_blocks)
+        .register("pathfinder_warmTraceCache",       methods::warm_trace_cache)
+        .register("pathfinder_getStorageAtHistorically", methods::get_storage_at_historically)
+        .register("pathfinder_getStorageWrites",     methods::get_storage_writes)
+        .register("pathfinder_getTransactionReceiptsByBlock", methods::get_transaction_receipts_by_block)
+        .register("pathfinder_getGatewayOutbox",     methods::get_gateway_outbox)
+        .register("pathfinder_flushGatewayOutbox",   methods::flush_gateway_outbox)
+        .register("pathfinder_getRejectedTransaction", methods::get_rejected_transaction)
+        .register("pathfinder_getLogFilter",         methods::get_log_filter)
+        .register("pathfinder_setLogFilter",         methods::set_log_filter)
+        .register("pathfinder_getNonceForSubmission", methods::get_nonce_for_submission)
+        .register("pathfinder_getNonces",            methods::get_nonces)
+        .register("pathfinder_explainFee",           methods::explain_fee)
+        .register("pathfinder_callWithProof",        methods::call_with_proof)
+        .register("pathfinder_getDecodedEvents",     methods::get_decoded_events)
+        .register("pathfinder_getBlockByTimestamp",  methods::get_block_by_timestamp)
+        .register("pathfinder_getTransactionsBySender", methods::get_transactions_by_sender)
+        .register("pathfinder_estimateFeeWithValidation", methods::estimate_fee_with_validation)
 }
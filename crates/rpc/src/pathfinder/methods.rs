@@ -1,5 +1,61 @@
+mod call_with_proof;
+mod estimate_fee_per_token;
+mod estimate_fee_with_validation;
+mod estimate_state_diff_size;
+mod explain_fee;
+mod gateway_outbox;
+mod get_block_by_timestamp;
+mod get_block_state_commitments;
+mod get_class_stats;
+mod get_decoded_events;
+mod get_event_proof;
+mod get_gas_price_estimate;
+mod get_nonce_for_submission;
+mod get_nonces;
+mod get_os_input;
 mod get_proof;
+mod get_receipt_proof;
+mod get_rejected_transaction;
+mod get_storage_at_historically;
+mod get_storage_writes;
+mod get_transaction_receipts_by_block;
 mod get_transaction_status;
+mod get_transactions_by_sender;
+mod log_filter;
+mod simulate_l1_message;
+mod subscribe_storage_changes;
+mod suggest_max_fee;
+mod trace_block_transactions_range;
+mod trace_cache;
+mod trace_transaction_chrome;
 
+pub(crate) use call_with_proof::call_with_proof;
+pub(crate) use estimate_fee_per_token::estimate_fee_per_token;
+pub(crate) use estimate_fee_with_validation::estimate_fee_with_validation;
+pub(crate) use estimate_state_diff_size::estimate_state_diff_size;
+pub(crate) use explain_fee::explain_fee;
+pub(crate) use gateway_outbox::{flush_gateway_outbox, get_gateway_outbox};
+pub(crate) use get_block_by_timestamp::get_block_by_timestamp;
+pub(crate) use get_block_state_commitments::get_block_state_commitments;
+pub(crate) use get_class_stats::get_class_stats;
+pub(crate) use get_decoded_events::get_decoded_events;
+pub(crate) use get_event_proof::get_event_proof;
+pub(crate) use get_gas_price_estimate::get_gas_price_estimate;
+pub(crate) use get_nonce_for_submission::get_nonce_for_submission;
+pub(crate) use get_nonces::get_nonces;
+pub(crate) use get_os_input::get_os_input;
 pub(crate) use get_proof::{get_class_proof, get_proof};
+pub(crate) use get_receipt_proof::get_receipt_proof;
+pub(crate) use get_rejected_transaction::get_rejected_transaction;
+pub(crate) use get_storage_at_historically::get_storage_at_historically;
+pub(crate) use get_storage_writes::get_storage_writes;
+pub(crate) use get_transaction_receipts_by_block::get_transaction_receipts_by_block;
 pub(crate) use get_transaction_status::get_transaction_status;
+pub(crate) use get_transactions_by_sender::get_transactions_by_sender;
+pub(crate) use log_filter::{get_log_filter, set_log_filter};
+pub(crate) use simulate_l1_message::simulate_l1_message;
+pub(crate) use subscribe_storage_changes::SubscribeStorageChanges;
+pub(crate) use suggest_max_fee::suggest_max_fee;
+pub(crate) use trace_block_transactions_range::trace_block_transactions_range;
+pub(crate) use trace_cache::{get_trace_cache_blocks, warm_trace_cache};
+pub(crate) use trace_transaction_chrome::trace_transaction_chrome;
@@ -0,0 +1,390 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Context};
+use pathfinder_common::{
+    BlockHash,
+    BlockId,
+    BlockNumber,
+    CallResultValue,
+    ContractAddress,
+    StorageAddress,
+    StorageValue,
+};
+use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
+
+use super::get_proof::{get_proof, GetProofError, GetProofInput, GetProofOutput};
+use crate::context::RpcContext;
+use crate::dto::SerializeForVersion;
+use crate::error::ApplicationError;
+use crate::method::call::{CallError, FunctionCall};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CallWithProofInput {
+    pub request: FunctionCall,
+    pub block_id: BlockId,
+}
+
+impl crate::dto::DeserializeForVersion for CallWithProofInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                request: value.deserialize("request")?,
+                block_id: value.deserialize("block_id")?,
+            })
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct CallWithProofOutput {
+    block_hash: BlockHash,
+    block_number: BlockNumber,
+    result: Vec<CallResultValue>,
+    contracts: Vec<ContractReads>,
+}
+
+/// The storage of a contract read by the call, along with the proofs of the
+/// contract and of each storage value.
+#[derive(Debug)]
+struct ContractReads {
+    contract_address: ContractAddress,
+    storage: Vec<(StorageAddress, StorageValue)>,
+    /// The storage proofs are in the same order as `storage`.
+    proof: GetProofOutput,
+}
+
+impl SerializeForVersion for CallWithProofOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("block_hash", &self.block_hash)?;
+        serializer.serialize_field("block_number", &self.block_number)?;
+        serializer.serialize_iter("result", self.result.len(), &mut self.result.iter())?;
+        serializer.serialize_iter(
+            "contracts",
+            self.contracts.len(),
+            &mut self.contracts.iter(),
+        )?;
+        serializer.end()
+    }
+}
+
+impl SerializeForVersion for &ContractReads {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("contract_address", &self.contract_address)?;
+        serializer.serialize_iter(
+            "storage",
+            self.storage.len(),
+            &mut self
+                .storage
+                .iter()
+                .map(|&(key, value)| StorageRead { key, value }),
+        )?;
+        serializer.serialize_field("proof", &self.proof)?;
+        serializer.end()
+    }
+}
+
+struct StorageRead {
+    key: StorageAddress,
+    value: StorageValue,
+}
+
+impl SerializeForVersion for StorageRead {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("key", &self.key)?;
+        serializer.serialize_field("value", &self.value)?;
+        serializer.end()
+    }
+}
+
+#[derive(Debug)]
+pub enum CallWithProofError {
+    Call(CallError),
+    Proof(GetProofError),
+}
+
+impl From<anyhow::Error> for CallWithProofError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Call(CallError::Internal(e))
+    }
+}
+
+impl From<CallError> for CallWithProofError {
+    fn from(e: CallError) -> Self {
+        Self::Call(e)
+    }
+}
+
+impl From<pathfinder_executor::CallError> for CallWithProofError {
+    fn from(e: pathfinder_executor::CallError) -> Self {
+        Self::Call(e.into())
+    }
+}
+
+impl From<GetProofError> for CallWithProofError {
+    fn from(e: GetProofError) -> Self {
+        Self::Proof(e)
+    }
+}
+
+impl From<CallWithProofError> for ApplicationError {
+    fn from(e: CallWithProofError) -> Self {
+        match e {
+            CallWithProofError::Call(e) => e.into(),
+            CallWithProofError::Proof(e) => e.into(),
+        }
+    }
+}
+
+/// Executes the call like `starknet_call` and returns its result along with
+/// the storage it read and Merkle proofs of those reads against the block's
+/// state commitment.
+///
+/// Every contract the call touched is included, even if none of its storage
+/// was read, so that its class hash can be verified as well.
+pub async fn call_with_proof(
+    context: RpcContext,
+    input: CallWithProofInput,
+) -> Result<CallWithProofOutput, CallWithProofError> {
+    let block_id = match input.block_id {
+        BlockId::Pending => {
+            return Err(GetProofError::Internal(anyhow!(
+                "'pending' is not currently supported by this method!"
+            ))
+            .into())
+        }
+        other => other.try_into().expect("Only pending cast should fail"),
+    };
+
+    let span = tracing::Span::current();
+    let execution_context = context.clone();
+    let (block_hash, block_number, result, reads) =
+        util::task::spawn_blocking_execution(move |_| {
+            let _g = span.enter();
+            let context = execution_context;
+
+            let mut db = context
+                .storage
+                .connection()
+                .context("Creating database connection")?;
+            let db = db.transaction().context("Creating database transaction")?;
+
+            let header = db
+                .block_header(block_id)
+                .context("Querying block header")?
+                .ok_or(CallError::BlockNotFound)?;
+            let (block_hash, block_number) = (header.hash, header.number);
+
+            let state = ExecutionState::simulation(
+                &db,
+                context.chain_id,
+                header,
+                None,
+                L1BlobDataAvailability::Disabled,
+                context.config.custom_versioned_constants,
+                context.fee_tokens(),
+            )
+            .with_execution_policy(context.config.execution_policy.clone());
+
+            let (result, reads) = pathfinder_executor::call_with_state_reads(
+                state,
+                input.request.contract_address,
+                input.request.entry_point_selector,
+                input.request.calldata,
+            )
+            .map_err(|error| crate::executor::annotate_call_error(&db, error))?;
+
+            Ok::<_, CallWithProofError>((block_hash, block_number, result, reads))
+        })
+        .await
+        .context("Executing call")??;
+
+    let mut storage = BTreeMap::<_, Vec<_>>::new();
+    for contract_address in reads
+        .class_hashes
+        .keys()
+        .chain(reads.nonces.keys())
+        .copied()
+    {
+        storage.entry(contract_address).or_default();
+    }
+    for (&(contract_address, key), &value) in &reads.storage {
+        storage
+            .entry(contract_address)
+            .or_default()
+            .push((key, value));
+    }
+
+    let mut contracts = Vec::with_capacity(storage.len());
+    for (contract_address, storage) in storage {
+        // Proving against the hash makes sure the block wasn't reorged away in the
+        // meantime.
+        let input = GetProofInput {
+            block_id: BlockId::Hash(block_hash),
+            contract_address,
+            keys: storage.iter().map(|(key, _)| *key).collect(),
+        };
+        let proof = get_proof(context.clone(), input).await?;
+
+        contracts.push(ContractReads {
+            contract_address,
+            storage,
+            proof,
+        });
+    }
+
+    Ok(CallWithProofOutput {
+        block_hash,
+        block_number,
+        result,
+        contracts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{
+        BlockHeader,
+        BlockTimestamp,
+        CallParam,
+        EntryPoint,
+        GasPrice,
+        StateUpdate,
+    };
+    use pathfinder_merkle_tree::starknet_state::update_starknet_state;
+    use serde_json::json;
+    use starknet_gateway_test_fixtures::class_definitions::{
+        CONTRACT_DEFINITION,
+        CONTRACT_DEFINITION_CLASS_HASH,
+    };
+
+    use super::*;
+    use crate::dto::Serializer;
+    use crate::RpcVersion;
+
+    async fn test_context() -> (RpcContext, ContractAddress, StorageAddress, StorageValue) {
+        let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let header = BlockHeader::builder()
+            .number(BlockNumber::GENESIS)
+            .timestamp(BlockTimestamp::new_or_panic(0))
+            .finalize_with_hash(block_hash!("0xb00"));
+        tx.insert_block_header(&header).unwrap();
+
+        // Deploy a test contract with a value in storage, updating the tries so
+        // that there is something to prove.
+        let block_number = BlockNumber::GENESIS + 1;
+        let block_hash = block_hash!("0xb01");
+        tx.insert_cairo_class(CONTRACT_DEFINITION_CLASS_HASH, CONTRACT_DEFINITION)
+            .unwrap();
+        let header = BlockHeader::builder()
+            .number(block_number)
+            .timestamp(BlockTimestamp::new_or_panic(1))
+            .eth_l1_gas_price(GasPrice(1))
+            .finalize_with_hash(block_hash);
+        tx.insert_block_header(&header).unwrap();
+
+        let contract_address = contract_address!("0xc01");
+        let key = storage_address!("0x123");
+        let value = storage_value!("0x3");
+        let state_update = StateUpdate::default()
+            .with_block_hash(block_hash)
+            .with_declared_cairo_class(CONTRACT_DEFINITION_CLASS_HASH)
+            .with_deployed_contract(contract_address, CONTRACT_DEFINITION_CLASS_HASH)
+            .with_storage_update(contract_address, key, value);
+        tx.insert_state_update(block_number, &state_update).unwrap();
+        update_starknet_state(
+            &tx,
+            (&state_update).into(),
+            false,
+            block_number,
+            storage.clone(),
+        )
+        .unwrap();
+
+        tx.commit().unwrap();
+        drop(db);
+
+        let context =
+            RpcContext::for_tests_on(pathfinder_common::Chain::Mainnet).with_storage(storage);
+
+        (context, contract_address, key, value)
+    }
+
+    #[tokio::test]
+    async fn storage_reads_are_proven() {
+        let (context, contract_address, key, value) = test_context().await;
+
+        let input = CallWithProofInput {
+            request: FunctionCall {
+                contract_address,
+                entry_point_selector: EntryPoint::hashed(b"get_value"),
+                calldata: vec![CallParam(*key.get())],
+            },
+            block_id: BlockId::Latest,
+        };
+        let output = call_with_proof(context, input).await.unwrap();
+
+        assert_eq!(output.block_number, BlockNumber::GENESIS + 1);
+        assert_eq!(output.result, vec![CallResultValue(value.0)]);
+
+        let output = output
+            .serialize(Serializer::new(RpcVersion::PathfinderV01))
+            .unwrap();
+        let contract = output["contracts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|contract| contract["contract_address"] == json!("0xc01"))
+            .unwrap();
+        assert_eq!(
+            contract["storage"],
+            json!([{"key": "0x123", "value": "0x3"}])
+        );
+        assert!(!contract["proof"]["contract_proof"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            contract["proof"]["contract_data"]["storage_proofs"]
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn pending_is_rejected() {
+        let (context, contract_address, key, _) = test_context().await;
+
+        let input = CallWithProofInput {
+            request: FunctionCall {
+                contract_address,
+                entry_point_selector: EntryPoint::hashed(b"get_value"),
+                calldata: vec![CallParam(*key.get())],
+            },
+            block_id: BlockId::Pending,
+        };
+        let error = call_with_proof(context, input).await.unwrap_err();
+
+        assert!(matches!(
+            error,
+            CallWithProofError::Proof(GetProofError::Internal(_))
+        ));
+    }
+}
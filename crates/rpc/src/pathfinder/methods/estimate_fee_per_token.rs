@@ -0,0 +1,239 @@
+use anyhow::Context;
+use pathfinder_common::{BlockId, ContractAddress};
+use pathfinder_executor::types::FeeEstimate;
+use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
+
+use crate::context::RpcContext;
+use crate::method::estimate_fee::{EstimateFeeError, Input, SimulationFlag};
+
+/// Fee estimates of each transaction, one per fee token it can pay in.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Output(Vec<Vec<TokenFeeEstimate>>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TokenFeeEstimate {
+    fee_token_address: ContractAddress,
+    fee_estimate: FeeEstimate,
+}
+
+impl crate::dto::SerializeForVersion for Output {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        serializer.serialize_iter(self.0.len(), &mut self.0.iter())
+    }
+}
+
+impl crate::dto::SerializeForVersion for &Vec<TokenFeeEstimate> {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        serializer.serialize_iter(self.len(), &mut self.iter())
+    }
+}
+
+impl crate::dto::SerializeForVersion for &TokenFeeEstimate {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("fee_token_address", &self.fee_token_address)?;
+        serializer.serialize_field("fee_estimate", &self.fee_estimate)?;
+        serializer.end()
+    }
+}
+
+/// Estimates the fees of the transactions in the chain's ETH and STRK tokens
+/// as well as in each of the additionally configured fee tokens.
+///
+/// A transaction is only estimated in the tokens matching the unit it pays
+/// fees in, i.e. STRK and other `fri` tokens for V3 transactions.
+pub async fn estimate_fee_per_token(
+    context: RpcContext,
+    input: Input,
+) -> Result<Output, EstimateFeeError> {
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_execution(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .execution_storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let (header, pending) = match input.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&db)
+                    .context("Querying pending data")?;
+
+                (pending.header(), Some(pending.state_update.clone()))
+            }
+            other => {
+                let block_id = other.try_into().expect("Only pending cast should fail");
+                let header = db
+                    .block_header(block_id)
+                    .context("Querying block header")?
+                    .ok_or(EstimateFeeError::BlockNotFound)?;
+
+                (header, None)
+            }
+        };
+
+        let skip_validate = input
+            .simulation_flags
+            .iter()
+            .any(|flag| flag == &SimulationFlag::SkipValidate);
+
+        let state = ExecutionState::simulation(
+            &db,
+            context.chain_id,
+            header,
+            pending,
+            L1BlobDataAvailability::Enabled,
+            context.config.custom_versioned_constants.clone(),
+            context.fee_tokens(),
+        )
+        .with_execution_policy(context.config.execution_policy.clone());
+
+        let transactions = input
+            .request
+            .iter()
+            .map(|tx| {
+                crate::executor::map_broadcasted_transaction(
+                    tx,
+                    context.chain_id,
+                    skip_validate,
+                    true,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let output = pathfinder_executor::estimate_per_token(state, transactions)
+            .map_err(|error| crate::executor::annotate_execution_error(&db, error))?
+            .into_iter()
+            .map(|estimates| {
+                estimates
+                    .into_iter()
+                    .map(|(token, fee_estimate)| TokenFeeEstimate {
+                        fee_token_address: token.address,
+                        fee_estimate,
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(Output(output))
+    })
+    .await
+    .context("Executing transaction")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::prelude::*;
+    use pathfinder_common::Tip;
+    use pathfinder_executor::types::PriceUnit;
+    use pathfinder_executor::FeeToken;
+
+    use super::*;
+    use crate::context::ETH_FEE_TOKEN_ADDRESS;
+    use crate::types::request::{
+        BroadcastedInvokeTransaction,
+        BroadcastedInvokeTransactionV1,
+        BroadcastedInvokeTransactionV3,
+        BroadcastedTransaction,
+    };
+    use crate::types::{DataAvailabilityMode, ResourceBounds};
+
+    const CUSTOM_TOKEN: ContractAddress = contract_address!("0x1234");
+
+    /// Calls `balanceOf` of the ETH fee token through the test account.
+    fn invoke_calldata(account_contract_address: ContractAddress) -> Vec<CallParam> {
+        vec![
+            CallParam(*ETH_FEE_TOKEN_ADDRESS.get()),
+            CallParam(EntryPoint::hashed(b"balanceOf").0),
+            call_param!("1"),
+            CallParam(*account_contract_address.get()),
+        ]
+    }
+
+    #[tokio::test]
+    async fn estimates_per_matching_token() {
+        let (mut context, last_block_header, account_contract_address, _) =
+            crate::test_setup::test_context_with_starknet_version(StarknetVersion::new(
+                0, 13, 1, 0,
+            ))
+            .await;
+        context.config.additional_fee_tokens = vec![FeeToken {
+            address: CUSTOM_TOKEN,
+            unit: PriceUnit::Wei,
+        }];
+
+        let invoke_v1 = BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(
+            BroadcastedInvokeTransactionV1 {
+                nonce: transaction_nonce!("0x0"),
+                version: TransactionVersion::ONE,
+                max_fee: Fee::default(),
+                signature: vec![],
+                sender_address: account_contract_address,
+                calldata: invoke_calldata(account_contract_address),
+            },
+        ));
+        let invoke_v3 = BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V3(
+            BroadcastedInvokeTransactionV3 {
+                version: TransactionVersion::THREE,
+                signature: vec![],
+                sender_address: account_contract_address,
+                calldata: invoke_calldata(account_contract_address),
+                nonce: transaction_nonce!("0x1"),
+                resource_bounds: ResourceBounds::default(),
+                tip: Tip(0),
+                paymaster_data: vec![],
+                account_deployment_data: vec![],
+                nonce_data_availability_mode: DataAvailabilityMode::L1,
+                fee_data_availability_mode: DataAvailabilityMode::L1,
+            },
+        ));
+
+        let input = Input {
+            request: vec![invoke_v1, invoke_v3],
+            simulation_flags: vec![SimulationFlag::SkipValidate],
+            block_id: BlockId::Number(last_block_header.number),
+        };
+        let Output(output) = estimate_fee_per_token(context.clone(), input)
+            .await
+            .unwrap();
+
+        let tokens = output
+            .iter()
+            .map(|estimates| {
+                estimates
+                    .iter()
+                    .map(|estimate| (estimate.fee_token_address, estimate.fee_estimate.unit))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                vec![
+                    (
+                        context.contract_addresses.eth_l2_token_address,
+                        PriceUnit::Wei
+                    ),
+                    (CUSTOM_TOKEN, PriceUnit::Wei),
+                ],
+                vec![(
+                    context.contract_addresses.strk_l2_token_address,
+                    PriceUnit::Fri
+                )],
+            ]
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use anyhow::Context;
+use pathfinder_common::BlockId;
+use pathfinder_executor::types::FeeEstimate;
+use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
+use primitive_types::U256;
+
+use crate::context::RpcContext;
+use crate::dto::U256Hex;
+use crate::method::estimate_fee::EstimateFeeError;
+use crate::types::request::BroadcastedTransaction;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    request: Vec<BroadcastedTransaction>,
+    block_id: BlockId,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                request: value.deserialize_array("request", BroadcastedTransaction::deserialize)?,
+                block_id: value.deserialize("block_id")?,
+            })
+        })
+    }
+}
+
+/// Fee estimates including validation, with the share of `__validate__` in
+/// each.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Output(Vec<ValidatedFeeEstimate>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ValidatedFeeEstimate {
+    /// The estimate of the transaction including `__validate__`.
+    fee_estimate: FeeEstimate,
+    /// The part of `fee_estimate` spent on `__validate__`.
+    validation: ValidationCost,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ValidationCost {
+    l1_gas_consumed: U256,
+    l1_data_gas_consumed: U256,
+    l2_gas_consumed: U256,
+    overall_fee: U256,
+}
+
+impl ValidationCost {
+    /// The difference between estimates with and without validation.
+    fn new(validated: &FeeEstimate, skip_validate: &FeeEstimate) -> Self {
+        Self {
+            l1_gas_consumed: validated
+                .l1_gas_consumed
+                .saturating_sub(skip_validate.l1_gas_consumed),
+            l1_data_gas_consumed: validated
+                .l1_data_gas_consumed
+                .saturating_sub(skip_validate.l1_data_gas_consumed),
+            l2_gas_consumed: validated
+                .l2_gas_consumed
+                .saturating_sub(skip_validate.l2_gas_consumed),
+            overall_fee: validated
+                .overall_fee
+                .saturating_sub(skip_validate.overall_fee),
+        }
+    }
+}
+
+impl crate::dto::SerializeForVersion for Output {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        serializer.serialize_iter(self.0.len(), &mut self.0.iter())
+    }
+}
+
+impl crate::dto::SerializeForVersion for &ValidatedFeeEstimate {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("fee_estimate", &self.fee_estimate)?;
+        serializer.serialize_field("validation", &self.validation)?;
+        serializer.end()
+    }
+}
+
+impl crate::dto::SerializeForVersion for &ValidationCost {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("l1_gas_consumed", &U256Hex(self.l1_gas_consumed))?;
+        serializer.serialize_field("l1_data_gas_consumed", &U256Hex(self.l1_data_gas_consumed))?;
+        serializer.serialize_field("l2_gas_consumed", &U256Hex(self.l2_gas_consumed))?;
+        serializer.serialize_field("overall_fee", &U256Hex(self.overall_fee))?;
+        serializer.end()
+    }
+}
+
+/// Estimates the fees of fully signed transactions, executing their
+/// `__validate__` entry point, and reports how much of each estimate is due to
+/// validation.
+///
+/// Transactions are executed with the version they were signed with, so that
+/// signature checks pass. The validation share is the difference to an
+/// estimate with `SKIP_VALIDATE`, which misses the validation cost of accounts
+/// such as session key or paymaster accounts.
+pub async fn estimate_fee_with_validation(
+    context: RpcContext,
+    input: Input,
+) -> Result<Output, EstimateFeeError> {
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_execution(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .execution_storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let (header, pending) = match input.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&db)
+                    .context("Querying pending data")?;
+
+                (pending.header(), Some(pending.state_update.clone()))
+            }
+            other => {
+                let block_id = other.try_into().expect("Only pending cast should fail");
+                let header = db
+                    .block_header(block_id)
+                    .context("Querying block header")?
+                    .ok_or(EstimateFeeError::BlockNotFound)?;
+
+                (header, None)
+            }
+        };
+
+        let estimate = |skip_validate: bool| -> Result<Vec<FeeEstimate>, EstimateFeeError> {
+            let state = ExecutionState::simulation(
+                &db,
+                context.chain_id,
+                header.clone(),
+                pending.clone(),
+                L1BlobDataAvailability::Enabled,
+                context.config.custom_versioned_constants.clone(),
+                context.fee_tokens(),
+            )
+            .with_execution_policy(context.config.execution_policy.clone());
+
+            let transactions = input
+                .request
+                .iter()
+                .map(|tx| {
+                    crate::executor::map_broadcasted_transaction(
+                        tx,
+                        context.chain_id,
+                        skip_validate,
+                        true,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            pathfinder_executor::estimate(state, transactions)
+                .map_err(|error| crate::executor::annotate_execution_error(&db, error).into())
+        };
+
+        let validated = estimate(false)?;
+        let skip_validate = estimate(true)?;
+
+        let output = validated
+            .into_iter()
+            .zip(skip_validate)
+            .map(|(fee_estimate, skip_validate)| ValidatedFeeEstimate {
+                validation: ValidationCost::new(&fee_estimate, &skip_validate),
+                fee_estimate,
+            })
+            .collect();
+
+        Ok(Output(output))
+    })
+    .await
+    .context("Executing transaction")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::prelude::*;
+
+    use super::*;
+    use crate::context::ETH_FEE_TOKEN_ADDRESS;
+    use crate::types::request::{BroadcastedInvokeTransaction, BroadcastedInvokeTransactionV1};
+
+    #[tokio::test]
+    async fn reports_validation_share() {
+        let (context, last_block_header, account_contract_address, _) =
+            crate::test_setup::test_context_with_starknet_version(StarknetVersion::new(
+                0, 13, 1, 0,
+            ))
+            .await;
+
+        let invoke = BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(
+            BroadcastedInvokeTransactionV1 {
+                nonce: transaction_nonce!("0x0"),
+                version: TransactionVersion::ONE,
+                max_fee: Fee::default(),
+                signature: vec![],
+                sender_address: account_contract_address,
+                calldata: vec![
+                    CallParam(*ETH_FEE_TOKEN_ADDRESS.get()),
+                    CallParam(EntryPoint::hashed(b"balanceOf").0),
+                    call_param!("1"),
+                    CallParam(*account_contract_address.get()),
+                ],
+            },
+        ));
+
+        let input = Input {
+            request: vec![invoke],
+            block_id: BlockId::Number(last_block_header.number),
+        };
+        let Output(output) = estimate_fee_with_validation(context, input).await.unwrap();
+
+        assert_eq!(output.len(), 1);
+        let estimate = &output[0];
+        assert!(estimate.fee_estimate.overall_fee > U256::zero());
+        assert!(estimate.validation.overall_fee <= estimate.fee_estimate.overall_fee);
+        assert!(estimate.validation.l1_gas_consumed <= estimate.fee_estimate.l1_gas_consumed);
+    }
+}
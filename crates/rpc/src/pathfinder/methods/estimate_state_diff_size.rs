@@ -0,0 +1,279 @@
+use std::collections::HashSet;
+
+use pathfinder_executor::types::{StateDiff, TransactionSimulation, TransactionTrace};
+
+use crate::context::RpcContext;
+use crate::method::simulate_transactions::{
+    simulate_transactions,
+    SimulateTransactionError,
+    SimulateTransactionInput,
+};
+
+/// Size of a single felt in the data availability encoding.
+const FELT_SIZE_IN_BYTES: u64 = 32;
+
+/// EIP-4844 charges one unit of blob gas per byte of blob data.
+const BLOB_GAS_PER_BYTE: u64 = 1;
+
+pub struct Output(Vec<StateDiffSize>);
+
+/// Projected data availability footprint of a single simulated transaction.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct StateDiffSize {
+    storage_updates: u64,
+    nonce_updates: u64,
+    /// Deployed contracts and replaced classes.
+    class_hash_updates: u64,
+    /// Sierra classes only, Cairo 0 declarations are not part of the
+    /// published state diff.
+    declared_classes: u64,
+    /// State diff length as used for the block's state diff commitment.
+    state_diff_length: u64,
+    /// Number of bytes of the uncompressed state diff as published to L1.
+    state_diff_size: u64,
+    blob_gas: u64,
+    /// L1 data gas charged for data availability by the executor.
+    l1_data_gas: u128,
+}
+
+impl StateDiffSize {
+    fn from_simulation(simulation: &TransactionSimulation) -> Self {
+        let (state_diff, execution_resources) = match &simulation.trace {
+            TransactionTrace::Declare(trace) => (&trace.state_diff, &trace.execution_resources),
+            TransactionTrace::DeployAccount(trace) => {
+                (&trace.state_diff, &trace.execution_resources)
+            }
+            TransactionTrace::Invoke(trace) => (&trace.state_diff, &trace.execution_resources),
+            TransactionTrace::L1Handler(trace) => (&trace.state_diff, &trace.execution_resources),
+        };
+
+        Self {
+            l1_data_gas: execution_resources.data_availability.l1_data_gas,
+            ..Self::from_state_diff(state_diff)
+        }
+    }
+
+    /// Counts the felts of the state diff in the data availability encoding:
+    ///
+    /// - the number of updated contracts, followed by each contract's address,
+    ///   a word packing its nonce, class flag and number of storage updates,
+    ///   the new class hash if it changed and a key and value per storage
+    ///   update,
+    /// - the number of declared classes, followed by the class hash and
+    ///   compiled class hash of each.
+    fn from_state_diff(state_diff: &StateDiff) -> Self {
+        let storage_updates = state_diff
+            .storage_diffs
+            .values()
+            .map(Vec::len)
+            .sum::<usize>() as u64;
+        let nonce_updates = state_diff.nonces.len() as u64;
+        let class_hash_updates =
+            (state_diff.deployed_contracts.len() + state_diff.replaced_classes.len()) as u64;
+        let declared_classes = state_diff.declared_classes.len() as u64;
+
+        let updated_contracts = state_diff
+            .storage_diffs
+            .keys()
+            .chain(state_diff.nonces.keys())
+            .chain(state_diff.deployed_contracts.iter().map(|x| &x.address))
+            .chain(
+                state_diff
+                    .replaced_classes
+                    .iter()
+                    .map(|x| &x.contract_address),
+            )
+            .collect::<HashSet<_>>()
+            .len() as u64;
+
+        let felts = 1
+            + 2 * updated_contracts
+            + class_hash_updates
+            + 2 * storage_updates
+            + 1
+            + 2 * declared_classes;
+        let state_diff_size = felts * FELT_SIZE_IN_BYTES;
+
+        Self {
+            storage_updates,
+            nonce_updates,
+            class_hash_updates,
+            declared_classes,
+            state_diff_length: storage_updates
+                + nonce_updates
+                + class_hash_updates
+                + declared_classes,
+            state_diff_size,
+            blob_gas: state_diff_size * BLOB_GAS_PER_BYTE,
+            l1_data_gas: 0,
+        }
+    }
+}
+
+/// Simulates the transactions and returns the size of the state diff each of
+/// them would publish to L1.
+pub async fn estimate_state_diff_size(
+    context: RpcContext,
+    input: SimulateTransactionInput,
+) -> Result<Output, SimulateTransactionError> {
+    let simulations = simulate_transactions(context, input).await?;
+    Ok(Output(
+        simulations
+            .simulations
+            .iter()
+            .map(StateDiffSize::from_simulation)
+            .collect(),
+    ))
+}
+
+impl crate::dto::SerializeForVersion for Output {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        serializer.serialize_iter(self.0.len(), &mut self.0.iter())
+    }
+}
+
+impl crate::dto::SerializeForVersion for &StateDiffSize {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("storage_updates", &self.storage_updates)?;
+        serializer.serialize_field("nonce_updates", &self.nonce_updates)?;
+        serializer.serialize_field("class_hash_updates", &self.class_hash_updates)?;
+        serializer.serialize_field("declared_classes", &self.declared_classes)?;
+        serializer.serialize_field("state_diff_length", &self.state_diff_length)?;
+        serializer.serialize_field("state_diff_size", &self.state_diff_size)?;
+        serializer.serialize_field("blob_gas", &self.blob_gas)?;
+        serializer.serialize_field("l1_data_gas", &crate::dto::U128Hex(self.l1_data_gas))?;
+        serializer.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{StarknetVersion, TransactionVersion};
+    use pathfinder_executor::types::{
+        DeclaredSierraClass,
+        DeployedContract,
+        ReplacedClass,
+        StorageDiff,
+    };
+
+    use super::*;
+    use crate::method::simulate_transactions::tests::setup_storage_with_starknet_version;
+    use crate::types::request::{
+        BroadcastedDeclareTransaction,
+        BroadcastedDeclareTransactionV1,
+        BroadcastedTransaction,
+    };
+
+    #[test]
+    fn encoding_size() {
+        let storage_diff = StorageDiff {
+            key: storage_address!("0x1"),
+            value: storage_value!("0x1"),
+        };
+        let state_diff = StateDiff {
+            storage_diffs: BTreeMap::from([
+                (
+                    contract_address!("0x1"),
+                    vec![storage_diff.clone(), storage_diff.clone()],
+                ),
+                (contract_address!("0x2"), vec![storage_diff]),
+            ]),
+            deployed_contracts: vec![DeployedContract {
+                address: contract_address!("0x3"),
+                class_hash: class_hash!("0x3"),
+            }],
+            deprecated_declared_classes: [class_hash!("0x4")].into(),
+            declared_classes: vec![DeclaredSierraClass {
+                class_hash: sierra_hash!("0x5"),
+                compiled_class_hash: casm_hash!("0x5"),
+            }],
+            nonces: BTreeMap::from([(contract_address!("0x1"), contract_nonce!("0x1"))]),
+            replaced_classes: vec![ReplacedClass {
+                contract_address: contract_address!("0x2"),
+                class_hash: class_hash!("0x6"),
+            }],
+        };
+
+        let size = StateDiffSize::from_state_diff(&state_diff);
+
+        // 1 + 3 contracts * 2 + 2 class hashes + 3 storage updates * 2 + 1 + 1
+        // declared class * 2.
+        let felts = 18;
+        assert_eq!(
+            size,
+            StateDiffSize {
+                storage_updates: 3,
+                nonce_updates: 1,
+                class_hash_updates: 2,
+                declared_classes: 1,
+                state_diff_length: 7,
+                state_diff_size: felts * 32,
+                blob_gas: felts * 32,
+                l1_data_gas: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn empty_state_diff() {
+        let size = StateDiffSize::from_state_diff(&StateDiff::default());
+        // Only the two length prefixes.
+        assert_eq!(size.state_diff_size, 64);
+        assert_eq!(size.state_diff_length, 0);
+    }
+
+    #[tokio::test]
+    async fn declare_cairo_v0_class() {
+        const CAIRO0_DEFINITION: &[u8] =
+            include_bytes!("../../../fixtures/contracts/cairo0_test.json");
+
+        let contract_class = crate::types::ContractClass::from_definition_bytes(CAIRO0_DEFINITION)
+            .unwrap()
+            .as_cairo()
+            .unwrap();
+
+        let (storage, last_block_header, account_contract_address, _, _) =
+            setup_storage_with_starknet_version(StarknetVersion::new(0, 13, 1, 0)).await;
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let declare = BroadcastedTransaction::Declare(BroadcastedDeclareTransaction::V1(
+            BroadcastedDeclareTransactionV1 {
+                version: TransactionVersion::ONE_WITH_QUERY_VERSION,
+                max_fee: fee!("0x10000"),
+                signature: vec![],
+                nonce: transaction_nonce!("0x0"),
+                contract_class,
+                sender_address: account_contract_address,
+            },
+        ));
+
+        let input = SimulateTransactionInput {
+            block_id: last_block_header.number.into(),
+            transactions: vec![declare],
+            simulation_flags: crate::dto::SimulationFlags(vec![]),
+            bundle: Default::default(),
+        };
+
+        let output = estimate_state_diff_size(context, input).await.unwrap();
+        let size = &output.0[0];
+
+        assert_eq!(size.nonce_updates, 1);
+        assert_eq!(size.class_hash_updates, 0);
+        // Cairo 0 classes are not published as part of the state diff.
+        assert_eq!(size.declared_classes, 0);
+        // Fee transfer.
+        assert!(size.storage_updates > 0);
+        assert_eq!(size.blob_gas, size.state_diff_size);
+        assert!(size.l1_data_gas > 0);
+    }
+}
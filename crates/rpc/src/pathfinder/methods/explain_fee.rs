@@ -0,0 +1,418 @@
+use anyhow::Context;
+use pathfinder_common::receipt::{ExecutionResources, Receipt};
+use pathfinder_common::transaction::{Transaction, TransactionVariant};
+use pathfinder_common::version_matrix::{self, GasModel};
+use pathfinder_common::{BlockHeader, Fee, GasPrice, TransactionHash, TransactionVersion};
+
+use crate::context::RpcContext;
+use crate::dto::U128Hex;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExplainFeeInput {
+    transaction_hash: TransactionHash,
+}
+
+impl crate::dto::DeserializeForVersion for ExplainFeeInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                transaction_hash: value.deserialize("transaction_hash").map(TransactionHash)?,
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(ExplainFeeError: TxnHashNotFound);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExplainFeeOutput {
+    transaction_version: TransactionVersion,
+    actual_fee: Fee,
+    versioned_constants: &'static str,
+    components: Vec<FeeComponent>,
+    computation: Computation,
+    data_availability: pathfinder_common::receipt::L1Gas,
+}
+
+/// A part of the fee, i.e. a resource consumed at a price.
+#[derive(Debug, PartialEq, Eq)]
+struct FeeComponent {
+    resource: &'static str,
+    amount: u128,
+    price: u128,
+}
+
+impl FeeComponent {
+    fn fee(&self) -> u128 {
+        self.amount.saturating_mul(self.price)
+    }
+}
+
+/// The L1 gas the VM resources used by the transaction are worth. Only the
+/// most expensive resource is charged for.
+#[derive(Debug, PartialEq, Eq)]
+struct Computation {
+    resources: Vec<ResourceUsage>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ResourceUsage {
+    resource: String,
+    count: u64,
+    l1_gas: u128,
+}
+
+impl Computation {
+    fn new(
+        resources: &ExecutionResources,
+        costs: &std::collections::BTreeMap<String, pathfinder_executor::VmResourceFeeCost>,
+    ) -> Self {
+        let builtins = &resources.builtins;
+        let usage = [
+            // Memory holes are charged as steps.
+            ("n_steps", resources.n_steps + resources.n_memory_holes),
+            ("output_builtin", builtins.output),
+            ("pedersen_builtin", builtins.pedersen),
+            ("range_check_builtin", builtins.range_check),
+            ("ecdsa_builtin", builtins.ecdsa),
+            ("bitwise_builtin", builtins.bitwise),
+            ("ec_op_builtin", builtins.ec_op),
+            ("keccak_builtin", builtins.keccak),
+            ("poseidon_builtin", builtins.poseidon),
+            ("add_mod_builtin", builtins.add_mod),
+            ("mul_mod_builtin", builtins.mul_mod),
+            ("range_check96_builtin", builtins.range_check96),
+        ];
+
+        let resources = usage
+            .into_iter()
+            .filter(|(_, count)| *count > 0)
+            .filter_map(|(resource, count)| {
+                let cost = costs.get(resource)?;
+                let l1_gas = (u128::from(count) * u128::from(cost.numerator))
+                    .div_ceil(u128::from(cost.denominator));
+                Some(ResourceUsage {
+                    resource: resource.to_owned(),
+                    count,
+                    l1_gas,
+                })
+            })
+            .collect();
+
+        Self { resources }
+    }
+
+    fn bottleneck(&self) -> Option<&ResourceUsage> {
+        self.resources.iter().max_by_key(|usage| usage.l1_gas)
+    }
+}
+
+impl ExplainFeeOutput {
+    fn new(
+        header: &BlockHeader,
+        transaction: &Transaction,
+        receipt: &Receipt,
+    ) -> anyhow::Result<Self> {
+        let transaction_version = transaction.version();
+        let (l1_gas_price, l1_data_gas_price, l2_gas_price) =
+            if transaction_version == TransactionVersion::THREE {
+                (
+                    header.strk_l1_gas_price,
+                    header.strk_l1_data_gas_price,
+                    header.strk_l2_gas_price,
+                )
+            } else {
+                (
+                    header.eth_l1_gas_price,
+                    header.eth_l1_data_gas_price,
+                    header.eth_l2_gas_price,
+                )
+            };
+
+        let resources = &receipt.execution_resources;
+        let component = |resource, amount, GasPrice(price)| FeeComponent {
+            resource,
+            amount,
+            price,
+        };
+        let mut components = vec![
+            component("L1_GAS", resources.total_gas_consumed.l1_gas, l1_gas_price),
+            component(
+                "L1_DATA_GAS",
+                resources.total_gas_consumed.l1_data_gas,
+                l1_data_gas_price,
+            ),
+            component("L2_GAS", resources.l2_gas.0, l2_gas_price),
+        ];
+        let tip = match &transaction.variant {
+            TransactionVariant::DeclareV3(tx) => tx.tip,
+            TransactionVariant::DeployAccountV3(tx) => tx.tip,
+            TransactionVariant::InvokeV3(tx) => tx.tip,
+            _ => Default::default(),
+        };
+        let charges_tip =
+            version_matrix::capabilities(header.starknet_version).gas_model >= GasModel::L2Gas;
+        if tip.0 > 0 && charges_tip {
+            components.push(FeeComponent {
+                resource: "TIP",
+                amount: resources.l2_gas.0,
+                price: tip.0.into(),
+            });
+        }
+
+        let costs = pathfinder_executor::vm_resource_fee_costs(&header.starknet_version)
+            .context("Reading VM resource fee costs")?;
+
+        Ok(Self {
+            transaction_version,
+            actual_fee: receipt.actual_fee,
+            versioned_constants: pathfinder_executor::versioned_constants_name(
+                &header.starknet_version,
+                false,
+            ),
+            components,
+            computation: Computation::new(resources, &costs),
+            data_availability: resources.data_availability.clone(),
+        })
+    }
+}
+
+/// Breaks the actual fee of an executed transaction down into the resources
+/// it paid for, along with the L1 gas its VM resources are worth according to
+/// the versioned constants of its block.
+///
+/// The sum of the components can exceed the actual fee if it was capped by
+/// the transaction's resource bounds or max fee.
+pub async fn explain_fee(
+    context: RpcContext,
+    input: ExplainFeeInput,
+) -> Result<ExplainFeeOutput, ExplainFeeError> {
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_storage(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let pending = context
+            .pending_data
+            .get(&db)
+            .context("Querying pending data")?;
+
+        if let Some((transaction, (receipt, _))) = pending
+            .block
+            .transactions
+            .iter()
+            .zip(pending.block.transaction_receipts.iter())
+            .find(|(t, _)| t.hash == input.transaction_hash)
+        {
+            return Ok(ExplainFeeOutput::new(
+                &pending.header(),
+                transaction,
+                receipt,
+            )?);
+        }
+
+        let (transaction, receipt, _, block_number) = db
+            .transaction_with_receipt(input.transaction_hash)
+            .context("Reading transaction receipt from database")?
+            .ok_or(ExplainFeeError::TxnHashNotFound)?;
+
+        let header = db
+            .block_header(block_number.into())
+            .context("Fetching block header")?
+            .context("Block header is missing")?;
+
+        Ok(ExplainFeeOutput::new(&header, &transaction, &receipt)?)
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+impl crate::dto::SerializeForVersion for ExplainFeeOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let components_total = self
+            .components
+            .iter()
+            .fold(0u128, |total, c| total.saturating_add(c.fee()));
+
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("unit", &crate::dto::PriceUnit(&self.transaction_version))?;
+        serializer.serialize_field("actual_fee", &self.actual_fee)?;
+        serializer.serialize_field("versioned_constants", &self.versioned_constants)?;
+        serializer.serialize_iter(
+            "components",
+            self.components.len(),
+            &mut self.components.iter(),
+        )?;
+        serializer.serialize_field("components_total", &U128Hex(components_total))?;
+        serializer.serialize_field("computation", &self.computation)?;
+        serializer.serialize_field(
+            "data_availability",
+            &DataAvailability(&self.data_availability),
+        )?;
+        serializer.end()
+    }
+}
+
+impl crate::dto::SerializeForVersion for &FeeComponent {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("resource", &self.resource)?;
+        serializer.serialize_field("amount", &U128Hex(self.amount))?;
+        serializer.serialize_field("price", &U128Hex(self.price))?;
+        serializer.serialize_field("fee", &U128Hex(self.fee()))?;
+        serializer.end()
+    }
+}
+
+impl crate::dto::SerializeForVersion for Computation {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let bottleneck = self.bottleneck();
+
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field(
+            "l1_gas",
+            &U128Hex(bottleneck.map_or(0, |usage| usage.l1_gas)),
+        )?;
+        serializer.serialize_optional("bottleneck", bottleneck.map(|usage| &usage.resource))?;
+        serializer.serialize_iter(
+            "resources",
+            self.resources.len(),
+            &mut self.resources.iter(),
+        )?;
+        serializer.end()
+    }
+}
+
+impl crate::dto::SerializeForVersion for &ResourceUsage {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("resource", &self.resource)?;
+        serializer.serialize_field("count", &self.count)?;
+        serializer.serialize_field("l1_gas", &U128Hex(self.l1_gas))?;
+        serializer.end()
+    }
+}
+
+struct DataAvailability<'a>(&'a pathfinder_common::receipt::L1Gas);
+
+impl crate::dto::SerializeForVersion for DataAvailability<'_> {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("l1_gas", &U128Hex(self.0.l1_gas))?;
+        serializer.serialize_field("l1_data_gas", &U128Hex(self.0.l1_data_gas))?;
+        serializer.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::receipt::{BuiltinCounters, L1Gas, L2Gas};
+    use pathfinder_common::transaction::InvokeTransactionV3;
+    use pathfinder_common::{StarknetVersion, Tip};
+    use serde_json::json;
+
+    use super::*;
+    use crate::dto::{SerializeForVersion, Serializer};
+    use crate::RpcVersion;
+
+    #[test]
+    fn breakdown() {
+        let header = BlockHeader {
+            starknet_version: StarknetVersion::new(0, 13, 4, 0),
+            strk_l1_gas_price: GasPrice(10),
+            strk_l1_data_gas_price: GasPrice(2),
+            strk_l2_gas_price: GasPrice(1),
+            ..Default::default()
+        };
+        let transaction = Transaction {
+            hash: transaction_hash!("0x1"),
+            variant: TransactionVariant::InvokeV3(InvokeTransactionV3 {
+                tip: Tip(1),
+                ..Default::default()
+            }),
+        };
+        let receipt = Receipt {
+            actual_fee: Fee(felt!("0x1000")),
+            execution_resources: ExecutionResources {
+                builtins: BuiltinCounters {
+                    pedersen: 100,
+                    ..Default::default()
+                },
+                n_steps: 990,
+                n_memory_holes: 10,
+                data_availability: L1Gas {
+                    l1_gas: 0,
+                    l1_data_gas: 128,
+                },
+                total_gas_consumed: L1Gas {
+                    l1_gas: 0,
+                    l1_data_gas: 128,
+                },
+                l2_gas: L2Gas(1000),
+            },
+            ..Default::default()
+        };
+
+        let output = ExplainFeeOutput::new(&header, &transaction, &receipt)
+            .unwrap()
+            .serialize(Serializer::new(RpcVersion::PathfinderV01))
+            .unwrap();
+
+        let component = |resource, amount: u128, price: u128| {
+            json!({
+                "resource": resource,
+                "amount": format!("{amount:#x}"),
+                "price": format!("{price:#x}"),
+                "fee": format!("{:#x}", amount * price),
+            })
+        };
+        assert_eq!(
+            output,
+            json!({
+                "unit": "FRI",
+                "actual_fee": "0x1000",
+                "versioned_constants": "latest",
+                "components": [
+                    component("L1_GAS", 0, 10),
+                    component("L1_DATA_GAS", 128, 2),
+                    component("L2_GAS", 1000, 1),
+                    component("TIP", 1000, 1),
+                ],
+                "components_total": format!("{:#x}", 128 * 2 + 1000 + 1000),
+                "computation": {
+                    // Pedersen costs 0.08 L1 gas, steps 0.0025 L1 gas.
+                    "l1_gas": "0x8",
+                    "bottleneck": "pedersen_builtin",
+                    "resources": [
+                        {"resource": "n_steps", "count": 1000, "l1_gas": "0x3"},
+                        {"resource": "pedersen_builtin", "count": 100, "l1_gas": "0x8"},
+                    ],
+                },
+                "data_availability": {
+                    "l1_gas": "0x0",
+                    "l1_data_gas": "0x80",
+                },
+            })
+        );
+    }
+}
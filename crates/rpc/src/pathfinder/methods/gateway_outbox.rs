@@ -0,0 +1,161 @@
+use anyhow::{anyhow, Context};
+use pathfinder_storage::OutboxEntry;
+
+use crate::context::RpcContext;
+use crate::outbox::ResubmissionSummary;
+
+crate::error::generate_rpc_error_subset!(GatewayOutboxError:);
+
+fn ensure_enabled(context: &RpcContext) -> Result<(), GatewayOutboxError> {
+    if context.config.gateway_outbox {
+        Ok(())
+    } else {
+        Err(GatewayOutboxError::Custom(anyhow!(
+            "The gateway outbox is disabled, see --rpc.gateway-outbox"
+        )))
+    }
+}
+
+pub struct GetGatewayOutboxOutput(Vec<OutboxEntry>);
+
+impl crate::dto::SerializeForVersion for GetGatewayOutboxOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        serializer.serialize_iter(self.0.len(), &mut self.0.iter().map(QueuedTransaction))
+    }
+}
+
+struct QueuedTransaction<'a>(&'a OutboxEntry);
+
+impl crate::dto::SerializeForVersion for QueuedTransaction<'_> {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("transaction_hash", &self.0.transaction_hash)?;
+        serializer.serialize_field("attempts", &self.0.attempts)?;
+        serializer.serialize_field("created_at", &self.0.created_at)?;
+        serializer.serialize_field("next_attempt_at", &self.0.next_attempt_at)?;
+        serializer.serialize_field("last_error", &self.0.last_error)?;
+        serializer.end()
+    }
+}
+
+/// Lists the transactions queued for resubmission to the gateway, oldest
+/// first.
+pub async fn get_gateway_outbox(
+    context: RpcContext,
+) -> Result<GetGatewayOutboxOutput, GatewayOutboxError> {
+    ensure_enabled(&context)?;
+
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_storage(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let entries = db.outbox_entries()?;
+
+        Ok(GetGatewayOutboxOutput(entries))
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+pub struct FlushGatewayOutboxOutput(ResubmissionSummary);
+
+impl crate::dto::SerializeForVersion for FlushGatewayOutboxOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("delivered", &self.0.delivered)?;
+        serializer.serialize_field("rejected", &self.0.rejected)?;
+        serializer.serialize_field("failed", &self.0.failed)?;
+        serializer.end()
+    }
+}
+
+/// Resubmits all queued transactions immediately, regardless of their backoff.
+pub async fn flush_gateway_outbox(
+    context: RpcContext,
+) -> Result<FlushGatewayOutboxOutput, GatewayOutboxError> {
+    ensure_enabled(&context)?;
+
+    let summary = crate::outbox::resubmit_queued(&context, true).await?;
+
+    Ok(FlushGatewayOutboxOutput(summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+    use crate::dto::{SerializeForVersion, Serializer};
+    use crate::RpcVersion;
+
+    fn context() -> RpcContext {
+        let mut context = RpcContext::for_tests();
+        context.config.gateway_outbox = true;
+        context
+    }
+
+    #[tokio::test]
+    async fn lists_queued_transactions() {
+        let context = context();
+        {
+            let mut db = context.storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            db.insert_outbox_entry(transaction_hash!("0x1"), b"{}", None, 10, 15, "timeout")
+                .unwrap();
+            db.commit().unwrap();
+        }
+
+        let output = get_gateway_outbox(context).await.unwrap();
+        let output = output
+            .serialize(Serializer {
+                version: RpcVersion::PathfinderV01,
+            })
+            .unwrap();
+
+        assert_eq!(
+            output,
+            serde_json::json!([{
+                "transaction_hash": "0x1",
+                "attempts": 1,
+                "created_at": 10,
+                "next_attempt_at": 15,
+                "last_error": "timeout",
+            }])
+        );
+    }
+
+    #[tokio::test]
+    async fn flushing_an_empty_outbox() {
+        let output = flush_gateway_outbox(context()).await.unwrap();
+
+        assert_eq!(output.0, ResubmissionSummary::default());
+    }
+
+    #[tokio::test]
+    async fn disabled() {
+        let context = RpcContext::for_tests();
+
+        assert!(matches!(
+            get_gateway_outbox(context.clone()).await,
+            Err(GatewayOutboxError::Custom(_))
+        ));
+        assert!(matches!(
+            flush_gateway_outbox(context).await,
+            Err(GatewayOutboxError::Custom(_))
+        ));
+    }
+}
@@ -0,0 +1,155 @@
+use anyhow::Context;
+use pathfinder_common::{BlockHash, BlockNumber, BlockTimestamp};
+
+use crate::context::RpcContext;
+
+crate::error::generate_rpc_error_subset!(GetBlockByTimestampError: BlockNotFound);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetBlockByTimestampInput {
+    timestamp: BlockTimestamp,
+    direction: Direction,
+}
+
+/// Which block to return if no block has exactly the requested timestamp.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Direction {
+    /// The latest block before the timestamp.
+    Before,
+    /// The earliest block after the timestamp.
+    After,
+}
+
+impl crate::dto::DeserializeForVersion for GetBlockByTimestampInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        use serde::de::Error;
+
+        value.deserialize_map(|value| {
+            Ok(Self {
+                timestamp: BlockTimestamp::new(value.deserialize("timestamp")?)
+                    .ok_or_else(|| serde_json::Error::custom("Invalid timestamp"))?,
+                direction: value.deserialize("direction")?,
+            })
+        })
+    }
+}
+
+impl crate::dto::DeserializeForVersion for Direction {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        let s: String = value.deserialize()?;
+        match s.as_str() {
+            "BEFORE" => Ok(Self::Before),
+            "AFTER" => Ok(Self::After),
+            _ => Err(serde::de::Error::unknown_variant(&s, &["BEFORE", "AFTER"])),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetBlockByTimestampOutput {
+    block_number: BlockNumber,
+    block_hash: BlockHash,
+    timestamp: BlockTimestamp,
+}
+
+impl crate::dto::SerializeForVersion for GetBlockByTimestampOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("block_number", &self.block_number)?;
+        serializer.serialize_field("block_hash", &self.block_hash)?;
+        serializer.serialize_field("timestamp", &self.timestamp)?;
+        serializer.end()
+    }
+}
+
+/// Returns the block with the given timestamp or, if there is none, the
+/// closest block before or after it depending on the requested direction.
+///
+/// Only stored blocks are considered, the pending block never is.
+pub async fn get_block_by_timestamp(
+    context: RpcContext,
+    input: GetBlockByTimestampInput,
+) -> Result<GetBlockByTimestampOutput, GetBlockByTimestampError> {
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_storage(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let block_number = match input.direction {
+            Direction::Before => tx.block_number_at_or_before_timestamp(input.timestamp),
+            Direction::After => tx.block_number_at_or_after_timestamp(input.timestamp),
+        }
+        .context("Searching block by timestamp")?
+        .ok_or(GetBlockByTimestampError::BlockNotFound)?;
+
+        let header = tx
+            .block_header(block_number.into())
+            .context("Querying block header")?
+            .ok_or(GetBlockByTimestampError::BlockNotFound)?;
+
+        Ok(GetBlockByTimestampOutput {
+            block_number: header.number,
+            block_hash: header.hash,
+            timestamp: header.timestamp,
+        })
+    })
+    .await
+    .context("Joining database task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn block_by_timestamp(
+        context: &RpcContext,
+        timestamp: u64,
+        direction: Direction,
+    ) -> Result<GetBlockByTimestampOutput, GetBlockByTimestampError> {
+        let input = GetBlockByTimestampInput {
+            timestamp: BlockTimestamp::new_or_panic(timestamp),
+            direction,
+        };
+        get_block_by_timestamp(context.clone(), input).await
+    }
+
+    #[tokio::test]
+    async fn closest_block_in_direction() {
+        let context = RpcContext::for_tests();
+        let headers = {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            let latest = tx
+                .block_number(pathfinder_storage::BlockId::Latest)
+                .unwrap()
+                .unwrap();
+            tx.block_range(BlockNumber::GENESIS, latest).unwrap()
+        };
+        let first = headers.first().unwrap();
+        let last = headers.last().unwrap();
+        assert!(first.timestamp < last.timestamp);
+
+        let exact = block_by_timestamp(&context, first.timestamp.get(), Direction::After)
+            .await
+            .unwrap();
+        assert_eq!(exact.block_number, first.number);
+
+        let before = block_by_timestamp(&context, last.timestamp.get() + 1, Direction::Before)
+            .await
+            .unwrap();
+        assert_eq!(before.block_number, last.number);
+        assert_eq!(before.block_hash, last.hash);
+
+        let error = block_by_timestamp(&context, last.timestamp.get() + 1, Direction::After)
+            .await
+            .unwrap_err();
+        assert_matches::assert_matches!(error, GetBlockByTimestampError::BlockNotFound);
+    }
+}
@@ -0,0 +1,178 @@
+use anyhow::{anyhow, Context};
+use pathfinder_common::prelude::*;
+use pathfinder_common::{BlockId, ReceiptCommitment, StateDiffCommitment};
+
+use crate::context::RpcContext;
+
+crate::error::generate_rpc_error_subset!(GetBlockStateCommitmentsError: BlockNotFound);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetBlockStateCommitmentsInput {
+    block_id: BlockId,
+}
+
+impl crate::dto::DeserializeForVersion for GetBlockStateCommitmentsInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_id: value.deserialize("block_id")?,
+            })
+        })
+    }
+}
+
+/// All commitments stored for a block, allowing a block's roots to be
+/// correlated without access to the database.
+#[derive(Debug, PartialEq)]
+pub struct GetBlockStateCommitmentsOutput {
+    block_number: BlockNumber,
+    block_hash: BlockHash,
+    state_commitment: StateCommitment,
+    /// Root of the contract storage trie, absent if the trie is empty or has
+    /// been pruned.
+    storage_commitment: Option<StorageCommitment>,
+    /// Root of the class trie, absent if the trie is empty or has been
+    /// pruned.
+    class_commitment: Option<ClassCommitment>,
+    transaction_commitment: TransactionCommitment,
+    event_commitment: EventCommitment,
+    receipt_commitment: ReceiptCommitment,
+    state_diff_commitment: StateDiffCommitment,
+    state_diff_length: u64,
+}
+
+impl crate::dto::SerializeForVersion for GetBlockStateCommitmentsOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("block_number", &self.block_number)?;
+        serializer.serialize_field("block_hash", &self.block_hash)?;
+        serializer.serialize_field("state_commitment", &self.state_commitment)?;
+        serializer.serialize_optional_with_null("storage_commitment", self.storage_commitment)?;
+        serializer.serialize_optional_with_null("class_commitment", self.class_commitment)?;
+        serializer.serialize_field("transaction_commitment", &self.transaction_commitment)?;
+        serializer.serialize_field("event_commitment", &self.event_commitment)?;
+        serializer.serialize_field("receipt_commitment", &self.receipt_commitment)?;
+        serializer.serialize_field("state_diff_commitment", &self.state_diff_commitment.0)?;
+        serializer.serialize_field("state_diff_length", &self.state_diff_length)?;
+        serializer.end()
+    }
+}
+
+/// Returns the commitments stored in the header of a block, together with the
+/// roots of the storage and class tries it was built from.
+pub async fn get_block_state_commitments(
+    context: RpcContext,
+    input: GetBlockStateCommitmentsInput,
+) -> Result<GetBlockStateCommitmentsOutput, GetBlockStateCommitmentsError> {
+    let block_id = match input.block_id {
+        BlockId::Pending => {
+            return Err(GetBlockStateCommitmentsError::Internal(anyhow!(
+                "'pending' is not currently supported by this method!"
+            )))
+        }
+        other => other.try_into().expect("Only pending cast should fail"),
+    };
+
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_storage(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let header = tx
+            .block_header(block_id)
+            .context("Fetching block header")?
+            .ok_or(GetBlockStateCommitmentsError::BlockNotFound)?;
+
+        let storage_commitment = tx
+            .storage_root(header.number)
+            .context("Querying storage commitment")?;
+        let class_commitment = tx
+            .class_root(header.number)
+            .context("Querying class commitment")?;
+
+        Ok(GetBlockStateCommitmentsOutput {
+            block_number: header.number,
+            block_hash: header.hash,
+            state_commitment: header.state_commitment,
+            storage_commitment,
+            class_commitment,
+            transaction_commitment: header.transaction_commitment,
+            event_commitment: header.event_commitment,
+            receipt_commitment: header.receipt_commitment,
+            state_diff_commitment: header.state_diff_commitment,
+            state_diff_length: header.state_diff_length,
+        })
+    })
+    .await
+    .context("Joining database task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn matches_header() {
+        let context = RpcContext::for_tests();
+        let (header, storage_root, class_root) = {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            let header = tx
+                .block_header(pathfinder_storage::BlockId::Latest)
+                .unwrap()
+                .unwrap();
+            let storage_root = tx.storage_root(header.number).unwrap().unwrap();
+            let class_root = tx.class_root(header.number).unwrap().unwrap();
+            (header, storage_root, class_root)
+        };
+
+        let input = GetBlockStateCommitmentsInput {
+            block_id: BlockId::Latest,
+        };
+        let output = get_block_state_commitments(context, input).await.unwrap();
+
+        assert_eq!(output.block_number, header.number);
+        assert_eq!(output.block_hash, header.hash);
+        assert_eq!(output.state_commitment, header.state_commitment);
+        assert_eq!(output.storage_commitment, Some(storage_root));
+        assert_eq!(output.storage_commitment, Some(header.storage_commitment));
+        assert_eq!(output.class_commitment, Some(class_root));
+        assert_eq!(output.transaction_commitment, header.transaction_commitment);
+        assert_eq!(output.event_commitment, header.event_commitment);
+        assert_eq!(output.receipt_commitment, header.receipt_commitment);
+        assert_eq!(output.state_diff_commitment, header.state_diff_commitment);
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+        let input = GetBlockStateCommitmentsInput {
+            block_id: BlockId::Number(BlockNumber::MAX),
+        };
+        let error = get_block_state_commitments(context, input)
+            .await
+            .unwrap_err();
+
+        assert_matches::assert_matches!(error, GetBlockStateCommitmentsError::BlockNotFound);
+    }
+
+    #[tokio::test]
+    async fn pending_is_rejected() {
+        let context = RpcContext::for_tests();
+        let input = GetBlockStateCommitmentsInput {
+            block_id: BlockId::Pending,
+        };
+        let error = get_block_state_commitments(context, input)
+            .await
+            .unwrap_err();
+
+        assert_matches::assert_matches!(error, GetBlockStateCommitmentsError::Internal(_));
+    }
+}
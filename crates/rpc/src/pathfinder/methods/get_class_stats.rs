@@ -0,0 +1,136 @@
+use anyhow::Context;
+use pathfinder_common::BlockNumber;
+use pathfinder_storage::ClassStats;
+
+use crate::context::RpcContext;
+
+crate::error::generate_rpc_error_subset!(GetClassStatsError:);
+
+/// Number of classes returned if the request does not specify a limit.
+const DEFAULT_LIMIT: u32 = 100;
+const MAX_LIMIT: u32 = 1000;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetClassStatsInput {
+    from_block: BlockNumber,
+    to_block: BlockNumber,
+    limit: Option<u32>,
+}
+
+impl crate::dto::DeserializeForVersion for GetClassStatsInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        use serde::de::Error;
+
+        value.deserialize_map(|value| {
+            Ok(Self {
+                from_block: BlockNumber::new(value.deserialize("from_block")?)
+                    .ok_or_else(|| serde_json::Error::custom("Invalid from_block"))?,
+                to_block: BlockNumber::new(value.deserialize("to_block")?)
+                    .ok_or_else(|| serde_json::Error::custom("Invalid to_block"))?,
+                limit: value.deserialize_optional("limit")?,
+            })
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetClassStatsOutput(Vec<ClassStats>);
+
+impl crate::dto::SerializeForVersion for GetClassStatsOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        serializer.serialize_iter(self.0.len(), &mut self.0.iter().map(ClassStatsDto))
+    }
+}
+
+struct ClassStatsDto<'a>(&'a ClassStats);
+
+impl crate::dto::SerializeForVersion for ClassStatsDto<'_> {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("class_hash", &self.0.class_hash)?;
+        serializer.serialize_field("invocations", &self.0.invocations)?;
+        serializer.serialize_field("l1_gas", &self.0.l1_gas)?;
+        serializer.serialize_field("l1_data_gas", &self.0.l1_data_gas)?;
+        serializer.serialize_field("l2_gas", &self.0.l2_gas)?;
+        serializer.end()
+    }
+}
+
+/// Returns the classes targeted by the most transactions in the given block
+/// range, along with the gas these transactions consumed.
+///
+/// Statistics are only collected for blocks synced with `--sync.class-stats`
+/// enabled.
+pub async fn get_class_stats(
+    context: RpcContext,
+    input: GetClassStatsInput,
+) -> Result<GetClassStatsOutput, GetClassStatsError> {
+    let limit = input.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_storage(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let stats = tx
+            .class_stats(input.from_block, input.to_block, limit as usize)
+            .context("Querying class statistics")?;
+
+        Ok(GetClassStatsOutput(stats))
+    })
+    .await
+    .context("Joining database task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn aggregates_stats_of_range() {
+        let context = RpcContext::for_tests();
+        let latest = {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            let latest = tx
+                .block_number(pathfinder_storage::BlockId::Latest)
+                .unwrap()
+                .unwrap();
+            for block in 0..=latest.get() {
+                tx.insert_class_stats(BlockNumber::new_or_panic(block))
+                    .unwrap();
+            }
+            tx.commit().unwrap();
+            latest
+        };
+
+        let input = GetClassStatsInput {
+            from_block: BlockNumber::GENESIS,
+            to_block: latest,
+            limit: None,
+        };
+        let GetClassStatsOutput(stats) = get_class_stats(context.clone(), input).await.unwrap();
+        assert!(!stats.is_empty());
+        assert!(stats
+            .windows(2)
+            .all(|pair| pair[0].invocations >= pair[1].invocations));
+
+        let input = GetClassStatsInput {
+            from_block: BlockNumber::GENESIS,
+            to_block: latest,
+            limit: Some(1),
+        };
+        let GetClassStatsOutput(top) = get_class_stats(context, input).await.unwrap();
+        assert_eq!(top, stats[..1]);
+    }
+}
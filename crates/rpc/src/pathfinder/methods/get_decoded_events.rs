@@ -0,0 +1,237 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use anyhow::Context;
+use pathfinder_common::{ClassHash, ContractAddress};
+
+use crate::context::RpcContext;
+use crate::dto::{SerializeForVersion, Serializer};
+use crate::event_abi::{DecodedEvent, EventAbi};
+use crate::method::get_events::{
+    get_events,
+    EmittedEvent,
+    GetEventsError,
+    GetEventsInput,
+    GetEventsResult,
+};
+
+#[derive(Debug)]
+pub struct GetDecodedEventsOutput {
+    events: Vec<DecodedEmittedEvent>,
+    continuation_token: Option<String>,
+}
+
+#[derive(Debug)]
+struct DecodedEmittedEvent {
+    event: EmittedEvent,
+    /// [None] if the ABI of the emitting class does not describe the event.
+    decoded: Option<DecodedEvent>,
+}
+
+impl SerializeForVersion for GetDecodedEventsOutput {
+    fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_iter("events", self.events.len(), &mut self.events.iter())?;
+        serializer.serialize_optional("continuation_token", self.continuation_token.clone())?;
+        serializer.end()
+    }
+}
+
+impl SerializeForVersion for &DecodedEmittedEvent {
+    fn serialize(&self, serializer: Serializer) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let event = &self.event;
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_iter(
+            "data",
+            event.data.len(),
+            &mut event.data.iter().map(|d| d.0),
+        )?;
+        serializer.serialize_iter(
+            "keys",
+            event.keys.len(),
+            &mut event.keys.iter().map(|k| k.0),
+        )?;
+        serializer.serialize_field("from_address", &event.from_address)?;
+        serializer.serialize_optional("block_hash", event.block_hash)?;
+        serializer.serialize_optional("block_number", event.block_number)?;
+        serializer.serialize_field("transaction_hash", &event.transaction_hash)?;
+        serializer.serialize_optional(
+            "decoded",
+            self.decoded.as_ref().map(|decoded| {
+                serde_json::json!({
+                    "name": decoded.name,
+                    "fields": decoded.fields,
+                })
+            }),
+        )?;
+        serializer.end()
+    }
+}
+
+/// Returns the events matching the filter like `starknet_getEvents`, along
+/// with their keys and data decoded using the ABI of the emitting class.
+///
+/// Events which the ABI does not describe are returned with their raw keys
+/// and data only. Pending events are decoded with the class of the emitting
+/// contract in the latest block, so events of contracts deployed in the
+/// pending block are not decoded.
+pub async fn get_decoded_events(
+    context: RpcContext,
+    input: GetEventsInput,
+) -> Result<GetDecodedEventsOutput, GetEventsError> {
+    let GetEventsResult {
+        events,
+        continuation_token,
+    } = get_events(context.clone(), input).await?;
+
+    let span = tracing::Span::current();
+    let events = util::task::spawn_blocking_storage(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Creating database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let mut class_hashes = HashMap::<(ContractAddress, _), Option<ClassHash>>::new();
+        let mut abis = HashMap::<ClassHash, Option<EventAbi>>::new();
+        let mut decoded_events = Vec::with_capacity(events.len());
+        for event in events {
+            let class_hash = match class_hashes.entry((event.from_address, event.block_number)) {
+                Entry::Occupied(entry) => *entry.get(),
+                Entry::Vacant(entry) => {
+                    let block_id = match event.block_number {
+                        Some(number) => number.into(),
+                        None => pathfinder_storage::BlockId::Latest,
+                    };
+                    let class_hash = tx
+                        .contract_class_hash(block_id, event.from_address)
+                        .context("Querying contract class hash")?;
+                    *entry.insert(class_hash)
+                }
+            };
+
+            let abi = match class_hash {
+                Some(class_hash) => match abis.entry(class_hash) {
+                    Entry::Occupied(entry) => entry.into_mut().as_ref(),
+                    Entry::Vacant(entry) => {
+                        let definition = tx
+                            .class_definition(class_hash)
+                            .context("Querying class definition")?;
+                        entry
+                            .insert(definition.and_then(|d| EventAbi::from_definition(&d)))
+                            .as_ref()
+                    }
+                },
+                None => None,
+            };
+
+            let decoded = abi.and_then(|abi| abi.decode(&event.keys, &event.data));
+            decoded_events.push(DecodedEmittedEvent { event, decoded });
+        }
+
+        Ok::<_, GetEventsError>(decoded_events)
+    })
+    .await
+    .context("Joining database task")??;
+
+    Ok(GetDecodedEventsOutput {
+        events,
+        continuation_token,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::event::Event;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::receipt::Receipt;
+    use pathfinder_common::transaction::Transaction;
+    use pathfinder_common::{BlockHeader, BlockNumber, EntryPoint, EventKey, StateUpdate};
+    use serde_json::json;
+
+    use super::*;
+    use crate::dto::DeserializeForVersion;
+    use crate::RpcVersion;
+
+    #[tokio::test]
+    async fn events_are_decoded_with_class_abi() {
+        let storage = pathfinder_storage::StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let class_hash = class_hash!("0xc1a55");
+        let contract_address = contract_address!("0xc0");
+        let definition = json!({
+            "abi": [{
+                "type": "event",
+                "name": "Transfer",
+                "keys": [],
+                "data": [{"name": "amount", "type": "felt"}]
+            }],
+            "entry_points_by_type": {},
+            "program": {}
+        });
+        tx.insert_cairo_class(class_hash, definition.to_string().as_bytes())
+            .unwrap();
+
+        let header = BlockHeader::builder()
+            .number(BlockNumber::GENESIS)
+            .finalize_with_hash(block_hash!("0xb0"));
+        tx.insert_block_header(&header).unwrap();
+        let state_update = StateUpdate::default()
+            .with_block_hash(header.hash)
+            .with_declared_cairo_class(class_hash)
+            .with_deployed_contract(contract_address, class_hash);
+        tx.insert_state_update(header.number, &state_update)
+            .unwrap();
+
+        let transaction = Transaction {
+            hash: transaction_hash!("0x1"),
+            variant: Default::default(),
+        };
+        let receipt = Receipt {
+            transaction_hash: transaction.hash,
+            ..Default::default()
+        };
+        let events = vec![
+            Event {
+                data: vec![event_data!("0x64")],
+                from_address: contract_address,
+                keys: vec![EventKey(EntryPoint::hashed(b"Transfer").0)],
+            },
+            Event {
+                data: vec![event_data!("0x64")],
+                from_address: contract_address,
+                keys: vec![event_key!("0x123")],
+            },
+        ];
+        tx.insert_transaction_data(header.number, &[(transaction, receipt)], Some(&[events]))
+            .unwrap();
+        tx.commit().unwrap();
+        drop(db);
+
+        let context = RpcContext::for_tests().with_storage(storage);
+        let input = GetEventsInput::deserialize(crate::dto::Value::new(
+            json!({"filter": {"chunk_size": 10}}),
+            RpcVersion::PathfinderV01,
+        ))
+        .unwrap();
+        let output = get_decoded_events(context, input)
+            .await
+            .unwrap()
+            .serialize(Serializer::new(RpcVersion::PathfinderV01))
+            .unwrap();
+
+        let events = output["events"].as_array().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[0]["decoded"],
+            json!({"name": "Transfer", "fields": {"amount": "0x64"}})
+        );
+        assert_eq!(events[0]["data"], json!(["0x64"]));
+        // Unknown events are returned raw.
+        assert_eq!(events[1].get("decoded"), None);
+        assert_eq!(events[1]["keys"], json!(["0x123"]));
+    }
+}
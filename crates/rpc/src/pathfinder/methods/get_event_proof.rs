@@ -0,0 +1,251 @@
+use anyhow::{anyhow, Context};
+use pathfinder_common::hash::PoseidonHash;
+use pathfinder_common::{
+    version_matrix,
+    BlockHeader,
+    EventCommitment,
+    StarknetVersion,
+    TransactionHash,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_merkle_tree::tree::TrieNodeWithHash;
+use pathfinder_merkle_tree::TransactionOrEventTree;
+
+use super::get_proof::ProofNodes;
+use crate::context::RpcContext;
+
+crate::error::generate_rpc_error_subset!(
+    GetEventProofError: TxnHashNotFound,
+    InvalidEventIndex,
+    ProofMissing
+);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetEventProofInput {
+    transaction_hash: TransactionHash,
+    /// Index of the event within the events emitted by the transaction.
+    event_index: u64,
+}
+
+impl crate::dto::DeserializeForVersion for GetEventProofInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                transaction_hash: TransactionHash(value.deserialize("transaction_hash")?),
+                event_index: value.deserialize("event_index")?,
+            })
+        })
+    }
+}
+
+/// Proof that an event is included in the event commitment of a block.
+#[derive(Debug, PartialEq)]
+pub struct GetEventProofOutput {
+    block_header: BlockHeader,
+    event_commitment: EventCommitment,
+    /// Index of the event within all events of the block, i.e. the leaf key.
+    block_event_index: u64,
+    /// The leaf value, i.e. the hash of the event.
+    event_hash: Felt,
+    /// Nodes from the event commitment down to the event hash.
+    event_proof: ProofNodes,
+}
+
+impl crate::dto::SerializeForVersion for GetEventProofOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("block_header", &self.block_header)?;
+        serializer.serialize_field("event_commitment", &self.event_commitment)?;
+        serializer.serialize_field("block_event_index", &self.block_event_index)?;
+        serializer.serialize_field("event_hash", &self.event_hash)?;
+        serializer.serialize_field("event_proof", &self.event_proof)?;
+        serializer.end()
+    }
+}
+
+/// Returns the Merkle path from one of a transaction's events to the event
+/// commitment of its block.
+///
+/// Only blocks from Starknet 0.13.2 onwards are supported. Older event hashes
+/// do not commit to the emitting transaction.
+pub async fn get_event_proof(
+    context: RpcContext,
+    input: GetEventProofInput,
+) -> Result<GetEventProofOutput, GetEventProofError> {
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_storage(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let (_, _, events, block_number) = tx
+            .transaction_with_receipt(input.transaction_hash)
+            .context("Fetching transaction")?
+            .ok_or(GetEventProofError::TxnHashNotFound)?;
+        tx.ensure_events_retained(block_number)?;
+        if input.event_index >= events.len() as u64 {
+            return Err(GetEventProofError::InvalidEventIndex);
+        }
+
+        let block_header = tx
+            .block_header(block_number.into())
+            .context("Fetching block header")?
+            .context("Block header missing")?;
+        if !version_matrix::capabilities(block_header.starknet_version)
+            .hash_scheme
+            .is_poseidon()
+        {
+            return Err(GetEventProofError::ProofMissing);
+        }
+
+        let events = tx
+            .events_for_block(block_number.into())
+            .context("Fetching block events")?
+            .context("Block events missing")?;
+
+        let preceding = events
+            .iter()
+            .take_while(|(hash, _)| *hash != input.transaction_hash)
+            .map(|(_, events)| events.len() as u64)
+            .sum::<u64>();
+        let block_event_index = preceding + input.event_index;
+
+        let event_hashes = events
+            .iter()
+            .flat_map(|(hash, events)| events.iter().map(|event| event.hash(*hash)))
+            .collect::<Vec<_>>();
+
+        let (root, proof) = event_commitment_proof(&event_hashes, block_event_index)?;
+        if root != block_header.event_commitment.0 {
+            return Err(anyhow!(
+                "Computed event commitment {root} does not match the block's {}",
+                block_header.event_commitment.0
+            )
+            .into());
+        }
+
+        Ok(GetEventProofOutput {
+            event_commitment: block_header.event_commitment,
+            block_header,
+            block_event_index,
+            event_hash: event_hashes[block_event_index as usize],
+            event_proof: ProofNodes(proof.into_iter().map(|(node, _)| node).collect()),
+        })
+    })
+    .await
+    .context("Joining database task")?
+}
+
+/// Rebuilds the event commitment tree of a block and proves the event at
+/// `index`. Returns the commitment along with the proof.
+fn event_commitment_proof(
+    event_hashes: &[Felt],
+    index: u64,
+) -> anyhow::Result<(Felt, Vec<TrieNodeWithHash>)> {
+    let mut tree = TransactionOrEventTree::<PoseidonHash>::default();
+    for (idx, hash) in event_hashes.iter().enumerate() {
+        tree.set(idx as u64, *hash)
+            .context("Building event commitment tree")?;
+    }
+    tree.commit_and_prove(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::event::Event;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_storage::fake::Config;
+    use pathfinder_storage::StorageBuilder;
+
+    use super::*;
+
+    fn event_commitment(
+        events: &[(TransactionHash, &[Event])],
+        _: StarknetVersion,
+    ) -> anyhow::Result<EventCommitment> {
+        let mut tree = TransactionOrEventTree::<PoseidonHash>::default();
+        let hashes = events
+            .iter()
+            .flat_map(|(hash, events)| events.iter().map(|event| event.hash(*hash)));
+        for (idx, hash) in hashes.enumerate() {
+            tree.set(idx as u64, hash)?;
+        }
+        tree.commit().map(EventCommitment)
+    }
+
+    #[tokio::test]
+    async fn proof_leads_to_event_hash() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let blocks = pathfinder_storage::fake::generate::with_config(
+            1,
+            Config {
+                calculate_event_commitment: Box::new(event_commitment),
+                ..Default::default()
+            },
+        );
+        pathfinder_storage::fake::fill(&storage, &blocks, None);
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let (transaction, _, events) = blocks[0].transaction_data.last().unwrap();
+        let input = GetEventProofInput {
+            transaction_hash: transaction.hash,
+            event_index: events.len() as u64 - 1,
+        };
+
+        let output = get_event_proof(context, input).await.unwrap();
+        let total_events = blocks[0]
+            .transaction_data
+            .iter()
+            .map(|(_, _, events)| events.len() as u64)
+            .sum::<u64>();
+        assert_eq!(output.block_header, blocks[0].header.header);
+        assert_eq!(output.block_event_index, total_events - 1);
+        assert_eq!(
+            output.event_hash,
+            events.last().unwrap().hash(transaction.hash)
+        );
+
+        // Follow the proof from the commitment to the event hash.
+        assert!(TransactionOrEventTree::<PoseidonHash>::verify_proof(
+            output.event_commitment.0,
+            output.block_event_index,
+            &output.event_proof.0,
+            output.event_hash
+        ));
+    }
+
+    #[tokio::test]
+    async fn invalid_event_index() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let blocks = pathfinder_storage::fake::generate::n_blocks(1);
+        pathfinder_storage::fake::fill(&storage, &blocks, None);
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let (transaction, _, events) = &blocks[0].transaction_data[0];
+        let input = GetEventProofInput {
+            transaction_hash: transaction.hash,
+            event_index: events.len() as u64,
+        };
+
+        let error = get_event_proof(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(error, GetEventProofError::InvalidEventIndex);
+    }
+
+    #[tokio::test]
+    async fn unknown_transaction() {
+        let context = RpcContext::for_tests();
+        let input = GetEventProofInput {
+            transaction_hash: transaction_hash!("0xdeadbeef"),
+            event_index: 0,
+        };
+
+        let error = get_event_proof(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(error, GetEventProofError::TxnHashNotFound);
+    }
+}
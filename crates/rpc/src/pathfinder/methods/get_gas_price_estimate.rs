@@ -0,0 +1,257 @@
+use anyhow::Context;
+use pathfinder_common::{BlockHeader, BlockNumber, GasPrice};
+
+use crate::context::RpcContext;
+use crate::dto::ResourcePrice;
+
+crate::error::generate_rpc_error_subset!(GetGasPriceEstimateError: NoBlocks);
+
+/// Number of most recent blocks the estimate is derived from, the pending
+/// block not included.
+const WINDOW: u64 = 20;
+
+/// Suggested gas prices derived from the most recent blocks.
+#[derive(Debug, PartialEq)]
+pub struct GetGasPriceEstimateOutput {
+    /// The latest block taken into account, the pending block excluded.
+    latest_block: BlockNumber,
+    pub(super) l1_gas: PriceEstimate,
+    pub(super) l1_data_gas: PriceEstimate,
+    pub(super) l2_gas: PriceEstimate,
+}
+
+/// Estimate for the price of a single resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct PriceEstimate {
+    /// Exponential moving average of the observed prices.
+    pub(super) suggested: ResourcePrice,
+    /// Lowest observed price.
+    min: ResourcePrice,
+    /// Highest observed price.
+    max: ResourcePrice,
+}
+
+impl crate::dto::SerializeForVersion for GetGasPriceEstimateOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("latest_block", &self.latest_block)?;
+        serializer.serialize_field("l1_gas", &self.l1_gas)?;
+        serializer.serialize_field("l1_data_gas", &self.l1_data_gas)?;
+        serializer.serialize_field("l2_gas", &self.l2_gas)?;
+        serializer.end()
+    }
+}
+
+impl crate::dto::SerializeForVersion for PriceEstimate {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("suggested", &self.suggested)?;
+        serializer.serialize_field("min", &self.min)?;
+        serializer.serialize_field("max", &self.max)?;
+        serializer.end()
+    }
+}
+
+impl PriceEstimate {
+    /// Computes the estimate from prices ordered from oldest to newest.
+    ///
+    /// Returns `None` if there are no prices.
+    fn from_prices(prices: impl Iterator<Item = ResourcePrice>) -> Option<Self> {
+        prices.fold(None, |estimate, price| {
+            let Some(estimate) = estimate else {
+                return Some(Self {
+                    suggested: price,
+                    min: price,
+                    max: price,
+                });
+            };
+
+            Some(Self {
+                suggested: ResourcePrice {
+                    price_in_wei: ema(estimate.suggested.price_in_wei, price.price_in_wei),
+                    price_in_fri: ema(estimate.suggested.price_in_fri, price.price_in_fri),
+                },
+                min: ResourcePrice {
+                    price_in_wei: GasPrice(estimate.min.price_in_wei.0.min(price.price_in_wei.0)),
+                    price_in_fri: GasPrice(estimate.min.price_in_fri.0.min(price.price_in_fri.0)),
+                },
+                max: ResourcePrice {
+                    price_in_wei: GasPrice(estimate.max.price_in_wei.0.max(price.price_in_wei.0)),
+                    price_in_fri: GasPrice(estimate.max.price_in_fri.0.max(price.price_in_fri.0)),
+                },
+            })
+        })
+    }
+}
+
+/// Adds `price` to the exponential moving average with a smoothing factor of
+/// `2 / (WINDOW + 1)`.
+fn ema(average: GasPrice, price: GasPrice) -> GasPrice {
+    let window = u128::from(WINDOW);
+    GasPrice((2 * price.0 + (window - 1) * average.0) / (window + 1))
+}
+
+/// Returns suggested L1 gas, L1 data gas and L2 gas prices along with the
+/// range observed over the most recent blocks and the pending block.
+///
+/// Intended for wallets and tooling which would otherwise need an external
+/// gas price oracle.
+pub async fn get_gas_price_estimate(
+    context: RpcContext,
+) -> Result<GetGasPriceEstimateOutput, GetGasPriceEstimateError> {
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_storage(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let pending = context
+            .pending_data
+            .get(&tx)
+            .context("Fetching pending data")?;
+
+        estimate_gas_prices(&tx, pending.header())
+    })
+    .await
+    .context("Joining database task")?
+}
+
+/// Derives the gas price estimate from the most recent blocks and the given
+/// pending block header.
+pub(super) fn estimate_gas_prices(
+    tx: &pathfinder_storage::Transaction<'_>,
+    pending: BlockHeader,
+) -> Result<GetGasPriceEstimateOutput, GetGasPriceEstimateError> {
+    let latest_block = tx
+        .block_id(pathfinder_storage::BlockId::Latest)
+        .context("Fetching latest block")?
+        .ok_or(GetGasPriceEstimateError::NoBlocks)?
+        .0;
+
+    let first_block = latest_block.get().saturating_sub(WINDOW - 1);
+    let mut headers = (first_block..=latest_block.get())
+        .map(|number| {
+            tx.block_header(BlockNumber::new_or_panic(number).into())
+                .context("Fetching block header")?
+                .context("Block header missing")
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    headers.push(pending);
+
+    let estimate = |price: fn(&BlockHeader) -> ResourcePrice| {
+        PriceEstimate::from_prices(headers.iter().map(price)).expect("There is at least one header")
+    };
+
+    Ok(GetGasPriceEstimateOutput {
+        latest_block,
+        l1_gas: estimate(|header| ResourcePrice {
+            price_in_wei: header.eth_l1_gas_price,
+            price_in_fri: header.strk_l1_gas_price,
+        }),
+        l1_data_gas: estimate(|header| ResourcePrice {
+            price_in_wei: header.eth_l1_data_gas_price,
+            price_in_fri: header.strk_l1_data_gas_price,
+        }),
+        l2_gas: estimate(|header| ResourcePrice {
+            price_in_wei: header.eth_l2_gas_price,
+            price_in_fri: header.strk_l2_gas_price,
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::BlockHash;
+    use pathfinder_crypto::Felt;
+    use pathfinder_storage::{Storage, StorageBuilder};
+
+    use super::*;
+
+    /// Creates a chain whose L1 gas prices are the given ones.
+    fn storage_with_l1_gas_prices(prices: &[u128]) -> Storage {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let mut parent: Option<BlockHeader> = None;
+        for (i, &price) in prices.iter().enumerate() {
+            let builder = match &parent {
+                Some(parent) => parent.child_builder(),
+                None => BlockHeader::builder(),
+            };
+            let header = builder
+                .eth_l1_gas_price(GasPrice(price))
+                .strk_l1_gas_price(GasPrice(price * 10))
+                .finalize_with_hash(BlockHash(Felt::from_u64(i as u64 + 1)));
+            tx.insert_block_header(&header).unwrap();
+            parent = Some(header);
+        }
+
+        tx.commit().unwrap();
+        storage
+    }
+
+    #[tokio::test]
+    async fn constant_prices() {
+        let storage = storage_with_l1_gas_prices(&[100, 100, 100]);
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let output = get_gas_price_estimate(context).await.unwrap();
+        let expected = ResourcePrice {
+            price_in_wei: GasPrice(100),
+            price_in_fri: GasPrice(1000),
+        };
+        assert_eq!(output.latest_block, BlockNumber::new_or_panic(2));
+        assert_eq!(
+            output.l1_gas,
+            PriceEstimate {
+                suggested: expected,
+                min: expected,
+                max: expected,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn rising_prices() {
+        let storage = storage_with_l1_gas_prices(&[100, 200, 300, 400]);
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let output = get_gas_price_estimate(context).await.unwrap();
+        assert_eq!(output.l1_gas.min.price_in_wei, GasPrice(100));
+        assert_eq!(output.l1_gas.max.price_in_wei, GasPrice(400));
+        // The average lags behind the latest price.
+        assert!(output.l1_gas.suggested.price_in_wei.0 > 100);
+        assert!(output.l1_gas.suggested.price_in_wei.0 < 400);
+    }
+
+    #[tokio::test]
+    async fn only_recent_blocks_are_considered() {
+        let mut prices = vec![1];
+        prices.extend([100; WINDOW as usize]);
+        let storage = storage_with_l1_gas_prices(&prices);
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let output = get_gas_price_estimate(context).await.unwrap();
+        assert_eq!(output.l1_gas.min.price_in_wei, GasPrice(100));
+    }
+
+    #[tokio::test]
+    async fn no_blocks() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let error = get_gas_price_estimate(context).await.unwrap_err();
+        assert_matches::assert_matches!(error, GetGasPriceEstimateError::NoBlocks);
+    }
+}
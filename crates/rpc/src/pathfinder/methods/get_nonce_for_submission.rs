@@ -0,0 +1,164 @@
+use anyhow::Context;
+use pathfinder_common::{ContractAddress, ContractNonce};
+use pathfinder_crypto::Felt;
+
+use crate::context::RpcContext;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Input {
+    contract_address: ContractAddress,
+}
+
+impl crate::dto::DeserializeForVersion for Input {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                contract_address: value.deserialize("contract_address").map(ContractAddress)?,
+            })
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Output(ContractNonce);
+
+crate::error::generate_rpc_error_subset!(Error: ContractNotFound);
+
+/// Returns the nonce the next transaction sent by a contract should use.
+///
+/// Unlike `starknet_getNonce` this accounts for the pending block as well as
+/// for transactions queued in the gateway outbox, which are not yet visible
+/// to the gateway.
+pub async fn get_nonce_for_submission(context: RpcContext, input: Input) -> Result<Output, Error> {
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_storage(move |_| -> Result<_, Error> {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let pending = context
+            .pending_data
+            .get(&tx)
+            .context("Querying pending data")?;
+
+        let nonce = match pending.state_update.contract_nonce(input.contract_address) {
+            Some(nonce) => Some(nonce),
+            None => tx
+                .contract_nonce(input.contract_address, pathfinder_storage::BlockId::Latest)
+                .context("Querying contract nonce from database")?,
+        };
+        let nonce = match nonce {
+            Some(nonce) => Some(nonce),
+            // Early starknet contracts had no nonces, so its possible for a contract to
+            // exist without having the nonce explicitly set to zero on deployment.
+            None => {
+                let exists = pending
+                    .state_update
+                    .contract_class(input.contract_address)
+                    .is_some()
+                    || tx
+                        .contract_exists(
+                            input.contract_address,
+                            pathfinder_storage::BlockId::Latest,
+                        )
+                        .context("Checking contract exists")?;
+                exists.then_some(ContractNonce::ZERO)
+            }
+        };
+
+        let queued = tx.outbox_entries()?;
+        let queued = crate::outbox::queued_nonces(&queued, input.contract_address);
+
+        // Queued transactions the gateway accepted in the meantime are already
+        // accounted for by the state nonce.
+        let next_queued = queued
+            .into_iter()
+            .max()
+            .map(|nonce| ContractNonce(nonce.0 + Felt::ONE));
+
+        match (nonce, next_queued) {
+            (Some(nonce), Some(queued)) => Ok(Output(nonce.max(queued))),
+            (Some(nonce), None) => Ok(Output(nonce)),
+            // The contract is deployed by a queued transaction.
+            (None, Some(queued)) => Ok(Output(queued)),
+            (None, None) => Err(Error::ContractNotFound),
+        }
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+impl crate::dto::SerializeForVersion for Output {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        serializer.serialize(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn pending_nonce() {
+        let context = RpcContext::for_tests_with_pending().await;
+
+        let input = Input {
+            contract_address: contract_address_bytes!(b"contract 1"),
+        };
+        let nonce = get_nonce_for_submission(context, input).await.unwrap();
+        assert_eq!(nonce.0, contract_nonce_bytes!(b"pending nonce"));
+    }
+
+    #[tokio::test]
+    async fn accounts_for_queued_transactions() {
+        let context = RpcContext::for_tests();
+        let contract_address = contract_address_bytes!(b"contract 0");
+        {
+            let json = serde_json::json!({
+                "type": "INVOKE",
+                "version": "0x1",
+                "max_fee": "0x1",
+                "signature": [],
+                "nonce": "0x5",
+                "sender_address": contract_address,
+                "calldata": [],
+            });
+            let mut db = context.storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            db.insert_outbox_entry(
+                transaction_hash!("0x1"),
+                &serde_json::to_vec(&json).unwrap(),
+                None,
+                0,
+                0,
+                "timeout",
+            )
+            .unwrap();
+            db.commit().unwrap();
+        }
+
+        let input = Input { contract_address };
+        let nonce = get_nonce_for_submission(context, input).await.unwrap();
+        assert_eq!(nonce.0, contract_nonce!("0x6"));
+    }
+
+    #[tokio::test]
+    async fn contract_not_found() {
+        let context = RpcContext::for_tests();
+
+        let input = Input {
+            contract_address: contract_address_bytes!(b"invalid"),
+        };
+        let result = get_nonce_for_submission(context, input).await;
+        assert_matches!(result, Err(Error::ContractNotFound));
+    }
+}
@@ -0,0 +1,264 @@
+use anyhow::Context;
+use pathfinder_common::{BlockId, ContractAddress, ContractNonce};
+
+use crate::context::RpcContext;
+
+/// The maximum number of contracts in a single request.
+const MAX_ADDRESSES: usize = 1024;
+
+crate::error::generate_rpc_error_subset!(GetNoncesError: BlockNotFound);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetNoncesInput {
+    block_id: BlockId,
+    contract_addresses: Vec<ContractAddress>,
+}
+
+impl crate::dto::DeserializeForVersion for GetNoncesInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_id: value.deserialize("block_id")?,
+                contract_addresses: value.deserialize_array("contract_addresses", |value| {
+                    Ok(ContractAddress(value.deserialize()?))
+                })?,
+            })
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetNoncesOutput(Vec<(ContractAddress, Option<ContractNonce>)>);
+
+impl crate::dto::SerializeForVersion for GetNoncesOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        serializer.serialize_iter(self.0.len(), &mut self.0.iter().map(ContractWithNonce))
+    }
+}
+
+struct ContractWithNonce<'a>(&'a (ContractAddress, Option<ContractNonce>));
+
+impl crate::dto::SerializeForVersion for ContractWithNonce<'_> {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let (contract_address, nonce) = self.0;
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("contract_address", contract_address)?;
+        serializer.serialize_optional_with_null("nonce", *nonce)?;
+        serializer.end()
+    }
+}
+
+/// Returns the nonces of many contracts at once, in the order they were
+/// requested. The nonce of a contract which does not exist is `null`.
+///
+/// Unlike repeated `starknet_getNonce` calls the nonces are looked up in a
+/// single database query.
+pub async fn get_nonces(
+    context: RpcContext,
+    input: GetNoncesInput,
+) -> Result<GetNoncesOutput, GetNoncesError> {
+    if input.contract_addresses.len() > MAX_ADDRESSES {
+        return Err(GetNoncesError::Custom(anyhow::anyhow!(
+            "At most {MAX_ADDRESSES} contract addresses can be requested at once"
+        )));
+    }
+
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_storage(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let pending = input
+            .block_id
+            .is_pending()
+            .then(|| context.pending_data.get(&tx))
+            .transpose()
+            .context("Querying pending data")?;
+
+        let block_id = match input.block_id {
+            BlockId::Pending => pathfinder_storage::BlockId::Latest,
+            other => other.try_into().expect("Only pending cast should fail"),
+        };
+        let (block_number, _) = tx
+            .block_id(block_id)
+            .context("Fetching block number")?
+            .ok_or(GetNoncesError::BlockNotFound)?;
+
+        let nonces = tx
+            .contract_nonces(&input.contract_addresses, block_number)
+            .context("Querying contract nonces from database")?;
+
+        let nonces = input
+            .contract_addresses
+            .into_iter()
+            .zip(nonces)
+            .map(|(address, nonce)| {
+                let pending_nonce = pending.as_ref().and_then(|pending| {
+                    let state_update = &pending.state_update;
+                    state_update.contract_nonce(address).or_else(|| {
+                        // Contracts deployed in the pending block start at zero.
+                        state_update
+                            .contract_class(address)
+                            .map(|_| nonce.unwrap_or(ContractNonce::ZERO))
+                    })
+                });
+                (address, pending_nonce.or(nonce))
+            })
+            .collect();
+
+        Ok(GetNoncesOutput(nonces))
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::BlockNumber;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn nonces_in_request_order() {
+        let context = RpcContext::for_tests_with_pending().await;
+        let missing = contract_address_bytes!(b"missing");
+        let contract_addresses = vec![
+            contract_address_bytes!(b"contract 1"),
+            missing,
+            contract_address_bytes!(b"contract 0"),
+        ];
+
+        let latest = get_nonces(
+            context.clone(),
+            GetNoncesInput {
+                block_id: BlockId::Latest,
+                contract_addresses: contract_addresses.clone(),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            latest,
+            GetNoncesOutput(vec![
+                (
+                    contract_address_bytes!(b"contract 1"),
+                    Some(contract_nonce!("0x10"))
+                ),
+                (missing, None),
+                (
+                    contract_address_bytes!(b"contract 0"),
+                    Some(contract_nonce!("0x1"))
+                ),
+            ])
+        );
+
+        // Contract 1 is deployed in block 1 but only gets a nonce in block 2.
+        let block1 = get_nonces(
+            context.clone(),
+            GetNoncesInput {
+                block_id: BlockId::Number(BlockNumber::new_or_panic(1)),
+                contract_addresses: contract_addresses.clone(),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            block1.0.iter().map(|(_, nonce)| *nonce).collect::<Vec<_>>(),
+            vec![
+                Some(ContractNonce::ZERO),
+                None,
+                Some(contract_nonce!("0x1"))
+            ]
+        );
+
+        let genesis = get_nonces(
+            context.clone(),
+            GetNoncesInput {
+                block_id: BlockId::Number(BlockNumber::GENESIS),
+                contract_addresses,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            genesis
+                .0
+                .iter()
+                .map(|(_, nonce)| *nonce)
+                .collect::<Vec<_>>(),
+            vec![None, None, Some(contract_nonce!("0x1"))]
+        );
+
+        let pending = get_nonces(
+            context,
+            GetNoncesInput {
+                block_id: BlockId::Pending,
+                contract_addresses: vec![
+                    contract_address_bytes!(b"contract 1"),
+                    contract_address_bytes!(b"pending contract 0 address"),
+                    missing,
+                ],
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            pending,
+            GetNoncesOutput(vec![
+                (
+                    contract_address_bytes!(b"contract 1"),
+                    Some(contract_nonce_bytes!(b"pending nonce"))
+                ),
+                (
+                    contract_address_bytes!(b"pending contract 0 address"),
+                    Some(ContractNonce::ZERO)
+                ),
+                (missing, None),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+
+        let error = get_nonces(
+            context,
+            GetNoncesInput {
+                block_id: BlockId::Number(BlockNumber::new_or_panic(9999)),
+                contract_addresses: vec![contract_address_bytes!(b"contract 0")],
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_matches!(error, GetNoncesError::BlockNotFound);
+    }
+
+    #[tokio::test]
+    async fn too_many_addresses() {
+        let context = RpcContext::for_tests();
+
+        let error = get_nonces(
+            context,
+            GetNoncesInput {
+                block_id: BlockId::Latest,
+                contract_addresses: vec![contract_address!("0x1"); MAX_ADDRESSES + 1],
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_matches!(error, GetNoncesError::Custom(_));
+    }
+}
@@ -0,0 +1,350 @@
+use anyhow::Context;
+use pathfinder_common::transaction::Transaction;
+use pathfinder_common::{
+    BlockId,
+    BlockNumber,
+    CasmHash,
+    ClassHash,
+    ContractAddress,
+    ContractNonce,
+    StorageAddress,
+    StorageValue,
+};
+use pathfinder_executor::{ExecutionState, StateReads, TransactionExecutionError};
+
+use crate::compose_executor_transaction;
+use crate::context::RpcContext;
+use crate::dto::SerializeForVersion;
+use crate::error::ApplicationError;
+use crate::executor::can_trace_locally;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetOsInputInput {
+    block_id: BlockId,
+}
+
+impl crate::dto::DeserializeForVersion for GetOsInputInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_id: value.deserialize("block_id")?,
+            })
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct GetOsInputOutput {
+    block_number: BlockNumber,
+    transactions: Vec<Transaction>,
+    reads: StateReads,
+}
+
+impl SerializeForVersion for GetOsInputOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("block_number", &self.block_number)?;
+        serializer.serialize_iter(
+            "transactions",
+            self.transactions.len(),
+            &mut self
+                .transactions
+                .iter()
+                .map(crate::dto::TransactionWithHash),
+        )?;
+        serializer.serialize_iter(
+            "storage",
+            self.reads.storage.len(),
+            &mut self
+                .reads
+                .storage
+                .iter()
+                .map(|(&(address, key), &value)| StorageRead {
+                    address,
+                    key,
+                    value,
+                }),
+        )?;
+        serializer.serialize_iter(
+            "nonces",
+            self.reads.nonces.len(),
+            &mut self
+                .reads
+                .nonces
+                .iter()
+                .map(|(&address, &nonce)| NonceRead { address, nonce }),
+        )?;
+        serializer.serialize_iter(
+            "class_hashes",
+            self.reads.class_hashes.len(),
+            &mut self
+                .reads
+                .class_hashes
+                .iter()
+                .map(|(&address, &class_hash)| ClassHashRead {
+                    address,
+                    class_hash,
+                }),
+        )?;
+        serializer.serialize_iter(
+            "compiled_class_hashes",
+            self.reads.compiled_class_hashes.len(),
+            &mut self.reads.compiled_class_hashes.iter().map(
+                |(&class_hash, &compiled_class_hash)| CompiledClassHashRead {
+                    class_hash,
+                    compiled_class_hash,
+                },
+            ),
+        )?;
+        serializer.serialize_iter(
+            "executed_classes",
+            self.reads.executed_classes.len(),
+            &mut self.reads.executed_classes.iter().copied(),
+        )?;
+        serializer.end()
+    }
+}
+
+struct StorageRead {
+    address: ContractAddress,
+    key: StorageAddress,
+    value: StorageValue,
+}
+
+impl SerializeForVersion for StorageRead {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("contract_address", &self.address)?;
+        serializer.serialize_field("key", &self.key)?;
+        serializer.serialize_field("value", &self.value)?;
+        serializer.end()
+    }
+}
+
+struct NonceRead {
+    address: ContractAddress,
+    nonce: ContractNonce,
+}
+
+impl SerializeForVersion for NonceRead {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("contract_address", &self.address)?;
+        serializer.serialize_field("nonce", &self.nonce)?;
+        serializer.end()
+    }
+}
+
+struct ClassHashRead {
+    address: ContractAddress,
+    class_hash: ClassHash,
+}
+
+impl SerializeForVersion for ClassHashRead {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("contract_address", &self.address)?;
+        serializer.serialize_field("class_hash", &self.class_hash)?;
+        serializer.end()
+    }
+}
+
+struct CompiledClassHashRead {
+    class_hash: ClassHash,
+    compiled_class_hash: CasmHash,
+}
+
+impl SerializeForVersion for CompiledClassHashRead {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("class_hash", &self.class_hash)?;
+        serializer.serialize_field("compiled_class_hash", &self.compiled_class_hash)?;
+        serializer.end()
+    }
+}
+
+/// Re-executes the block and returns its transactions along with the state
+/// they read, i.e. the inputs needed to run the Starknet OS for the block.
+///
+/// Blocks older than Starknet 0.13.1.1 cannot be re-executed faithfully and are
+/// rejected.
+pub async fn get_os_input(
+    context: RpcContext,
+    input: GetOsInputInput,
+) -> Result<GetOsInputOutput, GetOsInputError> {
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_execution(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .execution_storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let (header, transactions) = match input.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&db)
+                    .context("Querying pending data")?;
+
+                (pending.header(), pending.block.transactions.clone())
+            }
+            other => {
+                let block_id = other.try_into().expect("Only pending cast should fail");
+                let header = db
+                    .block_header(block_id)
+                    .context("Querying block header")?
+                    .ok_or(GetOsInputError::BlockNotFound)?;
+                let transactions = db
+                    .transactions_for_block(block_id)
+                    .context("Querying transactions")?
+                    .context("Transaction data missing")?;
+
+                (header, transactions)
+            }
+        };
+
+        if !can_trace_locally(header.starknet_version) {
+            return Err(GetOsInputError::Custom(anyhow::anyhow!(
+                "Re-execution is not supported for blocks of Starknet version {}",
+                header.starknet_version
+            )));
+        }
+
+        let executor_transactions = transactions
+            .iter()
+            .map(|transaction| compose_executor_transaction(transaction, &db))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let block_number = header.number;
+        let state = ExecutionState::trace(
+            &db,
+            context.chain_id,
+            header,
+            None,
+            context.config.custom_versioned_constants,
+            context.fee_tokens(),
+        );
+        let reads = pathfinder_executor::os_input(state, executor_transactions)?;
+
+        Ok(GetOsInputOutput {
+            block_number,
+            transactions,
+            reads,
+        })
+    })
+    .await
+    .context("Re-executing block")?
+}
+
+#[derive(Debug)]
+pub enum GetOsInputError {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    BlockNotFound,
+    TransactionExecutionError {
+        transaction_index: usize,
+        error: String,
+        error_stack: pathfinder_executor::ErrorStack,
+    },
+}
+
+impl From<anyhow::Error> for GetOsInputError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<TransactionExecutionError> for GetOsInputError {
+    fn from(value: TransactionExecutionError) -> Self {
+        use TransactionExecutionError::*;
+        match value {
+            ExecutionError {
+                transaction_index,
+                error,
+                error_stack,
+            } => Self::TransactionExecutionError {
+                transaction_index,
+                error,
+                error_stack,
+            },
+            ExecutionRefused(refused) => Self::Custom(refused.into()),
+            Internal(e) => Self::Internal(e),
+            Custom(e) => Self::Custom(e),
+        }
+    }
+}
+
+impl From<GetOsInputError> for ApplicationError {
+    fn from(e: GetOsInputError) -> Self {
+        match e {
+            GetOsInputError::Internal(internal) => Self::Internal(internal),
+            GetOsInputError::Custom(internal) => Self::Custom(internal),
+            GetOsInputError::BlockNotFound => Self::BlockNotFound,
+            GetOsInputError::TransactionExecutionError {
+                transaction_index,
+                error,
+                error_stack,
+            } => Self::TransactionExecutionError {
+                transaction_index,
+                error,
+                error_stack,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::method::trace_block_transactions::tests::setup_multi_tx_trace_test;
+
+    #[tokio::test]
+    async fn reads_match_parent_state() {
+        let (context, header, traces) = setup_multi_tx_trace_test().await.unwrap();
+
+        let input = GetOsInputInput {
+            block_id: header.hash.into(),
+        };
+        let output = get_os_input(context.clone(), input).await.unwrap();
+
+        assert_eq!(output.block_number, header.number);
+        assert_eq!(output.transactions.len(), traces.len());
+        assert!(!output.reads.storage.is_empty());
+        assert!(!output.reads.executed_classes.is_empty());
+
+        let parent = header.number.parent().unwrap();
+        let mut db = context.storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+        for (&(contract, key), &value) in &output.reads.storage {
+            let expected = db
+                .storage_value(parent.into(), contract, key)
+                .unwrap()
+                .unwrap_or_default();
+            assert_eq!(value, expected, "contract {contract} key {key}");
+        }
+        for (&contract, &nonce) in &output.reads.nonces {
+            let expected = db
+                .contract_nonce(contract, parent.into())
+                .unwrap()
+                .unwrap_or_default();
+            assert_eq!(nonce, expected, "contract {contract}");
+        }
+    }
+}
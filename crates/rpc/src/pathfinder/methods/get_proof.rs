@@ -112,7 +112,7 @@ impl crate::dto::SerializeForVersion for PathWrapper {
 /// Wrapper around [`Vec<TrieNode>`] as we don't control [TrieNode] in this
 /// crate.
 #[derive(Clone, Debug, PartialEq)]
-pub struct ProofNodes(Vec<TrieNode>);
+pub struct ProofNodes(pub(crate) Vec<TrieNode>);
 
 impl crate::dto::SerializeForVersion for ProofNodes {
     fn serialize(
@@ -264,16 +264,20 @@ impl crate::dto::SerializeForVersion for GetClassProofOutput {
     }
 }
 
+/// Below this many keys the storage proofs are generated on the current thread,
+/// as spreading them over the rayon thread pool costs more than it saves.
+const PARALLEL_PROOFS_MIN_KEYS: usize = 16;
+
 /// Returns all the necessary data to trustlessly verify storage slots for a
 /// particular contract.
 pub async fn get_proof(
     context: RpcContext,
     input: GetProofInput,
 ) -> Result<GetProofOutput, GetProofError> {
-    const MAX_KEYS: usize = 100;
-    if input.keys.len() > MAX_KEYS {
+    let max_keys = context.config.get_proof_max_keys.get();
+    if input.keys.len() > max_keys {
         return Err(GetProofError::ProofLimitExceeded {
-            limit: MAX_KEYS as u32,
+            limit: max_keys as u32,
             requested: input.keys.len() as u32,
         });
     }
@@ -289,7 +293,7 @@ pub async fn get_proof(
 
     let storage = context.storage.clone();
     let span = tracing::Span::current();
-    let jh = util::task::spawn_blocking(move |_| {
+    let jh = util::task::spawn_blocking_storage(move |_| {
         let _g = span.enter();
         let mut db = storage
             .connection()
@@ -380,25 +384,33 @@ pub async fn get_proof(
             .contract_root_index(header.number, input.contract_address)
             .context("Querying contract root index")?;
 
-        let mut storage_proofs = Vec::new();
-        for k in &input.keys {
-            if let Some(root) = root {
-                let proof = ContractsStorageTree::get_proof(
-                    &tx,
+        let storage_proofs = match root {
+            Some(root) if input.keys.len() >= PARALLEL_PROOFS_MIN_KEYS => {
+                // The worker threads open their own connections, don't hold on to one from the
+                // same pool meanwhile.
+                drop(tx);
+                drop(db);
+                ContractsStorageTree::get_proofs_parallel(
+                    &storage,
                     input.contract_address,
                     header.number,
-                    k.view_bits(),
+                    &input.keys,
                     root,
                 )?
-                .into_iter()
-                .map(|(node, _)| node)
-                .collect();
-
-                storage_proofs.push(ProofNodes(proof));
-            } else {
-                storage_proofs.push(ProofNodes(vec![]));
             }
-        }
+            Some(root) => ContractsStorageTree::get_proofs(
+                &tx,
+                input.contract_address,
+                header.number,
+                &input.keys,
+                root,
+            )?,
+            None => vec![vec![]; input.keys.len()],
+        };
+        let storage_proofs = storage_proofs
+            .into_iter()
+            .map(|proof| ProofNodes(proof.into_iter().map(|(node, _)| node).collect()))
+            .collect();
 
         let contract_data = ContractData {
             class_hash,
@@ -437,7 +449,7 @@ pub async fn get_class_proof(
 
     let storage = context.storage.clone();
     let span = tracing::Span::current();
-    let jh = util::task::spawn_blocking(move |_| {
+    let jh = util::task::spawn_blocking_storage(move |_| {
         let _g = span.enter();
         let mut db = storage
             .connection()
@@ -567,6 +579,41 @@ mod tests {
             get_proof(context, input).await.unwrap();
         }
 
+        #[tokio::test]
+        async fn parallel_proofs_match_single_key_proofs() {
+            let context = RpcContext::for_tests();
+            let block_id = BlockId::Number(pathfinder_common::BlockNumber::GENESIS + 2);
+            let contract_address = contract_address_bytes!(b"contract 2 (sierra)");
+            let keys = (0..PARALLEL_PROOFS_MIN_KEYS as u64)
+                .map(|idx| StorageAddress::new_or_panic(Felt::from_u64(idx)))
+                .chain([storage_address_bytes!(b"storage addr 0")])
+                .collect::<Vec<_>>();
+
+            let storage_proofs =
+                |output: GetProofOutput| output.contract_data.unwrap().storage_proofs;
+
+            let input = GetProofInput {
+                block_id,
+                contract_address,
+                keys: keys.clone(),
+            };
+            let parallel = storage_proofs(get_proof(context.clone(), input).await.unwrap());
+
+            let mut single = Vec::new();
+            for key in keys {
+                let input = GetProofInput {
+                    block_id,
+                    contract_address,
+                    keys: vec![key],
+                };
+                single.extend(storage_proofs(
+                    get_proof(context.clone(), input).await.unwrap(),
+                ));
+            }
+
+            assert_eq!(parallel, single);
+        }
+
         #[tokio::test]
         async fn limit_exceeded() {
             let context = RpcContext::for_tests();
@@ -0,0 +1,197 @@
+use anyhow::{anyhow, Context};
+use pathfinder_common::hash::PoseidonHash;
+use pathfinder_common::receipt::Receipt;
+use pathfinder_common::{
+    version_matrix,
+    BlockHeader,
+    ReceiptCommitment,
+    TransactionHash,
+    TransactionIndex,
+};
+use pathfinder_crypto::Felt;
+use pathfinder_merkle_tree::tree::TrieNodeWithHash;
+use pathfinder_merkle_tree::TransactionOrEventTree;
+
+use super::get_proof::ProofNodes;
+use crate::context::RpcContext;
+
+crate::error::generate_rpc_error_subset!(GetReceiptProofError: TxnHashNotFound, ProofMissing);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetReceiptProofInput {
+    transaction_hash: TransactionHash,
+}
+
+impl crate::dto::DeserializeForVersion for GetReceiptProofInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                transaction_hash: TransactionHash(value.deserialize("transaction_hash")?),
+            })
+        })
+    }
+}
+
+/// Proof that a receipt is included in the receipt commitment of a block.
+#[derive(Debug, PartialEq)]
+pub struct GetReceiptProofOutput {
+    block_header: BlockHeader,
+    receipt_commitment: ReceiptCommitment,
+    transaction_index: TransactionIndex,
+    /// The leaf value, i.e. the hash of the receipt.
+    receipt_hash: Felt,
+    /// Nodes from the receipt commitment down to the receipt hash.
+    receipt_proof: ProofNodes,
+}
+
+impl crate::dto::SerializeForVersion for GetReceiptProofOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("block_header", &self.block_header)?;
+        serializer.serialize_field("receipt_commitment", &self.receipt_commitment)?;
+        serializer.serialize_field("transaction_index", &self.transaction_index.get())?;
+        serializer.serialize_field("receipt_hash", &self.receipt_hash)?;
+        serializer.serialize_field("receipt_proof", &self.receipt_proof)?;
+        serializer.end()
+    }
+}
+
+/// Returns the Merkle path from a transaction's receipt to the receipt
+/// commitment of its block.
+///
+/// Receipt commitments were introduced in Starknet 0.13.2, older blocks have
+/// no commitment to prove against.
+pub async fn get_receipt_proof(
+    context: RpcContext,
+    input: GetReceiptProofInput,
+) -> Result<GetReceiptProofOutput, GetReceiptProofError> {
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_storage(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let (_, receipt, _, block_number) = tx
+            .transaction_with_receipt(input.transaction_hash)
+            .context("Fetching receipt")?
+            .ok_or(GetReceiptProofError::TxnHashNotFound)?;
+
+        let block_header = tx
+            .block_header(block_number.into())
+            .context("Fetching block header")?
+            .context("Block header missing")?;
+        if !version_matrix::capabilities(block_header.starknet_version)
+            .hash_scheme
+            .is_poseidon()
+        {
+            return Err(GetReceiptProofError::ProofMissing);
+        }
+
+        let receipts = tx
+            .transactions_with_receipts_for_block(block_number.into())
+            .context("Fetching block receipts")?
+            .context("Block receipts missing")?
+            .into_iter()
+            .map(|(_, receipt)| receipt)
+            .collect::<Vec<_>>();
+
+        let (root, proof) = receipt_commitment_proof(&receipts, receipt.transaction_index)?;
+        if root != block_header.receipt_commitment.0 {
+            return Err(anyhow!(
+                "Computed receipt commitment {root} does not match the block's {}",
+                block_header.receipt_commitment.0
+            )
+            .into());
+        }
+
+        Ok(GetReceiptProofOutput {
+            receipt_commitment: block_header.receipt_commitment,
+            block_header,
+            transaction_index: receipt.transaction_index,
+            receipt_hash: receipt.hash(),
+            receipt_proof: ProofNodes(proof.into_iter().map(|(node, _)| node).collect()),
+        })
+    })
+    .await
+    .context("Joining database task")?
+}
+
+/// Rebuilds the receipt commitment tree of a block and proves the receipt at
+/// `index`. Returns the commitment along with the proof.
+fn receipt_commitment_proof(
+    receipts: &[Receipt],
+    index: TransactionIndex,
+) -> anyhow::Result<(Felt, Vec<TrieNodeWithHash>)> {
+    let mut tree = TransactionOrEventTree::<PoseidonHash>::default();
+    for (idx, receipt) in receipts.iter().enumerate() {
+        tree.set(idx as u64, receipt.hash())
+            .context("Building receipt commitment tree")?;
+    }
+    tree.commit_and_prove(index.get())
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_storage::fake::Config;
+    use pathfinder_storage::StorageBuilder;
+
+    use super::*;
+
+    fn receipt_commitment(receipts: &[Receipt]) -> anyhow::Result<ReceiptCommitment> {
+        let mut tree = TransactionOrEventTree::<PoseidonHash>::default();
+        for (idx, receipt) in receipts.iter().enumerate() {
+            tree.set(idx as u64, receipt.hash())?;
+        }
+        tree.commit().map(ReceiptCommitment)
+    }
+
+    #[tokio::test]
+    async fn proof_leads_to_receipt_hash() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let blocks = pathfinder_storage::fake::generate::with_config(
+            1,
+            Config {
+                calculate_receipt_commitment: Box::new(receipt_commitment),
+                ..Default::default()
+            },
+        );
+        pathfinder_storage::fake::fill(&storage, &blocks, None);
+        let context = RpcContext::for_tests().with_storage(storage);
+
+        let (_, receipt, _) = blocks[0].transaction_data.last().unwrap();
+        let input = GetReceiptProofInput {
+            transaction_hash: receipt.transaction_hash,
+        };
+
+        let output = get_receipt_proof(context, input).await.unwrap();
+        assert_eq!(output.block_header, blocks[0].header.header);
+        assert_eq!(output.transaction_index, receipt.transaction_index);
+        assert_eq!(output.receipt_hash, receipt.hash());
+
+        // Follow the proof from the commitment to the receipt hash.
+        assert!(TransactionOrEventTree::<PoseidonHash>::verify_proof(
+            output.receipt_commitment.0,
+            output.transaction_index.get(),
+            &output.receipt_proof.0,
+            output.receipt_hash
+        ));
+    }
+
+    #[tokio::test]
+    async fn unknown_transaction() {
+        let context = RpcContext::for_tests();
+        let input = GetReceiptProofInput {
+            transaction_hash: transaction_hash!("0xdeadbeef"),
+        };
+
+        let error = get_receipt_proof(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(error, GetReceiptProofError::TxnHashNotFound);
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::Context;
+use pathfinder_common::TransactionHash;
+use pathfinder_storage::RejectedTransaction;
+
+use crate::context::RpcContext;
+
+crate::error::generate_rpc_error_subset!(GetRejectedTransactionError: TxnHashNotFound);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetRejectedTransactionInput {
+    transaction_hash: TransactionHash,
+}
+
+impl crate::dto::DeserializeForVersion for GetRejectedTransactionInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                transaction_hash: TransactionHash(value.deserialize("transaction_hash")?),
+            })
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GetRejectedTransactionOutput {
+    transaction_hash: TransactionHash,
+    /// The transaction as it was submitted.
+    transaction: serde_json::Value,
+    error_code: String,
+    reason: String,
+    rejected_at: u64,
+}
+
+impl crate::dto::SerializeForVersion for GetRejectedTransactionOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("transaction_hash", &self.transaction_hash)?;
+        serializer.serialize_field("transaction", &self.transaction)?;
+        serializer.serialize_field("error_code", &self.error_code)?;
+        serializer.serialize_field("reason", &self.reason)?;
+        serializer.serialize_field("rejected_at", &self.rejected_at)?;
+        serializer.end()
+    }
+}
+
+/// Returns a transaction submitted through this node which the gateway
+/// rejected, if it was archived.
+pub async fn get_rejected_transaction(
+    context: RpcContext,
+    input: GetRejectedTransactionInput,
+) -> Result<GetRejectedTransactionOutput, GetRejectedTransactionError> {
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_storage(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let RejectedTransaction {
+            transaction_hash,
+            transaction_json,
+            error_code,
+            reason,
+            rejected_at,
+        } = db
+            .rejected_transaction(input.transaction_hash)?
+            .ok_or(GetRejectedTransactionError::TxnHashNotFound)?;
+        let transaction =
+            serde_json::from_slice(&transaction_json).context("Parsing archived transaction")?;
+
+        Ok(GetRejectedTransactionOutput {
+            transaction_hash,
+            transaction,
+            error_code,
+            reason,
+            rejected_at,
+        })
+    })
+    .await
+    .context("Joining blocking task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn archived_transaction() {
+        let context = RpcContext::for_tests();
+        {
+            let mut db = context.storage.connection().unwrap();
+            let db = db.transaction().unwrap();
+            db.insert_rejected_transaction(&RejectedTransaction {
+                transaction_hash: transaction_hash!("0x1"),
+                transaction_json: br#"{"type":"INVOKE"}"#.to_vec(),
+                error_code: "StarknetErrorCode.VALIDATE_FAILURE".to_owned(),
+                reason: "Validation failed".to_owned(),
+                rejected_at: 10,
+            })
+            .unwrap();
+            db.commit().unwrap();
+        }
+
+        let output = get_rejected_transaction(
+            context.clone(),
+            GetRejectedTransactionInput {
+                transaction_hash: transaction_hash!("0x1"),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            output,
+            GetRejectedTransactionOutput {
+                transaction_hash: transaction_hash!("0x1"),
+                transaction: serde_json::json!({"type": "INVOKE"}),
+                error_code: "StarknetErrorCode.VALIDATE_FAILURE".to_owned(),
+                reason: "Validation failed".to_owned(),
+                rejected_at: 10,
+            }
+        );
+
+        let error = get_rejected_transaction(
+            context,
+            GetRejectedTransactionInput {
+                transaction_hash: transaction_hash!("0x2"),
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_matches::assert_matches!(error, GetRejectedTransactionError::TxnHashNotFound);
+    }
+}
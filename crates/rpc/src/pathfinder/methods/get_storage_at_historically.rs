@@ -0,0 +1,203 @@
+use anyhow::{anyhow, Context};
+use pathfinder_common::{BlockId, BlockNumber, ContractAddress, StorageAddress, StorageValue};
+
+use crate::context::RpcContext;
+
+crate::error::generate_rpc_error_subset!(
+    GetStorageAtHistoricallyError: ContractNotFound,
+    BlockNotFound
+);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetStorageAtHistoricallyInput {
+    contract_address: ContractAddress,
+    key: StorageAddress,
+    block_id: BlockId,
+}
+
+impl crate::dto::DeserializeForVersion for GetStorageAtHistoricallyInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                contract_address: value.deserialize("contract_address").map(ContractAddress)?,
+                key: value.deserialize("key").map(StorageAddress)?,
+                block_id: value.deserialize("block_id")?,
+            })
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetStorageAtHistoricallyOutput {
+    value: StorageValue,
+    /// The block the value was written in, [None] if the key was never
+    /// written to.
+    written_in: Option<BlockNumber>,
+}
+
+impl crate::dto::SerializeForVersion for GetStorageAtHistoricallyOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("value", &self.value)?;
+        serializer.serialize_optional_with_null("written_in", self.written_in)?;
+        serializer.end()
+    }
+}
+
+/// Returns the value of a storage key at a block, along with the block it was
+/// written in.
+pub async fn get_storage_at_historically(
+    context: RpcContext,
+    input: GetStorageAtHistoricallyInput,
+) -> Result<GetStorageAtHistoricallyOutput, GetStorageAtHistoricallyError> {
+    let block_id = match input.block_id {
+        BlockId::Pending => {
+            return Err(GetStorageAtHistoricallyError::Internal(anyhow!(
+                "'pending' is not currently supported by this method!"
+            )))
+        }
+        other => other.try_into().expect("Only pending cast should fail"),
+    };
+
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_storage(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        let block_number = tx
+            .block_number(block_id)
+            .context("Querying block number")?
+            .ok_or(GetStorageAtHistoricallyError::BlockNotFound)?;
+
+        let write = tx
+            .storage_write(block_number.into(), input.contract_address, input.key)
+            .context("Querying storage write")?;
+
+        match write {
+            Some(write) => Ok(GetStorageAtHistoricallyOutput {
+                value: write.value,
+                written_in: Some(write.block_number),
+            }),
+            None if tx
+                .contract_exists(input.contract_address, block_number.into())
+                .context("Querying contract existence")? =>
+            {
+                Ok(GetStorageAtHistoricallyOutput {
+                    value: StorageValue::ZERO,
+                    written_in: None,
+                })
+            }
+            None => Err(GetStorageAtHistoricallyError::ContractNotFound),
+        }
+    })
+    .await
+    .context("Joining database task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+
+    fn input(
+        contract_address: ContractAddress,
+        block_id: BlockId,
+    ) -> GetStorageAtHistoricallyInput {
+        GetStorageAtHistoricallyInput {
+            contract_address,
+            key: storage_address_bytes!(b"storage addr 0"),
+            block_id,
+        }
+    }
+
+    #[tokio::test]
+    async fn returns_the_most_recent_write() {
+        let context = RpcContext::for_tests();
+        let contract = contract_address_bytes!(b"contract 1");
+
+        let output = get_storage_at_historically(
+            context.clone(),
+            input(contract, BlockNumber::new_or_panic(1).into()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            output,
+            GetStorageAtHistoricallyOutput {
+                value: storage_value_bytes!(b"storage value 1"),
+                written_in: Some(BlockNumber::new_or_panic(1)),
+            }
+        );
+
+        let output = get_storage_at_historically(context, input(contract, BlockId::Latest))
+            .await
+            .unwrap();
+        assert_eq!(
+            output,
+            GetStorageAtHistoricallyOutput {
+                value: storage_value_bytes!(b"storage value 2"),
+                written_in: Some(BlockNumber::new_or_panic(2)),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn unwritten_key_is_zero() {
+        let context = RpcContext::for_tests();
+        let output = get_storage_at_historically(
+            context,
+            input(contract_address_bytes!(b"contract 0"), BlockId::Latest),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            output,
+            GetStorageAtHistoricallyOutput {
+                value: StorageValue::ZERO,
+                written_in: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn contract_not_deployed_yet() {
+        let context = RpcContext::for_tests();
+        let error = get_storage_at_historically(
+            context,
+            input(
+                contract_address_bytes!(b"contract 1"),
+                BlockNumber::GENESIS.into(),
+            ),
+        )
+        .await
+        .unwrap_err();
+
+        assert_matches!(error, GetStorageAtHistoricallyError::ContractNotFound);
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+        let error = get_storage_at_historically(
+            context,
+            input(
+                contract_address_bytes!(b"contract 1"),
+                BlockNumber::MAX.into(),
+            ),
+        )
+        .await
+        .unwrap_err();
+
+        assert_matches!(error, GetStorageAtHistoricallyError::BlockNotFound);
+    }
+}
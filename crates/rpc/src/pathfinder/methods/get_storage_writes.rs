@@ -0,0 +1,344 @@
+use std::str::FromStr;
+
+use anyhow::Context;
+use pathfinder_common::{BlockNumber, ContractAddress, StorageAddress};
+use pathfinder_crypto::Felt;
+use pathfinder_storage::StorageWrite;
+
+use crate::context::RpcContext;
+
+/// The maximum number of writes returned in a single page.
+const MAX_CHUNK_SIZE: usize = 1024;
+
+crate::error::generate_rpc_error_subset!(
+    GetStorageWritesError: PageSizeTooBig,
+    InvalidContinuationToken
+);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetStorageWritesInput {
+    contract_address: ContractAddress,
+    from_block: Option<BlockNumber>,
+    to_block: Option<BlockNumber>,
+    reverse: bool,
+    chunk_size: usize,
+    continuation_token: Option<String>,
+}
+
+impl crate::dto::DeserializeForVersion for GetStorageWritesInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        use serde::de::Error;
+
+        value.deserialize_map(|value| {
+            Ok(Self {
+                contract_address: value.deserialize("contract_address").map(ContractAddress)?,
+                from_block: value
+                    .deserialize_optional("from_block")?
+                    .map(|n| {
+                        BlockNumber::new(n)
+                            .ok_or_else(|| serde_json::Error::custom("Invalid from_block"))
+                    })
+                    .transpose()?,
+                to_block: value
+                    .deserialize_optional("to_block")?
+                    .map(|n| {
+                        BlockNumber::new(n)
+                            .ok_or_else(|| serde_json::Error::custom("Invalid to_block"))
+                    })
+                    .transpose()?,
+                reverse: value.deserialize_optional("reverse")?.unwrap_or_default(),
+                chunk_size: value.deserialize("chunk_size")?,
+                continuation_token: value.deserialize_optional_serde("continuation_token")?,
+            })
+        })
+    }
+}
+
+/// The position of the next write to return, encoded as
+/// `<block number>-<key>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ContinuationToken {
+    block_number: BlockNumber,
+    key: StorageAddress,
+}
+
+impl FromStr for ContinuationToken {
+    type Err = GetStorageWritesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (block_number, key) = s
+            .split_once('-')
+            .ok_or(GetStorageWritesError::InvalidContinuationToken)?;
+        let block_number = block_number
+            .parse::<u64>()
+            .ok()
+            .and_then(BlockNumber::new)
+            .ok_or(GetStorageWritesError::InvalidContinuationToken)?;
+        let key = Felt::from_hex_str(key)
+            .ok()
+            .and_then(StorageAddress::new)
+            .ok_or(GetStorageWritesError::InvalidContinuationToken)?;
+
+        Ok(Self { block_number, key })
+    }
+}
+
+impl std::fmt::Display for ContinuationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.block_number.get(), self.key.0.to_hex_str())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetStorageWritesOutput {
+    writes: Vec<StorageWrite>,
+    continuation_token: Option<String>,
+}
+
+impl crate::dto::SerializeForVersion for GetStorageWritesOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_iter("writes", self.writes.len(), &mut self.writes.iter())?;
+        serializer.serialize_optional("continuation_token", self.continuation_token.clone())?;
+        serializer.end()
+    }
+}
+
+impl crate::dto::SerializeForVersion for &StorageWrite {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("block_number", &self.block_number)?;
+        serializer.serialize_field("key", &self.key)?;
+        serializer.serialize_field("value", &self.value)?;
+        serializer.end()
+    }
+}
+
+/// Returns the writes to the storage of a contract in chain order, or starting
+/// with the latest block if reversed, one page at a time.
+///
+/// Writes of the pending block are not returned.
+pub async fn get_storage_writes(
+    context: RpcContext,
+    input: GetStorageWritesInput,
+) -> Result<GetStorageWritesOutput, GetStorageWritesError> {
+    if input.chunk_size > MAX_CHUNK_SIZE {
+        return Err(GetStorageWritesError::PageSizeTooBig);
+    }
+
+    let from_block = input.from_block.unwrap_or(BlockNumber::GENESIS);
+    let to_block = input.to_block.unwrap_or(BlockNumber::MAX);
+    let resume_at = input
+        .continuation_token
+        .as_deref()
+        .map(str::parse::<ContinuationToken>)
+        .transpose()?;
+    if resume_at
+        .is_some_and(|token| token.block_number < from_block || token.block_number > to_block)
+    {
+        return Err(GetStorageWritesError::InvalidContinuationToken);
+    }
+
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_storage(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        // Fetch one more write than requested to know whether there is a next
+        // page.
+        let mut writes = tx
+            .storage_updates_for_contract(
+                input.contract_address,
+                from_block,
+                to_block,
+                input.reverse,
+                resume_at.map(|token| (token.block_number, token.key)),
+                input.chunk_size + 1,
+            )
+            .context("Querying storage writes")?;
+
+        let continuation_token = if writes.len() > input.chunk_size {
+            writes.pop().map(|write| {
+                ContinuationToken {
+                    block_number: write.block_number,
+                    key: write.key,
+                }
+                .to_string()
+            })
+        } else {
+            None
+        };
+
+        Ok(GetStorageWritesOutput {
+            writes,
+            continuation_token,
+        })
+    })
+    .await
+    .context("Joining database task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{BlockHeader, StateUpdate};
+    use pathfinder_storage::StorageBuilder;
+    use serde_json::json;
+
+    use super::*;
+    use crate::dto::{DeserializeForVersion, SerializeForVersion, Serializer};
+    use crate::RpcVersion;
+
+    fn input(value: serde_json::Value) -> GetStorageWritesInput {
+        GetStorageWritesInput::deserialize(crate::dto::Value::new(value, RpcVersion::PathfinderV01))
+            .unwrap()
+    }
+
+    fn context() -> RpcContext {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let contract = contract_address!("0xc");
+        let genesis = BlockHeader::builder().finalize_with_hash(block_hash!("0x0"));
+        let block1 = genesis
+            .child_builder()
+            .finalize_with_hash(block_hash!("0x1"));
+        tx.insert_block_header(&genesis).unwrap();
+        tx.insert_state_update(
+            genesis.number,
+            &StateUpdate::default()
+                .with_storage_update(contract, storage_address!("0x1"), storage_value!("0x10"))
+                .with_storage_update(contract, storage_address!("0x2"), storage_value!("0x20")),
+        )
+        .unwrap();
+        tx.insert_block_header(&block1).unwrap();
+        tx.insert_state_update(
+            block1.number,
+            &StateUpdate::default()
+                .with_storage_update(contract, storage_address!("0x1"), storage_value!("0x11"))
+                .with_storage_update(
+                    contract_address!("0xd"),
+                    storage_address!("0x1"),
+                    storage_value!("0x1"),
+                ),
+        )
+        .unwrap();
+        tx.commit().unwrap();
+        drop(db);
+
+        RpcContext::for_tests().with_storage(storage)
+    }
+
+    fn write(
+        block: u64,
+        key: StorageAddress,
+        value: pathfinder_common::StorageValue,
+    ) -> StorageWrite {
+        StorageWrite {
+            block_number: BlockNumber::new_or_panic(block),
+            key,
+            value,
+        }
+    }
+
+    #[tokio::test]
+    async fn paginates_writes_of_contract() {
+        let context = context();
+
+        let first_page = get_storage_writes(
+            context.clone(),
+            input(json!({"contract_address": "0xc", "chunk_size": 2})),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            first_page.writes,
+            vec![
+                write(0, storage_address!("0x1"), storage_value!("0x10")),
+                write(0, storage_address!("0x2"), storage_value!("0x20")),
+            ]
+        );
+        let token = first_page.continuation_token.unwrap();
+        assert_eq!(token, "1-0x1");
+
+        let rest = get_storage_writes(
+            context.clone(),
+            input(json!({
+                "contract_address": "0xc",
+                "chunk_size": 2,
+                "continuation_token": token,
+            })),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            rest,
+            GetStorageWritesOutput {
+                writes: vec![write(1, storage_address!("0x1"), storage_value!("0x11"))],
+                continuation_token: None,
+            }
+        );
+
+        let output = rest
+            .serialize(Serializer::new(RpcVersion::PathfinderV01))
+            .unwrap();
+        assert_eq!(
+            output,
+            json!({
+                "writes": [{"block_number": 1, "key": "0x1", "value": "0x11"}],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn reversed_writes_start_with_latest_block() {
+        let output = get_storage_writes(
+            context(),
+            input(json!({
+                "contract_address": "0xc",
+                "reverse": true,
+                "chunk_size": 10,
+            })),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            output.writes,
+            vec![
+                write(1, storage_address!("0x1"), storage_value!("0x11")),
+                write(0, storage_address!("0x2"), storage_value!("0x20")),
+                write(0, storage_address!("0x1"), storage_value!("0x10")),
+            ]
+        );
+        assert_eq!(output.continuation_token, None);
+    }
+
+    #[tokio::test]
+    async fn invalid_continuation_token() {
+        for token in ["invalid", "1-0xzz", "5-0x1"] {
+            let error = get_storage_writes(
+                context(),
+                input(json!({
+                    "contract_address": "0xc",
+                    "to_block": 1,
+                    "chunk_size": 10,
+                    "continuation_token": token,
+                })),
+            )
+            .await
+            .unwrap_err();
+            assert_matches::assert_matches!(error, GetStorageWritesError::InvalidContinuationToken);
+        }
+    }
+}
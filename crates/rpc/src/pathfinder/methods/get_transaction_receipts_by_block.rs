@@ -0,0 +1,269 @@
+use anyhow::Context;
+use pathfinder_common::event::Event;
+use pathfinder_common::receipt::Receipt;
+use pathfinder_common::transaction::Transaction;
+use pathfinder_common::{BlockHash, BlockId, BlockNumber};
+
+use crate::context::RpcContext;
+use crate::dto::TxnFinalityStatus;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetTransactionReceiptsByBlockInput {
+    block_id: BlockId,
+    include_events: bool,
+    include_messages: bool,
+}
+
+impl crate::dto::DeserializeForVersion for GetTransactionReceiptsByBlockInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_id: value.deserialize("block_id")?,
+                include_events: value
+                    .deserialize_optional("include_events")?
+                    .unwrap_or(true),
+                include_messages: value
+                    .deserialize_optional("include_messages")?
+                    .unwrap_or(true),
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(GetTransactionReceiptsByBlockError: BlockNotFound);
+
+pub struct GetTransactionReceiptsByBlockOutput {
+    /// [None] for the pending block.
+    block_hash: Option<BlockHash>,
+    block_number: BlockNumber,
+    finality: TxnFinalityStatus,
+    receipts: Vec<(Transaction, Receipt, Vec<Event>)>,
+}
+
+impl crate::dto::SerializeForVersion for GetTransactionReceiptsByBlockOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_optional("block_hash", self.block_hash)?;
+        serializer.serialize_field("block_number", &self.block_number)?;
+        serializer.serialize_iter(
+            "receipts",
+            self.receipts.len(),
+            &mut self.receipts.iter().map(|(transaction, receipt, events)| {
+                crate::dto::TxnReceipt {
+                    receipt,
+                    transaction,
+                    events,
+                    finality: self.finality,
+                }
+            }),
+        )?;
+        serializer.end()
+    }
+}
+
+/// Returns the receipts of all transactions in a block, read in a single pass
+/// over storage.
+///
+/// Events and L2 to L1 messages can be left out to reduce the response size,
+/// in which case they are returned as empty arrays so that each receipt still
+/// matches the `TXN_RECEIPT` schema.
+pub async fn get_transaction_receipts_by_block(
+    context: RpcContext,
+    input: GetTransactionReceiptsByBlockInput,
+) -> Result<GetTransactionReceiptsByBlockOutput, GetTransactionReceiptsByBlockError> {
+    let span = tracing::Span::current();
+    let mut output = util::task::spawn_blocking_storage(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let block_id = match input.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&db)
+                    .context("Querying pending data")?;
+
+                return Ok(GetTransactionReceiptsByBlockOutput {
+                    block_hash: None,
+                    block_number: pending.number,
+                    finality: crate::pending::finality_status(&pending.block),
+                    receipts: pending
+                        .block
+                        .transactions
+                        .iter()
+                        .cloned()
+                        .zip(pending.block.transaction_receipts.iter().cloned())
+                        .map(|(transaction, (receipt, events))| (transaction, receipt, events))
+                        .collect(),
+                });
+            }
+            other => other.try_into().expect("Only pending cast should fail"),
+        };
+
+        let header = db
+            .block_header(block_id)
+            .context("Fetching block header")?
+            .ok_or(GetTransactionReceiptsByBlockError::BlockNotFound)?;
+        if input.include_events {
+            db.ensure_events_retained(header.number)?;
+        }
+
+        let receipts = db
+            .transaction_data_for_block(block_id)
+            .context("Fetching transaction data")?
+            .context("Transaction data missing")?;
+
+        let finality = if db
+            .block_is_l1_accepted(block_id)
+            .context("Fetching block finality")?
+        {
+            TxnFinalityStatus::AcceptedOnL1
+        } else {
+            TxnFinalityStatus::AcceptedOnL2
+        };
+
+        Ok(GetTransactionReceiptsByBlockOutput {
+            block_hash: Some(header.hash),
+            block_number: header.number,
+            finality,
+            receipts,
+        })
+    })
+    .await
+    .context("Joining blocking task")??;
+
+    for (_, receipt, events) in &mut output.receipts {
+        if !input.include_events {
+            events.clear();
+        }
+        if !input.include_messages {
+            receipt.l2_to_l1_messages.clear();
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::{DeserializeForVersion, SerializeForVersion, Serializer};
+    use crate::RpcVersion;
+
+    fn input(block_id: BlockId) -> GetTransactionReceiptsByBlockInput {
+        GetTransactionReceiptsByBlockInput {
+            block_id,
+            include_events: true,
+            include_messages: true,
+        }
+    }
+
+    #[test]
+    fn parse_input() {
+        let value = serde_json::json!({ "block_id": "latest" });
+        let parsed = GetTransactionReceiptsByBlockInput::deserialize(crate::dto::Value::new(
+            value,
+            RpcVersion::PathfinderV01,
+        ))
+        .unwrap();
+        assert_eq!(parsed, input(BlockId::Latest));
+
+        let value = serde_json::json!({
+            "block_id": "pending",
+            "include_events": false,
+            "include_messages": false,
+        });
+        let parsed = GetTransactionReceiptsByBlockInput::deserialize(crate::dto::Value::new(
+            value,
+            RpcVersion::PathfinderV01,
+        ))
+        .unwrap();
+        assert_eq!(
+            parsed,
+            GetTransactionReceiptsByBlockInput {
+                block_id: BlockId::Pending,
+                include_events: false,
+                include_messages: false,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn matches_block_with_receipts() {
+        let context = RpcContext::for_tests();
+
+        let output = get_transaction_receipts_by_block(context.clone(), input(BlockId::Latest))
+            .await
+            .unwrap();
+        let block = crate::method::get_block_with_receipts(
+            context,
+            crate::method::get_block_with_receipts::Input {
+                block_id: BlockId::Latest,
+            },
+        )
+        .await
+        .unwrap();
+
+        let serializer = Serializer {
+            version: RpcVersion::PathfinderV01,
+        };
+        let output = output.serialize(serializer).unwrap();
+        let block = block.serialize(serializer).unwrap();
+
+        let receipts = output["receipts"].as_array().unwrap();
+        let expected = block["transactions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| &t["receipt"])
+            .collect::<Vec<_>>();
+        assert!(!receipts.is_empty());
+        assert_eq!(receipts.iter().collect::<Vec<_>>(), expected);
+        assert_eq!(output["block_hash"], block["block_hash"]);
+        assert_eq!(output["block_number"], block["block_number"]);
+    }
+
+    #[tokio::test]
+    async fn excludes_events_and_messages() {
+        let context = RpcContext::for_tests_with_pending().await;
+
+        let output = get_transaction_receipts_by_block(
+            context,
+            GetTransactionReceiptsByBlockInput {
+                block_id: BlockId::Pending,
+                include_events: false,
+                include_messages: false,
+            },
+        )
+        .await
+        .unwrap();
+
+        assert!(output.block_hash.is_none());
+        assert!(!output.receipts.is_empty());
+        for (_, receipt, events) in &output.receipts {
+            assert!(events.is_empty());
+            assert!(receipt.l2_to_l1_messages.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = RpcContext::for_tests();
+
+        let error = get_transaction_receipts_by_block(
+            context,
+            input(BlockId::Number(BlockNumber::new_or_panic(9999))),
+        )
+        .await
+        .unwrap_err();
+
+        assert_matches::assert_matches!(error, GetTransactionReceiptsByBlockError::BlockNotFound);
+    }
+}
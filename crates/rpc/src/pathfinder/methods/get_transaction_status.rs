@@ -2,6 +2,7 @@ use anyhow::Context;
 use pathfinder_common::TransactionHash;
 use starknet_gateway_types::reply::PendingBlock;
 
+use crate::circuit_breaker::GatewayCallError;
 use crate::context::RpcContext;
 
 #[derive(Debug, PartialEq, Eq)]
@@ -26,7 +27,7 @@ pub async fn get_transaction_status(
     input: GetGatewayTransactionInput,
 ) -> Result<TransactionStatus, GetGatewayTransactionError> {
     let span = tracing::Span::current();
-    let db_status = util::task::spawn_blocking(move |_| {
+    let db_status = util::task::spawn_blocking_storage(move |_| {
         let _g = span.enter();
 
         let mut db = context
@@ -75,12 +76,16 @@ pub async fn get_transaction_status(
     // Check gateway for rejected transactions.
     use starknet_gateway_client::GatewayApi;
     context
-        .sequencer
-        .transaction_status(input.transaction_hash)
+        .gateway_breaker
+        .call(context.sequencer.transaction_status(input.transaction_hash))
         .await
-        .context("Fetching transaction status from gateway")
         .map(|tx| tx.tx_status.into())
-        .map_err(GetGatewayTransactionError::Internal)
+        .map_err(|e| match e {
+            GatewayCallError::Unavailable => GetGatewayTransactionError::Custom(e.into()),
+            GatewayCallError::Gateway(e) => GetGatewayTransactionError::Internal(
+                anyhow::Error::new(e).context("Fetching transaction status from gateway"),
+            ),
+        })
 }
 
 fn pending_status(pending: &PendingBlock, tx_hash: &TransactionHash) -> Option<TransactionStatus> {
@@ -133,7 +138,7 @@ impl From<starknet_gateway_types::reply::Status> for TransactionStatus {
         match value {
             Status::NotReceived => Self::NotReceived,
             Status::Received => Self::Received,
-            Status::Pending => Self::Pending,
+            Status::Pending | Status::PreConfirmed => Self::Pending,
             Status::Rejected => Self::Rejected,
             Status::AcceptedOnL1 => Self::AcceptedOnL1,
             Status::AcceptedOnL2 => Self::AcceptedOnL2,
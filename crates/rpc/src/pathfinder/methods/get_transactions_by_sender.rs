@@ -0,0 +1,305 @@
+use std::str::FromStr;
+
+use anyhow::Context;
+use pathfinder_common::transaction::Transaction;
+use pathfinder_common::{BlockNumber, ContractAddress};
+
+use crate::context::RpcContext;
+use crate::dto::TransactionWithHash;
+
+/// The maximum number of transactions returned in a single page.
+const MAX_CHUNK_SIZE: usize = 1024;
+
+crate::error::generate_rpc_error_subset!(
+    GetTransactionsBySenderError: PageSizeTooBig,
+    InvalidContinuationToken
+);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetTransactionsBySenderInput {
+    sender_address: ContractAddress,
+    from_block: Option<BlockNumber>,
+    to_block: Option<BlockNumber>,
+    chunk_size: usize,
+    continuation_token: Option<String>,
+}
+
+impl crate::dto::DeserializeForVersion for GetTransactionsBySenderInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        use serde::de::Error;
+
+        value.deserialize_map(|value| {
+            Ok(Self {
+                sender_address: value.deserialize("sender_address").map(ContractAddress)?,
+                from_block: value
+                    .deserialize_optional("from_block")?
+                    .map(|n| {
+                        BlockNumber::new(n)
+                            .ok_or_else(|| serde_json::Error::custom("Invalid from_block"))
+                    })
+                    .transpose()?,
+                to_block: value
+                    .deserialize_optional("to_block")?
+                    .map(|n| {
+                        BlockNumber::new(n)
+                            .ok_or_else(|| serde_json::Error::custom("Invalid to_block"))
+                    })
+                    .transpose()?,
+                chunk_size: value.deserialize("chunk_size")?,
+                continuation_token: value.deserialize_optional_serde("continuation_token")?,
+            })
+        })
+    }
+}
+
+/// The position of the next transaction to return, encoded as
+/// `<block number>-<transaction index>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ContinuationToken {
+    block_number: BlockNumber,
+    transaction_index: usize,
+}
+
+impl FromStr for ContinuationToken {
+    type Err = GetTransactionsBySenderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (block_number, transaction_index) = s
+            .split_once('-')
+            .ok_or(GetTransactionsBySenderError::InvalidContinuationToken)?;
+        let block_number = block_number
+            .parse::<u64>()
+            .ok()
+            .and_then(BlockNumber::new)
+            .ok_or(GetTransactionsBySenderError::InvalidContinuationToken)?;
+        let transaction_index = transaction_index
+            .parse()
+            .map_err(|_| GetTransactionsBySenderError::InvalidContinuationToken)?;
+
+        Ok(Self {
+            block_number,
+            transaction_index,
+        })
+    }
+}
+
+impl std::fmt::Display for ContinuationToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.block_number.get(), self.transaction_index)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetTransactionsBySenderOutput {
+    transactions: Vec<SentTransaction>,
+    continuation_token: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct SentTransaction {
+    block_number: BlockNumber,
+    transaction_index: usize,
+    transaction: Transaction,
+}
+
+impl crate::dto::SerializeForVersion for GetTransactionsBySenderOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_iter(
+            "transactions",
+            self.transactions.len(),
+            &mut self.transactions.iter(),
+        )?;
+        serializer.serialize_optional("continuation_token", self.continuation_token.clone())?;
+        serializer.end()
+    }
+}
+
+impl crate::dto::SerializeForVersion for &SentTransaction {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("block_number", &self.block_number)?;
+        serializer.serialize_field("transaction_index", &self.transaction_index)?;
+        serializer.serialize_field("transaction", &TransactionWithHash(&self.transaction))?;
+        serializer.end()
+    }
+}
+
+/// Returns the transactions sent by an account in chain order, one page at a
+/// time.
+///
+/// Deploy and L1 handler transactions have no sender and are never returned,
+/// nor are transactions of the pending block.
+pub async fn get_transactions_by_sender(
+    context: RpcContext,
+    input: GetTransactionsBySenderInput,
+) -> Result<GetTransactionsBySenderOutput, GetTransactionsBySenderError> {
+    if input.chunk_size > MAX_CHUNK_SIZE {
+        return Err(GetTransactionsBySenderError::PageSizeTooBig);
+    }
+
+    let from = match &input.continuation_token {
+        Some(token) => {
+            let token = token.parse::<ContinuationToken>()?;
+            if input
+                .from_block
+                .is_some_and(|from| from > token.block_number)
+            {
+                return Err(GetTransactionsBySenderError::InvalidContinuationToken);
+            }
+            (token.block_number, token.transaction_index)
+        }
+        None => (input.from_block.unwrap_or(BlockNumber::GENESIS), 0),
+    };
+    let to_block = input.to_block.unwrap_or(BlockNumber::MAX);
+
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_storage(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .storage
+            .connection()
+            .context("Opening database connection")?;
+        let tx = db.transaction().context("Creating database transaction")?;
+
+        // Fetch one more position than requested to know whether there is a next
+        // page.
+        let mut positions = tx
+            .transactions_by_sender(input.sender_address, from, to_block, input.chunk_size + 1)
+            .context("Querying transactions by sender")?;
+
+        let continuation_token = if positions.len() > input.chunk_size {
+            positions.pop().map(|(block_number, transaction_index)| {
+                ContinuationToken {
+                    block_number,
+                    transaction_index,
+                }
+                .to_string()
+            })
+        } else {
+            None
+        };
+
+        let mut transactions = Vec::with_capacity(positions.len());
+        let mut block_transactions: Option<(BlockNumber, Vec<Transaction>)> = None;
+        for (block_number, transaction_index) in positions {
+            if block_transactions
+                .as_ref()
+                .map_or(true, |(number, _)| *number != block_number)
+            {
+                let txs = tx
+                    .transactions_for_block(block_number.into())
+                    .context("Querying transactions")?
+                    .context("Block transactions missing")?;
+                block_transactions = Some((block_number, txs));
+            }
+            let (_, txs) = block_transactions.as_ref().expect("Set above");
+            let transaction = txs
+                .get(transaction_index)
+                .context("Indexed transaction missing")?
+                .clone();
+
+            transactions.push(SentTransaction {
+                block_number,
+                transaction_index,
+                transaction,
+            });
+        }
+
+        Ok(GetTransactionsBySenderOutput {
+            transactions,
+            continuation_token,
+        })
+    })
+    .await
+    .context("Joining database task")?
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use serde_json::json;
+
+    use super::*;
+    use crate::dto::{DeserializeForVersion, SerializeForVersion, Serializer};
+    use crate::RpcVersion;
+
+    fn input(value: serde_json::Value) -> GetTransactionsBySenderInput {
+        GetTransactionsBySenderInput::deserialize(crate::dto::Value::new(
+            value,
+            RpcVersion::PathfinderV01,
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn paginates_transactions_of_sender() {
+        let context = RpcContext::for_tests();
+        let sender = contract_address_bytes!(b"contract 1");
+        let all = {
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.transactions_by_sender(sender, (BlockNumber::GENESIS, 0), BlockNumber::MAX, 100)
+                .unwrap()
+        };
+        assert!(all.len() > 1);
+
+        let first_page = get_transactions_by_sender(
+            context.clone(),
+            input(json!({"sender_address": sender, "chunk_size": 1})),
+        )
+        .await
+        .unwrap();
+        assert_eq!(first_page.transactions.len(), 1);
+        assert_eq!(first_page.transactions[0].block_number, all[0].0);
+        assert_eq!(first_page.transactions[0].transaction_index, all[0].1);
+        let token = first_page.continuation_token.unwrap();
+        assert_eq!(token, format!("{}-{}", all[1].0.get(), all[1].1));
+
+        let rest = get_transactions_by_sender(
+            context.clone(),
+            input(json!({
+                "sender_address": sender,
+                "chunk_size": 100,
+                "continuation_token": token,
+            })),
+        )
+        .await
+        .unwrap();
+        assert_eq!(rest.transactions.len(), all.len() - 1);
+        assert_eq!(rest.continuation_token, None);
+
+        let output = rest
+            .serialize(Serializer::new(RpcVersion::PathfinderV01))
+            .unwrap();
+        assert_eq!(
+            output["transactions"][0]["transaction"]["sender_address"],
+            json!(sender)
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_continuation_token() {
+        let context = RpcContext::for_tests();
+        let error = get_transactions_by_sender(
+            context,
+            input(json!({
+                "sender_address": "0x1",
+                "chunk_size": 10,
+                "continuation_token": "invalid",
+            })),
+        )
+        .await
+        .unwrap_err();
+        assert_matches::assert_matches!(
+            error,
+            GetTransactionsBySenderError::InvalidContinuationToken
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use std::time::{Duration, SystemTime};
+
+use anyhow::anyhow;
+
+use crate::context::RpcContext;
+use crate::log_filter::{CurrentLogFilter, LogFilter};
+
+crate::error::generate_rpc_error_subset!(LogFilterError:);
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SetLogFilterInput {
+    filter: String,
+    /// Seconds after which the initial filter is restored.
+    duration: Option<u64>,
+}
+
+impl crate::dto::DeserializeForVersion for SetLogFilterInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                filter: value.deserialize("filter")?,
+                duration: value.deserialize_optional("duration")?,
+            })
+        })
+    }
+}
+
+pub struct LogFilterOutput(CurrentLogFilter);
+
+impl crate::dto::SerializeForVersion for LogFilterOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let reverts_at = self.0.reverts_at.map(|reverts_at| {
+            reverts_at
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        });
+
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("filter", &self.0.filter)?;
+        serializer.serialize_field("initial_filter", &self.0.initial)?;
+        serializer.serialize_optional_with_null("reverts_at", reverts_at)?;
+        serializer.end()
+    }
+}
+
+fn log_filter(context: &RpcContext) -> Result<&LogFilter, LogFilterError> {
+    context.log_filter.as_ref().ok_or_else(|| {
+        LogFilterError::Custom(anyhow!(
+            "Log filter changes are disabled, see --rpc.log-filter-changes"
+        ))
+    })
+}
+
+/// Returns the tracing filter in effect.
+pub async fn get_log_filter(context: RpcContext) -> Result<LogFilterOutput, LogFilterError> {
+    Ok(LogFilterOutput(log_filter(&context)?.current()))
+}
+
+/// Replaces the tracing filter, taking `RUST_LOG` style directives. The
+/// initial filter is restored after `duration` seconds if set.
+pub async fn set_log_filter(
+    context: RpcContext,
+    input: SetLogFilterInput,
+) -> Result<LogFilterOutput, LogFilterError> {
+    let current = log_filter(&context)?
+        .set(&input.filter, input.duration.map(Duration::from_secs))
+        .map_err(|e| LogFilterError::Custom(e.context("Invalid log filter")))?;
+
+    Ok(LogFilterOutput(current))
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn disabled_by_default() {
+        let context = RpcContext::for_tests();
+
+        let error = set_log_filter(
+            context,
+            SetLogFilterInput {
+                filter: "pathfinder_executor=debug".to_owned(),
+                duration: Some(600),
+            },
+        )
+        .await
+        .unwrap_err();
+
+        assert_matches!(error, LogFilterError::Custom(_));
+    }
+
+    #[tokio::test]
+    async fn filter_is_replaced() {
+        let context = RpcContext::for_tests()
+            .with_log_filter(LogFilter::new("pathfinder=info".to_owned(), |_| Ok(())));
+
+        let output = set_log_filter(
+            context.clone(),
+            SetLogFilterInput {
+                filter: "pathfinder_executor=debug".to_owned(),
+                duration: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(output.0.filter, "pathfinder_executor=debug");
+
+        let output = get_log_filter(context).await.unwrap();
+        assert_eq!(
+            output.0,
+            CurrentLogFilter {
+                filter: "pathfinder_executor=debug".to_owned(),
+                initial: "pathfinder=info".to_owned(),
+                reverts_at: None,
+            }
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use anyhow::Context;
+use pathfinder_common::BlockId;
+use pathfinder_executor::{ExecutionState, L1BlobDataAvailability, TransactionExecutionError};
+use starknet_api::transaction::fields::Fee;
+
+use crate::context::RpcContext;
+use crate::dto::SerializeForVersion;
+use crate::error::ApplicationError;
+use crate::executor::ExecutionStateError;
+use crate::method::estimate_message_fee::{create_executor_transaction, MsgFromL1};
+use crate::method::simulate_transactions::TransactionSimulation;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SimulateL1MessageInput {
+    message: MsgFromL1,
+    /// The fee paid for the message on L1, in Wei.
+    paid_fee_on_l1: u128,
+    block_id: BlockId,
+}
+
+impl crate::dto::DeserializeForVersion for SimulateL1MessageInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                message: value.deserialize("message")?,
+                paid_fee_on_l1: value
+                    .deserialize::<crate::dto::U128Hex>("paid_fee_on_l1")?
+                    .0,
+                block_id: value.deserialize("block_id")?,
+            })
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct SimulateL1MessageOutput(pathfinder_executor::types::TransactionSimulation);
+
+impl SerializeForVersion for SimulateL1MessageOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        TransactionSimulation(&self.0).serialize(serializer)
+    }
+}
+
+/// Executes the L1 handler transaction consuming an L1 to L2 message on top
+/// of the state of the given block.
+///
+/// Returns the fee estimate along with the execution trace, which includes the
+/// resulting state diff.
+pub async fn simulate_l1_message(
+    context: RpcContext,
+    input: SimulateL1MessageInput,
+) -> Result<SimulateL1MessageOutput, SimulateL1MessageError> {
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_execution(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .execution_storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let (header, pending) = match input.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&db)
+                    .context("Querying pending data")?;
+
+                (pending.header(), Some(pending.state_update.clone()))
+            }
+            other => {
+                let block_id = other.try_into().expect("Only pending cast should fail");
+                let header = db
+                    .block_header(block_id)
+                    .context("Querying block header")?
+                    .ok_or(SimulateL1MessageError::BlockNotFound)?;
+
+                (header, None)
+            }
+        };
+
+        if !db.contract_exists(input.message.to_address, header.number.into())? {
+            return Err(SimulateL1MessageError::ContractNotFound);
+        }
+
+        let state = ExecutionState::simulation(
+            &db,
+            context.chain_id,
+            header,
+            pending,
+            L1BlobDataAvailability::Enabled,
+            context.config.custom_versioned_constants,
+            context.fee_tokens(),
+        )
+        .with_execution_policy(context.config.execution_policy.clone());
+
+        let transaction = create_executor_transaction(
+            input.message,
+            Fee(input.paid_fee_on_l1),
+            context.chain_id,
+        )?;
+
+        let mut simulations = pathfinder_executor::simulate(state, vec![transaction])
+            .map_err(|error| crate::executor::annotate_execution_error(&db, error))?;
+        let simulation = simulations
+            .pop()
+            .context("Simulation result missing for L1 handler transaction")?;
+
+        Ok(SimulateL1MessageOutput(simulation))
+    })
+    .await
+    .context("Simulating L1 message")?
+}
+
+#[derive(Debug)]
+pub enum SimulateL1MessageError {
+    Internal(anyhow::Error),
+    Custom(anyhow::Error),
+    BlockNotFound,
+    ContractNotFound,
+    TransactionExecutionError {
+        transaction_index: usize,
+        error: String,
+        error_stack: pathfinder_executor::ErrorStack,
+    },
+    ExecutionRefused(pathfinder_executor::ExecutionRefused),
+}
+
+impl From<anyhow::Error> for SimulateL1MessageError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::Internal(e)
+    }
+}
+
+impl From<TransactionExecutionError> for SimulateL1MessageError {
+    fn from(value: TransactionExecutionError) -> Self {
+        use TransactionExecutionError::*;
+        match value {
+            ExecutionError {
+                transaction_index,
+                error,
+                error_stack,
+            } => Self::TransactionExecutionError {
+                transaction_index,
+                error,
+                error_stack,
+            },
+            ExecutionRefused(refused) => Self::ExecutionRefused(refused),
+            Internal(e) => Self::Internal(e),
+            Custom(e) => Self::Custom(e),
+        }
+    }
+}
+
+impl From<ExecutionStateError> for SimulateL1MessageError {
+    fn from(error: ExecutionStateError) -> Self {
+        match error {
+            ExecutionStateError::BlockNotFound => Self::BlockNotFound,
+            ExecutionStateError::Internal(e) => Self::Internal(e),
+        }
+    }
+}
+
+impl From<SimulateL1MessageError> for ApplicationError {
+    fn from(e: SimulateL1MessageError) -> Self {
+        match e {
+            SimulateL1MessageError::Internal(internal) => Self::Internal(internal),
+            SimulateL1MessageError::Custom(internal) => Self::Custom(internal),
+            SimulateL1MessageError::BlockNotFound => Self::BlockNotFound,
+            SimulateL1MessageError::ContractNotFound => Self::ContractNotFound,
+            SimulateL1MessageError::TransactionExecutionError {
+                transaction_index,
+                error,
+                error_stack,
+            } => Self::TransactionExecutionError {
+                transaction_index,
+                error,
+                error_stack,
+            },
+            SimulateL1MessageError::ExecutionRefused(refused) => Self::ExecutionRefused(refused),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::prelude::*;
+    use pathfinder_common::L1DataAvailabilityMode;
+    use pathfinder_executor::types::TransactionTrace;
+    use pathfinder_storage::StorageBuilder;
+    use primitive_types::H160;
+
+    use super::*;
+
+    const CONTRACT_ADDRESS: ContractAddress =
+        contract_address!("0x57dde83c18c0efe7123c36a52d704cf27d5c38cdf0b1e1edc3b0dae3ee4e374");
+
+    /// Creates a chain of two blocks with a contract declaring an L1 handler
+    /// deployed in the second one.
+    fn setup() -> RpcContext {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let sierra_json = include_bytes!("../../../fixtures/contracts/l1_handler.json");
+        let casm_json = include_bytes!("../../../fixtures/contracts/l1_handler.casm");
+        let class_hash =
+            class_hash!("0x032908a85d43275f8509ba5f2acae88811b293463a3521dc05ab06d534b40848");
+        tx.insert_sierra_class(
+            &SierraHash(class_hash.0),
+            sierra_json,
+            &casm_hash!("0x0564bc2cef7e8e8ded01da5999b2028ac5962669a12e12b33aee1b17b0332435"),
+            casm_json,
+        )
+        .unwrap();
+
+        let genesis = BlockHeader::builder()
+            .number(BlockNumber::GENESIS)
+            .l1_da_mode(L1DataAvailabilityMode::Blob)
+            .finalize_with_hash(block_hash!("0xb00"));
+        tx.insert_block_header(&genesis).unwrap();
+
+        let header = genesis
+            .child_builder()
+            .timestamp(BlockTimestamp::new_or_panic(1))
+            .eth_l1_gas_price(GasPrice(2))
+            .eth_l1_data_gas_price(GasPrice(1))
+            .starknet_version(StarknetVersion::new(0, 13, 1, 0))
+            .l1_da_mode(L1DataAvailabilityMode::Blob)
+            .finalize_with_hash(block_hash!("0xb01"));
+        tx.insert_block_header(&header).unwrap();
+        tx.insert_state_update(
+            header.number,
+            &StateUpdate::default().with_deployed_contract(CONTRACT_ADDRESS, class_hash),
+        )
+        .unwrap();
+
+        tx.commit().unwrap();
+        drop(db);
+
+        RpcContext::for_tests().with_storage(storage)
+    }
+
+    fn input(to_address: ContractAddress, block_number: u64) -> SimulateL1MessageInput {
+        SimulateL1MessageInput {
+            message: MsgFromL1 {
+                from_address: EthereumAddress(H160::zero()),
+                to_address,
+                entry_point_selector: EntryPoint::hashed(b"my_l1_handler"),
+                payload: vec![call_param!("0xa")],
+            },
+            paid_fee_on_l1: 1_000_000,
+            block_id: BlockId::Number(BlockNumber::new_or_panic(block_number)),
+        }
+    }
+
+    #[tokio::test]
+    async fn returns_l1_handler_trace() {
+        let context = setup();
+
+        let output = simulate_l1_message(context, input(CONTRACT_ADDRESS, 1))
+            .await
+            .unwrap();
+        assert_matches::assert_matches!(
+            output.0.trace,
+            TransactionTrace::L1Handler(trace) if trace.function_invocation.is_some()
+        );
+        assert_eq!(
+            output.0.fee_estimation.unit,
+            pathfinder_executor::types::PriceUnit::Wei
+        );
+    }
+
+    #[tokio::test]
+    async fn contract_not_found() {
+        let context = setup();
+
+        let error = simulate_l1_message(context, input(contract_address!("0xdead"), 1))
+            .await
+            .unwrap_err();
+        assert_matches::assert_matches!(error, SimulateL1MessageError::ContractNotFound);
+    }
+
+    #[tokio::test]
+    async fn block_not_found() {
+        let context = setup();
+
+        let error = simulate_l1_message(context, input(CONTRACT_ADDRESS, 2))
+            .await
+            .unwrap_err();
+        assert_matches::assert_matches!(error, SimulateL1MessageError::BlockNotFound);
+    }
+}
@@ -0,0 +1,397 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use axum::async_trait;
+use pathfinder_common::{BlockNumber, ContractAddress, StateUpdate, StorageAddress, StorageValue};
+use tokio::sync::mpsc;
+
+use crate::context::RpcContext;
+use crate::jsonrpc::{RpcError, RpcSubscriptionFlow, SubscriptionMessage};
+use crate::method::REORG_SUBSCRIPTION_NAME;
+use crate::Reorg;
+
+pub struct SubscribeStorageChanges;
+
+#[derive(Debug, Clone)]
+pub struct Params {
+    contract_address: ContractAddress,
+    /// Only changes to these keys are sent if set.
+    keys: Option<HashSet<StorageAddress>>,
+}
+
+impl crate::dto::DeserializeForVersion for Params {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Params {
+                contract_address: ContractAddress(value.deserialize("contract_address")?),
+                keys: value
+                    .deserialize_optional_array("keys", |key| {
+                        Ok(StorageAddress(key.deserialize()?))
+                    })?
+                    .map(|keys| keys.into_iter().collect()),
+            })
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageChange {
+    contract_address: ContractAddress,
+    key: StorageAddress,
+    old_value: StorageValue,
+    new_value: StorageValue,
+    block_number: BlockNumber,
+    /// The change is part of the pending block and may still be dropped or
+    /// superseded.
+    pending: bool,
+}
+
+#[derive(Debug)]
+pub enum Notification {
+    StorageChange(StorageChange),
+    Reorg(Arc<Reorg>),
+}
+
+impl crate::dto::SerializeForVersion for Notification {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        match self {
+            Self::StorageChange(change) => {
+                let mut serializer = serializer.serialize_struct()?;
+                serializer.serialize_field("contract_address", &change.contract_address)?;
+                serializer.serialize_field("key", &change.key)?;
+                serializer.serialize_field("old_value", &change.old_value)?;
+                serializer.serialize_field("new_value", &change.new_value)?;
+                serializer.serialize_field("block_number", &change.block_number)?;
+                serializer.serialize_field("pending", &change.pending)?;
+                serializer.end()
+            }
+            Self::Reorg(reorg) => reorg.serialize(serializer),
+        }
+    }
+}
+
+const SUBSCRIPTION_NAME: &str = "pathfinder_subscriptionStorageChanges";
+
+#[async_trait]
+impl RpcSubscriptionFlow for SubscribeStorageChanges {
+    type Params = Params;
+    type Notification = Notification;
+
+    async fn subscribe(
+        state: RpcContext,
+        params: Self::Params,
+        tx: mpsc::Sender<SubscriptionMessage<Self::Notification>>,
+    ) -> Result<(), RpcError> {
+        let mut headers = state.notifications.block_headers.subscribe();
+        let mut reorgs = state.notifications.reorgs.subscribe();
+        let mut pending_data = state.pending_data.0.clone();
+        // Values already sent for the current pending block. The pending block is
+        // updated incrementally, so only keys whose value changed since the
+        // previous update are sent again.
+        let mut pending_block = BlockNumber::GENESIS;
+        let mut pending_sent = HashMap::new();
+        let mut pending_closed = false;
+        loop {
+            let changes = tokio::select! {
+                reorg = reorgs.recv() => {
+                    match reorg {
+                        Ok(reorg) => {
+                            let block_number = reorg.first_block_number;
+                            if tx.send(SubscriptionMessage {
+                                notification: Notification::Reorg(reorg),
+                                block_number,
+                                subscription_name: REORG_SUBSCRIPTION_NAME,
+                            }).await.is_err() {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            tracing::debug!(
+                                "Error receiving reorg from notifications channel, node might be \
+                                 lagging: {:?}",
+                                e
+                            );
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                header = headers.recv() => {
+                    match header {
+                        Ok(header) => {
+                            // The pending block has been finalized, its changes are sent
+                            // once more as part of the block.
+                            pending_sent.clear();
+                            block_changes(&state, &params, header.number).await?
+                        }
+                        Err(e) => {
+                            tracing::debug!(
+                                "Error receiving block header from notifications channel, node \
+                                 might be lagging: {:?}",
+                                e
+                            );
+                            break;
+                        }
+                    }
+                }
+                changed = pending_data.changed(), if !pending_closed => {
+                    if changed.is_err() {
+                        // Keep sending the changes of new blocks.
+                        tracing::debug!("Pending data channel closed");
+                        pending_closed = true;
+                        continue;
+                    }
+                    pending_changes(&state, &params)
+                        .await?
+                        .into_iter()
+                        .filter(|change| {
+                            if change.block_number != pending_block {
+                                pending_block = change.block_number;
+                                pending_sent.clear();
+                            }
+                            pending_sent.insert(change.key, change.new_value)
+                                != Some(change.new_value)
+                        })
+                        .collect()
+                }
+            };
+
+            for change in changes {
+                let block_number = change.block_number;
+                if tx
+                    .send(SubscriptionMessage {
+                        notification: Notification::StorageChange(change),
+                        block_number,
+                        subscription_name: SUBSCRIPTION_NAME,
+                    })
+                    .await
+                    .is_err()
+                {
+                    // Subscription has been closed.
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the changes to the subscribed storage made by block `number`.
+async fn block_changes(
+    state: &RpcContext,
+    params: &Params,
+    number: BlockNumber,
+) -> Result<Vec<StorageChange>, RpcError> {
+    let storage = state.storage.clone();
+    let params = params.clone();
+    util::task::spawn_blocking_storage(move |_| -> Result<_, RpcError> {
+        let mut conn = storage.connection().map_err(RpcError::InternalError)?;
+        let db = conn.transaction().map_err(RpcError::InternalError)?;
+        let Some(state_update) = db
+            .state_update(number.into())
+            .map_err(RpcError::InternalError)?
+        else {
+            // The block has already been reorged away.
+            return Ok(vec![]);
+        };
+        storage_changes(&db, &params, &state_update, number, false).map_err(RpcError::InternalError)
+    })
+    .await
+    .map_err(|e| RpcError::InternalError(e.into()))?
+}
+
+/// Returns the changes to the subscribed storage made by the current pending
+/// block.
+async fn pending_changes(
+    state: &RpcContext,
+    params: &Params,
+) -> Result<Vec<StorageChange>, RpcError> {
+    let storage = state.storage.clone();
+    let pending_data = state.pending_data.clone();
+    let params = params.clone();
+    util::task::spawn_blocking_storage(move |_| -> Result<_, RpcError> {
+        let mut conn = storage.connection().map_err(RpcError::InternalError)?;
+        let db = conn.transaction().map_err(RpcError::InternalError)?;
+        let pending = pending_data.get(&db).map_err(RpcError::InternalError)?;
+        storage_changes(&db, &params, &pending.state_update, pending.number, true)
+            .map_err(RpcError::InternalError)
+    })
+    .await
+    .map_err(|e| RpcError::InternalError(e.into()))?
+}
+
+/// Reads the values the subscribed keys had before `block_number` was applied.
+fn storage_changes(
+    db: &pathfinder_storage::Transaction<'_>,
+    params: &Params,
+    state_update: &StateUpdate,
+    block_number: BlockNumber,
+    pending: bool,
+) -> anyhow::Result<Vec<StorageChange>> {
+    let writes = state_update
+        .contract_updates
+        .get(&params.contract_address)
+        .map(|update| &update.storage)
+        .or_else(|| {
+            state_update
+                .system_contract_updates
+                .get(&params.contract_address)
+                .map(|update| &update.storage)
+        });
+    let Some(writes) = writes else {
+        return Ok(vec![]);
+    };
+
+    let mut changes = Vec::new();
+    for (&key, &new_value) in writes {
+        if params
+            .keys
+            .as_ref()
+            .is_some_and(|keys| !keys.contains(&key))
+        {
+            continue;
+        }
+        let old_value = match block_number.parent() {
+            Some(parent) => db
+                .storage_value(parent.into(), params.contract_address, key)?
+                .unwrap_or_default(),
+            None => StorageValue::ZERO,
+        };
+        changes.push(StorageChange {
+            contract_address: params.contract_address,
+            key,
+            old_value,
+            new_value,
+            block_number,
+            pending,
+        });
+    }
+    // Keep the notification order deterministic.
+    changes.sort_by_key(|change| change.key);
+
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{BlockHeader, StateUpdate};
+    use pathfinder_storage::StorageBuilder;
+    use starknet_gateway_types::reply::PendingBlock;
+
+    use super::*;
+    use crate::pending::PendingData;
+
+    #[tokio::test]
+    async fn sends_changes_of_blocks_and_pending_block() {
+        let contract = contract_address!("0xc");
+        let key = storage_address!("0x1");
+        let other_key = storage_address!("0x2");
+
+        let storage = StorageBuilder::in_memory().unwrap();
+        let genesis = BlockHeader::builder().finalize_with_hash(block_hash!("0x0"));
+        let block1 = genesis
+            .child_builder()
+            .finalize_with_hash(block_hash!("0x1"));
+        {
+            let mut db = storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.insert_block_header(&genesis).unwrap();
+            tx.insert_state_update(
+                genesis.number,
+                &StateUpdate::default()
+                    .with_storage_update(contract, key, storage_value!("0x10"))
+                    .with_storage_update(contract, other_key, storage_value!("0x20")),
+            )
+            .unwrap();
+            tx.insert_block_header(&block1).unwrap();
+            tx.insert_state_update(
+                block1.number,
+                &StateUpdate::default()
+                    .with_storage_update(contract, key, storage_value!("0x11"))
+                    .with_storage_update(contract, other_key, storage_value!("0x21"))
+                    .with_storage_update(contract_address!("0xd"), key, storage_value!("0x1")),
+            )
+            .unwrap();
+            tx.commit().unwrap();
+        }
+
+        let (pending_tx, pending_rx) = tokio::sync::watch::channel(PendingData::default());
+        let context = RpcContext::for_tests()
+            .with_storage(storage)
+            .with_pending_data(pending_rx);
+        let params = Params {
+            contract_address: contract,
+            keys: Some(HashSet::from([key])),
+        };
+        let (tx, mut rx) = mpsc::channel(16);
+        tokio::spawn(SubscribeStorageChanges::subscribe(
+            context.clone(),
+            params,
+            tx,
+        ));
+        // Give the subscription time to subscribe to the notifications.
+        while context.notifications.block_headers.receiver_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+        context
+            .notifications
+            .block_headers
+            .send(block1.clone().into())
+            .unwrap();
+
+        let message = rx.recv().await.unwrap();
+        assert_eq!(message.block_number, block1.number);
+        let Notification::StorageChange(change) = message.notification else {
+            panic!("Expected a storage change");
+        };
+        assert_eq!(
+            change,
+            StorageChange {
+                contract_address: contract,
+                key,
+                old_value: storage_value!("0x10"),
+                new_value: storage_value!("0x11"),
+                block_number: block1.number,
+                pending: false,
+            }
+        );
+
+        let pending = PendingData {
+            block: PendingBlock {
+                parent_hash: block1.hash,
+                ..Default::default()
+            }
+            .into(),
+            state_update: StateUpdate::default()
+                .with_storage_update(contract, key, storage_value!("0x12"))
+                .into(),
+            number: block1.number + 1,
+        };
+        pending_tx.send(pending.clone()).unwrap();
+
+        let message = rx.recv().await.unwrap();
+        let Notification::StorageChange(change) = message.notification else {
+            panic!("Expected a storage change");
+        };
+        assert_eq!(
+            change,
+            StorageChange {
+                contract_address: contract,
+                key,
+                old_value: storage_value!("0x11"),
+                new_value: storage_value!("0x12"),
+                block_number: block1.number + 1,
+                pending: true,
+            }
+        );
+
+        // Unchanged values of the same pending block are not sent again.
+        pending_tx.send(pending).unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,269 @@
+use anyhow::Context;
+use pathfinder_common::transaction::ResourceBound;
+use pathfinder_common::{GasPrice, ResourceAmount, ResourcePricePerUnit};
+use pathfinder_executor::types::FeeEstimate;
+use pathfinder_executor::{ExecutionState, L1BlobDataAvailability};
+use primitive_types::U256;
+
+use super::get_gas_price_estimate::{estimate_gas_prices, GetGasPriceEstimateError};
+use crate::context::RpcContext;
+use crate::method::estimate_fee::{EstimateFeeError, SimulationFlag};
+use crate::types::request::BroadcastedTransaction;
+
+/// Margin added on top of the simulated resource usage, in percent, to account
+/// for the state changing between the simulation and the inclusion of the
+/// transaction.
+const AMOUNT_MARGIN_PERCENT: u64 = 10;
+
+/// Factor applied to the expected gas prices. As with EIP-1559, doubling the
+/// price keeps the transaction valid through several blocks of rising prices.
+const PRICE_MULTIPLIER: u128 = 2;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct SuggestMaxFeeInput {
+    transaction: BroadcastedTransaction,
+    simulation_flags: Vec<SimulationFlag>,
+}
+
+impl crate::dto::DeserializeForVersion for SuggestMaxFeeInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                transaction: value.deserialize("transaction")?,
+                simulation_flags: value
+                    .deserialize_optional_array("simulation_flags", SimulationFlag::deserialize)?
+                    .unwrap_or_default(),
+            })
+        })
+    }
+}
+
+/// Suggested resource bounds of a V3 transaction.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SuggestMaxFeeOutput {
+    l1_gas: ResourceBound,
+    l1_data_gas: ResourceBound,
+    l2_gas: ResourceBound,
+}
+
+impl crate::dto::SerializeForVersion for SuggestMaxFeeOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("l1_gas", &self.l1_gas)?;
+        serializer.serialize_field("l1_data_gas", &self.l1_data_gas)?;
+        serializer.serialize_field("l2_gas", &self.l2_gas)?;
+        serializer.end()
+    }
+}
+
+/// Suggests resource bounds for a V3 transaction.
+///
+/// The transaction is simulated on top of the pending state. The amounts are
+/// the simulated resource usage plus a margin, and the prices are a multiple
+/// of the higher of the pending block's price and the forecast of
+/// `pathfinder_getGasPriceEstimate`.
+pub async fn suggest_max_fee(
+    context: RpcContext,
+    input: SuggestMaxFeeInput,
+) -> Result<SuggestMaxFeeOutput, EstimateFeeError> {
+    if input.transaction.version().without_query_version() != 3 {
+        return Err(EstimateFeeError::Custom(anyhow::anyhow!(
+            "Resource bounds can only be suggested for V3 transactions"
+        )));
+    }
+
+    let span = tracing::Span::current();
+    util::task::spawn_blocking_execution(move |_| {
+        let _g = span.enter();
+        let mut db = context
+            .execution_storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+
+        let pending = context
+            .pending_data
+            .get(&db)
+            .context("Querying pending data")?;
+        let header = pending.header();
+
+        let prices = estimate_gas_prices(&db, header.clone()).map_err(|error| match error {
+            GetGasPriceEstimateError::NoBlocks => EstimateFeeError::BlockNotFound,
+            GetGasPriceEstimateError::Internal(e) => EstimateFeeError::Internal(e),
+            GetGasPriceEstimateError::Custom(e) => EstimateFeeError::Custom(e),
+        })?;
+
+        let state = ExecutionState::simulation(
+            &db,
+            context.chain_id,
+            header.clone(),
+            Some(pending.state_update.clone()),
+            L1BlobDataAvailability::Enabled,
+            context.config.custom_versioned_constants.clone(),
+            context.fee_tokens(),
+        )
+        .with_execution_policy(context.config.execution_policy.clone());
+
+        let skip_validate = input
+            .simulation_flags
+            .iter()
+            .any(|flag| flag == &SimulationFlag::SkipValidate);
+        let transaction = crate::executor::map_broadcasted_transaction(
+            &input.transaction,
+            context.chain_id,
+            skip_validate,
+            true,
+        )?;
+
+        let FeeEstimate {
+            l1_gas_consumed,
+            l1_data_gas_consumed,
+            l2_gas_consumed,
+            ..
+        } = pathfinder_executor::estimate(state, vec![transaction])
+            .map_err(|error| crate::executor::annotate_execution_error(&db, error))?
+            .pop()
+            .context("Missing fee estimate")?;
+
+        Ok(SuggestMaxFeeOutput {
+            l1_gas: resource_bound(
+                l1_gas_consumed,
+                header.strk_l1_gas_price,
+                prices.l1_gas.suggested.price_in_fri,
+            ),
+            l1_data_gas: resource_bound(
+                l1_data_gas_consumed,
+                header.strk_l1_data_gas_price,
+                prices.l1_data_gas.suggested.price_in_fri,
+            ),
+            l2_gas: resource_bound(
+                l2_gas_consumed,
+                header.strk_l2_gas_price,
+                prices.l2_gas.suggested.price_in_fri,
+            ),
+        })
+    })
+    .await
+    .context("Executing transaction")?
+}
+
+fn resource_bound(consumed: U256, current: GasPrice, forecast: GasPrice) -> ResourceBound {
+    let consumed = u64::try_from(consumed).unwrap_or(u64::MAX);
+    let margin = consumed.saturating_mul(AMOUNT_MARGIN_PERCENT).div_ceil(100);
+    let price = current.0.max(forecast.0);
+
+    ResourceBound {
+        max_amount: ResourceAmount(consumed.saturating_add(margin)),
+        max_price_per_unit: ResourcePricePerUnit(price.saturating_mul(PRICE_MULTIPLIER)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::prelude::*;
+    use pathfinder_common::Tip;
+
+    use super::*;
+    use crate::context::ETH_FEE_TOKEN_ADDRESS;
+    use crate::types::request::{
+        BroadcastedInvokeTransaction,
+        BroadcastedInvokeTransactionV1,
+        BroadcastedInvokeTransactionV3,
+    };
+    use crate::types::{DataAvailabilityMode, ResourceBounds};
+
+    /// Calls `balanceOf` of the ETH fee token through the test account.
+    fn invoke_calldata(account_contract_address: ContractAddress) -> Vec<CallParam> {
+        vec![
+            CallParam(*ETH_FEE_TOKEN_ADDRESS.get()),
+            CallParam(EntryPoint::hashed(b"balanceOf").0),
+            call_param!("1"),
+            CallParam(*account_contract_address.get()),
+        ]
+    }
+
+    #[test]
+    fn resource_bound_adds_margins() {
+        let bound = resource_bound(U256::from(1000), GasPrice(5), GasPrice(7));
+        assert_eq!(bound.max_amount, ResourceAmount(1100));
+        assert_eq!(bound.max_price_per_unit, ResourcePricePerUnit(14));
+
+        let bound = resource_bound(U256::from(1), GasPrice(9), GasPrice(7));
+        assert_eq!(bound.max_amount, ResourceAmount(2));
+        assert_eq!(bound.max_price_per_unit, ResourcePricePerUnit(18));
+
+        let bound = resource_bound(U256::MAX, GasPrice(u128::MAX), GasPrice(0));
+        assert_eq!(bound.max_amount, ResourceAmount(u64::MAX));
+        assert_eq!(bound.max_price_per_unit, ResourcePricePerUnit(u128::MAX));
+    }
+
+    #[tokio::test]
+    async fn suggests_bounds_for_pending_block() {
+        let (context, _, account_contract_address, _) =
+            crate::test_setup::test_context_with_starknet_version(StarknetVersion::new(
+                0, 13, 1, 0,
+            ))
+            .await;
+
+        let transaction = BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V3(
+            BroadcastedInvokeTransactionV3 {
+                version: TransactionVersion::THREE,
+                signature: vec![],
+                sender_address: account_contract_address,
+                calldata: invoke_calldata(account_contract_address),
+                nonce: transaction_nonce!("0x0"),
+                resource_bounds: ResourceBounds::default(),
+                tip: Tip(0),
+                paymaster_data: vec![],
+                account_deployment_data: vec![],
+                nonce_data_availability_mode: DataAvailabilityMode::L1,
+                fee_data_availability_mode: DataAvailabilityMode::L1,
+            },
+        ));
+
+        let input = SuggestMaxFeeInput {
+            transaction,
+            simulation_flags: vec![SimulationFlag::SkipValidate],
+        };
+        let output = suggest_max_fee(context.clone(), input).await.unwrap();
+
+        let mut db = context.storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+        let pending = context.pending_data.get(&db).unwrap().header();
+
+        assert!(output.l1_gas.max_amount.0 > 0);
+        assert!(output.l1_gas.max_price_per_unit.0 >= 2 * pending.strk_l1_gas_price.0);
+        assert!(output.l1_data_gas.max_price_per_unit.0 >= 2 * pending.strk_l1_data_gas_price.0);
+    }
+
+    #[tokio::test]
+    async fn rejects_legacy_transactions() {
+        let (context, _, account_contract_address, _) =
+            crate::test_setup::test_context_with_starknet_version(StarknetVersion::new(
+                0, 13, 1, 0,
+            ))
+            .await;
+
+        let transaction = BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(
+            BroadcastedInvokeTransactionV1 {
+                nonce: transaction_nonce!("0x0"),
+                version: TransactionVersion::ONE,
+                max_fee: Fee::default(),
+                signature: vec![],
+                sender_address: account_contract_address,
+                calldata: invoke_calldata(account_contract_address),
+            },
+        ));
+
+        let input = SuggestMaxFeeInput {
+            transaction,
+            simulation_flags: vec![],
+        };
+        let error = suggest_max_fee(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(error, EstimateFeeError::Custom(_));
+    }
+}
@@ -0,0 +1,267 @@
+use anyhow::Context;
+use pathfinder_common::BlockId;
+use pathfinder_executor::TransactionExecutionError;
+
+use crate::compose_executor_transaction;
+use crate::context::RpcContext;
+use crate::executor::{can_trace_locally, ExecutionStateError};
+use crate::method::trace_block_transactions::{
+    trace_block_transactions,
+    TraceBlockTransactionsError,
+    TraceBlockTransactionsInput,
+    TraceBlockTransactionsOutput,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceBlockTransactionsRangeInput {
+    block_id: BlockId,
+    from_index: usize,
+    /// Inclusive.
+    to_index: usize,
+}
+
+impl crate::dto::DeserializeForVersion for TraceBlockTransactionsRangeInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_id: value.deserialize("block_id")?,
+                from_index: value.deserialize("from_index")?,
+                to_index: value.deserialize("to_index")?,
+            })
+        })
+    }
+}
+
+crate::error::generate_rpc_error_subset!(
+    TraceBlockTransactionsRangeError: BlockNotFound,
+    InvalidTxnIndex
+);
+
+impl From<TraceBlockTransactionsError> for TraceBlockTransactionsRangeError {
+    fn from(value: TraceBlockTransactionsError) -> Self {
+        match value {
+            TraceBlockTransactionsError::Internal(e) => Self::Internal(e),
+            TraceBlockTransactionsError::Custom(e) => Self::Custom(e),
+            TraceBlockTransactionsError::BlockNotFound => Self::BlockNotFound,
+        }
+    }
+}
+
+impl From<ExecutionStateError> for TraceBlockTransactionsRangeError {
+    fn from(value: ExecutionStateError) -> Self {
+        match value {
+            ExecutionStateError::BlockNotFound => Self::BlockNotFound,
+            ExecutionStateError::Internal(e) => Self::Internal(e),
+        }
+    }
+}
+
+impl From<TransactionExecutionError> for TraceBlockTransactionsRangeError {
+    fn from(value: TransactionExecutionError) -> Self {
+        TraceBlockTransactionsError::from(value).into()
+    }
+}
+
+/// Traces the transactions of a block from `from_index` to `to_index`,
+/// inclusive.
+///
+/// Only the transactions up to `to_index` are executed, and traces are only
+/// produced for the requested range. If the whole block has been traced before
+/// the traces are taken from the trace cache instead.
+///
+/// Blocks which cannot be executed locally are traced in full by
+/// `starknet_traceBlockTransactions`, and the result is sliced.
+pub async fn trace_block_transactions_range(
+    context: RpcContext,
+    input: TraceBlockTransactionsRangeInput,
+) -> Result<TraceBlockTransactionsOutput, TraceBlockTransactionsRangeError> {
+    if input.from_index > input.to_index {
+        return Err(TraceBlockTransactionsRangeError::InvalidTxnIndex);
+    }
+    let range = input.from_index..input.to_index + 1;
+
+    let span = tracing::Span::current();
+    let local_context = context.clone();
+    let traces = util::task::spawn_blocking_execution(move |_| {
+        let _g = span.enter();
+        let context = local_context;
+
+        let mut db = context.execution_storage.connection()?;
+        let db = db.transaction()?;
+
+        let (header, transactions, cache) = match input.block_id {
+            BlockId::Pending => {
+                let pending = context
+                    .pending_data
+                    .get(&db)
+                    .context("Querying pending data")?;
+
+                (
+                    pending.header(),
+                    pending.block.transactions.clone(),
+                    // Can't use the cache for pending blocks since they have no block hash.
+                    pathfinder_executor::TraceCache::default(),
+                )
+            }
+            other => {
+                let block_id = other.try_into().expect("Only pending should fail");
+                let header = db
+                    .block_header(block_id)?
+                    .ok_or(TraceBlockTransactionsRangeError::BlockNotFound)?;
+
+                let transactions = db
+                    .transactions_for_block(block_id)?
+                    .context("Transaction data missing")?
+                    .into_iter()
+                    .map(Into::into)
+                    .collect::<Vec<_>>();
+
+                (header, transactions, context.cache.clone())
+            }
+        };
+
+        if range.end > transactions.len() {
+            return Err(TraceBlockTransactionsRangeError::InvalidTxnIndex);
+        }
+
+        if !can_trace_locally(header.starknet_version) {
+            return Ok(None);
+        }
+
+        let executor_transactions = transactions
+            .iter()
+            .take(range.end)
+            .map(|transaction| compose_executor_transaction(transaction, &db))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let hash = header.hash;
+        let state = pathfinder_executor::ExecutionState::trace(
+            &db,
+            context.chain_id,
+            header,
+            None,
+            context.config.custom_versioned_constants,
+            context.fee_tokens(),
+        );
+        match pathfinder_executor::trace_range(
+            state,
+            cache,
+            hash,
+            executor_transactions,
+            range.clone(),
+        ) {
+            Ok(traces) => Ok(Some(traces)),
+            Err(TransactionExecutionError::ExecutionError { .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    })
+    .await
+    .context("trace_block_transactions_range: fetch block & transactions")??;
+
+    if let Some(traces) = traces {
+        return Ok(TraceBlockTransactionsOutput {
+            traces,
+            include_state_diffs: true,
+        });
+    }
+
+    let mut output = trace_block_transactions(
+        context,
+        TraceBlockTransactionsInput {
+            block_id: input.block_id,
+        },
+    )
+    .await?;
+    if range.end > output.traces.len() {
+        return Err(TraceBlockTransactionsRangeError::InvalidTxnIndex);
+    }
+    output.traces.truncate(range.end);
+    output.traces.drain(..range.start);
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dto::{SerializeForVersion, Serializer};
+    use crate::method::trace_block_transactions::tests::setup_multi_tx_trace_test;
+    use crate::RpcVersion;
+
+    #[tokio::test]
+    async fn traces_requested_range() {
+        let (context, next_block_header, traces) = setup_multi_tx_trace_test().await.unwrap();
+
+        for (from_index, to_index) in [(0, 0), (1, 2), (0, 2), (2, 2)] {
+            let input = TraceBlockTransactionsRangeInput {
+                block_id: next_block_header.hash.into(),
+                from_index,
+                to_index,
+            };
+            let output = trace_block_transactions_range(context.clone(), input)
+                .await
+                .unwrap();
+
+            let expected = TraceBlockTransactionsOutput {
+                traces: traces[from_index..=to_index]
+                    .iter()
+                    .map(|t| (t.transaction_hash, t.trace_root.clone()))
+                    .collect(),
+                include_state_diffs: true,
+            };
+            let serializer = Serializer {
+                version: RpcVersion::V08,
+            };
+            pretty_assertions_sorted::assert_eq!(
+                output.serialize(serializer).unwrap(),
+                expected.serialize(serializer).unwrap(),
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn reuses_traces_of_the_whole_block() {
+        let (context, next_block_header, traces) = setup_multi_tx_trace_test().await.unwrap();
+
+        crate::method::trace_block_transactions(
+            context.clone(),
+            TraceBlockTransactionsInput {
+                block_id: next_block_header.hash.into(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let input = TraceBlockTransactionsRangeInput {
+            block_id: next_block_header.hash.into(),
+            from_index: 1,
+            to_index: 1,
+        };
+        let output = trace_block_transactions_range(context, input)
+            .await
+            .unwrap();
+
+        assert_eq!(output.traces.len(), 1);
+        assert_eq!(output.traces[0].0, traces[1].transaction_hash);
+    }
+
+    #[tokio::test]
+    async fn invalid_range() {
+        let (context, next_block_header, _) = setup_multi_tx_trace_test().await.unwrap();
+
+        for (from_index, to_index) in [(2, 1), (0, 3), (5, 5)] {
+            let input = TraceBlockTransactionsRangeInput {
+                block_id: next_block_header.hash.into(),
+                from_index,
+                to_index,
+            };
+            let error = trace_block_transactions_range(context.clone(), input)
+                .await
+                .unwrap_err();
+            assert_matches::assert_matches!(
+                error,
+                TraceBlockTransactionsRangeError::InvalidTxnIndex
+            );
+        }
+    }
+}
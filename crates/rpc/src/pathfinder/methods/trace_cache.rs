@@ -0,0 +1,231 @@
+use std::collections::HashSet;
+
+use anyhow::{anyhow, Context};
+use pathfinder_common::BlockHash;
+use pathfinder_executor::TransactionExecutionError;
+
+use crate::compose_executor_transaction;
+use crate::context::RpcContext;
+use crate::executor::can_trace_locally;
+
+/// The maximum number of blocks in a single warm-up request.
+const MAX_BLOCKS: usize = 1024;
+
+crate::error::generate_rpc_error_subset!(TraceCacheError:);
+
+fn ensure_enabled(context: &RpcContext) -> Result<(), TraceCacheError> {
+    if context.config.trace_cache_warmup {
+        Ok(())
+    } else {
+        Err(TraceCacheError::Custom(anyhow!(
+            "Trace cache warm-up is disabled, see --rpc.trace-cache-warmup"
+        )))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetTraceCacheBlocksOutput(Vec<BlockHash>);
+
+impl crate::dto::SerializeForVersion for GetTraceCacheBlocksOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        serializer.serialize_iter(self.0.len(), &mut self.0.iter().copied())
+    }
+}
+
+/// Lists the blocks whose traces are cached, the ones least likely to be
+/// evicted first. The list can be passed to `pathfinder_warmTraceCache` of
+/// another node.
+pub async fn get_trace_cache_blocks(
+    context: RpcContext,
+) -> Result<GetTraceCacheBlocksOutput, TraceCacheError> {
+    ensure_enabled(&context)?;
+
+    Ok(GetTraceCacheBlocksOutput(context.cache.cached_blocks()))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct WarmTraceCacheInput {
+    block_hashes: Vec<BlockHash>,
+}
+
+impl crate::dto::DeserializeForVersion for WarmTraceCacheInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                block_hashes: value.deserialize_array("block_hashes", |value| {
+                    Ok(BlockHash(value.deserialize()?))
+                })?,
+            })
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct WarmTraceCacheOutput {
+    queued: usize,
+}
+
+impl crate::dto::SerializeForVersion for WarmTraceCacheOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        serializer.serialize_field("queued", &self.queued)?;
+        serializer.end()
+    }
+}
+
+/// Traces the given blocks in the background so that their traces are
+/// cached, in the order given. Blocks which are cached already are skipped.
+pub async fn warm_trace_cache(
+    context: RpcContext,
+    input: WarmTraceCacheInput,
+) -> Result<WarmTraceCacheOutput, TraceCacheError> {
+    ensure_enabled(&context)?;
+    if input.block_hashes.len() > MAX_BLOCKS {
+        return Err(TraceCacheError::Custom(anyhow!(
+            "At most {MAX_BLOCKS} blocks can be warmed up at once"
+        )));
+    }
+
+    let mut seen = HashSet::new();
+    let block_hashes = input
+        .block_hashes
+        .into_iter()
+        .filter(|block_hash| seen.insert(*block_hash) && !context.cache.contains(block_hash))
+        .collect::<Vec<_>>();
+    let queued = block_hashes.len();
+
+    util::task::spawn(warm(context, block_hashes));
+
+    Ok(WarmTraceCacheOutput { queued })
+}
+
+async fn warm(context: RpcContext, block_hashes: Vec<BlockHash>) {
+    let total = block_hashes.len();
+    let mut warmed = 0;
+
+    for block_hash in block_hashes {
+        let context = context.clone();
+        let result =
+            util::task::spawn_blocking_execution(move |_| trace_into_cache(context, block_hash))
+                .await
+                .context("Joining blocking task")
+                .and_then(|result| result);
+
+        match result {
+            Ok(true) => warmed += 1,
+            Ok(false) => {}
+            Err(error) => {
+                tracing::debug!(block=%block_hash, ?error, "Failed to warm up trace cache");
+            }
+        }
+    }
+
+    tracing::info!(%warmed, %total, "Trace cache warm-up finished");
+}
+
+/// Traces a block into the cache. Returns false if the block is unknown or
+/// its traces come from the feeder gateway, which are not cached.
+fn trace_into_cache(context: RpcContext, block_hash: BlockHash) -> anyhow::Result<bool> {
+    let mut db = context
+        .execution_storage
+        .connection()
+        .context("Creating database connection")?;
+    let db = db.transaction().context("Creating database transaction")?;
+
+    let block_id = pathfinder_storage::BlockId::Hash(block_hash);
+    let Some(header) = db.block_header(block_id).context("Querying block header")? else {
+        return Ok(false);
+    };
+    if !can_trace_locally(header.starknet_version) {
+        return Ok(false);
+    }
+
+    let transactions = db
+        .transactions_for_block(block_id)
+        .context("Querying transactions")?
+        .context("Transaction data missing")?
+        .iter()
+        .map(|transaction| compose_executor_transaction(transaction, &db))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let state = pathfinder_executor::ExecutionState::trace(
+        &db,
+        context.chain_id,
+        header,
+        None,
+        context.config.custom_versioned_constants,
+        context.fee_tokens(),
+    );
+    match pathfinder_executor::trace(state, context.cache, block_hash, transactions) {
+        Ok(_) => Ok(true),
+        // Traces of such blocks are fetched from the feeder gateway instead.
+        Err(TransactionExecutionError::ExecutionError { .. }) => Ok(false),
+        Err(TransactionExecutionError::ExecutionRefused(refused)) => {
+            Err(anyhow!("Execution refused: {refused}"))
+        }
+        Err(TransactionExecutionError::Internal(e) | TransactionExecutionError::Custom(e)) => {
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use assert_matches::assert_matches;
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+    use crate::method::trace_block_transactions::tests::setup_multi_tx_trace_test;
+
+    #[tokio::test]
+    async fn disabled() {
+        let context = RpcContext::for_tests();
+
+        let error = get_trace_cache_blocks(context.clone()).await.unwrap_err();
+        assert_matches!(error, TraceCacheError::Custom(_));
+
+        let input = WarmTraceCacheInput {
+            block_hashes: vec![block_hash!("0x1")],
+        };
+        let error = warm_trace_cache(context, input).await.unwrap_err();
+        assert_matches!(error, TraceCacheError::Custom(_));
+    }
+
+    #[tokio::test]
+    async fn warm_up_and_export() {
+        let (mut context, header, _) = setup_multi_tx_trace_test().await.unwrap();
+        context.config.trace_cache_warmup = true;
+
+        let input = WarmTraceCacheInput {
+            block_hashes: vec![header.hash, block_hash_bytes!(b"unknown"), header.hash],
+        };
+        let output = warm_trace_cache(context.clone(), input).await.unwrap();
+        assert_eq!(output, WarmTraceCacheOutput { queued: 2 });
+
+        tokio::time::timeout(Duration::from_secs(30), async {
+            while context.cache.cached_blocks().is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let output = get_trace_cache_blocks(context.clone()).await.unwrap();
+        assert_eq!(output, GetTraceCacheBlocksOutput(vec![header.hash]));
+
+        // Cached blocks are not traced again.
+        let input = WarmTraceCacheInput {
+            block_hashes: vec![header.hash],
+        };
+        let output = warm_trace_cache(context, input).await.unwrap();
+        assert_eq!(output, WarmTraceCacheOutput { queued: 0 });
+    }
+}
@@ -0,0 +1,368 @@
+use pathfinder_common::TransactionHash;
+use pathfinder_executor::types::{
+    CallType,
+    EntryPointType,
+    ExecuteInvocation,
+    FunctionInvocation,
+    TransactionTrace,
+};
+use serde::de::Error;
+
+use crate::context::RpcContext;
+use crate::method::trace_transaction::{self, TraceTransactionError};
+
+/// What the duration of a call stands for, as the trace contains no wall clock
+/// timings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Weight {
+    /// Cairo VM steps, including those of inner calls.
+    #[default]
+    Steps,
+    /// L2 gas consumed, including that of inner calls.
+    L2Gas,
+}
+
+impl crate::dto::DeserializeForVersion for Weight {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        let value: String = value.deserialize()?;
+        match value.as_str() {
+            "STEPS" => Ok(Self::Steps),
+            "L2_GAS" => Ok(Self::L2Gas),
+            _ => Err(serde_json::Error::custom("expected STEPS or L2_GAS")),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TraceTransactionChromeInput {
+    transaction_hash: TransactionHash,
+    weight: Weight,
+}
+
+impl crate::dto::DeserializeForVersion for TraceTransactionChromeInput {
+    fn deserialize(value: crate::dto::Value) -> Result<Self, serde_json::Error> {
+        value.deserialize_map(|value| {
+            Ok(Self {
+                transaction_hash: value.deserialize("transaction_hash").map(TransactionHash)?,
+                weight: value.deserialize_optional("weight")?.unwrap_or_default(),
+            })
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TraceTransactionChromeOutput(serde_json::Value);
+
+impl crate::dto::SerializeForVersion for TraceTransactionChromeOutput {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+/// Returns the call tree of the transaction's trace as a Chrome trace-event
+/// document, which can be opened in perfetto, speedscope or
+/// `chrome://tracing`.
+///
+/// Each call is a complete event nested within its caller. Since executing a
+/// transaction does not record how long each call took, the duration of a call
+/// is its weight, i.e. the Cairo VM steps or L2 gas it consumed, reported as
+/// microseconds. Inner calls are laid out one after the other from the start
+/// of their caller.
+pub async fn trace_transaction_chrome(
+    context: RpcContext,
+    input: TraceTransactionChromeInput,
+) -> Result<TraceTransactionChromeOutput, TraceTransactionError> {
+    let trace = trace_transaction::trace_transaction(
+        context,
+        trace_transaction::Input {
+            transaction_hash: input.transaction_hash,
+        },
+    )
+    .await?
+    .into_trace();
+
+    Ok(TraceTransactionChromeOutput(chrome_trace(
+        &trace,
+        input.transaction_hash,
+        input.weight,
+    )))
+}
+
+/// A top-level part of executing a transaction.
+enum Phase<'a> {
+    Call(&'a FunctionInvocation),
+    Reverted(&'a str),
+}
+
+fn chrome_trace(
+    trace: &TransactionTrace,
+    transaction_hash: TransactionHash,
+    weight: Weight,
+) -> serde_json::Value {
+    let phases: Vec<(&str, Option<Phase<'_>>)> = match trace {
+        TransactionTrace::Declare(trace) => vec![
+            (
+                "validate",
+                trace.validate_invocation.as_ref().map(Phase::Call),
+            ),
+            (
+                "fee_transfer",
+                trace.fee_transfer_invocation.as_ref().map(Phase::Call),
+            ),
+        ],
+        TransactionTrace::DeployAccount(trace) => vec![
+            (
+                "validate",
+                trace.validate_invocation.as_ref().map(Phase::Call),
+            ),
+            (
+                "constructor",
+                trace.constructor_invocation.as_ref().map(Phase::Call),
+            ),
+            (
+                "fee_transfer",
+                trace.fee_transfer_invocation.as_ref().map(Phase::Call),
+            ),
+        ],
+        TransactionTrace::Invoke(trace) => {
+            let execute = match &trace.execute_invocation {
+                ExecuteInvocation::FunctionInvocation(invocation) => {
+                    invocation.as_ref().map(Phase::Call)
+                }
+                ExecuteInvocation::RevertedReason(reason) => Some(Phase::Reverted(reason)),
+            };
+            vec![
+                (
+                    "validate",
+                    trace.validate_invocation.as_ref().map(Phase::Call),
+                ),
+                ("execute", execute),
+                (
+                    "fee_transfer",
+                    trace.fee_transfer_invocation.as_ref().map(Phase::Call),
+                ),
+            ]
+        }
+        TransactionTrace::L1Handler(trace) => {
+            vec![(
+                "l1_handler",
+                trace.function_invocation.as_ref().map(Phase::Call),
+            )]
+        }
+    };
+
+    let mut events = Vec::new();
+    let mut start = 0;
+    for (name, phase) in phases {
+        match phase {
+            Some(Phase::Call(invocation)) => {
+                let index = events.len();
+                events.push(serde_json::Value::Null);
+                let end = push_invocation(&mut events, invocation, start, weight);
+                events[index] = serde_json::json!({
+                    "name": name,
+                    "cat": "phase",
+                    "ph": "X",
+                    "ts": start,
+                    "dur": end - start,
+                    "pid": 1,
+                    "tid": 1,
+                });
+                start = end;
+            }
+            // Reverted calls are not part of the trace, so there is nothing to
+            // lay out.
+            Some(Phase::Reverted(reason)) => events.push(serde_json::json!({
+                "name": format!("{name} reverted"),
+                "cat": "phase",
+                "ph": "i",
+                "s": "t",
+                "ts": start,
+                "pid": 1,
+                "tid": 1,
+                "args": { "revert_reason": reason },
+            })),
+            None => {}
+        }
+    }
+
+    serde_json::json!({
+        "traceEvents": events,
+        "otherData": {
+            "transaction_hash": transaction_hash,
+            "weight": match weight {
+                Weight::Steps => "STEPS",
+                Weight::L2Gas => "L2_GAS",
+            },
+        },
+    })
+}
+
+/// Pushes the events of the invocation and its inner calls, returning where
+/// the invocation ends.
+fn push_invocation(
+    events: &mut Vec<serde_json::Value>,
+    invocation: &FunctionInvocation,
+    start: u64,
+    weight: Weight,
+) -> u64 {
+    let index = events.len();
+    events.push(serde_json::Value::Null);
+
+    let mut inner_end = start;
+    for inner in &invocation.internal_calls {
+        inner_end = push_invocation(events, inner, inner_end, weight);
+    }
+
+    let steps = invocation.computation_resources.steps as u64;
+    let l2_gas = u64::try_from(invocation.execution_resources.l2_gas).unwrap_or(u64::MAX);
+    let own = match weight {
+        Weight::Steps => steps,
+        Weight::L2Gas => l2_gas,
+    };
+    // Inner calls must fit within their caller for the events to nest.
+    let end = inner_end.max(start.saturating_add(own));
+
+    events[index] = serde_json::json!({
+        "name": invocation.selector.to_hex_str(),
+        "cat": match invocation.call_type {
+            CallType::Call => "CALL",
+            CallType::Delegate => "DELEGATE",
+        },
+        "ph": "X",
+        "ts": start,
+        "dur": end - start,
+        "pid": 1,
+        "tid": 1,
+        "args": {
+            "contract_address": invocation.contract_address,
+            "class_hash": invocation.class_hash,
+            "caller_address": invocation.caller_address,
+            "entry_point_type": match invocation.entry_point_type {
+                EntryPointType::Constructor => "CONSTRUCTOR",
+                EntryPointType::External => "EXTERNAL",
+                EntryPointType::L1Handler => "L1_HANDLER",
+            },
+            "steps": steps,
+            "l2_gas": l2_gas,
+            "events": invocation.events.len(),
+            "messages": invocation.messages.len(),
+            "is_reverted": invocation.is_reverted,
+        },
+    });
+
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_crypto::Felt;
+    use pathfinder_executor::types::{
+        ComputationResources,
+        InnerCallExecutionResources,
+        InvokeTransactionTrace,
+    };
+
+    use super::*;
+
+    fn invocation(
+        selector: u64,
+        steps: usize,
+        internal_calls: Vec<FunctionInvocation>,
+    ) -> FunctionInvocation {
+        FunctionInvocation {
+            calldata: vec![],
+            contract_address: contract_address!("0x1"),
+            selector: Felt::from_u64(selector),
+            call_type: CallType::Call,
+            caller_address: Felt::ZERO,
+            internal_calls,
+            class_hash: None,
+            entry_point_type: EntryPointType::External,
+            events: vec![],
+            messages: vec![],
+            result: vec![],
+            computation_resources: ComputationResources {
+                steps,
+                ..Default::default()
+            },
+            execution_resources: InnerCallExecutionResources::default(),
+            is_reverted: false,
+        }
+    }
+
+    fn spans(trace: &serde_json::Value) -> Vec<(String, u64, u64)> {
+        trace["traceEvents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|event| {
+                (
+                    event["name"].as_str().unwrap().to_owned(),
+                    event["ts"].as_u64().unwrap(),
+                    event["dur"].as_u64().unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn inner_calls_nest_within_their_caller() {
+        let trace = TransactionTrace::Invoke(InvokeTransactionTrace {
+            validate_invocation: Some(invocation(1, 10, vec![])),
+            execute_invocation: ExecuteInvocation::FunctionInvocation(Some(invocation(
+                2,
+                100,
+                vec![
+                    invocation(3, 30, vec![invocation(4, 50, vec![])]),
+                    invocation(5, 20, vec![]),
+                ],
+            ))),
+            fee_transfer_invocation: None,
+            state_diff: Default::default(),
+            execution_resources: Default::default(),
+        });
+
+        let trace = chrome_trace(&trace, transaction_hash!("0x1"), Weight::Steps);
+
+        assert_eq!(
+            spans(&trace),
+            vec![
+                ("validate".to_owned(), 0, 10),
+                ("0x1".to_owned(), 0, 10),
+                ("execute".to_owned(), 10, 100),
+                ("0x2".to_owned(), 10, 100),
+                // Wider than its own weight to fit the inner call.
+                ("0x3".to_owned(), 10, 50),
+                ("0x4".to_owned(), 10, 50),
+                ("0x5".to_owned(), 60, 20),
+            ]
+        );
+    }
+
+    #[test]
+    fn reverted_execution_is_marked() {
+        let trace = TransactionTrace::Invoke(InvokeTransactionTrace {
+            validate_invocation: Some(invocation(1, 10, vec![])),
+            execute_invocation: ExecuteInvocation::RevertedReason("Out of gas".to_owned()),
+            fee_transfer_invocation: Some(invocation(2, 5, vec![])),
+            state_diff: Default::default(),
+            execution_resources: Default::default(),
+        });
+
+        let trace = chrome_trace(&trace, transaction_hash!("0x1"), Weight::Steps);
+
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert_eq!(events[4]["name"], "fee_transfer");
+        assert_eq!(events[4]["ts"], 10);
+        let reverted = &events[2];
+        assert_eq!(reverted["name"], "execute reverted");
+        assert_eq!(reverted["ph"], "i");
+        assert_eq!(reverted["ts"], 10);
+        assert_eq!(reverted["args"]["revert_reason"], "Out of gas");
+    }
+}
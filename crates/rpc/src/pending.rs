@@ -11,6 +11,11 @@ use tokio::sync::watch::Receiver as WatchReceiver;
 #[derive(Clone)]
 pub struct PendingWatcher(pub WatchReceiver<PendingData>);
 
+/// The pending block and its state update.
+///
+/// Sync stores the definitions of the classes declared in the pending block
+/// before publishing it, so they can be read from the database like any other
+/// class, regardless of the block they are declared in.
 #[derive(Clone, Default, Debug, PartialEq)]
 pub struct PendingData {
     pub block: Arc<PendingBlock>,
@@ -49,6 +54,15 @@ impl PendingData {
     }
 }
 
+/// Finality of the transactions in a pending block. Starting with Starknet
+/// 0.14 the block being built is pre-confirmed rather than pending.
+pub(crate) fn finality_status(block: &PendingBlock) -> crate::dto::TxnFinalityStatus {
+    match block.status {
+        Status::PreConfirmed => crate::dto::TxnFinalityStatus::PreConfirmed,
+        _ => crate::dto::TxnFinalityStatus::AcceptedOnL2,
+    }
+}
+
 impl PendingWatcher {
     pub fn new(receiver: WatchReceiver<PendingData>) -> Self {
         Self(receiver)
@@ -223,4 +237,29 @@ mod tests {
 
         pretty_assertions_sorted::assert_eq_sorted!(result, expected);
     }
+
+    #[test]
+    fn pre_confirmed_finality() {
+        use crate::dto::{SerializeForVersion, Serializer};
+        use crate::RpcVersion;
+
+        let pending = PendingBlock {
+            status: Status::Pending,
+            ..Default::default()
+        };
+        let pre_confirmed = PendingBlock {
+            status: Status::PreConfirmed,
+            ..Default::default()
+        };
+
+        let serialize = |block: &PendingBlock, version| {
+            finality_status(block)
+                .serialize(Serializer::new(version))
+                .unwrap()
+        };
+
+        assert_eq!(serialize(&pending, RpcVersion::V09), "ACCEPTED_ON_L2");
+        assert_eq!(serialize(&pre_confirmed, RpcVersion::V08), "ACCEPTED_ON_L2");
+        assert_eq!(serialize(&pre_confirmed, RpcVersion::V09), "PRE_CONFIRMED");
+    }
 }
@@ -0,0 +1,172 @@
+//! Archive of transactions submitted through this node which the gateway
+//! rejected.
+//!
+//! Rejected transactions are not part of any block, so without the archive
+//! nothing is left of them once the error has been returned to the client.
+//! Archived transactions can be looked up by hash together with the reason of
+//! their rejection.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use pathfinder_common::TransactionHash;
+use pathfinder_storage::RejectedTransaction;
+use starknet_gateway_types::error::SequencerError;
+
+use crate::context::RpcContext;
+use crate::types::request::BroadcastedTransaction;
+
+/// Archives `transaction` if the archive is enabled and the gateway rejected
+/// it with `error`.
+///
+/// Failing to archive a transaction is logged instead of being returned, so
+/// that the client still receives the rejection.
+pub(crate) async fn record(
+    context: &RpcContext,
+    transaction: BroadcastedTransaction,
+    error: &SequencerError,
+) {
+    if !context.config.rejected_transaction_archive
+        || !matches!(error, SequencerError::StarknetError(_))
+    {
+        return;
+    }
+
+    let json = match crate::outbox::to_json(&transaction) {
+        Ok(json) => json,
+        Err(error) => {
+            tracing::warn!(%error, "Failed to archive rejected transaction");
+            return;
+        }
+    };
+    let hash = transaction.into_common(context.chain_id).hash;
+
+    record_json(context, hash, json, error).await;
+}
+
+/// Like [record], for transactions which were serialized already.
+pub(crate) async fn record_json(
+    context: &RpcContext,
+    transaction_hash: TransactionHash,
+    transaction_json: Vec<u8>,
+    error: &SequencerError,
+) {
+    if !context.config.rejected_transaction_archive {
+        return;
+    }
+    let SequencerError::StarknetError(error) = error else {
+        return;
+    };
+
+    let error_code = match serde_json::to_value(&error.code) {
+        Ok(serde_json::Value::String(code)) => code,
+        _ => format!("{:?}", error.code),
+    };
+    let rejected = RejectedTransaction {
+        transaction_hash,
+        transaction_json,
+        error_code,
+        reason: error.message.clone(),
+        rejected_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    };
+
+    let storage = context.storage.clone();
+    let result = util::task::spawn_blocking_storage(move |_| {
+        let mut db = storage
+            .connection()
+            .context("Creating database connection")?;
+        let db = db.transaction().context("Creating database transaction")?;
+        db.insert_rejected_transaction(&rejected)?;
+        db.commit().context("Committing database transaction")
+    })
+    .await
+    .context("Joining blocking task")
+    .and_then(|result| result);
+
+    match result {
+        Ok(()) => {
+            tracing::debug!(%transaction_hash, "Archived rejected transaction");
+            metrics::increment_counter!("rpc_rejected_transactions_archived_total");
+        }
+        Err(error) => {
+            tracing::warn!(%transaction_hash, ?error, "Failed to archive rejected transaction");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{Fee, TransactionVersion};
+    use starknet_gateway_types::error::{KnownStarknetErrorCode, StarknetError};
+
+    use super::*;
+    use crate::types::request::{BroadcastedInvokeTransaction, BroadcastedInvokeTransactionV1};
+
+    fn transaction() -> BroadcastedTransaction {
+        BroadcastedTransaction::Invoke(BroadcastedInvokeTransaction::V1(
+            BroadcastedInvokeTransactionV1 {
+                version: TransactionVersion::ONE,
+                max_fee: Fee(felt!("0x123")),
+                signature: vec![transaction_signature_elem!("0x456")],
+                nonce: transaction_nonce!("0x1"),
+                sender_address: contract_address!("0xabc"),
+                calldata: vec![call_param!("0x1")],
+            },
+        ))
+    }
+
+    fn rejection() -> SequencerError {
+        SequencerError::StarknetError(StarknetError {
+            code: KnownStarknetErrorCode::InvalidTransactionNonce.into(),
+            message: "Invalid transaction nonce".to_owned(),
+        })
+    }
+
+    fn archived(context: &RpcContext, hash: TransactionHash) -> Option<RejectedTransaction> {
+        let mut db = context.storage.connection().unwrap();
+        let db = db.transaction().unwrap();
+        db.rejected_transaction(hash).unwrap()
+    }
+
+    #[tokio::test]
+    async fn rejections_are_archived() {
+        let mut context = RpcContext::for_tests();
+        context.config.rejected_transaction_archive = true;
+        let hash = transaction().into_common(context.chain_id).hash;
+
+        record(&context, transaction(), &rejection()).await;
+
+        let archived = archived(&context, hash).unwrap();
+        assert_eq!(
+            archived.error_code,
+            "StarknetErrorCode.INVALID_TRANSACTION_NONCE"
+        );
+        assert_eq!(archived.reason, "Invalid transaction nonce");
+        assert_eq!(
+            archived.transaction_json,
+            crate::outbox::to_json(&transaction()).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn nothing_is_archived_when_disabled_or_not_rejected() {
+        let mut context = RpcContext::for_tests();
+        let hash = transaction().into_common(context.chain_id).hash;
+
+        record(&context, transaction(), &rejection()).await;
+        assert_eq!(archived(&context, hash), None);
+
+        context.config.rejected_transaction_archive = true;
+        record(
+            &context,
+            transaction(),
+            &SequencerError::InvalidStarknetErrorVariant,
+        )
+        .await;
+        assert_eq!(archived(&context, hash), None);
+    }
+}
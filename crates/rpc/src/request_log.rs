@@ -0,0 +1,285 @@
+//! Structured logging of JSON-RPC requests for post-hoc analysis of traffic
+//! patterns.
+//!
+//! Each logged request records the method, a hash and the size of its params,
+//! how long it took and its outcome. Only a sample of requests is logged, but
+//! requests slower than the slow threshold always are. Params are included
+//! verbatim unless they exceed the configured size, in which case only their
+//! hash identifies them.
+//!
+//! Entries are emitted as `tracing` events with the `rpc_request_log` target
+//! and, if configured, appended as JSON lines to a file which is rotated once
+//! it reaches its maximum size. Writing to the file happens on a dedicated
+//! thread; entries are dropped instead of slowing down requests if it falls
+//! behind.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use anyhow::Context;
+use serde_json::value::RawValue;
+
+use crate::jsonrpc::{RpcError, RpcOutput};
+use crate::RpcVersion;
+
+/// Number of entries buffered for the file writer before entries are dropped.
+const FILE_QUEUE_CAPACITY: usize = 4096;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestLogConfig {
+    /// Fraction of requests logged, between 0 and 1.
+    pub sample_rate: f64,
+    /// Requests taking longer than this are logged regardless of sampling.
+    pub slow_threshold: Option<Duration>,
+    /// Params larger than this many bytes are left out of the entry.
+    pub max_params_size: usize,
+    pub file: Option<RequestLogFile>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestLogFile {
+    pub path: PathBuf,
+    /// The file is rotated once it exceeds this many bytes.
+    pub max_size: u64,
+    /// Number of rotated files kept besides the current one.
+    pub max_files: NonZeroUsize,
+}
+
+/// Disabled unless created from a [RequestLogConfig].
+#[derive(Clone, Default)]
+pub struct RequestLog(Option<Arc<Inner>>);
+
+struct Inner {
+    config: RequestLogConfig,
+    /// Number of requests seen, used to spread sampled requests evenly.
+    requests: AtomicU64,
+    file: Option<mpsc::SyncSender<String>>,
+}
+
+impl RequestLog {
+    /// Opens the log file, if any, and starts the thread writing to it.
+    pub fn new(config: RequestLogConfig) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            (0.0..=1.0).contains(&config.sample_rate),
+            "Sample rate must be between 0 and 1"
+        );
+
+        let file = match &config.file {
+            Some(file_config) => {
+                let writer = FileWriter::open(file_config.clone()).with_context(|| {
+                    format!("Opening request log {}", file_config.path.display())
+                })?;
+                let (sender, receiver) = mpsc::sync_channel(FILE_QUEUE_CAPACITY);
+                std::thread::Builder::new()
+                    .name("rpc-request-log".to_owned())
+                    .spawn(move || writer.run(receiver))
+                    .context("Spawning request log thread")?;
+                Some(sender)
+            }
+            None => None,
+        };
+
+        Ok(Self(Some(Arc::new(Inner {
+            config,
+            requests: AtomicU64::new(0),
+            file,
+        }))))
+    }
+
+    /// Logs the request if it is sampled or slow.
+    pub(crate) fn record(
+        &self,
+        method: &str,
+        params: Option<&RawValue>,
+        version: RpcVersion,
+        elapsed: Duration,
+        output: &Result<RpcOutput, RpcError>,
+    ) {
+        let Some(inner) = &self.0 else {
+            return;
+        };
+
+        let slow = inner
+            .config
+            .slow_threshold
+            .is_some_and(|threshold| elapsed > threshold);
+        let request = inner.requests.fetch_add(1, Ordering::Relaxed);
+        if !slow && !is_sampled(request, inner.config.sample_rate) {
+            return;
+        }
+
+        let params_size = params.map_or(0, |params| params.get().len());
+        let params_hash = format!(
+            "{:016x}",
+            fnv1a(params.map(RawValue::get).unwrap_or_default().as_bytes())
+        );
+        let params = params.filter(|_| params_size <= inner.config.max_params_size);
+        let outcome = match output {
+            Ok(_) => "ok".to_owned(),
+            Err(error) => error.code().to_string(),
+        };
+        let duration_ms = elapsed.as_secs_f64() * 1000.0;
+
+        tracing::info!(
+            target: "rpc_request_log",
+            %method,
+            version = version.to_str(),
+            %params_hash,
+            params_size,
+            params = params.map_or("<redacted>", RawValue::get),
+            duration_ms,
+            %outcome,
+            slow,
+            "RPC request"
+        );
+
+        if let Some(file) = &inner.file {
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let entry = serde_json::json!({
+                "timestamp": timestamp,
+                "method": method,
+                "version": version.to_str(),
+                "params_hash": params_hash,
+                "params_size": params_size,
+                "params": params,
+                "duration_ms": duration_ms,
+                "outcome": outcome,
+                "slow": slow,
+            });
+            if file.try_send(entry.to_string()).is_err() {
+                metrics::increment_counter!("rpc_request_log_dropped_total");
+            }
+        }
+    }
+}
+
+/// Whether the `request`th request is sampled. Sampled requests are spaced
+/// evenly, e.g. every fourth request at a rate of 0.25.
+fn is_sampled(request: u64, sample_rate: f64) -> bool {
+    let before = (request as f64 * sample_rate).floor();
+    let after = ((request + 1) as f64 * sample_rate).floor();
+    after > before
+}
+
+/// A hash which is stable across releases, so that entries can be compared
+/// between runs.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+struct FileWriter {
+    config: RequestLogFile,
+    file: BufWriter<File>,
+    size: u64,
+}
+
+impl FileWriter {
+    fn open(config: RequestLogFile) -> std::io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&config.path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            config,
+            file: BufWriter::new(file),
+            size,
+        })
+    }
+
+    fn run(mut self, entries: mpsc::Receiver<String>) {
+        while let Ok(entry) = entries.recv() {
+            // Everything queued meanwhile is written before flushing, which
+            // batches writes under load.
+            let result = std::iter::once(entry)
+                .chain(entries.try_iter())
+                .try_for_each(|entry| self.write(&entry))
+                .and_then(|_| self.file.flush());
+            if let Err(error) = result {
+                let path = self.config.path.display();
+                tracing::warn!(%error, %path, "Writing request log failed");
+            }
+        }
+    }
+
+    fn write(&mut self, entry: &str) -> std::io::Result<()> {
+        if self.size > 0 && self.size + entry.len() as u64 + 1 > self.config.max_size {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{entry}")?;
+        self.size += entry.len() as u64 + 1;
+        Ok(())
+    }
+
+    /// Renames `log` to `log.1`, `log.1` to `log.2` and so on, removing the
+    /// oldest file, and starts a new `log`.
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+
+        let max_files = self.config.max_files.get();
+        let _ = std::fs::remove_file(rotated_path(&self.config.path, max_files));
+        for i in (1..max_files).rev() {
+            let from = rotated_path(&self.config.path, i);
+            if from.exists() {
+                std::fs::rename(from, rotated_path(&self.config.path, i + 1))?;
+            }
+        }
+        std::fs::rename(&self.config.path, rotated_path(&self.config.path, 1))?;
+
+        *self = Self::open(self.config.clone())?;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(format!(".{index}"));
+    path.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sampled_requests_are_spread_evenly() {
+        let sampled: Vec<u64> = (0..12).filter(|i| is_sampled(*i, 0.25)).collect();
+        assert_eq!(sampled, vec![3, 7, 11]);
+
+        assert!((0..100).all(|i| is_sampled(i, 1.0)));
+        assert!((0..100).all(|i| !is_sampled(i, 0.0)));
+    }
+
+    #[test]
+    fn file_is_rotated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("requests.log");
+        let mut writer = FileWriter::open(RequestLogFile {
+            path: path.clone(),
+            max_size: 10,
+            max_files: NonZeroUsize::new(2).unwrap(),
+        })
+        .unwrap();
+
+        for entry in ["first", "second", "third", "fourth"] {
+            writer.write(entry).unwrap();
+        }
+        writer.file.flush().unwrap();
+
+        let read = |path: PathBuf| std::fs::read_to_string(path).unwrap();
+        assert_eq!(read(path.clone()), "fourth\n");
+        assert_eq!(read(rotated_path(&path, 1)), "third\n");
+        assert_eq!(read(rotated_path(&path, 2)), "second\n");
+        assert!(!rotated_path(&path, 3).exists());
+    }
+}
@@ -0,0 +1,391 @@
+//! Responses to queries for data addressed by hash, e.g. a block by its hash
+//! or a transaction receipt by its transaction hash.
+//!
+//! Public RPC traffic is dominated by repeated identical requests for such
+//! data, which does not change once it is final. Responses are cached by
+//! method, params and RPC version until the cache exceeds its memory budget, at
+//! which point the least recently used ones are evicted.
+//!
+//! Responses which still report data as only accepted on L2 are not cached,
+//! since their finality status changes once the block is accepted on L1.
+//! Neither are responses about transactions which are not in a stored block
+//! yet. The whole cache is cleared on a reorg, and responses to requests
+//! received before it are not cached.
+
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use pathfinder_common::TransactionHash;
+use pathfinder_storage::Storage;
+use serde_json::value::RawValue;
+use serde_json::Value;
+use tokio::sync::broadcast;
+use util::cache::{EvictionPolicy, WeightedCache};
+
+use crate::jsonrpc::{RawParams, RpcOutput};
+use crate::{Reorg, RpcVersion};
+
+/// Methods which take a block id as their first parameter.
+const BLOCK_METHODS: &[&str] = &[
+    "starknet_getBlockWithTxHashes",
+    "starknet_getBlockWithTxs",
+    "starknet_getBlockWithReceipts",
+    "starknet_getStateUpdate",
+    "starknet_getBlockTransactionCount",
+    "starknet_getTransactionByBlockIdAndIndex",
+    "starknet_getClass",
+    "starknet_traceBlockTransactions",
+];
+
+/// Methods which only take a transaction hash. They serve transactions of the
+/// pending block too.
+const TRANSACTION_METHODS: &[&str] = &[
+    "starknet_getTransactionByHash",
+    "starknet_getTransactionReceipt",
+    "starknet_traceTransaction",
+];
+
+/// Responses containing one of these may change without a reorg.
+const NON_FINAL_STATUSES: &[&str] = &[
+    "\"ACCEPTED_ON_L2\"",
+    "\"PENDING\"",
+    "\"PRE_CONFIRMED\"",
+    "\"CANDIDATE\"",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Request {
+    method: &'static str,
+    params: String,
+    version: &'static str,
+    /// The transaction requested from one of the [TRANSACTION_METHODS].
+    transaction: Option<TransactionHash>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CacheKey {
+    request: Request,
+    /// The number of reorgs the cache had seen when the request was received.
+    generation: u64,
+}
+
+impl CacheKey {
+    /// Whether the requested data is in a stored block. Transactions of the
+    /// pending block have no finality status to check, and the pending block
+    /// is replaced without a reorg.
+    pub async fn is_stored(&self, storage: &Storage) -> bool {
+        let Some(transaction) = self.request.transaction else {
+            // Blocks addressed by hash are stored.
+            return true;
+        };
+
+        let storage = storage.clone();
+        let stored = util::task::spawn_blocking_storage(move |_| -> anyhow::Result<bool> {
+            let mut db = storage.connection()?;
+            let db = db.transaction()?;
+            Ok(db.transaction_block_hash(transaction)?.is_some())
+        })
+        .await;
+
+        matches!(stored, Ok(Ok(true)))
+    }
+}
+
+/// Disabled unless created with a memory budget.
+#[derive(Clone, Default)]
+pub(crate) struct ResponseCache(Option<Arc<Mutex<Inner>>>);
+
+struct Inner {
+    max_size: usize,
+    entries: WeightedCache<Request, Box<RawValue>>,
+    /// Incremented whenever the cache is cleared on a reorg.
+    generation: u64,
+    reorgs: broadcast::Receiver<Arc<Reorg>>,
+}
+
+impl ResponseCache {
+    pub fn new(max_size: Option<NonZeroUsize>, reorgs: broadcast::Receiver<Arc<Reorg>>) -> Self {
+        Self(max_size.map(|max_size| {
+            Arc::new(Mutex::new(Inner {
+                max_size: max_size.get(),
+                entries: WeightedCache::new(None, Some(max_size), EvictionPolicy::Lru),
+                generation: 0,
+                reorgs,
+            }))
+        }))
+    }
+
+    /// The key of the request if its response can be cached.
+    pub fn key(
+        &self,
+        method: &'static str,
+        params: &RawParams<'_>,
+        version: RpcVersion,
+    ) -> Option<CacheKey> {
+        let inner = self.0.as_ref()?;
+
+        let params: Value = serde_json::from_str(params.0?.get()).ok()?;
+        let first_param = |name: &str| match &params {
+            Value::Array(params) => params.first(),
+            Value::Object(params) => params.get(name),
+            _ => None,
+        };
+        let transaction = if BLOCK_METHODS.contains(&method) {
+            if !first_param("block_id").is_some_and(|id| id.get("block_hash").is_some()) {
+                return None;
+            }
+            None
+        } else if TRANSACTION_METHODS.contains(&method) {
+            let hash = first_param("transaction_hash")?;
+            Some(serde_json::from_value(hash.clone()).ok()?)
+        } else {
+            return None;
+        };
+
+        let mut inner = inner.lock().unwrap();
+        inner.clear_on_reorg();
+
+        Some(CacheKey {
+            request: Request {
+                method,
+                // Normalized so that formatting does not matter.
+                params: params.to_string(),
+                version: version.to_str(),
+                transaction,
+            },
+            generation: inner.generation,
+        })
+    }
+
+    pub fn get(&self, key: &CacheKey) -> Option<RpcOutput> {
+        let mut inner = self.0.as_ref()?.lock().unwrap();
+        inner.clear_on_reorg();
+
+        let response = inner.entries.get(&key.request).cloned();
+
+        let counter = match response {
+            Some(_) => "rpc_response_cache_hits_total",
+            None => "rpc_response_cache_misses_total",
+        };
+        metrics::increment_counter!(counter, "method" => key.request.method);
+
+        response.map(RpcOutput::Raw)
+    }
+
+    /// Caches the response unless it is not final yet, larger than the whole
+    /// cache or the request was received before a reorg.
+    pub fn insert(&self, key: CacheKey, output: &RpcOutput) {
+        let Some(inner) = &self.0 else {
+            return;
+        };
+
+        let response = match output {
+            RpcOutput::Value(value) => match serde_json::value::to_raw_value(value) {
+                Ok(response) => response,
+                Err(_) => return,
+            },
+            RpcOutput::Raw(raw) => raw.clone(),
+        };
+        if NON_FINAL_STATUSES
+            .iter()
+            .any(|status| response.get().contains(status))
+        {
+            return;
+        }
+
+        let mut inner = inner.lock().unwrap();
+        inner.clear_on_reorg();
+        inner.insert(key, response);
+
+        metrics::gauge!(
+            "rpc_response_cache_size_bytes",
+            inner.entries.total_weight() as f64
+        );
+        metrics::gauge!("rpc_response_cache_entries", inner.entries.len() as f64);
+    }
+}
+
+impl Inner {
+    fn insert(&mut self, key: CacheKey, response: Box<RawValue>) {
+        // The response may have been computed from data replaced since.
+        if key.generation != self.generation {
+            return;
+        }
+
+        let size = entry_size(&key.request, &response);
+        if size > self.max_size {
+            return;
+        }
+
+        let evicted = self.entries.set(key.request, response, size);
+        if evicted > 0 {
+            metrics::counter!("rpc_response_cache_evictions_total", evicted as u64);
+        }
+    }
+
+    /// Drops all entries if there was a reorg since the last access, or if
+    /// reorg notifications were missed.
+    fn clear_on_reorg(&mut self) {
+        let mut reorged = false;
+        loop {
+            match self.reorgs.try_recv() {
+                Ok(_) | Err(broadcast::error::TryRecvError::Lagged(_)) => reorged = true,
+                Err(broadcast::error::TryRecvError::Empty)
+                | Err(broadcast::error::TryRecvError::Closed) => break,
+            }
+        }
+
+        if reorged {
+            self.entries.clear();
+            self.generation += 1;
+        }
+    }
+}
+
+fn entry_size(request: &Request, response: &RawValue) -> usize {
+    request.params.len() + response.get().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{BlockHash, BlockNumber};
+    use serde_json::json;
+
+    use super::*;
+
+    fn cache(max_size: usize) -> (ResponseCache, broadcast::Sender<Arc<Reorg>>) {
+        let (tx, rx) = broadcast::channel(1);
+        (ResponseCache::new(NonZeroUsize::new(max_size), rx), tx)
+    }
+
+    fn key(cache: &ResponseCache, method: &'static str, params: Value) -> Option<CacheKey> {
+        let params = serde_json::value::to_raw_value(&params).unwrap();
+        cache.key(method, &RawParams(Some(&params)), RpcVersion::V08)
+    }
+
+    fn output(value: Value) -> RpcOutput {
+        RpcOutput::Value(value)
+    }
+
+    fn cached(cache: &ResponseCache, key: &CacheKey) -> Option<Value> {
+        cache.get(key).map(|output| match output {
+            RpcOutput::Raw(raw) => serde_json::from_str(raw.get()).unwrap(),
+            RpcOutput::Value(value) => value,
+        })
+    }
+
+    #[test]
+    fn only_queries_by_hash_are_cached() {
+        let (cache, _tx) = cache(1024);
+        let method = "starknet_getBlockWithTxHashes";
+
+        assert!(key(&cache, method, json!([{"block_hash": "0x1"}])).is_some());
+        assert!(key(&cache, method, json!({"block_id": {"block_hash": "0x1"}})).is_some());
+        assert!(key(&cache, method, json!([{"block_number": 1}])).is_none());
+        assert!(key(&cache, method, json!(["latest"])).is_none());
+        assert!(key(&cache, "starknet_getTransactionByHash", json!(["0x1"])).is_some());
+        assert!(key(&cache, "starknet_getNonce", json!(["latest", "0x1"])).is_none());
+
+        let disabled = ResponseCache::default();
+        assert!(key(&disabled, method, json!([{"block_hash": "0x1"}])).is_none());
+    }
+
+    #[rstest::rstest]
+    #[case::accepted_on_l2("ACCEPTED_ON_L2")]
+    #[case::pending("PENDING")]
+    #[case::pre_confirmed("PRE_CONFIRMED")]
+    #[case::candidate("CANDIDATE")]
+    fn non_final_responses_are_not_cached(#[case] status: &str) {
+        let (cache, _tx) = cache(1024);
+        let key = key(&cache, "starknet_getTransactionReceipt", json!(["0x1"])).unwrap();
+
+        cache.insert(key.clone(), &output(json!({"finality_status": status})));
+        assert_eq!(cached(&cache, &key), None);
+
+        let response = json!({"finality_status": "ACCEPTED_ON_L1"});
+        cache.insert(key.clone(), &output(response.clone()));
+        assert_eq!(cached(&cache, &key), Some(response));
+    }
+
+    #[tokio::test]
+    async fn only_stored_transactions_are_cached() {
+        let (cache, _tx) = cache(1024);
+        let storage = crate::test_utils::setup_storage(pathfinder_storage::TriePruneMode::Archive);
+        let method = "starknet_traceTransaction";
+
+        let stored = key(&cache, method, json!([transaction_hash_bytes!(b"txn 0")])).unwrap();
+        assert!(stored.is_stored(&storage).await);
+
+        let pending = key(&cache, method, json!({"transaction_hash": "0x1"})).unwrap();
+        assert!(!pending.is_stored(&storage).await);
+
+        let block = key(
+            &cache,
+            "starknet_getBlockWithTxs",
+            json!([{"block_hash": "0x1"}]),
+        );
+        assert!(block.unwrap().is_stored(&storage).await);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let (cache, _tx) = cache(100);
+        let method = "starknet_getTransactionByHash";
+        let first = key(&cache, method, json!(["0x1"])).unwrap();
+        let second = key(&cache, method, json!(["0x2"])).unwrap();
+        let third = key(&cache, method, json!(["0x3"])).unwrap();
+        let response = output(json!("a".repeat(40)));
+
+        cache.insert(first.clone(), &response);
+        cache.insert(second.clone(), &response);
+        cache.get(&first);
+        cache.insert(third.clone(), &response);
+
+        assert!(cached(&cache, &first).is_some());
+        assert!(cached(&cache, &second).is_none());
+        assert!(cached(&cache, &third).is_some());
+
+        // Responses larger than the whole cache are not cached.
+        cache.insert(first.clone(), &output(json!("a".repeat(200))));
+        assert!(cached(&cache, &third).is_some());
+    }
+
+    #[test]
+    fn reorg_clears_cache() {
+        let (cache, tx) = cache(1024);
+        let key = key(&cache, "starknet_getTransactionByHash", json!(["0x1"])).unwrap();
+        cache.insert(key.clone(), &output(json!({})));
+        assert!(cached(&cache, &key).is_some());
+
+        tx.send(Arc::new(Reorg {
+            first_block_number: BlockNumber::new_or_panic(1),
+            first_block_hash: BlockHash::ZERO,
+            last_block_number: BlockNumber::new_or_panic(1),
+            last_block_hash: BlockHash::ZERO,
+        }))
+        .unwrap();
+        assert!(cached(&cache, &key).is_none());
+    }
+
+    #[test]
+    fn responses_from_before_reorg_are_not_cached() {
+        let (cache, tx) = cache(1024);
+        let method = "starknet_getTransactionByHash";
+        let stale = key(&cache, method, json!(["0x1"])).unwrap();
+
+        tx.send(Arc::new(Reorg {
+            first_block_number: BlockNumber::new_or_panic(1),
+            first_block_hash: BlockHash::ZERO,
+            last_block_number: BlockNumber::new_or_panic(1),
+            last_block_hash: BlockHash::ZERO,
+        }))
+        .unwrap();
+        cache.insert(stale.clone(), &output(json!({})));
+        assert!(cached(&cache, &stale).is_none());
+
+        let fresh = key(&cache, method, json!(["0x1"])).unwrap();
+        cache.insert(fresh.clone(), &output(json!({})));
+        assert!(cached(&cache, &fresh).is_some());
+    }
+}
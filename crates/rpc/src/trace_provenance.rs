@@ -0,0 +1,109 @@
+//! Opt-in metadata on where a returned trace came from.
+//!
+//! Clients request it by sending the `Pathfinder-Response-Extensions:
+//! trace-provenance` header. Trace methods [record] the provenance of the
+//! trace they return and the router adds it to the response object as
+//! `pathfinder.trace_provenance`. This helps to track down discrepancies
+//! between nodes, which are often caused by traces being produced by different
+//! blockifier versions or being fetched from the feeder gateway.
+
+use std::cell::RefCell;
+use std::future::Future;
+
+/// Request header listing the response extensions a client opts into.
+pub(crate) const HEADER: &str = "pathfinder-response-extensions";
+const EXTENSION: &str = "trace-provenance";
+
+tokio::task_local! {
+    static PROVENANCE: RefCell<Option<TraceProvenance>>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TraceProvenance {
+    /// Produced by executing the block locally.
+    Local {
+        /// Whether the trace was served from the trace cache.
+        cached: bool,
+        /// Version of the blockifier release the block was executed with.
+        blockifier_version: &'static str,
+        /// Name of the versioned constants used for execution.
+        versioned_constants: &'static str,
+    },
+    /// Fetched from the feeder gateway.
+    Gateway,
+}
+
+/// Returns true if the client opted into trace provenance.
+pub(crate) fn requested(headers: &http::HeaderMap) -> bool {
+    headers
+        .get_all(HEADER)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|extension| extension.trim().eq_ignore_ascii_case(EXTENSION))
+}
+
+/// Records the provenance of the trace being returned. Does nothing unless
+/// called within [collect].
+pub(crate) fn record(provenance: TraceProvenance) {
+    let _ = PROVENANCE.try_with(|recorded| *recorded.borrow_mut() = Some(provenance));
+}
+
+/// Runs `f` and returns the provenance it recorded along with its output.
+pub(crate) async fn collect<F: Future>(f: F) -> (F::Output, Option<TraceProvenance>) {
+    PROVENANCE
+        .scope(RefCell::new(None), async move {
+            let output = f.await;
+            (output, PROVENANCE.with(|recorded| recorded.take()))
+        })
+        .await
+}
+
+impl crate::dto::SerializeForVersion for TraceProvenance {
+    fn serialize(
+        &self,
+        serializer: crate::dto::Serializer,
+    ) -> Result<crate::dto::Ok, crate::dto::Error> {
+        let mut serializer = serializer.serialize_struct()?;
+        match self {
+            Self::Local {
+                cached,
+                blockifier_version,
+                versioned_constants,
+            } => {
+                serializer.serialize_field("source", &"local")?;
+                serializer.serialize_field("cached", cached)?;
+                serializer.serialize_field("blockifier_version", blockifier_version)?;
+                serializer.serialize_field("versioned_constants", versioned_constants)?;
+            }
+            Self::Gateway => serializer.serialize_field("source", &"gateway")?,
+        }
+        serializer.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requested_via_header() {
+        let mut headers = http::HeaderMap::new();
+        assert!(!requested(&headers));
+
+        headers.insert(HEADER, "other, Trace-Provenance".parse().unwrap());
+        assert!(requested(&headers));
+    }
+
+    #[tokio::test]
+    async fn collects_recorded_provenance() {
+        let ((), provenance) = collect(async { record(TraceProvenance::Gateway) }).await;
+        assert_eq!(provenance, Some(TraceProvenance::Gateway));
+
+        let ((), provenance) = collect(async {}).await;
+        assert_eq!(provenance, None);
+
+        // Outside of a collection scope recording is a no-op.
+        record(TraceProvenance::Gateway);
+    }
+}
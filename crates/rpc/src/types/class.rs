@@ -222,8 +222,8 @@ impl CairoContractClass {
 
     pub fn serialize_to_json(&self) -> anyhow::Result<Vec<u8>> {
         // decode program
-        let decompressor =
-            flate2::read::GzDecoder::new(Cursor::new(base64::decode(&self.program).unwrap()));
+        let program = base64::decode(&self.program).context("Decoding program")?;
+        let decompressor = flate2::read::GzDecoder::new(Cursor::new(program));
         let mut program = Vec::new();
         decompressor
             .take(pathfinder_common::class_definition::CLASS_DEFINITION_MAX_ALLOWED_SIZE)
@@ -9,6 +9,7 @@ use starknet_gateway_types::request::add_transaction::{
 
 use crate::context::RpcContext;
 use crate::felt::RpcFelt;
+use crate::method::add_declare_transaction::{precheck, PrecheckError};
 use crate::types::request::BroadcastedDeclareTransaction;
 
 #[derive(Debug)]
@@ -60,6 +61,17 @@ impl From<AddDeclareTransactionError> for crate::error::ApplicationError {
     }
 }
 
+impl From<PrecheckError> for AddDeclareTransactionError {
+    fn from(value: PrecheckError) -> Self {
+        match value {
+            PrecheckError::ClassAlreadyDeclared => Self::ClassAlreadyDeclared,
+            PrecheckError::CompilationFailed(_) => Self::CompilationFailed,
+            PrecheckError::ContractClassSizeIsTooLarge => Self::ContractClassSizeIsTooLarge,
+            PrecheckError::Internal(error) => Self::UnexpectedError(error.to_string()),
+        }
+    }
+}
+
 impl From<anyhow::Error> for AddDeclareTransactionError {
     fn from(value: anyhow::Error) -> Self {
         AddDeclareTransactionError::UnexpectedError(value.to_string())
@@ -166,6 +178,11 @@ pub async fn add_declare_transaction(
 ) -> Result<AddDeclareTransactionOutput, AddDeclareTransactionError> {
     use starknet_gateway_types::request::add_transaction;
 
+    if context.config.declare_precheck {
+        let Transaction::Declare(tx) = &input.declare_transaction;
+        precheck(context.clone(), tx.clone()).await?;
+    }
+
     match input.declare_transaction {
         Transaction::Declare(BroadcastedDeclareTransaction::V0(_)) => {
             Err(AddDeclareTransactionError::UnsupportedTransactionVersion)
@@ -512,6 +529,34 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn precheck_rejects_uncompilable_class() {
+        let mut context = RpcContext::for_tests();
+        context.config.declare_precheck = true;
+
+        let declare_transaction = Transaction::Declare(BroadcastedDeclareTransaction::V2(
+            BroadcastedDeclareTransactionV2 {
+                version: TransactionVersion::TWO,
+                max_fee: fee!("0xfffffffffff"),
+                signature: vec![],
+                nonce: TransactionNonce(Default::default()),
+                contract_class: SierraContractClass {
+                    sierra_program: vec![],
+                    ..SIERRA_CLASS.clone()
+                },
+                sender_address: contract_address!("0x1"),
+                compiled_class_hash: casm_hash!("0x1"),
+            },
+        ));
+
+        let input = AddDeclareTransactionInput {
+            declare_transaction,
+            token: None,
+        };
+        let error = add_declare_transaction(context, input).await.unwrap_err();
+        assert_matches::assert_matches!(error, AddDeclareTransactionError::CompilationFailed);
+    }
+
     #[test_log::test(tokio::test)]
     #[ignore = "gateway 429"]
     async fn invalid_contract_definition_v1() {
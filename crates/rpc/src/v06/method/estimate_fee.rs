@@ -156,7 +156,7 @@ pub async fn estimate_fee_impl(
     l1_blob_data_availability: L1BlobDataAvailability,
 ) -> Result<Vec<FeeEstimate>, EstimateFeeError> {
     let span = tracing::Span::current();
-    let result = util::task::spawn_blocking(move |_| {
+    let result = util::task::spawn_blocking_execution(move |_| {
         let _g = span.enter();
         let mut db = context
             .execution_storage
@@ -32,7 +32,7 @@ pub async fn get_transaction_receipt_impl(
 ) -> Result<types::MaybePendingTransactionReceipt, GetTransactionReceiptError> {
     let storage = context.storage.clone();
     let span = tracing::Span::current();
-    let jh = util::task::spawn_blocking(move |_| {
+    let jh = util::task::spawn_blocking_storage(move |_| {
         let _g = span.enter();
         let mut db = storage
             .connection()
@@ -61,6 +61,7 @@ pub async fn get_transaction_receipt_impl(
             .transaction_with_receipt(input.transaction_hash)
             .context("Reading transaction receipt from database")?
             .ok_or(GetTransactionReceiptError::TxnHashNotFound)?;
+        db_tx.ensure_events_retained(block_number)?;
 
         let block_hash = db_tx
             .block_hash(block_number.into())
@@ -691,6 +692,39 @@ mod tests {
                 Err(GetTransactionReceiptError::TxnHashNotFound)
             );
         }
+
+        #[tokio::test]
+        async fn events_pruned() {
+            let context = RpcContext::for_tests();
+            let mut db = context.storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            let config = pathfinder_storage::EventRetentionConfig {
+                blocks_kept: 1,
+                allowlist: Default::default(),
+            };
+            tx.prune_events(&config, pathfinder_storage::EVENT_PRUNING_BATCH_SIZE)
+                .unwrap();
+            tx.commit().unwrap();
+
+            // Part of block 0, which has been pruned.
+            let input = GetTransactionReceiptInput {
+                transaction_hash: transaction_hash_bytes!(b"txn 0"),
+            };
+            let error = get_transaction_receipt(context.clone(), input)
+                .await
+                .unwrap_err();
+            assert_matches::assert_matches!(
+                crate::error::ApplicationError::from(error),
+                crate::error::ApplicationError::EventsPruned { available_from }
+                    if available_from == BlockNumber::new_or_panic(2)
+            );
+
+            // Part of block 2, which has not.
+            let input = GetTransactionReceiptInput {
+                transaction_hash: transaction_hash_bytes!(b"txn 3"),
+            };
+            get_transaction_receipt(context, input).await.unwrap();
+        }
     }
 
     #[tokio::test]
@@ -2,6 +2,7 @@ use anyhow::Context;
 use pathfinder_common::TransactionHash;
 use serde_with::skip_serializing_none;
 
+use crate::circuit_breaker::GatewayCallError;
 use crate::context::RpcContext;
 
 #[derive(serde::Deserialize, Debug, PartialEq, Eq)]
@@ -82,7 +83,7 @@ pub async fn get_transaction_status(
 ) -> Result<GetTransactionStatusOutput, GetTransactionStatusError> {
     // Check database.
     let span = tracing::Span::current();
-    let db_status = util::task::spawn_blocking(move |_| {
+    let db_status = util::task::spawn_blocking_storage(move |_| {
         let _g = span.enter();
 
         let mut db = context
@@ -132,11 +133,15 @@ pub async fn get_transaction_status(
     // Check gateway for rejected transactions.
     use starknet_gateway_client::GatewayApi;
     context
-        .sequencer
-        .transaction_status(input.transaction_hash)
+        .gateway_breaker
+        .call(context.sequencer.transaction_status(input.transaction_hash))
         .await
-        .context("Fetching transaction from gateway")
-        .map_err(GetTransactionStatusError::Internal)
+        .map_err(|e| match e {
+            GatewayCallError::Unavailable => GetTransactionStatusError::Custom(e.into()),
+            GatewayCallError::Gateway(e) => GetTransactionStatusError::Internal(
+                anyhow::Error::new(e).context("Fetching transaction from gateway"),
+            ),
+        })
         .and_then(|tx| {
             use starknet_gateway_types::reply::transaction_status::{
                 ExecutionStatus as GatewayExecutionStatus,
@@ -7,11 +7,12 @@ use starknet_gateway_client::GatewayApi;
 use starknet_gateway_types::trace::TransactionTrace as GatewayTxTrace;
 
 use super::simulate_transactions::dto::TransactionTrace;
+use crate::circuit_breaker::GatewayCallError;
 use crate::compose_executor_transaction;
 use crate::context::RpcContext;
 use crate::executor::{
     ExecutionStateError,
-    VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY,
+    can_trace_locally,
 };
 use crate::v06::method::simulate_transactions::dto::{
     DeclareTxnTrace,
@@ -199,13 +200,13 @@ pub async fn trace_block_transactions_impl(
 ) -> Result<TraceBlockTransactionsOutput, TraceBlockTransactionsError> {
     enum LocalExecution {
         Success(Vec<Trace>),
-        Unsupported(Vec<Transaction>),
+        Unsupported(BlockId, Vec<Transaction>),
     }
 
     let span = tracing::Span::current();
 
     let storage = context.execution_storage.clone();
-    let traces = util::task::spawn_blocking(move |_| {
+    let traces = util::task::spawn_blocking_execution(move |_| {
         let _g = span.enter();
 
         let mut db = storage.connection()?;
@@ -245,9 +246,14 @@ pub async fn trace_block_transactions_impl(
             }
         };
 
-        if header.starknet_version
-            < VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY
-        {
+        // The feeder gateway does not support every block tag, so traces are
+        // fetched by the hash of the resolved block instead.
+        let gateway_block_id = match input.block_id {
+            BlockId::Pending => BlockId::Pending,
+            _ => BlockId::Hash(header.hash),
+        };
+
+        if !can_trace_locally(header.starknet_version) {
             match input.block_id {
                 BlockId::Pending => {
                     return Err(TraceBlockTransactionsError::Internal(anyhow::anyhow!(
@@ -256,6 +262,7 @@ pub async fn trace_block_transactions_impl(
                 }
                 _ => {
                     return Ok::<_, TraceBlockTransactionsError>(LocalExecution::Unsupported(
+                        gateway_block_id,
                         transactions,
                     ))
                 }
@@ -278,7 +285,7 @@ pub async fn trace_block_transactions_impl(
         let traces = match pathfinder_executor::trace(state, cache, hash, executor_transactions) {
             Ok(traces) => traces,
             Err(TransactionExecutionError::ExecutionError { .. }) => {
-                return Ok(LocalExecution::Unsupported(transactions))
+                return Ok(LocalExecution::Unsupported(gateway_block_id, transactions))
             }
             Err(e) => return Err(e.into()),
         };
@@ -298,17 +305,23 @@ pub async fn trace_block_transactions_impl(
     .await
     .context("trace_block_transactions: fetch block & transactions")??;
 
-    let transactions = match traces {
+    let (gateway_block_id, transactions) = match traces {
         LocalExecution::Success(traces) => return Ok(TraceBlockTransactionsOutput(traces)),
-        LocalExecution::Unsupported(transactions) => transactions,
+        LocalExecution::Unsupported(gateway_block_id, transactions) => {
+            (gateway_block_id, transactions)
+        }
     };
 
     context
-        .sequencer
-        .block_traces(input.block_id)
+        .gateway_breaker
+        .call(context.sequencer.block_traces(gateway_block_id))
         .await
-        .context("Forwarding to feeder gateway")
-        .map_err(TraceBlockTransactionsError::from)
+        .map_err(|e| match e {
+            GatewayCallError::Unavailable => TraceBlockTransactionsError::Custom(e.into()),
+            GatewayCallError::Gateway(e) => {
+                TraceBlockTransactionsError::Internal(anyhow::Error::new(e).context("Forwarding to feeder gateway"))
+            }
+        })
         .map(|trace| {
             Ok(TraceBlockTransactionsOutput(
                 trace
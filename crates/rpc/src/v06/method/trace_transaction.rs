@@ -6,12 +6,13 @@ use serde::{Deserialize, Serialize};
 use starknet_gateway_client::GatewayApi;
 
 use super::simulate_transactions::dto::TransactionTrace;
+use crate::circuit_breaker::GatewayCallError;
 use crate::compose_executor_transaction;
 use crate::context::RpcContext;
 use crate::error::{ApplicationError, TraceError};
 use crate::executor::{
     ExecutionStateError,
-    VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY,
+    can_trace_locally,
 };
 use crate::v06::method::trace_block_transactions::map_gateway_trace;
 
@@ -124,7 +125,7 @@ pub async fn trace_transaction_impl(
 
     let span = tracing::Span::current();
     let local =
-        util::task::spawn_blocking(move |_| -> Result<LocalExecution, TraceTransactionError> {
+        util::task::spawn_blocking_execution(move |_| -> Result<LocalExecution, TraceTransactionError> {
             let _g = span.enter();
 
             let mut db = context
@@ -147,9 +148,7 @@ pub async fn trace_transaction_impl(
             {
                 let header = pending.header();
 
-                if header.starknet_version
-                    < VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY
-                {
+                if !can_trace_locally(header.starknet_version) {
                     return Ok(LocalExecution::Unsupported(pending_tx.clone()));
                 }
 
@@ -169,9 +168,7 @@ pub async fn trace_transaction_impl(
                     .context("Fetching block header")?
                     .context("Block header is missing")?;
 
-                if header.starknet_version
-                    < VERSIONS_LOWER_THAN_THIS_SHOULD_FALL_BACK_TO_FETCHING_TRACE_FROM_GATEWAY
-                {
+                if !can_trace_locally(header.starknet_version) {
                     let transaction = db
                         .transaction(input.transaction_hash)
                         .context("Fetching transaction data")?
@@ -245,10 +242,15 @@ pub async fn trace_transaction_impl(
     };
 
     let trace = context
-        .sequencer
-        .transaction_trace(input.transaction_hash)
+        .gateway_breaker
+        .call(context.sequencer.transaction_trace(input.transaction_hash))
         .await
-        .context("Proxying call to feeder gateway")?;
+        .map_err(|e| match e {
+            GatewayCallError::Unavailable => TraceTransactionError::Custom(e.into()),
+            GatewayCallError::Gateway(e) => {
+                TraceTransactionError::Internal(anyhow::Error::new(e).context("Proxying call to feeder gateway"))
+            }
+        })?;
 
     let trace = map_gateway_trace(transaction, trace)?;
 
@@ -0,0 +1,74 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use pathfinder_common::BlockNumber;
+use pathfinder_crypto::Felt;
+use pathfinder_storage::{Node, NodeRef, RootIndexUpdate, StorageBuilder, TrieUpdate};
+
+/// Number of nodes written per trie update, roughly that of a busy block.
+const NODES_PER_UPDATE: usize = 1000;
+
+/// A trie update consisting of a chain of binary nodes ending in a leaf.
+fn trie_update() -> TrieUpdate {
+    let mut nodes_added = vec![(Felt::from_u64(1), Node::LeafBinary)];
+    for i in 1..NODES_PER_UPDATE {
+        nodes_added.push((
+            Felt::from_u64(i as u64 + 1),
+            Node::Binary {
+                left: NodeRef::Index(i - 1),
+                right: NodeRef::StorageIndex(0),
+            },
+        ));
+    }
+
+    TrieUpdate {
+        nodes_added,
+        nodes_removed: vec![],
+        root_commitment: Felt::from_u64(NODES_PER_UPDATE as u64),
+    }
+}
+
+fn bench_trie_backend(c: &mut Criterion) {
+    let storage = StorageBuilder::in_memory().unwrap();
+    let update = trie_update();
+
+    c.bench_function("insert trie nodes", |b| {
+        b.iter_batched(
+            || storage.connection().unwrap(),
+            |mut connection| {
+                let tx = connection.transaction().unwrap();
+                tx.insert_contract_trie(&update, BlockNumber::GENESIS)
+                    .unwrap();
+                tx.commit().unwrap();
+            },
+            BatchSize::PerIteration,
+        )
+    });
+
+    let mut connection = storage.connection().unwrap();
+    let tx = connection.transaction().unwrap();
+    let RootIndexUpdate::Updated(root) = tx
+        .insert_contract_trie(&update, BlockNumber::GENESIS)
+        .unwrap()
+    else {
+        panic!("Trie root should have been inserted");
+    };
+    let indices = (root + 1 - NODES_PER_UPDATE as u64)..=root;
+
+    c.bench_function("read trie nodes", |b| {
+        b.iter(|| {
+            for index in indices.clone() {
+                black_box(tx.contract_trie_node(index).unwrap());
+            }
+        })
+    });
+
+    c.bench_function("read trie node hashes", |b| {
+        b.iter(|| {
+            for index in indices.clone() {
+                black_box(tx.contract_trie_node_hash(index).unwrap());
+            }
+        })
+    });
+}
+
+criterion_group!(benches, bench_trie_backend);
+criterion_main!(benches);
@@ -0,0 +1,919 @@
+//! Low-level key-value access to the high-churn trie node tables.
+//!
+//! Trie nodes make up the bulk of writes for archive nodes, and are a poor fit
+//! for sqlite's B-tree pages. The [TrieNodeBackend] trait is the only way the
+//! rest of the crate reads and writes trie nodes. They are stored in sqlite
+//! unless another backend is passed to
+//! [StorageBuilder::trie_node_backend](crate::StorageBuilder::trie_node_backend).
+//! Everything else, including the bookkeeping for trie pruning, stays in
+//! sqlite.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Context;
+use pathfinder_common::BlockNumber;
+use pathfinder_crypto::Felt;
+use rusqlite::OptionalExtension;
+
+use crate::params::{params, RowExt};
+
+/// The trie node tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrieTable {
+    Contracts,
+    Class,
+    Storage,
+}
+
+impl TrieTable {
+    pub const ALL: [TrieTable; 3] = [Self::Contracts, Self::Class, Self::Storage];
+
+    /// The name of the sqlite table, also used as the metrics label.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Contracts => "trie_contracts",
+            Self::Class => "trie_class",
+            Self::Storage => "trie_storage",
+        }
+    }
+}
+
+/// Storage of trie nodes keyed by their index.
+///
+/// Nodes are written in batches, one per trie update, and are immutable once
+/// written. The backend assigns indices, which must be unique per table and are
+/// never reused, even after deletion.
+pub trait TrieNodeBackend {
+    /// Reserves indices for a batch of `node_count` nodes written by
+    /// `block_number` and returns the first one. The nodes get consecutive
+    /// indices, in the order they are passed to
+    /// [write_batch](TrieNodeBackend::write_batch).
+    fn allocate_batch(
+        &self,
+        table: TrieTable,
+        block_number: BlockNumber,
+        node_count: usize,
+    ) -> anyhow::Result<u64>;
+
+    /// Stores the hashes and encoded nodes of the batch allocated at
+    /// `first_index`.
+    fn write_batch(
+        &self,
+        table: TrieTable,
+        first_index: u64,
+        nodes: &[(Felt, Vec<u8>)],
+    ) -> anyhow::Result<()>;
+
+    /// Returns the encoded node with the given index.
+    fn node(&self, table: TrieTable, index: u64) -> anyhow::Result<Option<Vec<u8>>>;
+
+    /// Returns the hash of the node with the given index.
+    fn node_hash(&self, table: TrieTable, index: u64) -> anyhow::Result<Option<Felt>>;
+
+    /// Deletes the nodes with the given indices. Missing nodes are ignored.
+    fn delete_nodes(&self, table: TrieTable, indices: &[u64]) -> anyhow::Result<()>;
+
+    /// Deletes all nodes written by `block_number`, for when the block is
+    /// purged by a reorg.
+    fn delete_block_nodes(&self, table: TrieTable, block_number: BlockNumber)
+        -> anyhow::Result<()>;
+}
+
+/// A trie node backend shared by all connections of a
+/// [Storage](crate::Storage).
+pub type SharedTrieNodeBackend = Arc<dyn TrieNodeBackend + Send + Sync>;
+
+/// Set on the indices of batched nodes. Indices without it refer to nodes
+/// written before batching was introduced, which are stored one per row in the
+/// original node tables.
+const BATCH_FLAG: u64 = 1 << 62;
+/// The number of low index bits holding the position of a node in its batch.
+const BATCH_POSITION_BITS: u32 = 24;
+const MAX_BATCH_LEN: usize = 1 << BATCH_POSITION_BITS;
+
+/// Length of the batch header: the number of index entries as a little-endian
+/// u32, with [SPARSE_FLAG] set for compacted batches.
+const HEADER_LEN: usize = 4;
+/// Set in the header of compacted batches. Their index only holds the nodes
+/// which were live at compaction, each entry prefixed with the position of its
+/// node.
+const SPARSE_FLAG: u32 = 1 << 31;
+/// Length of an entry in the batch index: the node hash followed by the end
+/// offset of its data as a little-endian u32.
+const ENTRY_LEN: usize = 32 + 4;
+/// Length of an entry in the index of a compacted batch: the position of the
+/// node as a little-endian u32 followed by a regular entry.
+const SPARSE_ENTRY_LEN: usize = 4 + ENTRY_LEN;
+/// Set in the first byte of the hash of removed nodes. Node hashes are felts,
+/// which never use the top bit of their big-endian encoding.
+const REMOVED_FLAG: u8 = 0x80;
+
+/// The location of a batched node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct BatchPosition {
+    batch: i64,
+    position: usize,
+}
+
+impl BatchPosition {
+    /// Returns [None] for legacy, unbatched indices.
+    fn from_index(index: u64) -> Option<Self> {
+        if index & BATCH_FLAG == 0 {
+            return None;
+        }
+
+        let index = index & !BATCH_FLAG;
+        Some(Self {
+            batch: (index >> BATCH_POSITION_BITS) as i64,
+            position: (index & (MAX_BATCH_LEN as u64 - 1)) as usize,
+        })
+    }
+
+    fn first_index(batch: i64) -> u64 {
+        BATCH_FLAG | ((batch as u64) << BATCH_POSITION_BITS)
+    }
+
+    /// Reads the index entry of the node, returning [None] if the node does not
+    /// exist or has been removed.
+    ///
+    /// Liveness is stored in the index entry itself, so this takes no reads
+    /// besides those of the batch blob.
+    fn live_entry(&self, blob: &rusqlite::blob::Blob<'_>) -> anyhow::Result<Option<Entry>> {
+        let layout = BatchLayout::read(blob)?;
+        let Some(i) = layout.find(blob, self.position)? else {
+            return Ok(None);
+        };
+
+        let entry = layout.entry(blob, i)?;
+        Ok((!entry.removed).then_some(entry))
+    }
+}
+
+/// Returns the index of the first node of the batch with the given id.
+#[cfg(test)]
+pub(crate) fn first_batch_index(batch: i64) -> u64 {
+    BatchPosition::first_index(batch)
+}
+
+const fn batches_table(table: TrieTable) -> &'static str {
+    match table {
+        TrieTable::Contracts => "trie_contracts_batches",
+        TrieTable::Class => "trie_class_batches",
+        TrieTable::Storage => "trie_storage_batches",
+    }
+}
+
+/// Opens the data of a batch, returning [None] if the batch has been deleted.
+fn open_batch<'conn>(
+    db: &'conn rusqlite::Connection,
+    table: TrieTable,
+    batch: i64,
+    read_only: bool,
+) -> anyhow::Result<Option<rusqlite::blob::Blob<'conn>>> {
+    let error = match db.blob_open(
+        rusqlite::DatabaseName::Main,
+        batches_table(table),
+        "data",
+        batch,
+        read_only,
+    ) {
+        Ok(blob) => return Ok(Some(blob)),
+        Err(error) => error,
+    };
+
+    // Sqlite does not tell a missing row or unwritten data apart from other
+    // failures, so whether the batch exists is only checked once opening it
+    // failed.
+    let mut stmt = db
+        .prepare_cached(&format!(
+            "SELECT 1 FROM {} WHERE id = ? AND data IS NOT NULL",
+            batches_table(table)
+        ))
+        .context("Creating batch statement")?;
+    let exists = stmt
+        .query_row(params![&batch], |_| Ok(()))
+        .optional()
+        .context("Querying batch")?
+        .is_some();
+
+    if exists {
+        Err(error).context("Opening batch")
+    } else {
+        Ok(None)
+    }
+}
+
+/// The index layout of a batch, as given by its header.
+#[derive(Debug, Clone, Copy)]
+struct BatchLayout {
+    entries: usize,
+    sparse: bool,
+}
+
+/// An entry of the batch index.
+struct Entry {
+    hash: Felt,
+    removed: bool,
+    /// The range of the node data within the batch.
+    data: std::ops::Range<usize>,
+}
+
+impl BatchLayout {
+    fn read(blob: &rusqlite::blob::Blob<'_>) -> anyhow::Result<Self> {
+        let mut header = [0u8; HEADER_LEN];
+        blob.read_at_exact(&mut header, 0)
+            .context("Reading batch header")?;
+        let header = u32::from_le_bytes(header);
+
+        Ok(Self {
+            entries: (header & !SPARSE_FLAG) as usize,
+            sparse: header & SPARSE_FLAG != 0,
+        })
+    }
+
+    fn entry_len(&self) -> usize {
+        if self.sparse {
+            SPARSE_ENTRY_LEN
+        } else {
+            ENTRY_LEN
+        }
+    }
+
+    /// The offset of the hash of the `i`-th index entry.
+    fn hash_offset(&self, i: usize) -> usize {
+        let position_len = if self.sparse { 4 } else { 0 };
+        HEADER_LEN + i * self.entry_len() + position_len
+    }
+
+    /// Returns the index entry of the node at `position`, if the batch holds
+    /// it.
+    fn find(
+        &self,
+        blob: &rusqlite::blob::Blob<'_>,
+        position: usize,
+    ) -> anyhow::Result<Option<usize>> {
+        if !self.sparse {
+            return Ok((position < self.entries).then_some(position));
+        }
+
+        // The entries of compacted batches are sorted by position.
+        let (mut low, mut high) = (0, self.entries);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.position(blob, mid)?.cmp(&position) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Ok(Some(mid)),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The position of the node of the `i`-th index entry.
+    fn position(&self, blob: &rusqlite::blob::Blob<'_>, i: usize) -> anyhow::Result<usize> {
+        if !self.sparse {
+            return Ok(i);
+        }
+
+        let mut position = [0u8; 4];
+        blob.read_at_exact(&mut position, HEADER_LEN + i * SPARSE_ENTRY_LEN)
+            .context("Reading batch index")?;
+        Ok(u32::from_le_bytes(position) as usize)
+    }
+
+    fn entry(&self, blob: &rusqlite::blob::Blob<'_>, i: usize) -> anyhow::Result<Entry> {
+        let hash_offset = self.hash_offset(i);
+        let mut entry = [0u8; ENTRY_LEN];
+        blob.read_at_exact(&mut entry, hash_offset)
+            .context("Reading batch index")?;
+
+        let removed = entry[0] & REMOVED_FLAG != 0;
+        entry[0] &= !REMOVED_FLAG;
+        let hash = Felt::from_be_slice(&entry[..32]).context("Parsing node hash")?;
+        let end = u32::from_le_bytes(entry[32..].try_into().expect("Slice has length 4")) as usize;
+
+        let start = if i == 0 {
+            0
+        } else {
+            let mut start = [0u8; 4];
+            blob.read_at_exact(&mut start, self.hash_offset(i - 1) + 32)
+                .context("Reading batch index")?;
+            u32::from_le_bytes(start) as usize
+        };
+
+        let data_offset = HEADER_LEN + self.entries * self.entry_len();
+        Ok(Entry {
+            hash,
+            removed,
+            data: data_offset + start..data_offset + end,
+        })
+    }
+}
+
+/// Serializes a batch as a header, an index of node hashes and data end
+/// offsets, followed by the concatenated node data.
+///
+/// The index lets single nodes be read without loading the whole batch.
+/// Compacted batches pass the `positions` of their nodes, which prefix the
+/// index entries.
+fn encode_batch(nodes: &[(Felt, Vec<u8>)], positions: Option<&[u32]>) -> anyhow::Result<Vec<u8>> {
+    let entry_len = if positions.is_some() {
+        SPARSE_ENTRY_LEN
+    } else {
+        ENTRY_LEN
+    };
+    let index_len = HEADER_LEN + nodes.len() * entry_len;
+    let data_len: usize = nodes.iter().map(|(_, data)| data.len()).sum();
+
+    let mut header = u32::try_from(nodes.len())?;
+    if positions.is_some() {
+        header |= SPARSE_FLAG;
+    }
+
+    let mut buffer = Vec::with_capacity(index_len + data_len);
+    buffer.extend_from_slice(&header.to_le_bytes());
+
+    let mut end = 0;
+    for (i, (hash, data)) in nodes.iter().enumerate() {
+        if let Some(positions) = positions {
+            buffer.extend_from_slice(&positions[i].to_le_bytes());
+        }
+        end += data.len();
+        buffer.extend_from_slice(hash.as_be_bytes());
+        buffer.extend_from_slice(&u32::try_from(end)?.to_le_bytes());
+    }
+    for (_, data) in nodes {
+        buffer.extend_from_slice(data);
+    }
+
+    Ok(buffer)
+}
+
+/// Rewrites a batch with only its live nodes, dropping the index entries and
+/// data of those removed.
+fn compact_batch(db: &rusqlite::Connection, table: TrieTable, batch: i64) -> anyhow::Result<()> {
+    let Some(blob) = open_batch(db, table, batch, true)? else {
+        return Ok(());
+    };
+    let layout = BatchLayout::read(&blob)?;
+
+    let mut nodes = Vec::new();
+    let mut positions = Vec::new();
+    for i in 0..layout.entries {
+        let entry = layout.entry(&blob, i)?;
+        if entry.removed {
+            continue;
+        }
+
+        let mut data = vec![0u8; entry.data.len()];
+        blob.read_at_exact(&mut data, entry.data.start)
+            .context("Reading node")?;
+        nodes.push((entry.hash, data));
+        positions.push(u32::try_from(layout.position(&blob, i)?)?);
+    }
+    drop(blob);
+
+    let data = encode_batch(&nodes, Some(&positions))?;
+    let mut stmt = db
+        .prepare_cached(&format!(
+            "UPDATE {} SET data = ? WHERE id = ?",
+            batches_table(table)
+        ))
+        .context("Creating compaction statement")?;
+    stmt.execute(params![&data, &batch])
+        .context("Compacting batch")?;
+
+    Ok(())
+}
+
+/// The default backend, storing nodes in the sqlite database itself.
+impl TrieNodeBackend for rusqlite::Connection {
+    fn allocate_batch(
+        &self,
+        table: TrieTable,
+        block_number: BlockNumber,
+        node_count: usize,
+    ) -> anyhow::Result<u64> {
+        anyhow::ensure!(
+            node_count <= MAX_BATCH_LEN,
+            "Batch of {node_count} nodes exceeds the maximum of {MAX_BATCH_LEN}"
+        );
+
+        let mut stmt = self
+            .prepare_cached(&format!(
+                "INSERT INTO {} (block_number, node_count, live_count) VALUES(?, ?, ?) RETURNING \
+                 id",
+                batches_table(table)
+            ))
+            .context("Creating allocate statement")?;
+
+        let batch: i64 = stmt
+            .query_row(params![&block_number, &node_count, &node_count], |row| {
+                row.get(0)
+            })
+            .context("Allocating batch")?;
+
+        Ok(BatchPosition::first_index(batch))
+    }
+
+    fn write_batch(
+        &self,
+        table: TrieTable,
+        first_index: u64,
+        nodes: &[(Felt, Vec<u8>)],
+    ) -> anyhow::Result<()> {
+        let batch = BatchPosition::from_index(first_index)
+            .filter(|batch| batch.position == 0)
+            .context("Not the first index of a batch")?;
+
+        let data = encode_batch(nodes, None)?;
+        let mut stmt = self
+            .prepare_cached(&format!(
+                "UPDATE {} SET data = ? WHERE id = ? AND node_count = ?",
+                batches_table(table)
+            ))
+            .context("Creating write statement")?;
+
+        let updated = stmt
+            .execute(params![&data, &batch.batch, &nodes.len()])
+            .context("Writing batch")?;
+        anyhow::ensure!(
+            updated == 1,
+            "Batch was not allocated for {} nodes",
+            nodes.len()
+        );
+
+        Ok(())
+    }
+
+    fn node(&self, table: TrieTable, index: u64) -> anyhow::Result<Option<Vec<u8>>> {
+        let Some(batch) = BatchPosition::from_index(index) else {
+            // We rely on sqlite caching the statement here. Storing the statement would be
+            // nice, however that leads to &mut requirements or interior mutable
+            // work-arounds.
+            let mut stmt = self
+                .prepare_cached(&format!("SELECT data FROM {} WHERE idx = ?", table.name()))
+                .context("Creating get statement")?;
+
+            return stmt
+                .query_row(params![&index], |row| row.get(0))
+                .optional()
+                .map_err(Into::into);
+        };
+
+        let Some(blob) = open_batch(self, table, batch.batch, true)? else {
+            return Ok(None);
+        };
+        let Some(entry) = batch.live_entry(&blob)? else {
+            return Ok(None);
+        };
+
+        let mut data = vec![0u8; entry.data.len()];
+        blob.read_at_exact(&mut data, entry.data.start)
+            .context("Reading node")?;
+
+        Ok(Some(data))
+    }
+
+    fn node_hash(&self, table: TrieTable, index: u64) -> anyhow::Result<Option<Felt>> {
+        let Some(batch) = BatchPosition::from_index(index) else {
+            let mut stmt = self
+                .prepare_cached(&format!("SELECT hash FROM {} WHERE idx = ?", table.name()))
+                .context("Creating get statement")?;
+
+            return stmt
+                .query_row(params![&index], |row| row.get_felt(0))
+                .optional()
+                .map_err(Into::into);
+        };
+
+        let Some(blob) = open_batch(self, table, batch.batch, true)? else {
+            return Ok(None);
+        };
+
+        Ok(batch.live_entry(&blob)?.map(|entry| entry.hash))
+    }
+
+    fn delete_nodes(&self, table: TrieTable, indices: &[u64]) -> anyhow::Result<()> {
+        let mut delete_stmt = self
+            .prepare_cached(&format!("DELETE FROM {} WHERE idx = ?", table.name()))
+            .context("Creating delete statement")?;
+
+        // Batched nodes are marked as removed in the batch index. A batch is
+        // compacted once at least half of its index entries are removed, and
+        // deleted once all of its nodes are.
+        let mut removals: HashMap<i64, Vec<usize>> = HashMap::new();
+        for idx in indices {
+            match BatchPosition::from_index(*idx) {
+                Some(batch) => removals
+                    .entry(batch.batch)
+                    .or_default()
+                    .push(batch.position),
+                None => {
+                    delete_stmt.execute(params![idx]).context("Deleting node")?;
+                }
+            }
+        }
+
+        let mut live_count_stmt = self
+            .prepare_cached(&format!(
+                "UPDATE {} SET live_count = live_count - ? WHERE id = ? RETURNING live_count",
+                batches_table(table)
+            ))
+            .context("Creating update statement")?;
+        let mut delete_batch_stmt = self
+            .prepare_cached(&format!(
+                "DELETE FROM {} WHERE id = ?",
+                batches_table(table)
+            ))
+            .context("Creating delete statement")?;
+
+        for (batch, positions) in removals {
+            let Some(mut blob) = open_batch(self, table, batch, false)? else {
+                continue;
+            };
+            let layout = BatchLayout::read(&blob)?;
+
+            let mut removed: usize = 0;
+            for position in positions {
+                let Some(i) = layout.find(&blob, position)? else {
+                    continue;
+                };
+
+                let offset = layout.hash_offset(i);
+                let mut flags = [0u8; 1];
+                blob.read_at_exact(&mut flags, offset)
+                    .context("Reading batch index")?;
+                if flags[0] & REMOVED_FLAG == 0 {
+                    flags[0] |= REMOVED_FLAG;
+                    blob.write_at(&flags, offset)
+                        .context("Marking node as removed")?;
+                    removed += 1;
+                }
+            }
+            drop(blob);
+
+            if removed == 0 {
+                continue;
+            }
+
+            let live_count: usize = live_count_stmt
+                .query_row(params![&removed, &batch], |row| row.get(0))
+                .context("Updating live node count")?;
+            if live_count == 0 {
+                delete_batch_stmt
+                    .execute(params![&batch])
+                    .context("Deleting batch")?;
+            } else if (layout.entries - live_count) * 2 >= layout.entries {
+                compact_batch(self, table, batch)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn delete_block_nodes(
+        &self,
+        table: TrieTable,
+        block_number: BlockNumber,
+    ) -> anyhow::Result<()> {
+        let mut stmt = self
+            .prepare_cached(&format!(
+                "DELETE FROM {} WHERE block_number = ?",
+                batches_table(table)
+            ))
+            .context("Creating delete statement")?;
+        stmt.execute(params![&block_number])
+            .context("Deleting batches of block")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::felt;
+
+    use super::*;
+
+    #[test]
+    fn sqlite_roundtrip() {
+        let mut db = crate::StorageBuilder::in_memory()
+            .unwrap()
+            .connection()
+            .unwrap();
+        let tx = db.transaction().unwrap();
+        let backend: &dyn TrieNodeBackend = tx.trie_backend();
+
+        for table in TrieTable::ALL {
+            let nodes = vec![
+                (felt!("0x1"), b"first".to_vec()),
+                (felt!("0x2"), b"second".to_vec()),
+                (felt!("0x3"), b"third".to_vec()),
+            ];
+            let first = backend
+                .allocate_batch(table, BlockNumber::GENESIS, nodes.len())
+                .unwrap();
+            backend.write_batch(table, first, &nodes).unwrap();
+
+            for (i, (hash, data)) in nodes.iter().enumerate() {
+                let idx = first + i as u64;
+                assert_eq!(backend.node(table, idx).unwrap().as_ref(), Some(data));
+                assert_eq!(backend.node_hash(table, idx).unwrap().as_ref(), Some(hash));
+            }
+            assert_eq!(backend.node(table, first + 3).unwrap(), None);
+
+            let next = backend
+                .allocate_batch(table, BlockNumber::GENESIS + 1, 1)
+                .unwrap();
+            assert!(next > first + 2);
+
+            backend.delete_nodes(table, &[first, 12345]).unwrap();
+            assert_eq!(backend.node(table, first).unwrap(), None);
+            assert_eq!(backend.node_hash(table, first).unwrap(), None);
+            assert!(backend.node(table, first + 1).unwrap().is_some());
+
+            // The batch is dropped once all of its nodes are deleted.
+            backend
+                .delete_nodes(table, &[first + 1, first + 2])
+                .unwrap();
+            let batches: u64 = tx
+                .inner()
+                .query_row(
+                    &format!("SELECT COUNT(*) FROM {}", batches_table(table)),
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert_eq!(batches, 1);
+        }
+    }
+
+    #[test]
+    fn batches_are_compacted() {
+        let mut db = crate::StorageBuilder::in_memory()
+            .unwrap()
+            .connection()
+            .unwrap();
+        let tx = db.transaction().unwrap();
+        let backend: &dyn TrieNodeBackend = tx.trie_backend();
+
+        let batch_size = |table: TrieTable| -> usize {
+            tx.inner()
+                .query_row(
+                    &format!("SELECT length(data) FROM {}", batches_table(table)),
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+
+        for table in TrieTable::ALL {
+            let nodes = (0..8u8)
+                .map(|i| (Felt::from_u64(i.into()), vec![i; 100]))
+                .collect::<Vec<_>>();
+            let first = backend
+                .allocate_batch(table, BlockNumber::GENESIS, nodes.len())
+                .unwrap();
+            backend.write_batch(table, first, &nodes).unwrap();
+            let full_size = batch_size(table);
+
+            // Removing less than half of the nodes only marks them as removed.
+            backend
+                .delete_nodes(table, &[first + 1, first + 4])
+                .unwrap();
+            assert_eq!(batch_size(table), full_size);
+
+            backend.delete_nodes(table, &[first, first + 6]).unwrap();
+            assert_eq!(batch_size(table), HEADER_LEN + 4 * SPARSE_ENTRY_LEN + 400);
+
+            for (i, (hash, data)) in nodes.iter().enumerate() {
+                let idx = first + i as u64;
+                if [0, 1, 4, 6].contains(&i) {
+                    assert_eq!(backend.node(table, idx).unwrap(), None);
+                    assert_eq!(backend.node_hash(table, idx).unwrap(), None);
+                } else {
+                    assert_eq!(backend.node(table, idx).unwrap().as_ref(), Some(data));
+                    assert_eq!(backend.node_hash(table, idx).unwrap().as_ref(), Some(hash));
+                }
+            }
+            assert_eq!(backend.node(table, first + 8).unwrap(), None);
+
+            // Compacted batches are compacted again, and deleted once empty.
+            backend
+                .delete_nodes(table, &[first + 2, first + 3])
+                .unwrap();
+            assert_eq!(backend.node(table, first + 5).unwrap(), Some(vec![5; 100]));
+            assert_eq!(backend.node(table, first + 7).unwrap(), Some(vec![7; 100]));
+
+            backend
+                .delete_nodes(table, &[first + 5, first + 7])
+                .unwrap();
+            let batches: u64 = tx
+                .inner()
+                .query_row(
+                    &format!("SELECT COUNT(*) FROM {}", batches_table(table)),
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert_eq!(batches, 0);
+        }
+    }
+
+    #[test]
+    fn block_nodes_are_deleted() {
+        let mut db = crate::StorageBuilder::in_memory()
+            .unwrap()
+            .connection()
+            .unwrap();
+        let tx = db.transaction().unwrap();
+        let backend: &dyn TrieNodeBackend = tx.trie_backend();
+
+        for table in TrieTable::ALL {
+            let nodes = vec![(felt!("0x1"), b"node".to_vec())];
+            let kept = backend
+                .allocate_batch(table, BlockNumber::GENESIS, 1)
+                .unwrap();
+            backend.write_batch(table, kept, &nodes).unwrap();
+            let purged = backend
+                .allocate_batch(table, BlockNumber::GENESIS + 1, 1)
+                .unwrap();
+            backend.write_batch(table, purged, &nodes).unwrap();
+
+            backend
+                .delete_block_nodes(table, BlockNumber::GENESIS + 1)
+                .unwrap();
+            assert!(backend.node(table, kept).unwrap().is_some());
+            assert_eq!(backend.node(table, purged).unwrap(), None);
+        }
+    }
+
+    /// Keeps trie nodes in memory, with each batch recorded under its block.
+    #[derive(Default)]
+    struct MemoryBackend(std::sync::Mutex<MemoryNodes>);
+
+    #[derive(Default)]
+    struct MemoryNodes {
+        next_index: u64,
+        /// The block and node count of each batch, by its first index.
+        batches: HashMap<(TrieTable, u64), (BlockNumber, usize)>,
+        nodes: HashMap<(TrieTable, u64), (Felt, Vec<u8>)>,
+    }
+
+    impl TrieNodeBackend for MemoryBackend {
+        fn allocate_batch(
+            &self,
+            table: TrieTable,
+            block_number: BlockNumber,
+            node_count: usize,
+        ) -> anyhow::Result<u64> {
+            let mut inner = self.0.lock().unwrap();
+            let first = inner.next_index;
+            inner.next_index += node_count as u64;
+            inner
+                .batches
+                .insert((table, first), (block_number, node_count));
+            Ok(first)
+        }
+
+        fn write_batch(
+            &self,
+            table: TrieTable,
+            first_index: u64,
+            nodes: &[(Felt, Vec<u8>)],
+        ) -> anyhow::Result<()> {
+            let mut inner = self.0.lock().unwrap();
+            for (i, node) in nodes.iter().enumerate() {
+                inner
+                    .nodes
+                    .insert((table, first_index + i as u64), node.clone());
+            }
+            Ok(())
+        }
+
+        fn node(&self, table: TrieTable, index: u64) -> anyhow::Result<Option<Vec<u8>>> {
+            let inner = self.0.lock().unwrap();
+            Ok(inner
+                .nodes
+                .get(&(table, index))
+                .map(|(_, data)| data.clone()))
+        }
+
+        fn node_hash(&self, table: TrieTable, index: u64) -> anyhow::Result<Option<Felt>> {
+            let inner = self.0.lock().unwrap();
+            Ok(inner.nodes.get(&(table, index)).map(|(hash, _)| *hash))
+        }
+
+        fn delete_nodes(&self, table: TrieTable, indices: &[u64]) -> anyhow::Result<()> {
+            let mut inner = self.0.lock().unwrap();
+            for index in indices {
+                inner.nodes.remove(&(table, *index));
+            }
+            Ok(())
+        }
+
+        fn delete_block_nodes(
+            &self,
+            table: TrieTable,
+            block_number: BlockNumber,
+        ) -> anyhow::Result<()> {
+            let mut inner = self.0.lock().unwrap();
+            let MemoryNodes { batches, nodes, .. } = &mut *inner;
+            batches.retain(|(batch_table, first), (batch_block, node_count)| {
+                let purged = *batch_table == table && *batch_block == block_number;
+                if purged {
+                    for index in *first..*first + *node_count as u64 {
+                        nodes.remove(&(table, index));
+                    }
+                }
+                !purged
+            });
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn configured_backend_is_used() {
+        let backend = Arc::new(MemoryBackend::default());
+        let storage = crate::StorageBuilder::memory()
+            .trie_prune_mode(Some(crate::TriePruneMode::Archive))
+            .trie_node_backend(backend.clone())
+            .migrate()
+            .unwrap()
+            .create_pool(std::num::NonZeroU32::new(1).unwrap())
+            .unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let update = crate::TrieUpdate {
+            nodes_added: vec![
+                (felt!("0x1"), crate::Node::LeafBinary),
+                (
+                    felt!("0x2"),
+                    crate::Node::Binary {
+                        left: crate::NodeRef::Index(0),
+                        right: crate::NodeRef::StorageIndex(0),
+                    },
+                ),
+            ],
+            nodes_removed: vec![],
+            root_commitment: felt!("0x2"),
+        };
+        let crate::RootIndexUpdate::Updated(root) =
+            tx.insert_class_trie(&update, BlockNumber::GENESIS).unwrap()
+        else {
+            panic!("Trie root should have been inserted");
+        };
+
+        assert_eq!(tx.class_trie_node_hash(root).unwrap(), Some(felt!("0x2")));
+        assert!(tx.class_trie_node(root).unwrap().is_some());
+        assert_eq!(backend.0.lock().unwrap().nodes.len(), 2);
+
+        // Nothing was written to sqlite.
+        let batches: u64 = tx
+            .inner()
+            .query_row(
+                &format!("SELECT COUNT(*) FROM {}", batches_table(TrieTable::Class)),
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(batches, 0);
+    }
+
+    #[test]
+    fn legacy_nodes_remain_readable() {
+        let mut db = crate::StorageBuilder::in_memory()
+            .unwrap()
+            .connection()
+            .unwrap();
+        let tx = db.transaction().unwrap();
+        let backend: &dyn TrieNodeBackend = tx.trie_backend();
+
+        for table in TrieTable::ALL {
+            let idx: u64 = tx
+                .inner()
+                .query_row(
+                    &format!(
+                        "INSERT INTO {} (hash, data) VALUES(?, ?) RETURNING idx",
+                        table.name()
+                    ),
+                    params![
+                        &felt!("0x1").as_be_bytes().as_slice(),
+                        &b"legacy".as_slice()
+                    ],
+                    |row| row.get(0),
+                )
+                .unwrap();
+
+            assert_eq!(backend.node(table, idx).unwrap().unwrap(), b"legacy");
+            assert_eq!(backend.node_hash(table, idx).unwrap(), Some(felt!("0x1")));
+
+            backend.delete_nodes(table, &[idx]).unwrap();
+            assert_eq!(backend.node(table, idx).unwrap(), None);
+        }
+    }
+}
@@ -2,32 +2,58 @@ use std::sync::{Arc, Mutex};
 
 mod block;
 mod class;
+mod class_stats;
+mod compiled_class_cache;
+mod consistency;
 mod ethereum;
 pub mod event;
+mod event_retention;
+mod gateway_outbox;
+mod quarantine;
 mod reference;
+mod rejected_transaction;
 mod reorg_counter;
 mod signature;
 mod state_update;
 pub(crate) mod transaction;
 mod trie;
 
+pub use class_stats::ClassStats;
+pub use compiled_class_cache::CachedCompiledClass;
+pub use consistency::{Inconsistency, TrieKind};
 use event::RunningEventFilter;
 pub use event::{
+    BlockEventFilter,
+    DeferredEventFilter,
     EmittedEvent,
     EventConstraints,
     EventFilterError,
+    EventOrder,
     PageOfEvents,
     PAGE_SIZE_LIMIT as EVENT_PAGE_SIZE_LIMIT,
 };
+pub use event_retention::{
+    EventPruning,
+    EventRetention,
+    EventRetentionConfig,
+    EventsPruned,
+    EVENT_PRUNING_BATCH_SIZE,
+};
+pub use gateway_outbox::OutboxEntry;
 use pathfinder_common::event::Event;
 use pathfinder_common::receipt::Receipt;
 use pathfinder_common::transaction::Transaction as StarknetTransaction;
 use pathfinder_common::{BlockNumber, TransactionHash};
+pub use quarantine::QuarantinedBlock;
+pub use rejected_transaction::RejectedTransaction;
 pub(crate) use reorg_counter::ReorgCounter;
 // Re-export this so users don't require rusqlite as a direct dep.
 pub use rusqlite::TransactionBehavior;
+pub use state_update::StorageWrite;
+pub use transaction::transaction_data_needs_recompression;
 pub use trie::{Node, NodeRef, RootIndexUpdate, StoredNode, TrieUpdate};
 
+use crate::backend::{SharedTrieNodeBackend, TrieNodeBackend};
 use crate::bloom::AggregateBloomCache;
 
 type PooledConnection = r2d2::PooledConnection<r2d2_sqlite::SqliteConnectionManager>;
@@ -37,6 +63,7 @@ pub struct Connection {
     event_filter_cache: Arc<AggregateBloomCache>,
     running_event_filter: Arc<Mutex<RunningEventFilter>>,
     trie_prune_mode: TriePruneMode,
+    trie_node_backend: Option<SharedTrieNodeBackend>,
 }
 
 impl Connection {
@@ -45,12 +72,14 @@ impl Connection {
         event_filter_cache: Arc<AggregateBloomCache>,
         running_event_filter: Arc<Mutex<RunningEventFilter>>,
         trie_prune_mode: TriePruneMode,
+        trie_node_backend: Option<SharedTrieNodeBackend>,
     ) -> Self {
         Self {
             connection,
             event_filter_cache,
             running_event_filter,
             trie_prune_mode,
+            trie_node_backend,
         }
     }
 
@@ -61,6 +90,7 @@ impl Connection {
             event_filter_cache: self.event_filter_cache.clone(),
             running_event_filter: self.running_event_filter.clone(),
             trie_prune_mode: self.trie_prune_mode,
+            trie_node_backend: self.trie_node_backend.clone(),
         })
     }
 
@@ -74,8 +104,21 @@ impl Connection {
             event_filter_cache: self.event_filter_cache.clone(),
             running_event_filter: self.running_event_filter.clone(),
             trie_prune_mode: self.trie_prune_mode,
+            trie_node_backend: self.trie_node_backend.clone(),
         })
     }
+
+    /// Moves the content of the write-ahead log into the database and
+    /// truncates the log file.
+    ///
+    /// Returns `false` if the checkpoint could not complete because of
+    /// concurrent readers or writers.
+    pub fn checkpoint_wal(&self) -> anyhow::Result<bool> {
+        let busy: i64 =
+            self.connection
+                .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| row.get(0))?;
+        Ok(busy == 0)
+    }
 }
 
 pub struct Transaction<'inner> {
@@ -83,6 +126,7 @@ pub struct Transaction<'inner> {
     event_filter_cache: Arc<AggregateBloomCache>,
     running_event_filter: Arc<Mutex<RunningEventFilter>>,
     trie_prune_mode: TriePruneMode,
+    trie_node_backend: Option<SharedTrieNodeBackend>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -105,10 +149,22 @@ impl Transaction<'_> {
     // the real implementations be kept in separate files with more reasonable
     // LOC counts and easier test oversight.
 
-    fn inner(&self) -> &rusqlite::Transaction<'_> {
+    pub(crate) fn inner(&self) -> &rusqlite::Transaction<'_> {
         &self.transaction
     }
 
+    /// The backend storing the trie nodes, the sqlite database unless
+    /// configured otherwise.
+    pub(crate) fn trie_backend(&self) -> &dyn TrieNodeBackend {
+        match &self.trie_node_backend {
+            Some(backend) => backend.as_ref(),
+            None => {
+                let connection: &rusqlite::Connection = &self.transaction;
+                connection
+            }
+        }
+    }
+
     pub fn commit(self) -> anyhow::Result<()> {
         Ok(self.transaction.commit()?)
     }
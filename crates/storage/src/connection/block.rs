@@ -6,6 +6,7 @@ use pathfinder_common::{
     BlockHash,
     BlockHeader,
     BlockNumber,
+    BlockTimestamp,
     GasPrice,
     StarknetVersion,
     StateCommitment,
@@ -13,6 +14,7 @@ use pathfinder_common::{
     TransactionCommitment,
 };
 
+use crate::backend::TrieTable;
 use crate::prelude::*;
 use crate::BlockId;
 
@@ -122,6 +124,20 @@ impl Transaction<'_> {
             )
             .context("Deleting event bloom filter")?;
 
+        self.inner()
+            .execute(
+                "DELETE FROM event_selectors WHERE block_number = ?",
+                params![&block],
+            )
+            .context("Deleting event selectors")?;
+
+        self.inner()
+            .execute(
+                "DELETE FROM class_stats WHERE block_number = ?",
+                params![&block],
+            )
+            .context("Deleting class statistics")?;
+
         self.inner()
             .execute(
                 "DELETE FROM transactions WHERE block_number = ?",
@@ -199,6 +215,12 @@ impl Transaction<'_> {
             )
             .context("Deleting block from trie_class_removals table")?;
 
+        for table in TrieTable::ALL {
+            self.trie_backend()
+                .delete_block_nodes(table, block)
+                .with_context(|| format!("Deleting block from {} batches", table.name()))?;
+        }
+
         Ok(())
     }
 
@@ -230,6 +252,17 @@ impl Transaction<'_> {
                     Ok((number, hash))
                 },
             ),
+            BlockId::L1Accepted => self.inner().query_row(
+                "SELECT number, hash FROM canonical_blocks WHERE number = (SELECT l1_l2_head FROM \
+                 refs WHERE idx = 1)",
+                [],
+                |row| {
+                    let number = row.get_block_number(0)?;
+                    let hash = row.get_block_hash(1)?;
+
+                    Ok((number, hash))
+                },
+            ),
         }
         .optional()
         .map_err(|e| e.into())
@@ -266,6 +299,16 @@ impl Transaction<'_> {
                     .optional()
                     .map_err(|e| e.into())
             }
+            BlockId::L1Accepted => self
+                .inner()
+                .query_row(
+                    "SELECT hash FROM canonical_blocks WHERE number = (SELECT l1_l2_head FROM refs \
+                     WHERE idx = 1)",
+                    [],
+                    |row| row.get_block_hash(0),
+                )
+                .optional()
+                .map_err(|e| e.into()),
         }
     }
 
@@ -300,6 +343,16 @@ impl Transaction<'_> {
                 )
                 .optional()
                 .map_err(|e| e.into()),
+            BlockId::L1Accepted => self
+                .inner()
+                .query_row(
+                    "SELECT number FROM canonical_blocks WHERE number = (SELECT l1_l2_head FROM \
+                     refs WHERE idx = 1)",
+                    [],
+                    |row| row.get_block_number(0),
+                )
+                .optional()
+                .map_err(|e| e.into()),
         }
     }
 
@@ -323,6 +376,13 @@ impl Transaction<'_> {
                 )?;
                 stmt.query_row(params![&hash], |row| row.get(0))
             }
+            BlockId::L1Accepted => {
+                let mut stmt = self.inner().prepare_cached(
+                    "SELECT EXISTS(SELECT 1 FROM canonical_blocks WHERE number = (SELECT \
+                     l1_l2_head FROM refs WHERE idx = 1))",
+                )?;
+                stmt.query_row([], |row| row.get(0))
+            }
         }
         .map_err(|e| e.into())
     }
@@ -341,6 +401,10 @@ impl Transaction<'_> {
             BlockId::Latest => "SELECT * FROM block_headers ORDER BY number DESC LIMIT 1",
             BlockId::Number(_) => "SELECT * FROM block_headers WHERE number = ?",
             BlockId::Hash(_) => "SELECT * FROM block_headers WHERE hash = ?",
+            BlockId::L1Accepted => {
+                "SELECT * FROM block_headers WHERE number = (SELECT l1_l2_head FROM refs WHERE \
+                 idx = 1)"
+            }
         };
 
         let mut stmt = self
@@ -349,7 +413,7 @@ impl Transaction<'_> {
             .context("Preparing block header query")?;
 
         let header = match block {
-            BlockId::Latest => stmt.query_row([], parse_row_as_header),
+            BlockId::Latest | BlockId::L1Accepted => stmt.query_row([], parse_row_as_header),
             BlockId::Number(number) => stmt.query_row(params![&number], parse_row_as_header),
             BlockId::Hash(hash) => stmt.query_row(params![&hash], parse_row_as_header),
         }
@@ -380,6 +444,82 @@ impl Transaction<'_> {
         Ok(headers)
     }
 
+    /// Returns the latest block with a timestamp at or before `timestamp`.
+    pub fn block_number_at_or_before_timestamp(
+        &self,
+        timestamp: BlockTimestamp,
+    ) -> anyhow::Result<Option<BlockNumber>> {
+        let Some((first, partition_point, _)) =
+            self.partition_blocks_by_timestamp(|block| block > timestamp)?
+        else {
+            return Ok(None);
+        };
+        Ok((partition_point > first).then(|| BlockNumber::new_or_panic(partition_point - 1)))
+    }
+
+    /// Returns the earliest block with a timestamp at or after `timestamp`.
+    pub fn block_number_at_or_after_timestamp(
+        &self,
+        timestamp: BlockTimestamp,
+    ) -> anyhow::Result<Option<BlockNumber>> {
+        let Some((_, partition_point, end)) =
+            self.partition_blocks_by_timestamp(|block| block >= timestamp)?
+        else {
+            return Ok(None);
+        };
+        Ok((partition_point < end).then(|| BlockNumber::new_or_panic(partition_point)))
+    }
+
+    /// Binary searches the stored headers for the first block whose timestamp
+    /// satisfies `predicate`, relying on block timestamps never decreasing.
+    ///
+    /// Returns the number of the first stored block, the number of the block
+    /// found and the number after the last stored block, which is also
+    /// returned as the block found if no block satisfies `predicate`. Returns
+    /// [None] if there are no blocks.
+    fn partition_blocks_by_timestamp(
+        &self,
+        predicate: impl Fn(BlockTimestamp) -> bool,
+    ) -> anyhow::Result<Option<(u64, u64, u64)>> {
+        let bounds = self
+            .inner()
+            .query_row(
+                "SELECT MIN(number), MAX(number) FROM block_headers",
+                [],
+                |row| {
+                    Ok(row
+                        .get_optional_block_number(0)?
+                        .zip(row.get_optional_block_number(1)?))
+                },
+            )
+            .context("Querying block range")?;
+        let Some((first, last)) = bounds else {
+            return Ok(None);
+        };
+
+        let mut stmt = self
+            .inner()
+            .prepare_cached("SELECT timestamp FROM block_headers WHERE number = ?")
+            .context("Preparing block timestamp query")?;
+
+        let (mut low, mut high) = (first.get(), last.get() + 1);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let timestamp = stmt
+                .query_row(params![&BlockNumber::new_or_panic(middle)], |row| {
+                    row.get_timestamp(0)
+                })
+                .with_context(|| format!("Querying timestamp of block {middle}"))?;
+            if predicate(timestamp) {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+
+        Ok(Some((first.get(), low, last.get() + 1)))
+    }
+
     pub fn state_commitment(&self, block: BlockId) -> anyhow::Result<Option<StateCommitment>> {
         let sql = match block {
             BlockId::Latest => {
@@ -387,6 +527,10 @@ impl Transaction<'_> {
             }
             BlockId::Number(_) => "SELECT state_commitment FROM block_headers WHERE number = ?",
             BlockId::Hash(_) => "SELECT state_commitment FROM block_headers WHERE hash = ?",
+            BlockId::L1Accepted => {
+                "SELECT state_commitment FROM block_headers WHERE number = (SELECT l1_l2_head \
+                 FROM refs WHERE idx = 1)"
+            }
         };
 
         let mut stmt = self
@@ -395,7 +539,7 @@ impl Transaction<'_> {
             .context("Preparing state commitment query")?;
 
         let state_commitment = match block {
-            BlockId::Latest => {
+            BlockId::Latest | BlockId::L1Accepted => {
                 stmt.query_row([], |row| row.get_state_commitment("state_commitment"))
             }
             BlockId::Number(number) => stmt.query_row(params![&number], |row| {
@@ -536,18 +680,24 @@ impl Transaction<'_> {
         Ok(ret)
     }
 
-    pub fn state_diff_commitment(
+    /// Returns the state diff commitment and length of the block's header.
+    pub fn state_diff_commitment_and_length(
         &self,
         block_number: BlockNumber,
-    ) -> anyhow::Result<Option<StateDiffCommitment>> {
+    ) -> anyhow::Result<Option<(StateDiffCommitment, u64)>> {
         let mut stmt = self
             .inner()
-            .prepare_cached("SELECT state_diff_commitment FROM block_headers WHERE number = ?")
+            .prepare_cached(
+                "SELECT state_diff_commitment, state_diff_length FROM block_headers \
+                 WHERE number = ?",
+            )
             .context("Preparing state diff commitment query")?;
 
         let state_diff_commitment = stmt
             .query_row(params![&block_number], |row| {
-                row.get_state_diff_commitment("state_diff_commitment")
+                let commitment = row.get_state_diff_commitment("state_diff_commitment")?;
+                let length: u64 = row.get("state_diff_length")?;
+                Ok((commitment, length))
             })
             .optional()
             .context("Querying for state diff commitment")?;
@@ -797,6 +947,26 @@ mod tests {
         assert_eq!(class_exists, None);
     }
 
+    #[test]
+    fn block_number_by_timestamp() {
+        let (mut connection, headers) = setup();
+        let tx = connection.transaction().unwrap();
+        // Timestamps of the headers are 10, 12 and 15.
+        let timestamp = BlockTimestamp::new_or_panic;
+
+        let before = |t| tx.block_number_at_or_before_timestamp(timestamp(t)).unwrap();
+        assert_eq!(before(9), None);
+        assert_eq!(before(10), Some(headers[0].number));
+        assert_eq!(before(13), Some(headers[1].number));
+        assert_eq!(before(100), Some(headers[2].number));
+
+        let after = |t| tx.block_number_at_or_after_timestamp(timestamp(t)).unwrap();
+        assert_eq!(after(0), Some(headers[0].number));
+        assert_eq!(after(12), Some(headers[1].number));
+        assert_eq!(after(13), Some(headers[2].number));
+        assert_eq!(after(16), None);
+    }
+
     #[test]
     fn block_id() {
         let (mut connection, headers) = setup();
@@ -832,6 +1002,39 @@ mod tests {
         assert!(!l2_by_number);
     }
 
+    #[test]
+    fn l1_accepted_block_id() {
+        let (mut connection, headers) = setup();
+        let tx = connection.transaction().unwrap();
+
+        assert_eq!(tx.block_id(BlockId::L1Accepted).unwrap(), None);
+        assert!(!tx.block_exists(BlockId::L1Accepted).unwrap());
+
+        tx.update_l1_l2_pointer(Some(headers[1].number)).unwrap();
+
+        assert_eq!(
+            tx.block_id(BlockId::L1Accepted).unwrap(),
+            Some((headers[1].number, headers[1].hash))
+        );
+        assert_eq!(
+            tx.block_hash(BlockId::L1Accepted).unwrap(),
+            Some(headers[1].hash)
+        );
+        assert_eq!(
+            tx.block_number(BlockId::L1Accepted).unwrap(),
+            Some(headers[1].number)
+        );
+        assert!(tx.block_exists(BlockId::L1Accepted).unwrap());
+        assert_eq!(
+            tx.block_header(BlockId::L1Accepted).unwrap().as_ref(),
+            Some(&headers[1])
+        );
+        assert_eq!(
+            tx.state_commitment(BlockId::L1Accepted).unwrap(),
+            Some(headers[1].state_commitment)
+        );
+    }
+
     mod next_ancestor {
         use pretty_assertions_sorted::assert_eq;
 
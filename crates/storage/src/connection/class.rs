@@ -136,6 +136,37 @@ impl Transaction<'_> {
             .collect::<Result<Vec<_>, _>>()?)
     }
 
+    /// Returns the uncompressed definitions of the given classes, in the same
+    /// order.
+    ///
+    /// Classes which are unknown or whose declaration we have not stored yet
+    /// are `None`.
+    pub fn declared_class_definitions(
+        &self,
+        classes: &[ClassHash],
+    ) -> anyhow::Result<Vec<Option<Vec<u8>>>> {
+        let mut stmt = self.inner().prepare_cached(
+            "SELECT definition FROM class_definitions WHERE hash = ? AND block_number IS NOT NULL",
+        )?;
+
+        classes
+            .iter()
+            .map(|hash| {
+                let definition = stmt
+                    .query_row(params![hash], |row| row.get_blob(0).map(|x| x.to_vec()))
+                    .optional()
+                    .context("Querying for class definition")?;
+
+                definition
+                    .map(|definition| {
+                        zstd::decode_all(definition.as_slice())
+                            .context("Decompressing class definition")
+                    })
+                    .transpose()
+            })
+            .collect()
+    }
+
     /// Returns the uncompressed class definition.
     pub fn class_definition(&self, class_hash: ClassHash) -> anyhow::Result<Option<Vec<u8>>> {
         self.class_definition_with_block_number(class_hash)
@@ -195,6 +226,14 @@ impl Transaction<'_> {
         };
 
         match block_id {
+        BlockId::L1Accepted => {
+            return match self.l1_l2_pointer()? {
+                Some(number) => {
+                    self.compressed_class_definition_at_with_block_number(number.into(), class_hash)
+                }
+                None => Ok(None),
+            };
+        }
         BlockId::Latest => {
             let mut stmt = self.inner().prepare_cached(
                 "SELECT definition, block_number FROM class_definitions WHERE hash=? AND block_number IS NOT NULL",
@@ -345,6 +384,14 @@ impl Transaction<'_> {
         };
 
         let definition = match block_id {
+        BlockId::L1Accepted => {
+            return match self.l1_l2_pointer()? {
+                Some(number) => {
+                    self.casm_definition_at_with_block_number(number.into(), class_hash)
+                }
+                None => Ok(None),
+            };
+        }
         BlockId::Latest => {
             let mut stmt = self.inner().prepare_cached(
                 r"SELECT
@@ -425,6 +472,12 @@ impl Transaction<'_> {
         class_hash: ClassHash,
     ) -> anyhow::Result<Option<CasmHash>> {
         let compiled_class_hash = match block_id {
+        BlockId::L1Accepted => {
+            return match self.l1_l2_pointer()? {
+                Some(number) => self.casm_hash_at(number.into(), class_hash),
+                None => Ok(None),
+            };
+        }
         BlockId::Latest => {
             let mut stmt = self.inner().prepare_cached(
             r#"SELECT
@@ -520,6 +573,7 @@ impl Transaction<'_> {
 #[cfg(test)]
 mod tests {
     use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::{BlockHeader, StateUpdate};
     use pathfinder_crypto::Felt;
 
     use super::*;
@@ -556,6 +610,42 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn declared_class_definitions() {
+        let mut connection = crate::StorageBuilder::in_memory()
+            .unwrap()
+            .connection()
+            .unwrap();
+        let transaction = connection.transaction().unwrap();
+
+        let declared = class_hash_bytes!(b"declared");
+        let undeclared = class_hash_bytes!(b"undeclared");
+        let unknown = class_hash_bytes!(b"unknown");
+        transaction
+            .insert_cairo_class(declared, b"declared definition")
+            .unwrap();
+        transaction
+            .insert_cairo_class(undeclared, b"undeclared definition")
+            .unwrap();
+
+        let header = BlockHeader::builder().finalize_with_hash(block_hash!("0xabc"));
+        transaction.insert_block_header(&header).unwrap();
+        transaction
+            .insert_state_update(
+                header.number,
+                &StateUpdate::default().with_declared_cairo_class(declared),
+            )
+            .unwrap();
+
+        let result = transaction
+            .declared_class_definitions(&[unknown, declared, undeclared])
+            .unwrap();
+        assert_eq!(
+            result,
+            vec![None, Some(b"declared definition".to_vec()), None]
+        );
+    }
+
     #[test]
     fn insert_cairo() {
         let mut connection = crate::StorageBuilder::in_memory()
@@ -0,0 +1,253 @@
+//! Per-class usage statistics, aggregated per block.
+
+use std::collections::HashMap;
+
+use anyhow::Context;
+use pathfinder_common::transaction::TransactionVariant;
+use pathfinder_common::{BlockNumber, ClassHash};
+
+use crate::prelude::*;
+
+/// Usage of a class by the transactions of a range of blocks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassStats {
+    pub class_hash: ClassHash,
+    /// Number of transactions whose target contract, i.e. the account for
+    /// invoke and declare transactions, is of this class.
+    pub invocations: u64,
+    pub l1_gas: u64,
+    pub l1_data_gas: u64,
+    pub l2_gas: u64,
+}
+
+impl Transaction<'_> {
+    /// Aggregates the transactions of `block` by the class of the contract
+    /// they target.
+    ///
+    /// Must be called after the block's transactions and state update have
+    /// been inserted, as the class of a contract deployed in the same block is
+    /// only known from the latter.
+    pub fn insert_class_stats(&self, block: BlockNumber) -> anyhow::Result<()> {
+        let transactions = self
+            .transaction_data_for_block(block.into())
+            .context("Querying transactions")?
+            .context("Block transactions are missing")?;
+
+        let mut stats: HashMap<ClassHash, ClassStats> = HashMap::new();
+        for (transaction, receipt, _) in transactions {
+            let class_hash = match &transaction.variant {
+                TransactionVariant::DeployV0(tx) => tx.class_hash,
+                TransactionVariant::DeployV1(tx) => tx.class_hash,
+                TransactionVariant::DeployAccountV1(tx) => tx.class_hash,
+                TransactionVariant::DeployAccountV3(tx) => tx.class_hash,
+                variant => {
+                    let contract_address = match variant {
+                        TransactionVariant::DeclareV0(tx) | TransactionVariant::DeclareV1(tx) => {
+                            tx.sender_address
+                        }
+                        TransactionVariant::DeclareV2(tx) => tx.sender_address,
+                        TransactionVariant::DeclareV3(tx) => tx.sender_address,
+                        TransactionVariant::InvokeV0(tx) => tx.sender_address,
+                        TransactionVariant::InvokeV1(tx) => tx.sender_address,
+                        TransactionVariant::InvokeV3(tx) => tx.sender_address,
+                        TransactionVariant::L1Handler(tx) => tx.contract_address,
+                        _ => unreachable!("Deploy transactions are handled above"),
+                    };
+                    let Some(class_hash) = self
+                        .contract_class_hash(block.into(), contract_address)
+                        .context("Querying contract class")?
+                    else {
+                        // E.g. version 0 declare transactions are sent from 0x1, which is not a
+                        // contract.
+                        continue;
+                    };
+                    class_hash
+                }
+            };
+
+            let resources = &receipt.execution_resources;
+            let entry = stats.entry(class_hash).or_insert_with(|| ClassStats {
+                class_hash,
+                ..Default::default()
+            });
+            entry.invocations += 1;
+            entry.l1_gas += u64::try_from(resources.total_gas_consumed.l1_gas)?;
+            entry.l1_data_gas += u64::try_from(resources.total_gas_consumed.l1_data_gas)?;
+            entry.l2_gas += u64::try_from(resources.l2_gas.0)?;
+        }
+
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            INSERT INTO class_stats
+                (block_number, class_hash, invocations, l1_gas, l1_data_gas, l2_gas)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ",
+        )?;
+        for stats in stats.into_values() {
+            stmt.execute(params![
+                &block,
+                &stats.class_hash,
+                &stats.invocations.try_into_sql_int()?,
+                &stats.l1_gas.try_into_sql_int()?,
+                &stats.l1_data_gas.try_into_sql_int()?,
+                &stats.l2_gas.try_into_sql_int()?,
+            ])
+            .context("Inserting class statistics")?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the usage of the `limit` most invoked classes in
+    /// `from..=to`, most invoked first.
+    ///
+    /// Only covers blocks for which [Transaction::insert_class_stats] was
+    /// called.
+    pub fn class_stats(
+        &self,
+        from: BlockNumber,
+        to: BlockNumber,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ClassStats>> {
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            SELECT class_hash, SUM(invocations), SUM(l1_gas), SUM(l1_data_gas), SUM(l2_gas)
+            FROM class_stats
+            WHERE block_number BETWEEN ? AND ?
+            GROUP BY class_hash
+            ORDER BY SUM(invocations) DESC, class_hash
+            LIMIT ?
+            ",
+        )?;
+
+        stmt.query_map(params![&from, &to, &limit.try_into_sql_int()?], |row| {
+            Ok(ClassStats {
+                class_hash: row.get_class_hash(0)?,
+                invocations: row.get_i64(1)? as u64,
+                l1_gas: row.get_i64(2)? as u64,
+                l1_data_gas: row.get_i64(3)? as u64,
+                l2_gas: row.get_i64(4)? as u64,
+            })
+        })
+        .context("Querying class statistics")?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::receipt::{ExecutionResources, L1Gas, L2Gas, Receipt};
+    use pathfinder_common::transaction::{
+        DeployAccountTransactionV1,
+        InvokeTransactionV1,
+        Transaction as StarknetTransaction,
+    };
+    use pathfinder_common::{BlockHeader, StateUpdate, TransactionHash};
+
+    use super::*;
+
+    fn receipt(transaction_hash: TransactionHash, l2_gas: u128) -> Receipt {
+        Receipt {
+            transaction_hash,
+            execution_resources: ExecutionResources {
+                total_gas_consumed: L1Gas {
+                    l1_gas: 10,
+                    l1_data_gas: 1,
+                },
+                l2_gas: L2Gas(l2_gas),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn aggregates_by_target_class() {
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let account = contract_address!("0xa");
+        let account_class = class_hash!("0xac");
+        let deployed_class = class_hash!("0xdc");
+
+        let header = BlockHeader::builder().finalize_with_hash(block_hash!("0x1"));
+        tx.insert_block_header(&header).unwrap();
+        tx.insert_state_update(
+            header.number,
+            &StateUpdate::default().with_deployed_contract(account, account_class),
+        )
+        .unwrap();
+
+        let invoke = |hash| StarknetTransaction {
+            hash,
+            variant: TransactionVariant::InvokeV1(InvokeTransactionV1 {
+                sender_address: account,
+                ..Default::default()
+            }),
+        };
+        let deploy_account = StarknetTransaction {
+            hash: transaction_hash!("0x3"),
+            variant: TransactionVariant::DeployAccountV1(DeployAccountTransactionV1 {
+                class_hash: deployed_class,
+                ..Default::default()
+            }),
+        };
+        let transactions = vec![
+            (
+                invoke(transaction_hash!("0x1")),
+                receipt(transaction_hash!("0x1"), 100),
+            ),
+            (
+                invoke(transaction_hash!("0x2")),
+                receipt(transaction_hash!("0x2"), 200),
+            ),
+            (deploy_account, receipt(transaction_hash!("0x3"), 50)),
+        ];
+        tx.insert_transaction_data(
+            header.number,
+            &transactions,
+            Some(&[vec![], vec![], vec![]]),
+        )
+        .unwrap();
+
+        tx.insert_class_stats(header.number).unwrap();
+
+        let stats = tx
+            .class_stats(BlockNumber::GENESIS, header.number, 10)
+            .unwrap();
+        assert_eq!(
+            stats,
+            vec![
+                ClassStats {
+                    class_hash: account_class,
+                    invocations: 2,
+                    l1_gas: 20,
+                    l1_data_gas: 2,
+                    l2_gas: 300,
+                },
+                ClassStats {
+                    class_hash: deployed_class,
+                    invocations: 1,
+                    l1_gas: 10,
+                    l1_data_gas: 1,
+                    l2_gas: 50,
+                },
+            ]
+        );
+
+        let top = tx
+            .class_stats(BlockNumber::GENESIS, header.number, 1)
+            .unwrap();
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].class_hash, account_class);
+
+        tx.purge_block(header.number).unwrap();
+        let stats = tx
+            .class_stats(BlockNumber::GENESIS, header.number, 10)
+            .unwrap();
+        assert_eq!(stats, vec![]);
+    }
+}
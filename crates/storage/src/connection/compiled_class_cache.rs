@@ -0,0 +1,113 @@
+//! Class definitions prepared for execution, persisted across restarts.
+//!
+//! Entries are keyed by class hash and a version chosen by the executor, so
+//! that entries written by a different executor are never used.
+
+use anyhow::Context;
+use pathfinder_common::{BlockNumber, ClassHash};
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedCompiledClass {
+    /// The block the class was declared in, `None` if it is only known from
+    /// the pending block.
+    pub block_number: Option<BlockNumber>,
+    /// Set for Sierra classes.
+    pub sierra_version: Option<String>,
+    pub definition: Vec<u8>,
+}
+
+impl Transaction<'_> {
+    /// Returns the cached definition of a class, if the class is known.
+    pub fn cached_compiled_class(
+        &self,
+        class_hash: ClassHash,
+        version: &str,
+    ) -> anyhow::Result<Option<CachedCompiledClass>> {
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            SELECT class_definitions.block_number, compiled_class_cache.sierra_version,
+                compiled_class_cache.definition
+            FROM compiled_class_cache
+            JOIN class_definitions ON class_definitions.hash = compiled_class_cache.class_hash
+            WHERE compiled_class_cache.class_hash = ? AND compiled_class_cache.version = ?
+            ",
+        )?;
+
+        stmt.query_row(params![&class_hash, &version], |row| {
+            Ok(CachedCompiledClass {
+                block_number: row.get_optional_block_number(0)?,
+                sierra_version: row.get_optional_str(1)?.map(ToOwned::to_owned),
+                definition: row.get_blob(2)?.to_vec(),
+            })
+        })
+        .optional()
+        .context("Querying cached compiled class")
+    }
+
+    pub fn insert_cached_compiled_class(
+        &self,
+        class_hash: ClassHash,
+        version: &str,
+        sierra_version: Option<&str>,
+        definition: &[u8],
+    ) -> anyhow::Result<()> {
+        self.inner()
+            .execute(
+                r"
+                INSERT OR REPLACE INTO compiled_class_cache
+                    (class_hash, version, sierra_version, definition)
+                VALUES (?, ?, ?, ?)
+                ",
+                params![&class_hash, &version, &sierra_version, &definition],
+            )
+            .context("Inserting cached compiled class")?;
+
+        Ok(())
+    }
+
+    /// Removes the cached classes of all versions other than `version`.
+    /// Returns the number of removed classes.
+    pub fn purge_compiled_class_cache(&self, version: &str) -> anyhow::Result<usize> {
+        self.inner()
+            .execute(
+                "DELETE FROM compiled_class_cache WHERE version != ?",
+                params![&version],
+            )
+            .context("Purging compiled class cache")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+    use crate::StorageBuilder;
+
+    #[test]
+    fn cached_classes_are_versioned() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let class_hash = class_hash!("0x123");
+        tx.insert_cairo_class(class_hash, b"definition").unwrap();
+        tx.insert_cached_compiled_class(class_hash, "1", None, b"prepared")
+            .unwrap();
+
+        assert_eq!(
+            tx.cached_compiled_class(class_hash, "1").unwrap(),
+            Some(CachedCompiledClass {
+                block_number: None,
+                sierra_version: None,
+                definition: b"prepared".to_vec(),
+            })
+        );
+        assert_eq!(tx.cached_compiled_class(class_hash, "2").unwrap(), None);
+
+        assert_eq!(tx.purge_compiled_class_cache("2").unwrap(), 1);
+        assert_eq!(tx.cached_compiled_class(class_hash, "1").unwrap(), None);
+    }
+}
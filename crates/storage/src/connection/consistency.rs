@@ -0,0 +1,325 @@
+//! Checks of invariants spanning several tables, which the schema itself does
+//! not enforce.
+
+use anyhow::Context;
+use pathfinder_common::{BlockNumber, ClassHash, ContractAddress};
+
+use crate::backend::TrieTable;
+use crate::prelude::*;
+
+/// A violated cross-table invariant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inconsistency {
+    /// The block header counts transactions but the transaction data is
+    /// missing.
+    MissingTransactions { block: BlockNumber },
+    /// A contract was deployed with, or replaced by, a class we know nothing
+    /// about.
+    MissingClass {
+        block: BlockNumber,
+        contract_address: ContractAddress,
+        class_hash: ClassHash,
+    },
+    /// The root of a Merkle trie refers to a node which does not exist.
+    MissingTrieRoot {
+        block: BlockNumber,
+        trie: TrieKind,
+        root_index: u64,
+    },
+    /// No event filter covers these blocks.
+    EventFilterGap {
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrieKind {
+    Class,
+    Storage,
+    Contract(ContractAddress),
+}
+
+impl Inconsistency {
+    /// The first block whose data is affected.
+    pub fn block(&self) -> BlockNumber {
+        match self {
+            Inconsistency::MissingTransactions { block }
+            | Inconsistency::MissingClass { block, .. }
+            | Inconsistency::MissingTrieRoot { block, .. } => *block,
+            Inconsistency::EventFilterGap { from_block, .. } => *from_block,
+        }
+    }
+}
+
+impl std::fmt::Display for Inconsistency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Inconsistency::MissingTransactions { block } => {
+                write!(f, "block {block} has no transaction data")
+            }
+            Inconsistency::MissingClass {
+                block,
+                contract_address,
+                class_hash,
+            } => write!(
+                f,
+                "block {block} sets the class of contract {contract_address} to unknown class \
+                 {class_hash}"
+            ),
+            Inconsistency::MissingTrieRoot {
+                block,
+                trie,
+                root_index,
+            } => {
+                let trie = match trie {
+                    TrieKind::Class => "class".to_owned(),
+                    TrieKind::Storage => "storage".to_owned(),
+                    TrieKind::Contract(address) => format!("contract {address} storage"),
+                };
+                write!(
+                    f,
+                    "block {block} has a {trie} trie root referring to missing node {root_index}"
+                )
+            }
+            Inconsistency::EventFilterGap {
+                from_block,
+                to_block,
+            } => write!(
+                f,
+                "blocks {from_block}..={to_block} are not covered by an event filter"
+            ),
+        }
+    }
+}
+
+impl Transaction<'_> {
+    /// Checks the invariants between the block, transaction, state update,
+    /// class, trie and event filter tables.
+    ///
+    /// This reads the whole database and can take a long time.
+    pub fn consistency_check(&self) -> anyhow::Result<Vec<Inconsistency>> {
+        let mut inconsistencies = Vec::new();
+
+        inconsistencies.extend(self.blocks_missing_transactions()?);
+        inconsistencies.extend(self.contract_updates_missing_class()?);
+        inconsistencies.extend(self.trie_roots_missing_node()?);
+        inconsistencies.extend(self.event_filter_gaps()?);
+
+        inconsistencies.sort_by_key(Inconsistency::block);
+
+        Ok(inconsistencies)
+    }
+
+    fn blocks_missing_transactions(&self) -> anyhow::Result<Vec<Inconsistency>> {
+        let mut stmt = self.inner().prepare(
+            r"
+            SELECT number FROM block_headers
+            WHERE transaction_count > 0
+            AND NOT EXISTS (SELECT 1 FROM transactions WHERE block_number = number)
+            ORDER BY number
+            ",
+        )?;
+
+        stmt.query_map([], |row| {
+            Ok(Inconsistency::MissingTransactions {
+                block: row.get_block_number(0)?,
+            })
+        })
+        .context("Querying blocks without transactions")?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(Into::into)
+    }
+
+    fn contract_updates_missing_class(&self) -> anyhow::Result<Vec<Inconsistency>> {
+        let mut stmt = self.inner().prepare(
+            r"
+            SELECT block_number, contract_address, class_hash FROM contract_updates
+            WHERE NOT EXISTS (SELECT 1 FROM class_definitions WHERE hash = class_hash)
+            ORDER BY block_number
+            ",
+        )?;
+
+        stmt.query_map([], |row| {
+            Ok(Inconsistency::MissingClass {
+                block: row.get_block_number(0)?,
+                contract_address: row.get_contract_address(1)?,
+                class_hash: row.get_class_hash(2)?,
+            })
+        })
+        .context("Querying contract updates with unknown classes")?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(Into::into)
+    }
+
+    fn trie_roots_missing_node(&self) -> anyhow::Result<Vec<Inconsistency>> {
+        let backend = self.trie_backend();
+        let mut inconsistencies = Vec::new();
+
+        // Nodes may be batched, so their existence is checked through the backend
+        // rather than by joining against the node tables.
+        for (roots, table, trie) in [
+            ("class_roots", TrieTable::Class, TrieKind::Class),
+            ("storage_roots", TrieTable::Storage, TrieKind::Storage),
+        ] {
+            let mut stmt = self.inner().prepare(&format!(
+                "SELECT block_number, root_index FROM {roots} WHERE root_index IS NOT NULL"
+            ))?;
+            let mut rows = stmt
+                .query([])
+                .with_context(|| format!("Querying {roots}"))?;
+
+            while let Some(row) = rows.next()? {
+                let root_index: u64 = row.get(1)?;
+                if backend.node_hash(table, root_index)?.is_none() {
+                    inconsistencies.push(Inconsistency::MissingTrieRoot {
+                        block: row.get_block_number(0)?,
+                        trie,
+                        root_index,
+                    });
+                }
+            }
+        }
+
+        let mut stmt = self.inner().prepare(
+            "SELECT block_number, contract_address, root_index FROM contract_roots WHERE \
+             root_index IS NOT NULL",
+        )?;
+        let mut rows = stmt.query([]).context("Querying contract_roots")?;
+        while let Some(row) = rows.next()? {
+            let root_index: u64 = row.get(2)?;
+            if backend
+                .node_hash(TrieTable::Contracts, root_index)?
+                .is_none()
+            {
+                inconsistencies.push(Inconsistency::MissingTrieRoot {
+                    block: row.get_block_number(0)?,
+                    trie: TrieKind::Contract(row.get_contract_address(1)?),
+                    root_index,
+                });
+            }
+        }
+
+        Ok(inconsistencies)
+    }
+
+    /// The stored event filters must cover consecutive block ranges starting at
+    /// genesis. Blocks after the last stored filter are covered by the running
+    /// filter, which is rebuilt on startup.
+    fn event_filter_gaps(&self) -> anyhow::Result<Vec<Inconsistency>> {
+        let mut stmt = self
+            .inner()
+            .prepare("SELECT from_block, to_block FROM event_filters ORDER BY from_block")?;
+        let ranges = stmt
+            .query_map([], |row| {
+                Ok((row.get_block_number(0)?, row.get_block_number(1)?))
+            })
+            .context("Querying event filters")?;
+
+        let mut inconsistencies = Vec::new();
+        let mut next_block = BlockNumber::GENESIS;
+        for range in ranges {
+            let (from_block, to_block) = range?;
+            if from_block > next_block {
+                inconsistencies.push(Inconsistency::EventFilterGap {
+                    from_block: next_block,
+                    to_block: from_block - 1,
+                });
+            }
+            next_block = next_block.max(to_block + 1);
+        }
+
+        Ok(inconsistencies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+    use pathfinder_common::StateUpdate;
+
+    use super::*;
+    use crate::fake::Block;
+    use crate::{Storage, StorageBuilder};
+
+    fn setup() -> (Storage, Vec<Block>) {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let blocks = crate::fake::generate::n_blocks(3);
+        crate::fake::fill(&storage, &blocks, None);
+        (storage, blocks)
+    }
+
+    #[test]
+    fn consistent_database() {
+        let (storage, _) = setup();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        assert_eq!(tx.consistency_check().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn missing_class() {
+        let (storage, blocks) = setup();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let block = blocks[1].header.header.number;
+        tx.insert_state_update(
+            block,
+            &StateUpdate::default()
+                .with_deployed_contract(contract_address!("0xc"), class_hash!("0xdead")),
+        )
+        .unwrap();
+
+        assert_eq!(
+            tx.consistency_check().unwrap(),
+            vec![Inconsistency::MissingClass {
+                block,
+                contract_address: contract_address!("0xc"),
+                class_hash: class_hash!("0xdead"),
+            }]
+        );
+    }
+
+    #[test]
+    fn missing_transactions_and_event_filter_gap() {
+        let (storage, blocks) = setup();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let block = blocks
+            .iter()
+            .find(|block| !block.transaction_data.is_empty())
+            .unwrap()
+            .header
+            .header
+            .number;
+        tx.inner()
+            .execute(
+                "DELETE FROM transactions WHERE block_number = ?",
+                params![&block],
+            )
+            .unwrap();
+        tx.inner()
+            .execute(
+                "INSERT INTO event_filters (from_block, to_block, bitmap) VALUES (?, ?, x'')",
+                params![
+                    &BlockNumber::new_or_panic(10),
+                    &BlockNumber::new_or_panic(19)
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(
+            tx.consistency_check().unwrap(),
+            vec![
+                Inconsistency::EventFilterGap {
+                    from_block: BlockNumber::GENESIS,
+                    to_block: BlockNumber::new_or_panic(9),
+                },
+                Inconsistency::MissingTransactions { block },
+            ]
+        );
+    }
+}
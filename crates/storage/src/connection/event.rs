@@ -1,3 +1,4 @@
+use std::collections::{BTreeSet, HashSet};
 use std::num::NonZeroUsize;
 use std::rc::Rc;
 use std::sync::Arc;
@@ -13,10 +14,12 @@ use pathfinder_common::{
     EventKey,
     TransactionHash,
 };
+use rayon::prelude::*;
 use rusqlite::types::Value;
 
 use crate::bloom::{AggregateBloom, BlockRange, BloomFilter};
 use crate::prelude::*;
+use crate::AGGREGATE_BLOOM_BLOCK_RANGE_LEN;
 
 // We're using the upper 4 bits of the 32 byte representation of a felt
 // to store the index of the key in the values set in the Bloom filter.
@@ -25,6 +28,14 @@ use crate::prelude::*;
 pub const EVENT_KEY_FILTER_LIMIT: usize = 16;
 pub const PAGE_SIZE_LIMIT: usize = 1_024;
 
+/// Set in `storage_flags` while the event filters of completed block ranges
+/// are not stored, see [Transaction::defer_event_filters].
+const EVENT_FILTERS_DEFERRED_FLAG: &str = "event_filters_deferred";
+
+/// The number of blocks whose events are loaded at once when building a
+/// deferred event filter.
+const DEFERRED_EVENT_FILTER_CHUNK_LEN: u64 = 512;
+
 #[derive(Debug, Default)]
 pub struct EventConstraints {
     pub from_block: Option<BlockNumber>,
@@ -33,6 +44,20 @@ pub struct EventConstraints {
     pub keys: Vec<Vec<EventKey>>,
     pub page_size: usize,
     pub offset: usize,
+    pub order: EventOrder,
+}
+
+/// The order events are returned in.
+///
+/// Events are ordered by block number, then by the index of the emitting
+/// transaction within the block, then by the index of the event within the
+/// transaction's events. [EventOrder::Descending] reverses this order as a
+/// whole, i.e. the most recent event comes first.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum EventOrder {
+    #[default]
+    Ascending,
+    Descending,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -51,6 +76,10 @@ pub enum EventFilterError {
     Internal(#[from] anyhow::Error),
     #[error("requested page size is too small, supported minimum is 1")]
     PageSizeTooSmall,
+    #[error("events before block {available_from} have been pruned")]
+    Pruned { available_from: BlockNumber },
+    #[error("event filters are being built, events before block {available_from} are unavailable")]
+    FiltersDeferred { available_from: BlockNumber },
 }
 
 impl From<rusqlite::Error> for EventFilterError {
@@ -59,6 +88,22 @@ impl From<rusqlite::Error> for EventFilterError {
     }
 }
 
+/// The event filter of a block range, built after the fact by
+/// [Transaction::build_deferred_event_filter].
+pub struct DeferredEventFilter(AggregateBloom);
+
+impl DeferredEventFilter {
+    pub fn from_block(&self) -> BlockNumber {
+        self.0.from_block
+    }
+
+    pub fn to_block(&self) -> BlockNumber {
+        self.0.to_block
+    }
+}
+
+/// Points to the next event of a page. In [EventOrder::Descending] order the
+/// `offset` counts events from the end of the block.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct ContinuationToken {
     pub block_number: BlockNumber,
@@ -83,13 +128,13 @@ impl Transaction<'_> {
     /// Upsert the [running event Bloom filter](RunningEventFilter) for the
     /// given block number. This function operates under the assumption that
     /// blocks are _never_ skipped so even if there are no events for a
-    /// block, this function should still be called with an empty iterator.
+    /// block, this function should still be called with an empty filter.
     /// When testing it is fine to skip blocks, as long as the block at the end
     /// of the current range is not skipped.
-    pub(super) fn upsert_block_event_filters<'a>(
+    pub(super) fn upsert_block_event_filters(
         &self,
         block_number: BlockNumber,
-        events: impl Iterator<Item = &'a Event>,
+        block_filter: &BlockEventFilter,
     ) -> anyhow::Result<()> {
         let mut insert_stmt = self.inner().prepare_cached(
             r"
@@ -102,33 +147,284 @@ impl Transaction<'_> {
 
         let mut running_event_filter = self.running_event_filter.lock().unwrap();
 
-        let mut bloom = BloomFilter::new();
-        for event in events {
-            bloom.set_keys(&event.keys);
-            bloom.set_address(&event.from_address);
-        }
-
-        running_event_filter.filter.insert(&bloom, block_number);
+        running_event_filter
+            .filter
+            .insert(&block_filter.0, block_number);
         running_event_filter.next_block = block_number + 1;
 
         // This check is the reason that blocks cannot be skipped, if they were we would
         // risk missing the last block of the running event filter's range.
         if block_number == running_event_filter.filter.to_block {
-            insert_stmt.execute(params![
-                &running_event_filter.filter.from_block,
-                &running_event_filter.filter.to_block,
-                &running_event_filter.filter.compress_bitmap()
-            ])?;
+            if !running_event_filter.deferred {
+                insert_stmt.execute(params![
+                    &running_event_filter.filter.from_block,
+                    &running_event_filter.filter.to_block,
+                    &running_event_filter.filter.compress_bitmap()
+                ])?;
+            }
 
             *running_event_filter = RunningEventFilter {
                 filter: AggregateBloom::new(block_number + 1),
                 next_block: block_number + 1,
+                deferred: running_event_filter.deferred,
             };
         }
 
         Ok(())
     }
 
+    /// Stops storing the event filters of completed block ranges, which speeds
+    /// up initial sync. Until the deferral ends only events of the block range
+    /// the latest block belongs to can be queried.
+    ///
+    /// The skipped filters are built afterwards with
+    /// [Self::build_deferred_event_filter], after which
+    /// [Self::end_event_filter_deferral] resumes storing them as usual.
+    pub fn defer_event_filters(&self) -> anyhow::Result<()> {
+        self.inner()
+            .execute(
+                "INSERT OR IGNORE INTO storage_flags (flag) VALUES (?)",
+                [EVENT_FILTERS_DEFERRED_FLAG],
+            )
+            .context("Setting event filter deferral flag")?;
+        self.running_event_filter.lock().unwrap().deferred = true;
+
+        Ok(())
+    }
+
+    pub fn event_filters_deferred(&self) -> bool {
+        self.running_event_filter.lock().unwrap().deferred
+    }
+
+    /// Builds the filter of the first completed block range whose event filter
+    /// was not stored because of the [deferral](Self::defer_event_filters).
+    ///
+    /// Returns [None] if there is no such range, or if not all of its blocks
+    /// are visible to this transaction yet. This only reads from the database
+    /// so that the write lock is not held while events are decoded, the filter
+    /// is stored with [Self::insert_deferred_event_filter].
+    pub fn build_deferred_event_filter(&self) -> anyhow::Result<Option<DeferredEventFilter>> {
+        let running_from_block = {
+            let running_event_filter = self.running_event_filter.lock().unwrap();
+            if !running_event_filter.deferred {
+                return Ok(None);
+            }
+            running_event_filter.filter.from_block
+        };
+
+        let from_block = self.first_block_without_event_filter()?;
+        let to_block = from_block + AGGREGATE_BLOOM_BLOCK_RANGE_LEN - 1;
+        if to_block >= running_from_block {
+            return Ok(None);
+        }
+
+        let mut load_events_stmt = self.inner().prepare_cached(
+            r"
+            SELECT events
+            FROM transactions
+            WHERE block_number BETWEEN ? AND ?
+            ORDER BY block_number
+            ",
+        )?;
+
+        let mut filter = AggregateBloom::new(from_block);
+        let mut chunk_from = from_block;
+        while chunk_from <= to_block {
+            let chunk_to =
+                std::cmp::min(chunk_from + DEFERRED_EVENT_FILTER_CHUNK_LEN - 1, to_block);
+            let events: Vec<Option<Vec<u8>>> = load_events_stmt
+                .query_map(params![&chunk_from, &chunk_to], |row| {
+                    Ok(row.get_optional_blob(0)?.map(<[u8]>::to_vec))
+                })
+                .context("Querying events")?
+                .collect::<Result<_, _>>()?;
+
+            if events.len() as u64 != chunk_to.get() - chunk_from.get() + 1 {
+                return Ok(None);
+            }
+            let Some(events) = events.into_iter().collect::<Option<Vec<_>>>() else {
+                // Events of the range have not been synced yet.
+                return Ok(None);
+            };
+
+            let blooms = events
+                .into_par_iter()
+                .map(|events| block_bloom_filter(&events))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            for (block_number, bloom) in (chunk_from.get()..).zip(&blooms) {
+                filter.insert(bloom, BlockNumber::new_or_panic(block_number));
+            }
+
+            chunk_from = chunk_to + 1;
+        }
+
+        Ok(Some(DeferredEventFilter(filter)))
+    }
+
+    pub fn insert_deferred_event_filter(&self, filter: &DeferredEventFilter) -> anyhow::Result<()> {
+        self.inner()
+            .execute(
+                r"
+                INSERT INTO event_filters
+                (from_block, to_block, bitmap)
+                VALUES (?, ?, ?)
+                ON CONFLICT DO UPDATE SET bitmap=excluded.bitmap
+                ",
+                params![
+                    &filter.0.from_block,
+                    &filter.0.to_block,
+                    &filter.0.compress_bitmap()
+                ],
+            )
+            .context("Inserting event filter")?;
+
+        Ok(())
+    }
+
+    /// Ends the [deferral](Self::defer_event_filters) if the filters of all
+    /// completed block ranges have been stored. Returns whether event filters
+    /// are no longer deferred.
+    ///
+    /// This should be called from an
+    /// [immediate](crate::TransactionBehavior::Immediate) transaction, so that
+    /// no range can be completed between the check and the end of the
+    /// deferral.
+    pub fn end_event_filter_deferral(&self) -> anyhow::Result<bool> {
+        if !self.event_filters_deferred() {
+            return Ok(true);
+        }
+
+        let first_block_without_event_filter = self.first_block_without_event_filter()?;
+        let mut running_event_filter = self.running_event_filter.lock().unwrap();
+        if running_event_filter.filter.from_block != first_block_without_event_filter {
+            return Ok(false);
+        }
+
+        self.inner()
+            .execute(
+                "DELETE FROM storage_flags WHERE flag = ?",
+                [EVENT_FILTERS_DEFERRED_FLAG],
+            )
+            .context("Clearing event filter deferral flag")?;
+        running_event_filter.deferred = false;
+
+        Ok(true)
+    }
+
+    /// The block after the last one covered by a stored event filter.
+    fn first_block_without_event_filter(&self) -> anyhow::Result<BlockNumber> {
+        let last_to_block = self
+            .inner()
+            .query_row(
+                "SELECT to_block FROM event_filters ORDER BY from_block DESC LIMIT 1",
+                [],
+                |row| row.get_block_number(0),
+            )
+            .optional()
+            .context("Querying last stored event filter to_block")?;
+
+        Ok(last_to_block.map_or(BlockNumber::GENESIS, |to_block| to_block + 1))
+    }
+
+    /// The first block whose events can be queried while event filters are
+    /// [deferred](Self::defer_event_filters), [None] if they are not.
+    fn deferred_events_available_from(&self) -> Option<BlockNumber> {
+        let running_event_filter = self.running_event_filter.lock().unwrap();
+        running_event_filter
+            .deferred
+            .then_some(running_event_filter.filter.from_block)
+    }
+
+    /// Replaces the selectors indexed for the given block with the distinct
+    /// selectors, i.e. first keys, of `events`.
+    pub(super) fn upsert_event_selectors<'a>(
+        &self,
+        block_number: BlockNumber,
+        events: impl IntoIterator<Item = &'a Event>,
+    ) -> anyhow::Result<()> {
+        self.inner()
+            .execute(
+                "DELETE FROM event_selectors WHERE block_number = ?",
+                params![&block_number],
+            )
+            .context("Deleting event selectors")?;
+
+        let mut insert_stmt = self.inner().prepare_cached(
+            r"
+            INSERT INTO event_selectors (selector, block_number)
+            VALUES (?, ?)
+            ",
+        )?;
+
+        let selectors = events
+            .into_iter()
+            .filter_map(|event| event.keys.first())
+            .collect::<HashSet<_>>();
+        for selector in selectors {
+            insert_stmt
+                .execute(params![selector, &block_number])
+                .context("Inserting event selector")?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the blocks in the given range which contain an event matching
+    /// the first key constraint, or `None` if the first key is not
+    /// constrained.
+    ///
+    /// Only the blocks covered by `event_filters` are queried, so that the
+    /// lookup is bounded by the number of filters loaded.
+    fn blocks_with_selectors(
+        &self,
+        constraints: &EventConstraints,
+        event_filters: &[Arc<AggregateBloom>],
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+    ) -> anyhow::Result<Option<BTreeSet<BlockNumber>>> {
+        let Some(selectors) = constraints.keys.first().filter(|keys| !keys.is_empty()) else {
+            return Ok(None);
+        };
+
+        let (Some(first_filter), Some(last_filter)) = (event_filters.first(), event_filters.last())
+        else {
+            return Ok(Some(BTreeSet::new()));
+        };
+        let from_block = std::cmp::max(from_block, first_filter.from_block);
+        let to_block = std::cmp::min(to_block, last_filter.to_block);
+
+        let selectors = Rc::new(
+            selectors
+                .iter()
+                .map(|selector| Value::from(selector.0.as_be_bytes().to_vec()))
+                .collect::<Vec<Value>>(),
+        );
+
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            SELECT DISTINCT block_number
+            FROM event_selectors
+            WHERE selector IN rarray(:selectors)
+            AND block_number BETWEEN :from_block AND :to_block
+            ",
+        )?;
+
+        let blocks = stmt
+            .query_map(
+                // Cannot use crate::params::named_params![] here because of the rarray.
+                rusqlite::named_params![
+                    ":selectors": &selectors,
+                    ":from_block": &from_block.get(),
+                    ":to_block": &to_block.get(),
+                ],
+                |row| row.get_block_number(0),
+            )
+            .context("Querying event selectors")?
+            .collect::<Result<BTreeSet<_>, _>>()?;
+
+        Ok(Some(blocks))
+    }
+
     /// Return all of the events in the given block range, filtered by the given
     /// keys and contract address. Along with the events, return the last
     /// block number that was scanned, which may be smaller than `to_block`
@@ -149,6 +445,21 @@ impl Transaction<'_> {
         }
         let to_block = std::cmp::min(to_block, latest_block);
 
+        if let Some(available_from) = self.deferred_events_available_from() {
+            anyhow::ensure!(
+                from_block >= available_from,
+                EventFilterError::FiltersDeferred { available_from }
+            );
+        }
+
+        if let Some(retention) = self.event_retention()? {
+            let available_from = retention.available_from(contract_address);
+            anyhow::ensure!(
+                from_block >= available_from,
+                crate::EventsPruned { available_from }
+            );
+        }
+
         let constraints = EventConstraints {
             contract_address,
             keys,
@@ -156,12 +467,18 @@ impl Transaction<'_> {
             ..Default::default()
         };
 
-        let (event_filters, _) = self.load_event_filter_range(from_block, to_block, None)?;
-
-        let blocks_to_scan = event_filters
-            .iter()
-            .flat_map(|filter| filter.check(&constraints))
-            .filter(|&block| (from_block..=to_block).contains(&block));
+        let (event_filters, _) =
+            self.load_event_filter_range(from_block, to_block, None, EventOrder::Ascending)?;
+        let selector_blocks =
+            self.blocks_with_selectors(&constraints, &event_filters, from_block, to_block)?;
+
+        let blocks_to_scan = candidate_blocks(
+            &event_filters,
+            &constraints,
+            selector_blocks.as_ref(),
+            from_block,
+            to_block,
+        );
 
         let no_key_constraints = constraints.keys.iter().flatten().count() == 0;
         let keys: Vec<std::collections::HashSet<_>> = constraints
@@ -222,6 +539,11 @@ impl Transaction<'_> {
         Ok((emitted_events, Some(to_block)))
     }
 
+    /// Returns a page of events matching the constraints, in the order given
+    /// by [EventConstraints::order].
+    ///
+    /// Scanning starts at `from_block`, or at `to_block` in descending order,
+    /// skipping `offset` matching events of the first block.
     #[tracing::instrument(skip(self))]
     pub fn events(
         &self,
@@ -247,13 +569,43 @@ impl Transaction<'_> {
             None => latest_block,
         };
 
-        let (event_filters, load_limit_reached) =
-            self.load_event_filter_range(from_block, to_block, Some(max_event_filters_to_load))?;
+        if let Some(retention) = self.event_retention()? {
+            let available_from = retention.available_from(constraints.contract_address);
+            if from_block < available_from {
+                return Err(EventFilterError::Pruned { available_from });
+            }
+        }
 
-        let blocks_to_scan = event_filters
-            .iter()
-            .flat_map(|filter| filter.check(constraints))
-            .filter(|&block| (from_block..=to_block).contains(&block));
+        if let Some(available_from) = self.deferred_events_available_from() {
+            if from_block < available_from {
+                return Err(EventFilterError::FiltersDeferred { available_from });
+            }
+        }
+
+        let descending = constraints.order == EventOrder::Descending;
+        let start_block = if descending { to_block } else { from_block };
+
+        let (event_filters, load_limit_reached) = self.load_event_filter_range(
+            from_block,
+            to_block,
+            Some(max_event_filters_to_load),
+            constraints.order,
+        )?;
+        let selector_blocks =
+            self.blocks_with_selectors(constraints, &event_filters, from_block, to_block)?;
+
+        let blocks_to_scan = candidate_blocks(
+            &event_filters,
+            constraints,
+            selector_blocks.as_ref(),
+            from_block,
+            to_block,
+        );
+        let blocks_to_scan: Box<dyn Iterator<Item = BlockNumber>> = if descending {
+            Box::new(blocks_to_scan.rev())
+        } else {
+            Box::new(blocks_to_scan)
+        };
 
         let keys: Vec<std::collections::HashSet<_>> = constraints
             .keys
@@ -296,7 +648,7 @@ impl Transaction<'_> {
                 }
             };
 
-            let events = events
+            let mut events = events
                 .into_iter()
                 .flat_map(|(transaction_hash, events)| {
                     events.into_iter().zip(std::iter::repeat(transaction_hash))
@@ -320,6 +672,13 @@ impl Transaction<'_> {
                         .zip(keys.iter())
                         .all(|(key, filter)| filter.is_empty() || filter.contains(key))
                 })
+                .collect::<Vec<_>>();
+            if descending {
+                events.reverse();
+            }
+
+            let events = events
+                .into_iter()
                 .skip_while(|_| {
                     let should_skip = offset > 0;
                     offset = offset.saturating_sub(1);
@@ -343,7 +702,7 @@ impl Transaction<'_> {
                 let continuation_token = continuation_token(
                     &emitted_events,
                     ContinuationToken {
-                        block_number: from_block,
+                        block_number: start_block,
                         offset: constraints.offset,
                     },
                 )
@@ -363,16 +722,26 @@ impl Transaction<'_> {
         }
 
         if load_limit_reached {
-            let last_loaded_block = event_filters
-                .last()
-                .expect("At least one filter is present")
-                .to_block;
+            // Event filter block ranges are inclusive so continue just past the
+            // last loaded filter.
+            let block_number = if descending {
+                let first_loaded_block = event_filters
+                    .first()
+                    .expect("At least one filter is present")
+                    .from_block;
+                first_loaded_block - 1
+            } else {
+                let last_loaded_block = event_filters
+                    .last()
+                    .expect("At least one filter is present")
+                    .to_block;
+                last_loaded_block + 1
+            };
 
             Ok(PageOfEvents {
                 events: emitted_events,
                 continuation_token: Some(ContinuationToken {
-                    // Event filter block range is inclusive so + 1.
-                    block_number: last_loaded_block + 1,
+                    block_number,
                     offset: 0,
                 }),
             })
@@ -387,11 +756,15 @@ impl Transaction<'_> {
     /// Load the event bloom filters (either from the cache or the database) for
     /// the given block range with an optional database load limit. Returns the
     /// loaded filters and a boolean indicating if the load limit was reached.
+    ///
+    /// The filters are returned in ascending order. If the load limit is
+    /// reached, the filters loaded are the ones scanned first in `order`.
     fn load_event_filter_range(
         &self,
         start_block: BlockNumber,
         end_block: BlockNumber,
         max_event_filters_to_load: Option<NonZeroUsize>,
+        order: EventOrder,
     ) -> anyhow::Result<(Vec<Arc<AggregateBloom>>, bool)> {
         let mut total_filters_stmt = self.inner().prepare_cached(
             r"
@@ -422,16 +795,28 @@ impl Transaction<'_> {
                 .collect::<Vec<Value>>(),
         );
 
-        let mut load_stmt = self.inner().prepare_cached(
-            r"
-            SELECT from_block, to_block, bitmap
-            FROM event_filters
-            WHERE from_block <= :end_block AND to_block >= :start_block
-            AND from_block NOT IN rarray(:cached_filters)
-            ORDER BY from_block
-            LIMIT :max_event_filters_to_load
-            ",
-        )?;
+        let mut load_stmt = self.inner().prepare_cached(match order {
+            EventOrder::Ascending => {
+                r"
+                SELECT from_block, to_block, bitmap
+                FROM event_filters
+                WHERE from_block <= :end_block AND to_block >= :start_block
+                AND from_block NOT IN rarray(:cached_filters)
+                ORDER BY from_block
+                LIMIT :max_event_filters_to_load
+                "
+            }
+            EventOrder::Descending => {
+                r"
+                SELECT from_block, to_block, bitmap
+                FROM event_filters
+                WHERE from_block <= :end_block AND to_block >= :start_block
+                AND from_block NOT IN rarray(:cached_filters)
+                ORDER BY from_block DESC
+                LIMIT :max_event_filters_to_load
+                "
+            }
+        })?;
         // Use limit if provided, otherwise set it to the number of filters that cover
         // the entire requested range.
         let max_event_filters_to_load =
@@ -462,19 +847,36 @@ impl Transaction<'_> {
             .collect::<Result<Vec<_>, _>>()?;
 
         self.event_filter_cache.set_many(&event_filters);
-        event_filters.extend(cached_filters);
-        event_filters.sort_by_key(|filter| filter.from_block);
 
         let total_loaded_filters = total_event_filters - cache_hits;
         let load_limit_reached = total_loaded_filters > max_event_filters_to_load;
 
+        // Cached filters past the ones loaded would be scanned before the
+        // filters which were not loaded, out of order.
+        let loaded_range = event_filters
+            .iter()
+            .map(|filter| filter.from_block)
+            .min()
+            .zip(event_filters.iter().map(|filter| filter.from_block).max());
+        let cached_filters = cached_filters.into_iter().filter(|filter| {
+            match (load_limit_reached, loaded_range, order) {
+                (true, Some((_, last)), EventOrder::Ascending) => filter.from_block < last,
+                (true, Some((first, _)), EventOrder::Descending) => filter.from_block > first,
+                _ => true,
+            }
+        });
+        event_filters.extend(cached_filters);
+        event_filters.sort_by_key(|filter| filter.from_block);
+
         // There are no event filters in the database yet or the loaded ones
         // don't cover the requested range.
         let should_include_running = event_filters
             .last()
             .map_or(true, |last| end_block > last.to_block);
 
-        if should_include_running && !load_limit_reached {
+        // The running filter comes last in ascending order, after the filters
+        // which were not loaded.
+        if should_include_running && (!load_limit_reached || order == EventOrder::Descending) {
             let running_event_filter = self.running_event_filter.lock().unwrap();
             event_filters.push(Arc::new(running_event_filter.filter.clone()));
         }
@@ -487,6 +889,34 @@ impl Transaction<'_> {
     }
 }
 
+/// Returns the blocks in `from_block..=to_block` which may contain events
+/// matching the constraints.
+///
+/// Bloom filters covering none of the `selector_blocks` are skipped without
+/// being checked, and false positives for the selector are dropped before the
+/// block's events are loaded.
+fn candidate_blocks<'a>(
+    event_filters: &'a [Arc<AggregateBloom>],
+    constraints: &'a EventConstraints,
+    selector_blocks: Option<&'a BTreeSet<BlockNumber>>,
+    from_block: BlockNumber,
+    to_block: BlockNumber,
+) -> impl DoubleEndedIterator<Item = BlockNumber> + 'a {
+    event_filters
+        .iter()
+        .filter(move |filter| {
+            selector_blocks.map_or(true, |blocks| {
+                blocks
+                    .range(filter.from_block..=filter.to_block)
+                    .next()
+                    .is_some()
+            })
+        })
+        .flat_map(|filter| filter.check(constraints))
+        .filter(move |&block| (from_block..=to_block).contains(&block))
+        .filter(move |block| selector_blocks.map_or(true, |blocks| blocks.contains(block)))
+}
+
 impl AggregateBloom {
     /// Returns the block numbers that match the given constraints.
     pub fn check(&self, constraints: &EventConstraints) -> Vec<BlockNumber> {
@@ -553,9 +983,31 @@ impl BloomFilter {
     }
 }
 
+/// The Bloom filter of a single block's events.
+///
+/// Building the filter is independent of the database so it can be done ahead
+/// of time, on a different thread, and then merged into the running aggregate
+/// filter when the block is inserted.
+#[derive(Clone)]
+pub struct BlockEventFilter(BloomFilter);
+
+impl BlockEventFilter {
+    pub fn new<'a>(events: impl IntoIterator<Item = &'a Event>) -> Self {
+        let mut bloom = BloomFilter::new();
+        for event in events {
+            bloom.set_keys(&event.keys);
+            bloom.set_address(&event.from_address);
+        }
+        Self(bloom)
+    }
+}
+
 pub(crate) struct RunningEventFilter {
     filter: AggregateBloom,
     next_block: BlockNumber,
+    /// Whether the filter is discarded instead of stored once its range is
+    /// complete, see [Transaction::defer_event_filters].
+    deferred: bool,
 }
 
 /// Rebuild the [event filter](RunningEventFilter) for the range of blocks
@@ -564,11 +1016,12 @@ pub(crate) struct RunningEventFilter {
 /// filter for each [block range](crate::bloom::AGGREGATE_BLOOM_BLOCK_RANGE_LEN)
 /// is stored once the range is complete, before that it is kept in memory and
 /// can be lost upon shutdown.
+///
+/// While event filters are [deferred](Transaction::defer_event_filters) only
+/// the range of the latest block is rebuilt.
 pub(crate) fn rebuild_running_event_filter(
     tx: &rusqlite::Transaction<'_>,
 ) -> anyhow::Result<RunningEventFilter> {
-    use super::transaction;
-
     let mut latest_stmt = tx.prepare(
         r"
         SELECT number 
@@ -592,6 +1045,16 @@ pub(crate) fn rebuild_running_event_filter(
         ",
     )?;
 
+    let deferred = tx
+        .query_row(
+            "SELECT 1 FROM storage_flags WHERE flag = ?",
+            [EVENT_FILTERS_DEFERRED_FLAG],
+            |_| Ok(()),
+        )
+        .optional()
+        .context("Querying event filter deferral flag")?
+        .is_some();
+
     let Some(latest) = latest_stmt
         .query_row([], |row| row.get_block_number(0))
         .optional()
@@ -601,6 +1064,7 @@ pub(crate) fn rebuild_running_event_filter(
         return Ok(RunningEventFilter {
             filter: AggregateBloom::new(BlockNumber::GENESIS),
             next_block: BlockNumber::GENESIS,
+            deferred,
         });
     };
     let last_to_block = last_to_block_stmt
@@ -609,6 +1073,13 @@ pub(crate) fn rebuild_running_event_filter(
         .context("Querying last stored event filter to_block")?;
 
     let first_running_event_filter_block = match last_to_block {
+        // Filters of earlier ranges are built separately.
+        _ if deferred => {
+            let next_block = latest + 1;
+            BlockNumber::new_or_panic(
+                next_block.get() - next_block.get() % AGGREGATE_BLOOM_BLOCK_RANGE_LEN,
+            )
+        }
         // Last stored block was at the end of the running event filter range, no need
         // to rebuild.
         Some(last_to_block) if last_to_block == latest.get() => {
@@ -617,6 +1088,7 @@ pub(crate) fn rebuild_running_event_filter(
             return Ok(RunningEventFilter {
                 filter: AggregateBloom::new(next_block),
                 next_block,
+                deferred,
             });
         }
         Some(last_to_block) => BlockNumber::new_or_panic(last_to_block + 1),
@@ -624,7 +1096,9 @@ pub(crate) fn rebuild_running_event_filter(
         None => BlockNumber::GENESIS,
     };
 
-    let total_blocks_to_cover = latest.get() - first_running_event_filter_block.get();
+    let total_blocks_to_cover = latest
+        .get()
+        .saturating_sub(first_running_event_filter_block.get());
     let mut covered_blocks = 0;
     let mut last_progress_report = Instant::now();
 
@@ -648,37 +1122,9 @@ pub(crate) fn rebuild_running_event_filter(
 
                 covered_blocks += 1;
 
-                let Some(events) = row
-                    .get_optional_blob(0)?
-                    .map(|events_blob| -> anyhow::Result<_> {
-                        let events = transaction::compression::decompress_events(events_blob)
-                            .context("Decompressing events")?;
-                        let events: transaction::dto::EventsForBlock =
-                            bincode::serde::decode_from_slice(&events, bincode::config::standard())
-                                .context("Deserializing events")?
-                                .0;
-
-                        Ok(events)
-                    })
-                    .transpose()?
-                    .map(|efb| {
-                        efb.events()
-                            .into_iter()
-                            .flatten()
-                            .map(Event::from)
-                            .collect::<Vec<_>>()
-                    })
-                else {
-                    return Ok(None);
-                };
-
-                let mut bloom = BloomFilter::new();
-                for event in events {
-                    bloom.set_keys(&event.keys);
-                    bloom.set_address(&event.from_address);
-                }
-
-                Ok(Some(bloom))
+                row.get_optional_blob(0)?
+                    .map(block_bloom_filter)
+                    .transpose()
             },
         )
         .context("Querying events to rebuild")?
@@ -703,9 +1149,30 @@ pub(crate) fn rebuild_running_event_filter(
     Ok(RunningEventFilter {
         filter,
         next_block: first_running_event_filter_block + rebuilt_filters.len() as u64,
+        deferred,
     })
 }
 
+/// Builds the Bloom filter of a block from its stored events.
+fn block_bloom_filter(events: &[u8]) -> anyhow::Result<BloomFilter> {
+    use super::transaction;
+
+    let events =
+        transaction::compression::decompress_events(events).context("Decompressing events")?;
+    let events: transaction::dto::EventsForBlock =
+        bincode::serde::decode_from_slice(&events, bincode::config::standard())
+            .context("Deserializing events")?
+            .0;
+
+    let mut bloom = BloomFilter::new();
+    for event in events.events().into_iter().flatten().map(Event::from) {
+        bloom.set_keys(&event.keys);
+        bloom.set_address(&event.from_address);
+    }
+
+    Ok(bloom)
+}
+
 fn continuation_token(
     events: &[EmittedEvent],
     previous_token: ContinuationToken,
@@ -753,7 +1220,7 @@ mod tests {
     use rstest::rstest;
 
     use super::*;
-    use crate::{test_utils, AGGREGATE_BLOOM_BLOCK_RANGE_LEN};
+    use crate::test_utils;
 
     static MAX_BLOCKS_TO_SCAN: LazyLock<NonZeroUsize> =
         LazyLock::new(|| NonZeroUsize::new(100).unwrap());
@@ -765,6 +1232,48 @@ mod tests {
 
         use super::*;
 
+        #[test]
+        fn block_event_filter_matches_events() {
+            let events = [
+                Event {
+                    from_address: contract_address!("0x1234"),
+                    keys: vec![event_key!("0xdeadbeef")],
+                    data: vec![],
+                },
+                Event {
+                    from_address: contract_address!("0x5678"),
+                    keys: vec![event_key!("0xcafe"), event_key!("0xbabe")],
+                    data: vec![],
+                },
+            ];
+
+            let mut aggregate = AggregateBloom::new(BlockNumber::GENESIS);
+            aggregate.insert(&BlockEventFilter::new(&events).0, BlockNumber::GENESIS);
+            aggregate.insert(&BlockEventFilter::new([]).0, BlockNumber::GENESIS + 1);
+
+            for (address, keys) in [
+                (
+                    contract_address!("0x1234"),
+                    vec![vec![event_key!("0xdeadbeef")]],
+                ),
+                (
+                    contract_address!("0x5678"),
+                    vec![vec![event_key!("0xcafe")], vec![event_key!("0xbabe")]],
+                ),
+            ] {
+                let constraints = EventConstraints {
+                    from_block: None,
+                    to_block: None,
+                    contract_address: Some(address),
+                    keys,
+                    page_size: 1024,
+                    offset: 0,
+                    order: EventOrder::Ascending,
+                };
+                assert_eq!(aggregate.check(&constraints), vec![BlockNumber::GENESIS]);
+            }
+        }
+
         #[test]
         fn matching_constraints() {
             let mut aggregate = AggregateBloom::new(BlockNumber::GENESIS);
@@ -782,6 +1291,7 @@ mod tests {
                 keys: vec![vec![event_key!("0xdeadbeef")]],
                 page_size: 1024,
                 offset: 0,
+                order: EventOrder::Ascending,
             };
 
             assert_eq!(
@@ -807,6 +1317,7 @@ mod tests {
                 keys: vec![vec![event_key!("0xdeadbeef")]],
                 page_size: 1024,
                 offset: 0,
+                order: EventOrder::Ascending,
             };
 
             assert_eq!(aggregate.check(&constraints), Vec::<BlockNumber>::new());
@@ -829,6 +1340,7 @@ mod tests {
                 keys: vec![vec![event_key!("0xfeebdaed"), event_key!("0x4321")]],
                 page_size: 1024,
                 offset: 0,
+                order: EventOrder::Ascending,
             };
 
             assert_eq!(aggregate.check(&constraints), Vec::<BlockNumber>::new());
@@ -856,6 +1368,7 @@ mod tests {
                 ],
                 page_size: 1024,
                 offset: 0,
+                order: EventOrder::Ascending,
             };
 
             assert_eq!(aggregate.check(&constraints), Vec::<BlockNumber>::new());
@@ -884,6 +1397,7 @@ mod tests {
                 keys: vec![],
                 page_size: 1024,
                 offset: 0,
+                order: EventOrder::Ascending,
             };
 
             assert_eq!(aggregate.check(&constraints), all_blocks(&aggregate));
@@ -906,6 +1420,7 @@ mod tests {
             keys: vec![vec![], vec![event_key!("0xdeadbeef")]],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1015,6 +1530,7 @@ mod tests {
                     keys: vec![],
                     page_size: 1024,
                     offset: 0,
+                    order: EventOrder::Ascending,
                 },
                 *MAX_BLOCKS_TO_SCAN,
                 *MAX_EVENT_FILTERS_TO_LOAD,
@@ -1031,6 +1547,63 @@ mod tests {
             .collect::<Vec<_>>();
 
         assert_eq!(addresses, expected);
+
+        let addresses = tx
+            .events(
+                &EventConstraints {
+                    page_size: 1024,
+                    order: EventOrder::Descending,
+                    ..Default::default()
+                },
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_EVENT_FILTERS_TO_LOAD,
+            )
+            .unwrap()
+            .events
+            .iter()
+            .map(|e| e.from_address)
+            .collect::<Vec<_>>();
+
+        let expected = expected.into_iter().rev().collect::<Vec<_>>();
+        assert_eq!(addresses, expected);
+    }
+
+    #[test]
+    fn get_events_in_descending_order_with_paging() {
+        let (storage, test_data) = test_utils::setup_test_storage();
+        let mut expected_events = test_data.events;
+        expected_events.reverse();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let mut constraints = EventConstraints {
+            page_size: test_utils::EVENTS_PER_BLOCK + 1,
+            order: EventOrder::Descending,
+            ..Default::default()
+        };
+
+        let mut events = vec![];
+        loop {
+            let page = tx
+                .events(
+                    &constraints,
+                    *MAX_BLOCKS_TO_SCAN,
+                    *MAX_EVENT_FILTERS_TO_LOAD,
+                )
+                .unwrap();
+            events.extend(page.events);
+
+            match page.continuation_token {
+                Some(token) => {
+                    // The token points to the upper end of the remaining range.
+                    constraints.to_block = Some(token.block_number);
+                    constraints.offset = token.offset;
+                }
+                None => break,
+            }
+        }
+
+        pretty_assertions_sorted::assert_eq!(events, expected_events);
     }
 
     #[test]
@@ -1048,6 +1621,7 @@ mod tests {
             keys: vec![],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let expected_events = &emitted_events[test_utils::EVENTS_PER_BLOCK * BLOCK_NUMBER
@@ -1083,6 +1657,7 @@ mod tests {
             keys: vec![],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let expected_events =
@@ -1117,6 +1692,7 @@ mod tests {
             keys: vec![],
             page_size: test_utils::EVENTS_PER_BLOCK + 1,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let expected_events = &emitted_events[..test_utils::EVENTS_PER_BLOCK + 1];
@@ -1146,6 +1722,7 @@ mod tests {
             keys: vec![],
             page_size: test_utils::EVENTS_PER_BLOCK + 1,
             offset: events.continuation_token.unwrap().offset,
+            order: EventOrder::Ascending,
         };
 
         let expected_events =
@@ -1181,6 +1758,7 @@ mod tests {
             keys: vec![],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let expected_events = &emitted_events[test_utils::EVENTS_PER_BLOCK * FROM_BLOCK_NUMBER..];
@@ -1216,6 +1794,7 @@ mod tests {
             keys: vec![],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1249,6 +1828,7 @@ mod tests {
             keys: vec![vec![expected_event.keys[0]], vec![expected_event.keys[1]]],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1288,6 +1868,96 @@ mod tests {
         );
     }
 
+    #[test]
+    fn get_events_by_selector_scans_only_blocks_with_selector() {
+        let (storage, test_data) = test_utils::setup_test_storage();
+        let emitted_events = test_data.events;
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let expected_event = &emitted_events[27];
+        let constraints = EventConstraints {
+            from_block: None,
+            to_block: None,
+            contract_address: None,
+            keys: vec![vec![expected_event.keys[0]]],
+            page_size: test_utils::NUM_EVENTS,
+            offset: 0,
+            order: EventOrder::Ascending,
+        };
+
+        // A single block scan suffices as the other blocks are pruned by the
+        // selector index.
+        let events = tx
+            .events(
+                &constraints,
+                1.try_into().unwrap(),
+                *MAX_EVENT_FILTERS_TO_LOAD,
+            )
+            .unwrap();
+        assert_eq!(
+            events,
+            PageOfEvents {
+                events: vec![expected_event.clone()],
+                continuation_token: None,
+            }
+        );
+    }
+
+    #[test]
+    fn event_selector_index_follows_block_changes() {
+        let (storage, test_data) = test_utils::setup_test_storage();
+        let emitted_events = test_data.events;
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let event = &emitted_events[27];
+        let constraints = EventConstraints {
+            keys: vec![vec![event.keys[0]]],
+            ..Default::default()
+        };
+        let to_block = BlockNumber::new_or_panic(test_utils::NUM_BLOCKS as u64);
+        let selector_blocks = |tx: &Transaction<'_>| {
+            let (event_filters, _) = tx
+                .load_event_filter_range(
+                    BlockNumber::GENESIS,
+                    to_block,
+                    None,
+                    EventOrder::Ascending,
+                )
+                .unwrap();
+            tx.blocks_with_selectors(&constraints, &event_filters, BlockNumber::GENESIS, to_block)
+                .unwrap()
+                .unwrap()
+        };
+
+        assert_eq!(selector_blocks(&tx), BTreeSet::from([event.block_number]));
+
+        // Blocks outside of the loaded filters are not looked up.
+        let outside_filters = tx
+            .blocks_with_selectors(&constraints, &[], BlockNumber::GENESIS, to_block)
+            .unwrap()
+            .unwrap();
+        assert_eq!(outside_filters, BTreeSet::new());
+
+        tx.update_events(event.block_number, vec![]).unwrap();
+        assert_eq!(selector_blocks(&tx), BTreeSet::new());
+
+        tx.update_events(
+            event.block_number,
+            vec![vec![Event {
+                from_address: event.from_address,
+                keys: event.keys.clone(),
+                data: vec![],
+            }]],
+        )
+        .unwrap();
+        assert_eq!(selector_blocks(&tx), BTreeSet::from([event.block_number]));
+
+        tx.purge_block(event.block_number).unwrap();
+        assert_eq!(selector_blocks(&tx), BTreeSet::new());
+    }
+
     #[test]
     fn get_events_with_no_filter() {
         let (storage, test_data) = test_utils::setup_test_storage();
@@ -1302,6 +1972,7 @@ mod tests {
             keys: vec![],
             page_size: test_utils::NUM_EVENTS,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1334,6 +2005,7 @@ mod tests {
             keys: vec![],
             page_size: 10,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1361,6 +2033,7 @@ mod tests {
             keys: vec![],
             page_size: 10,
             offset: 10,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1388,6 +2061,7 @@ mod tests {
             keys: vec![],
             page_size: 10,
             offset: 30,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1421,6 +2095,7 @@ mod tests {
             page_size: PAGE_SIZE,
             // _after_ the last one
             offset: test_utils::NUM_BLOCKS * test_utils::EVENTS_PER_BLOCK,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1459,6 +2134,7 @@ mod tests {
             keys: keys_for_expected_events.clone(),
             page_size: 2,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1487,6 +2163,7 @@ mod tests {
             keys: keys_for_expected_events.clone(),
             page_size: 2,
             offset: 2,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1515,6 +2192,7 @@ mod tests {
             keys: keys_for_expected_events.clone(),
             page_size: 2,
             offset: 2,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1543,6 +2221,7 @@ mod tests {
             keys: keys_for_expected_events.clone(),
             page_size: 2,
             offset: 4,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1568,6 +2247,7 @@ mod tests {
             keys: keys_for_expected_events,
             page_size: 2,
             offset: 1,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1600,6 +2280,7 @@ mod tests {
             keys: vec![],
             page_size: 20,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1627,6 +2308,7 @@ mod tests {
             keys: vec![],
             page_size: 20,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1684,6 +2366,7 @@ mod tests {
             keys: vec![vec![], vec![event_key!("0xdeadbeef")]],
             page_size: emitted_events.len(),
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1702,6 +2385,71 @@ mod tests {
         );
     }
 
+    #[test]
+    fn deferred_event_filters_are_built_afterwards() {
+        // Two and a half ranges.
+        let n_blocks = 2 * AGGREGATE_BLOOM_BLOCK_RANGE_LEN + AGGREGATE_BLOOM_BLOCK_RANGE_LEN / 2;
+        let n_blocks = usize::try_from(n_blocks).unwrap();
+
+        let storage = crate::StorageBuilder::in_memory().unwrap();
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+        tx.defer_event_filters().unwrap();
+
+        let headers = test_utils::create_blocks(n_blocks);
+        let transactions = test_utils::create_transactions_and_receipts(n_blocks, 1);
+        for (header, (transaction, receipt, events)) in headers.iter().zip(transactions.clone()) {
+            tx.insert_block_header(header).unwrap();
+            tx.insert_transaction_data(
+                header.number,
+                &[(transaction, receipt)],
+                Some(std::slice::from_ref(&events)),
+            )
+            .unwrap();
+        }
+        let emitted_events = test_utils::extract_events(&headers, &transactions, 1);
+
+        let event_filter_count = || {
+            tx.inner()
+                .query_row("SELECT COUNT(*) FROM event_filters", [], |row| {
+                    row.get::<_, u64>(0)
+                })
+                .unwrap()
+        };
+        assert_eq!(event_filter_count(), 0);
+
+        // Only the range of the latest block is kept, also after a restart.
+        tx.rebuild_running_event_filter().unwrap();
+        assert!(tx.event_filters_deferred());
+        let constraints = EventConstraints {
+            page_size: emitted_events.len(),
+            ..Default::default()
+        };
+        let available_from = BlockNumber::GENESIS + 2 * AGGREGATE_BLOOM_BLOCK_RANGE_LEN;
+        assert_matches::assert_matches!(
+            tx.events(&constraints, *MAX_BLOCKS_TO_SCAN, *MAX_EVENT_FILTERS_TO_LOAD),
+            Err(EventFilterError::FiltersDeferred { available_from: from })
+                if from == available_from
+        );
+
+        assert!(!tx.end_event_filter_deferral().unwrap());
+        while let Some(filter) = tx.build_deferred_event_filter().unwrap() {
+            tx.insert_deferred_event_filter(&filter).unwrap();
+        }
+        assert_eq!(event_filter_count(), 2);
+        assert!(tx.end_event_filter_deferral().unwrap());
+        assert!(!tx.event_filters_deferred());
+
+        let events = tx
+            .events(
+                &constraints,
+                *MAX_BLOCKS_TO_SCAN,
+                *MAX_EVENT_FILTERS_TO_LOAD,
+            )
+            .unwrap();
+        assert_eq!(events.events, emitted_events);
+    }
+
     #[test]
     fn event_filter_filter_load_limit() {
         let n_blocks = 2 * AGGREGATE_BLOOM_BLOCK_RANGE_LEN + AGGREGATE_BLOOM_BLOCK_RANGE_LEN / 2;
@@ -1722,6 +2470,7 @@ mod tests {
             keys: vec![vec![], vec![event_key!("0xdeadbeef")]],
             page_size: emitted_events.len(),
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1751,6 +2500,7 @@ mod tests {
             keys: vec![vec![], vec![event_key!("0xdeadbeef")]],
             page_size: emitted_events.len(),
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -1772,6 +2522,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn event_filter_load_limit_in_descending_order() {
+        let n_blocks = 2 * AGGREGATE_BLOOM_BLOCK_RANGE_LEN + AGGREGATE_BLOOM_BLOCK_RANGE_LEN / 2;
+        let n_blocks = usize::try_from(n_blocks).unwrap();
+
+        let (storage, test_data) = test_utils::setup_custom_test_storage(n_blocks, 1);
+        let mut emitted_events = test_data.events;
+        let events_per_block = emitted_events.len() / n_blocks;
+        let block_range_len = usize::try_from(AGGREGATE_BLOOM_BLOCK_RANGE_LEN).unwrap();
+
+        let mut connection = storage.connection().unwrap();
+        let tx = connection.transaction().unwrap();
+
+        let constraints = EventConstraints {
+            page_size: emitted_events.len(),
+            order: EventOrder::Descending,
+            ..Default::default()
+        };
+
+        let events = tx
+            .events(&constraints, *MAX_BLOCKS_TO_SCAN, 1.try_into().unwrap())
+            .unwrap();
+
+        let mut later_events = emitted_events.split_off(events_per_block * block_range_len);
+        later_events.reverse();
+        assert_eq!(
+            events,
+            PageOfEvents {
+                // The running event filter and the last stored one are scanned...
+                events: later_events,
+                // ...with a continuation token pointing to the end of the first block range.
+                continuation_token: Some(ContinuationToken {
+                    block_number: BlockNumber::new_or_panic(AGGREGATE_BLOOM_BLOCK_RANGE_LEN - 1),
+                    offset: 0,
+                })
+            }
+        );
+
+        let constraints = EventConstraints {
+            to_block: Some(events.continuation_token.unwrap().block_number),
+            ..constraints
+        };
+
+        let events = tx
+            .events(&constraints, *MAX_BLOCKS_TO_SCAN, 1.try_into().unwrap())
+            .unwrap();
+
+        emitted_events.reverse();
+        assert_eq!(
+            events,
+            PageOfEvents {
+                events: emitted_events,
+                continuation_token: None,
+            }
+        );
+    }
+
     #[rustfmt::skip]
     #[rstest]
     #[case(0,  0,  0, 0, 0)] //  0     ..=(N    )
@@ -1834,6 +2641,7 @@ mod tests {
             keys: vec![],
             page_size: emitted_events.len(),
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let page = tx
@@ -0,0 +1,356 @@
+//! Event retention for nodes which do not keep the full event history.
+//!
+//! Events of blocks older than the retention window are dropped, except for
+//! events emitted by an allowlist of contracts whose history is kept in full.
+//! Dropped events cannot be restored, so a contract added to the allowlist
+//! later on only has its events retained from the pruning horizon at that
+//! point.
+//!
+//! Pruning rewrites the stored events of a block in place, so the receipts and
+//! event lists of blocks before the horizon are incomplete. Readers serving
+//! them must check [Transaction::events_pruned] first.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Context;
+use pathfinder_common::{BlockNumber, ContractAddress};
+
+use crate::prelude::*;
+
+/// Maximum number of blocks whose events are pruned by a single call to
+/// [Transaction::prune_events].
+pub const EVENT_PRUNING_BATCH_SIZE: u64 = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventRetentionConfig {
+    /// Number of latest blocks whose events are kept in full.
+    pub blocks_kept: u64,
+    /// Contracts whose events are never pruned.
+    pub allowlist: HashSet<ContractAddress>,
+}
+
+/// The event history available in the database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventRetention {
+    /// Events of blocks before this one have been pruned, except for those of
+    /// allowlisted contracts.
+    pub pruned_before: BlockNumber,
+    /// Allowlisted contracts along with the first block from which their
+    /// events are complete.
+    pub allowlist: HashMap<ContractAddress, BlockNumber>,
+}
+
+/// Result of a single call to [Transaction::prune_events].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventPruning {
+    pub retention: EventRetention,
+    /// Number of blocks outside of the retention window which are still to be
+    /// pruned.
+    pub remaining: u64,
+}
+
+/// The events of a block are incomplete because they have been pruned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("events before block {available_from} have been pruned")]
+pub struct EventsPruned {
+    pub available_from: BlockNumber,
+}
+
+impl EventRetention {
+    /// The first block from which the events emitted by `contract_address`, or
+    /// by all contracts if [None], are complete.
+    pub fn available_from(&self, contract_address: Option<ContractAddress>) -> BlockNumber {
+        contract_address
+            .and_then(|address| self.allowlist.get(&address).copied())
+            .unwrap_or(self.pruned_before)
+    }
+}
+
+impl Transaction<'_> {
+    /// Returns [None] if events have never been pruned.
+    pub fn event_retention(&self) -> anyhow::Result<Option<EventRetention>> {
+        let Some(pruned_before) = self.events_pruned_before()? else {
+            return Ok(None);
+        };
+
+        let mut stmt = self
+            .inner()
+            .prepare_cached("SELECT contract_address, retained_from FROM event_retention_allowlist")
+            .context("Preparing event retention allowlist query")?;
+        let allowlist = stmt
+            .query_map([], |row| {
+                Ok((row.get_contract_address(0)?, row.get_block_number(1)?))
+            })
+            .context("Querying event retention allowlist")?
+            .collect::<Result<HashMap<_, _>, _>>()
+            .context("Iterating over event retention allowlist")?;
+
+        Ok(Some(EventRetention {
+            pruned_before,
+            allowlist,
+        }))
+    }
+
+    /// Returns [EventsPruned] if the events of `block` have been pruned, in
+    /// which case its receipts and event lists must not be served as they
+    /// are incomplete.
+    pub fn events_pruned(&self, block: BlockNumber) -> anyhow::Result<Option<EventsPruned>> {
+        Ok(self
+            .events_pruned_before()?
+            .filter(|pruned_before| block < *pruned_before)
+            .map(|available_from| EventsPruned { available_from }))
+    }
+
+    /// Fails with [EventsPruned] if the events of `block` have been pruned.
+    pub fn ensure_events_retained(&self, block: BlockNumber) -> anyhow::Result<()> {
+        match self.events_pruned(block)? {
+            Some(pruned) => Err(pruned.into()),
+            None => Ok(()),
+        }
+    }
+
+    fn events_pruned_before(&self) -> anyhow::Result<Option<BlockNumber>> {
+        self.inner()
+            .query_row(
+                "SELECT pruned_before FROM event_retention WHERE id = 0",
+                [],
+                |row| row.get_block_number(0),
+            )
+            .optional()
+            .context("Querying event retention")
+    }
+
+    /// Drops the events of up to `max_blocks` blocks outside the retention
+    /// window, apart from those emitted by allowlisted contracts, and returns
+    /// the resulting event history.
+    ///
+    /// The stored allowlist is updated to match `config`. Only blocks which
+    /// crossed the window since the last call are rewritten, so this is cheap
+    /// to call after every block. Catching up on a long history, e.g. on the
+    /// first run against an archive database, takes several calls which should
+    /// each be committed.
+    pub fn prune_events(
+        &self,
+        config: &EventRetentionConfig,
+        max_blocks: u64,
+    ) -> anyhow::Result<EventPruning> {
+        let retention = self.event_retention()?;
+        let pruned_before = retention
+            .as_ref()
+            .map_or(BlockNumber::GENESIS, |r| r.pruned_before);
+        let stored = retention.map(|r| r.allowlist).unwrap_or_default();
+
+        for address in stored.keys().filter(|a| !config.allowlist.contains(a)) {
+            self.inner()
+                .execute(
+                    "DELETE FROM event_retention_allowlist WHERE contract_address = ?",
+                    params![address],
+                )
+                .context("Removing contract from event retention allowlist")?;
+        }
+        for address in config.allowlist.iter().filter(|a| !stored.contains_key(a)) {
+            self.inner()
+                .execute(
+                    "INSERT INTO event_retention_allowlist (contract_address, retained_from) \
+                     VALUES (?, ?)",
+                    params![address, &pruned_before],
+                )
+                .context("Adding contract to event retention allowlist")?;
+        }
+
+        let horizon = match self.block_number(crate::BlockId::Latest)? {
+            Some(latest) => BlockNumber::new_or_panic(
+                (latest.get() + 1)
+                    .saturating_sub(config.blocks_kept)
+                    .max(pruned_before.get()),
+            ),
+            None => pruned_before,
+        };
+
+        let pruned_until = BlockNumber::new_or_panic(
+            horizon
+                .get()
+                .min(pruned_before.get().saturating_add(max_blocks)),
+        );
+
+        for block in (pruned_before.get()..pruned_until.get()).map(BlockNumber::new_or_panic) {
+            // Blocks synced via P2P checkpoints may not have any events stored.
+            let Some(events) = self.query_events_by_block(block)? else {
+                continue;
+            };
+
+            let retained = events
+                .iter()
+                .map(|events| {
+                    events
+                        .iter()
+                        .filter(|event| config.allowlist.contains(&event.from_address))
+                        .cloned()
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+
+            if retained != events {
+                self.overwrite_events(block, &retained)
+                    .context("Pruning events")?;
+            }
+        }
+
+        self.inner()
+            .execute(
+                r"
+                INSERT INTO event_retention (id, pruned_before) VALUES (0, ?)
+                ON CONFLICT DO UPDATE SET pruned_before = excluded.pruned_before
+                ",
+                params![&pruned_until],
+            )
+            .context("Updating event retention")?;
+
+        Ok(EventPruning {
+            retention: self
+                .event_retention()?
+                .expect("Event retention was just stored"),
+            remaining: horizon.get() - pruned_until.get(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn prunes_events_of_contracts_not_allowlisted() {
+        let (storage, test_data) = test_utils::setup_test_storage();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        assert_eq!(tx.event_retention().unwrap(), None);
+
+        let retained = test_data.events[0].clone();
+        let config = EventRetentionConfig {
+            blocks_kept: 2,
+            allowlist: HashSet::from([retained.from_address]),
+        };
+        let pruning = tx.prune_events(&config, EVENT_PRUNING_BATCH_SIZE).unwrap();
+        assert_eq!(pruning.remaining, 0);
+        let retention = pruning.retention;
+
+        // Blocks 0 and 1 are pruned out of four.
+        assert_eq!(retention.pruned_before, BlockNumber::new_or_panic(2));
+        assert_eq!(
+            retention.available_from(Some(retained.from_address)),
+            BlockNumber::GENESIS
+        );
+        assert_eq!(retention.available_from(None), retention.pruned_before);
+
+        let events = |block: u64| {
+            tx.events_for_block(BlockNumber::new_or_panic(block).into())
+                .unwrap()
+                .unwrap()
+                .into_iter()
+                .flat_map(|(_, events)| events)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(events(0).len(), 1);
+        assert_eq!(events(0)[0].from_address, retained.from_address);
+        assert!(events(1).is_empty());
+        assert_eq!(events(2).len(), test_utils::EVENTS_PER_BLOCK);
+
+        // Pruning again without new blocks is a no-op.
+        let pruning = tx.prune_events(&config, EVENT_PRUNING_BATCH_SIZE).unwrap();
+        assert_eq!(pruning.retention, retention);
+
+        assert_eq!(
+            tx.events_pruned(BlockNumber::GENESIS).unwrap(),
+            Some(EventsPruned {
+                available_from: retention.pruned_before
+            })
+        );
+        assert_eq!(tx.events_pruned(retention.pruned_before).unwrap(), None);
+
+        let query = |contract_address| {
+            let constraints = crate::EventConstraints {
+                contract_address,
+                page_size: 100,
+                ..Default::default()
+            };
+            let limit = std::num::NonZeroUsize::new(100).unwrap();
+            tx.events(&constraints, limit, limit)
+        };
+        assert_matches::assert_matches!(
+            query(None),
+            Err(crate::EventFilterError::Pruned { available_from })
+                if available_from == retention.pruned_before
+        );
+        let page = query(Some(retained.from_address)).unwrap();
+        assert_eq!(page.events, vec![retained]);
+
+        // Contracts added later are only complete from the current horizon.
+        let config = EventRetentionConfig {
+            blocks_kept: 2,
+            allowlist: HashSet::from([contract_address!("0x1234")]),
+        };
+        let retention = tx
+            .prune_events(&config, EVENT_PRUNING_BATCH_SIZE)
+            .unwrap()
+            .retention;
+        assert_eq!(
+            retention.allowlist,
+            HashMap::from([(contract_address!("0x1234"), BlockNumber::new_or_panic(2))])
+        );
+    }
+
+    #[test]
+    fn prunes_in_batches() {
+        let (storage, _) = test_utils::setup_test_storage();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let config = EventRetentionConfig {
+            blocks_kept: 1,
+            allowlist: Default::default(),
+        };
+
+        // Blocks 0 to 2 are outside of the window.
+        let pruning = tx.prune_events(&config, 2).unwrap();
+        assert_eq!(
+            pruning.retention.pruned_before,
+            BlockNumber::new_or_panic(2)
+        );
+        assert_eq!(pruning.remaining, 1);
+        assert!(tx
+            .events_pruned(BlockNumber::new_or_panic(1))
+            .unwrap()
+            .is_some());
+        assert!(tx
+            .events_pruned(BlockNumber::new_or_panic(2))
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            tx.events_for_block(BlockNumber::new_or_panic(2).into())
+                .unwrap()
+                .unwrap()
+                .into_iter()
+                .flat_map(|(_, events)| events)
+                .count(),
+            test_utils::EVENTS_PER_BLOCK
+        );
+
+        let pruning = tx.prune_events(&config, 2).unwrap();
+        assert_eq!(
+            pruning.retention.pruned_before,
+            BlockNumber::new_or_panic(3)
+        );
+        assert_eq!(pruning.remaining, 0);
+        assert_matches::assert_matches!(
+            tx.ensure_events_retained(BlockNumber::new_or_panic(2))
+                .unwrap_err()
+                .downcast_ref::<EventsPruned>(),
+            Some(EventsPruned { available_from }) if *available_from == BlockNumber::new_or_panic(3)
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//! Transactions which could not be forwarded to the gateway because of a
+//! transient failure, persisted until they are resubmitted.
+//!
+//! Timestamps are seconds since the Unix epoch.
+
+use anyhow::Context;
+use pathfinder_common::TransactionHash;
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxEntry {
+    pub id: i64,
+    pub transaction_hash: TransactionHash,
+    /// The transaction as submitted to the RPC API.
+    pub transaction_json: Vec<u8>,
+    /// Deploy token forwarded to the gateway along with declare transactions.
+    pub token: Option<String>,
+    /// Number of failed submissions, including the original one.
+    pub attempts: u32,
+    pub created_at: u64,
+    pub next_attempt_at: u64,
+    pub last_error: String,
+}
+
+impl Transaction<'_> {
+    /// Adds a transaction whose first submission failed with `error`. Returns
+    /// the id of the new entry.
+    pub fn insert_outbox_entry(
+        &self,
+        transaction_hash: TransactionHash,
+        transaction_json: &[u8],
+        token: Option<&str>,
+        now: u64,
+        next_attempt_at: u64,
+        error: &str,
+    ) -> anyhow::Result<i64> {
+        self.inner()
+            .execute(
+                r"
+                INSERT INTO gateway_outbox
+                    (transaction_hash, transaction_json, token, attempts, created_at,
+                    next_attempt_at, last_error)
+                VALUES (?, ?, ?, 1, ?, ?, ?)
+                ",
+                params![
+                    &transaction_hash,
+                    &transaction_json,
+                    &token,
+                    &(now as i64),
+                    &(next_attempt_at as i64),
+                    &error
+                ],
+            )
+            .context("Inserting outbox entry")?;
+
+        Ok(self.inner().last_insert_rowid())
+    }
+
+    /// Returns all entries, oldest first.
+    pub fn outbox_entries(&self) -> anyhow::Result<Vec<OutboxEntry>> {
+        self.query_outbox_entries("", params![])
+    }
+
+    /// Returns the entries due for resubmission at `now`, oldest first.
+    pub fn due_outbox_entries(&self, now: u64) -> anyhow::Result<Vec<OutboxEntry>> {
+        self.query_outbox_entries("WHERE next_attempt_at <= ?", params![&(now as i64)])
+    }
+
+    fn query_outbox_entries(
+        &self,
+        filter: &str,
+        params: &[&dyn rusqlite::ToSql],
+    ) -> anyhow::Result<Vec<OutboxEntry>> {
+        let mut stmt = self
+            .inner()
+            .prepare_cached(&format!(
+                r"
+                SELECT id, transaction_hash, transaction_json, token, attempts, created_at,
+                    next_attempt_at, last_error
+                FROM gateway_outbox
+                {filter}
+                ORDER BY id
+                "
+            ))
+            .context("Preparing outbox query")?;
+
+        let entries = stmt
+            .query_map(params, |row| {
+                Ok(OutboxEntry {
+                    id: row.get_i64(0)?,
+                    transaction_hash: row.get_transaction_hash(1)?,
+                    transaction_json: row.get_blob(2)?.to_vec(),
+                    token: row.get_optional_str(3)?.map(ToOwned::to_owned),
+                    attempts: row.get_i64(4)? as u32,
+                    created_at: row.get_i64(5)? as u64,
+                    next_attempt_at: row.get_i64(6)? as u64,
+                    last_error: row.get(7)?,
+                })
+            })
+            .context("Querying outbox entries")?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Iterating over outbox entries")?;
+
+        Ok(entries)
+    }
+
+    /// Records another failed submission of an entry.
+    pub fn reschedule_outbox_entry(
+        &self,
+        id: i64,
+        next_attempt_at: u64,
+        error: &str,
+    ) -> anyhow::Result<()> {
+        self.inner()
+            .execute(
+                r"
+                UPDATE gateway_outbox
+                SET attempts = attempts + 1, next_attempt_at = ?, last_error = ?
+                WHERE id = ?
+                ",
+                params![&(next_attempt_at as i64), &error, &id],
+            )
+            .context("Rescheduling outbox entry")?;
+
+        Ok(())
+    }
+
+    pub fn delete_outbox_entry(&self, id: i64) -> anyhow::Result<()> {
+        self.inner()
+            .execute("DELETE FROM gateway_outbox WHERE id = ?", params![&id])
+            .context("Deleting outbox entry")?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+    use crate::StorageBuilder;
+
+    #[test]
+    fn entries_are_rescheduled_and_deleted() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let first = tx
+            .insert_outbox_entry(transaction_hash!("0x1"), b"{}", None, 10, 20, "timeout")
+            .unwrap();
+        let second = tx
+            .insert_outbox_entry(
+                transaction_hash!("0x2"),
+                b"[]",
+                Some("token"),
+                10,
+                30,
+                "502",
+            )
+            .unwrap();
+
+        assert_eq!(tx.outbox_entries().unwrap().len(), 2);
+        assert!(tx.due_outbox_entries(19).unwrap().is_empty());
+
+        let due = tx.due_outbox_entries(20).unwrap();
+        assert_eq!(
+            due,
+            vec![OutboxEntry {
+                id: first,
+                transaction_hash: transaction_hash!("0x1"),
+                transaction_json: b"{}".to_vec(),
+                token: None,
+                attempts: 1,
+                created_at: 10,
+                next_attempt_at: 20,
+                last_error: "timeout".to_owned(),
+            }]
+        );
+
+        tx.reschedule_outbox_entry(first, 40, "503").unwrap();
+        let due = tx.due_outbox_entries(30).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].id, second);
+        assert_eq!(due[0].token.as_deref(), Some("token"));
+
+        let rescheduled = &tx.due_outbox_entries(40).unwrap()[0];
+        assert_eq!(rescheduled.attempts, 2);
+        assert_eq!(rescheduled.last_error, "503");
+
+        tx.delete_outbox_entry(first).unwrap();
+        tx.delete_outbox_entry(second).unwrap();
+        assert!(tx.outbox_entries().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+//! Blocks received from the gateway which failed verification, kept for
+//! operators to inspect. Quarantined blocks are never stored as part of the
+//! chain.
+//!
+//! Timestamps are seconds since the Unix epoch.
+
+use anyhow::Context;
+use pathfinder_common::{BlockHash, BlockNumber};
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuarantinedBlock {
+    pub block_number: BlockNumber,
+    pub block_hash: BlockHash,
+    /// The name of the failed check.
+    pub check: String,
+    pub reason: String,
+    pub quarantined_at: u64,
+}
+
+impl Transaction<'_> {
+    pub fn insert_quarantined_block(&self, block: &QuarantinedBlock) -> anyhow::Result<()> {
+        self.inner()
+            .execute(
+                r"
+                INSERT INTO quarantined_blocks
+                    (block_number, block_hash, check_name, reason, quarantined_at)
+                VALUES (?, ?, ?, ?, ?)
+                ",
+                params![
+                    &block.block_number,
+                    &block.block_hash,
+                    &block.check,
+                    &block.reason,
+                    &(block.quarantined_at as i64)
+                ],
+            )
+            .context("Inserting quarantined block")?;
+
+        Ok(())
+    }
+
+    /// Returns all quarantined blocks, oldest first.
+    pub fn quarantined_blocks(&self) -> anyhow::Result<Vec<QuarantinedBlock>> {
+        let mut stmt = self
+            .inner()
+            .prepare_cached(
+                r"
+                SELECT block_number, block_hash, check_name, reason, quarantined_at
+                FROM quarantined_blocks
+                ORDER BY id
+                ",
+            )
+            .context("Preparing quarantined blocks query")?;
+
+        let blocks = stmt
+            .query_map([], |row| {
+                Ok(QuarantinedBlock {
+                    block_number: row.get_block_number(0)?,
+                    block_hash: row.get_block_hash(1)?,
+                    check: row.get(2)?,
+                    reason: row.get(3)?,
+                    quarantined_at: row.get_i64(4)? as u64,
+                })
+            })
+            .context("Querying quarantined blocks")?
+            .collect::<Result<Vec<_>, _>>()
+            .context("Iterating over quarantined blocks")?;
+
+        Ok(blocks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+    use crate::StorageBuilder;
+
+    #[test]
+    fn quarantined_blocks_are_listed_in_order() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let first = QuarantinedBlock {
+            block_number: BlockNumber::new_or_panic(10),
+            block_hash: block_hash!("0x1"),
+            check: "signature".to_owned(),
+            reason: "Invalid signature".to_owned(),
+            quarantined_at: 100,
+        };
+        // The same block may fail again after a restart.
+        let second = QuarantinedBlock {
+            quarantined_at: 200,
+            ..first.clone()
+        };
+        tx.insert_quarantined_block(&first).unwrap();
+        tx.insert_quarantined_block(&second).unwrap();
+
+        assert_eq!(tx.quarantined_blocks().unwrap(), vec![first, second]);
+    }
+}
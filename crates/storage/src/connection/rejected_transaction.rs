@@ -0,0 +1,120 @@
+//! Transactions submitted through this node which the gateway rejected.
+//!
+//! Timestamps are seconds since the Unix epoch.
+
+use anyhow::Context;
+use pathfinder_common::TransactionHash;
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedTransaction {
+    pub transaction_hash: TransactionHash,
+    /// The transaction as submitted to the RPC API.
+    pub transaction_json: Vec<u8>,
+    /// The error code returned by the gateway.
+    pub error_code: String,
+    /// The error message returned by the gateway.
+    pub reason: String,
+    pub rejected_at: u64,
+}
+
+impl Transaction<'_> {
+    /// Records a rejected transaction, replacing an earlier rejection of the
+    /// same transaction.
+    pub fn insert_rejected_transaction(
+        &self,
+        transaction: &RejectedTransaction,
+    ) -> anyhow::Result<()> {
+        self.inner()
+            .execute(
+                r"
+                INSERT OR REPLACE INTO rejected_transactions
+                    (transaction_hash, transaction_json, error_code, reason, rejected_at)
+                VALUES (?, ?, ?, ?, ?)
+                ",
+                params![
+                    &transaction.transaction_hash,
+                    &transaction.transaction_json,
+                    &transaction.error_code,
+                    &transaction.reason,
+                    &(transaction.rejected_at as i64)
+                ],
+            )
+            .context("Inserting rejected transaction")?;
+
+        Ok(())
+    }
+
+    pub fn rejected_transaction(
+        &self,
+        transaction_hash: TransactionHash,
+    ) -> anyhow::Result<Option<RejectedTransaction>> {
+        let mut stmt = self
+            .inner()
+            .prepare_cached(
+                r"
+                SELECT transaction_json, error_code, reason, rejected_at
+                FROM rejected_transactions
+                WHERE transaction_hash = ?
+                ",
+            )
+            .context("Preparing rejected transaction query")?;
+
+        stmt.query_row(params![&transaction_hash], |row| {
+            Ok(RejectedTransaction {
+                transaction_hash,
+                transaction_json: row.get_blob(0)?.to_vec(),
+                error_code: row.get(1)?,
+                reason: row.get(2)?,
+                rejected_at: row.get_i64(3)? as u64,
+            })
+        })
+        .optional()
+        .context("Querying rejected transaction")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+    use crate::StorageBuilder;
+
+    #[test]
+    fn later_rejections_replace_earlier_ones() {
+        let storage = StorageBuilder::in_memory().unwrap();
+        let mut db = storage.connection().unwrap();
+        let tx = db.transaction().unwrap();
+
+        let rejected = RejectedTransaction {
+            transaction_hash: transaction_hash!("0x1"),
+            transaction_json: b"{}".to_vec(),
+            error_code: "StarknetErrorCode.INVALID_TRANSACTION_NONCE".to_owned(),
+            reason: "Invalid transaction nonce".to_owned(),
+            rejected_at: 10,
+        };
+        tx.insert_rejected_transaction(&rejected).unwrap();
+        assert_eq!(
+            tx.rejected_transaction(transaction_hash!("0x1")).unwrap(),
+            Some(rejected.clone())
+        );
+        assert_eq!(
+            tx.rejected_transaction(transaction_hash!("0x2")).unwrap(),
+            None
+        );
+
+        let rejected_again = RejectedTransaction {
+            error_code: "StarknetErrorCode.VALIDATE_FAILURE".to_owned(),
+            reason: "Validation failed".to_owned(),
+            rejected_at: 20,
+            ..rejected
+        };
+        tx.insert_rejected_transaction(&rejected_again).unwrap();
+        assert_eq!(
+            tx.rejected_transaction(transaction_hash!("0x1")).unwrap(),
+            Some(rejected_again)
+        );
+    }
+}
@@ -59,6 +59,16 @@ impl Transaction<'_> {
                     Ok(BlockCommitmentSignature { r, s })
                 },
             ),
+            BlockId::L1Accepted => self.inner().query_row(
+                "SELECT signature_r, signature_s FROM block_signatures WHERE block_number = \
+                 (SELECT l1_l2_head FROM refs WHERE idx = 1)",
+                [],
+                |row| {
+                    let r = row.get_block_commitment_signature_elem(0)?;
+                    let s = row.get_block_commitment_signature_elem(1)?;
+                    Ok(BlockCommitmentSignature { r, s })
+                },
+            ),
         }
         .optional()
         .map_err(|e| e.into())
@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::num::NonZeroUsize;
+use std::rc::Rc;
 
 use anyhow::Context;
 use pathfinder_common::state_update::{
@@ -22,12 +23,22 @@ use pathfinder_common::{
     StorageAddress,
     StorageValue,
 };
+use rusqlite::types::Value;
 
 use crate::prelude::*;
 use crate::BlockId;
 
 type StorageUpdates = Vec<(StorageAddress, StorageValue)>;
 
+/// A write to a contract's storage, see [Transaction::storage_write] and
+/// [Transaction::storage_updates_for_contract].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageWrite {
+    pub block_number: BlockNumber,
+    pub key: StorageAddress,
+    pub value: StorageValue,
+}
+
 impl Transaction<'_> {
     /// Inserts a canonical [StateUpdate] into storage.
     pub fn insert_state_update(
@@ -275,6 +286,8 @@ impl Transaction<'_> {
         const LATEST: &str = formatcp!("{PREFIX} ORDER BY b1.number DESC LIMIT 1");
         const NUMBER: &str = formatcp!("{PREFIX} WHERE b1.number = ?");
         const HASH: &str = formatcp!("{PREFIX} WHERE b1.hash = ?");
+        const L1_ACCEPTED: &str =
+            formatcp!("{PREFIX} WHERE b1.number = (SELECT l1_l2_head FROM refs WHERE idx = 1)");
 
         let handle_row = |row: &rusqlite::Row<'_>| {
             let number = row.get_block_number(0)?;
@@ -292,6 +305,7 @@ impl Transaction<'_> {
             BlockId::Latest => tx.query_row(LATEST, [], handle_row),
             BlockId::Number(number) => tx.query_row(NUMBER, params![&number], handle_row),
             BlockId::Hash(hash) => tx.query_row(HASH, params![&hash], handle_row),
+            BlockId::L1Accepted => tx.query_row(L1_ACCEPTED, [], handle_row),
         }
         .optional()
         .map_err(Into::into)
@@ -589,11 +603,39 @@ impl Transaction<'_> {
         contract_address: ContractAddress,
         key: StorageAddress,
     ) -> anyhow::Result<Option<StorageValue>> {
+        Ok(self
+            .storage_write(block, contract_address, key)?
+            .map(|write| write.value))
+    }
+
+    /// Returns the most recent write to `key` of `contract_address` at or
+    /// before `block`, i.e. its [value](Self::storage_value) along with the
+    /// block it was written in.
+    pub fn storage_write(
+        &self,
+        block: BlockId,
+        contract_address: ContractAddress,
+        key: StorageAddress,
+    ) -> anyhow::Result<Option<StorageWrite>> {
+        let write = |row: &rusqlite::Row<'_>| -> rusqlite::Result<StorageWrite> {
+            Ok(StorageWrite {
+                block_number: row.get_block_number(0)?,
+                key,
+                value: row.get_storage_value(1)?,
+            })
+        };
+
         match block {
+            BlockId::L1Accepted => {
+                return match self.l1_l2_pointer()? {
+                    Some(number) => self.storage_write(number.into(), contract_address, key),
+                    None => Ok(None),
+                };
+            }
             BlockId::Latest => {
                 let mut stmt = self.inner().prepare_cached(
                     r"
-                    SELECT storage_value
+                    SELECT block_number, storage_value
                     FROM storage_updates
                     JOIN contract_addresses ON contract_addresses.id = storage_updates.contract_address_id
                     JOIN storage_addresses ON storage_addresses.id = storage_updates.storage_address_id
@@ -601,14 +643,12 @@ impl Transaction<'_> {
                     ORDER BY block_number DESC LIMIT 1
                     ",
                 )?;
-                stmt.query_row(params![&contract_address, &key], |row| {
-                    row.get_storage_value(0)
-                })
+                stmt.query_row(params![&contract_address, &key], write)
             }
             BlockId::Number(number) => {
                 let mut stmt = self.inner().prepare_cached(
                     r"
-                    SELECT storage_value
+                    SELECT block_number, storage_value
                     FROM storage_updates
                     JOIN contract_addresses ON contract_addresses.id = storage_updates.contract_address_id
                     JOIN storage_addresses ON storage_addresses.id = storage_updates.storage_address_id
@@ -616,14 +656,12 @@ impl Transaction<'_> {
                     ORDER BY block_number DESC LIMIT 1
                     ",
                 )?;
-                stmt.query_row(params![&contract_address, &key, &number], |row| {
-                    row.get_storage_value(0)
-                })
+                stmt.query_row(params![&contract_address, &key, &number], write)
             }
             BlockId::Hash(hash) => {
                 let mut stmt = self.inner().prepare_cached(
                     r"
-                    SELECT storage_value
+                    SELECT block_number, storage_value
                     FROM storage_updates
                     JOIN contract_addresses ON contract_addresses.id = storage_updates.contract_address_id
                     JOIN storage_addresses ON storage_addresses.id = storage_updates.storage_address_id
@@ -633,15 +671,92 @@ impl Transaction<'_> {
                     ORDER BY block_number DESC LIMIT 1
                     ",
                 )?;
-                stmt.query_row(params![&contract_address, &key, &hash], |row| {
-                    row.get_storage_value(0)
-                })
+                stmt.query_row(params![&contract_address, &key, &hash], write)
             }
         }
         .optional()
         .map_err(|e| e.into())
     }
 
+    /// Returns up to `limit` writes to the storage of `contract_address` in
+    /// blocks `from_block..=to_block`, ordered by block number and then by key.
+    ///
+    /// With `reverse` the writes are returned starting with the latest block,
+    /// so that the value of a key at some block is the first write to it.
+    ///
+    /// The listing starts at the write of `resume_at` if set, given by its
+    /// block and key, so that a listing cut short by `limit` can be continued.
+    pub fn storage_updates_for_contract(
+        &self,
+        contract_address: ContractAddress,
+        from_block: BlockNumber,
+        to_block: BlockNumber,
+        reverse: bool,
+        resume_at: Option<(BlockNumber, StorageAddress)>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<StorageWrite>> {
+        let sql = if reverse {
+            r"
+            SELECT block_number, storage_address, storage_value
+            FROM storage_updates
+            JOIN storage_addresses ON storage_addresses.id = storage_updates.storage_address_id
+            WHERE contract_address_id = (
+                SELECT id FROM contract_addresses WHERE contract_address = :contract_address
+            ) AND block_number BETWEEN :from_block AND :to_block
+            AND (
+                :resume_block IS NULL
+                OR (block_number, storage_address) <= (:resume_block, :resume_key)
+            )
+            ORDER BY block_number DESC, storage_address DESC
+            LIMIT :limit
+            "
+        } else {
+            r"
+            SELECT block_number, storage_address, storage_value
+            FROM storage_updates
+            JOIN storage_addresses ON storage_addresses.id = storage_updates.storage_address_id
+            WHERE contract_address_id = (
+                SELECT id FROM contract_addresses WHERE contract_address = :contract_address
+            ) AND block_number BETWEEN :from_block AND :to_block
+            AND (
+                :resume_block IS NULL
+                OR (block_number, storage_address) >= (:resume_block, :resume_key)
+            )
+            ORDER BY block_number ASC, storage_address ASC
+            LIMIT :limit
+            "
+        };
+        let limit: i64 = limit.try_into()?;
+        let (resume_block, resume_key) = resume_at.unzip();
+        let mut stmt = self
+            .inner()
+            .prepare_cached(sql)
+            .context("Preparing statement")?;
+
+        let rows = stmt
+            .query_map(
+                named_params![
+                    ":contract_address": &contract_address,
+                    ":from_block": &from_block,
+                    ":to_block": &to_block,
+                    ":resume_block": &resume_block,
+                    ":resume_key": &resume_key,
+                    ":limit": &limit,
+                ],
+                |row| {
+                    Ok(StorageWrite {
+                        block_number: row.get_block_number(0)?,
+                        key: row.get_storage_address(1)?,
+                        value: row.get_storage_value(2)?,
+                    })
+                },
+            )
+            .context("Querying storage updates")?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .context("Iterating over storage updates")
+    }
+
     pub fn contract_exists(
         &self,
         contract_address: ContractAddress,
@@ -670,6 +785,12 @@ impl Transaction<'_> {
                     |row| row.get(0),
                 )
             }
+            BlockId::L1Accepted => {
+                return match self.l1_l2_pointer()? {
+                    Some(number) => self.contract_exists(contract_address, number.into()),
+                    None => Ok(false),
+                };
+            }
             BlockId::Latest => {
                 let mut stmt = self.inner().prepare_cached(
                     "SELECT EXISTS(SELECT 1 FROM contract_updates WHERE contract_address = ?)",
@@ -689,6 +810,12 @@ impl Transaction<'_> {
         block_id: BlockId,
     ) -> anyhow::Result<Option<ContractNonce>> {
         match block_id {
+            BlockId::L1Accepted => {
+                return match self.l1_l2_pointer()? {
+                    Some(number) => self.contract_nonce(contract_address, number.into()),
+                    None => Ok(None),
+                };
+            }
             BlockId::Latest => {
                 let mut stmt = self.inner().prepare_cached(
                     r"
@@ -733,12 +860,102 @@ impl Transaction<'_> {
         .map_err(|e| e.into())
     }
 
+    /// Returns the nonces of `contract_addresses` at `block`, in the same
+    /// order. Contracts which do not exist at `block` are [None], while
+    /// contracts whose nonce was never updated have a zero nonce.
+    pub fn contract_nonces(
+        &self,
+        contract_addresses: &[ContractAddress],
+        block: BlockNumber,
+    ) -> anyhow::Result<Vec<Option<ContractNonce>>> {
+        let addresses = Rc::new(
+            contract_addresses
+                .iter()
+                .map(|address| Value::from(address.0.as_be_bytes().to_vec()))
+                .collect::<Vec<Value>>(),
+        );
+
+        // SQLite takes the bare `nonce` column from the row with the maximum block
+        // number, i.e. the latest nonce update of each contract.
+        let mut stmt = self
+            .inner()
+            .prepare_cached(
+                r"
+                SELECT contract_address, nonce, MAX(block_number)
+                FROM nonce_updates
+                JOIN contract_addresses ON contract_addresses.id = nonce_updates.contract_address_id
+                WHERE contract_address IN rarray(:addresses) AND block_number <= :block
+                GROUP BY nonce_updates.contract_address_id
+                ",
+            )
+            .context("Preparing contract nonces query")?;
+        let nonces = stmt
+            .query_map(
+                // Cannot use crate::params::named_params![] here because of the rarray.
+                rusqlite::named_params![
+                    ":addresses": &addresses,
+                    ":block": &block.get(),
+                ],
+                |row| Ok((row.get_contract_address(0)?, row.get_contract_nonce(1)?)),
+            )
+            .context("Querying contract nonces")?
+            .collect::<Result<HashMap<_, _>, _>>()
+            .context("Iterating over contract nonces")?;
+
+        // Early starknet contracts had no nonces, so it's possible for a contract to
+        // exist without having the nonce explicitly set to zero on deployment.
+        let without_nonce = Rc::new(
+            contract_addresses
+                .iter()
+                .filter(|address| !nonces.contains_key(address))
+                .map(|address| Value::from(address.0.as_be_bytes().to_vec()))
+                .collect::<Vec<Value>>(),
+        );
+        let existing = if without_nonce.is_empty() {
+            HashSet::new()
+        } else {
+            let mut stmt = self
+                .inner()
+                .prepare_cached(
+                    r"
+                    SELECT DISTINCT contract_address FROM contract_updates
+                    WHERE contract_address IN rarray(:addresses) AND block_number <= :block
+                    ",
+                )
+                .context("Preparing contract existence query")?;
+            stmt.query_map(
+                rusqlite::named_params![
+                    ":addresses": &without_nonce,
+                    ":block": &block.get(),
+                ],
+                |row| row.get_contract_address(0),
+            )
+            .context("Querying contract existence")?
+            .collect::<Result<HashSet<_>, _>>()
+            .context("Iterating over existing contracts")?
+        };
+
+        Ok(contract_addresses
+            .iter()
+            .map(|address| match nonces.get(address) {
+                Some(nonce) => Some(*nonce),
+                None => existing.contains(address).then_some(ContractNonce::ZERO),
+            })
+            .collect())
+    }
+
     pub fn contract_class_hash(
         &self,
         block_id: BlockId,
         contract_address: ContractAddress,
     ) -> anyhow::Result<Option<ClassHash>> {
         match block_id {
+            BlockId::L1Accepted => {
+                return match self.l1_l2_pointer()? {
+                    Some(number) => self.contract_class_hash(number.into(), contract_address),
+                    None => Ok(None),
+                };
+            }
             BlockId::Latest => {
                 let mut stmt = self.inner().prepare_cached(
                     r"SELECT class_hash FROM contract_updates
@@ -1061,6 +1278,182 @@ mod tests {
         assert_eq!(declared_at, header_0.number);
     }
 
+    #[test]
+    fn contract_nonces() {
+        let mut db = crate::StorageBuilder::in_memory()
+            .unwrap()
+            .connection()
+            .unwrap();
+        let tx = db.transaction().unwrap();
+
+        let account = contract_address!("0x1");
+        let legacy = contract_address!("0x2");
+        let late = contract_address!("0x3");
+        let missing = contract_address!("0x4");
+
+        let header_0 = BlockHeader::builder().finalize_with_hash(block_hash!("0xabc"));
+        let header_1 = header_0
+            .child_builder()
+            .finalize_with_hash(block_hash!("0xdef"));
+
+        let diff_0 = StateUpdate::default()
+            .with_deployed_contract(account, class_hash!("0x10"))
+            .with_contract_nonce(account, contract_nonce!("0x1"))
+            .with_deployed_contract(legacy, class_hash!("0x10"));
+        let diff_1 = StateUpdate::default()
+            .with_contract_nonce(account, contract_nonce!("0x2"))
+            .with_deployed_contract(late, class_hash!("0x10"))
+            .with_contract_nonce(late, contract_nonce!("0x5"));
+
+        for (header, diff) in [(&header_0, diff_0), (&header_1, diff_1)] {
+            tx.insert_block_header(header).unwrap();
+            tx.insert_state_update(header.number, &diff).unwrap();
+        }
+
+        let addresses = [late, missing, account, legacy];
+        assert_eq!(
+            tx.contract_nonces(&addresses, header_0.number).unwrap(),
+            vec![
+                None,
+                None,
+                Some(contract_nonce!("0x1")),
+                Some(ContractNonce::ZERO)
+            ]
+        );
+        assert_eq!(
+            tx.contract_nonces(&addresses, header_1.number).unwrap(),
+            vec![
+                Some(contract_nonce!("0x5")),
+                None,
+                Some(contract_nonce!("0x2")),
+                Some(ContractNonce::ZERO)
+            ]
+        );
+        assert!(tx.contract_nonces(&[], header_1.number).unwrap().is_empty());
+    }
+
+    #[test]
+    fn storage_writes() {
+        let mut db = crate::StorageBuilder::in_memory()
+            .unwrap()
+            .connection()
+            .unwrap();
+        let tx = db.transaction().unwrap();
+
+        let contract = contract_address!("0x12345");
+        let other = contract_address!("0x6789");
+
+        let header_0 = BlockHeader::builder().finalize_with_hash(block_hash!("0xabc"));
+        let header_1 = header_0
+            .child_builder()
+            .finalize_with_hash(block_hash!("0xabcdef"));
+        let header_2 = header_1
+            .child_builder()
+            .finalize_with_hash(block_hash!("0xa111123"));
+
+        let diff_0 = StateUpdate::default()
+            .with_storage_update(contract, storage_address!("0x2"), storage_value!("0x20"))
+            .with_storage_update(contract, storage_address!("0x1"), storage_value!("0x10"))
+            .with_storage_update(other, storage_address!("0x1"), storage_value!("0x99"));
+        let diff_1 = StateUpdate::default();
+        let diff_2 = StateUpdate::default().with_storage_update(
+            contract,
+            storage_address!("0x1"),
+            storage_value!("0x11"),
+        );
+
+        for (header, diff) in [
+            (&header_0, diff_0),
+            (&header_1, diff_1),
+            (&header_2, diff_2),
+        ] {
+            tx.insert_block_header(header).unwrap();
+            tx.insert_state_update(header.number, &diff).unwrap();
+        }
+
+        let write = |block: &BlockHeader, key, value| StorageWrite {
+            block_number: block.number,
+            key,
+            value,
+        };
+        let expected = vec![
+            write(&header_0, storage_address!("0x1"), storage_value!("0x10")),
+            write(&header_0, storage_address!("0x2"), storage_value!("0x20")),
+            write(&header_2, storage_address!("0x1"), storage_value!("0x11")),
+        ];
+
+        let writes = |from: &BlockHeader, to: &BlockHeader, reverse, resume_at, limit| {
+            tx.storage_updates_for_contract(
+                contract,
+                from.number,
+                to.number,
+                reverse,
+                resume_at,
+                limit,
+            )
+            .unwrap()
+        };
+        assert_eq!(writes(&header_0, &header_2, false, None, 10), expected);
+        assert_eq!(
+            writes(&header_0, &header_2, true, None, 10),
+            expected.iter().copied().rev().collect::<Vec<_>>()
+        );
+        assert_eq!(writes(&header_1, &header_1, false, None, 10), vec![]);
+
+        // Listings cut short by the limit continue at the next write.
+        assert_eq!(writes(&header_0, &header_2, false, None, 2), expected[..2]);
+        let next = (expected[2].block_number, expected[2].key);
+        assert_eq!(
+            writes(&header_0, &header_2, false, Some(next), 2),
+            expected[2..]
+        );
+        let next = (expected[1].block_number, expected[1].key);
+        assert_eq!(
+            writes(&header_0, &header_2, true, Some(next), 10),
+            vec![expected[1], expected[0]]
+        );
+
+        let writes = tx
+            .storage_updates_for_contract(
+                contract_address!("0xdead"),
+                header_0.number,
+                header_2.number,
+                false,
+                None,
+                10,
+            )
+            .unwrap();
+        assert_eq!(writes, vec![]);
+
+        let key = storage_address!("0x1");
+        let write = tx
+            .storage_write(header_1.number.into(), contract, key)
+            .unwrap();
+        assert_eq!(
+            write,
+            Some(StorageWrite {
+                block_number: header_0.number,
+                key,
+                value: storage_value!("0x10"),
+            })
+        );
+        let write = tx
+            .storage_write(header_2.number.into(), contract, key)
+            .unwrap();
+        assert_eq!(
+            write,
+            Some(StorageWrite {
+                block_number: header_2.number,
+                key,
+                value: storage_value!("0x11"),
+            })
+        );
+        let write = tx
+            .storage_write(header_2.number.into(), contract, storage_address!("0x3"))
+            .unwrap();
+        assert_eq!(write, None);
+    }
+
     #[test]
     fn contract_class_hash() {
         let mut db = crate::StorageBuilder::in_memory()
@@ -4,9 +4,9 @@ use anyhow::Context;
 use pathfinder_common::event::Event;
 use pathfinder_common::receipt::Receipt;
 use pathfinder_common::transaction::Transaction as StarknetTransaction;
-use pathfinder_common::{BlockHash, BlockNumber, TransactionHash};
+use pathfinder_common::{BlockHash, BlockNumber, ContractAddress, TransactionHash};
 
-use super::{EventsForBlock, TransactionDataForBlock, TransactionWithReceipt};
+use super::{BlockEventFilter, EventsForBlock, TransactionDataForBlock, TransactionWithReceipt};
 use crate::prelude::*;
 use crate::BlockId;
 
@@ -46,9 +46,82 @@ pub(crate) mod compression {
             zstd::dict::DecoderDictionary::new(include_bytes!("../assets/events.zdict"))
         });
 
+    /// The encoding of a compressed transaction or event blob.
+    ///
+    /// Apart from [CompressionFormat::Legacy] blobs start with a tag byte
+    /// identifying their format, so that the codec can change without
+    /// rewriting existing rows. Tags must differ from the first byte of the
+    /// zstd magic number, which legacy blobs start with.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub(crate) enum CompressionFormat {
+        /// A bare zstd frame compressed with the bundled dictionaries, as
+        /// written before formats were tagged.
+        Legacy,
+        /// A zstd frame compressed with the bundled dictionaries.
+        ZstdDictionary,
+    }
+
+    impl CompressionFormat {
+        /// The format new blobs are written in.
+        pub(crate) const CURRENT: Self = Self::ZstdDictionary;
+
+        pub(crate) const ALL: [Self; 2] = [Self::Legacy, Self::ZstdDictionary];
+
+        const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+        fn tag(self) -> Option<u8> {
+            match self {
+                Self::Legacy => None,
+                Self::ZstdDictionary => Some(1),
+            }
+        }
+
+        /// Identifies the format of a blob, returning the format and the
+        /// compressed payload.
+        pub(crate) fn of(blob: &[u8]) -> std::io::Result<(Self, &[u8])> {
+            if blob.starts_with(&Self::ZSTD_MAGIC) {
+                return Ok((Self::Legacy, blob));
+            }
+
+            match blob.split_first() {
+                Some((1, payload)) => Ok((Self::ZstdDictionary, payload)),
+                Some((tag, _)) => Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Unknown compression format {tag}"),
+                )),
+                None => Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Empty compressed blob",
+                )),
+            }
+        }
+
+        /// Whether blobs in this format are encoded with the current codec and
+        /// need no rewriting. [CompressionFormat::Legacy] blobs are bare frames
+        /// of the current codec, so they are not rewritten just to add the tag.
+        pub(crate) fn is_current(self) -> bool {
+            match self {
+                Self::Legacy | Self::ZstdDictionary => true,
+            }
+        }
+    }
+
+    /// Prefixes a compressed payload with the tag of the current format.
+    fn envelope(payload: Vec<u8>) -> Vec<u8> {
+        match CompressionFormat::CURRENT.tag() {
+            Some(tag) => {
+                let mut blob = Vec::with_capacity(payload.len() + 1);
+                blob.push(tag);
+                blob.extend(payload);
+                blob
+            }
+            None => payload,
+        }
+    }
+
     pub(super) fn compress_transactions(input: &[u8]) -> std::io::Result<Vec<u8>> {
         let mut compressor = new_txs_compressor()?;
-        compressor.compress(input)
+        compressor.compress(input).map(envelope)
     }
 
     pub(crate) fn new_txs_compressor() -> std::io::Result<zstd::bulk::Compressor<'static>> {
@@ -57,7 +130,7 @@ pub(crate) mod compression {
 
     pub(super) fn compress_events(input: &[u8]) -> std::io::Result<Vec<u8>> {
         let mut compressor = new_events_compressor()?;
-        compressor.compress(input)
+        compressor.compress(input).map(envelope)
     }
 
     pub(crate) fn new_events_compressor() -> std::io::Result<zstd::bulk::Compressor<'static>> {
@@ -65,8 +138,12 @@ pub(crate) mod compression {
     }
 
     pub(crate) fn decompress_transactions(input: &[u8]) -> std::io::Result<Vec<u8>> {
-        let mut decompressor = new_txs_decompressor()?;
-        decompressor.decompress(input, MAX_TRANSACTIONS_UNCOMPRESSED_SIZE)
+        match CompressionFormat::of(input)? {
+            (CompressionFormat::Legacy | CompressionFormat::ZstdDictionary, payload) => {
+                let mut decompressor = new_txs_decompressor()?;
+                decompressor.decompress(payload, MAX_TRANSACTIONS_UNCOMPRESSED_SIZE)
+            }
+        }
     }
 
     fn new_txs_decompressor() -> std::io::Result<zstd::bulk::Decompressor<'static>> {
@@ -74,13 +151,46 @@ pub(crate) mod compression {
     }
 
     pub(crate) fn decompress_events(input: &[u8]) -> std::io::Result<Vec<u8>> {
-        let mut decompressor = new_events_decompressor()?;
-        decompressor.decompress(input, MAX_EVENTS_UNCOMPRESSED_SIZE)
+        match CompressionFormat::of(input)? {
+            (CompressionFormat::Legacy | CompressionFormat::ZstdDictionary, payload) => {
+                let mut decompressor = new_events_decompressor()?;
+                decompressor.decompress(payload, MAX_EVENTS_UNCOMPRESSED_SIZE)
+            }
+        }
     }
 
     fn new_events_decompressor() -> std::io::Result<zstd::bulk::Decompressor<'static>> {
         zstd::bulk::Decompressor::with_prepared_dictionary(&ZSTD_EVENTS_DECODER_DICTIONARY)
     }
+
+    /// Re-encodes a transaction blob in the current format. Returns [None] if
+    /// its format [is current](CompressionFormat::is_current).
+    pub(super) fn recompress_transactions(blob: &[u8]) -> std::io::Result<Option<Vec<u8>>> {
+        if CompressionFormat::of(blob)?.0.is_current() {
+            return Ok(None);
+        }
+
+        compress_transactions(&decompress_transactions(blob)?).map(Some)
+    }
+
+    /// Re-encodes an event blob in the current format. Returns [None] if its
+    /// format [is current](CompressionFormat::is_current).
+    pub(super) fn recompress_events(blob: &[u8]) -> std::io::Result<Option<Vec<u8>>> {
+        if CompressionFormat::of(blob)?.0.is_current() {
+            return Ok(None);
+        }
+
+        compress_events(&decompress_events(blob)?).map(Some)
+    }
+}
+
+/// Whether transaction data may be stored in a format which
+/// [Transaction::recompress_transaction_data] rewrites. While all formats are
+/// encoded with the current codec there is nothing to rewrite.
+pub fn transaction_data_needs_recompression() -> bool {
+    compression::CompressionFormat::ALL
+        .iter()
+        .any(|format| !format.is_current())
 }
 
 type TransactionsAndEventsByBlock = (Vec<(StarknetTransaction, Receipt)>, Vec<Vec<Event>>);
@@ -99,10 +209,45 @@ impl Transaction<'_> {
         transactions: &[(StarknetTransaction, Receipt)],
         events: Option<&[Vec<Event>]>,
     ) -> anyhow::Result<()> {
-        if let Some(events) = events {
-            self.upsert_block_event_filters(block_number, events.iter().flatten())
+        let event_filter = events.map(|events| BlockEventFilter::new(events.iter().flatten()));
+        self.insert_transaction_data_impl(block_number, transactions, events, event_filter)
+    }
+
+    /// Same as [insert_transaction_data](Self::insert_transaction_data), but
+    /// with the block's event filter already built by the caller. This keeps
+    /// the filter construction off the (serial) insertion path.
+    ///
+    /// `event_filter` must have been built from `events`.
+    pub fn insert_transaction_data_with_event_filter(
+        &self,
+        block_number: BlockNumber,
+        transactions: &[(StarknetTransaction, Receipt)],
+        events: &[Vec<Event>],
+        event_filter: BlockEventFilter,
+    ) -> anyhow::Result<()> {
+        self.insert_transaction_data_impl(
+            block_number,
+            transactions,
+            Some(events),
+            Some(event_filter),
+        )
+    }
+
+    fn insert_transaction_data_impl(
+        &self,
+        block_number: BlockNumber,
+        transactions: &[(StarknetTransaction, Receipt)],
+        events: Option<&[Vec<Event>]>,
+        event_filter: Option<BlockEventFilter>,
+    ) -> anyhow::Result<()> {
+        if let Some(event_filter) = event_filter {
+            self.upsert_block_event_filters(block_number, &event_filter)
                 .context("Inserting events into Bloom filter")?;
         }
+        if let Some(events) = events {
+            self.upsert_event_selectors(block_number, events.iter().flatten())
+                .context("Indexing event selectors")?;
+        }
         if transactions.is_empty() && events.map_or(true, |evts| evts.is_empty()) {
             return Ok(());
         }
@@ -121,6 +266,13 @@ impl Transaction<'_> {
                  :block_number, :idx)",
             )
             .context("Preparing insert transaction hash statement")?;
+        let mut insert_transaction_sender_stmt = self
+            .inner()
+            .prepare_cached(
+                "INSERT INTO transaction_senders (sender, block_number, idx) VALUES (:sender, \
+                 :block_number, :idx)",
+            )
+            .context("Preparing insert transaction sender statement")?;
 
         for (idx, (transaction, ..)) in transactions.iter().enumerate() {
             let idx: i64 = idx.try_into()?;
@@ -129,6 +281,15 @@ impl Transaction<'_> {
                 ":block_number": &block_number,
                 ":idx": &idx,
             ])?;
+            if let Some(sender) = transaction.variant.sender_address() {
+                insert_transaction_sender_stmt
+                    .execute(named_params![
+                        ":sender": &sender,
+                        ":block_number": &block_number,
+                        ":idx": &idx,
+                    ])
+                    .context("Inserting transaction sender")?;
+            }
         }
         let transactions_with_receipts: Vec<_> = transactions
             .iter()
@@ -176,6 +337,24 @@ impl Transaction<'_> {
         &self,
         block_number: BlockNumber,
         events: Vec<Vec<Event>>,
+    ) -> anyhow::Result<()> {
+        self.overwrite_events(block_number, &events)?;
+
+        let event_filter = BlockEventFilter::new(events.iter().flatten());
+        self.upsert_block_event_filters(block_number, &event_filter)
+            .context("Inserting events into Bloom filter")?;
+        self.upsert_event_selectors(block_number, events.iter().flatten())
+            .context("Indexing event selectors")?;
+
+        Ok(())
+    }
+
+    /// Replaces the stored events of a block without updating the event
+    /// indexes.
+    pub(super) fn overwrite_events(
+        &self,
+        block_number: BlockNumber,
+        events: &[Vec<Event>],
     ) -> anyhow::Result<()> {
         let mut stmt = self
             .inner()
@@ -207,10 +386,6 @@ impl Transaction<'_> {
         ])
         .context("Updating events")?;
 
-        let events = events.iter().flatten();
-        self.upsert_block_event_filters(block_number, events)
-            .context("Inserting events into Bloom filter")?;
-
         Ok(())
     }
 
@@ -366,6 +541,133 @@ impl Transaction<'_> {
             .map_err(|e| e.into())
     }
 
+    /// Returns the positions, i.e. the block number and the index within the
+    /// block, of the transactions sent by `sender` up to and including
+    /// `to_block`, in chain order.
+    ///
+    /// The search starts at the position `from` and returns at most `limit`
+    /// positions.
+    pub fn transactions_by_sender(
+        &self,
+        sender: ContractAddress,
+        from: (BlockNumber, usize),
+        to_block: BlockNumber,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(BlockNumber, usize)>> {
+        let (from_block, from_index) = from;
+        let from_index: i64 = from_index.try_into()?;
+        let limit: i64 = limit.try_into()?;
+
+        let mut stmt = self.inner().prepare_cached(
+            r"
+            SELECT block_number, idx
+            FROM transaction_senders
+            WHERE sender = :sender
+            AND (block_number, idx) >= (:from_block, :from_index)
+            AND block_number <= :to_block
+            ORDER BY block_number, idx
+            LIMIT :limit
+            ",
+        )?;
+
+        let positions = stmt
+            .query_map(
+                named_params![
+                    ":sender": &sender,
+                    ":from_block": &from_block,
+                    ":from_index": &from_index,
+                    ":to_block": &to_block,
+                    ":limit": &limit,
+                ],
+                |row| {
+                    let block_number = row.get_block_number(0)?;
+                    let idx = row.get_i64(1)?;
+                    Ok((block_number, idx))
+                },
+            )
+            .context("Querying transactions by sender")?
+            .map(|row| {
+                let (block_number, idx) = row?;
+                Ok((block_number, idx.try_into()?))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(positions)
+    }
+
+    /// Re-encodes the transaction and event blobs of up to `max_blocks` blocks,
+    /// starting at `from`, which are not encoded with the current codec.
+    ///
+    /// Returns the number of rows rewritten and the block to continue from,
+    /// which is [None] once all blocks have been processed.
+    pub fn recompress_transaction_data(
+        &self,
+        from: BlockNumber,
+        max_blocks: usize,
+    ) -> anyhow::Result<(usize, Option<BlockNumber>)> {
+        let max_blocks: i64 = max_blocks.try_into()?;
+        let mut select_stmt = self.inner().prepare_cached(
+            r"
+            SELECT block_number, transactions, events
+            FROM transactions
+            WHERE block_number >= ?
+            ORDER BY block_number
+            LIMIT ?
+            ",
+        )?;
+        let mut update_stmt = self.inner().prepare_cached(
+            r"
+            UPDATE transactions
+            SET transactions = :transactions, events = :events
+            WHERE block_number = :block_number
+            ",
+        )?;
+
+        // Collect the rows first, as they are updated while iterating.
+        let rows = select_stmt
+            .query_map(params![&from, &max_blocks], |row| {
+                Ok((
+                    row.get_block_number(0)?,
+                    row.get_blob(1)?.to_vec(),
+                    row.get_optional_blob(2)?.map(<[u8]>::to_vec),
+                ))
+            })
+            .context("Querying transaction data")?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let next = match rows.last() {
+            Some((last, ..)) if rows.len() as i64 == max_blocks => Some(*last + 1),
+            _ => None,
+        };
+
+        let mut rewritten = 0;
+        for (block_number, transactions, events) in rows {
+            let new_transactions = compression::recompress_transactions(&transactions)
+                .context("Recompressing transactions")?;
+            let new_events = events
+                .as_deref()
+                .map(compression::recompress_events)
+                .transpose()
+                .context("Recompressing events")?
+                .flatten();
+
+            if new_transactions.is_none() && new_events.is_none() {
+                continue;
+            }
+
+            update_stmt
+                .execute(named_params![
+                    ":block_number": &block_number,
+                    ":transactions": &new_transactions.unwrap_or(transactions),
+                    ":events": &new_events.or(events),
+                ])
+                .context("Updating transaction data")?;
+            rewritten += 1;
+        }
+
+        Ok((rewritten, next))
+    }
+
     fn query_transactions_by_block(
         &self,
         block_number: BlockNumber,
@@ -480,7 +782,7 @@ impl Transaction<'_> {
         ))
     }
 
-    fn query_events_by_block(
+    pub(super) fn query_events_by_block(
         &self,
         block_number: BlockNumber,
     ) -> anyhow::Result<Option<Vec<Vec<Event>>>> {
@@ -3309,4 +3611,132 @@ mod tests {
             .unwrap();
         assert_eq!(invalid, None);
     }
+
+    #[test]
+    fn transactions_by_sender() {
+        let (mut db, header, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        // Add a second block with two more transactions of the invoke v1 sender.
+        let (invoke, _) = body[6].clone();
+        let sender = invoke.variant.sender_address().unwrap();
+        let next_header = header
+            .child_builder()
+            .finalize_with_hash(block_hash_bytes!(b"next block hash"));
+        let next_body = [
+            transaction_hash_bytes!(b"next tx hash 0"),
+            transaction_hash_bytes!(b"next tx hash 1"),
+        ]
+        .into_iter()
+        .map(|hash| {
+            let transaction = StarknetTransaction {
+                hash,
+                ..invoke.clone()
+            };
+            let receipt = Receipt {
+                transaction_hash: hash,
+                ..Default::default()
+            };
+            (transaction, receipt)
+        })
+        .collect::<Vec<_>>();
+        tx.insert_block_header(&next_header).unwrap();
+        tx.insert_transaction_data(next_header.number, &next_body, None)
+            .unwrap();
+
+        let all = tx
+            .transactions_by_sender(sender, (BlockNumber::GENESIS, 0), BlockNumber::MAX, 10)
+            .unwrap();
+        assert_eq!(
+            all,
+            vec![
+                (header.number, 6),
+                (next_header.number, 0),
+                (next_header.number, 1)
+            ]
+        );
+
+        let page = tx
+            .transactions_by_sender(sender, (header.number, 7), BlockNumber::MAX, 1)
+            .unwrap();
+        assert_eq!(page, vec![(next_header.number, 0)]);
+
+        let range = tx
+            .transactions_by_sender(sender, (BlockNumber::GENESIS, 0), header.number, 10)
+            .unwrap();
+        assert_eq!(range, vec![(header.number, 6)]);
+
+        // Deployed accounts are the senders of their deploy account transaction.
+        let deploy_account = body[4].0.variant.sender_address().unwrap();
+        let result = tx
+            .transactions_by_sender(
+                deploy_account,
+                (BlockNumber::GENESIS, 0),
+                BlockNumber::MAX,
+                10,
+            )
+            .unwrap();
+        assert_eq!(result, vec![(header.number, 4)]);
+
+        // Deploy transactions have no sender.
+        let TransactionVariant::DeployV0(deploy) = &body[3].0.variant else {
+            unreachable!("Transaction 3 is a deploy transaction");
+        };
+        let result = tx
+            .transactions_by_sender(
+                deploy.contract_address,
+                (BlockNumber::GENESIS, 0),
+                BlockNumber::MAX,
+                10,
+            )
+            .unwrap();
+        assert_eq!(result, vec![]);
+    }
+
+    #[test]
+    fn recompress_transaction_data() {
+        let (mut db, header, body) = setup();
+        let tx = db.transaction().unwrap();
+
+        // Strip the format tags to get the blobs written before formats were tagged.
+        tx.inner()
+            .execute(
+                "UPDATE transactions SET transactions = substr(transactions, 2), events = \
+                 substr(events, 2)",
+                [],
+            )
+            .unwrap();
+        let legacy: Vec<u8> = tx
+            .inner()
+            .query_row("SELECT transactions FROM transactions", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(
+            compression::CompressionFormat::of(&legacy).unwrap().0,
+            compression::CompressionFormat::Legacy
+        );
+
+        let expected = body.into_iter().map(|(t, _)| t).collect::<Vec<_>>();
+        let result = tx.transactions_for_block(header.number.into()).unwrap();
+        assert_eq!(result.as_ref(), Some(&expected));
+
+        // Legacy blobs are encoded with the current codec and are not rewritten.
+        assert!(!transaction_data_needs_recompression());
+        let result = tx.recompress_transaction_data(header.number, 1).unwrap();
+        assert_eq!(result, (0, Some(header.number + 1)));
+        let result = tx
+            .recompress_transaction_data(header.number + 1, 1)
+            .unwrap();
+        assert_eq!(result, (0, None));
+
+        let unchanged: Vec<u8> = tx
+            .inner()
+            .query_row("SELECT transactions FROM transactions", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(unchanged, legacy);
+        assert!(tx.events_for_block(header.number.into()).unwrap().is_some());
+    }
 }
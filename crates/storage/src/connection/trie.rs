@@ -6,6 +6,7 @@ use bitvec::vec::BitVec;
 use pathfinder_common::prelude::*;
 use pathfinder_crypto::Felt;
 
+use crate::backend::TrieTable;
 use crate::prelude::*;
 use crate::{BlockId, TriePruneMode};
 
@@ -24,17 +25,14 @@ impl Transaction<'_> {
     }
 
     pub fn class_root(&self, block_number: BlockNumber) -> anyhow::Result<Option<ClassCommitment>> {
-        self.inner()
-        .query_row(
-            r"SELECT hash FROM trie_class WHERE idx = (
-                SELECT root_index FROM class_roots WHERE block_number <= ? ORDER BY block_number DESC LIMIT 1
-            )",
-            params![&block_number],
-            |row| row.get_optional_class_commitment(0),
-        )
-        .optional()
-        .map(|x| x.flatten())
-        .map_err(Into::into)
+        let Some(root_index) = self.class_root_index(block_number)? else {
+            return Ok(None);
+        };
+
+        let hash = self
+            .trie_backend()
+            .node_hash(TrieTable::Class, root_index)?;
+        Ok(hash.map(ClassCommitment))
     }
 
     pub fn class_root_exists(&self, block_number: BlockNumber) -> anyhow::Result<bool> {
@@ -60,6 +58,20 @@ impl Transaction<'_> {
             .map_err(Into::into)
     }
 
+    pub fn storage_root(
+        &self,
+        block_number: BlockNumber,
+    ) -> anyhow::Result<Option<StorageCommitment>> {
+        let Some(root_index) = self.storage_root_index(block_number)? else {
+            return Ok(None);
+        };
+
+        let hash = self
+            .trie_backend()
+            .node_hash(TrieTable::Storage, root_index)?;
+        Ok(hash.map(StorageCommitment))
+    }
+
     pub fn storage_root_exists(&self, block_number: BlockNumber) -> anyhow::Result<bool> {
         self.inner()
             .query_row(
@@ -92,16 +104,14 @@ impl Transaction<'_> {
         block_number: BlockNumber,
         contract: ContractAddress,
     ) -> anyhow::Result<Option<ContractRoot>> {
-        self.inner()
-        .query_row(
-            r"SELECT hash FROM trie_contracts WHERE idx = (
-                SELECT root_index FROM contract_roots WHERE block_number <= ? AND contract_address = ? ORDER BY block_number DESC LIMIT 1
-            )",
-            params![&block_number, &contract],
-            |row| row.get_contract_root(0),
-        )
-        .optional()
-        .map_err(Into::into)
+        let Some(root_index) = self.contract_root_index(block_number, contract)? else {
+            return Ok(None);
+        };
+
+        let hash = self
+            .trie_backend()
+            .node_hash(TrieTable::Contracts, root_index)?;
+        Ok(hash.map(ContractRoot))
     }
 
     pub fn insert_class_root(
@@ -321,15 +331,15 @@ impl Transaction<'_> {
         update: &TrieUpdate,
         block_number: BlockNumber,
     ) -> anyhow::Result<RootIndexUpdate> {
-        self.insert_trie(update, block_number, "trie_contracts")
+        self.insert_trie(update, block_number, TrieTable::Contracts)
     }
 
     pub fn contract_trie_node(&self, index: u64) -> anyhow::Result<Option<StoredNode>> {
-        self.trie_node(index, "trie_contracts")
+        self.trie_node(index, TrieTable::Contracts)
     }
 
     pub fn contract_trie_node_hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
-        self.trie_node_hash(index, "trie_contracts")
+        self.trie_node_hash(index, TrieTable::Contracts)
     }
 
     pub fn insert_class_trie(
@@ -337,15 +347,15 @@ impl Transaction<'_> {
         update: &TrieUpdate,
         block_number: BlockNumber,
     ) -> anyhow::Result<RootIndexUpdate> {
-        self.insert_trie(update, block_number, "trie_class")
+        self.insert_trie(update, block_number, TrieTable::Class)
     }
 
     pub fn class_trie_node(&self, index: u64) -> anyhow::Result<Option<StoredNode>> {
-        self.trie_node(index, "trie_class")
+        self.trie_node(index, TrieTable::Class)
     }
 
     pub fn class_trie_node_hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
-        self.trie_node_hash(index, "trie_class")
+        self.trie_node_hash(index, TrieTable::Class)
     }
 
     pub fn insert_storage_trie(
@@ -353,15 +363,15 @@ impl Transaction<'_> {
         update: &TrieUpdate,
         block_number: BlockNumber,
     ) -> anyhow::Result<RootIndexUpdate> {
-        self.insert_trie(update, block_number, "trie_storage")
+        self.insert_trie(update, block_number, TrieTable::Storage)
     }
 
     pub fn storage_trie_node(&self, index: u64) -> anyhow::Result<Option<StoredNode>> {
-        self.trie_node(index, "trie_storage")
+        self.trie_node(index, TrieTable::Storage)
     }
 
     pub fn storage_trie_node_hash(&self, index: u64) -> anyhow::Result<Option<Felt>> {
-        self.trie_node_hash(index, "trie_storage")
+        self.trie_node_hash(index, TrieTable::Storage)
     }
 
     /// Prune tries by removing nodes that are no longer needed at the given
@@ -374,16 +384,17 @@ impl Transaction<'_> {
             return Ok(());
         };
         tracing::info!("Cleaning up state trie");
-        self.prune_trie(block_number, num_blocks_kept, "trie_contracts")?;
-        self.prune_trie(block_number, num_blocks_kept, "trie_class")?;
-        self.prune_trie(block_number, num_blocks_kept, "trie_storage")?;
+        for table in TrieTable::ALL {
+            self.prune_trie(block_number, num_blocks_kept, table)?;
+        }
         Ok(())
     }
 
     pub fn coalesce_trie_removals(&self, target_block: BlockNumber) -> anyhow::Result<()> {
-        self.coalesce_removed_trie_nodes(target_block, "trie_contracts")?;
-        self.coalesce_removed_trie_nodes(target_block, "trie_storage")?;
-        self.coalesce_removed_trie_nodes(target_block, "trie_class")
+        for table in TrieTable::ALL {
+            self.coalesce_removed_trie_nodes(target_block, table)?;
+        }
+        Ok(())
     }
 
     /// Mark the input nodes as ready for removal.
@@ -391,9 +402,10 @@ impl Transaction<'_> {
         &self,
         removed: &[u64],
         block_number: BlockNumber,
-        table: &'static str,
+        table: TrieTable,
     ) -> anyhow::Result<()> {
         if !removed.is_empty() {
+            let table = table.name();
             let mut stmt = self
                 .inner()
                 .prepare_cached(&format!(
@@ -421,8 +433,9 @@ impl Transaction<'_> {
     fn coalesce_removed_trie_nodes(
         &self,
         target_block: BlockNumber,
-        table: &'static str,
+        table: TrieTable,
     ) -> anyhow::Result<()> {
+        let table = table.name();
         let mut stmt = self
             .inner()
             .prepare_cached(&format!(
@@ -442,40 +455,37 @@ impl Transaction<'_> {
         &self,
         block_number: BlockNumber,
         num_blocks_kept: u64,
-        table: &'static str,
+        table: TrieTable,
     ) -> anyhow::Result<()> {
         if let Some(before_block) = block_number.checked_sub(num_blocks_kept) {
+            let backend = self.trie_backend();
+            let name = table.name();
+
             // Delete nodes that have already been marked as ready for deletion.
             let mut select_stmt = self
                 .inner()
                 .prepare_cached(&format!(
-                    r"SELECT indices FROM {table}_removals WHERE block_number < ?"
+                    r"SELECT indices FROM {name}_removals WHERE block_number < ?"
                 ))
                 .context("Creating removal statement")?;
             let mut rows = select_stmt
                 .query(params![&before_block])
                 .context("Fetching nodes to delete")?;
-            let mut delete_stmt = self
-                .inner()
-                .prepare_cached(&format!(r"DELETE FROM {table} WHERE idx = ?"))
-                .context("Creating delete statement")?;
             while let Some(row) = rows.next().context("Iterating over rows")? {
                 let (indices, _) = bincode::decode_from_slice::<Vec<u64>, _>(
                     row.get_blob(0)?,
                     bincode::config::standard(),
                 )
                 .context("Decoding indices")?;
-                for idx in indices.iter() {
-                    delete_stmt.execute(params![idx]).context("Deleting node")?;
-                }
-                metrics::counter!(METRIC_TRIE_NODES_REMOVED, indices.len() as u64, "table" => table);
+                backend.delete_nodes(table, &indices)?;
+                metrics::counter!(METRIC_TRIE_NODES_REMOVED, indices.len() as u64, "table" => name);
             }
 
             // Delete the removal markers.
             let mut delete_stmt = self
                 .inner()
                 .prepare_cached(&format!(
-                    r"DELETE FROM {table}_removals WHERE block_number < ?"
+                    r"DELETE FROM {name}_removals WHERE block_number < ?"
                 ))
                 .context("Creating statement to delete removal markers")?;
             delete_stmt
@@ -491,7 +501,7 @@ impl Transaction<'_> {
         &self,
         update: &TrieUpdate,
         block_number: BlockNumber,
-        table: &'static str,
+        table: TrieTable,
     ) -> anyhow::Result<RootIndexUpdate> {
         if let TriePruneMode::Prune { num_blocks_kept } = self.trie_prune_mode {
             self.prune_trie(block_number, num_blocks_kept, table)?;
@@ -506,12 +516,7 @@ impl Transaction<'_> {
             }
         }
 
-        let mut stmt = self
-            .inner()
-            .prepare_cached(&format!(
-                "INSERT INTO {table} (hash, data) VALUES(?, ?) RETURNING idx",
-            ))
-            .context("Creating insert statement")?;
+        let backend = self.trie_backend();
 
         let mut to_insert = Vec::new();
         let mut to_process = vec![NodeRef::Index(update.nodes_added.len() - 1)];
@@ -543,32 +548,33 @@ impl Transaction<'_> {
             }
         }
 
+        // All nodes of the update are stored in a single batch. Nodes are added in
+        // reverse to ensure children always have an assigned index for the parent to
+        // use.
+        let first_index = backend.allocate_batch(table, block_number, to_insert.len())?;
+
         let mut indices = HashMap::new();
+        let mut batch = Vec::with_capacity(to_insert.len());
 
         // Reusable (and oversized) buffer for encoding.
         let mut buffer = [0u8; 256];
 
-        // Insert nodes in reverse to ensure children always have an assigned index for
-        // the parent to use.
-        for idx in to_insert.into_iter().rev() {
+        for (position, idx) in to_insert.into_iter().rev().enumerate() {
             let (hash, node) = &update.nodes_added.get(idx).context("Node index missing")?;
 
             let node = node.as_stored(&indices)?;
 
             let length = node.encode(&mut buffer).context("Encoding node")?;
 
-            let storage_idx: u64 = stmt
-                .query_row(
-                    params![&hash.as_be_bytes().as_slice(), &&buffer[..length]],
-                    |row| row.get(0),
-                )
-                .context("Inserting node")?;
+            batch.push((*hash, buffer[..length].to_vec()));
 
-            indices.insert(idx, storage_idx);
-
-            metrics::increment_counter!(METRIC_TRIE_NODES_ADDED, "table" => table);
+            indices.insert(idx, first_index + position as u64);
         }
 
+        backend.write_batch(table, first_index, &batch)?;
+
+        metrics::counter!(METRIC_TRIE_NODES_ADDED, batch.len() as u64, "table" => table.name());
+
         Ok(RootIndexUpdate::Updated(
             *indices
                 .get(&(update.nodes_added.len() - 1))
@@ -577,19 +583,8 @@ impl Transaction<'_> {
     }
 
     /// Returns the node with the given index.
-    fn trie_node(&self, index: u64, table: &'static str) -> anyhow::Result<Option<StoredNode>> {
-        // We rely on sqlite caching the statement here. Storing the statement would be
-        // nice, however that leads to &mut requirements or interior mutable
-        // work-arounds.
-        let mut stmt = self
-            .inner()
-            .prepare_cached(&format!("SELECT data FROM {table} WHERE idx = ?"))
-            .context("Creating get statement")?;
-
-        let Some(data): Option<Vec<u8>> = stmt
-            .query_row(params![&index], |row| row.get(0))
-            .optional()?
-        else {
+    fn trie_node(&self, index: u64, table: TrieTable) -> anyhow::Result<Option<StoredNode>> {
+        let Some(data) = self.trie_backend().node(table, index)? else {
             return Ok(None);
         };
 
@@ -599,18 +594,8 @@ impl Transaction<'_> {
     }
 
     /// Returns the hash of the node with the given index.
-    fn trie_node_hash(&self, index: u64, table: &'static str) -> anyhow::Result<Option<Felt>> {
-        // We rely on sqlite caching the statement here. Storing the statement would be
-        // nice, however that leads to &mut requirements or interior mutable
-        // work-arounds.
-        let mut stmt = self
-            .inner()
-            .prepare_cached(&format!("SELECT hash FROM {table} WHERE idx = ?"))
-            .context("Creating get statement")?;
-
-        stmt.query_row(params![&index], |row| row.get_felt(0))
-            .optional()
-            .map_err(Into::into)
+    fn trie_node_hash(&self, index: u64, table: TrieTable) -> anyhow::Result<Option<Felt>> {
+        self.trie_backend().node_hash(table, index)
     }
 }
 
@@ -797,6 +782,11 @@ mod tests {
 
     use super::*;
 
+    /// The index of the first node stored by the `n`th trie update of a table.
+    fn batch_node(n: i64) -> u64 {
+        crate::backend::first_batch_index(n)
+    }
+
     #[test]
     fn class_roots() {
         let mut db = crate::StorageBuilder::in_memory()
@@ -1135,7 +1125,7 @@ mod tests {
                     (felt!("4"), Node::LeafBinary),
                     (felt!("5"), Node::LeafBinary),
                 ],
-                nodes_removed: vec![1],
+                nodes_removed: vec![batch_node(1)],
                 root_commitment: Felt::ZERO,
             },
             BlockNumber::GENESIS + 1,
@@ -1181,7 +1171,7 @@ mod tests {
         .unwrap();
 
         // At this point, index 1 should still be in the table.
-        assert!(tx.class_trie_node(1).unwrap().is_some());
+        assert!(tx.class_trie_node(batch_node(1)).unwrap().is_some());
 
         tx.insert_class_trie(
             &TrieUpdate {
@@ -1204,7 +1194,7 @@ mod tests {
         .unwrap();
 
         // At this point, index 1 should no longer be in the table.
-        assert!(tx.class_trie_node(1).unwrap().is_none());
+        assert!(tx.class_trie_node(batch_node(1)).unwrap().is_none());
     }
 
     #[test]
@@ -1249,7 +1239,7 @@ mod tests {
                     (felt!("4"), Node::LeafBinary),
                     (felt!("5"), Node::LeafBinary),
                 ],
-                nodes_removed: vec![1],
+                nodes_removed: vec![batch_node(1)],
                 root_commitment: Felt::ZERO,
             },
             BlockNumber::GENESIS + 1,
@@ -1315,7 +1305,7 @@ mod tests {
         .unwrap();
 
         // Nothing was pruned.
-        assert!(tx.class_trie_node(1).unwrap().is_some());
+        assert!(tx.class_trie_node(batch_node(1)).unwrap().is_some());
 
         // Simulate a configuration change.
         tx.trie_prune_mode = TriePruneMode::Prune { num_blocks_kept: 2 };
@@ -1327,7 +1317,7 @@ mod tests {
         tx.prune_tries().unwrap();
 
         // The class trie was pruned.
-        assert!(tx.class_trie_node(1).unwrap().is_none());
+        assert!(tx.class_trie_node(batch_node(1)).unwrap().is_none());
     }
 
     #[test]
@@ -1398,10 +1388,10 @@ mod tests {
         )
         .unwrap();
 
-        // At this point, indices 1, 2, 3 should be in the table.
-        assert!(tx.class_trie_node(1).unwrap().is_some());
-        assert!(tx.class_trie_node(2).unwrap().is_some());
-        assert!(tx.class_trie_node(3).unwrap().is_some());
+        // At this point, the nodes of the first three batches should be in the table.
+        assert!(tx.class_trie_node(batch_node(1)).unwrap().is_some());
+        assert!(tx.class_trie_node(batch_node(2)).unwrap().is_some());
+        assert!(tx.class_trie_node(batch_node(3)).unwrap().is_some());
 
         tx.insert_class_trie(
             &TrieUpdate {
@@ -1416,7 +1406,7 @@ mod tests {
                     (felt!("4"), Node::LeafBinary),
                     (felt!("5"), Node::LeafBinary),
                 ],
-                nodes_removed: vec![1, 2, 3],
+                nodes_removed: vec![batch_node(1), batch_node(2), batch_node(3)],
                 root_commitment: Felt::ZERO,
             },
             BlockNumber::GENESIS + 1,
@@ -1442,10 +1432,11 @@ mod tests {
         )
         .unwrap();
 
-        // At this point, 1, 2, 3 should no longer be in the table.
-        assert!(tx.class_trie_node(1).unwrap().is_none());
-        assert!(tx.class_trie_node(2).unwrap().is_none());
-        assert!(tx.class_trie_node(3).unwrap().is_none());
+        // At this point, the nodes of the first three batches should no longer be in
+        // the table.
+        assert!(tx.class_trie_node(batch_node(1)).unwrap().is_none());
+        assert!(tx.class_trie_node(batch_node(2)).unwrap().is_none());
+        assert!(tx.class_trie_node(batch_node(3)).unwrap().is_none());
     }
 
     #[test]
@@ -1478,7 +1469,7 @@ mod tests {
                 BlockNumber::GENESIS,
             )
             .unwrap();
-        assert_eq!(root_update, RootIndexUpdate::Updated(1));
+        assert_eq!(root_update, RootIndexUpdate::Updated(batch_node(1)));
     }
 
     #[test]
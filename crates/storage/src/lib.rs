@@ -1,22 +1,29 @@
 //! Local storage.
 //!
-//! Currently this consists of a Sqlite backend implementation.
+//! Currently this consists of a Sqlite backend implementation. Trie nodes are
+//! accessed through the [TrieNodeBackend] trait and can be moved to a
+//! different key-value store using [StorageBuilder::trie_node_backend].
 
 // This is intended for internal use only -- do not make public.
 mod prelude;
 
+mod backend;
+pub use backend::{SharedTrieNodeBackend, TrieNodeBackend, TrieTable};
 mod bloom;
 use bloom::AggregateBloomCache;
 pub use bloom::AGGREGATE_BLOOM_BLOCK_RANGE_LEN;
 mod connection;
 pub mod fake;
 mod params;
+mod read_only;
+pub use read_only::{supported_schema_version, ReadOnlyStorage, Snapshot};
 mod schema;
 pub mod test_utils;
 
-use std::num::NonZeroU32;
+use std::num::{NonZeroU32, NonZeroUsize};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use anyhow::Context;
 pub use connection::*;
@@ -38,6 +45,26 @@ pub enum JournalMode {
     WAL,
 }
 
+/// Controls how the database schema is migrated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MigrationConfig {
+    /// Number of items, e.g. blocks, migrations of large amounts of data
+    /// commit at once.
+    pub batch_size: NonZeroUsize,
+    /// Free disk space on the database volume in bytes, if known. Migrations
+    /// are refused if they are projected to require more.
+    pub available_space: Option<u64>,
+}
+
+impl Default for MigrationConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: NonZeroUsize::new(1000).unwrap(),
+            available_space: None,
+        }
+    }
+}
+
 /// Identifies a specific starknet block stored in the database.
 ///
 /// Note that this excludes the `Pending` variant since we never store pending
@@ -47,6 +74,9 @@ pub enum BlockId {
     Latest,
     Number(BlockNumber),
     Hash(BlockHash),
+    /// The latest block whose state update was accepted on Ethereum, i.e. the
+    /// [L1-L2 pointer](Transaction::l1_l2_pointer).
+    L1Accepted,
 }
 
 impl From<BlockHash> for BlockId {
@@ -69,6 +99,7 @@ impl TryFrom<pathfinder_common::BlockId> for BlockId {
             pathfinder_common::BlockId::Number(x) => Ok(BlockId::Number(x)),
             pathfinder_common::BlockId::Hash(x) => Ok(BlockId::Hash(x)),
             pathfinder_common::BlockId::Latest => Ok(BlockId::Latest),
+            pathfinder_common::BlockId::L1Accepted => Ok(BlockId::L1Accepted),
             pathfinder_common::BlockId::Pending => {
                 Err("Pending is invalid within the storage context")
             }
@@ -95,6 +126,9 @@ struct Inner {
     event_filter_cache: Arc<AggregateBloomCache>,
     running_event_filter: Arc<Mutex<RunningEventFilter>>,
     trie_prune_mode: TriePruneMode,
+    trie_node_backend: Option<SharedTrieNodeBackend>,
+    /// Set for in-memory databases, see [StorageManager].
+    _keep_alive: Option<Arc<Mutex<rusqlite::Connection>>>,
 }
 
 pub struct StorageManager {
@@ -103,6 +137,11 @@ pub struct StorageManager {
     event_filter_cache: Arc<AggregateBloomCache>,
     running_event_filter: Arc<Mutex<RunningEventFilter>>,
     trie_prune_mode: TriePruneMode,
+    trie_node_backend: Option<SharedTrieNodeBackend>,
+    /// An in-memory database is dropped together with its last connection.
+    /// Since the pool may close idle connections, one is kept open for as
+    /// long as the manager or any [Storage] created from it exists.
+    keep_alive: Option<Arc<Mutex<rusqlite::Connection>>>,
 }
 
 impl std::fmt::Debug for StorageManager {
@@ -111,6 +150,11 @@ impl std::fmt::Debug for StorageManager {
             .field("database_path", &self.database_path)
             .field("journal_mode", &self.journal_mode)
             .field("trie_prune_mode", &self.trie_prune_mode)
+            .field(
+                "custom_trie_node_backend",
+                &self.trie_node_backend.is_some(),
+            )
+            .field("in_memory", &self.keep_alive.is_some())
             .finish()
     }
 }
@@ -135,6 +179,8 @@ impl StorageManager {
             event_filter_cache: self.event_filter_cache.clone(),
             running_event_filter: self.running_event_filter.clone(),
             trie_prune_mode: self.trie_prune_mode,
+            trie_node_backend: self.trie_node_backend.clone(),
+            _keep_alive: self.keep_alive.clone(),
         }))
     }
 
@@ -155,6 +201,9 @@ pub struct StorageBuilder {
     journal_mode: JournalMode,
     event_filter_cache_size: usize,
     trie_prune_mode: Option<TriePruneMode>,
+    trie_node_backend: Option<SharedTrieNodeBackend>,
+    migration_config: MigrationConfig,
+    in_memory: bool,
 }
 
 impl StorageBuilder {
@@ -164,6 +213,35 @@ impl StorageBuilder {
             journal_mode: JournalMode::WAL,
             event_filter_cache_size: 16,
             trie_prune_mode: None,
+            trie_node_backend: None,
+            migration_config: Default::default(),
+            in_memory: false,
+        }
+    }
+
+    /// A new database which only lives in memory and is dropped together with
+    /// the last [Storage] using it.
+    ///
+    /// Every call creates a separate database. Other than that it behaves like
+    /// a new [file](Self::file) database, including migrations and the trie
+    /// prune mode.
+    pub fn memory() -> Self {
+        // Create a unique database name so that they are not shared between
+        // concurrent users. i.e. Make every in-mem Storage unique.
+        static COUNT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        let count = COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        // &cache=shared allows other threads to see and access the inmemory database
+        let database_path = PathBuf::from(format!("file:memdb{count}?mode=memory&cache=shared"));
+
+        Self {
+            database_path,
+            // In-memory databases do not support WAL.
+            journal_mode: JournalMode::Rollback,
+            event_filter_cache_size: 16,
+            trie_prune_mode: None,
+            trie_node_backend: None,
+            migration_config: Default::default(),
+            in_memory: true,
         }
     }
 
@@ -182,6 +260,21 @@ impl StorageBuilder {
         self
     }
 
+    /// Stores trie nodes in `backend` instead of the sqlite database.
+    ///
+    /// Writes to the backend are not part of the sqlite transaction, and are
+    /// therefore not rolled back together with it. This is meant for
+    /// evaluating alternative key-value stores on archive nodes.
+    pub fn trie_node_backend(mut self, backend: SharedTrieNodeBackend) -> Self {
+        self.trie_node_backend = Some(backend);
+        self
+    }
+
+    pub fn migration_config(mut self, migration_config: MigrationConfig) -> Self {
+        self.migration_config = migration_config;
+        self
+    }
+
     /// Convenience function for tests to create an in-memory database.
     pub fn in_memory() -> anyhow::Result<Storage> {
         Self::in_memory_with_trie_pruning(TriePruneMode::Archive)
@@ -208,36 +301,10 @@ impl StorageBuilder {
         trie_prune_mode: TriePruneMode,
         pool_size: NonZeroU32,
     ) -> anyhow::Result<Storage> {
-        // Create a unique database name so that they are not shared between
-        // concurrent tests. i.e. Make every in-mem Storage unique.
-        static COUNT: std::sync::Mutex<u64> = std::sync::Mutex::new(0);
-        let unique_mem_db = {
-            let mut count = COUNT.lock().unwrap();
-            // &cache=shared allows other threads to see and access the inmemory database
-            let unique_mem_db = format!("file:memdb{count}?mode=memory&cache=shared");
-            *count += 1;
-            unique_mem_db
-        };
-
-        let database_path = PathBuf::from(unique_mem_db);
-        // This connection must be held until a pool has been created, since an
-        // in-memory database is dropped once all its connections are. This connection
-        // therefore holds the database in-place until the pool is established.
-        let conn = rusqlite::Connection::open(&database_path)?;
-
-        let mut storage = Self::file(database_path)
-            .journal_mode(JournalMode::Rollback)
-            .migrate()?;
-
-        if let TriePruneMode::Prune { .. } = trie_prune_mode {
-            conn.execute(
-                "INSERT INTO storage_flags (flag) VALUES ('prune_tries')",
-                [],
-            )?;
-        }
-
-        storage.trie_prune_mode = trie_prune_mode;
-        storage.create_pool(pool_size)
+        Self::memory()
+            .trie_prune_mode(Some(trie_prune_mode))
+            .migrate()?
+            .create_pool(pool_size)
     }
 
     /// A workaround for scenarios where a test requires multiple parallel
@@ -276,6 +343,14 @@ impl StorageBuilder {
     /// and passed to the various components which require access to the
     /// database.
     pub fn migrate(self) -> anyhow::Result<StorageManager> {
+        let keep_alive = if self.in_memory {
+            let connection = rusqlite::Connection::open(&self.database_path)
+                .context("Creating in-memory database")?;
+            Some(Arc::new(Mutex::new(connection)))
+        } else {
+            None
+        };
+
         let mut open_flags = OpenFlags::default();
         open_flags.remove(OpenFlags::SQLITE_OPEN_CREATE);
         let (mut connection, is_new_database) =
@@ -291,6 +366,8 @@ impl StorageBuilder {
                     |c| Ok((c, false)),
                 )
                 .context("Opening DB for migration")?;
+        // The database has only just been created by the keep-alive connection.
+        let is_new_database = is_new_database || self.in_memory;
 
         // Migration is done with rollback journal mode. Otherwise dropped tables
         // get copied into the WAL which is prohibitively expensive for large
@@ -300,7 +377,7 @@ impl StorageBuilder {
         setup_connection(&mut connection, JournalMode::Rollback)
             .context("Setting up database connection")?;
 
-        migrate_database(&mut connection).context("Migrate database")?;
+        migrate_database(&mut connection, &self.migration_config).context("Migrate database")?;
 
         // Set the journal mode to the desired value.
         setup_journal_mode(&mut connection, self.journal_mode).context("Setting journal mode")?;
@@ -329,6 +406,8 @@ impl StorageBuilder {
             )),
             running_event_filter: Arc::new(Mutex::new(running_event_filter)),
             trie_prune_mode,
+            trie_node_backend: self.trie_node_backend,
+            keep_alive,
         })
     }
 
@@ -393,6 +472,13 @@ impl StorageBuilder {
 }
 
 impl Storage {
+    /// Creates a new in-memory database which keeps the tries of all blocks.
+    ///
+    /// Use [StorageBuilder::memory] for other configurations.
+    pub fn in_memory() -> anyhow::Result<Self> {
+        StorageBuilder::in_memory()
+    }
+
     /// Returns a new Sqlite [Connection] to the database.
     pub fn connection(&self) -> anyhow::Result<Connection> {
         let conn = self.0.pool.get()?;
@@ -401,6 +487,7 @@ impl Storage {
             self.0.event_filter_cache.clone(),
             self.0.running_event_filter.clone(),
             self.0.trie_prune_mode,
+            self.0.trie_node_backend.clone(),
         ))
     }
 
@@ -462,7 +549,10 @@ fn setup_connection(
 
 /// Migrates the database to the latest version. This __MUST__ be called
 /// at the beginning of the application.
-fn migrate_database(connection: &mut rusqlite::Connection) -> anyhow::Result<()> {
+fn migrate_database(
+    connection: &mut rusqlite::Connection,
+    config: &MigrationConfig,
+) -> anyhow::Result<()> {
     let mut current_revision = schema_version(connection)?;
     let migrations = schema::migrations();
 
@@ -514,38 +604,127 @@ fn migrate_database(connection: &mut rusqlite::Connection) -> anyhow::Result<()>
     let amount = latest_revision - current_revision;
     tracing::info!(%current_revision, %latest_revision, migrations=%amount, "Performing database migrations");
 
+    let pending = &migrations[migrations.len() - amount..];
+    ensure_migration_headroom(connection, pending, config.available_space)?;
+
     // Sequentially apply each missing migration.
-    migrations
-        .iter()
-        .rev()
-        .take(amount)
-        .rev()
-        .try_for_each(|migration| {
-            let mut do_migration = || -> anyhow::Result<()> {
-                current_revision += 1;
-                let span = tracing::info_span!("db_migration", revision = current_revision);
-                let _enter = span.enter();
-
-                let transaction = connection
-                    .transaction()
-                    .context("Create database transaction")?;
-                migration(&transaction)?;
-                transaction
-                    .pragma_update(None, VERSION_KEY, current_revision)
-                    .context("Failed to update the schema version number")?;
-                transaction
-                    .commit()
-                    .context("Commit migration transaction")?;
-
-                Ok(())
-            };
+    pending.iter().try_for_each(|migration| {
+        current_revision += 1;
+        let span = tracing::info_span!("db_migration", revision = current_revision);
+        let _enter = span.enter();
+
+        match migration {
+            schema::Migration::Single(migration) => {
+                apply_migration(connection, *migration, current_revision)
+            }
+            schema::Migration::Batched(migration) => {
+                apply_batched_migration(connection, migration, current_revision, config.batch_size)
+            }
+        }
+        .with_context(|| format!("Migrating to {current_revision}"))
+    })?;
 
-            do_migration().with_context(|| format!("Migrating to {current_revision}"))
-        })?;
+    Ok(())
+}
+
+/// Fails if the pending migrations are projected to require more disk space
+/// than is available. Only batched migrations, which migrate large amounts of
+/// data, estimate their requirements.
+fn ensure_migration_headroom(
+    connection: &rusqlite::Connection,
+    pending: &[schema::Migration],
+    available_space: Option<u64>,
+) -> anyhow::Result<()> {
+    const MIB: u64 = 1024 * 1024;
+
+    let mut required = 0;
+    for migration in pending {
+        let schema::Migration::Batched(migration) = migration else {
+            continue;
+        };
+        // Estimates fail if they rely on tables which pending migrations have yet to
+        // create.
+        match (migration.headroom)(connection) {
+            Ok(headroom) => required += headroom,
+            Err(error) => tracing::debug!(%error, "Failed to estimate migration disk space"),
+        }
+    }
+    if required == 0 {
+        return Ok(());
+    }
+
+    tracing::info!(
+        required_mib = required / MIB,
+        available_mib = available_space.map(|available| available / MIB),
+        "Database migrations require additional disk space"
+    );
+    if let Some(available) = available_space {
+        anyhow::ensure!(
+            required <= available,
+            "Database migrations require about {} MiB of disk space but only {} MiB are available",
+            required / MIB,
+            available / MIB
+        );
+    }
 
     Ok(())
 }
 
+fn apply_migration(
+    connection: &mut rusqlite::Connection,
+    migration: schema::MigrationFn,
+    revision: usize,
+) -> anyhow::Result<()> {
+    let transaction = connection
+        .transaction()
+        .context("Create database transaction")?;
+    migration(&transaction)?;
+    transaction
+        .pragma_update(None, VERSION_KEY, revision)
+        .context("Failed to update the schema version number")?;
+    transaction.commit().context("Commit migration transaction")
+}
+
+/// Applies a migration batch by batch, bumping the schema version together with
+/// the last batch.
+fn apply_batched_migration(
+    connection: &mut rusqlite::Connection,
+    migration: &schema::BatchedMigration,
+    revision: usize,
+    batch_size: NonZeroUsize,
+) -> anyhow::Result<()> {
+    let mut position = None;
+    let mut last_progress_report = Instant::now();
+    loop {
+        let transaction = connection
+            .transaction()
+            .context("Create database transaction")?;
+        position = (migration.batch)(&transaction, position, batch_size)?;
+        if position.is_none() {
+            transaction
+                .pragma_update(None, VERSION_KEY, revision)
+                .context("Failed to update the schema version number")?;
+        }
+        transaction
+            .commit()
+            .context("Commit migration transaction")?;
+
+        // Migrations run with a rollback journal, but should the database be in WAL
+        // mode this keeps the WAL from growing with every batch.
+        connection
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+            .context("Checkpointing WAL")?;
+
+        let Some(position) = position else {
+            return Ok(());
+        };
+        if last_progress_report.elapsed().as_secs() >= 10 {
+            tracing::info!(%position, "Migration in progress");
+            last_progress_report = Instant::now();
+        }
+    }
+}
+
 /// Returns the current schema version of the existing database,
 /// or `0` if database does not yet exist.
 fn schema_version(connection: &rusqlite::Connection) -> anyhow::Result<usize> {
@@ -586,7 +765,7 @@ mod tests {
     fn full_migration() {
         let mut conn = rusqlite::Connection::open_in_memory().unwrap();
         setup_connection(&mut conn, JournalMode::Rollback).unwrap();
-        migrate_database(&mut conn).unwrap();
+        migrate_database(&mut conn, &Default::default()).unwrap();
         let version = schema_version(&conn).unwrap();
         let expected = schema::migrations().len() + schema::BASE_SCHEMA_REVISION;
         assert_eq!(version, expected);
@@ -603,7 +782,82 @@ mod tests {
             .unwrap();
 
         // Migration should fail.
-        migrate_database(&mut conn).unwrap_err();
+        migrate_database(&mut conn, &Default::default()).unwrap_err();
+    }
+
+    /// Copies the numbers 0..10 from `source` to `target`.
+    const BATCHED_MIGRATION: schema::BatchedMigration = schema::BatchedMigration {
+        batch: |tx, after, batch_size| {
+            let after = match after {
+                Some(after) => after as i64,
+                None => tx.query_row("SELECT COALESCE(MAX(n), -1) FROM target", [], |row| {
+                    row.get(0)
+                })?,
+            };
+            let copied = tx.execute(
+                "INSERT INTO target SELECT n FROM source WHERE n > ? ORDER BY n LIMIT ?",
+                rusqlite::params![after, batch_size.get()],
+            )?;
+            if copied < batch_size.get() {
+                return Ok(None);
+            }
+            Ok(Some(tx.query_row(
+                "SELECT MAX(n) FROM target",
+                [],
+                |row| row.get(0),
+            )?))
+        },
+        headroom: |_| Ok(10 * 1024 * 1024),
+    };
+
+    fn batched_migration_database() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            r"
+            CREATE TABLE source (n INTEGER PRIMARY KEY);
+            CREATE TABLE target (n INTEGER PRIMARY KEY);
+            WITH RECURSIVE numbers(n) AS (SELECT 0 UNION ALL SELECT n + 1 FROM numbers WHERE n < 9)
+            INSERT INTO source SELECT n FROM numbers;
+            ",
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn batched_migration_is_resumed() {
+        let mut conn = batched_migration_database();
+        // A previous run was stopped after copying the first batch.
+        conn.execute_batch("INSERT INTO target VALUES (0), (1), (2)")
+            .unwrap();
+
+        apply_batched_migration(
+            &mut conn,
+            &BATCHED_MIGRATION,
+            1,
+            NonZeroUsize::new(3).unwrap(),
+        )
+        .unwrap();
+
+        let copied: Vec<i64> = conn
+            .prepare("SELECT n FROM target ORDER BY n")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(copied, (0..10).collect::<Vec<_>>());
+        assert_eq!(schema_version(&conn).unwrap(), 1);
+    }
+
+    #[test]
+    fn migrations_are_refused_without_headroom() {
+        let conn = batched_migration_database();
+        let pending = [schema::Migration::Batched(BATCHED_MIGRATION)];
+
+        ensure_migration_headroom(&conn, &pending, None).unwrap();
+        ensure_migration_headroom(&conn, &pending, Some(10 * 1024 * 1024)).unwrap();
+        ensure_migration_headroom(&conn, &pending, Some(1024 * 1024)).unwrap_err();
     }
 
     #[test]
@@ -686,6 +940,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn in_memory_database_outlives_its_pools() {
+        let manager = StorageBuilder::memory()
+            .trie_prune_mode(Some(TriePruneMode::Prune { num_blocks_kept: 5 }))
+            .migrate()
+            .unwrap();
+        assert_matches::assert_matches!(
+            manager.trie_prune_mode,
+            TriePruneMode::Prune { num_blocks_kept: 5 }
+        );
+
+        let storage = manager.create_pool(NonZeroU32::new(1).unwrap()).unwrap();
+        let mut conn = storage.connection().unwrap();
+        let tx = conn.transaction().unwrap();
+        tx.insert_block_header(&pathfinder_common::BlockHeader::default())
+            .unwrap();
+        tx.commit().unwrap();
+        // Closes all connections of the pool.
+        drop(conn);
+        drop(storage);
+
+        let storage = manager.create_pool(NonZeroU32::new(1).unwrap()).unwrap();
+        let mut conn = storage.connection().unwrap();
+        let tx = conn.transaction().unwrap();
+        assert!(tx.block_exists(BlockNumber::GENESIS.into()).unwrap());
+        assert!(tx.trie_pruning_enabled());
+
+        // The pruning flag is stored like for a file database.
+        StorageBuilder::file(manager.database_path.clone())
+            .journal_mode(JournalMode::Rollback)
+            .trie_prune_mode(Some(TriePruneMode::Archive))
+            .migrate()
+            .unwrap_err();
+    }
+
     #[test]
     fn running_event_filter_rebuilt_after_shutdown() {
         let n_blocks = 6;
@@ -878,6 +1167,7 @@ mod tests {
             keys: vec![],
             page_size: 1024,
             offset: 0,
+            order: EventOrder::Ascending,
         };
 
         let events = tx
@@ -211,6 +211,13 @@ pub trait RowExt {
         Ok(self.get_optional_felt(index)?.map(ClassCommitment))
     }
 
+    fn get_optional_storage_commitment<Index: RowIndex>(
+        &self,
+        index: Index,
+    ) -> rusqlite::Result<Option<StorageCommitment>> {
+        Ok(self.get_optional_felt(index)?.map(StorageCommitment))
+    }
+
     fn get_block_number<Index: RowIndex>(&self, index: Index) -> rusqlite::Result<BlockNumber> {
         let num = self.get_i64(index)?;
         // Always safe since we are fetching an i64
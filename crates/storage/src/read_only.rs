@@ -0,0 +1,303 @@
+//! Read-only access to a pathfinder database for external tools.
+//!
+//! [ReadOnlyStorage] opens an existing database without migrating it or
+//! requiring any of the node's configuration, and [Snapshot] exposes the
+//! queries tools commonly need: block headers, transactions and receipts,
+//! events, class definitions, contract state and Merkle trie nodes.
+//!
+//! Unlike the rest of this crate, whose API follows the needs of the node,
+//! the methods in this module are kept stable. Additions are backwards
+//! compatible, while changes to existing methods are called out in the
+//! changelog. A database can be read while a node is syncing it.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use pathfinder_storage::{BlockId, ReadOnlyStorage};
+//!
+//! let storage = ReadOnlyStorage::open("mainnet.sqlite")?;
+//! let header = storage.read(|snapshot| snapshot.block_header(BlockId::Latest))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::num::{NonZeroU32, NonZeroUsize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use pathfinder_common::event::Event;
+use pathfinder_common::receipt::Receipt;
+use pathfinder_common::transaction::Transaction as StarknetTransaction;
+use pathfinder_common::{
+    BlockHash,
+    BlockHeader,
+    BlockNumber,
+    ClassHash,
+    ContractAddress,
+    ContractNonce,
+    StateUpdate,
+    StorageAddress,
+    StorageValue,
+    TransactionHash,
+};
+use pathfinder_crypto::Felt;
+use rusqlite::OpenFlags;
+
+use crate::bloom::AggregateBloomCache;
+use crate::{
+    BlockId,
+    EventConstraints,
+    EventFilterError,
+    JournalMode,
+    PageOfEvents,
+    Storage,
+    StorageManager,
+    StoredNode,
+    Transaction,
+    TrieKind,
+    TriePruneMode,
+};
+
+/// The schema version of the databases this version of the crate can read.
+pub fn supported_schema_version() -> usize {
+    crate::schema::BASE_SCHEMA_REVISION + crate::schema::migrations().len()
+}
+
+/// A read-only handle to an existing database. Cheap to clone.
+#[derive(Clone)]
+pub struct ReadOnlyStorage(Storage);
+
+impl ReadOnlyStorage {
+    /// Opens the database at `database_path` with a pool of up to 8
+    /// connections.
+    pub fn open(database_path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        Self::open_with_pool_size(database_path, NonZeroU32::new(8).unwrap())
+    }
+
+    /// Opens the database at `database_path`. Fails if the database does not
+    /// exist or its schema version differs from the
+    /// [supported one](supported_schema_version).
+    pub fn open_with_pool_size(
+        database_path: impl Into<PathBuf>,
+        pool_size: NonZeroU32,
+    ) -> anyhow::Result<Self> {
+        let database_path = database_path.into();
+        let mut connection = rusqlite::Connection::open_with_flags(
+            &database_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI,
+        )
+        .with_context(|| format!("Opening database at {}", database_path.display()))?;
+
+        let version = crate::schema_version(&connection)?;
+        let supported = supported_schema_version();
+        anyhow::ensure!(
+            version == supported,
+            "Database schema version {version} is not supported, expected {supported}"
+        );
+
+        let running_event_filter =
+            crate::event::rebuild_running_event_filter(&connection.transaction()?)
+                .context("Rebuilding running event filter")?;
+
+        let manager = StorageManager {
+            database_path,
+            // Only used to set up connections, the journal mode of the database itself is
+            // left as is.
+            journal_mode: JournalMode::WAL,
+            event_filter_cache: Arc::new(AggregateBloomCache::with_size(16)),
+            running_event_filter: Arc::new(Mutex::new(running_event_filter)),
+            // Only relevant for writes.
+            trie_prune_mode: TriePruneMode::Archive,
+            trie_node_backend: None,
+            keep_alive: None,
+        };
+
+        Ok(Self(manager.create_read_only_pool(pool_size)?))
+    }
+
+    /// Runs `f` on a consistent snapshot of the database.
+    ///
+    /// Blocks while waiting for a free connection, so call this from a
+    /// blocking context when used within an async runtime.
+    pub fn read<T>(&self, f: impl FnOnce(&Snapshot<'_>) -> anyhow::Result<T>) -> anyhow::Result<T> {
+        let mut connection = self.0.connection()?;
+        let transaction = connection
+            .transaction()
+            .context("Creating database transaction")?;
+
+        f(&Snapshot(transaction))
+    }
+}
+
+/// A consistent view of the database, see [ReadOnlyStorage::read].
+pub struct Snapshot<'a>(Transaction<'a>);
+
+impl Snapshot<'_> {
+    pub fn block_id(&self, block: BlockId) -> anyhow::Result<Option<(BlockNumber, BlockHash)>> {
+        self.0.block_id(block)
+    }
+
+    pub fn block_header(&self, block: BlockId) -> anyhow::Result<Option<BlockHeader>> {
+        self.0.block_header(block)
+    }
+
+    pub fn state_update(&self, block: BlockId) -> anyhow::Result<Option<StateUpdate>> {
+        self.0.state_update(block)
+    }
+
+    /// The transactions of a block along with their receipts and events.
+    pub fn transactions(
+        &self,
+        block: BlockId,
+    ) -> anyhow::Result<Option<Vec<(StarknetTransaction, Receipt, Vec<Event>)>>> {
+        self.0.transaction_data_for_block(block)
+    }
+
+    /// A transaction along with its receipt, events and the number of the
+    /// block it was included in.
+    pub fn transaction(
+        &self,
+        hash: TransactionHash,
+    ) -> anyhow::Result<Option<(StarknetTransaction, Receipt, Vec<Event>, BlockNumber)>> {
+        self.0.transaction_with_receipt(hash)
+    }
+
+    /// Events matching `constraints`, scanning at most `max_blocks_to_scan`
+    /// blocks before returning a continuation token.
+    ///
+    /// Events of blocks synced after the storage was opened may be missed for
+    /// lack of an up to date event filter.
+    pub fn events(
+        &self,
+        constraints: &EventConstraints,
+        max_blocks_to_scan: NonZeroUsize,
+    ) -> Result<PageOfEvents, EventFilterError> {
+        self.0
+            .events(constraints, max_blocks_to_scan, NonZeroUsize::MAX)
+    }
+
+    /// The serialized definition of a class.
+    pub fn class_definition(&self, class_hash: ClassHash) -> anyhow::Result<Option<Vec<u8>>> {
+        self.0.class_definition(class_hash)
+    }
+
+    /// The serialized CASM of a Sierra class.
+    pub fn casm_definition(&self, class_hash: ClassHash) -> anyhow::Result<Option<Vec<u8>>> {
+        self.0.casm_definition(class_hash)
+    }
+
+    pub fn storage_value(
+        &self,
+        block: BlockId,
+        contract_address: ContractAddress,
+        key: StorageAddress,
+    ) -> anyhow::Result<Option<StorageValue>> {
+        self.0.storage_value(block, contract_address, key)
+    }
+
+    pub fn contract_nonce(
+        &self,
+        block: BlockId,
+        contract_address: ContractAddress,
+    ) -> anyhow::Result<Option<ContractNonce>> {
+        self.0.contract_nonce(contract_address, block)
+    }
+
+    pub fn contract_class_hash(
+        &self,
+        block: BlockId,
+        contract_address: ContractAddress,
+    ) -> anyhow::Result<Option<ClassHash>> {
+        self.0.contract_class_hash(block, contract_address)
+    }
+
+    /// The index of the root node of a trie at the given block, [None] if the
+    /// trie is empty or was pruned.
+    pub fn trie_root_index(
+        &self,
+        kind: TrieKind,
+        block: BlockNumber,
+    ) -> anyhow::Result<Option<u64>> {
+        match kind {
+            TrieKind::Class => self.0.class_root_index(block),
+            TrieKind::Storage => self.0.storage_root_index(block),
+            TrieKind::Contract(address) => self.0.contract_root_index(block, address),
+        }
+    }
+
+    pub fn trie_node(&self, kind: TrieKind, index: u64) -> anyhow::Result<Option<StoredNode>> {
+        match kind {
+            TrieKind::Class => self.0.class_trie_node(index),
+            TrieKind::Storage => self.0.storage_trie_node(index),
+            TrieKind::Contract(_) => self.0.contract_trie_node(index),
+        }
+    }
+
+    pub fn trie_node_hash(&self, kind: TrieKind, index: u64) -> anyhow::Result<Option<Felt>> {
+        match kind {
+            TrieKind::Class => self.0.class_trie_node_hash(index),
+            TrieKind::Storage => self.0.storage_trie_node_hash(index),
+            TrieKind::Contract(_) => self.0.contract_trie_node_hash(index),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pathfinder_common::macro_prelude::*;
+
+    use super::*;
+    use crate::StorageBuilder;
+
+    #[test]
+    fn reads_existing_database() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("db.sqlite");
+        let storage = StorageBuilder::file(path.clone())
+            .migrate()
+            .unwrap()
+            .create_pool(NonZeroU32::new(1).unwrap())
+            .unwrap();
+
+        let header = BlockHeader::builder().finalize_with_hash(block_hash!("0xabc"));
+        {
+            let mut db = storage.connection().unwrap();
+            let tx = db.transaction().unwrap();
+            tx.insert_block_header(&header).unwrap();
+            tx.commit().unwrap();
+        }
+
+        let read_only = ReadOnlyStorage::open(&path).unwrap();
+        let latest = read_only
+            .read(|snapshot| snapshot.block_header(BlockId::Latest))
+            .unwrap();
+        assert_eq!(latest, Some(header));
+
+        // Writes are rejected by the connection.
+        let next = BlockHeader::child_builder(&header).finalize_with_hash(block_hash!("0xdef"));
+        let result = read_only.read(|snapshot| snapshot.0.insert_block_header(&next));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rejects_unsupported_schema_version() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("db.sqlite");
+        StorageBuilder::file(path.clone()).migrate().unwrap();
+
+        rusqlite::Connection::open(&path)
+            .unwrap()
+            .pragma_update(None, "user_version", supported_schema_version() + 1)
+            .unwrap();
+
+        let error = ReadOnlyStorage::open(&path).err().unwrap();
+        assert!(error.to_string().contains("not supported"), "{error}");
+    }
+
+    #[test]
+    fn missing_database() {
+        let dir = tempfile::TempDir::new().unwrap();
+
+        assert!(ReadOnlyStorage::open(dir.path().join("db.sqlite")).is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use std::num::NonZeroUsize;
+
 mod base;
 
 mod revision_0041;
@@ -27,41 +29,83 @@ mod revision_0064;
 mod revision_0065;
 mod revision_0066;
 mod revision_0067;
+mod revision_0068;
+mod revision_0069;
+mod revision_0070;
+mod revision_0071;
+mod revision_0072;
+mod revision_0073;
+mod revision_0074;
+mod revision_0075;
+mod revision_0076;
 
 pub(crate) use base::base_schema;
 
-type MigrationFn = fn(&rusqlite::Transaction<'_>) -> anyhow::Result<()>;
+pub(crate) type MigrationFn = fn(&rusqlite::Transaction<'_>) -> anyhow::Result<()>;
+
+/// Applies a batch of a [BatchedMigration], continuing after the given
+/// position, and returns the position of the last item migrated or [None] once
+/// the migration is complete.
+type BatchFn =
+    fn(&rusqlite::Transaction<'_>, Option<u64>, NonZeroUsize) -> anyhow::Result<Option<u64>>;
+
+pub enum Migration {
+    /// Applied in a single transaction.
+    Single(MigrationFn),
+    Batched(BatchedMigration),
+}
+
+/// A migration of a large amount of data, which is applied in batches of
+/// bounded size each committed in its own transaction. This keeps the journal
+/// small and preserves progress if the node is stopped midway.
+pub struct BatchedMigration {
+    /// Called with [None] as position for the first batch of each run, in
+    /// which case the migration has to determine where to continue from the
+    /// database.
+    pub batch: BatchFn,
+    /// Estimates the additional disk space the migration requires in bytes.
+    pub headroom: fn(&rusqlite::Connection) -> anyhow::Result<u64>,
+}
 
 /// The full list of pathfinder migrations.
-pub fn migrations() -> &'static [MigrationFn] {
+pub fn migrations() -> &'static [Migration] {
     &[
-        revision_0041::migrate,
-        revision_0042::migrate,
-        revision_0043::migrate,
-        revision_0044::migrate,
-        revision_0045::migrate,
-        revision_0046::migrate,
-        revision_0047::migrate,
-        revision_0048::migrate,
-        revision_0049::migrate,
-        revision_0050::migrate,
-        revision_0051::migrate,
-        revision_0052::migrate,
-        revision_0053::migrate,
-        revision_0054::migrate,
-        revision_0055::migrate,
-        revision_0056::migrate,
-        revision_0057::migrate,
-        revision_0058::migrate,
-        revision_0059::migrate,
-        revision_0060::migrate,
-        revision_0061::migrate,
-        revision_0062::migrate,
-        revision_0063::migrate,
-        revision_0064::migrate,
-        revision_0065::migrate,
-        revision_0066::migrate,
-        revision_0067::migrate,
+        Migration::Single(revision_0041::migrate),
+        Migration::Single(revision_0042::migrate),
+        Migration::Single(revision_0043::migrate),
+        Migration::Single(revision_0044::migrate),
+        Migration::Single(revision_0045::migrate),
+        Migration::Single(revision_0046::migrate),
+        Migration::Single(revision_0047::migrate),
+        Migration::Single(revision_0048::migrate),
+        Migration::Single(revision_0049::migrate),
+        Migration::Single(revision_0050::migrate),
+        Migration::Single(revision_0051::migrate),
+        Migration::Single(revision_0052::migrate),
+        Migration::Single(revision_0053::migrate),
+        Migration::Single(revision_0054::migrate),
+        Migration::Single(revision_0055::migrate),
+        Migration::Single(revision_0056::migrate),
+        Migration::Single(revision_0057::migrate),
+        Migration::Single(revision_0058::migrate),
+        Migration::Single(revision_0059::migrate),
+        Migration::Single(revision_0060::migrate),
+        Migration::Single(revision_0061::migrate),
+        Migration::Single(revision_0062::migrate),
+        Migration::Single(revision_0063::migrate),
+        Migration::Single(revision_0064::migrate),
+        Migration::Single(revision_0065::migrate),
+        Migration::Single(revision_0066::migrate),
+        Migration::Single(revision_0067::migrate),
+        Migration::Single(revision_0068::migrate),
+        Migration::Single(revision_0069::migrate),
+        Migration::Single(revision_0070::migrate),
+        Migration::Single(revision_0071::migrate),
+        Migration::Single(revision_0072::migrate),
+        Migration::Batched(revision_0073::MIGRATION),
+        Migration::Single(revision_0074::migrate),
+        Migration::Single(revision_0075::migrate),
+        Migration::Single(revision_0076::migrate),
     ]
 }
 
@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+use std::time::Instant;
+
+use anyhow::Context;
+use pathfinder_common::EventKey;
+use pathfinder_crypto::Felt;
+
+use crate::connection::transaction::{compression, dto};
+use crate::params::{params, RowExt};
+
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tracing::info!("Creating event_selectors table and indexing event selectors");
+
+    tx.execute(
+        r"
+        CREATE TABLE event_selectors (
+            selector     BLOB NOT NULL,
+            block_number INTEGER NOT NULL,
+            PRIMARY KEY (selector, block_number)
+        ) WITHOUT ROWID
+        ",
+        [],
+    )
+    .context("Creating event_selectors table")?;
+
+    index_event_selectors(tx).context("Indexing event selectors")
+}
+
+/// Inserts the distinct selectors, i.e. the first keys, of each block's events
+/// into the `event_selectors` table.
+fn index_event_selectors(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    let block_count = tx
+        .query_row(
+            "SELECT COUNT(*) FROM transactions WHERE events IS NOT NULL",
+            [],
+            |row| row.get::<_, u64>(0),
+        )
+        .context("Counting blocks with events")?;
+
+    if block_count == 0 {
+        return Ok(());
+    }
+
+    let mut fetch_events_stmt = tx.prepare(
+        "SELECT block_number, events FROM transactions WHERE events IS NOT NULL ORDER BY \
+         block_number",
+    )?;
+    let mut insert_selector_stmt = tx.prepare_cached(
+        r"
+        INSERT INTO event_selectors (selector, block_number)
+        VALUES (?, ?)
+        ",
+    )?;
+
+    let mut rows = fetch_events_stmt.query([]).context("Querying events")?;
+
+    let mut indexed_count: u64 = 0;
+    let mut last_progress_report = Instant::now();
+
+    tracing::info!("Indexing event selectors: 0.00% (0/{})", block_count);
+    while let Some(row) = rows.next()? {
+        let block_number = row.get_block_number(0)?;
+        let events = row.get_blob(1)?;
+        let events = compression::decompress_events(events).context("Decompressing events")?;
+        let events: dto::EventsForBlock =
+            bincode::serde::decode_from_slice(&events, bincode::config::standard())
+                .context("Deserializing events")?
+                .0;
+
+        let selectors = events
+            .events()
+            .into_iter()
+            .flatten()
+            .filter_map(|event| event.keys.into_iter().next())
+            .map(|selector| EventKey(Felt::from(selector)))
+            .collect::<HashSet<_>>();
+        for selector in selectors {
+            insert_selector_stmt
+                .execute(params![&selector, &block_number])
+                .context("Inserting event selector")?;
+        }
+
+        indexed_count += 1;
+
+        if last_progress_report.elapsed().as_secs() >= 10 {
+            tracing::info!(
+                "Indexing event selectors: {:.2}% ({}/{})",
+                indexed_count as f64 / block_count as f64 * 100.0,
+                indexed_count,
+                block_count
+            );
+            last_progress_report = Instant::now();
+        }
+    }
+    tracing::info!(
+        "Indexing event selectors: 100.00% ({count}/{count})",
+        count = block_count,
+    );
+
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use anyhow::Context;
+
+/// Adds the `class_stats` table.
+///
+/// It is only filled for blocks synced with class statistics enabled, existing
+/// blocks are not backfilled.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"
+        CREATE TABLE class_stats (
+            block_number INTEGER NOT NULL,
+            class_hash   BLOB NOT NULL,
+            invocations  INTEGER NOT NULL,
+            l1_gas       INTEGER NOT NULL,
+            l1_data_gas  INTEGER NOT NULL,
+            l2_gas       INTEGER NOT NULL,
+            PRIMARY KEY (block_number, class_hash)
+        ) WITHOUT ROWID
+        ",
+        [],
+    )
+    .context("Creating class_stats table")?;
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use anyhow::Context;
+
+/// Adds the `compiled_class_cache` table.
+///
+/// It holds class definitions prepared for execution so that they are cheaper
+/// to load than the originals after a restart.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"
+        CREATE TABLE compiled_class_cache (
+            class_hash     BLOB NOT NULL,
+            version        TEXT NOT NULL,
+            sierra_version TEXT,
+            definition     BLOB NOT NULL,
+            PRIMARY KEY (class_hash, version)
+        ) WITHOUT ROWID
+        ",
+        [],
+    )
+    .context("Creating compiled_class_cache table")?;
+
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use anyhow::Context;
+
+/// Adds the `gateway_outbox` table.
+///
+/// It holds transactions which could not be forwarded to the gateway because
+/// of a transient failure, until they are resubmitted successfully.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"
+        CREATE TABLE gateway_outbox (
+            id               INTEGER PRIMARY KEY,
+            transaction_hash BLOB NOT NULL,
+            transaction_json BLOB NOT NULL,
+            token            TEXT,
+            attempts         INTEGER NOT NULL,
+            created_at       INTEGER NOT NULL,
+            next_attempt_at  INTEGER NOT NULL,
+            last_error       TEXT NOT NULL
+        )
+        ",
+        [],
+    )
+    .context("Creating gateway_outbox table")?;
+
+    Ok(())
+}
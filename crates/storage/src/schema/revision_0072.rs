@@ -0,0 +1,31 @@
+use anyhow::Context;
+
+/// Adds the `event_retention` and `event_retention_allowlist` tables.
+///
+/// They record up to which block events have been pruned and which contracts'
+/// events were retained in full.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"
+        CREATE TABLE event_retention (
+            id            INTEGER PRIMARY KEY CHECK (id = 0),
+            pruned_before INTEGER NOT NULL
+        )
+        ",
+        [],
+    )
+    .context("Creating event_retention table")?;
+
+    tx.execute(
+        r"
+        CREATE TABLE event_retention_allowlist (
+            contract_address BLOB PRIMARY KEY,
+            retained_from    INTEGER NOT NULL
+        )
+        ",
+        [],
+    )
+    .context("Creating event_retention_allowlist table")?;
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use std::num::NonZeroUsize;
+
+use anyhow::Context;
+use pathfinder_common::transaction::Transaction as StarknetTransaction;
+
+use crate::connection::transaction::{compression, dto};
+use crate::params::{params, RowExt};
+use crate::schema::BatchedMigration;
+
+/// Creates the `transaction_senders` table and inserts the sender of each
+/// stored transaction, a batch of blocks at a time.
+pub(crate) const MIGRATION: BatchedMigration = BatchedMigration {
+    batch: index_transaction_senders,
+    headroom,
+};
+
+/// Estimated size of a `transaction_senders` row, including b-tree overhead.
+const BYTES_PER_SENDER: u64 = 64;
+
+fn headroom(connection: &rusqlite::Connection) -> anyhow::Result<u64> {
+    let transaction_count = connection
+        .query_row("SELECT COUNT(*) FROM transaction_hashes", [], |row| {
+            row.get::<_, u64>(0)
+        })
+        .context("Counting transactions")?;
+
+    Ok(transaction_count * BYTES_PER_SENDER)
+}
+
+fn index_transaction_senders(
+    tx: &rusqlite::Transaction<'_>,
+    after: Option<u64>,
+    batch_size: NonZeroUsize,
+) -> anyhow::Result<Option<u64>> {
+    tx.execute(
+        r"
+        CREATE TABLE IF NOT EXISTS transaction_senders (
+            sender       BLOB NOT NULL,
+            block_number INTEGER NOT NULL REFERENCES block_headers(number) ON DELETE CASCADE,
+            idx          INTEGER NOT NULL,
+            PRIMARY KEY (sender, block_number, idx)
+        ) WITHOUT ROWID
+        ",
+        [],
+    )
+    .context("Creating transaction_senders table")?;
+
+    // Batches consist of whole blocks, so a previous run left off after the last
+    // block with an indexed sender.
+    let after = match after {
+        Some(after) => Some(after),
+        None => {
+            tracing::info!("Indexing transaction senders");
+            tx.query_row(
+                "SELECT MAX(block_number) FROM transaction_senders",
+                [],
+                |row| row.get::<_, Option<u64>>(0),
+            )
+            .context("Querying last indexed block")?
+        }
+    };
+
+    let mut fetch_transactions_stmt = tx.prepare_cached(
+        r"
+        SELECT block_number, transactions FROM transactions
+        WHERE block_number > ?
+        ORDER BY block_number
+        LIMIT ?
+        ",
+    )?;
+    let mut insert_sender_stmt = tx.prepare_cached(
+        r"
+        INSERT INTO transaction_senders (sender, block_number, idx)
+        VALUES (?, ?, ?)
+        ",
+    )?;
+
+    let after_param = after.map_or(-1, |after| after as i64);
+    let limit = batch_size.get() as i64;
+    let mut rows = fetch_transactions_stmt
+        .query(params![&after_param, &limit])
+        .context("Querying transactions")?;
+
+    let mut last_block = None;
+    let mut block_count = 0;
+    while let Some(row) = rows.next()? {
+        let block_number = row.get_block_number(0)?;
+        let transactions = row.get_blob(1)?;
+        let transactions = compression::decompress_transactions(transactions)
+            .context("Decompressing transactions")?;
+        let transactions: dto::TransactionsWithReceiptsForBlock =
+            bincode::serde::decode_from_slice(&transactions, bincode::config::standard())
+                .context("Deserializing transactions")?
+                .0;
+
+        for (idx, transaction) in transactions
+            .transactions_with_receipts()
+            .into_iter()
+            .enumerate()
+        {
+            let transaction = StarknetTransaction::from(transaction.transaction);
+            let Some(sender) = transaction.variant.sender_address() else {
+                continue;
+            };
+            let idx: i64 = idx.try_into()?;
+            insert_sender_stmt
+                .execute(params![&sender, &block_number, &idx])
+                .context("Inserting transaction sender")?;
+        }
+
+        last_block = Some(block_number.get());
+        block_count += 1;
+    }
+
+    if block_count < batch_size.get() {
+        tracing::info!("Indexing transaction senders: done");
+        return Ok(None);
+    }
+
+    Ok(last_block)
+}
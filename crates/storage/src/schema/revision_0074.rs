@@ -0,0 +1,35 @@
+use anyhow::Context;
+
+/// Adds the `trie_*_batches` tables, which store all nodes a block adds to a
+/// trie in a single row.
+///
+/// Existing nodes are left in the per-node tables, where they remain readable
+/// until pruned. They are not repacked as the block which added them is not
+/// known.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    for table in ["trie_contracts", "trie_class", "trie_storage"] {
+        tx.execute(
+            &format!(
+                r"
+                CREATE TABLE {table}_batches (
+                    id           INTEGER PRIMARY KEY AUTOINCREMENT,
+                    block_number INTEGER NOT NULL,
+                    node_count   INTEGER NOT NULL,
+                    live_count   INTEGER NOT NULL,
+                    data         BLOB
+                )
+                "
+            ),
+            [],
+        )
+        .with_context(|| format!("Creating {table}_batches table"))?;
+
+        tx.execute(
+            &format!("CREATE INDEX {table}_batches_block_number ON {table}_batches(block_number)"),
+            [],
+        )
+        .with_context(|| format!("Creating {table}_batches block number index"))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use anyhow::Context;
+
+/// Adds the `quarantined_blocks` table, which records blocks received from the
+/// gateway that failed verification.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"
+        CREATE TABLE quarantined_blocks (
+            id             INTEGER PRIMARY KEY,
+            block_number   INTEGER NOT NULL,
+            block_hash     BLOB NOT NULL,
+            check_name     TEXT NOT NULL,
+            reason         TEXT NOT NULL,
+            quarantined_at INTEGER NOT NULL
+        )
+        ",
+        [],
+    )
+    .context("Creating quarantined_blocks table")?;
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use anyhow::Context;
+
+/// Adds the `rejected_transactions` table.
+///
+/// It holds transactions submitted through this node which the gateway
+/// rejected, together with the reason, so that they can be looked up later.
+pub(crate) fn migrate(tx: &rusqlite::Transaction<'_>) -> anyhow::Result<()> {
+    tx.execute(
+        r"
+        CREATE TABLE rejected_transactions (
+            transaction_hash BLOB PRIMARY KEY,
+            transaction_json BLOB NOT NULL,
+            error_code       TEXT NOT NULL,
+            reason           TEXT NOT NULL,
+            rejected_at      INTEGER NOT NULL
+        )
+        ",
+        [],
+    )
+    .context("Creating rejected_transactions table")?;
+
+    Ok(())
+}
@@ -0,0 +1,308 @@
+//! An in-memory cache bounded by its number of entries, their total weight,
+//! or both.
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::num::NonZeroUsize;
+
+/// Which entry to evict once the cache is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// Evict the least recently used entry.
+    #[default]
+    Lru,
+    /// Evict the least frequently used entry, ties are broken by recency.
+    Lfu,
+}
+
+impl EvictionPolicy {
+    /// Entries with the lowest rank are evicted first. Ranks are unique since
+    /// every access advances the clock.
+    fn rank<V>(self, slot: &Slot<V>) -> (u64, u64) {
+        match self {
+            Self::Lru => (slot.last_used, 0),
+            Self::Lfu => (slot.uses, slot.last_used),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct WeightedCache<K, V> {
+    max_entries: Option<NonZeroUsize>,
+    max_weight: Option<NonZeroUsize>,
+    policy: EvictionPolicy,
+    entries: HashMap<K, Slot<V>>,
+    /// Keys ordered by their rank, the first one is evicted first.
+    order: BTreeMap<(u64, u64), K>,
+    total_weight: usize,
+    /// Incremented on every access, used to order entries by recency.
+    clock: u64,
+}
+
+#[derive(Debug)]
+struct Slot<V> {
+    value: V,
+    weight: usize,
+    last_used: u64,
+    uses: u64,
+}
+
+impl<K: Hash + Eq + Clone, V> WeightedCache<K, V> {
+    pub fn new(
+        max_entries: Option<NonZeroUsize>,
+        max_weight: Option<NonZeroUsize>,
+        policy: EvictionPolicy,
+    ) -> Self {
+        Self {
+            max_entries,
+            max_weight,
+            policy,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            total_weight: 0,
+            clock: 0,
+        }
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.clock += 1;
+        let slot = self.entries.get_mut(key)?;
+        self.order.remove(&self.policy.rank(slot));
+        slot.last_used = self.clock;
+        slot.uses += 1;
+        self.order.insert(self.policy.rank(slot), key.clone());
+        Some(&slot.value)
+    }
+
+    /// Inserts or replaces an entry and returns the number of other entries
+    /// evicted to make room for it.
+    ///
+    /// Replacing an entry keeps its usage count, so that an in-flight entry
+    /// being completed is not penalized under [EvictionPolicy::Lfu].
+    pub fn set(&mut self, key: K, value: V, weight: usize) -> usize {
+        self.clock += 1;
+        let uses = match self.take(&key) {
+            Some(previous) => previous.uses,
+            None => 0,
+        };
+
+        let slot = Slot {
+            value,
+            weight,
+            last_used: self.clock,
+            uses,
+        };
+        self.order.insert(self.policy.rank(&slot), key.clone());
+        self.entries.insert(key.clone(), slot);
+        self.total_weight += weight;
+
+        let mut evicted = 0;
+        while self.is_over_capacity() {
+            let Some(victim) = self.victim(&key) else {
+                // Only the new entry is left, it is kept even if it exceeds the
+                // weight limit on its own.
+                break;
+            };
+            self.remove(&victim);
+            evicted += 1;
+        }
+        evicted
+    }
+
+    pub fn remove(&mut self, key: &K) {
+        self.take(key);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.total_weight = 0;
+    }
+
+    pub fn total_weight(&self) -> usize {
+        self.total_weight
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the entries in the reverse order of eviction, i.e. the entry
+    /// which would be evicted last comes first.
+    pub fn entries_by_rank(&self) -> Vec<(&K, &V)> {
+        self.order
+            .values()
+            .rev()
+            .map(|key| (key, &self.entries[key].value))
+            .collect()
+    }
+
+    fn take(&mut self, key: &K) -> Option<Slot<V>> {
+        let slot = self.entries.remove(key)?;
+        self.order.remove(&self.policy.rank(&slot));
+        self.total_weight -= slot.weight;
+        Some(slot)
+    }
+
+    fn is_over_capacity(&self) -> bool {
+        self.max_entries
+            .is_some_and(|max_entries| self.entries.len() > max_entries.get())
+            || self
+                .max_weight
+                .is_some_and(|max_weight| self.total_weight > max_weight.get())
+    }
+
+    fn victim(&self, keep: &K) -> Option<K> {
+        self.order.values().find(|key| *key != keep).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(
+        max_entries: Option<usize>,
+        max_weight: Option<usize>,
+        policy: EvictionPolicy,
+    ) -> WeightedCache<u32, &'static str> {
+        WeightedCache::new(
+            max_entries.and_then(NonZeroUsize::new),
+            max_weight.and_then(NonZeroUsize::new),
+            policy,
+        )
+    }
+
+    #[test]
+    fn lru_evicts_least_recently_used() {
+        let mut cache = cache(Some(2), None, EvictionPolicy::Lru);
+        cache.set(1, "a", 1);
+        cache.set(2, "b", 1);
+        cache.get(&1);
+
+        assert_eq!(cache.set(3, "c", 1), 1);
+        assert!(cache.get(&2).is_none());
+        assert!(cache.get(&1).is_some());
+        assert!(cache.get(&3).is_some());
+    }
+
+    #[test]
+    fn lfu_evicts_least_frequently_used() {
+        let mut cache = cache(Some(2), None, EvictionPolicy::Lfu);
+        cache.set(1, "a", 1);
+        cache.set(2, "b", 1);
+        cache.get(&1);
+        cache.get(&1);
+        cache.get(&2);
+        // 2 is now the most recently used, but 1 was used more often.
+
+        assert_eq!(cache.set(3, "c", 1), 1);
+        assert!(cache.get(&2).is_none());
+        assert!(cache.get(&1).is_some());
+    }
+
+    #[test]
+    fn lfu_keeps_new_entry() {
+        let mut cache = cache(Some(1), None, EvictionPolicy::Lfu);
+        cache.set(1, "a", 1);
+        cache.get(&1);
+
+        // The new entry has the lowest rank, but is not evicted right away.
+        assert_eq!(cache.set(2, "b", 1), 1);
+        assert!(cache.get(&1).is_none());
+        assert!(cache.get(&2).is_some());
+    }
+
+    #[test]
+    fn entries_by_rank() {
+        let mut cache = cache(Some(3), None, EvictionPolicy::Lfu);
+        cache.set(1, "a", 1);
+        cache.set(2, "b", 1);
+        cache.set(3, "c", 1);
+        cache.get(&1);
+        cache.get(&1);
+        cache.get(&3);
+
+        let keys = |cache: &WeightedCache<_, _>| {
+            cache
+                .entries_by_rank()
+                .into_iter()
+                .map(|(key, _)| *key)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&cache), vec![1, 3, 2]);
+
+        let mut cache = self::cache(Some(3), None, EvictionPolicy::Lru);
+        cache.set(1, "a", 1);
+        cache.set(2, "b", 1);
+        cache.set(3, "c", 1);
+        cache.get(&1);
+        assert_eq!(keys(&cache), vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn weight_limit() {
+        let mut cache = cache(Some(10), Some(100), EvictionPolicy::Lru);
+        cache.set(1, "a", 40);
+        cache.set(2, "b", 40);
+        assert_eq!(cache.total_weight(), 80);
+
+        assert_eq!(cache.set(3, "c", 50), 1);
+        assert!(cache.get(&1).is_none());
+        assert_eq!(cache.total_weight(), 90);
+
+        // An entry heavier than the limit evicts everything else but is kept.
+        assert_eq!(cache.set(4, "d", 200), 2);
+        assert!(cache.get(&4).is_some());
+        assert_eq!(cache.total_weight(), 200);
+    }
+
+    #[test]
+    fn weight_limit_only() {
+        let mut cache = cache(None, Some(100), EvictionPolicy::Lru);
+        for key in 0..100 {
+            cache.set(key, "a", 1);
+        }
+        assert_eq!(cache.len(), 100);
+
+        cache.get(&0);
+        assert_eq!(cache.set(100, "b", 10), 10);
+        assert!(cache.get(&0).is_some());
+        assert!(cache.get(&1).is_none());
+        assert!(cache.get(&11).is_some());
+        assert_eq!(cache.total_weight(), 100);
+    }
+
+    #[test]
+    fn replacing_keeps_usage_and_updates_weight() {
+        let mut cache = cache(Some(2), None, EvictionPolicy::Lfu);
+        cache.set(1, "inflight", 0);
+        cache.get(&1);
+        cache.set(1, "done", 30);
+        assert_eq!(cache.total_weight(), 30);
+
+        cache.set(2, "b", 1);
+        cache.set(3, "c", 1);
+        assert_eq!(cache.get(&1), Some(&"done"));
+        assert!(cache.get(&2).is_none());
+    }
+
+    #[test]
+    fn clear() {
+        let mut cache = cache(None, Some(100), EvictionPolicy::Lru);
+        cache.set(1, "a", 40);
+        cache.set(2, "b", 40);
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.total_weight(), 0);
+        assert!(cache.entries_by_rank().is_empty());
+
+        cache.set(3, "c", 40);
+        assert_eq!(cache.total_weight(), 40);
+    }
+}
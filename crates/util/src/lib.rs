@@ -1,6 +1,7 @@
 //! Common Rust utilities used in Pathfinder. This crate does not include any
 //! Starknet specific code.
 
+pub mod cache;
 pub mod error;
 pub mod make_stream;
 pub mod task;
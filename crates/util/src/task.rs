@@ -1,5 +1,8 @@
 use std::future::Future;
-use std::sync::LazyLock;
+use std::num::NonZeroUsize;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, LazyLock, Mutex, OnceLock};
 
 use tokio_util::sync::CancellationToken;
 use tokio_util::task::TaskTracker;
@@ -66,6 +69,60 @@ where
     task_tracker.spawn_blocking(|| f(cancellation_token))
 }
 
+/// Runs the provided closure on the dedicated thread pool for execution work,
+/// i.e. calls, fee estimations, simulations and traces.
+///
+/// Keeping execution apart from [`spawn_blocking_storage`] ensures that long
+/// running traces cannot starve quick storage reads. Otherwise this behaves
+/// like [`spawn_blocking`], including graceful shutdown tracking.
+pub fn spawn_blocking_execution<F, R>(f: F) -> tokio::task::JoinHandle<R>
+where
+    F: FnOnce(CancellationToken) -> R + Send + 'static,
+    R: Send + 'static,
+{
+    POOLS.execution.spawn(f)
+}
+
+/// Runs the provided closure on the dedicated thread pool for storage reads.
+///
+/// See [`spawn_blocking_execution`].
+pub fn spawn_blocking_storage<F, R>(f: F) -> tokio::task::JoinHandle<R>
+where
+    F: FnOnce(CancellationToken) -> R + Send + 'static,
+    R: Send + 'static,
+{
+    POOLS.storage.spawn(f)
+}
+
+/// Number of threads of the pools used by [`spawn_blocking_execution`] and
+/// [`spawn_blocking_storage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolSizes {
+    pub execution: NonZeroUsize,
+    pub storage: NonZeroUsize,
+}
+
+impl Default for PoolSizes {
+    /// One thread per available CPU core for each pool.
+    fn default() -> Self {
+        let threads = std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+        Self {
+            execution: threads,
+            storage: threads,
+        }
+    }
+}
+
+/// Sets the sizes of the dedicated blocking thread pools.
+///
+/// Must be called before the first use of either pool, as their threads are
+/// started on first use. Fails if the sizes have already been configured.
+pub fn configure_pools(sizes: PoolSizes) -> anyhow::Result<()> {
+    POOL_SIZES
+        .set(sizes)
+        .map_err(|_| anyhow::anyhow!("Blocking thread pools are already configured"))
+}
+
 /// Runs the provided closure on an [`std::thread`] by calling
 /// [`std::thread::spawn`].
 ///
@@ -127,3 +184,136 @@ static HANDLE: LazyLock<Handle> = LazyLock::new(|| Handle {
     task_tracker: TaskTracker::new(),
     cancellation_token: CancellationToken::new(),
 });
+
+static POOL_SIZES: OnceLock<PoolSizes> = OnceLock::new();
+
+static POOLS: LazyLock<Pools> = LazyLock::new(|| {
+    let sizes = POOL_SIZES.get().copied().unwrap_or_default();
+    Pools {
+        execution: BlockingPool::new("execution", sizes.execution),
+        storage: BlockingPool::new("storage", sizes.storage),
+    }
+});
+
+struct Pools {
+    execution: BlockingPool,
+    storage: BlockingPool,
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A fixed size pool of threads processing jobs in submission order.
+struct BlockingPool {
+    name: &'static str,
+    sender: mpsc::Sender<Job>,
+    /// Number of jobs submitted but not yet picked up by a thread.
+    queued: Arc<AtomicUsize>,
+}
+
+impl BlockingPool {
+    fn new(name: &'static str, threads: NonZeroUsize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let queued = Arc::new(AtomicUsize::new(0));
+
+        for i in 0..threads.get() {
+            let receiver = receiver.clone();
+            let queued = queued.clone();
+            std::thread::Builder::new()
+                .name(format!("{name}-{i}"))
+                .spawn(move || loop {
+                    // The lock is released before the job is run.
+                    let Ok(job) = receiver.lock().expect("Lock should not be poisoned").recv()
+                    else {
+                        return;
+                    };
+                    let depth = queued.fetch_sub(1, Ordering::Relaxed) - 1;
+                    metrics::gauge!("blocking_pool_queue_depth", depth as f64, "pool" => name);
+                    job();
+                })
+                .expect("Spawning blocking pool thread");
+        }
+
+        Self {
+            name,
+            sender,
+            queued,
+        }
+    }
+
+    fn spawn<F, R>(&self, f: F) -> tokio::task::JoinHandle<R>
+    where
+        F: FnOnce(CancellationToken) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let Handle {
+            task_tracker,
+            cancellation_token,
+        } = HANDLE.clone();
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let job: Job = Box::new(move || {
+            let result = std::panic::catch_unwind(AssertUnwindSafe(|| f(cancellation_token)));
+            // The caller may no longer be interested in the result.
+            let _ = tx.send(result);
+        });
+
+        let depth = self.queued.fetch_add(1, Ordering::Relaxed) + 1;
+        metrics::gauge!("blocking_pool_queue_depth", depth as f64, "pool" => self.name);
+        self.sender
+            .send(job)
+            .expect("Blocking pool threads should never exit");
+
+        // Tracking the result, rather than the job itself, still makes graceful
+        // shutdown wait for the job to complete.
+        task_tracker.spawn(async move {
+            match rx.await.expect("Blocking pool threads should never exit") {
+                Ok(result) => result,
+                // Surface panics through the join handle, as `spawn_blocking` does.
+                Err(panic) => std::panic::resume_unwind(panic),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn execution_does_not_starve_storage() {
+        let sizes = PoolSizes::default();
+        let (release, blocked) = std::sync::mpsc::channel::<()>();
+        let blocked = Arc::new(Mutex::new(blocked));
+
+        // Occupy every execution thread.
+        let execution = (0..sizes.execution.get())
+            .map(|_| {
+                let blocked = blocked.clone();
+                spawn_blocking_execution(move |_| {
+                    let _ = blocked.lock().unwrap().recv();
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let read = spawn_blocking_storage(|_| 42);
+        let read = tokio::time::timeout(std::time::Duration::from_secs(5), read)
+            .await
+            .expect("Storage read should not wait for execution")
+            .unwrap();
+        assert_eq!(read, 42);
+
+        drop(release);
+        for handle in execution {
+            handle.await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn panics_are_propagated() {
+        let error = spawn_blocking_storage(|_| panic!("boom"))
+            .await
+            .unwrap_err();
+        assert!(error.is_panic());
+    }
+}